@@ -0,0 +1,206 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use spider::bytes::Bytes;
+use spider::configuration::{FrontierScorer, PriorityFrontier};
+use spider::hashbrown::HashSet;
+use spider::page::{build, convert_abs_path, get_page_selectors};
+use spider::url::Url;
+use spider::utils::frontier::Frontier;
+use spider::utils::interner::ListBucket;
+use spider::utils::PageResponse;
+use spider::CaseInsensitiveString;
+
+/// A listing page with a few hundred links of every form: absolute, relative, protocol relative and dot segments.
+const LISTING: &str = include_str!("fixtures/listing.html");
+/// A long article with links inside the text and scripts holding markup.
+const ARTICLE: &str = include_str!("fixtures/article.html");
+
+/// The url the fixture pages are served from.
+const BASE: &str = "https://example.com/news/";
+
+/// The href attributes of the fixture page.
+fn hrefs(html: &str) -> Vec<String> {
+    html.split("href=\"")
+        .skip(1)
+        .filter_map(|part| part.split('"').next())
+        .map(String::from)
+        .collect()
+}
+
+/// The absolute links of the fixture page.
+fn links(html: &str) -> Vec<CaseInsensitiveString> {
+    let base = Url::parse(BASE).expect("valid base");
+    hrefs(html)
+        .iter()
+        .map(|href| CaseInsensitiveString::from(convert_abs_path(&base, href).to_string()))
+        .collect()
+}
+
+/// bench extracting the links of the fixture pages
+pub fn bench_link_extraction(c: &mut Criterion) {
+    let rt = spider::tokio::runtime::Runtime::new().unwrap();
+    let selectors = get_page_selectors(BASE, false, false).expect("valid selectors");
+    let mut group = c.benchmark_group("core/link-extraction");
+
+    for (name, html) in [("listing", LISTING), ("article", ARTICLE)] {
+        let page = build(
+            BASE,
+            PageResponse {
+                content: Some(Box::new(Bytes::from_static(html.as_bytes()))),
+                status_code: spider::reqwest::StatusCode::OK,
+                ..Default::default()
+            },
+        );
+
+        group.throughput(Throughput::Bytes(html.len() as u64));
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.to_async(&rt)
+                .iter(|| async { black_box(page.links(&selectors).await) })
+        });
+    }
+
+    group.finish();
+}
+
+/// bench resolving the hrefs of the fixture pages against the page url
+pub fn bench_url_normalization(c: &mut Criterion) {
+    let base = Url::parse(BASE).expect("valid base");
+    let mut group = c.benchmark_group("core/url-normalization");
+
+    for (name, html) in [("listing", LISTING), ("article", ARTICLE)] {
+        let hrefs = hrefs(html);
+
+        group.throughput(Throughput::Elements(hrefs.len() as u64));
+        group.bench_function(BenchmarkId::new("convert_abs_path", name), |b| {
+            b.iter(|| {
+                for href in hrefs.iter() {
+                    black_box(convert_abs_path(&base, href));
+                }
+            })
+        });
+        group.bench_function(BenchmarkId::new("url_join", name), |b| {
+            b.iter(|| {
+                for href in hrefs.iter() {
+                    let _ = black_box(base.join(href));
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// bench the frontier and the visited set operations of the crawl loop
+pub fn bench_frontier(c: &mut Criterion) {
+    let links = links(LISTING);
+    let discovered: HashSet<CaseInsensitiveString> = links.iter().cloned().collect();
+    let mut group = c.benchmark_group("core/frontier");
+
+    group.throughput(Throughput::Elements(links.len() as u64));
+
+    for (name, scorer) in [
+        ("unordered", None),
+        ("breadth_first", Some(FrontierScorer::BreadthFirst)),
+        ("same_path_first", Some(FrontierScorer::SamePathFirst)),
+        ("freshness_first", Some(FrontierScorer::FreshnessFirst)),
+    ] {
+        group.bench_function(BenchmarkId::new("discover_and_drain", name), |b| {
+            b.iter(|| {
+                let frontier = Frontier::new(scorer.map(PriorityFrontier::new), 16);
+                let mut pending = discovered.clone();
+
+                frontier.discovered(BASE, &discovered);
+
+                while !pending.is_empty() {
+                    black_box(frontier.next_batch(&mut pending));
+                }
+            })
+        });
+    }
+
+    for (name, bloom) in [("exact", false), ("bloom", true)] {
+        group.bench_function(BenchmarkId::new("visited_set", name), |b| {
+            b.iter(|| {
+                let mut visited: ListBucket = if bloom {
+                    ListBucket::with_bloom(100_000, 0.001)
+                } else {
+                    ListBucket::new()
+                };
+                let mut pending = HashSet::new();
+
+                for link in links.iter() {
+                    visited.insert(link.clone());
+                }
+                visited.extend_links(&mut pending, discovered.clone());
+
+                black_box(pending.len())
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// bench deciding if the requests of the browser are blocked
+#[cfg(feature = "chrome")]
+pub fn bench_interception(c: &mut Criterion) {
+    use spider::chromiumoxide::handler::network::{MockRoute, MockRouter};
+    use spider::configuration::ConsentMode;
+
+    let urls: Vec<String> = links(LISTING).iter().map(|l| l.to_string()).collect();
+    let policy = ConsentMode::new()
+        .with_allowlist(vec!["example-static.net".into()])
+        .third_party_policy(BASE);
+    let router = MockRouter {
+        routes: vec![
+            MockRoute {
+                pattern: "https://example.com/api/*".into(),
+                status: 200,
+                ..Default::default()
+            },
+            MockRoute {
+                pattern: "*.js".into(),
+                status: 204,
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    let mut group = c.benchmark_group("core/interception");
+
+    group.throughput(Throughput::Elements(urls.len() as u64));
+    group.bench_function("third_party_policy", |b| {
+        b.iter(|| {
+            for url in urls.iter() {
+                black_box(policy.is_blocked(url));
+            }
+        })
+    });
+    group.bench_function("mock_router", |b| {
+        b.iter(|| {
+            for url in urls.iter() {
+                black_box(router.find("GET", url));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+#[cfg(feature = "chrome")]
+criterion_group!(
+    benches,
+    bench_link_extraction,
+    bench_url_normalization,
+    bench_frontier,
+    bench_interception
+);
+
+#[cfg(not(feature = "chrome"))]
+criterion_group!(
+    benches,
+    bench_link_extraction,
+    bench_url_normalization,
+    bench_frontier
+);
+
+criterion_main!(benches);
@@ -2,15 +2,840 @@ use crate::compact_str::CompactString;
 use crate::features::chrome_common::RequestInterceptConfiguration;
 pub use crate::features::chrome_common::{
     AuthChallengeResponse, AuthChallengeResponseResponse, AutomationScripts, AutomationScriptsMap,
-    CaptureScreenshotFormat, CaptureScreenshotParams, ClipViewport, ExecutionScripts,
-    ExecutionScriptsMap, ScreenShotConfig, ScreenshotParams, Viewport, WaitFor, WaitForDelay,
-    WaitForIdleNetwork, WaitForSelector, WebAutomation,
+    BrowserLaunchOptions, BrowserPoolConfig, BrowserStorageLimits, CaptureScreenshotFormat, CaptureScreenshotParams,
+    ChallengeKind, ChallengeOutcome, ChromeConnectionOptions, ClipViewport, ConsentMode, DevicePreset, ExecutionScripts, ExecutionScriptsMap, FulfillCacheConfig,
+    Geolocation, MaxResponseBytes, MockResponse, MockRouter, NetworkRecording, NetworkResourceType, PerformanceMetrics, PerformanceMetricsConfig,
+    RecordedResponse, RecordingMode, RequestWaterfallEntry, ResourceAction, ResourcePolicy, ScreenShotConfig, ScreenshotParams, Viewport, WaitFor,
+    WaitForChallenge, WaitForDelay, WaitForIdleNetwork, WaitForSelector, WebAutomation, WebDriverBiDiConfig,
 };
 pub use crate::features::openai_common::GPTConfigs;
 use crate::website::CronType;
 use reqwest::header::{AsHeaderName, HeaderMap, HeaderName, HeaderValue, IntoHeaderName};
 use std::time::Duration;
 
+/// Override the address a host connects to, like `curl --resolve host:port:addr`. The TLS SNI and `Host` header keep the host of the url.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResolveOverride {
+    /// The host of the url to override.
+    pub host: String,
+    /// The port to connect to.
+    pub port: u16,
+    /// The address to connect to.
+    pub addr: std::net::IpAddr,
+}
+
+impl ResolveOverride {
+    /// Create a new resolve override.
+    pub fn new(host: &str, port: u16, addr: std::net::IpAddr) -> Self {
+        Self {
+            host: host.to_ascii_lowercase(),
+            port,
+            addr,
+        }
+    }
+
+    /// The socket address to connect to.
+    pub fn socket_addr(&self) -> std::net::SocketAddr {
+        std::net::SocketAddr::new(self.addr, self.port)
+    }
+
+    /// Whether the HTTP client of the crawl honours the override. The client maps the host on every port, so the override
+    /// only applies on the port the crawl connects to: the port of the crawl url on its host and the default HTTP or
+    /// HTTPS port on the other hosts.
+    pub fn applies_to(&self, url: Option<&url::Url>) -> bool {
+        match url {
+            Some(url)
+                if url
+                    .host_str()
+                    .is_some_and(|host| host.eq_ignore_ascii_case(&self.host)) =>
+            {
+                url.port_or_known_default() == Some(self.port)
+            }
+            _ => self.port == 80 || self.port == 443,
+        }
+    }
+
+    /// The chrome `--host-resolver-rules` rule mapping the host to the address. Chrome keeps the port of the url.
+    pub fn host_resolver_rule(&self) -> String {
+        match self.addr {
+            std::net::IpAddr::V4(addr) => format!("MAP {} {}", self.host, addr),
+            std::net::IpAddr::V6(addr) => format!("MAP {} [{}]", self.host, addr),
+        }
+    }
+}
+
+impl std::str::FromStr for ResolveOverride {
+    type Err = String;
+
+    /// Parse `host:port:addr`. IPv6 addresses can be wrapped in brackets.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, ':');
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(host), Some(port), Some(addr)) if !host.is_empty() => {
+                let port = port
+                    .parse::<u16>()
+                    .map_err(|_| format!("invalid port in resolve override `{s}`"))?;
+                let addr = addr
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .parse::<std::net::IpAddr>()
+                    .map_err(|_| format!("invalid address in resolve override `{s}`"))?;
+
+                Ok(Self::new(host, port, addr))
+            }
+            _ => Err(format!(
+                "resolve override `{s}` is not in the form host:port:addr"
+            )),
+        }
+    }
+}
+
+/// The encoding of a TLS client certificate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClientCertificateSource {
+    /// The PEM encoded private key followed by the certificate chain.
+    #[cfg_attr(feature = "serde", serde(alias = "pem"))]
+    Pem(Vec<u8>),
+    /// The DER encoded PKCS#12 archive with the password. This requires one of the `reqwest_native_tls` flags.
+    #[cfg_attr(feature = "serde", serde(alias = "pkcs12"))]
+    Pkcs12 {
+        /// The archive.
+        der: Vec<u8>,
+        /// The password of the archive.
+        password: String,
+    },
+}
+
+/// A TLS client certificate for mutual TLS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClientCertificate {
+    /// The certificate and private key.
+    pub source: ClientCertificateSource,
+    /// The hosts to use the certificate for. Use `*.example.com` to match the subdomains. Leave empty to use the certificate for every host.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hosts: Vec<String>,
+}
+
+impl ClientCertificate {
+    /// A certificate from the PEM encoded private key and certificate chain.
+    pub fn pem(pem: &[u8]) -> Self {
+        Self {
+            source: ClientCertificateSource::Pem(pem.to_vec()),
+            hosts: Vec::new(),
+        }
+    }
+
+    /// A certificate from the DER encoded PKCS#12 archive.
+    pub fn pkcs12(der: &[u8], password: &str) -> Self {
+        Self {
+            source: ClientCertificateSource::Pkcs12 {
+                der: der.to_vec(),
+                password: password.into(),
+            },
+            hosts: Vec::new(),
+        }
+    }
+
+    /// Only use the certificate for the hosts.
+    pub fn with_hosts(mut self, hosts: Vec<String>) -> Self {
+        self.hosts = hosts;
+        self
+    }
+
+    /// The certificate can be used for the host. The hosts are compared case insensitively.
+    pub fn matches_host(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+
+        self.hosts.is_empty()
+            || self.hosts.iter().any(|pattern| {
+                let pattern = pattern.to_ascii_lowercase();
+
+                match pattern.strip_prefix("*.") {
+                    Some(domain) => {
+                        host.len() > domain.len() + 1
+                            && host.ends_with(domain)
+                            && host[..host.len() - domain.len()].ends_with('.')
+                    }
+                    _ => pattern == host,
+                }
+            })
+    }
+}
+
+/// The HTTP clients sending the client certificates of the hosts that select another certificate than the host of the
+/// crawl. The clients are built when the crawl starts and skipped when comparing configurations.
+#[derive(Debug, Clone, Default)]
+pub struct CertificateClients(std::sync::Arc<std::sync::Mutex<CertificateClientList>>);
+
+/// The client certificates in order with the client sending each.
+type CertificateClientList = Vec<(ClientCertificate, Option<crate::Client>)>;
+
+impl PartialEq for CertificateClients {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl CertificateClients {
+    /// Set the certificates in order with the client of each, `None` for the certificate of the client of the crawl.
+    pub(crate) fn set(&self, clients: CertificateClientList) {
+        if let Ok(mut current) = self.0.lock() {
+            *current = clients;
+        }
+    }
+
+    /// The client sending the first certificate matching the host of the url, `None` when the client of the crawl sends
+    /// it.
+    pub(crate) fn client(&self, url: &str) -> Option<crate::Client> {
+        let url = url::Url::parse(url).ok()?;
+        let host = url.host_str()?;
+        let clients = self.0.lock().ok()?;
+
+        clients
+            .iter()
+            .find(|(certificate, _)| certificate.matches_host(host))
+            .and_then(|(_, client)| client.clone())
+    }
+}
+
+/// The callback to transform the page body with the page url and body. Callbacks are skipped when comparing configurations.
+#[derive(Debug, Clone, Copy)]
+pub struct BodyTransformCallback(pub fn(&str, bytes::Bytes) -> bytes::Bytes);
+
+impl PartialEq for BodyTransformCallback {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// A proxy used by the rotation with optional credentials for the proxy auth challenge.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestProxy {
+    /// The proxy server ex: `http://proxy.example.com:8080` or `socks5://127.0.0.1:1080`.
+    pub addr: String,
+    /// The username sent when the proxy asks for authentication.
+    pub username: Option<String>,
+    /// The password sent when the proxy asks for authentication.
+    pub password: Option<String>,
+}
+
+impl RequestProxy {
+    /// A new proxy without credentials.
+    pub fn new(addr: &str) -> Self {
+        Self {
+            addr: addr.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the credentials to answer the proxy auth challenge.
+    pub fn with_credentials(mut self, username: &str, password: &str) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+}
+
+/// Rotate the proxies used by new pages. Each browser page, or each `pages_per_proxy` pages, uses the next proxy in the pool.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProxyRotation {
+    /// The pool of proxies.
+    pub proxies: Vec<RequestProxy>,
+    /// The amount of pages to open before moving to the next proxy. Values below 1 rotate every page.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pages_per_proxy: usize,
+    /// The pages opened and the browser contexts created for each proxy.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) state: std::sync::Arc<ProxyRotationState>,
+}
+
+impl PartialEq for ProxyRotation {
+    fn eq(&self, other: &Self) -> bool {
+        self.proxies == other.proxies && self.pages_per_proxy == other.pages_per_proxy
+    }
+}
+
+impl ProxyRotation {
+    /// Rotate the proxies on every new page.
+    pub fn new(proxies: Vec<RequestProxy>) -> Self {
+        Self {
+            proxies,
+            ..Default::default()
+        }
+    }
+
+    /// Move to the next proxy after the amount of pages.
+    pub fn with_pages_per_proxy(mut self, pages_per_proxy: usize) -> Self {
+        self.pages_per_proxy = pages_per_proxy;
+        self
+    }
+
+    /// The position of the proxy to use for the next page.
+    #[cfg(feature = "chrome")]
+    pub(crate) fn next_index(&self) -> Option<usize> {
+        if self.proxies.is_empty() {
+            None
+        } else {
+            let page = self
+                .state
+                .pages
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Some((page / self.pages_per_proxy.max(1)) % self.proxies.len())
+        }
+    }
+}
+
+/// The runtime state shared by the clones of a proxy rotation.
+#[cfg(feature = "chrome")]
+#[derive(Debug, Default)]
+pub(crate) struct ProxyRotationState {
+    /// The pages opened.
+    pub(crate) pages: std::sync::atomic::AtomicUsize,
+    /// The browser context of each proxy by position.
+    pub(crate) contexts: std::sync::Mutex<
+        hashbrown::HashMap<usize, chromiumoxide::cdp::browser_protocol::browser::BrowserContextId>,
+    >,
+}
+
+/// A file downloaded by the browser while crawling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DownloadedFile {
+    /// The url of the page that started the download.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub page_url: String,
+    /// The url of the resource downloaded.
+    pub url: String,
+    /// The file name suggested by the server.
+    pub suggested_filename: String,
+    /// The path of the file on disk. Files are named by the download id of the browser.
+    pub path: std::path::PathBuf,
+    /// The size of the file in bytes.
+    pub size: u64,
+}
+
+/// Save the files downloaded by links in the browser to a directory instead of cancelling them.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DownloadCapture {
+    /// The directory to save the files to. The directory is created if missing.
+    pub dir: std::path::PathBuf,
+    /// Stop waiting for the downloads of a page once no download event was received for the time, 30 seconds by
+    /// default.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub idle_timeout: Option<Duration>,
+    /// The files downloaded since the crawl started.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) files: std::sync::Arc<std::sync::Mutex<Vec<DownloadedFile>>>,
+}
+
+impl PartialEq for DownloadCapture {
+    fn eq(&self, other: &Self) -> bool {
+        self.dir == other.dir && self.idle_timeout == other.idle_timeout
+    }
+}
+
+/// The time without download events before the downloads of a page stop being waited for.
+const DOWNLOAD_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+impl DownloadCapture {
+    /// Save the downloads to the directory.
+    pub fn new(dir: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Stop waiting for the downloads of a page after the time without download events.
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    /// The time without download events before the downloads of a page stop being waited for.
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout.unwrap_or(DOWNLOAD_IDLE_TIMEOUT)
+    }
+
+    /// The files downloaded since the crawl started.
+    pub fn files(&self) -> Vec<DownloadedFile> {
+        match self.files.lock() {
+            Ok(files) => files.clone(),
+            _ => Default::default(),
+        }
+    }
+
+    /// The files downloaded by the page.
+    pub fn files_of(&self, page_url: &str) -> Vec<DownloadedFile> {
+        match self.files.lock() {
+            Ok(files) => files
+                .iter()
+                .filter(|file| file.page_url == page_url)
+                .cloned()
+                .collect(),
+            _ => Default::default(),
+        }
+    }
+
+    /// Record a completed download.
+    #[cfg(feature = "chrome")]
+    pub(crate) fn push(&self, file: DownloadedFile) {
+        if let Ok(mut files) = self.files.lock() {
+            files.push(file);
+        }
+    }
+
+    /// Clear the downloads for a new crawl.
+    #[cfg(feature = "chrome")]
+    pub(crate) fn reset(&self) {
+        if let Ok(mut files) = self.files.lock() {
+            files.clear();
+        }
+    }
+}
+
+/// The failures a retry policy crawls the url again for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RetryOn {
+    /// The navigation or request timed out.
+    Timeout,
+    /// The document failed to load from a network error.
+    NetworkError,
+    /// The server answered with a 5xx status.
+    ServerError,
+    /// The server answered with 429 Too Many Requests.
+    TooManyRequests,
+}
+
+/// Re-queue the urls that failed in the crawl frontier with a jittered exponential backoff.
+/// Unlike [`Configuration::retry`] the url goes back to the frontier so the permit is released while waiting.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RetryPolicy {
+    /// The maximum amount of attempts for a url including the first one.
+    pub max_attempts: u32,
+    /// The delay before the first retry, doubled on each attempt.
+    pub backoff: Duration,
+    /// The maximum delay between attempts.
+    pub max_backoff: Duration,
+    /// The failures to retry.
+    pub retry_on: Vec<RetryOn>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            retry_on: vec![RetryOn::Timeout, RetryOn::NetworkError],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Retry timeouts and network errors up to the amount of attempts.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Default::default()
+        }
+    }
+
+    /// Set the initial and maximum delay between attempts.
+    pub fn with_backoff(mut self, backoff: Duration, max_backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Set the failures to retry.
+    pub fn with_retry_on(mut self, retry_on: Vec<RetryOn>) -> Self {
+        self.retry_on = retry_on;
+        self
+    }
+
+    /// The failure of the response status if the policy retries it. Timeouts are reported as 408 or 504 and network errors as 599.
+    pub fn retries(&self, status_code: reqwest::StatusCode) -> bool {
+        let failure = match status_code.as_u16() {
+            408 | 504 => RetryOn::Timeout,
+            599 => RetryOn::NetworkError,
+            429 => RetryOn::TooManyRequests,
+            500..=598 => RetryOn::ServerError,
+            _ => return false,
+        };
+
+        self.retry_on.contains(&failure)
+    }
+
+    /// The jittered delay before the attempt after the first one.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        use backoff::backoff::Backoff;
+
+        let mut backoff = backoff::ExponentialBackoffBuilder::new()
+            .with_initial_interval(self.backoff)
+            .with_multiplier(2.0)
+            .with_randomization_factor(0.5)
+            .with_max_interval(self.max_backoff)
+            .with_max_elapsed_time(None)
+            .build();
+
+        let mut delay = self.backoff;
+
+        for _ in 0..attempt.max(1) {
+            if let Some(next) = backoff.next_backoff() {
+                delay = next;
+            }
+        }
+
+        delay
+    }
+}
+
+/// Adapt the request rate of each host to its responses with a token bucket. The rate slows down on 429 and 503
+/// responses, pauses the host for the `Retry-After` delay and speeds back up after a run of successful responses.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdaptiveRateLimit {
+    /// The requests per second a host starts with.
+    pub initial_rate: f64,
+    /// The lowest requests per second a host slows down to.
+    pub min_rate: f64,
+    /// The highest requests per second a host speeds up to.
+    pub max_rate: f64,
+    /// The requests a host can receive at once after being idle.
+    pub burst: f64,
+    /// The factor applied to the rate on a 429 or 503 response.
+    pub slowdown: f64,
+    /// The factor applied to the rate after the successful responses.
+    pub speedup: f64,
+    /// The successful responses in a row before speeding up.
+    pub speedup_after: u32,
+    /// The longest `Retry-After` delay respected.
+    pub max_retry_after: Duration,
+}
+
+impl Default for AdaptiveRateLimit {
+    fn default() -> Self {
+        Self {
+            initial_rate: 2.0,
+            min_rate: 0.1,
+            max_rate: 10.0,
+            burst: 1.0,
+            slowdown: 0.5,
+            speedup: 1.25,
+            speedup_after: 10,
+            max_retry_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl AdaptiveRateLimit {
+    /// Start each host at the requests per second.
+    pub fn new(initial_rate: f64) -> Self {
+        Self {
+            initial_rate,
+            ..Default::default()
+        }
+    }
+
+    /// Set the lowest and highest requests per second of a host.
+    pub fn with_bounds(mut self, min_rate: f64, max_rate: f64) -> Self {
+        self.min_rate = min_rate;
+        self.max_rate = max_rate;
+        self
+    }
+
+    /// Set the requests a host can receive at once.
+    pub fn with_burst(mut self, burst: f64) -> Self {
+        self.burst = burst;
+        self
+    }
+}
+
+/// The settings of the crawls after the first one, ex: render everything on the first crawl and recrawl over HTTP with
+/// conditional requests. The settings left unset keep the value of the first crawl.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecrawlConfig {
+    /// Fetch the pages over HTTP without the browser.
+    pub http_only: bool,
+    /// Use the HTTP cache so the pages are revalidated with conditional requests.
+    pub cache: Option<bool>,
+    /// The max depth of the recrawls.
+    pub depth: Option<usize>,
+    /// The delay between the requests of the recrawls in milliseconds.
+    pub delay: Option<u64>,
+    /// The file recording the completed crawls, so the recrawl settings still apply after a restart.
+    pub state_file: Option<std::path::PathBuf>,
+    /// The crawls completed by this process.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) completed: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl PartialEq for RecrawlConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.http_only == other.http_only
+            && self.cache == other.cache
+            && self.depth == other.depth
+            && self.delay == other.delay
+            && self.state_file == other.state_file
+    }
+}
+
+impl Default for RecrawlConfig {
+    fn default() -> Self {
+        Self {
+            http_only: true,
+            cache: Some(true),
+            depth: None,
+            delay: None,
+            state_file: None,
+            completed: Default::default(),
+        }
+    }
+}
+
+impl RecrawlConfig {
+    /// Recrawl over HTTP with the cache revalidating the pages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetch the pages of the recrawls over HTTP without the browser.
+    pub fn with_http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+
+    /// Use the HTTP cache for the recrawls. Use `None` to keep the setting of the first crawl.
+    pub fn with_cache(mut self, cache: Option<bool>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Set the max depth of the recrawls.
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// Set the delay between the requests of the recrawls in milliseconds.
+    pub fn with_delay(mut self, delay: u64) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Record the completed crawls in the file.
+    pub fn with_state_file(mut self, state_file: impl Into<std::path::PathBuf>) -> Self {
+        self.state_file = Some(state_file.into());
+        self
+    }
+
+    /// The crawls completed, the most of this process and the state file.
+    pub fn completed_crawls(&self) -> usize {
+        let saved = self
+            .state_file
+            .as_ref()
+            .and_then(|state_file| std::fs::read_to_string(state_file).ok())
+            .and_then(|state| state.trim().parse().ok())
+            .unwrap_or_default();
+
+        self.completed
+            .load(std::sync::atomic::Ordering::Relaxed)
+            .max(saved)
+    }
+
+    /// Record a completed crawl.
+    pub(crate) fn complete_crawl(&self) {
+        let completed = self.completed_crawls() + 1;
+        self.completed
+            .store(completed, std::sync::atomic::Ordering::Relaxed);
+
+        if let Some(state_file) = &self.state_file {
+            if let Some(parent) = state_file.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            if let Err(e) = std::fs::write(state_file, completed.to_string()) {
+                log::warn!("failed to save the recrawl state: {e}");
+            }
+        }
+    }
+}
+
+/// Score a discovered url from its link depth and the url of the page linking to it. The urls with the highest score are crawled first.
+pub type FrontierScoreFn =
+    std::sync::Arc<dyn Fn(&url::Url, usize, Option<&url::Url>) -> f64 + Send + Sync>;
+
+/// The order the frontier crawls the discovered urls in.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrontierScorer {
+    /// The shallowest links first.
+    #[default]
+    BreadthFirst,
+    /// The deepest links first.
+    DepthFirst,
+    /// The links sharing the most path segments with the page linking to them first.
+    SamePathFirst,
+    /// The links with the most recent date in the path like `/2025/01/06/` first, then the shallowest.
+    FreshnessFirst,
+    /// A custom score.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(FrontierScoreFn),
+}
+
+impl PartialEq for FrontierScorer {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Custom(a), Self::Custom(b)) => std::sync::Arc::ptr_eq(a, b),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl std::fmt::Debug for FrontierScorer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BreadthFirst => f.write_str("BreadthFirst"),
+            Self::DepthFirst => f.write_str("DepthFirst"),
+            Self::SamePathFirst => f.write_str("SamePathFirst"),
+            Self::FreshnessFirst => f.write_str("FreshnessFirst"),
+            Self::Custom(_) => f.debug_tuple("Custom").finish(),
+        }
+    }
+}
+
+impl FrontierScorer {
+    /// The score of the url.
+    pub fn score(&self, url: &url::Url, depth: usize, referrer: Option<&url::Url>) -> f64 {
+        use crate::utils::frontier;
+
+        match self {
+            Self::BreadthFirst => frontier::breadth_first(url, depth, referrer),
+            Self::DepthFirst => frontier::depth_first(url, depth, referrer),
+            Self::SamePathFirst => frontier::same_path_first(url, depth, referrer),
+            Self::FreshnessFirst => frontier::freshness_first(url, depth, referrer),
+            Self::Custom(score) => score(url, depth, referrer),
+        }
+    }
+}
+
+/// Crawl the discovered urls in batches ordered by a score instead of the discovery order, so the important pages
+/// are reached before a budget or limit stops the crawl.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PriorityFrontier {
+    /// The score ordering the urls.
+    pub scorer: FrontierScorer,
+    /// The urls taken from the frontier at once. Defaults to the concurrency limit.
+    pub batch_size: Option<usize>,
+}
+
+impl PriorityFrontier {
+    /// Order the frontier with the scorer.
+    pub fn new(scorer: FrontierScorer) -> Self {
+        Self {
+            scorer,
+            batch_size: None,
+        }
+    }
+
+    /// Score the urls with a custom function.
+    pub fn with_score<F>(score: F) -> Self
+    where
+        F: Fn(&url::Url, usize, Option<&url::Url>) -> f64 + Send + Sync + 'static,
+    {
+        Self::new(FrontierScorer::Custom(std::sync::Arc::new(score)))
+    }
+
+    /// Set the urls taken from the frontier at once. Smaller batches follow the order closer while larger batches keep more requests in flight.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = Some(batch_size.max(1));
+        self
+    }
+}
+
+/// Cap the pages crawled from a link depth, ex: at most 100 pages at depth 3 or more. The frontier takes the
+/// shallowest urls first when a budget is set so the pages near the start url are crawled before the budget is spent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepthBudget {
+    /// The max pages crawled keyed by the min depth they apply from. The start page is at depth 0.
+    pub limits: std::collections::BTreeMap<usize, usize>,
+}
+
+impl DepthBudget {
+    /// No limit until one is added.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Crawl at most the pages at the depth or deeper.
+    pub fn with_limit(mut self, min_depth: usize, max_pages: usize) -> Self {
+        self.limits.insert(min_depth, max_pages);
+        self
+    }
+}
+
+/// How sitemaps are followed and merged with the crawl.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SitemapConfig {
+    /// The deepest nesting of `<sitemapindex>` entries followed.
+    pub max_depth: usize,
+    /// Fetch the urls with the highest priority and freshest `lastmod` first.
+    pub prioritize_lastmod: bool,
+    /// The age under which a `lastmod` counts as fresh.
+    pub fresh_within: Duration,
+    /// The boost added to the sitemap priority of fresh urls.
+    pub priority_boost: f32,
+    /// Add the sitemap urls to the crawl frontier before the crawl starts instead of fetching them once it ends.
+    pub merge_into_frontier: bool,
+}
+
+impl Default for SitemapConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 8,
+            prioritize_lastmod: true,
+            fresh_within: Duration::from_secs(7 * 24 * 60 * 60),
+            priority_boost: 0.5,
+            merge_into_frontier: false,
+        }
+    }
+}
+
+impl SitemapConfig {
+    /// Follow the nested sitemap indexes up to the depth.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Boost the urls modified within the window.
+    pub fn with_priority_boost(mut self, priority_boost: f32, fresh_within: Duration) -> Self {
+        self.priority_boost = priority_boost;
+        self.fresh_within = fresh_within;
+        self
+    }
+
+    /// Add the sitemap urls to the crawl frontier.
+    pub fn with_merge_into_frontier(mut self, merge_into_frontier: bool) -> Self {
+        self.merge_into_frontier = merge_into_frontier;
+        self
+    }
+}
+
 /// Redirect policy configuration for request
 #[derive(Debug, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -28,6 +853,156 @@ pub enum RedirectPolicy {
     )]
     /// A strict policy only allowing request that match the domain set for crawling.
     Strict,
+    #[cfg_attr(
+        feature = "serde",
+        serde(alias = "SameHost", alias = "same_host", alias = "SAME_HOST",)
+    )]
+    /// Only follow the redirects staying on the host of the first request.
+    SameHost,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            alias = "SameRegistrableDomain",
+            alias = "same_registrable_domain",
+            alias = "SAME_REGISTRABLE_DOMAIN",
+        )
+    )]
+    /// Only follow the redirects staying on the registrable domain of the first request ex: `www.example.com` to `example.com`.
+    SameRegistrableDomain,
+    #[cfg_attr(
+        feature = "serde",
+        serde(alias = "None", alias = "none", alias = "NONE",)
+    )]
+    /// Never follow a redirect, the redirect response is returned.
+    None,
+    #[cfg_attr(
+        feature = "serde",
+        serde(alias = "MaxHops", alias = "max_hops", alias = "MAX_HOPS",)
+    )]
+    /// Follow up to the count of redirects ignoring the redirect limit, the last redirect response is returned.
+    MaxHops(usize),
+}
+
+impl RedirectPolicy {
+    /// Whether the redirect to the target url is followed. The origin is the url of the first request and the hops
+    /// count the redirects including this one. The loose and strict policies are checked by the HTTP client. The Chrome
+    /// pages follow the same decisions, a blocked redirect fails the navigation.
+    pub fn allows(&self, origin: &str, target: &str, hops: usize) -> bool {
+        let host = |url: &str| {
+            url::Url::parse(url)
+                .ok()
+                .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+        };
+
+        match self {
+            Self::Loose | Self::Strict => true,
+            Self::SameHost => matches!((host(origin), host(target)), (Some(o), Some(t)) if o == t),
+            Self::SameRegistrableDomain => matches!(
+                (host(origin), host(target)),
+                (Some(o), Some(t)) if crate::utils::registrable_domain(&o) == crate::utils::registrable_domain(&t)
+            ),
+            Self::None => false,
+            Self::MaxHops(max) => hops <= *max,
+        }
+    }
+}
+
+/// The order to send the pages to the subscribers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputOrder {
+    #[default]
+    #[cfg_attr(
+        feature = "serde",
+        serde(alias = "Completion", alias = "completion", alias = "COMPLETION",)
+    )]
+    /// Send the pages as soon as the request completes.
+    Completion,
+    #[cfg_attr(feature = "serde", serde(alias = "Url", alias = "url", alias = "URL",))]
+    /// Buffer the pages and send them sorted by url after the crawl.
+    Url,
+    #[cfg_attr(
+        feature = "serde",
+        serde(alias = "Discovery", alias = "discovery", alias = "DISCOVERY",)
+    )]
+    /// Buffer the pages and send them in the order the links were found after the crawl. The bloom filter
+    /// [`VisitedSet`] does not keep the links and the pages are sent sorted by url instead.
+    Discovery,
+}
+
+/// The store of the links visited during a crawl.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VisitedSet {
+    #[default]
+    /// Keep every link visited. The links visited can be listed after the crawl.
+    Exact,
+    /// Keep the links visited in a bloom filter sized for the expected links. The memory stays fixed for crawls of tens of
+    /// millions of links at the cost of skipping the links colliding with the links visited at the false positive rate.
+    /// The links visited can not be listed after the crawl.
+    Bloom {
+        /// The links expected for the crawl.
+        expected_links: usize,
+        /// The rate of links skipped as visited when the expected links are reached, ex: `0.001`.
+        false_positive_rate: f64,
+    },
+}
+
+/// The strategy to pick the user agent for each new page when using chrome.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UserAgentStrategy {
+    #[default]
+    #[cfg_attr(
+        feature = "serde",
+        serde(alias = "Fixed", alias = "fixed", alias = "FIXED",)
+    )]
+    /// Use the same user agent for all pages.
+    Fixed,
+    #[cfg_attr(
+        feature = "serde",
+        serde(alias = "Random", alias = "random", alias = "RANDOM",)
+    )]
+    /// Pick a random user agent from the list for each page.
+    Random(Vec<CompactString>),
+    #[cfg_attr(
+        feature = "serde",
+        serde(alias = "PerDomain", alias = "per_domain", alias = "PER_DOMAIN",)
+    )]
+    /// Pick a user agent from the list for each domain. Pages on the same domain always use the same agent.
+    PerDomain(Vec<CompactString>),
+}
+
+impl UserAgentStrategy {
+    /// Get the user agent to use for the url. Returns `None` when the configured user agent should be used.
+    #[cfg(feature = "chrome")]
+    pub fn get_user_agent(&self, url: &str) -> Option<&str> {
+        match self {
+            UserAgentStrategy::Fixed => None,
+            UserAgentStrategy::Random(agents) => {
+                if agents.is_empty() {
+                    None
+                } else {
+                    Some(agents[fastrand::usize(..agents.len())].as_str())
+                }
+            }
+            UserAgentStrategy::PerDomain(agents) => {
+                use std::hash::{Hash, Hasher};
+
+                if agents.is_empty() {
+                    None
+                } else {
+                    let host = match url::Url::parse(url) {
+                        Ok(u) => u.host_str().unwrap_or_default().to_ascii_lowercase(),
+                        _ => Default::default(),
+                    };
+                    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                    host.hash(&mut hasher);
+                    Some(agents[hasher.finish() as usize % agents.len()].as_str())
+                }
+            }
+        }
+    }
 }
 
 #[cfg(not(feature = "regex"))]
@@ -65,9 +1040,21 @@ pub struct AllowListSet(pub AllowList);
 pub struct Configuration {
     /// Respect robots.txt file and not scrape not allowed files. This may slow down crawls if robots.txt file has a delay included.
     pub respect_robots_txt: bool,
-    /// Allow sub-domains.
+    /// Cache the robots.txt of each host for the duration and revalidate it with its etag once expired. The robots.txt of each host is only fetched once by the website when unset.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub robots_txt_ttl: Option<Duration>,
+    /// Apply the robots.txt rules to the requests made by rendered pages like iframes and XHRs.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub respect_robots_txt_subresources: bool,
+    /// The robots.txt rules of the crawl shared with the browser pages.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) robots_txt_rules:
+        Option<std::sync::Arc<crate::packages::robotparser::parser::RobotFileParser>>,
+    /// Allow the subdomains of the registrable domain ex: `blog.example.co.uk` when crawling `www.example.co.uk`. The registrable domain comes from the public suffix list with the `public_suffix` flag.
     pub subdomains: bool,
-    /// Allow all tlds for domain.
+    /// Allow the registrable domain under every public suffix ex: `example.de` and `shop.example.co.uk` when crawling `example.com`.
     pub tld: bool,
     /// Preserve the HTTP host header from being included.
     pub preserve_host_header: bool,
@@ -85,6 +1072,103 @@ pub struct Configuration {
     pub http2_prior_knowledge: bool,
     /// Use proxy list for performing network request.
     pub proxies: Option<Box<Vec<String>>>,
+    /// The callback to transform or redact the page body before it is sent to the subscribers and stored.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub on_body_transform_callback: Option<BodyTransformCallback>,
+    /// Detect and redact personal data in the page content before it is sent to the subscribers and stored.
+    pub pii_redaction: Option<Box<crate::features::pii::PiiRedaction>>,
+    /// Discover the RSS and Atom feeds linked by the pages and collect their entries.
+    pub feed_discovery: Option<Box<crate::features::feed::FeedDiscovery>>,
+    /// Discover the navigation targets set by javascript like `onclick` handlers, `data-href` attributes and route manifests.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub js_link_discovery: Option<Box<crate::features::js_links::JsLinkDiscovery>>,
+    /// Follow the next page links and page number parameters of the paginated listings.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pagination: Option<Box<crate::features::pagination::Pagination>>,
+    /// Detect the language of the pages and skip the pages in other languages.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub language_filter: Option<Box<crate::features::language::LanguageFilter>>,
+    /// Fetch the hosts advertising HTTP/3 over QUIC in the HTTP crawl.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub http3: Option<Box<crate::features::http3::Http3>>,
+    /// Extract the fields of a scraping schema from the pages into [`crate::page::Page::extracted`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extractor: Option<Box<crate::features::extractor::Extractor>>,
+    /// Extract the title, byline, publish date and main text of the pages into [`crate::page::Page::article`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub text_extraction: Option<Box<crate::features::text_extraction::TextExtraction>>,
+    /// Split the markdown of the pages into token bounded chunks into [`crate::page::Page::chunks`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub chunking: Option<Box<crate::features::chunking::Chunker>>,
+    /// Extract structured JSON from the pages with a language model into [`crate::page::Page::llm_extracted`].
+    #[cfg(feature = "llm_extraction")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub llm_extraction: Option<Box<crate::features::llm_extraction::LlmExtraction>>,
+    /// Check the outbound links of the pages and report the broken ones with the pages linking to them.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub link_check: Option<Box<crate::features::link_check::LinkCheck>>,
+    /// Tag the requests matching patterns and dump samples of each tag for debugging.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub request_sampling: Option<Box<crate::features::request_sampling::RequestSampling>>,
+    /// Write the crawl state to a file periodically to resume the crawl after a restart.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub checkpoint: Option<Box<crate::features::checkpoint::Checkpoint>>,
+    /// Skip the pages with nearly the same content as a page crawled before.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub near_duplicates: Option<Box<crate::features::near_duplicate::NearDuplicates>>,
+    /// Normalize the links found before they are deduplicated and added to the frontier.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub url_normalization: Option<Box<crate::features::url_normalization::UrlNormalization>>,
+    /// Send the validators of the last crawl as conditional requests and skip the body of the pages not modified.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub revalidation: Option<Box<crate::features::revalidation::Revalidation>>,
+    /// Inject artificial failures at configurable rates to test the crawl configuration.
+    #[cfg(feature = "chaos")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub chaos: Option<Box<crate::features::chaos::Chaos>>,
+    /// Send the crawl lifecycle events to the subscribers.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub crawl_events: Option<Box<crate::features::events::CrawlEvents>>,
+    /// Record the Prometheus metrics of the crawl.
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metrics: Option<Box<crate::features::metrics::CrawlMetrics>>,
+    /// Operate the running crawl over HTTP: the status, the seeds, pause and resume and the request rate.
+    #[cfg(feature = "control_server")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub control_server: Option<Box<crate::features::control_server::ControlServer>>,
+    /// Authenticate to a corporate proxy with NTLM or Negotiate through a local relay.
+    #[cfg(feature = "proxy_auth")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub proxy_auth: Option<Box<crate::features::proxy_auth::ProxyAuth>>,
+    /// Share the frontier, the visited links and the host rate limits with other workers in Redis.
+    #[cfg(feature = "distributed")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub distributed: Option<Box<crate::features::distributed::Distributed>>,
+    /// The hooks running before and after the discover, fetch, extract and finalize phases of the crawl.
+    #[cfg(feature = "phases")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub phase_hooks: Option<crate::features::phases::PhaseHooks>,
+    /// Stream the crawled pages to a sink as they complete.
+    #[cfg(feature = "sink")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub sink: Option<crate::features::sink::PageSink>,
+    /// The async predicate deciding if a discovered url is crawled, its priority and its fetcher.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub on_should_crawl: Option<crate::features::should_crawl::OnShouldCrawl>,
+    /// Route the urls to the HTTP or Chrome fetcher and fetch the pages needing javascript again with Chrome.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fetch_routing: Option<Box<crate::features::fetch_routing::FetchRouting>>,
+    /// The delay, concurrency, User-Agent, proxy and fetcher of the urls of some registrable domains.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub politeness: Option<Box<crate::features::politeness::PolitenessProfiles>>,
+    /// The TLS client certificates for mutual TLS. The first certificate matching the host of each request is used.
+    pub client_certificates: Option<Box<Vec<ClientCertificate>>>,
+    /// The clients of the hosts selecting another client certificate than the host of the crawl.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) certificate_clients: CertificateClients,
+    /// Override the addresses hosts connect to. The TLS SNI and `Host` header keep the host of the url.
+    pub resolve: Option<Box<Vec<ResolveOverride>>>,
     /// Headers to include with request.
     pub headers: Option<Box<SerializableHeaderMap>>,
     #[cfg(feature = "sitemap")]
@@ -93,6 +1177,10 @@ pub struct Configuration {
     #[cfg(feature = "sitemap")]
     /// Prevent including the sitemap links with the crawl.
     pub ignore_sitemap: bool,
+    #[cfg(feature = "sitemap")]
+    /// Follow nested sitemaps, order the urls by `lastmod` and merge them into the frontier.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sitemap_config: SitemapConfig,
     /// The max redirections allowed for request.
     pub redirect_limit: Box<usize>,
     /// The redirect policy type to use.
@@ -116,6 +1204,13 @@ pub struct Configuration {
     #[cfg(feature = "chrome")]
     /// Use stealth mode for requests.
     pub stealth_mode: bool,
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    /// Bypass the Content Security Policy of the pages and allow the insecure content of https pages.
+    pub bypass_csp: bool,
+    #[cfg(feature = "chrome")]
+    /// The strategy to pick the user agent for each new page.
+    pub user_agent_strategy: UserAgentStrategy,
     /// Configure the viewport for chrome. This does nothing without the flag `chrome` enabled.
     #[cfg(feature = "chrome")]
     pub viewport: Option<Viewport>,
@@ -124,7 +1219,35 @@ pub struct Configuration {
     pub timezone_id: Option<Box<String>>,
     /// Overrides default host system locale with the specified one. This does nothing without the flag `chrome` enabled.
     #[cfg(feature = "chrome")]
-    pub locale: Option<Box<String>>,
+    pub locale: Option<Box<String>>,
+    /// Overrides the geolocation position of the browser.
+    #[cfg(feature = "chrome")]
+    pub geolocation: Option<Box<Geolocation>>,
+    /// Slow down the CPU of the pages by the factor, `4.0` runs the pages 4x slower.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cpu_throttling_rate: Option<f64>,
+    /// Block the third party requests and cookies of the browser.
+    #[cfg(feature = "chrome")]
+    pub consent_mode: Option<Box<ConsentMode>>,
+    /// Answer the browser requests matching the routes with canned responses.
+    #[cfg(feature = "chrome")]
+    pub mock_router: Option<Box<MockRouter>>,
+    /// Record the responses of the browser to a directory or replay them offline.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub network_recording: Option<Box<NetworkRecording>>,
+    /// Fulfill the repeated subresource requests of the pages from a shared cache.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fulfill_cache: Option<Box<FulfillCacheConfig>>,
+    /// Abort the browser responses over the bytes allowed for their resource type.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_response_bytes: Option<Box<MaxResponseBytes>>,
+    /// Save the files downloaded by the browser to a directory.
+    #[cfg(feature = "chrome")]
+    pub download_capture: Option<Box<DownloadCapture>>,
     /// Set a custom script to eval on each new document. This does nothing without the flag `chrome` enabled.
     #[cfg(feature = "chrome")]
     pub evaluate_on_new_document: Option<Box<String>>,
@@ -150,6 +1273,45 @@ pub struct Configuration {
     pub accept_invalid_certs: bool,
     /// The auth challenge response. The 'chrome_intercept' flag is also required in order to intercept the response.
     pub auth_challenge_response: Option<AuthChallengeResponse>,
+    /// Rotate the proxies of new browser pages.
+    #[cfg(feature = "chrome")]
+    pub proxy_rotation: Option<Box<ProxyRotation>>,
+    /// The amount of browsers to spread the hosts of the crawl across.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub browser_shards: usize,
+    /// Spread the pages across a pool of browsers recycled on memory leaks or when they stop answering.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub browser_pool: Option<Box<BrowserPoolConfig>>,
+    /// Kill browsers left running by a previous crashed run when the first browser launches. Orphans are always logged.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kill_orphaned_browsers: bool,
+    /// Override the browser executable, arguments, sandbox and headless mode.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub browser_launch_options: Option<Box<BrowserLaunchOptions>>,
+    /// Limit the browser disk cache, the site storage quota and the location of the temp profiles.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub browser_storage_limits: Option<Box<BrowserStorageLimits>>,
+    /// Kill the launched browsers and remove their temp profiles when the process receives SIGTERM or SIGINT, then shut the running crawls down. The process is not exited.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub browser_signal_cleanup: bool,
+    /// Record the latency histograms of the CDP commands sent to the browser.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub chrome_command_metrics: bool,
+    /// Collect the links inside the open shadow roots and the same-origin iframes of the pages.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub chrome_deep_links: bool,
+    /// Collect the performance metrics of the pages after load.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub performance_metrics: Option<Box<PerformanceMetricsConfig>>,
     /// The OpenAI configs to use to help drive the chrome browser. This does nothing without the 'openai' flag.
     pub openai_config: Option<GPTConfigs>,
     /// Setup fingerprint ID on each document. This does nothing without the flag `chrome` enabled.
@@ -158,6 +1320,14 @@ pub struct Configuration {
     /// The chrome connection url. Useful for targeting different headless instances. Defaults to using the env CHROME_URL.
     #[cfg(feature = "chrome")]
     pub chrome_connection_url: Option<String>,
+    /// The auth token and reconnection of the remote browser of the chrome connection url.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub chrome_connection_options: Option<Box<ChromeConnectionOptions>>,
+    /// Render the pages with Firefox or another WebDriver BiDi browser instead of Chrome. Only the chrome intercept, the consent mode third parties and the mocked routes apply to the pages.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub webdriver_bidi: Option<Box<WebDriverBiDiConfig>>,
     /// Scripts to execute for individual pages, the full path of the url is required for an exact match. This is useful for running one off JS on pages like performing custom login actions.
     #[cfg(feature = "chrome")]
     pub execution_scripts: Option<ExecutionScripts>,
@@ -170,6 +1340,21 @@ pub struct Configuration {
     pub return_page_links: bool,
     /// Retry count to attempt to swap proxies etc.
     pub retry: u8,
+    /// Re-queue the failed urls in the crawl frontier with a backoff.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub retry_policy: Option<Box<RetryPolicy>>,
+    /// Adapt the request rate of each host to the 429 and 503 responses.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub adaptive_rate_limit: Option<Box<AdaptiveRateLimit>>,
+    /// The settings of the crawls following the first one.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub recrawl: Option<Box<RecrawlConfig>>,
+    /// Order the crawl frontier with a score.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub priority_frontier: Option<Box<PriorityFrontier>>,
+    /// Cap the pages crawled from a link depth.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub depth_budget: Option<Box<DepthBudget>>,
     /// Setup network interception for request. This does nothing without the flag `chrome_intercept` enabled.
     #[cfg(feature = "chrome")]
     pub chrome_intercept: RequestInterceptConfiguration,
@@ -180,12 +1365,48 @@ pub struct Configuration {
     /// Crawl budget for the paths. This helps prevent crawling extra pages and limiting the amount.
     pub(crate) inner_budget:
         Option<hashbrown::HashMap<case_insensitive_string::CaseInsensitiveString, u32>>,
+    /// The budget left by the crawl resumed from a checkpoint, used once in place of the budget.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) resumed_budget:
+        Option<hashbrown::HashMap<case_insensitive_string::CaseInsensitiveString, u32>>,
     /// Expect only to handle HTML to save on resources. This mainly only blocks the crawling and returning of resources from the server.
     pub only_html: bool,
     /// The concurrency limits to apply.
     pub concurrency_limit: Option<usize>,
+    /// The requests in flight allowed to each host, independently of the global concurrency limit.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_concurrent_per_host: Option<usize>,
+    /// The store of the links visited.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub visited_set: VisitedSet,
+    /// The order to send the pages to the subscribers. Ordered output is buffered until the crawl completes.
+    pub output_order: OutputOrder,
+    /// Render only options that were set without the `chrome` flag enabled.
+    #[cfg(not(feature = "chrome"))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    render_only_options: Vec<&'static str>,
 }
 
+/// Errors found when validating the configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigurationError {
+    /// The option requires a browser to render the page and the `chrome` flag is not enabled.
+    RenderOnlyOption(&'static str),
+}
+
+impl std::fmt::Display for ConfigurationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigurationError::RenderOnlyOption(option) => write!(
+                f,
+                "the option `{option}` requires the `chrome` feature flag to render pages"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigurationError {}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 /// Serializable HTTP headers.
 pub struct SerializableHeaderMap(pub HeaderMap);
@@ -400,169 +1621,532 @@ impl Configuration {
 
     /// Setup the budget for crawling.
     pub(crate) fn configure_budget(&mut self) {
-        self.inner_budget.clone_from(&self.budget);
+        match self.resumed_budget.take() {
+            Some(budget) => self.inner_budget = Some(budget),
+            _ => self.inner_budget.clone_from(&self.budget),
+        }
+    }
+
+    /// Get the whitelist compiled.
+    pub(crate) fn get_whitelist_compiled(&self) -> &AllowList {
+        &self.whitelist.0
+    }
+
+    /// Whether the link is whitelisted, when a whitelist is set, and not blacklisted.
+    pub(crate) fn allows_link(&self, link: &CompactString) -> bool {
+        let whitelist = self.get_whitelist_compiled();
+        let blacklist = self.get_blacklist_compiled();
+
+        (whitelist.is_empty() || crate::black_list::contains(whitelist, link))
+            && (blacklist.is_empty() || !crate::black_list::contains(blacklist, link))
+    }
+
+    #[cfg(feature = "regex")]
+    /// Compile the regex for the whitelist.
+    pub fn get_whitelist(&self) -> Box<regex::RegexSet> {
+        match &self.whitelist_url {
+            Some(whitelist) => match regex::RegexSet::new(&**whitelist) {
+                Ok(s) => Box::new(s),
+                _ => Default::default(),
+            },
+            _ => Default::default(),
+        }
+    }
+
+    #[cfg(not(feature = "regex"))]
+    /// Handle the whitelist options.
+    pub fn get_whitelist(&self) -> Box<Vec<CompactString>> {
+        match &self.whitelist_url {
+            Some(whitelist) => whitelist.to_owned(),
+            _ => Default::default(),
+        }
+    }
+
+    /// Respect robots.txt file.
+    pub fn with_respect_robots_txt(&mut self, respect_robots_txt: bool) -> &mut Self {
+        self.respect_robots_txt = respect_robots_txt;
+        self
+    }
+
+    /// Cache the robots.txt of each host across the crawls of the website for the duration. Expired rules are revalidated with a conditional request and re-fetched when changed.
+    pub fn with_robots_txt_ttl(&mut self, robots_txt_ttl: Option<Duration>) -> &mut Self {
+        self.robots_txt_ttl = robots_txt_ttl;
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Respect robots.txt for the iframes, XHRs and other subresources requested by the rendered page on the host crawled.
+    pub fn with_respect_robots_txt_subresources(
+        &mut self,
+        respect_robots_txt_subresources: bool,
+    ) -> &mut Self {
+        self.track_render_only_option(
+            "respect_robots_txt_subresources",
+            respect_robots_txt_subresources,
+        );
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Respect robots.txt for the iframes, XHRs and other subresources requested by the rendered page on the host crawled.
+    pub fn with_respect_robots_txt_subresources(
+        &mut self,
+        respect_robots_txt_subresources: bool,
+    ) -> &mut Self {
+        self.respect_robots_txt_subresources = respect_robots_txt_subresources;
+        self
+    }
+
+    /// Include subdomains detection. The links sharing the registrable domain of the public suffix list are crawled.
+    pub fn with_subdomains(&mut self, subdomains: bool) -> &mut Self {
+        self.subdomains = subdomains;
+        self
+    }
+
+    /// Include tld detection. The links of the registrable domain under any public suffix are crawled.
+    pub fn with_tld(&mut self, tld: bool) -> &mut Self {
+        self.tld = tld;
+        self
+    }
+
+    /// Delay between request as ms.
+    pub fn with_delay(&mut self, delay: u64) -> &mut Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Only use HTTP/2.
+    pub fn with_http2_prior_knowledge(&mut self, http2_prior_knowledge: bool) -> &mut Self {
+        self.http2_prior_knowledge = http2_prior_knowledge;
+        self
+    }
+
+    /// Max time to wait for request. By default request times out in 15s. Set to None to disable.
+    pub fn with_request_timeout(&mut self, request_timeout: Option<Duration>) -> &mut Self {
+        match request_timeout {
+            Some(timeout) => self.request_timeout = Some(timeout.into()),
+            _ => self.request_timeout = None,
+        };
+
+        self
+    }
+
+    #[cfg(feature = "sitemap")]
+    /// Set the sitemap url. This does nothing without the `sitemap` feature flag.
+    pub fn with_sitemap(&mut self, sitemap_url: Option<&str>) -> &mut Self {
+        match sitemap_url {
+            Some(sitemap_url) => {
+                self.sitemap_url = Some(CompactString::new(sitemap_url.to_string()).into())
+            }
+            _ => self.sitemap_url = None,
+        };
+        self
+    }
+
+    #[cfg(not(feature = "sitemap"))]
+    /// Set the sitemap url. This does nothing without the `sitemap` feature flag.
+    pub fn with_sitemap(&mut self, _sitemap_url: Option<&str>) -> &mut Self {
+        self
+    }
+
+    #[cfg(feature = "sitemap")]
+    /// Ignore the sitemap when crawling. This method does nothing if the `sitemap` is not enabled.
+    pub fn with_ignore_sitemap(&mut self, ignore_sitemap: bool) -> &mut Self {
+        self.ignore_sitemap = ignore_sitemap;
+        self
+    }
+
+    #[cfg(not(feature = "sitemap"))]
+    /// Ignore the sitemap when crawling. This method does nothing if the `sitemap` is not enabled.
+    pub fn with_ignore_sitemap(&mut self, _ignore_sitemap: bool) -> &mut Self {
+        self
+    }
+
+    #[cfg(feature = "sitemap")]
+    /// Set how nested sitemap indexes are followed, how `lastmod` prioritizes fresh urls and whether the urls merge into the crawl frontier. This method does nothing if the `sitemap` is not enabled.
+    pub fn with_sitemap_config(&mut self, sitemap_config: SitemapConfig) -> &mut Self {
+        self.sitemap_config = sitemap_config;
+        self
+    }
+
+    #[cfg(not(feature = "sitemap"))]
+    /// Set how nested sitemap indexes are followed, how `lastmod` prioritizes fresh urls and whether the urls merge into the crawl frontier. This method does nothing if the `sitemap` is not enabled.
+    pub fn with_sitemap_config(&mut self, _sitemap_config: SitemapConfig) -> &mut Self {
+        self
+    }
+
+    /// Add user agent to request.
+    pub fn with_user_agent(&mut self, user_agent: Option<&str>) -> &mut Self {
+        match user_agent {
+            Some(agent) => self.user_agent = Some(CompactString::new(agent).into()),
+            _ => self.user_agent = None,
+        };
+        self
+    }
+
+    /// Preserve the HOST header.
+    pub fn with_preserve_host_header(&mut self, preserve: bool) -> &mut Self {
+        self.preserve_host_header = preserve;
+        self
+    }
+
+    #[cfg(not(feature = "openai"))]
+    /// The OpenAI configs to use to drive the browser. This method does nothing if the `openai` is not enabled.
+    pub fn with_openai(&mut self, _openai_config: Option<GPTConfigs>) -> &mut Self {
+        self
+    }
+
+    /// The OpenAI configs to use to drive the browser. This method does nothing if the `openai` is not enabled.
+    #[cfg(feature = "openai")]
+    pub fn with_openai(&mut self, openai_config: Option<GPTConfigs>) -> &mut Self {
+        match openai_config {
+            Some(openai_config) => self.openai_config = Some(openai_config),
+            _ => self.openai_config = None,
+        };
+        self
+    }
+
+    #[cfg(feature = "cookies")]
+    /// Cookie string to use in request. This does nothing without the `cookies` flag enabled.
+    pub fn with_cookies(&mut self, cookie_str: &str) -> &mut Self {
+        self.cookie_str = Box::new(cookie_str.into());
+        self
+    }
+
+    #[cfg(not(feature = "cookies"))]
+    /// Cookie string to use in request. This does nothing without the `cookies` flag enabled.
+    pub fn with_cookies(&mut self, _cookie_str: &str) -> &mut Self {
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Set custom fingerprint ID for request. This does nothing without the `chrome` flag enabled.
+    pub fn with_fingerprint(&mut self, fingerprint: bool) -> &mut Self {
+        self.fingerprint = fingerprint;
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Set custom fingerprint ID for request. This does nothing without the `chrome` flag enabled.
+    pub fn with_fingerprint(&mut self, fingerprint: bool) -> &mut Self {
+        self.track_render_only_option("fingerprint", fingerprint);
+        self
+    }
+
+    /// Use proxies for request.
+    pub fn with_proxies(&mut self, proxies: Option<Vec<String>>) -> &mut Self {
+        match proxies {
+            Some(p) => self.proxies = Some(p.into()),
+            _ => self.proxies = None,
+        };
+        self
+    }
+
+    /// Connect to the address set for a host instead of resolving it with DNS, like `curl --resolve host:port:addr`.
+    /// The TLS SNI and `Host` header keep the host of the url. The HTTP client skips the overrides on other ports than the
+    /// port of the crawl url, or the default HTTP and HTTPS ports for other hosts. This can be used to test an origin server before a DNS cutover
+    /// or to crawl a staging environment behind internal DNS. The browser launched with the `chrome` flag maps the hosts with
+    /// `--host-resolver-rules`, a remote browser has to be started with the rules.
+    pub fn with_resolve(&mut self, resolve: Option<Vec<ResolveOverride>>) -> &mut Self {
+        self.resolve = resolve.map(Box::new);
+        self
+    }
+
+    /// Perform a callback to transform the body of each page before it reaches the subscribers, scrape storage, and outputs. The callback receives the page url and body.
+    pub fn with_on_body_transform_callback(
+        &mut self,
+        on_body_transform_callback: Option<fn(&str, bytes::Bytes) -> bytes::Bytes>,
+    ) -> &mut Self {
+        self.on_body_transform_callback = on_body_transform_callback.map(BodyTransformCallback);
+        self
+    }
+
+    /// Detect and redact emails, phone numbers, and national identification numbers in the page content. The findings are counted for each crawl.
+    pub fn with_pii_redaction(
+        &mut self,
+        pii_redaction: Option<crate::features::pii::PiiRedaction>,
+    ) -> &mut Self {
+        self.pii_redaction = pii_redaction.map(Box::new);
+        self
+    }
+
+    /// Detect the `<link rel="alternate">` RSS and Atom feeds of the pages, fetch them and collect their entries. The entry links can also be added to the crawl.
+    pub fn with_feed_discovery(
+        &mut self,
+        feed_discovery: Option<crate::features::feed::FeedDiscovery>,
+    ) -> &mut Self {
+        self.feed_discovery = feed_discovery.map(Box::new);
+        self
+    }
+
+    /// Add the urls assigned to `location` in the `onclick` handlers, the urls of attributes like `data-href` and the routes of the
+    /// Next.js route manifests of the pages to the crawl. The targets are guessed from the html and noisier than the anchors.
+    pub fn with_js_link_discovery(
+        &mut self,
+        js_link_discovery: Option<crate::features::js_links::JsLinkDiscovery>,
+    ) -> &mut Self {
+        self.js_link_discovery = js_link_discovery.map(Box::new);
+        self
+    }
+
+    /// Follow the `rel=next` links, the common next page links and the `?page=N` parameters of the pages up to the page limit of the
+    /// pagination. In chrome mode the next page links rendered by javascript are followed too.
+    pub fn with_pagination(
+        &mut self,
+        pagination: Option<crate::features::pagination::Pagination>,
+    ) -> &mut Self {
+        self.pagination = pagination.map(Box::new);
+        self
+    }
+
+    /// Detect the language of the pages and skip storing the pages and following the links of the pages not in the languages of the
+    /// filter. The pages with no language found are kept.
+    pub fn with_language_filter(
+        &mut self,
+        language_filter: Option<crate::features::language::LanguageFilter>,
+    ) -> &mut Self {
+        self.language_filter = language_filter.map(Box::new);
+        self
+    }
+
+    /// Use HTTP/3 for the hosts advertising `h3` with the `Alt-Svc` header in the HTTP crawl with the fallback to HTTP/2 or HTTP/1.1.
+    /// The requests use HTTP/3 with the feature flag `http3` only.
+    pub fn with_http3(&mut self, http3: Option<crate::features::http3::Http3>) -> &mut Self {
+        self.http3 = http3.map(Box::new);
+        self
+    }
+
+    /// Extract the fields of the scraping schema from every page into [`crate::page::Page::extracted`].
+    pub fn with_extractor(
+        &mut self,
+        extractor: Option<crate::features::extractor::Extractor>,
+    ) -> &mut Self {
+        self.extractor = extractor.map(Box::new);
+        self
+    }
+
+    /// Extract the title, byline, publish date and boilerplate free main text of every page into [`crate::page::Page::article`].
+    pub fn with_text_extraction(
+        &mut self,
+        text_extraction: Option<crate::features::text_extraction::TextExtraction>,
+    ) -> &mut Self {
+        self.text_extraction = text_extraction.map(Box::new);
+        self
     }
 
-    /// Get the whitelist compiled.
-    pub(crate) fn get_whitelist_compiled(&self) -> &AllowList {
-        &self.whitelist.0
+    /// Split every page into chunks of at most the max tokens with overlap, the url, heading path and position of each chunk kept for vector database ingestion, into [`crate::page::Page::chunks`].
+    pub fn with_chunking(
+        &mut self,
+        chunking: Option<crate::features::chunking::Chunker>,
+    ) -> &mut Self {
+        self.chunking = chunking.map(Box::new);
+        self
     }
 
-    #[cfg(feature = "regex")]
-    /// Compile the regex for the whitelist.
-    pub fn get_whitelist(&self) -> Box<regex::RegexSet> {
-        match &self.whitelist_url {
-            Some(whitelist) => match regex::RegexSet::new(&**whitelist) {
-                Ok(s) => Box::new(s),
-                _ => Default::default(),
-            },
-            _ => Default::default(),
-        }
+    /// Extract structured JSON from every page with the prompt, the JSON Schema and the model of the extraction into [`crate::page::Page::llm_extracted`], at most the concurrency of the extraction requests running at once.
+    #[cfg(feature = "llm_extraction")]
+    pub fn with_llm_extraction(
+        &mut self,
+        llm_extraction: Option<crate::features::llm_extraction::LlmExtraction>,
+    ) -> &mut Self {
+        self.llm_extraction = llm_extraction.map(Box::new);
+        self
     }
 
-    #[cfg(not(feature = "regex"))]
-    /// Handle the whitelist options.
-    pub fn get_whitelist(&self) -> Box<Vec<CompactString>> {
-        match &self.whitelist_url {
-            Some(whitelist) => whitelist.to_owned(),
-            _ => Default::default(),
-        }
+    /// Check every outbound link of the pages, the external links included, with lightweight HEAD requests and report the links answering with a 4xx or 5xx status code or timing out.
+    pub fn with_link_check(
+        &mut self,
+        link_check: Option<crate::features::link_check::LinkCheck>,
+    ) -> &mut Self {
+        self.link_check = link_check.map(Box::new);
+        self
     }
 
-    /// Respect robots.txt file.
-    pub fn with_respect_robots_txt(&mut self, respect_robots_txt: bool) -> &mut Self {
-        self.respect_robots_txt = respect_robots_txt;
+    /// Tag the requests matching the patterns and write the first full request and response dumps of each tag for every crawl to the directory.
+    pub fn with_request_sampling(
+        &mut self,
+        request_sampling: Option<crate::features::request_sampling::RequestSampling>,
+    ) -> &mut Self {
+        self.request_sampling = request_sampling.map(Box::new);
         self
     }
 
-    /// Include subdomains detection.
-    pub fn with_subdomains(&mut self, subdomains: bool) -> &mut Self {
-        self.subdomains = subdomains;
+    /// Write the frontier, the visited links and the budgets of the crawl to a file periodically. Resume the crawl with [`crate::website::Website::resume_from`].
+    pub fn with_checkpoint(
+        &mut self,
+        checkpoint: Option<crate::features::checkpoint::Checkpoint>,
+    ) -> &mut Self {
+        self.checkpoint = checkpoint.map(Box::new);
         self
     }
 
-    /// Include tld detection.
-    pub fn with_tld(&mut self, tld: bool) -> &mut Self {
-        self.tld = tld;
+    /// Compare the SimHash fingerprints of the pages and suppress the near duplicates of the pages crawled before from the results and the link expansion.
+    pub fn with_near_duplicates(
+        &mut self,
+        near_duplicates: Option<crate::features::near_duplicate::NearDuplicates>,
+    ) -> &mut Self {
+        self.near_duplicates = near_duplicates.map(Box::new);
         self
     }
 
-    /// Delay between request as ms.
-    pub fn with_delay(&mut self, delay: u64) -> &mut Self {
-        self.delay = delay;
+    /// Normalize the links found before they are deduplicated and added to the frontier: strip the tracking parameters and session ids, sort the query and follow the canonical urls in place of their variants.
+    pub fn with_url_normalization(
+        &mut self,
+        url_normalization: Option<crate::features::url_normalization::UrlNormalization>,
+    ) -> &mut Self {
+        self.url_normalization = url_normalization.map(Box::new);
         self
     }
 
-    /// Only use HTTP/2.
-    pub fn with_http2_prior_knowledge(&mut self, http2_prior_knowledge: bool) -> &mut Self {
-        self.http2_prior_knowledge = http2_prior_knowledge;
+
+    /// Keep the `ETag` and `Last-Modified` headers of the pages and send them back as `If-None-Match` and `If-Modified-Since` on the next crawl. The pages answering with a 304 are marked unchanged without downloading the body. Only the http crawl sends conditional requests.
+    pub fn with_revalidation(
+        &mut self,
+        revalidation: Option<crate::features::revalidation::Revalidation>,
+    ) -> &mut Self {
+        self.revalidation = revalidation.map(Box::new);
         self
     }
 
-    /// Max time to wait for request. By default request times out in 15s. Set to None to disable.
-    pub fn with_request_timeout(&mut self, request_timeout: Option<Duration>) -> &mut Self {
-        match request_timeout {
-            Some(timeout) => self.request_timeout = Some(timeout.into()),
-            _ => self.request_timeout = None,
-        };
-
+    /// Inject artificial timeouts, server errors, slow bodies and dropped browser events at the rates of the config. Only use this to test the retry and alerting configuration.
+    #[cfg(feature = "chaos")]
+    pub fn with_chaos(&mut self, chaos: Option<crate::features::chaos::Chaos>) -> &mut Self {
+        self.chaos = chaos.map(Box::new);
         self
     }
 
-    #[cfg(feature = "sitemap")]
-    /// Set the sitemap url. This does nothing without the `sitemap` feature flag.
-    pub fn with_sitemap(&mut self, sitemap_url: Option<&str>) -> &mut Self {
-        match sitemap_url {
-            Some(sitemap_url) => {
-                self.sitemap_url = Some(CompactString::new(sitemap_url.to_string()).into())
-            }
-            _ => self.sitemap_url = None,
-        };
+    /// Send the pages crawled or failed, the links over the budget and with Chrome the blocked requests, the authentication challenges and the crashed tabs to the subscribers of the events.
+    pub fn with_crawl_events(
+        &mut self,
+        crawl_events: Option<crate::features::events::CrawlEvents>,
+    ) -> &mut Self {
+        self.crawl_events = crawl_events.map(Box::new);
         self
     }
 
-    #[cfg(not(feature = "sitemap"))]
-    /// Set the sitemap url. This does nothing without the `sitemap` feature flag.
-    pub fn with_sitemap(&mut self, _sitemap_url: Option<&str>) -> &mut Self {
+    /// Record the pages crawled, the bytes transferred, the fetch latency by host, the frontier size and with Chrome the blocked requests and open tabs. The counters are shared with the clones of the metrics.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(
+        &mut self,
+        metrics: Option<crate::features::metrics::CrawlMetrics>,
+    ) -> &mut Self {
+        self.metrics = metrics.map(Box::new);
         self
     }
 
-    #[cfg(feature = "sitemap")]
-    /// Ignore the sitemap when crawling. This method does nothing if the `sitemap` is not enabled.
-    pub fn with_ignore_sitemap(&mut self, ignore_sitemap: bool) -> &mut Self {
-        self.ignore_sitemap = ignore_sitemap;
+    /// Operate the running crawl with the endpoints of the control server. The state is shared with the clones of the server.
+    #[cfg(feature = "control_server")]
+    pub fn with_control_server(
+        &mut self,
+        control_server: Option<crate::features::control_server::ControlServer>,
+    ) -> &mut Self {
+        self.control_server = control_server.map(Box::new);
         self
     }
 
-    #[cfg(not(feature = "sitemap"))]
-    /// Ignore the sitemap when crawling. This method does nothing if the `sitemap` is not enabled.
-    pub fn with_ignore_sitemap(&mut self, _ignore_sitemap: bool) -> &mut Self {
+    /// Authenticate to a corporate proxy with NTLM or Negotiate. The HTTP client and Chrome use a relay on the loopback running the handshake in place of the proxies.
+    #[cfg(feature = "proxy_auth")]
+    pub fn with_proxy_auth(
+        &mut self,
+        proxy_auth: Option<crate::features::proxy_auth::ProxyAuth>,
+    ) -> &mut Self {
+        self.proxy_auth = proxy_auth.map(Box::new);
         self
     }
 
-    /// Add user agent to request.
-    pub fn with_user_agent(&mut self, user_agent: Option<&str>) -> &mut Self {
-        match user_agent {
-            Some(agent) => self.user_agent = Some(CompactString::new(agent).into()),
-            _ => self.user_agent = None,
-        };
+    /// Share the frontier, the visited links and the host rate limits of the crawl with other workers in Redis. Start the crawl with [`crate::website::Website::crawl_distributed`].
+    #[cfg(feature = "distributed")]
+    pub fn with_distributed(
+        &mut self,
+        distributed: Option<crate::features::distributed::Distributed>,
+    ) -> &mut Self {
+        self.distributed = distributed.map(Box::new);
         self
     }
 
-    /// Preserve the HOST header.
-    pub fn with_preserve_host_header(&mut self, preserve: bool) -> &mut Self {
-        self.preserve_host_header = preserve;
+    /// Stream the crawled pages to the sink as they complete. The page tasks wait for the sink before taking the next link, so a slow sink slows the crawl down.
+    #[cfg(feature = "sink")]
+    pub fn with_sink(&mut self, sink: Option<crate::features::sink::PageSink>) -> &mut Self {
+        self.sink = sink;
         self
     }
 
-    #[cfg(not(feature = "openai"))]
-    /// The OpenAI configs to use to drive the browser. This method does nothing if the `openai` is not enabled.
-    pub fn with_openai(&mut self, _openai_config: Option<GPTConfigs>) -> &mut Self {
+    /// Run the hooks before and after the discover, fetch, extract and finalize phases of the crawl. The extract hooks run once per page before it is sent to the subscribers.
+    #[cfg(feature = "phases")]
+    pub fn with_phase_hooks(
+        &mut self,
+        phase_hooks: Option<crate::features::phases::PhaseHooks>,
+    ) -> &mut Self {
+        self.phase_hooks = phase_hooks;
         self
     }
 
-    /// The OpenAI configs to use to drive the browser. This method does nothing if the `openai` is not enabled.
-    #[cfg(feature = "openai")]
-    pub fn with_openai(&mut self, openai_config: Option<GPTConfigs>) -> &mut Self {
-        match openai_config {
-            Some(openai_config) => self.openai_config = Some(openai_config),
-            _ => self.openai_config = None,
-        };
+    /// Ask the predicate about each discovered url before it enters the frontier. The predicate can skip the url, set its priority in the frontier or force the fetcher of the url.
+    pub fn with_on_should_crawl(
+        &mut self,
+        on_should_crawl: Option<crate::features::should_crawl::OnShouldCrawl>,
+    ) -> &mut Self {
+        self.on_should_crawl = on_should_crawl;
         self
     }
 
-    #[cfg(feature = "cookies")]
-    /// Cookie string to use in request. This does nothing without the `cookies` flag enabled.
-    pub fn with_cookies(&mut self, cookie_str: &str) -> &mut Self {
-        self.cookie_str = Box::new(cookie_str.into());
+    /// Route the urls to the HTTP or Chrome fetcher by pattern or with a function, and fetch the pages over HTTP that are an empty app shell or a javascript challenge again with Chrome.
+    pub fn with_fetch_routing(
+        &mut self,
+        fetch_routing: Option<crate::features::fetch_routing::FetchRouting>,
+    ) -> &mut Self {
+        self.fetch_routing = fetch_routing.map(Box::new);
         self
     }
 
-    #[cfg(not(feature = "cookies"))]
-    /// Cookie string to use in request. This does nothing without the `cookies` flag enabled.
-    pub fn with_cookies(&mut self, _cookie_str: &str) -> &mut Self {
+    /// Treat the urls of each registrable domain with its politeness profile: the delay and the concurrency of the requests, the User-Agent and the proxy of the HTTP requests and the fetcher. The delay and the concurrency are applied by the host limits of the crawl, a url waiting on its domain does not hold a crawl permit.
+    pub fn with_politeness(
+        &mut self,
+        politeness: Option<crate::features::politeness::PolitenessProfiles>,
+    ) -> &mut Self {
+        self.politeness = politeness.map(Box::new);
         self
     }
 
+    /// The client of the politeness profile of the url, or of the client certificate of its host, in place of the
+    /// client of the crawl.
     #[cfg(feature = "chrome")]
-    /// Set custom fingerprint ID for request. This does nothing without the `chrome` flag enabled.
-    pub fn with_fingerprint(&mut self, fingerprint: bool) -> &mut Self {
-        self.fingerprint = fingerprint;
-        self
+    pub(crate) fn request_client(&self, url: &str) -> Option<crate::Client> {
+        self.politeness
+            .as_ref()
+            .and_then(|politeness| politeness.client(url))
+            .or_else(|| self.certificate_clients.client(url))
+    }
+
+    /// Fetch the page with the faults of the chaos config injected.
+    #[cfg(feature = "chaos")]
+    pub(crate) async fn chaos_fetch(
+        &self,
+        url: &str,
+        fetch: impl std::future::Future<Output = crate::page::Page>,
+    ) -> crate::page::Page {
+        match &self.chaos {
+            Some(chaos) => chaos.fetch(url, &self.request_timeout, fetch).await,
+            _ => fetch.await,
+        }
     }
 
-    #[cfg(not(feature = "chrome"))]
-    /// Set custom fingerprint ID for request. This does nothing without the `chrome` flag enabled.
-    pub fn with_fingerprint(&mut self, _fingerprint: bool) -> &mut Self {
-        self
+    /// Fetch the page with the faults of the chaos config injected.
+    #[cfg(not(feature = "chaos"))]
+    pub(crate) async fn chaos_fetch(
+        &self,
+        _url: &str,
+        fetch: impl std::future::Future<Output = crate::page::Page>,
+    ) -> crate::page::Page {
+        fetch.await
     }
 
-    /// Use proxies for request.
-    pub fn with_proxies(&mut self, proxies: Option<Vec<String>>) -> &mut Self {
-        match proxies {
-            Some(p) => self.proxies = Some(p.into()),
-            _ => self.proxies = None,
-        };
+    /// Set the TLS client certificates for mutual TLS. The first certificate matching the host of each request is sent.
+    pub fn with_client_certificates(
+        &mut self,
+        client_certificates: Option<Vec<ClientCertificate>>,
+    ) -> &mut Self {
+        self.client_certificates = client_certificates.map(Box::new);
         self
     }
 
@@ -596,72 +2180,258 @@ impl Configuration {
         self
     }
 
-    /// Return the links found on the page in the channel subscriptions. This method does nothing if the `decentralized` is enabled.
-    pub fn with_return_page_links(&mut self, return_page_links: bool) -> &mut Self {
-        self.return_page_links = return_page_links;
+    /// Return the links found on the page in the channel subscriptions. This method does nothing if the `decentralized` is enabled.
+    pub fn with_return_page_links(&mut self, return_page_links: bool) -> &mut Self {
+        self.return_page_links = return_page_links;
+        self
+    }
+
+    /// Set HTTP headers for request using [reqwest::header::HeaderMap](https://docs.rs/reqwest/latest/reqwest/header/struct.HeaderMap.html).
+    pub fn with_headers(&mut self, headers: Option<reqwest::header::HeaderMap>) -> &mut Self {
+        match headers {
+            Some(m) => self.headers = Some(SerializableHeaderMap::from(m).into()),
+            _ => self.headers = None,
+        };
+        self
+    }
+
+    /// Set the max redirects allowed for request.
+    pub fn with_redirect_limit(&mut self, redirect_limit: usize) -> &mut Self {
+        self.redirect_limit = redirect_limit.into();
+        self
+    }
+
+    /// Set the redirect policy to use.
+    pub fn with_redirect_policy(&mut self, policy: RedirectPolicy) -> &mut Self {
+        self.redirect_policy = policy;
+        self
+    }
+
+    /// Determine whether to collect all the resources found on pages.
+    pub fn with_full_resources(&mut self, full_resources: bool) -> &mut Self {
+        self.full_resources = full_resources;
+        self
+    }
+
+    #[cfg(feature = "cron")]
+    /// Setup cron jobs to run. This does nothing without the `cron` flag enabled.
+    pub fn with_cron(&mut self, cron_str: &str, cron_type: CronType) -> &mut Self {
+        self.cron_str = cron_str.into();
+        self.cron_type = cron_type;
+        self
+    }
+
+    #[cfg(not(feature = "cron"))]
+    /// Setup cron jobs to run. This does nothing without the `cron` flag enabled.
+    pub fn with_cron(&mut self, _cron_str: &str, _cron_type: CronType) -> &mut Self {
+        self
+    }
+
+    /// Set a crawl page limit. If the value is 0 there is no limit. This does nothing without the feat flag `budget` enabled.
+    pub fn with_limit(&mut self, limit: u32) -> &mut Self {
+        self.with_budget(Some(hashbrown::HashMap::from([("*", limit)])));
+        self
+    }
+
+    /// Set the concurrency limits. If you set the value to None to use the default limits using the system CPU cors * n.
+    pub fn with_concurrency_limit(&mut self, limit: Option<usize>) -> &mut Self {
+        self.concurrency_limit = limit;
+        self
+    }
+
+    /// Set the requests in flight allowed to each host. The global concurrency limit still caps the crawl, so a crawl
+    /// across many domains can run many pages at once without sending more than the limit to a single host.
+    pub fn with_max_concurrent_per_host(
+        &mut self,
+        max_concurrent_per_host: Option<usize>,
+    ) -> &mut Self {
+        self.max_concurrent_per_host = max_concurrent_per_host;
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Set the authentiation challenge response. This does nothing without the feat flag `chrome` enabled.
+    pub fn with_auth_challenge_response(
+        &mut self,
+        auth_challenge_response: Option<AuthChallengeResponse>,
+    ) -> &mut Self {
+        self.auth_challenge_response = auth_challenge_response;
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Rotate the proxies of new browser pages. Each proxy gets its own browser context and the credentials answer the proxy auth challenge.
+    pub fn with_proxy_rotation(&mut self, proxy_rotation: Option<ProxyRotation>) -> &mut Self {
+        self.proxy_rotation = proxy_rotation.map(Box::new);
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Rotate the proxies of new browser pages. Each proxy gets its own browser context and the credentials answer the proxy auth challenge.
+    pub fn with_proxy_rotation(&mut self, proxy_rotation: Option<ProxyRotation>) -> &mut Self {
+        self.track_render_only_option("proxy_rotation", proxy_rotation.is_some());
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Spread the hosts of the crawl across the amount of browsers. A host always renders in the same browser so a site crashing tabs only affects its shard.
+    /// Sharding is skipped when proxy rotation is used since the proxy contexts belong to a single browser.
+    pub fn with_browser_shards(&mut self, browser_shards: usize) -> &mut Self {
+        self.browser_shards = browser_shards;
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Spread the hosts of the crawl across the amount of browsers. A host always renders in the same browser so a site crashing tabs only affects its shard.
+    /// Sharding is skipped when proxy rotation is used since the proxy contexts belong to a single browser.
+    pub fn with_browser_shards(&mut self, browser_shards: usize) -> &mut Self {
+        self.track_render_only_option("browser_shards", browser_shards > 1);
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Spread the pages of the crawl across a pool of browsers instead of one browser for the crawl. The pool grows up to its maximum size as the browsers fill up
+    /// and recycles the browsers past their lifetime, over the memory ceiling or not answering CDP. The pool replaces the browser shards.
+    pub fn with_browser_pool(&mut self, browser_pool: Option<BrowserPoolConfig>) -> &mut Self {
+        self.browser_pool = browser_pool.map(Box::new);
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Spread the pages of the crawl across a pool of browsers instead of one browser for the crawl. The pool grows up to its maximum size as the browsers fill up
+    /// and recycles the browsers past their lifetime, over the memory ceiling or not answering CDP. The pool replaces the browser shards.
+    pub fn with_browser_pool(&mut self, browser_pool: Option<BrowserPoolConfig>) -> &mut Self {
+        self.track_render_only_option("browser_pool", browser_pool.is_some());
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Override the browser executable, extra arguments, sandbox and headless mode used to launch chrome.
+    /// The defaults adapt to the platform, the Linux only flags are dropped on Windows and macOS.
+    pub fn with_browser_launch_options(
+        &mut self,
+        browser_launch_options: Option<BrowserLaunchOptions>,
+    ) -> &mut Self {
+        self.browser_launch_options = browser_launch_options.map(Box::new);
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Override the browser executable, extra arguments, sandbox and headless mode used to launch chrome.
+    /// The defaults adapt to the platform, the Linux only flags are dropped on Windows and macOS.
+    pub fn with_browser_launch_options(
+        &mut self,
+        browser_launch_options: Option<BrowserLaunchOptions>,
+    ) -> &mut Self {
+        self.track_render_only_option("browser_launch_options", browser_launch_options.is_some());
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Limit the disk cache size, the IndexedDB and localStorage quota of each origin and set the directory of the temp profiles so long crawls do not silently fill the disk.
+    pub fn with_browser_storage_limits(
+        &mut self,
+        browser_storage_limits: Option<BrowserStorageLimits>,
+    ) -> &mut Self {
+        self.browser_storage_limits = browser_storage_limits.map(Box::new);
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Limit the disk cache size, the IndexedDB and localStorage quota of each origin and set the directory of the temp profiles so long crawls do not silently fill the disk.
+    pub fn with_browser_storage_limits(
+        &mut self,
+        browser_storage_limits: Option<BrowserStorageLimits>,
+    ) -> &mut Self {
+        self.track_render_only_option("browser_storage_limits", browser_storage_limits.is_some());
         self
     }
 
-    /// Set HTTP headers for request using [reqwest::header::HeaderMap](https://docs.rs/reqwest/latest/reqwest/header/struct.HeaderMap.html).
-    pub fn with_headers(&mut self, headers: Option<reqwest::header::HeaderMap>) -> &mut Self {
-        match headers {
-            Some(m) => self.headers = Some(SerializableHeaderMap::from(m).into()),
-            _ => self.headers = None,
-        };
+    #[cfg(feature = "chrome")]
+    /// Kill browsers left running by a previous crashed run and remove their temp profiles when the first browser launches.
+    /// Orphans are detected and logged either way, detection is only available on Linux.
+    pub fn with_kill_orphaned_browsers(&mut self, kill_orphaned_browsers: bool) -> &mut Self {
+        self.kill_orphaned_browsers = kill_orphaned_browsers;
         self
     }
 
-    /// Set the max redirects allowed for request.
-    pub fn with_redirect_limit(&mut self, redirect_limit: usize) -> &mut Self {
-        self.redirect_limit = redirect_limit.into();
+    #[cfg(not(feature = "chrome"))]
+    /// Kill browsers left running by a previous crashed run and remove their temp profiles when the first browser launches.
+    /// Orphans are detected and logged either way, detection is only available on Linux.
+    pub fn with_kill_orphaned_browsers(&mut self, kill_orphaned_browsers: bool) -> &mut Self {
+        self.track_render_only_option("kill_orphaned_browsers", kill_orphaned_browsers);
         self
     }
 
-    /// Set the redirect policy to use.
-    pub fn with_redirect_policy(&mut self, policy: RedirectPolicy) -> &mut Self {
-        self.redirect_policy = policy;
+    #[cfg(feature = "chrome")]
+    /// Kill the launched browsers with their helper processes and remove their temp profiles when the process receives SIGTERM or SIGINT, then shut the running crawls down.
+    /// The process is not exited, the crawl returns to the caller.
+    /// The handler is installed once per process on the first browser launch.
+    pub fn with_browser_signal_cleanup(&mut self, browser_signal_cleanup: bool) -> &mut Self {
+        self.browser_signal_cleanup = browser_signal_cleanup;
         self
     }
 
-    /// Determine whether to collect all the resources found on pages.
-    pub fn with_full_resources(&mut self, full_resources: bool) -> &mut Self {
-        self.full_resources = full_resources;
+    #[cfg(not(feature = "chrome"))]
+    /// Kill the launched browsers with their helper processes and remove their temp profiles when the process receives SIGTERM or SIGINT, then shut the running crawls down.
+    /// The process is not exited, the crawl returns to the caller.
+    /// The handler is installed once per process on the first browser launch.
+    pub fn with_browser_signal_cleanup(&mut self, browser_signal_cleanup: bool) -> &mut Self {
+        self.track_render_only_option("browser_signal_cleanup", browser_signal_cleanup);
         self
     }
 
-    #[cfg(feature = "cron")]
-    /// Setup cron jobs to run. This does nothing without the `cron` flag enabled.
-    pub fn with_cron(&mut self, cron_str: &str, cron_type: CronType) -> &mut Self {
-        self.cron_str = cron_str.into();
-        self.cron_type = cron_type;
+    #[cfg(feature = "chrome")]
+    /// Record per method latency histograms of the CDP commands like `Page.navigate`, `Runtime.evaluate` and `Page.captureScreenshot`.
+    /// The latency only covers the round trip to the browser, telling slow commands apart from slow networks.
+    pub fn with_chrome_command_metrics(&mut self, chrome_command_metrics: bool) -> &mut Self {
+        self.chrome_command_metrics = chrome_command_metrics;
         self
     }
 
-    #[cfg(not(feature = "cron"))]
-    /// Setup cron jobs to run. This does nothing without the `cron` flag enabled.
-    pub fn with_cron(&mut self, _cron_str: &str, _cron_type: CronType) -> &mut Self {
+    #[cfg(not(feature = "chrome"))]
+    /// Record per method latency histograms of the CDP commands like `Page.navigate`, `Runtime.evaluate` and `Page.captureScreenshot`.
+    /// The latency only covers the round trip to the browser, telling slow commands apart from slow networks.
+    pub fn with_chrome_command_metrics(&mut self, chrome_command_metrics: bool) -> &mut Self {
+        self.track_render_only_option("chrome_command_metrics", chrome_command_metrics);
         self
     }
 
-    /// Set a crawl page limit. If the value is 0 there is no limit. This does nothing without the feat flag `budget` enabled.
-    pub fn with_limit(&mut self, limit: u32) -> &mut Self {
-        self.with_budget(Some(hashbrown::HashMap::from([("*", limit)])));
+    #[cfg(feature = "chrome")]
+    /// Walk the open shadow roots and the same-origin iframes of the rendered pages for links. The anchors of web components and
+    /// embedded documents are not part of the html of the page and are missed by the link extraction otherwise.
+    pub fn with_chrome_deep_links(&mut self, chrome_deep_links: bool) -> &mut Self {
+        self.chrome_deep_links = chrome_deep_links;
         self
     }
 
-    /// Set the concurrency limits. If you set the value to None to use the default limits using the system CPU cors * n.
-    pub fn with_concurrency_limit(&mut self, limit: Option<usize>) -> &mut Self {
-        self.concurrency_limit = limit;
+    #[cfg(not(feature = "chrome"))]
+    /// Walk the open shadow roots and the same-origin iframes of the rendered pages for links. The anchors of web components and
+    /// embedded documents are not part of the html of the page and are missed by the link extraction otherwise.
+    pub fn with_chrome_deep_links(&mut self, chrome_deep_links: bool) -> &mut Self {
+        self.track_render_only_option("chrome_deep_links", chrome_deep_links);
         self
     }
 
     #[cfg(feature = "chrome")]
-    /// Set the authentiation challenge response. This does nothing without the feat flag `chrome` enabled.
-    pub fn with_auth_challenge_response(
+    /// Collect the `Performance.getMetrics` metrics of the pages after load into [`crate::page::Page::performance_metrics`], with the
+    /// first and largest contentful paint when the paint timings are enabled.
+    pub fn with_performance_metrics(
         &mut self,
-        auth_challenge_response: Option<AuthChallengeResponse>,
+        performance_metrics: Option<PerformanceMetricsConfig>,
     ) -> &mut Self {
-        self.auth_challenge_response = auth_challenge_response;
+        self.performance_metrics = performance_metrics.map(Box::new);
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Collect the `Performance.getMetrics` metrics of the pages after load, with the first and largest contentful paint when the
+    /// paint timings are enabled.
+    pub fn with_performance_metrics(
+        &mut self,
+        performance_metrics: Option<PerformanceMetricsConfig>,
+    ) -> &mut Self {
+        self.track_render_only_option("performance_metrics", performance_metrics.is_some());
         self
     }
 
@@ -679,8 +2449,12 @@ impl Configuration {
     /// Set a custom script to evaluate on new document creation. This does nothing without the feat flag `chrome` enabled.
     pub fn with_evaluate_on_new_document(
         &mut self,
-        _evaluate_on_new_document: Option<Box<String>>,
+        evaluate_on_new_document: Option<Box<String>>,
     ) -> &mut Self {
+        self.track_render_only_option(
+            "evaluate_on_new_document",
+            evaluate_on_new_document.is_some(),
+        );
         self
     }
 
@@ -688,8 +2462,9 @@ impl Configuration {
     /// Set the authentiation challenge response. This does nothing without the feat flag `chrome` enabled.
     pub fn with_auth_challenge_response(
         &mut self,
-        _auth_challenge_response: Option<AuthChallengeResponse>,
+        auth_challenge_response: Option<AuthChallengeResponse>,
     ) -> &mut Self {
+        self.track_render_only_option("auth_challenge_response", auth_challenge_response.is_some());
         self
     }
 
@@ -714,10 +2489,31 @@ impl Configuration {
 
     /// Configures the view port for chrome. This method does nothing if the `chrome` feature is not enabled.
     #[cfg(not(feature = "chrome"))]
-    pub fn with_viewport(
-        &mut self,
-        _viewport: Option<crate::configuration::Viewport>,
-    ) -> &mut Self {
+    pub fn with_viewport(&mut self, viewport: Option<crate::configuration::Viewport>) -> &mut Self {
+        self.track_render_only_option("viewport", viewport.is_some());
+        self
+    }
+
+    /// Emulate a device by setting the viewport, scale factor, touch support and user agent together. The viewport only applies with the `chrome` flag enabled.
+    #[cfg(feature = "chrome")]
+    pub fn with_device_preset(&mut self, device_preset: Option<DevicePreset>) -> &mut Self {
+        match device_preset {
+            Some(preset) => {
+                self.viewport = Some(preset.viewport());
+                self.with_user_agent(Some(preset.user_agent()));
+            }
+            _ => self.viewport = None,
+        };
+        self
+    }
+
+    /// Emulate a device by setting the viewport, scale factor, touch support and user agent together. The viewport only applies with the `chrome` flag enabled.
+    #[cfg(not(feature = "chrome"))]
+    pub fn with_device_preset(&mut self, device_preset: Option<DevicePreset>) -> &mut Self {
+        if let Some(preset) = device_preset {
+            self.with_user_agent(Some(preset.user_agent()));
+        }
+        self.track_render_only_option("device_preset", device_preset.is_some());
         self
     }
 
@@ -727,6 +2523,43 @@ impl Configuration {
         self
     }
 
+    /// Re-queue the urls failing with the policy failures in the crawl frontier after a jittered exponential backoff.
+    pub fn with_retry_policy(&mut self, retry_policy: Option<RetryPolicy>) -> &mut Self {
+        self.retry_policy = retry_policy.map(Box::new);
+        self
+    }
+
+    /// Limit the requests of each host with a token bucket slowing down on 429 and 503 responses and `Retry-After` and speeding back up after sustained success.
+    /// The limit applies on top of the fixed [`Configuration::delay`].
+    pub fn with_adaptive_rate_limit(
+        &mut self,
+        adaptive_rate_limit: Option<AdaptiveRateLimit>,
+    ) -> &mut Self {
+        self.adaptive_rate_limit = adaptive_rate_limit.map(Box::new);
+        self
+    }
+
+    /// Use different settings for the crawls following the first one. See [`crate::website::Website::crawl_scheduled`].
+    pub fn with_recrawl(&mut self, recrawl: Option<RecrawlConfig>) -> &mut Self {
+        self.recrawl = recrawl.map(Box::new);
+        self
+    }
+
+    /// Crawl the discovered urls in batches ordered by the score of the frontier instead of the discovery order.
+    pub fn with_priority_frontier(
+        &mut self,
+        priority_frontier: Option<PriorityFrontier>,
+    ) -> &mut Self {
+        self.priority_frontier = priority_frontier.map(Box::new);
+        self
+    }
+
+    /// Cap the pages crawled from a link depth, ex: at most 100 pages at depth 3 or more.
+    pub fn with_depth_budget(&mut self, depth_budget: Option<DepthBudget>) -> &mut Self {
+        self.depth_budget = depth_budget.map(Box::new);
+        self
+    }
+
     /// Configures the viewport of the browser, which defaults to 800x600. This method does nothing if the [chrome] feature is not enabled.
     #[cfg(feature = "chrome")]
     pub fn with_viewport(&mut self, viewport: Option<crate::configuration::Viewport>) -> &mut Self {
@@ -746,7 +2579,45 @@ impl Configuration {
 
     #[cfg(not(feature = "chrome"))]
     /// Use stealth mode for the request. This does nothing without the `chrome` flag enabled.
-    pub fn with_stealth(&mut self, _stealth_mode: bool) -> &mut Self {
+    pub fn with_stealth(&mut self, stealth_mode: bool) -> &mut Self {
+        self.track_render_only_option("stealth_mode", stealth_mode);
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Bypass the Content Security Policy of each page and allow mixed content so the injected scripts and the stealth shims run on pages with a strict CSP.
+    pub fn with_bypass_csp(&mut self, bypass_csp: bool) -> &mut Self {
+        self.bypass_csp = bypass_csp;
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Bypass the Content Security Policy of each page and allow mixed content so the injected scripts and the stealth shims run on pages with a strict CSP.
+    pub fn with_bypass_csp(&mut self, bypass_csp: bool) -> &mut Self {
+        self.track_render_only_option("bypass_csp", bypass_csp);
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Set the strategy to pick the user agent for each new page. The agent is applied to the page and the extra headers together.
+    pub fn with_user_agent_strategy(
+        &mut self,
+        user_agent_strategy: UserAgentStrategy,
+    ) -> &mut Self {
+        self.user_agent_strategy = user_agent_strategy;
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Set the strategy to pick the user agent for each new page. The agent is applied to the page and the extra headers together.
+    pub fn with_user_agent_strategy(
+        &mut self,
+        user_agent_strategy: UserAgentStrategy,
+    ) -> &mut Self {
+        self.track_render_only_option(
+            "user_agent_strategy",
+            user_agent_strategy != UserAgentStrategy::Fixed,
+        );
         self
     }
 
@@ -771,8 +2642,36 @@ impl Configuration {
     /// Wait for idle network request. This method does nothing if the `chrome` feature is not enabled.
     pub fn with_wait_for_idle_network(
         &mut self,
-        _wait_for_idle_network: Option<WaitForIdleNetwork>,
+        wait_for_idle_network: Option<WaitForIdleNetwork>,
+    ) -> &mut Self {
+        self.track_render_only_option("wait_for_idle_network", wait_for_idle_network.is_some());
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Wait for the anti-bot challenges like the Cloudflare interstitials and the queue-it waiting rooms to clear before capturing the content. This method does nothing if the [chrome] feature is not enabled.
+    pub fn with_wait_for_challenge(
+        &mut self,
+        wait_for_challenge: Option<WaitForChallenge>,
+    ) -> &mut Self {
+        match self.wait_for.as_mut() {
+            Some(wait_for) => wait_for.challenge = wait_for_challenge,
+            _ => {
+                let mut wait_for = WaitFor::default();
+                wait_for.challenge = wait_for_challenge;
+                self.wait_for = Some(wait_for);
+            }
+        }
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Wait for the anti-bot challenges like the Cloudflare interstitials and the queue-it waiting rooms to clear before capturing the content. This method does nothing if the `chrome` feature is not enabled.
+    pub fn with_wait_for_challenge(
+        &mut self,
+        wait_for_challenge: Option<WaitForChallenge>,
     ) -> &mut Self {
+        self.track_render_only_option("wait_for_challenge", wait_for_challenge.is_some());
         self
     }
 
@@ -797,8 +2696,9 @@ impl Configuration {
     /// Wait for idle dom mutations for target element. This method does nothing if the [chrome] feature is not enabled.
     pub fn with_wait_for_idle_dom(
         &mut self,
-        _wait_for_idle_dom: Option<WaitForSelector>,
+        wait_for_idle_dom: Option<WaitForSelector>,
     ) -> &mut Self {
+        self.track_render_only_option("wait_for_idle_dom", wait_for_idle_dom.is_some());
         self
     }
 
@@ -823,8 +2723,9 @@ impl Configuration {
     /// Wait for a selector. This method does nothing if the `chrome` feature is not enabled.
     pub fn with_wait_for_selector(
         &mut self,
-        _wait_for_selector: Option<WaitForSelector>,
+        wait_for_selector: Option<WaitForSelector>,
     ) -> &mut Self {
+        self.track_render_only_option("wait_for_selector", wait_for_selector.is_some());
         self
     }
 
@@ -844,7 +2745,8 @@ impl Configuration {
 
     #[cfg(not(feature = "chrome"))]
     /// Wait for with delay. Should only be used for testing. This method does nothing if the 'chrome' feature is not enabled.
-    pub fn with_wait_for_delay(&mut self, _wait_for_delay: Option<WaitForDelay>) -> &mut Self {
+    pub fn with_wait_for_delay(&mut self, wait_for_delay: Option<WaitForDelay>) -> &mut Self {
+        self.track_render_only_option("wait_for_delay", wait_for_delay.is_some());
         self
     }
 
@@ -867,6 +2769,19 @@ impl Configuration {
         self
     }
 
+    #[cfg(feature = "chrome_intercept")]
+    /// Set the action of each resource type of the intercepted requests like blocking the fonts or answering the images with an empty response. The actions take precedence over the `block_visuals`, `block_stylesheets` and `block_javascript` flags of the intercept config. This method does nothing if the `chrome_intercept` is not enabled.
+    pub fn with_resource_policy(&mut self, resource_policy: ResourcePolicy) -> &mut Self {
+        self.chrome_intercept.resource_policy = resource_policy;
+        self
+    }
+
+    #[cfg(not(feature = "chrome_intercept"))]
+    /// Set the action of each resource type of the intercepted requests like blocking the fonts or answering the images with an empty response. The actions take precedence over the `block_visuals`, `block_stylesheets` and `block_javascript` flags of the intercept config. This method does nothing if the `chrome_intercept` is not enabled.
+    pub fn with_resource_policy(&mut self, _resource_policy: ResourcePolicy) -> &mut Self {
+        self
+    }
+
     #[cfg(feature = "chrome")]
     /// Set the connection url for the chrome instance. This method does nothing if the `chrome` is not enabled.
     pub fn with_chrome_connection(&mut self, chrome_connection_url: Option<String>) -> &mut Self {
@@ -876,7 +2791,45 @@ impl Configuration {
 
     #[cfg(not(feature = "chrome"))]
     /// Set the connection url for the chrome instance. This method does nothing if the `chrome` is not enabled.
-    pub fn with_chrome_connection(&mut self, _chrome_connection_url: Option<String>) -> &mut Self {
+    pub fn with_chrome_connection(&mut self, chrome_connection_url: Option<String>) -> &mut Self {
+        self.track_render_only_option("chrome_connection_url", chrome_connection_url.is_some());
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Set the auth token and the reconnection of the remote browser of the chrome connection url. This method does nothing if the `chrome` is not enabled.
+    pub fn with_chrome_connection_options(
+        &mut self,
+        chrome_connection_options: Option<ChromeConnectionOptions>,
+    ) -> &mut Self {
+        self.chrome_connection_options = chrome_connection_options.map(Box::new);
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Set the auth token and the reconnection of the remote browser of the chrome connection url. This method does nothing if the `chrome` is not enabled.
+    pub fn with_chrome_connection_options(
+        &mut self,
+        chrome_connection_options: Option<ChromeConnectionOptions>,
+    ) -> &mut Self {
+        self.track_render_only_option(
+            "chrome_connection_options",
+            chrome_connection_options.is_some(),
+        );
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Render the pages with Firefox or another WebDriver BiDi browser instead of Chrome. This method does nothing if the `chrome` is not enabled.
+    pub fn with_webdriver_bidi(&mut self, webdriver_bidi: Option<WebDriverBiDiConfig>) -> &mut Self {
+        self.webdriver_bidi = webdriver_bidi.map(Box::new);
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Render the pages with Firefox or another WebDriver BiDi browser instead of Chrome. This method does nothing if the `chrome` is not enabled.
+    pub fn with_webdriver_bidi(&mut self, webdriver_bidi: Option<WebDriverBiDiConfig>) -> &mut Self {
+        self.track_render_only_option("webdriver_bidi", webdriver_bidi.is_some());
         self
     }
 
@@ -884,8 +2837,9 @@ impl Configuration {
     /// Set JS to run on certain pages. This method does nothing if the `chrome` is not enabled.
     pub fn with_execution_scripts(
         &mut self,
-        _execution_scripts: Option<ExecutionScriptsMap>,
+        execution_scripts: Option<ExecutionScriptsMap>,
     ) -> &mut Self {
+        self.track_render_only_option("execution_scripts", execution_scripts.is_some());
         self
     }
 
@@ -904,8 +2858,9 @@ impl Configuration {
     /// Run web automated actions on certain pages. This method does nothing if the `chrome` is not enabled.
     pub fn with_automation_scripts(
         &mut self,
-        _automation_scripts: Option<AutomationScriptsMap>,
+        automation_scripts: Option<AutomationScriptsMap>,
     ) -> &mut Self {
+        self.track_render_only_option("automation_scripts", automation_scripts.is_some());
         self
     }
 
@@ -979,7 +2934,8 @@ impl Configuration {
 
     #[cfg(not(feature = "chrome"))]
     /// Overrides default host system timezone with the specified one. This does nothing without the `chrome` flag enabled.
-    pub fn with_timezone_id(&mut self, _timezone_id: Option<String>) -> &mut Self {
+    pub fn with_timezone_id(&mut self, timezone_id: Option<String>) -> &mut Self {
+        self.track_render_only_option("timezone_id", timezone_id.is_some());
         self
     }
 
@@ -995,7 +2951,8 @@ impl Configuration {
 
     #[cfg(not(feature = "chrome"))]
     /// Overrides default host system locale with the specified one. This does nothing without the `chrome` flag enabled.
-    pub fn with_locale(&mut self, _locale: Option<String>) -> &mut Self {
+    pub fn with_locale(&mut self, locale: Option<String>) -> &mut Self {
+        self.track_render_only_option("locale", locale.is_some());
         self
     }
 
@@ -1009,9 +2966,136 @@ impl Configuration {
         self
     }
 
+    #[cfg(not(feature = "chrome"))]
+    /// Overrides the geolocation position of the browser.
+    pub fn with_geolocation(&mut self, geolocation: Option<Geolocation>) -> &mut Self {
+        self.track_render_only_option("geolocation", geolocation.is_some());
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Overrides the geolocation position of the browser.
+    pub fn with_geolocation(&mut self, geolocation: Option<Geolocation>) -> &mut Self {
+        self.geolocation = geolocation.map(Box::new);
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Slow down the CPU of the pages by the factor to render them like a low-end device, `1.0` is no throttling.
+    pub fn with_cpu_throttling_rate(&mut self, cpu_throttling_rate: Option<f64>) -> &mut Self {
+        self.track_render_only_option("cpu_throttling_rate", cpu_throttling_rate.is_some());
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Slow down the CPU of the pages by the factor to render them like a low-end device, `1.0` is no throttling.
+    pub fn with_cpu_throttling_rate(&mut self, cpu_throttling_rate: Option<f64>) -> &mut Self {
+        self.cpu_throttling_rate = cpu_throttling_rate;
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Block every third party request by registrable domain except the allowlist and disable cookies in the browser.
+    pub fn with_consent_mode(&mut self, consent_mode: Option<ConsentMode>) -> &mut Self {
+        self.track_render_only_option("consent_mode", consent_mode.is_some());
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Block every third party request by registrable domain except the allowlist and disable cookies in the browser.
+    pub fn with_consent_mode(&mut self, consent_mode: Option<ConsentMode>) -> &mut Self {
+        self.consent_mode = consent_mode.map(Box::new);
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Answer the browser requests matching the url patterns with canned responses for tests and offline runs.
+    pub fn with_mock_router(&mut self, mock_router: Option<MockRouter>) -> &mut Self {
+        self.track_render_only_option("mock_router", mock_router.is_some());
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Answer the browser requests matching the url patterns with canned responses for tests and offline runs.
+    pub fn with_mock_router(&mut self, mock_router: Option<MockRouter>) -> &mut Self {
+        self.mock_router = mock_router.map(Box::new);
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Record every response the browser receives to a directory, or replay a recording so the browser requests never reach the network.
+    /// Replaying fails the requests missing from the recording which makes re-crawls deterministic when debugging extraction.
+    pub fn with_network_recording(
+        &mut self,
+        network_recording: Option<NetworkRecording>,
+    ) -> &mut Self {
+        self.track_render_only_option("network_recording", network_recording.is_some());
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Record every response the browser receives to a directory, or replay a recording so the browser requests never reach the network.
+    /// Replaying fails the requests missing from the recording which makes re-crawls deterministic when debugging extraction.
+    pub fn with_network_recording(
+        &mut self,
+        network_recording: Option<NetworkRecording>,
+    ) -> &mut Self {
+        self.network_recording = network_recording.map(Box::new);
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Fulfill the repeated scripts, stylesheets, fonts and images of the pages from a cache shared by the crawl instead of the network.
+    pub fn with_fulfill_cache(&mut self, fulfill_cache: Option<FulfillCacheConfig>) -> &mut Self {
+        self.track_render_only_option("fulfill_cache", fulfill_cache.is_some());
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Fulfill the repeated scripts, stylesheets, fonts and images of the pages from a cache shared by the crawl instead of the network.
+    pub fn with_fulfill_cache(&mut self, fulfill_cache: Option<FulfillCacheConfig>) -> &mut Self {
+        self.fulfill_cache = fulfill_cache.map(Box::new);
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Abort the browser responses over the bytes allowed for their resource type so an endpoint streaming a huge body can not stall the page until the timeout.
+    pub fn with_max_response_bytes(&mut self, max_response_bytes: Option<MaxResponseBytes>) -> &mut Self {
+        self.track_render_only_option("max_response_bytes", max_response_bytes.is_some());
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Abort the browser responses over the bytes allowed for their resource type so an endpoint streaming a huge body can not stall the page until the timeout.
+    pub fn with_max_response_bytes(&mut self, max_response_bytes: Option<MaxResponseBytes>) -> &mut Self {
+        self.max_response_bytes = max_response_bytes.map(Box::new);
+        self
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// Save the files downloaded when a link triggers a download in the browser.
+    pub fn with_download_capture(
+        &mut self,
+        download_capture: Option<DownloadCapture>,
+    ) -> &mut Self {
+        self.track_render_only_option("download_capture", download_capture.is_some());
+        self
+    }
+
+    #[cfg(feature = "chrome")]
+    /// Save the files downloaded when a link triggers a download in the browser.
+    pub fn with_download_capture(
+        &mut self,
+        download_capture: Option<DownloadCapture>,
+    ) -> &mut Self {
+        self.download_capture = download_capture.map(Box::new);
+        self
+    }
+
     /// Set the chrome screenshot configuration. This does nothing without the `chrome` flag enabled.
     #[cfg(not(feature = "chrome"))]
-    pub fn with_screenshot(&mut self, _screenshot_config: Option<ScreenShotConfig>) -> &mut Self {
+    pub fn with_screenshot(&mut self, screenshot_config: Option<ScreenShotConfig>) -> &mut Self {
+        self.track_render_only_option("screenshot", screenshot_config.is_some());
         self
     }
 
@@ -1022,12 +3106,54 @@ impl Configuration {
         self
     }
 
+    /// Set the order to send the pages to the subscribers. Use [`OutputOrder::Url`] or [`OutputOrder::Discovery`] for a stable order between runs, the pages are buffered until the crawl completes.
+    /// The buffered pages are sent as the subscribers receive them, receive in a separate task to not block the crawl.
+    pub fn with_output_order(&mut self, output_order: OutputOrder) -> &mut Self {
+        self.output_order = output_order;
+        self
+    }
+
+    /// Set the store of the links visited. Use [`VisitedSet::Bloom`] to bound the memory of very large crawls.
+    pub fn with_visited_set(&mut self, visited_set: VisitedSet) -> &mut Self {
+        self.visited_set = visited_set;
+        self
+    }
+
     /// Block assets from loading from the network
     pub fn with_block_assets(&mut self, only_html: bool) -> &mut Self {
         self.only_html = only_html;
         self
     }
 
+    #[cfg(not(feature = "chrome"))]
+    /// Track a render only option that was set without the `chrome` flag enabled.
+    fn track_render_only_option(&mut self, option: &'static str, set: bool) {
+        self.render_only_options.retain(|o| *o != option);
+        if set {
+            self.render_only_options.push(option);
+        }
+    }
+
+    #[cfg(not(feature = "chrome"))]
+    /// The render only options that were set and will be ignored since the `chrome` flag is not enabled.
+    pub fn render_only_options(&self) -> &[&'static str] {
+        &self.render_only_options
+    }
+
+    #[cfg(feature = "chrome")]
+    /// The render only options that were set and will be ignored since the `chrome` flag is not enabled.
+    pub fn render_only_options(&self) -> &[&'static str] {
+        &[]
+    }
+
+    /// Validate the configuration for the enabled features. Returns an error for the first option that cannot be used in the current build, ex: a chrome only option without the `chrome` flag enabled.
+    pub fn validate(&self) -> Result<(), ConfigurationError> {
+        match self.render_only_options().first() {
+            Some(option) => Err(ConfigurationError::RenderOnlyOption(option)),
+            _ => Ok(()),
+        }
+    }
+
     /// Build the website configuration when using with_builder.
     pub fn build(&self) -> Self {
         self.to_owned()
@@ -0,0 +1,327 @@
+use crate::page::Page;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// The pages and failures counted across a crawl.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrawlStats {
+    /// The pages crawled.
+    pub pages: usize,
+    /// The pages that failed to load or returned a client or server error.
+    pub errors: usize,
+    /// The urls of the pages that failed.
+    pub broken_links: BTreeSet<String>,
+}
+
+impl CrawlStats {
+    /// Count the page of the crawl.
+    pub fn record(&mut self, page: &Page) {
+        self.pages += 1;
+
+        if page.error_status.is_some()
+            || page.status_code.is_client_error()
+            || page.status_code.is_server_error()
+        {
+            self.errors += 1;
+            self.broken_links.insert(page.get_url().to_string());
+        }
+    }
+
+    /// The share of the pages that failed between 0.0 and 1.0.
+    pub fn error_rate(&self) -> f64 {
+        if self.pages == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.pages as f64
+        }
+    }
+
+    /// Write the broken links to the baseline file, one url per line.
+    pub fn save_baseline(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut contents = String::new();
+
+        for link in &self.broken_links {
+            contents.push_str(link);
+            contents.push('\n');
+        }
+
+        std::fs::write(path, contents)
+    }
+}
+
+/// Read the broken links of a baseline file written by [`CrawlStats::save_baseline`].
+pub fn load_baseline(path: &Path) -> std::io::Result<BTreeSet<String>> {
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// An invariant the crawl did not hold.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AssertionFailure {
+    /// The name of the assertion ex: `min_pages`.
+    pub assertion: String,
+    /// The value expected.
+    pub expected: String,
+    /// The value of the crawl.
+    pub actual: String,
+    /// The urls involved, ex: the broken links not in the baseline.
+    pub urls: Vec<String>,
+}
+
+/// The outcome of the assertions of a crawl.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AssertionReport {
+    /// The stats of the crawl checked.
+    pub pages: usize,
+    /// The pages that failed.
+    pub errors: usize,
+    /// The share of the pages that failed.
+    pub error_rate: f64,
+    /// The assertions that failed.
+    pub failures: Vec<AssertionFailure>,
+}
+
+impl AssertionReport {
+    /// Whether every assertion held.
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Invariants checked after a crawl to use the crawl as a CI gate for website deployments, ex: at least 500 pages,
+/// an error rate below 1% and no broken link missing from the baseline file of the previous run.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrawlAssertions {
+    /// The minimum pages crawled.
+    pub min_pages: Option<usize>,
+    /// The maximum pages crawled.
+    pub max_pages: Option<usize>,
+    /// The maximum share of pages failing between 0.0 and 1.0.
+    pub max_error_rate: Option<f64>,
+    /// The maximum pages failing.
+    pub max_errors: Option<usize>,
+    /// The baseline file of the broken links allowed. Any other broken link fails the assertions.
+    pub baseline: Option<PathBuf>,
+}
+
+impl CrawlAssertions {
+    /// No invariant checked until one is set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require at least the pages.
+    pub fn with_min_pages(mut self, min_pages: usize) -> Self {
+        self.min_pages = Some(min_pages);
+        self
+    }
+
+    /// Require at most the pages.
+    pub fn with_max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    /// Require the share of failing pages to stay at or below the rate.
+    pub fn with_max_error_rate(mut self, max_error_rate: f64) -> Self {
+        self.max_error_rate = Some(max_error_rate);
+        self
+    }
+
+    /// Require at most the failing pages.
+    pub fn with_max_errors(mut self, max_errors: usize) -> Self {
+        self.max_errors = Some(max_errors);
+        self
+    }
+
+    /// Fail on the broken links not in the baseline file. A missing baseline file allows no broken link.
+    pub fn with_baseline(mut self, baseline: impl Into<PathBuf>) -> Self {
+        self.baseline = Some(baseline.into());
+        self
+    }
+
+    /// Check the stats of the crawl. The baseline file is read when set.
+    pub fn check(&self, stats: &CrawlStats) -> std::io::Result<AssertionReport> {
+        let baseline = match &self.baseline {
+            Some(path) if path.exists() => Some(load_baseline(path)?),
+            Some(_) => Some(Default::default()),
+            _ => None,
+        };
+
+        Ok(self.check_with_baseline(stats, baseline.as_ref()))
+    }
+
+    /// Check the stats of the crawl against the broken links of the baseline.
+    pub fn check_with_baseline(
+        &self,
+        stats: &CrawlStats,
+        baseline: Option<&BTreeSet<String>>,
+    ) -> AssertionReport {
+        let mut failures = Vec::new();
+        let mut fail = |assertion: &str, expected: String, actual: String, urls: Vec<String>| {
+            failures.push(AssertionFailure {
+                assertion: assertion.into(),
+                expected,
+                actual,
+                urls,
+            })
+        };
+
+        if let Some(min_pages) = self.min_pages {
+            if stats.pages < min_pages {
+                fail(
+                    "min_pages",
+                    format!(">= {min_pages}"),
+                    stats.pages.to_string(),
+                    Vec::new(),
+                );
+            }
+        }
+
+        if let Some(max_pages) = self.max_pages {
+            if stats.pages > max_pages {
+                fail(
+                    "max_pages",
+                    format!("<= {max_pages}"),
+                    stats.pages.to_string(),
+                    Vec::new(),
+                );
+            }
+        }
+
+        if let Some(max_error_rate) = self.max_error_rate {
+            if stats.error_rate() > max_error_rate {
+                fail(
+                    "max_error_rate",
+                    format!("<= {max_error_rate}"),
+                    stats.error_rate().to_string(),
+                    stats.broken_links.iter().cloned().collect(),
+                );
+            }
+        }
+
+        if let Some(max_errors) = self.max_errors {
+            if stats.errors > max_errors {
+                fail(
+                    "max_errors",
+                    format!("<= {max_errors}"),
+                    stats.errors.to_string(),
+                    stats.broken_links.iter().cloned().collect(),
+                );
+            }
+        }
+
+        if let Some(baseline) = baseline {
+            let new_broken_links: Vec<String> =
+                stats.broken_links.difference(baseline).cloned().collect();
+
+            if !new_broken_links.is_empty() {
+                fail(
+                    "no_new_broken_links",
+                    "0".into(),
+                    new_broken_links.len().to_string(),
+                    new_broken_links,
+                );
+            }
+        }
+
+        AssertionReport {
+            pages: stats.pages,
+            errors: stats.errors,
+            error_rate: stats.error_rate(),
+            failures,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page::build;
+    use crate::utils::PageResponse;
+    use reqwest::StatusCode;
+
+    fn page(url: &str, status_code: StatusCode) -> Page {
+        build(
+            url,
+            PageResponse {
+                status_code,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn crawl_assertions() {
+        let mut stats = CrawlStats::default();
+
+        for i in 0..9 {
+            stats.record(&page(&format!("https://example.com/{i}"), StatusCode::OK));
+        }
+        stats.record(&page("https://example.com/gone", StatusCode::NOT_FOUND));
+
+        assert_eq!(stats.pages, 10);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.error_rate(), 0.1);
+
+        let assertions = CrawlAssertions::new()
+            .with_min_pages(10)
+            .with_max_error_rate(0.2);
+        assert!(assertions.check(&stats).unwrap().passed());
+
+        let report = assertions
+            .clone()
+            .with_min_pages(500)
+            .with_max_error_rate(0.01)
+            .check(&stats)
+            .unwrap();
+        let failed: Vec<&str> = report
+            .failures
+            .iter()
+            .map(|f| f.assertion.as_str())
+            .collect();
+        assert_eq!(failed, ["min_pages", "max_error_rate"]);
+        assert_eq!(report.failures[1].urls, ["https://example.com/gone"]);
+
+        let mut baseline = BTreeSet::new();
+        let report = assertions.check_with_baseline(&stats, Some(&baseline));
+        assert_eq!(report.failures[0].assertion, "no_new_broken_links");
+
+        baseline.insert("https://example.com/gone".to_string());
+        assert!(assertions
+            .check_with_baseline(&stats, Some(&baseline))
+            .passed());
+    }
+
+    #[test]
+    fn baseline_roundtrip() {
+        let mut stats = CrawlStats::default();
+        stats.record(&page("https://example.com/gone", StatusCode::GONE));
+
+        let path = std::env::temp_dir()
+            .join(format!("spider-baseline-{}", std::process::id()))
+            .join("broken.txt");
+
+        stats.save_baseline(&path).unwrap();
+        assert_eq!(load_baseline(&path).unwrap(), stats.broken_links);
+
+        let report = CrawlAssertions::new()
+            .with_baseline(&path)
+            .check(&stats)
+            .unwrap();
+        assert!(report.passed());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,358 @@
+use crate::configuration::{BrowserPoolConfig, Configuration};
+use crate::features::chrome::{close_browser, launch_browser, BrowserShard};
+use crate::utils::log;
+use chromiumoxide::cdp::browser_protocol::system_info::GetProcessInfoParams;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use url::Url;
+
+/// The time a browser has to answer a health check before it is recycled.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A browser of the pool.
+struct PooledBrowser {
+    /// The browser and its context.
+    shard: BrowserShard,
+    /// The task polling the browser handler.
+    handle: std::sync::Mutex<Option<JoinHandle<()>>>,
+    /// When the browser was launched.
+    launched: Instant,
+    /// The pages open in the browser.
+    active: AtomicUsize,
+    /// The browser takes no more pages and is closed once its pages are done.
+    retired: AtomicBool,
+}
+
+impl PooledBrowser {
+    /// Stop handing out the browser.
+    fn retire(&self, reason: &str) {
+        if !self.retired.swap(true, Ordering::Relaxed) {
+            log("Recycling pooled browser: ", reason);
+        }
+    }
+
+    /// Whether the browser takes new pages.
+    fn is_retired(&self) -> bool {
+        self.retired.load(Ordering::Relaxed)
+    }
+
+    /// The pages open in the browser.
+    fn active(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Whether the connection to the browser dropped.
+    fn disconnected(&self) -> bool {
+        match self.handle.lock() {
+            Ok(handle) => handle.as_ref().is_some_and(|handle| handle.is_finished()),
+            _ => false,
+        }
+    }
+
+    /// Whether the browser answers CDP commands in time.
+    async fn responsive(&self) -> bool {
+        !self.disconnected()
+            && matches!(
+                tokio::time::timeout(HEALTH_CHECK_TIMEOUT, self.shard.0.version()).await,
+                Ok(Ok(_))
+            )
+    }
+
+    /// The resident memory in bytes of the browser and its renderers. Only Linux reports the memory.
+    async fn memory_usage(&self) -> Option<u64> {
+        let info = tokio::time::timeout(
+            HEALTH_CHECK_TIMEOUT,
+            self.shard.0.execute(GetProcessInfoParams::default()),
+        )
+        .await
+        .ok()?
+        .ok()?;
+
+        let rss = info
+            .result
+            .process_info
+            .iter()
+            .filter_map(|process| process_rss(process.id))
+            .sum::<u64>();
+
+        if rss > 0 {
+            Some(rss)
+        } else {
+            None
+        }
+    }
+
+    /// Dispose of the context and stop polling the browser. The process exits once the last lease is dropped.
+    async fn close(&self) {
+        let handle = match self.handle.lock() {
+            Ok(mut handle) => handle.take(),
+            _ => None,
+        };
+
+        if let Some(handle) = handle {
+            let mut context_id = self.shard.1.clone();
+            close_browser(handle, &self.shard.0, &mut context_id).await;
+        }
+    }
+}
+
+/// The resident memory in bytes of the process read from `/proc`.
+#[cfg(target_os = "linux")]
+fn process_rss(pid: i64) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| {
+            value
+                .trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse::<u64>()
+                .ok()
+        })
+        .map(|kb| kb * 1024)
+}
+
+/// The resident memory in bytes of the process, not reported on this platform.
+#[cfg(not(target_os = "linux"))]
+fn process_rss(_pid: i64) -> Option<u64> {
+    None
+}
+
+/// The state of the pool shared with the leases and the health checks.
+struct PoolInner {
+    /// The settings of the pool.
+    config: BrowserPoolConfig,
+    /// The configuration the browsers are launched with.
+    configuration: Configuration,
+    /// The url the browser contexts set the cookies for.
+    url_parsed: Option<Box<Url>>,
+    /// The browsers of the pool.
+    browsers: Mutex<Vec<Arc<PooledBrowser>>>,
+}
+
+impl PoolInner {
+    /// Launch a browser for the pool.
+    async fn launch(&self) -> Option<Arc<PooledBrowser>> {
+        match launch_browser(&self.configuration, &self.url_parsed).await {
+            Some((browser, handle, context_id)) => Some(Arc::new(PooledBrowser {
+                shard: (Arc::new(browser), context_id),
+                handle: std::sync::Mutex::new(Some(handle)),
+                launched: Instant::now(),
+                active: AtomicUsize::new(0),
+                retired: AtomicBool::new(false),
+            })),
+            _ => {
+                log("", "Pooled chrome failed to start.");
+                None
+            }
+        }
+    }
+
+    /// Close the retired browsers without pages and launch browsers up to the minimum size.
+    async fn maintain(&self, browsers: &mut Vec<Arc<PooledBrowser>>) {
+        for browser in browsers.iter().filter(|b| b.disconnected()) {
+            browser.retire("disconnected");
+        }
+
+        let mut index = 0;
+
+        while index < browsers.len() {
+            if browsers[index].is_retired() && browsers[index].active() == 0 {
+                browsers.swap_remove(index).close().await;
+            } else {
+                index += 1;
+            }
+        }
+
+        while browsers.iter().filter(|b| !b.is_retired()).count() < self.config.min_size {
+            match self.launch().await {
+                Some(browser) => browsers.push(browser),
+                _ => break,
+            }
+        }
+    }
+
+    /// Retire the browsers past their lifetime, over the memory ceiling or not answering CDP.
+    async fn health_check(&self) {
+        let browsers: Vec<_> = self.browsers.lock().await.clone();
+
+        for browser in browsers.iter().filter(|b| !b.is_retired()) {
+            if let Some(max_lifetime) = self.config.max_lifetime {
+                if browser.launched.elapsed() >= max_lifetime {
+                    browser.retire("max lifetime reached");
+                    continue;
+                }
+            }
+
+            if !browser.responsive().await {
+                browser.retire("not responding to CDP");
+                continue;
+            }
+
+            if let Some(memory_ceiling) = self.config.memory_ceiling {
+                if let Some(usage) = browser.memory_usage().await {
+                    if usage > memory_ceiling {
+                        browser.retire("memory ceiling reached");
+                    }
+                }
+            }
+        }
+
+        let mut browsers = self.browsers.lock().await;
+        self.maintain(&mut browsers).await;
+    }
+}
+
+/// A pool of browsers the chrome crawl spreads its pages across. The pool grows up to the maximum size when the
+/// browsers are full, and recycles the browsers that reach their lifetime, go over the memory ceiling or stop answering
+/// CDP. A recycled browser takes no new pages and is closed once its open pages are done.
+pub struct BrowserPool {
+    /// The state shared with the leases and the health checks.
+    inner: Arc<PoolInner>,
+    /// The task running the health checks.
+    health_task: Option<JoinHandle<()>>,
+}
+
+impl BrowserPool {
+    /// Launch the minimum browsers of the pool and start the health checks.
+    pub async fn launch(
+        config: &BrowserPoolConfig,
+        configuration: &Configuration,
+        url_parsed: &Option<Box<Url>>,
+    ) -> Self {
+        let mut config = config.clone();
+
+        config.max_size = config.max_size.max(config.min_size).max(1);
+        config.max_pages_per_browser = config.max_pages_per_browser.max(1);
+
+        let inner = Arc::new(PoolInner {
+            config,
+            configuration: configuration.clone(),
+            url_parsed: url_parsed.clone(),
+            browsers: Mutex::new(Vec::new()),
+        });
+
+        {
+            let mut browsers = inner.browsers.lock().await;
+            inner.maintain(&mut browsers).await;
+        }
+
+        let health_task = if inner.config.health_check_interval.is_zero() {
+            None
+        } else {
+            Some(tokio::spawn(health_checks(
+                Arc::downgrade(&inner),
+                inner.config.health_check_interval,
+            )))
+        };
+
+        Self { inner, health_task }
+    }
+
+    /// Hand out the least loaded browser, launching a new browser when every browser is full and the pool is below
+    /// its maximum size. `None` is returned when no browser could be launched.
+    pub async fn acquire(&self) -> Option<BrowserLease> {
+        let config = &self.inner.config;
+        let mut browsers = self.inner.browsers.lock().await;
+
+        self.inner.maintain(&mut browsers).await;
+
+        let least_loaded = browsers
+            .iter()
+            .filter(|b| !b.is_retired())
+            .min_by_key(|b| b.active())
+            .cloned();
+
+        let open = browsers.iter().filter(|b| !b.is_retired()).count();
+
+        let browser = match least_loaded {
+            Some(browser) if browser.active() < config.max_pages_per_browser => Some(browser),
+            least_loaded if open < config.max_size => match self.inner.launch().await {
+                Some(browser) => {
+                    browsers.push(browser.clone());
+                    Some(browser)
+                }
+                _ => least_loaded,
+            },
+            least_loaded => least_loaded,
+        }?;
+
+        browser.active.fetch_add(1, Ordering::Relaxed);
+
+        Some(BrowserLease { browser })
+    }
+
+    /// The browsers in the pool, including the retired browsers waiting for their pages.
+    pub async fn size(&self) -> usize {
+        self.inner.browsers.lock().await.len()
+    }
+
+    /// Stop the health checks and close every browser.
+    pub async fn shutdown(&self) {
+        if let Some(health_task) = &self.health_task {
+            health_task.abort();
+        }
+
+        let browsers: Vec<_> = self.inner.browsers.lock().await.drain(..).collect();
+
+        for browser in browsers {
+            browser.close().await;
+        }
+    }
+}
+
+impl Drop for BrowserPool {
+    fn drop(&mut self) {
+        if let Some(health_task) = &self.health_task {
+            health_task.abort();
+        }
+    }
+}
+
+/// Run the health checks of the pool until it is dropped.
+async fn health_checks(inner: Weak<PoolInner>, interval: Duration) {
+    let mut interval = tokio::time::interval(interval);
+
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+
+        match inner.upgrade() {
+            Some(inner) => inner.health_check().await,
+            _ => break,
+        }
+    }
+}
+
+/// A browser handed out by the pool for a page. The page counts against the browser until the lease is dropped.
+pub struct BrowserLease {
+    /// The browser leased.
+    browser: Arc<PooledBrowser>,
+}
+
+impl BrowserLease {
+    /// The browser and its context to open the page in.
+    pub fn shard(&self) -> &BrowserShard {
+        &self.browser.shard
+    }
+
+    /// Report the page failed to open. The browser is recycled when it no longer answers CDP.
+    pub async fn mark_failed(&self) {
+        if !self.browser.is_retired() && !self.browser.responsive().await {
+            self.browser.retire("not responding to CDP");
+        }
+    }
+}
+
+impl Drop for BrowserLease {
+    fn drop(&mut self) {
+        self.browser.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,274 @@
+use crate::utils::log;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A captcha widget found on a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptchaKind {
+    /// The Google reCAPTCHA v2 checkbox or invisible widget.
+    RecaptchaV2,
+    /// The hCaptcha widget.
+    HCaptcha,
+    /// The Cloudflare Turnstile widget.
+    Turnstile,
+}
+
+/// A captcha to solve, sent to the [`ChallengeSolver`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptchaChallenge {
+    /// The widget found.
+    pub kind: CaptchaKind,
+    /// The public site key of the widget.
+    pub sitekey: String,
+    /// The url of the page showing the widget.
+    pub page_url: String,
+    /// The action of the widget when set, used by Turnstile.
+    pub action: Option<String>,
+}
+
+/// Solve the captchas found on the pages rendered with Chrome. The token returned is written to the response field of the
+/// widget and given to its callback.
+#[async_trait::async_trait]
+pub trait ChallengeSolver: Send + Sync {
+    /// The token of the solved captcha, `None` when the captcha is not solved.
+    async fn solve(&self, challenge: &CaptchaChallenge) -> Option<String>;
+}
+
+/// A solver leaving the captchas unsolved.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopSolver;
+
+#[async_trait::async_trait]
+impl ChallengeSolver for NoopSolver {
+    async fn solve(&self, _challenge: &CaptchaChallenge) -> Option<String> {
+        None
+    }
+}
+
+/// The answer of the solving service.
+#[derive(Debug, serde::Deserialize)]
+struct HttpSolverResponse {
+    /// The token of the solved captcha.
+    token: Option<String>,
+}
+
+/// A solver posting the [`CaptchaChallenge`] as JSON to a solving service answering with `{"token": "..."}`. Wrap the API of a
+/// commercial solver with a small service or implement [`ChallengeSolver`] for it.
+#[derive(Debug, Clone)]
+pub struct HttpSolver {
+    /// The url of the service.
+    pub endpoint: String,
+    /// The key sent as a bearer token.
+    pub api_key: Option<String>,
+    /// The max time to wait for the token. Defaults to 120s.
+    pub timeout: Duration,
+    /// The client of the requests.
+    client: reqwest::Client,
+}
+
+impl HttpSolver {
+    /// Solve the captchas with the service at the endpoint.
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            api_key: None,
+            timeout: Duration::from_secs(120),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Send the key as a bearer token.
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Set the max time to wait for the token.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl ChallengeSolver for HttpSolver {
+    async fn solve(&self, challenge: &CaptchaChallenge) -> Option<String> {
+        let body = serde_json::to_vec(challenge).ok()?;
+        let mut request = self
+            .client
+            .post(&self.endpoint)
+            .timeout(self.timeout)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body);
+
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                let body = response.bytes().await.ok()?;
+                serde_json::from_slice::<HttpSolverResponse>(&body)
+                    .ok()?
+                    .token
+                    .filter(|token| !token.is_empty())
+            }
+            Ok(response) => {
+                log("Captcha solver failed: ", response.status().as_str());
+                None
+            }
+            Err(e) => {
+                log("Captcha solver failed: ", e.to_string());
+                None
+            }
+        }
+    }
+}
+
+/// The solver of the crawl.
+#[derive(Clone)]
+pub struct CaptchaSolver(Arc<dyn ChallengeSolver>);
+
+impl CaptchaSolver {
+    /// Solve the captchas with the solver.
+    pub fn new(solver: impl ChallengeSolver + 'static) -> Self {
+        Self(Arc::new(solver))
+    }
+
+    /// The token of the solved captcha.
+    pub async fn solve(&self, challenge: &CaptchaChallenge) -> Option<String> {
+        self.0.solve(challenge).await
+    }
+}
+
+impl Default for CaptchaSolver {
+    fn default() -> Self {
+        Self::new(NoopSolver)
+    }
+}
+
+impl std::fmt::Debug for CaptchaSolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CaptchaSolver").finish()
+    }
+}
+
+impl PartialEq for CaptchaSolver {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// The first captcha widget of the page as `{kind, sitekey, action}` from the widget containers or the widget iframes.
+pub(crate) static CAPTCHA_DETECT_JS: &'static str = r#"(()=>{const param=(src,name)=>{try{const url=new URL(src,location.href);return url.searchParams.get(name)||new URLSearchParams(url.hash.slice(1)).get(name)}catch(_){return null}},widget=(kind,selector,frame,key)=>{const el=document.querySelector(selector);if(el&&el.dataset.sitekey)return{kind,sitekey:el.dataset.sitekey,action:el.dataset.action||null};const iframe=document.querySelector(frame),sitekey=iframe&&param(iframe.src,key);return sitekey?{kind,sitekey,action:null}:null};return widget("turnstile",".cf-turnstile[data-sitekey]","iframe[src*='challenges.cloudflare.com']","k")||widget("h_captcha",".h-captcha[data-sitekey]","iframe[src*='hcaptcha.com']","sitekey")||widget("recaptcha_v2",".g-recaptcha[data-sitekey]","iframe[src*='/recaptcha/api2/anchor'], iframe[src*='/recaptcha/enterprise/anchor']","k")})()"#;
+
+/// Write the token to the response fields of the widget and call the callback of the widget with it.
+fn captcha_inject_js(kind: CaptchaKind, token: &str) -> String {
+    let (field, container) = match kind {
+        CaptchaKind::RecaptchaV2 => ("g-recaptcha-response", ".g-recaptcha"),
+        CaptchaKind::HCaptcha => ("h-captcha-response", ".h-captcha"),
+        CaptchaKind::Turnstile => ("cf-turnstile-response", ".cf-turnstile"),
+    };
+    let token = serde_json::to_string(token).unwrap_or_default();
+
+    format!(
+        r#"(()=>{{const token={token};let set=0;document.querySelectorAll("textarea[name='{field}'], input[name='{field}'], textarea[name='g-recaptcha-response']").forEach(el=>{{el.value=token;el.innerHTML=token;set++}});const el=document.querySelector("{container}[data-callback]"),callback=el&&el.dataset.callback.split(".").reduce((target,key)=>target&&target[key],window);if(typeof callback==="function"){{callback(token);set++}}return set}})()"#
+    )
+}
+
+/// Solve the first captcha widget of the page and inject the token. Returns the widget found and whether its token was
+/// injected.
+pub async fn solve_page_captcha(
+    page: &chromiumoxide::Page,
+    solver: &CaptchaSolver,
+    page_url: &str,
+) -> Option<(CaptchaKind, bool)> {
+    #[derive(serde::Deserialize)]
+    struct Widget {
+        kind: CaptchaKind,
+        sitekey: String,
+        action: Option<String>,
+    }
+
+    let widget = page
+        .evaluate(CAPTCHA_DETECT_JS)
+        .await
+        .ok()?
+        .into_value::<Option<Widget>>()
+        .ok()
+        .flatten()?;
+
+    let challenge = CaptchaChallenge {
+        kind: widget.kind,
+        sitekey: widget.sitekey,
+        page_url: page
+            .url()
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| page_url.into()),
+        action: widget.action,
+    };
+
+    let injected = match solver.solve(&challenge).await {
+        Some(token) => match page
+            .evaluate(captcha_inject_js(challenge.kind, &token))
+            .await
+        {
+            Ok(result) => result.into_value::<u64>().unwrap_or_default() > 0,
+            _ => false,
+        },
+        _ => false,
+    };
+
+    log(
+        "Captcha found on the page: ",
+        format!("{:?} injected: {}", challenge.kind, injected),
+    );
+
+    Some((challenge.kind, injected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captcha_challenge_json() {
+        let challenge = CaptchaChallenge {
+            kind: CaptchaKind::HCaptcha,
+            sitekey: "10000000-ffff-ffff-ffff-000000000001".into(),
+            page_url: "https://example.com/login".into(),
+            action: None,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&challenge).unwrap(),
+            serde_json::json!({
+                "kind": "h_captcha",
+                "sitekey": "10000000-ffff-ffff-ffff-000000000001",
+                "pageUrl": "https://example.com/login",
+                "action": null
+            })
+        );
+        assert!(
+            captcha_inject_js(CaptchaKind::Turnstile, "a\"b").contains(r#"const token="a\"b";"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn noop_solver() {
+        let solver = CaptchaSolver::default();
+        let challenge = CaptchaChallenge {
+            kind: CaptchaKind::Turnstile,
+            sitekey: "1x00000000000000000000AA".into(),
+            page_url: "https://example.com/".into(),
+            action: Some("login".into()),
+        };
+
+        assert_eq!(solver.solve(&challenge).await, None);
+        assert_eq!(solver, solver.clone());
+    }
+}
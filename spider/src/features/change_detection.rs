@@ -0,0 +1,296 @@
+use crate::features::snapshot::{SnapshotError, SNAPSHOT_VERSION};
+use crate::page::Page;
+use crate::utils::fnv1a;
+use std::collections::BTreeMap;
+
+/// The change of a page compared to the previous crawl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ChangeStatus {
+    /// The page was not found in the previous crawl.
+    New,
+    /// The normalized content of the page changed.
+    Changed,
+    /// The normalized content of the page is the same.
+    Unchanged,
+}
+
+/// The change of a crawled page.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ChangeReport {
+    /// The url of the page.
+    pub url: String,
+    /// The change of the page.
+    pub status: ChangeStatus,
+    /// The unified diff of the normalized content from the previous crawl when the page changed and diffs are enabled.
+    pub diff: Option<String>,
+}
+
+/// The normalized content of a page in a content snapshot.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ContentRecord {
+    /// The FNV-1a hash of the normalized content.
+    pub hash: u64,
+    /// The normalized content, kept only when diffs are enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// The normalized content hashes of the pages of a crawl, compared to the next crawl.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ContentSnapshot {
+    /// The snapshot format version.
+    pub version: u32,
+    /// The normalized content of the pages keyed by url.
+    #[serde(default)]
+    pub pages: BTreeMap<String, ContentRecord>,
+}
+
+impl ContentSnapshot {
+    /// A new empty content snapshot.
+    pub fn new() -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            ..Default::default()
+        }
+    }
+
+    /// Serialize the snapshot to json.
+    pub fn to_json(&self) -> Result<String, SnapshotError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Deserialize a snapshot from json. Snapshots written with a newer format version are rejected.
+    pub fn from_json(json: &str) -> Result<Self, SnapshotError> {
+        let snapshot: ContentSnapshot = serde_json::from_str(json)?;
+
+        if snapshot.version > SNAPSHOT_VERSION {
+            Err(SnapshotError::UnsupportedVersion(snapshot.version))
+        } else {
+            Ok(snapshot)
+        }
+    }
+}
+
+/// How the content of the pages is normalized before it is compared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChangeDetection {
+    /// The CSS selectors of the elements removed before the comparison ex: `time`, `.timestamp` or `#csrf`.
+    pub strip_selectors: Vec<String>,
+    /// The attributes removed from every element before the comparison ex: `nonce`.
+    pub strip_attributes: Vec<String>,
+    /// Keep the normalized content of the pages to report a unified diff of the changed pages.
+    pub diff: bool,
+}
+
+impl Default for ChangeDetection {
+    fn default() -> Self {
+        Self {
+            strip_selectors: vec!["script".into(), "noscript".into()],
+            strip_attributes: vec!["nonce".into(), "integrity".into()],
+            diff: false,
+        }
+    }
+}
+
+impl ChangeDetection {
+    /// Compare the pages without the scripts and the `nonce` and `integrity` attributes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the CSS selectors of the elements removed before the comparison. Invalid selectors are ignored.
+    pub fn with_strip_selectors(mut self, strip_selectors: Vec<String>) -> Self {
+        self.strip_selectors = strip_selectors;
+        self
+    }
+
+    /// Set the attributes removed from every element before the comparison.
+    pub fn with_strip_attributes(mut self, strip_attributes: Vec<String>) -> Self {
+        self.strip_attributes = strip_attributes;
+        self
+    }
+
+    /// Report a unified diff of the normalized content of the changed pages.
+    pub fn with_diff(mut self, diff: bool) -> Self {
+        self.diff = diff;
+        self
+    }
+
+    /// The content of the html without the stripped elements and attributes, one trimmed line per non empty line.
+    pub fn normalize(&self, html: &str) -> String {
+        let mut element_content_handlers = Vec::new();
+
+        for selector in &self.strip_selectors {
+            if selector.parse::<lol_html::Selector>().is_ok() {
+                element_content_handlers.push(lol_html::element!(selector, |el| {
+                    el.remove();
+                    Ok(())
+                }));
+            }
+        }
+
+        if !self.strip_attributes.is_empty() {
+            element_content_handlers.push(lol_html::element!("*", |el| {
+                for attribute in &self.strip_attributes {
+                    el.remove_attribute(attribute);
+                }
+                Ok(())
+            }));
+        }
+
+        let stripped = if element_content_handlers.is_empty() {
+            html.to_string()
+        } else {
+            lol_html::rewrite_str(
+                html,
+                lol_html::RewriteStrSettings {
+                    element_content_handlers,
+                    ..lol_html::RewriteStrSettings::new()
+                },
+            )
+            .unwrap_or_else(|_| html.to_string())
+        };
+
+        let mut normalized = String::with_capacity(stripped.len());
+
+        for line in stripped.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            normalized.push_str(line);
+            normalized.push('\n');
+        }
+
+        normalized
+    }
+}
+
+/// Report the pages of a crawl that are new, changed or unchanged compared to the content snapshot of the previous
+/// crawl. Record the pages from a subscription and keep [`ChangeDetector::snapshot`] for the next crawl.
+#[derive(Debug, Default, Clone)]
+pub struct ChangeDetector {
+    /// The normalization of the content.
+    config: ChangeDetection,
+    /// The content of the previous crawl.
+    previous: ContentSnapshot,
+    /// The content of this crawl.
+    current: ContentSnapshot,
+}
+
+impl ChangeDetector {
+    /// Compare the pages to the content snapshot of the previous crawl.
+    pub fn new(config: ChangeDetection, previous: ContentSnapshot) -> Self {
+        Self {
+            config,
+            previous,
+            current: ContentSnapshot::new(),
+        }
+    }
+
+    /// Record the page and report its change since the previous crawl.
+    pub fn detect(&mut self, page: &Page) -> ChangeReport {
+        let url = page.get_url().to_string();
+        let text = self.config.normalize(&page.get_html());
+        let hash = fnv1a(text.as_bytes());
+
+        let (status, diff) = match self.previous.pages.get(&url) {
+            Some(previous) if previous.hash == hash => (ChangeStatus::Unchanged, None),
+            Some(previous) => (
+                ChangeStatus::Changed,
+                match &previous.text {
+                    Some(previous_text) if self.config.diff => Some(
+                        similar::TextDiff::from_lines(previous_text.as_str(), text.as_str())
+                            .unified_diff()
+                            .header(&url, &url)
+                            .to_string(),
+                    ),
+                    _ => None,
+                },
+            ),
+            _ => (ChangeStatus::New, None),
+        };
+
+        self.current.pages.insert(
+            url.clone(),
+            ContentRecord {
+                hash,
+                text: if self.config.diff { Some(text) } else { None },
+            },
+        );
+
+        ChangeReport { url, status, diff }
+    }
+
+    /// The urls of the previous crawl not recorded in this crawl.
+    pub fn removed(&self) -> Vec<String> {
+        self.previous
+            .pages
+            .keys()
+            .filter(|url| !self.current.pages.contains_key(*url))
+            .cloned()
+            .collect()
+    }
+
+    /// The content snapshot of this crawl to compare the next crawl to.
+    pub fn snapshot(&self) -> &ContentSnapshot {
+        &self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page::build;
+    use crate::utils::PageResponse;
+
+    fn page(url: &str, html: &str) -> Page {
+        build(
+            url,
+            PageResponse {
+                content: Some(Box::new(html.to_string().into())),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn normalize_content() {
+        let config = ChangeDetection::new().with_strip_selectors(vec![".ts".into(), "[".into()]);
+        let html = "<p>hello</p>\n   <span class=\"ts\">12:00</span>\n<script nonce=\"a\">x</script><div nonce=\"b\">  world </div>";
+
+        assert_eq!(
+            config.normalize(html),
+            "<p>hello</p>\n<script>x</script><div>  world </div>\n"
+        );
+    }
+
+    #[test]
+    fn detect_changes() {
+        let config = ChangeDetection::new()
+            .with_strip_selectors(vec!["time".into()])
+            .with_diff(true);
+        let mut first = ChangeDetector::new(config.clone(), ContentSnapshot::new());
+
+        assert_eq!(
+            first
+                .detect(&page("https://example.com", "<p>a</p>\n<time>1</time>"))
+                .status,
+            ChangeStatus::New
+        );
+        first.detect(&page("https://example.com/b", "<p>b</p>"));
+        first.detect(&page("https://example.com/c", "<p>c</p>"));
+
+        let previous = ContentSnapshot::from_json(&first.snapshot().to_json().unwrap()).unwrap();
+        let mut second = ChangeDetector::new(config, previous);
+
+        let report = second.detect(&page("https://example.com", "<p>a</p>\n<time>2</time>"));
+        assert_eq!(report.status, ChangeStatus::Unchanged);
+        assert_eq!(report.diff, None);
+
+        let report = second.detect(&page("https://example.com/b", "<p>b2</p>"));
+        assert_eq!(report.status, ChangeStatus::Changed);
+        let diff = report.diff.unwrap();
+        assert!(diff.contains("-<p>b</p>"));
+        assert!(diff.contains("+<p>b2</p>"));
+
+        assert_eq!(second.removed(), ["https://example.com/c"]);
+    }
+}
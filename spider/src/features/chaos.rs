@@ -0,0 +1,274 @@
+use crate::page::{build, Page};
+use crate::utils::PageResponse;
+use reqwest::StatusCode;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long a timeout holds the request when the crawl has no request timeout.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The faults injected since the crawl started.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InjectedFaults {
+    /// The requests failed with a timeout.
+    pub timeouts: usize,
+    /// The requests failed with a `503 Service Unavailable`.
+    pub server_errors: usize,
+    /// The responses slowed down.
+    pub slow_bodies: usize,
+}
+
+/// The counts of the faults injected.
+#[derive(Debug, Default)]
+pub(crate) struct ChaosCounts {
+    timeouts: AtomicUsize,
+    server_errors: AtomicUsize,
+    slow_bodies: AtomicUsize,
+}
+
+/// Inject artificial failures in the crawl at configurable rates to check the retry, rate limit and alerting
+/// configuration before relying on it in production. The faults apply to the first attempt of each page, the retries
+/// reach the site. Do not enable this for production crawls.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chaos {
+    /// The share of the requests held for the request timeout and failed like a timed out request between 0.0 and 1.0.
+    pub timeout_rate: f64,
+    /// The share of the requests failed with a `503 Service Unavailable` between 0.0 and 1.0.
+    pub server_error_rate: f64,
+    /// The share of the responses slowed down by the slow body delay between 0.0 and 1.0.
+    pub slow_body_rate: f64,
+    /// The time the slow responses take at least, counted in the request timeout.
+    pub slow_body_delay: Duration,
+    /// The share of the CDP events dropped before they reach the page between 0.0 and 1.0. This does nothing without the `chrome` flag.
+    pub dropped_event_rate: f64,
+    /// The faults injected.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) counts: Arc<ChaosCounts>,
+}
+
+impl Default for Chaos {
+    fn default() -> Self {
+        Self {
+            timeout_rate: 0.0,
+            server_error_rate: 0.0,
+            slow_body_rate: 0.0,
+            slow_body_delay: Duration::from_secs(5),
+            dropped_event_rate: 0.0,
+            counts: Default::default(),
+        }
+    }
+}
+
+impl PartialEq for Chaos {
+    fn eq(&self, other: &Self) -> bool {
+        self.timeout_rate == other.timeout_rate
+            && self.server_error_rate == other.server_error_rate
+            && self.slow_body_rate == other.slow_body_rate
+            && self.slow_body_delay == other.slow_body_delay
+            && self.dropped_event_rate == other.dropped_event_rate
+    }
+}
+
+/// Whether the fault happens at the rate.
+fn roll(rate: f64) -> bool {
+    rate > 0.0 && fastrand::f64() < rate
+}
+
+impl Chaos {
+    /// No fault injected until a rate is set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold the share of the requests for the request timeout and fail them like a timed out request.
+    pub fn with_timeout_rate(mut self, rate: f64) -> Self {
+        self.timeout_rate = rate;
+        self
+    }
+
+    /// Fail the share of the requests with a `503 Service Unavailable`.
+    pub fn with_server_error_rate(mut self, rate: f64) -> Self {
+        self.server_error_rate = rate;
+        self
+    }
+
+    /// Slow down the share of the responses to take at least the delay, timing out past the request timeout.
+    pub fn with_slow_bodies(mut self, rate: f64, delay: Duration) -> Self {
+        self.slow_body_rate = rate;
+        self.slow_body_delay = delay;
+        self
+    }
+
+    /// Drop the share of the CDP events before they reach the page.
+    pub fn with_dropped_event_rate(mut self, rate: f64) -> Self {
+        self.dropped_event_rate = rate;
+        self
+    }
+
+    /// A page failed with the status.
+    fn failed_page(url: &str, status_code: StatusCode, error: &str) -> Page {
+        let mut page = build(
+            url,
+            PageResponse {
+                status_code,
+                ..Default::default()
+            },
+        );
+        page.error_status = Some(error.into());
+        page
+    }
+
+    /// A page failed like a request timing out in the HTTP client.
+    fn timed_out_page(url: &str) -> Page {
+        Self::failed_page(
+            url,
+            StatusCode::from_u16(599).unwrap_or(StatusCode::REQUEST_TIMEOUT),
+            "chaos: operation timed out",
+        )
+    }
+
+    /// Fetch the page with a fault injected at the configured rates. A timeout holds the request for the request
+    /// timeout and fails without reaching the site, a server error fails right away and a slow body makes the
+    /// response take at least the delay, failing like a timeout when the delay is past the request timeout.
+    pub(crate) async fn fetch<F>(
+        &self,
+        url: &str,
+        request_timeout: &Option<Box<Duration>>,
+        fetch: F,
+    ) -> Page
+    where
+        F: Future<Output = Page>,
+    {
+        let request_timeout = request_timeout.as_deref().copied();
+
+        if roll(self.timeout_rate) {
+            self.counts.timeouts.fetch_add(1, Ordering::Relaxed);
+            log::info!("chaos: timeout {}", url);
+            tokio::time::sleep(request_timeout.unwrap_or(DEFAULT_TIMEOUT)).await;
+            Self::timed_out_page(url)
+        } else if roll(self.server_error_rate) {
+            self.counts.server_errors.fetch_add(1, Ordering::Relaxed);
+            log::info!("chaos: server error {}", url);
+            Self::failed_page(
+                url,
+                StatusCode::SERVICE_UNAVAILABLE,
+                "chaos: service unavailable",
+            )
+        } else if roll(self.slow_body_rate) {
+            self.counts.slow_bodies.fetch_add(1, Ordering::Relaxed);
+            log::info!("chaos: slow body {}", url);
+            let slow_body = async {
+                let (page, _) = tokio::join!(fetch, tokio::time::sleep(self.slow_body_delay));
+                page
+            };
+
+            match request_timeout {
+                Some(request_timeout) => tokio::time::timeout(request_timeout, slow_body)
+                    .await
+                    .unwrap_or_else(|_| Self::timed_out_page(url)),
+                _ => slow_body.await,
+            }
+        } else {
+            fetch.await
+        }
+    }
+
+    /// The faults injected since the crawl started.
+    pub fn injected(&self) -> InjectedFaults {
+        InjectedFaults {
+            timeouts: self.counts.timeouts.load(Ordering::Relaxed),
+            server_errors: self.counts.server_errors.load(Ordering::Relaxed),
+            slow_bodies: self.counts.slow_bodies.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Reset the counts for a new crawl.
+    pub(crate) fn reset(&self) {
+        self.counts.timeouts.store(0, Ordering::Relaxed);
+        self.counts.server_errors.store(0, Ordering::Relaxed);
+        self.counts.slow_bodies.store(0, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ok_page() -> Page {
+        build(
+            "https://example.com",
+            PageResponse {
+                status_code: StatusCode::OK,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn injects_faults_at_rate() {
+        let chaos = Chaos::new().with_server_error_rate(1.0);
+        let page = chaos
+            .fetch("https://example.com", &None, async {
+                panic!("the failed request reached the site")
+            })
+            .await;
+
+        assert_eq!(page.status_code, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(page.should_retry);
+        assert_eq!(page.get_url(), "https://example.com");
+
+        let chaos = chaos.with_server_error_rate(0.0);
+        let page = chaos
+            .fetch("https://example.com", &None, async { ok_page() })
+            .await;
+        assert_eq!(page.status_code, StatusCode::OK);
+        assert_eq!(
+            chaos.injected(),
+            InjectedFaults {
+                timeouts: 0,
+                server_errors: 1,
+                slow_bodies: 0,
+            }
+        );
+
+        chaos.reset();
+        assert_eq!(chaos.injected(), InjectedFaults::default());
+    }
+
+    #[tokio::test]
+    async fn times_out_within_the_request_timeout() {
+        let request_timeout = Some(Box::new(Duration::from_millis(100)));
+
+        let chaos = Chaos::new().with_timeout_rate(1.0);
+        let started = std::time::Instant::now();
+        let page = chaos
+            .fetch("https://example.com", &request_timeout, async { ok_page() })
+            .await;
+
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert_eq!(page.status_code.as_u16(), 599);
+        assert!(page.should_retry);
+
+        let chaos = Chaos::new().with_slow_bodies(1.0, Duration::from_millis(40));
+        let started = std::time::Instant::now();
+        let page = chaos
+            .fetch("https://example.com", &request_timeout, async { ok_page() })
+            .await;
+
+        assert!(started.elapsed() >= Duration::from_millis(40));
+        assert_eq!(page.status_code, StatusCode::OK);
+
+        let chaos = Chaos::new().with_slow_bodies(1.0, Duration::from_secs(30));
+        let started = std::time::Instant::now();
+        let page = chaos
+            .fetch("https://example.com", &request_timeout, async { ok_page() })
+            .await;
+
+        assert!(started.elapsed() < Duration::from_secs(30));
+        assert_eq!(page.status_code.as_u16(), 599);
+        assert_eq!(chaos.injected().slow_bodies, 1);
+    }
+}
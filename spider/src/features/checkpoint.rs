@@ -0,0 +1,353 @@
+use crate::utils::bloom::BloomFilter;
+use crate::utils::log;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The magic bytes starting a checkpoint file.
+const CHECKPOINT_MAGIC: &[u8; 4] = b"SPCK";
+
+/// The version of the checkpoint format written by this crate.
+pub const CHECKPOINT_VERSION: u32 = 2;
+
+/// The state of a crawl written to disk to resume it after a restart.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CrawlCheckpoint {
+    /// The start url of the crawl.
+    pub url: String,
+    /// The links visited. Empty when the links visited are kept in a bloom filter.
+    pub visited: Vec<String>,
+    /// The links found and not crawled yet, including the pages being fetched when the checkpoint was written.
+    pub frontier: Vec<String>,
+    /// The budget left for each path.
+    pub budget: Vec<(String, u32)>,
+    /// The bloom filter of the links visited when the crawl uses [`crate::configuration::VisitedSet::Bloom`].
+    pub bloom: Option<BloomFilter>,
+}
+
+/// Write a length prefixed string.
+pub(crate) fn write_str(w: &mut impl Write, s: &str) -> std::io::Result<()> {
+    w.write_all(&(s.len() as u32).to_le_bytes())?;
+    w.write_all(s.as_bytes())
+}
+
+/// Read a little endian u32.
+pub(crate) fn read_u32(r: &mut impl Read) -> std::io::Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+/// Read a little endian u64.
+pub(crate) fn read_u64(r: &mut impl Read) -> std::io::Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Read a length prefixed string.
+pub(crate) fn read_str(r: &mut impl Read) -> std::io::Result<String> {
+    let mut buf = vec![0u8; read_u32(r)? as usize];
+    r.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Read a count prefixed list of strings.
+pub(crate) fn read_list(r: &mut impl Read) -> std::io::Result<Vec<String>> {
+    let count = read_u32(r)? as usize;
+    let mut list = Vec::with_capacity(count.min(1 << 16));
+    for _ in 0..count {
+        list.push(read_str(r)?);
+    }
+    Ok(list)
+}
+
+impl CrawlCheckpoint {
+    /// Encode the checkpoint: the magic, the version, then the url, the visited links, the frontier, the budget and the
+    /// bloom filter as little endian length prefixed values.
+    pub fn encode(&self, w: &mut impl Write) -> std::io::Result<()> {
+        w.write_all(CHECKPOINT_MAGIC)?;
+        w.write_all(&CHECKPOINT_VERSION.to_le_bytes())?;
+        write_str(w, &self.url)?;
+
+        for list in [&self.visited, &self.frontier] {
+            w.write_all(&(list.len() as u32).to_le_bytes())?;
+            for link in list {
+                write_str(w, link)?;
+            }
+        }
+
+        w.write_all(&(self.budget.len() as u32).to_le_bytes())?;
+        for (path, left) in &self.budget {
+            write_str(w, path)?;
+            w.write_all(&left.to_le_bytes())?;
+        }
+
+        match &self.bloom {
+            Some(bloom) => {
+                w.write_all(&[1])?;
+                bloom.encode(w)?;
+            }
+            _ => w.write_all(&[0])?,
+        }
+
+        Ok(())
+    }
+
+    /// Decode a checkpoint written by [`CrawlCheckpoint::encode`].
+    pub fn decode(r: &mut impl Read) -> std::io::Result<Self> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+
+        if &magic != CHECKPOINT_MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "not a crawl checkpoint"));
+        }
+
+        let version = read_u32(r)?;
+
+        if version > CHECKPOINT_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("checkpoint version {version} is not supported, the latest version is {CHECKPOINT_VERSION}"),
+            ));
+        }
+
+        let url = read_str(r)?;
+        let visited = read_list(r)?;
+        let frontier = read_list(r)?;
+        let count = read_u32(r)? as usize;
+        let mut budget = Vec::with_capacity(count.min(1 << 16));
+
+        for _ in 0..count {
+            budget.push((read_str(r)?, read_u32(r)?));
+        }
+
+        let mut bloom = None;
+
+        if version >= 2 {
+            let mut flag = [0u8; 1];
+            r.read_exact(&mut flag)?;
+            if flag[0] == 1 {
+                bloom = Some(BloomFilter::decode(r)?);
+            }
+        }
+
+        Ok(Self {
+            url,
+            visited,
+            frontier,
+            budget,
+            bloom,
+        })
+    }
+
+    /// Write the checkpoint to the file. The file is replaced at once so a crash while writing keeps the previous checkpoint.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let tmp = path.with_extension("tmp");
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&tmp)?);
+
+        self.encode(&mut file)?;
+        file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+        std::fs::rename(tmp, path)
+    }
+
+    /// Read the checkpoint of the file.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        Self::decode(&mut std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+}
+
+/// Write the frontier, the visited links and the budgets of the crawl to a file periodically so the crawl can resume
+/// after a restart with [`crate::website::Website::resume_from`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint {
+    /// The checkpoint file.
+    pub path: PathBuf,
+    /// The time between the checkpoints of a crawl.
+    pub interval: Duration,
+    /// The time of the last checkpoint.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) last: Arc<Mutex<Option<Instant>>>,
+    /// The count of checkpoints taken.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    taken: Arc<AtomicUsize>,
+    /// The count of the last checkpoint written, held while a checkpoint is written.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    written: Arc<tokio::sync::Mutex<usize>>,
+}
+
+impl PartialEq for Checkpoint {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path && self.interval == other.interval
+    }
+}
+
+impl Checkpoint {
+    /// Write the crawl state to the file every minute.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            interval: Duration::from_secs(60),
+            last: Default::default(),
+            taken: Default::default(),
+            written: Default::default(),
+        }
+    }
+
+    /// Set the time between the checkpoints.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Whether a checkpoint is due. The first call of a crawl starts the interval.
+    pub(crate) fn due(&self) -> bool {
+        match self.last.lock() {
+            Ok(mut last) => match *last {
+                Some(at) if at.elapsed() >= self.interval => {
+                    *last = Some(Instant::now());
+                    true
+                }
+                Some(_) => false,
+                _ => {
+                    *last = Some(Instant::now());
+                    false
+                }
+            },
+            _ => false,
+        }
+    }
+
+    /// Take the crawl state for a checkpoint. The checkpoints are written in the order they are taken.
+    pub(crate) fn take(&self, checkpoint: CrawlCheckpoint) -> PendingCheckpoint {
+        PendingCheckpoint {
+            checkpoint,
+            order: self.taken.fetch_add(1, Ordering::Relaxed) + 1,
+            path: self.path.clone(),
+            written: self.written.clone(),
+        }
+    }
+
+    /// Reset the interval for a new crawl.
+    pub(crate) fn reset(&self) {
+        if let Ok(mut last) = self.last.lock() {
+            last.take();
+        }
+    }
+}
+
+/// A checkpoint taken and not written yet.
+pub(crate) struct PendingCheckpoint {
+    /// The crawl state.
+    checkpoint: CrawlCheckpoint,
+    /// The order the checkpoint was taken in.
+    order: usize,
+    /// The checkpoint file.
+    path: PathBuf,
+    /// The order of the last checkpoint written.
+    written: Arc<tokio::sync::Mutex<usize>>,
+}
+
+impl PendingCheckpoint {
+    /// Write the checkpoint to the file off the async runtime. The checkpoint is dropped when a later one was written.
+    pub(crate) async fn write(self) {
+        let PendingCheckpoint {
+            checkpoint,
+            order,
+            path,
+            written,
+        } = self;
+        let mut written = written.lock().await;
+
+        if *written > order {
+            return;
+        }
+
+        match tokio::task::spawn_blocking(move || checkpoint.save(&path)).await {
+            Ok(Ok(_)) => *written = order,
+            Ok(Err(err)) => log("checkpoint error: ", err.to_string()),
+            Err(err) => log("checkpoint error: ", err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkpoint_roundtrip() {
+        let checkpoint = CrawlCheckpoint {
+            url: "https://example.com".into(),
+            visited: vec!["https://example.com".into(), "https://example.com/a".into()],
+            frontier: vec!["https://example.com/b".into()],
+            budget: vec![("*".into(), 8), ("/blog".into(), 2)],
+            bloom: None,
+        };
+        let path = std::env::temp_dir()
+            .join(format!("spider-checkpoint-{}", std::process::id()))
+            .join("crawl.ckpt");
+
+        checkpoint.save(&path).unwrap();
+        assert_eq!(CrawlCheckpoint::load(&path).unwrap(), checkpoint);
+
+        std::fs::write(&path, b"nope").unwrap();
+        assert!(CrawlCheckpoint::load(&path).is_err());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn checkpoint_order() {
+        let path = std::env::temp_dir()
+            .join(format!("spider-checkpoint-order-{}", std::process::id()))
+            .join("crawl.ckpt");
+        let checkpoint = Checkpoint::new(&path);
+        let state = |url: &str| CrawlCheckpoint {
+            url: url.into(),
+            ..Default::default()
+        };
+
+        let first = checkpoint.take(state("https://example.com/1"));
+        let second = checkpoint.take(state("https://example.com/2"));
+
+        second.write().await;
+        first.write().await;
+        assert_eq!(
+            CrawlCheckpoint::load(&path).unwrap().url,
+            "https://example.com/2"
+        );
+
+        let mut bloom = BloomFilter::new(100, 0.01);
+        bloom.insert("https://example.com/a");
+        checkpoint
+            .take(CrawlCheckpoint {
+                bloom: Some(bloom.clone()),
+                ..state("https://example.com/3")
+            })
+            .write()
+            .await;
+        assert_eq!(CrawlCheckpoint::load(&path).unwrap().bloom, Some(bloom));
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn checkpoint_due() {
+        let checkpoint = Checkpoint::new("crawl.ckpt").with_interval(Duration::ZERO);
+
+        assert!(!checkpoint.due());
+        assert!(checkpoint.due());
+
+        checkpoint.reset();
+        assert!(!checkpoint.due());
+    }
+}
@@ -74,6 +74,7 @@ pub fn get_browser_config(
     cache_enabled: bool,
     viewport: impl Into<Option<chromiumoxide::handler::viewport::Viewport>>,
     request_timeout: &Option<Box<core::time::Duration>>,
+    launch_options: &Option<Box<crate::configuration::BrowserLaunchOptions>>,
 ) -> Option<BrowserConfig> {
     let builder = BrowserConfig::builder()
         .disable_default_args()
@@ -95,29 +96,29 @@ pub fn get_browser_config(
         builder
     };
 
-    let builder = match proxies {
-        Some(proxies) => {
-            let mut chrome_args = Vec::from(CHROME_ARGS.map(|e| e.replace("://", "=").to_string()));
+    let mut chrome_args = platform_chrome_args(&CHROME_ARGS, true, launch_options);
 
-            chrome_args.push(string_concat!(r#"--proxy-server="#, proxies.join(";")));
+    if let Some(proxies) = proxies {
+        chrome_args.push(string_concat!(
+            r#"--proxy-server="#,
+            proxies
+                .iter()
+                .map(|proxy| chrome_proxy_server(proxy))
+                .collect::<Vec<_>>()
+                .join(";")
+        ));
+    }
 
-            builder.args(chrome_args)
-        }
-        _ => builder.args(CHROME_ARGS),
-    };
-    let builder = if std::env::var("CHROME_BIN").is_ok() {
-        match std::env::var("CHROME_BIN") {
-            Ok(v) => builder.chrome_executable(v),
-            _ => builder,
-        }
-    } else {
-        builder
+    let builder = builder.args(chrome_args);
+    let builder = match chrome_executable(launch_options) {
+        Some(executable) => builder.chrome_executable(executable),
+        _ => builder,
     };
 
     match builder.viewport(viewport).build() {
         Ok(b) => Some(b),
         Err(error) => {
-            log("", error);
+            log::error!("{error}");
             None
         }
     }
@@ -131,10 +132,10 @@ pub fn get_browser_config(
     cache_enabled: bool,
     viewport: impl Into<Option<chromiumoxide::handler::viewport::Viewport>>,
     request_timeout: &Option<Box<core::time::Duration>>,
+    launch_options: &Option<Box<crate::configuration::BrowserLaunchOptions>>,
 ) -> Option<BrowserConfig> {
     let builder = BrowserConfig::builder()
         .disable_default_args()
-        .no_sandbox()
         .request_timeout(match request_timeout.as_ref() {
             Some(timeout) => **timeout,
             _ => Default::default(),
@@ -153,39 +154,202 @@ pub fn get_browser_config(
         builder
     };
 
-    let mut chrome_args = Vec::from(CHROME_ARGS.map(|e| {
-        if e == "--headless" {
-            "".to_string()
-        } else {
-            e.replace("://", "=").to_string()
-        }
-    }));
+    let mut chrome_args = platform_chrome_args(&CHROME_ARGS, false, launch_options);
 
-    let builder = match proxies {
-        Some(proxies) => {
-            chrome_args.push(string_concat!(r#"--proxy-server="#, proxies.join(";")));
+    if let Some(proxies) = proxies {
+        chrome_args.push(string_concat!(
+            r#"--proxy-server="#,
+            proxies
+                .iter()
+                .map(|proxy| chrome_proxy_server(proxy))
+                .collect::<Vec<_>>()
+                .join(";")
+        ));
+    }
 
-            builder.args(chrome_args)
-        }
-        _ => builder.args(chrome_args),
-    };
-    let builder = if std::env::var("CHROME_BIN").is_ok() {
-        match std::env::var("CHROME_BIN") {
-            Ok(v) => builder.chrome_executable(v),
-            _ => builder,
-        }
-    } else {
-        builder
+    let builder = builder.args(chrome_args);
+    let builder = match chrome_executable(launch_options) {
+        Some(executable) => builder.chrome_executable(executable),
+        _ => builder,
     };
+
     match builder.viewport(viewport).build() {
         Ok(b) => Some(b),
         Err(error) => {
-            log("", error);
+            log::error!("{error}");
             None
         }
     }
 }
 
+/// The flags only understood by chrome on Linux.
+const LINUX_ONLY_ARGS: [&str; 3] = [
+    "--no-zygote",
+    "--disable-setuid-sandbox",
+    "--disable-dev-shm-usage",
+];
+
+/// The flags turning off the browser sandbox.
+const NO_SANDBOX_ARGS: [&str; 2] = ["--no-sandbox", "--disable-setuid-sandbox"];
+
+/// The flag writing the renderer shared memory to the temp dir instead of `/dev/shm`.
+const DISABLE_DEV_SHM_ARG: &str = "--disable-dev-shm-usage";
+
+/// The smallest `/dev/shm` renders are reliable with. Docker mounts 64MB by default.
+pub const MIN_SHM_SIZE: u64 = 512 * 1024 * 1024;
+
+lazy_static! {
+    static ref CONTAINERIZED: bool = detect_container();
+    static ref SHM_SIZE: Option<u64> = std::fs::read_to_string("/proc/mounts")
+        .ok()
+        .and_then(|mounts| parse_shm_size(&mounts));
+}
+
+/// Whether the process runs inside a Docker, Podman or Kubernetes container.
+pub fn is_containerized() -> bool {
+    *CONTAINERIZED
+}
+
+/// Detect a container from the runtime marker files, env variables and the cgroup of the init process.
+fn detect_container() -> bool {
+    if !cfg!(target_os = "linux") {
+        return false;
+    }
+
+    std::path::Path::new("/.dockerenv").exists()
+        || std::path::Path::new("/run/.containerenv").exists()
+        || std::env::var_os("KUBERNETES_SERVICE_HOST").is_some()
+        || std::env::var_os("container").is_some()
+        || std::fs::read_to_string("/proc/1/cgroup").is_ok_and(|cgroup| {
+            ["docker", "kubepods", "containerd", "libpod", "lxc"]
+                .iter()
+                .any(|runtime| cgroup.contains(runtime))
+        })
+}
+
+/// The size in bytes of the `/dev/shm` mount when it is limited, read from `/proc/mounts`.
+pub fn shm_size() -> Option<u64> {
+    *SHM_SIZE
+}
+
+/// Parse the `size` option of the `/dev/shm` entry of a mount table.
+pub fn parse_shm_size(mounts: &str) -> Option<u64> {
+    let options = mounts.lines().find_map(|line| {
+        let mut fields = line.split_whitespace().skip(1);
+        match fields.next() {
+            Some("/dev/shm") => fields.nth(1),
+            _ => None,
+        }
+    })?;
+    let size = options
+        .split(',')
+        .find_map(|option| option.strip_prefix("size="))?;
+    let (digits, unit) = match size.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => size.split_at(i),
+        _ => (size, ""),
+    };
+    let multiplier = match unit.to_ascii_lowercase().as_str() {
+        "" => 1,
+        "k" => 1024,
+        "m" => 1024 * 1024,
+        "g" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+
+    digits.parse::<u64>().ok().map(|n| n * multiplier)
+}
+
+/// Warn once when the browser runs without the sandbox or with a `/dev/shm` too small for the renderers.
+fn warn_unsafe_launch(sandbox: bool, container: bool) {
+    static NO_SANDBOX: std::sync::Once = std::sync::Once::new();
+    static SMALL_SHM: std::sync::Once = std::sync::Once::new();
+
+    if !sandbox {
+        NO_SANDBOX.call_once(|| {
+            if container {
+                log::warn!("Running the browser without the sandbox inside a container. Set the sandbox launch option to keep it on.");
+            } else {
+                log::warn!("Running the browser without the sandbox. Only crawl trusted sites.");
+            }
+        });
+    }
+
+    if let Some(size) = shm_size().filter(|size| *size < MIN_SHM_SIZE) {
+        SMALL_SHM.call_once(|| {
+            log::warn!(
+                "/dev/shm is only {}MB, renders write shared memory to the temp dir instead. Raise it with `docker run --shm-size=1g`.",
+                size / 1024 / 1024
+            );
+        });
+    }
+}
+
+/// The browser arguments adapted to the platform and launch options: the headless flag follows the headless mode, the Linux only flags are dropped elsewhere and the sandbox flags follow the sandbox option.
+/// Inside containers or with a small `/dev/shm` the renderers stop using the shared memory mount, the sandbox is only changed by the sandbox option.
+pub fn platform_chrome_args(
+    args: &[&str],
+    headless: bool,
+    launch_options: &Option<Box<crate::configuration::BrowserLaunchOptions>>,
+) -> Vec<String> {
+    let launch_options = launch_options.as_deref();
+    let linux = cfg!(target_os = "linux");
+    let container = launch_options
+        .and_then(|o| o.container)
+        .unwrap_or_else(is_containerized);
+    let sandbox = launch_options.and_then(|o| o.sandbox).unwrap_or(!linux);
+    let headless_new = launch_options
+        .and_then(|o| o.headless_new)
+        .unwrap_or(cfg!(feature = "chrome_headless_new"));
+    let shm_guard = linux && (container || shm_size().is_some_and(|size| size < MIN_SHM_SIZE));
+
+    let mut chrome_args: Vec<String> = args
+        .iter()
+        .filter_map(|arg| {
+            if arg.starts_with("--headless") {
+                match (headless, headless_new) {
+                    (true, true) => Some("--headless=new"),
+                    (true, false) => Some("--headless"),
+                    _ => None,
+                }
+            } else if sandbox && NO_SANDBOX_ARGS.contains(arg)
+                || !linux && LINUX_ONLY_ARGS.contains(arg)
+                || !shm_guard && *arg == DISABLE_DEV_SHM_ARG
+            {
+                None
+            } else {
+                Some(*arg)
+            }
+        })
+        .map(String::from)
+        .collect();
+
+    if !sandbox && !chrome_args.iter().any(|arg| arg == "--no-sandbox") {
+        chrome_args.push("--no-sandbox".into());
+    }
+
+    if shm_guard && !chrome_args.iter().any(|arg| arg == DISABLE_DEV_SHM_ARG) {
+        chrome_args.push(DISABLE_DEV_SHM_ARG.into());
+    }
+
+    if let Some(launch_options) = launch_options {
+        chrome_args.extend(launch_options.args.iter().cloned());
+    }
+
+    warn_unsafe_launch(sandbox, container);
+
+    chrome_args
+}
+
+/// The browser executable set by the launch options or the `CHROME_BIN` env variable. The browser is detected when unset.
+fn chrome_executable(
+    launch_options: &Option<Box<crate::configuration::BrowserLaunchOptions>>,
+) -> Option<std::path::PathBuf> {
+    match launch_options.as_ref().and_then(|o| o.executable.clone()) {
+        Some(executable) => Some(executable),
+        _ => std::env::var_os("CHROME_BIN").map(std::path::PathBuf::from),
+    }
+}
+
 /// create the browser handler configuration
 fn create_handler_config(config: &Configuration) -> HandlerConfig {
     HandlerConfig {
@@ -205,9 +369,18 @@ fn create_handler_config(config: &Configuration) -> HandlerConfig {
         ignore_ads: config.chrome_intercept.block_ads,
         ignore_javascript: config.chrome_intercept.block_javascript,
         ignore_stylesheets: config.chrome_intercept.block_stylesheets,
+        bypass_service_worker: config.chrome_intercept.bypass_service_worker,
+        block_service_workers: config.chrome_intercept.block_service_workers,
+        resource_policy: (&config.chrome_intercept.resource_policy).into(),
         extra_headers: match config.headers {
             Some(ref headers) => {
-                let hm = crate::utils::header_utils::header_map_to_hash_map(headers.inner());
+                let mut hm = crate::utils::header_utils::Headers::from(headers.inner());
+                // keep the intercepted request agent the same as the agent used in the JS environment.
+                if config.stealth_mode {
+                    if let Some(agent) = config.user_agent.as_ref() {
+                        hm.insert("User-Agent", agent);
+                    }
+                }
                 if hm.is_empty() {
                     None
                 } else {
@@ -217,14 +390,77 @@ fn create_handler_config(config: &Configuration) -> HandlerConfig {
             _ => None,
         },
         only_html: config.only_html && !config.full_resources,
+        event_drop_rate: event_drop_rate(config),
         ..HandlerConfig::default()
     }
 }
 
+/// The share of the CDP events the chaos config drops.
+#[cfg(feature = "chaos")]
+fn event_drop_rate(config: &Configuration) -> f64 {
+    config
+        .chaos
+        .as_ref()
+        .map_or(0.0, |chaos| chaos.dropped_event_rate.clamp(0.0, 1.0))
+}
+
+/// The share of the CDP events the chaos config drops.
+#[cfg(not(feature = "chaos"))]
+fn event_drop_rate(_: &Configuration) -> f64 {
+    0.0
+}
+
 lazy_static! {
     static ref CHROM_BASE: Option<String> = std::env::var("CHROME_URL").ok();
 }
 
+/// Connect to the remote browser, trying again with a backoff when the connection fails. The handler connects again
+/// the same way when the connection drops during the crawl.
+async fn connect_browser(
+    url: &str,
+    config: &Configuration,
+) -> Option<(Browser, chromiumoxide::Handler)> {
+    let options = config
+        .chrome_connection_options
+        .as_deref()
+        .cloned()
+        .unwrap_or_default();
+    let mut attempt = 0;
+
+    loop {
+        let handler_config = HandlerConfig {
+            reconnect_attempts: options.reconnect_attempts,
+            reconnect_delay: options.reconnect_delay,
+            ..create_handler_config(&config)
+        };
+
+        match Browser::connect_with_auth(url, options.auth_token.as_deref(), handler_config).await {
+            Ok(browser) => return Some(browser),
+            Err(err) if attempt < options.reconnect_attempts => {
+                attempt += 1;
+                log::warn!("chrome connection failed, attempt {attempt}: {:?}", err);
+                tokio::time::sleep(options.backoff(attempt)).await;
+            }
+            Err(err) => {
+                log::error!("{:?}", err);
+                return None;
+            }
+        }
+    }
+}
+
+/// Whether the handler connects again to the remote browser when the connection drops.
+fn reconnects(config: &Configuration) -> bool {
+    (config.chrome_connection_url.is_some() || CHROM_BASE.is_some())
+        && config
+            .chrome_connection_options
+            .as_deref()
+            .cloned()
+            .unwrap_or_default()
+            .reconnect_attempts
+            > 0
+}
+
 /// Get the default viewport
 #[cfg(not(feature = "real_browser"))]
 pub fn default_viewport() -> Option<chromiumoxide::handler::viewport::Viewport> {
@@ -253,13 +489,7 @@ pub async fn setup_browser_configuration(
     };
 
     match chrome_connection {
-        Some(v) => match Browser::connect_with_config(&*v, create_handler_config(&config)).await {
-            Ok(browser) => Some(browser),
-            Err(err) => {
-                log::error!("{:?}", err);
-                None
-            }
-        },
+        Some(v) => connect_browser(v, config).await,
         _ => match get_browser_config(
             &proxies,
             config.chrome_intercept.enabled,
@@ -271,16 +501,22 @@ pub async fn setup_browser_configuration(
                 _ => default_viewport(),
             },
             &config.request_timeout,
+            &chrome_launch_options(config),
         ) {
             Some(mut browser_config) => {
                 browser_config.ignore_visuals = config.chrome_intercept.block_visuals;
                 browser_config.ignore_javascript = config.chrome_intercept.block_javascript;
                 browser_config.ignore_ads = config.chrome_intercept.block_ads;
                 browser_config.ignore_stylesheets = config.chrome_intercept.block_stylesheets;
+                browser_config.bypass_service_worker =
+                    config.chrome_intercept.bypass_service_worker;
+                browser_config.block_service_workers =
+                    config.chrome_intercept.block_service_workers;
+                browser_config.resource_policy = (&config.chrome_intercept.resource_policy).into();
+                browser_config.event_drop_rate = event_drop_rate(config);
                 browser_config.extra_headers = match config.headers {
                     Some(ref headers) => {
-                        let hm =
-                            crate::utils::header_utils::header_map_to_hash_map(headers.inner());
+                        let hm = crate::utils::header_utils::Headers::from(headers.inner());
                         if hm.is_empty() {
                             None
                         } else {
@@ -291,9 +527,17 @@ pub async fn setup_browser_configuration(
                 };
                 browser_config.only_html = config.only_html && !config.full_resources;
 
+                if let Some(limits) = config.browser_storage_limits.as_deref() {
+                    browser_config.disk_cache_size = limits.disk_cache_size;
+                    browser_config.profile_root = limits.profile_dir.clone();
+                }
+
                 match Browser::launch(browser_config).await {
                     Ok(browser) => Some(browser),
-                    _ => None,
+                    Err(err) => {
+                        log::error!("{:?}", err);
+                        None
+                    }
                 }
             }
             _ => None,
@@ -301,6 +545,65 @@ pub async fn setup_browser_configuration(
     }
 }
 
+/// The flag letting https pages load http scripts and frames.
+const INSECURE_CONTENT_ARG: &str = "--allow-running-insecure-content";
+
+/// The launch options with the arguments of the configuration: the mixed content allowed when the CSP is bypassed and
+/// the host resolver rules of the resolve overrides.
+fn chrome_launch_options(
+    config: &Configuration,
+) -> Option<Box<crate::configuration::BrowserLaunchOptions>> {
+    let mut args = Vec::new();
+
+    if config.bypass_csp && !CHROME_ARGS.contains(&INSECURE_CONTENT_ARG) {
+        args.push(INSECURE_CONTENT_ARG.to_string());
+    }
+
+    if let Some(resolve) = config.resolve.as_deref().filter(|r| !r.is_empty()) {
+        let rules = resolve
+            .iter()
+            .map(|entry| entry.host_resolver_rule())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        args.push(format!("--host-resolver-rules={rules}"));
+    }
+
+    if args.is_empty() {
+        config.browser_launch_options.clone()
+    } else {
+        let mut launch_options = config.browser_launch_options.clone().unwrap_or_default();
+        launch_options.args.extend(args);
+        Some(launch_options)
+    }
+}
+
+/// Scan for browsers orphaned by a previous crashed run and install the signal cleanup once per process.
+fn setup_browser_process_cleanup(config: &Configuration) {
+    static ORPHAN_SCAN: std::sync::Once = std::sync::Once::new();
+    static SIGNAL_CLEANUP: std::sync::Once = std::sync::Once::new();
+
+    ORPHAN_SCAN.call_once(|| {
+        let orphans = chromiumoxide::reaper::reap_orphaned_browsers(config.kill_orphaned_browsers);
+
+        if !orphans.is_empty() {
+            if config.kill_orphaned_browsers {
+                log::warn!("Killed {} orphaned browser processes", orphans.len());
+            } else {
+                log::warn!(
+                    "Found {} orphaned browser processes from a previous run: {:?}",
+                    orphans.len(),
+                    orphans
+                );
+            }
+        }
+    });
+
+    if config.browser_signal_cleanup {
+        SIGNAL_CLEANUP.call_once(chromiumoxide::reaper::spawn_signal_handler);
+    }
+}
+
 /// Launch a chromium browser with configurations and wait until the instance is up.
 pub async fn launch_browser(
     config: &Configuration,
@@ -312,6 +615,10 @@ pub async fn launch_browser(
 )> {
     use chromiumoxide::cdp::browser_protocol::target::CreateBrowserContextParams;
     use chromiumoxide::error::CdpError;
+    setup_browser_process_cleanup(config);
+    if config.chrome_command_metrics {
+        chromiumoxide::metrics::set_enabled(true);
+    }
     let browser_configuration = setup_browser_configuration(&config).await;
     let mut context_id = None;
 
@@ -347,14 +654,16 @@ pub async fn launch_browser(
 
             if !context_id.is_some() {
                 let mut create_content = CreateBrowserContextParams::default();
-                create_content.dispose_on_detach = Some(true);
+                // the context of a remote browser outlives a dropped connection the handler connects again, it is
+                // disposed when the crawl closes the browser.
+                create_content.dispose_on_detach = Some(!reconnects(config));
 
                 if let Some(ref p) = config.proxies {
                     if let Some(p) = p.get(0) {
                         if p.starts_with("http://localhost") {
                             create_content.proxy_bypass_list = Some("<-loopback>".into());
                         }
-                        create_content.proxy_server = Some(p.into());
+                        create_content.proxy_server = Some(chrome_proxy_server(p));
                     }
                 }
 
@@ -362,7 +671,7 @@ pub async fn launch_browser(
                     Ok(c) => {
                         let _ = browser.send_new_context(c.clone()).await;
                         let _ = context_id.insert(c);
-                        if !config.cookie_str.is_empty() {
+                        if !config.cookie_str.is_empty() && config.consent_mode.is_none() {
                             if let Some(parsed) = url_parsed {
                                 let cookies = parse_cookies_with_jar(&config.cookie_str, &*parsed);
                                 if let Ok(co) = cookies {
@@ -382,6 +691,585 @@ pub async fn launch_browser(
     }
 }
 
+/// A browser the hosts of a crawl are sharded across with its context.
+pub type BrowserShard = (std::sync::Arc<Browser>, Option<BrowserContextId>);
+
+/// Launch the browsers for the shards after the first. The first shard is the browser already launched for the crawl.
+pub async fn launch_browser_shards(
+    config: &Configuration,
+    url_parsed: &Option<Box<Url>>,
+) -> Vec<(
+    std::sync::Arc<Browser>,
+    tokio::task::JoinHandle<()>,
+    Option<BrowserContextId>,
+)> {
+    let mut shards = Vec::new();
+
+    if config.proxy_rotation.is_none() && config.browser_pool.is_none() {
+        for _ in 1..config.browser_shards {
+            match launch_browser(config, url_parsed).await {
+                Some((browser, handle, context_id)) => {
+                    shards.push((std::sync::Arc::new(browser), handle, context_id))
+                }
+                _ => log("", "Chrome shard failed to start."),
+            }
+        }
+    }
+
+    shards
+}
+
+/// The position of the shard for the host of the url. The same host maps to the same shard for the crawl.
+pub fn browser_shard_index(url: &str, shards: usize) -> usize {
+    use std::hash::{Hash, Hasher};
+
+    if shards <= 1 {
+        return 0;
+    }
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    match Url::parse(url) {
+        Ok(u) => u.host_str().unwrap_or_default().hash(&mut hasher),
+        _ => url.hash(&mut hasher),
+    }
+    (hasher.finish() % shards as u64) as usize
+}
+
+/// The proxy server for chrome. Chrome resolves hosts through `socks5://` proxies and does not know the `socks5h://` scheme used by the HTTP client.
+/// The credentials of the url are left out, the pages answer the proxy challenges with [`proxy_credentials`].
+fn chrome_proxy_server(proxy: &str) -> String {
+    let proxy = match Url::parse(proxy) {
+        Ok(mut url) if !url.username().is_empty() => {
+            let _ = url.set_username("");
+            let _ = url.set_password(None);
+            url.as_str().trim_end_matches('/').to_string()
+        }
+        _ => proxy.to_string(),
+    };
+
+    match proxy.strip_prefix("socks5h://") {
+        Some(addr) => string_concat!("socks5://", addr),
+        _ => proxy,
+    }
+}
+
+/// The credentials of the first proxy of the browser, like the secret of the proxy auth relay.
+fn proxy_credentials(
+    proxies: &Option<Box<Vec<string_concat::String>>>,
+) -> Option<chromiumoxide::auth::Credentials> {
+    let url = Url::parse(proxies.as_deref()?.first()?).ok()?;
+
+    if url.username().is_empty() {
+        return None;
+    }
+
+    Some(chromiumoxide::auth::Credentials {
+        username: percent_encoding::percent_decode_str(url.username())
+            .decode_utf8_lossy()
+            .into_owned(),
+        password: percent_encoding::percent_decode_str(url.password().unwrap_or_default())
+            .decode_utf8_lossy()
+            .into_owned(),
+    })
+}
+
+/// The browser context of the page.
+async fn page_browser_context_id(page: &Page) -> Option<BrowserContextId> {
+    match page
+        .execute(
+            chromiumoxide::cdp::browser_protocol::target::GetTargetInfoParams {
+                target_id: Some(page.target_id().clone()),
+            },
+        )
+        .await
+    {
+        Ok(info) => info.result.target_info.browser_context_id,
+        _ => None,
+    }
+}
+
+/// The downloads started by the main frame of a page.
+struct PageDownloads {
+    /// The main frame of the page, the download events of the other pages of the browser context are skipped.
+    frame_id: String,
+    /// The url crawled by the page.
+    page_url: String,
+    /// The capture directory.
+    dir: std::path::PathBuf,
+    /// The downloads in progress by id.
+    pending: hashbrown::HashMap<
+        String,
+        chromiumoxide::cdp::browser_protocol::browser::EventDownloadWillBegin,
+    >,
+}
+
+impl PageDownloads {
+    /// Track the download when the page started it.
+    fn begin(
+        &mut self,
+        event: &chromiumoxide::cdp::browser_protocol::browser::EventDownloadWillBegin,
+    ) {
+        if event.frame_id.as_ref() == self.frame_id {
+            self.pending.insert(event.guid.clone(), event.clone());
+        }
+    }
+
+    /// The file of a download of the page once completed.
+    fn progress(
+        &mut self,
+        event: &chromiumoxide::cdp::browser_protocol::browser::EventDownloadProgress,
+    ) -> Option<crate::configuration::DownloadedFile> {
+        use chromiumoxide::cdp::browser_protocol::browser::DownloadProgressState;
+
+        match event.state {
+            DownloadProgressState::Completed => {
+                let download = self.pending.remove(&event.guid)?;
+
+                Some(crate::configuration::DownloadedFile {
+                    page_url: self.page_url.clone(),
+                    url: download.url,
+                    suggested_filename: download.suggested_filename,
+                    path: self.dir.join(&download.guid),
+                    size: event.received_bytes as u64,
+                })
+            }
+            DownloadProgressState::Canceled => {
+                self.pending.remove(&event.guid);
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Save the downloads started by the page to the capture directory and record them once completed. The downloads stop
+/// being waited for when the page closes or no download event was received for the idle timeout of the capture.
+async fn setup_download_capture(
+    page: &Page,
+    download_capture: &crate::configuration::DownloadCapture,
+    url: &str,
+) {
+    use chromiumoxide::cdp::browser_protocol::browser::{
+        EventDownloadProgress, EventDownloadWillBegin, SetDownloadBehaviorBehavior,
+        SetDownloadBehaviorParams,
+    };
+
+    // chrome requires an absolute download path.
+    let dir = match tokio::fs::create_dir_all(&download_capture.dir).await {
+        Ok(_) => match tokio::fs::canonicalize(&download_capture.dir).await {
+            Ok(dir) => dir,
+            Err(e) => {
+                log::warn!("download capture directory error: {:?}", e);
+                return;
+            }
+        },
+        Err(e) => {
+            log::warn!("download capture directory error: {:?}", e);
+            return;
+        }
+    };
+
+    let (will_begin, progress) = tokio::join!(
+        page.event_listener::<EventDownloadWillBegin>(),
+        page.event_listener::<EventDownloadProgress>()
+    );
+
+    let (mut will_begin, mut progress) = match (will_begin, progress) {
+        (Ok(will_begin), Ok(progress)) => (will_begin, progress),
+        _ => return,
+    };
+
+    let mut params = SetDownloadBehaviorParams::new(SetDownloadBehaviorBehavior::AllowAndName);
+    params.browser_context_id = page_browser_context_id(page).await;
+    params.download_path = Some(dir.display().to_string());
+    params.events_enabled = Some(true);
+
+    if let Err(e) = page.execute(params).await {
+        log::warn!("download capture setup error: {:?}", e);
+        return;
+    }
+
+    let download_capture = download_capture.clone();
+    let idle_timeout = download_capture.idle_timeout();
+    // the main frame of a page has the id of its target.
+    let mut downloads = PageDownloads {
+        frame_id: page.target_id().as_ref().to_string(),
+        page_url: url.to_string(),
+        dir,
+        pending: Default::default(),
+    };
+
+    crate::utils::spawn_task("download_capture", async move {
+        loop {
+            tokio::select! {
+                event = will_begin.next() => match event {
+                    Some(event) => downloads.begin(&event),
+                    _ => break,
+                },
+                event = progress.next() => match event {
+                    Some(event) => {
+                        if let Some(file) = downloads.progress(&event) {
+                            download_capture.push(file);
+                        }
+                    }
+                    _ => break,
+                },
+                _ = tokio::time::sleep(idle_timeout) => break,
+            }
+        }
+    });
+}
+
+/// Send the requests blocked by the browser, the authentication challenges and the crash of the page to the
+/// subscribers of the crawl events.
+async fn setup_crawl_events(
+    page: &Page,
+    crawl_events: &crate::features::events::CrawlEvents,
+    url: &str,
+) {
+    use crate::features::events::CrawlEvent;
+    use chromiumoxide::cdp::browser_protocol::fetch::EventAuthRequired;
+    use chromiumoxide::cdp::browser_protocol::inspector::EventTargetCrashed;
+    use chromiumoxide::handler::network::EventRequestBlocked;
+
+    let (blocked, challenged, crashed) = tokio::join!(
+        page.event_listener::<EventRequestBlocked>(),
+        page.event_listener::<EventAuthRequired>(),
+        page.event_listener::<EventTargetCrashed>()
+    );
+
+    let (mut blocked, mut challenged, mut crashed) = match (blocked, challenged, crashed) {
+        (Ok(blocked), Ok(challenged), Ok(crashed)) => (blocked, challenged, crashed),
+        _ => return,
+    };
+
+    let crawl_events = crawl_events.clone();
+    let page_url = url.to_string();
+
+    tokio::task::spawn(async move {
+        loop {
+            let event = tokio::select! {
+                Some(event) = blocked.next() => CrawlEvent::RequestBlocked {
+                    page_url: page_url.clone(),
+                    url: event.url.clone(),
+                    resource_type: event.resource_type.clone(),
+                    reason: event.reason.clone(),
+                },
+                Some(event) = challenged.next() => CrawlEvent::AuthChallenged {
+                    page_url: page_url.clone(),
+                    url: event.request.url.clone(),
+                    source: event
+                        .auth_challenge
+                        .source
+                        .as_ref()
+                        .map_or_else(|| "Server".into(), |source| source.as_ref().to_string()),
+                    scheme: event.auth_challenge.scheme.clone(),
+                },
+                Some(_) = crashed.next() => CrawlEvent::TargetCrashed {
+                    url: page_url.clone(),
+                },
+                else => break,
+            };
+
+            crawl_events.send(event);
+        }
+    });
+}
+
+/// The headers to replay a response. The body saved is decoded so the encoding and length no longer apply.
+fn recorded_headers(
+    headers: &chromiumoxide::cdp::browser_protocol::network::Headers,
+) -> Vec<(String, String)> {
+    match headers.inner().as_object() {
+        Some(headers) => headers
+            .iter()
+            .filter(|(name, _)| {
+                !name.eq_ignore_ascii_case("content-encoding")
+                    && !name.eq_ignore_ascii_case("content-length")
+            })
+            .filter_map(|(name, value)| {
+                value.as_str().map(|value| {
+                    // multiple values are joined by new lines.
+                    (name.clone(), value.replace('\n', ", "))
+                })
+            })
+            .collect(),
+        _ => Default::default(),
+    }
+}
+
+/// The decoded body of the response of the request.
+async fn response_body(
+    page: &Page,
+    request_id: chromiumoxide::cdp::browser_protocol::network::RequestId,
+) -> Option<Vec<u8>> {
+    use chromiumoxide::cdp::browser_protocol::network::GetResponseBodyParams;
+
+    let body = page
+        .execute(GetResponseBodyParams::new(request_id))
+        .await
+        .ok()?;
+
+    if body.result.base64_encoded {
+        use base64::Engine;
+        base64::engine::general_purpose::STANDARD
+            .decode(&body.result.body)
+            .ok()
+    } else {
+        Some(body.result.body.into_bytes())
+    }
+}
+
+/// Save the responses received by the page to the network recording.
+async fn setup_network_recording(
+    page: &Page,
+    network_recording: &crate::configuration::NetworkRecording,
+) {
+    use chromiumoxide::cdp::browser_protocol::network::{
+        EventLoadingFinished, EventRequestWillBeSent, EventResponseReceived,
+    };
+
+    let (will_be_sent, received, finished) = tokio::join!(
+        page.event_listener::<EventRequestWillBeSent>(),
+        page.event_listener::<EventResponseReceived>(),
+        page.event_listener::<EventLoadingFinished>()
+    );
+
+    let (mut will_be_sent, mut received, mut finished) = match (will_be_sent, received, finished) {
+        (Ok(will_be_sent), Ok(received), Ok(finished)) => (will_be_sent, received, finished),
+        _ => return,
+    };
+
+    let page = page.clone();
+    let network_recording = network_recording.clone();
+
+    tokio::task::spawn(async move {
+        let mut pending = hashbrown::HashMap::new();
+        let mut methods = hashbrown::HashMap::new();
+
+        loop {
+            tokio::select! {
+                Some(event) = will_be_sent.next() => {
+                    let method = methods.insert(event.request_id.clone(), event.request.method.clone());
+                    // redirects never reach the response events, save them to replay the same hops.
+                    if let Some(ref redirect) = event.redirect_response {
+                        let response = crate::configuration::RecordedResponse {
+                            method: method.unwrap_or_default(),
+                            url: redirect.url.clone(),
+                            status: redirect.status as u16,
+                            headers: recorded_headers(&redirect.headers),
+                            body: Default::default(),
+                        };
+                        if let Err(e) = network_recording.save(&response).await {
+                            log::warn!("network recording error: {:?}", e);
+                        }
+                    }
+                }
+                Some(event) = received.next() => {
+                    pending.insert(event.request_id.clone(), crate::configuration::RecordedResponse {
+                        method: methods.get(&event.request_id).cloned().unwrap_or_default(),
+                        url: event.response.url.clone(),
+                        status: event.response.status as u16,
+                        headers: recorded_headers(&event.response.headers),
+                        body: Default::default(),
+                    });
+                }
+                Some(event) = finished.next() => {
+                    methods.remove(&event.request_id);
+                    if let Some(mut response) = pending.remove(&event.request_id) {
+                        if let Some(body) = response_body(&page, event.request_id.clone()).await {
+                            response.body = body;
+                        }
+                        if let Err(e) = network_recording.save(&response).await {
+                            log::warn!("network recording error: {:?}", e);
+                        }
+                    }
+                }
+                else => break,
+            }
+        }
+    });
+}
+
+/// Keep the headers the browser sent for the pages matching a tag of the request sampling, and sample the other requests
+/// of the page like the `fetch` calls with their response.
+async fn setup_request_sampling(
+    page: &Page,
+    request_sampling: &crate::features::request_sampling::RequestSampling,
+) {
+    use chromiumoxide::cdp::browser_protocol::network::{
+        EventLoadingFinished, EventRequestWillBeSent, EventResponseReceived, ResourceType,
+    };
+
+    let (will_be_sent, received, finished) = tokio::join!(
+        page.event_listener::<EventRequestWillBeSent>(),
+        page.event_listener::<EventResponseReceived>(),
+        page.event_listener::<EventLoadingFinished>()
+    );
+
+    let (mut will_be_sent, mut received, mut finished) = match (will_be_sent, received, finished) {
+        (Ok(will_be_sent), Ok(received), Ok(finished)) => (will_be_sent, received, finished),
+        _ => return,
+    };
+
+    let page = page.clone();
+    let request_sampling = request_sampling.clone();
+
+    tokio::task::spawn(async move {
+        let mut requests = hashbrown::HashMap::new();
+        let mut responses = hashbrown::HashMap::new();
+
+        loop {
+            tokio::select! {
+                Some(event) = will_be_sent.next() => {
+                    let request = &event.request;
+
+                    if event.r#type == Some(ResourceType::Document) {
+                        // the page is recorded with its response once crawled.
+                        request_sampling.sent(&request.url, recorded_headers(&request.headers));
+                    } else if request_sampling.wants(&request.url) {
+                        requests.insert(
+                            event.request_id.clone(),
+                            (request.method.clone(), request.url.clone(), recorded_headers(&request.headers)),
+                        );
+                    }
+                }
+                Some(event) = received.next() => {
+                    if requests.contains_key(&event.request_id) {
+                        responses.insert(
+                            event.request_id.clone(),
+                            (event.response.status as u16, recorded_headers(&event.response.headers)),
+                        );
+                    }
+                }
+                Some(event) = finished.next() => {
+                    if let Some((method, url, request_headers)) = requests.remove(&event.request_id) {
+                        let (status, response_headers) = responses.remove(&event.request_id).unwrap_or_default();
+                        let body = response_body(&page, event.request_id.clone()).await.unwrap_or_default();
+
+                        request_sampling
+                            .record_exchange(&method, &url, &request_headers, &status.to_string(), &response_headers, &body)
+                            .await;
+                    }
+                }
+                else => break,
+            }
+        }
+    });
+}
+
+/// Share the cacheable subresources received by the page with the other pages of the crawl, and fulfill the repeated
+/// requests of the page from the cache.
+async fn setup_fulfill_cache(page: &Page, fulfill_cache: &crate::configuration::FulfillCacheConfig) {
+    use chromiumoxide::cdp::browser_protocol::network::{
+        EventLoadingFinished, EventResponseReceived,
+    };
+    use chromiumoxide::handler::fulfill_cache::{CachedBody, CachedResponse, FulfillCache};
+
+    if page
+        .set_fulfill_cache(Some(fulfill_cache.cache().clone()))
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    let (received, finished) = tokio::join!(
+        page.event_listener::<EventResponseReceived>(),
+        page.event_listener::<EventLoadingFinished>()
+    );
+
+    let (mut received, mut finished) = match (received, finished) {
+        (Ok(received), Ok(finished)) => (received, finished),
+        _ => return,
+    };
+
+    let page = page.clone();
+    let fulfill_cache = fulfill_cache.clone();
+
+    tokio::task::spawn(async move {
+        let cache = fulfill_cache.cache();
+        let mut pending = hashbrown::HashMap::new();
+
+        loop {
+            tokio::select! {
+                Some(event) = received.next() => {
+                    let response = &event.response;
+
+                    if response.status != 200 || !FulfillCache::is_cacheable(&event.r#type, "GET") {
+                        continue;
+                    }
+
+                    let headers = recorded_headers(&response.headers);
+                    let header = |name: &str| {
+                        headers
+                            .iter()
+                            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                            .map(|(_, value)| value.as_str())
+                    };
+
+                    if header("cache-control").is_some_and(|value| value.contains("no-store")) {
+                        continue;
+                    }
+
+                    let validator = match header("etag").or_else(|| header("last-modified")) {
+                        Some(validator) => validator.to_string(),
+                        _ => continue,
+                    };
+
+                    // the requests fulfilled from the cache are received again with the same validator.
+                    if !cache.contains(&response.url, &validator) {
+                        pending.insert(
+                            event.request_id.clone(),
+                            (response.url.clone(), headers, validator),
+                        );
+                    }
+                }
+                Some(event) = finished.next() => {
+                    if let Some((url, headers, validator)) = pending.remove(&event.request_id) {
+                        let body = match response_body(&page, event.request_id.clone()).await {
+                            Some(body) if body.len() <= fulfill_cache.max_entry_bytes => body,
+                            _ => continue,
+                        };
+
+                        let body = if cache.memory_bytes() + body.len() <= fulfill_cache.max_memory_bytes {
+                            CachedBody::Memory(body.into())
+                        } else {
+                            match fulfill_cache.body_path(&url) {
+                                Some(_) if cache.disk_bytes() + body.len() > fulfill_cache.max_disk_bytes => continue,
+                                Some(path) => {
+                                    if let Some(dir) = path.parent() {
+                                        let _ = tokio::fs::create_dir_all(dir).await;
+                                    }
+                                    match tokio::fs::write(&path, &body).await {
+                                        Ok(_) => CachedBody::Disk(path, body.len()),
+                                        Err(e) => {
+                                            log::warn!("fulfill cache error: {:?}", e);
+                                            continue;
+                                        }
+                                    }
+                                }
+                                _ => continue,
+                            }
+                        };
+
+                        cache.insert(
+                            url,
+                            CachedResponse {
+                                status: 200,
+                                headers,
+                                validator,
+                                body,
+                            },
+                        );
+                    }
+                }
+                else => break,
+            }
+        }
+    });
+}
+
 /// configure the browser
 pub async fn configure_browser(new_page: &Page, configuration: &Configuration) {
     let timezone_id = async {
@@ -419,7 +1307,27 @@ pub async fn configure_browser(new_page: &Page, configuration: &Configuration) {
         }
     };
 
-    tokio::join!(timezone_id, locale);
+    let geolocation = async {
+        if let Some(geolocation) = configuration.geolocation.as_deref() {
+            // the position is only readable after the permission is granted for the context of the page.
+            let browser_context_id = page_browser_context_id(new_page).await;
+            let mut grant =
+                chromiumoxide::cdp::browser_protocol::browser::GrantPermissionsParams::new(vec![
+                    chromiumoxide::cdp::browser_protocol::browser::PermissionType::Geolocation,
+                ]);
+            grant.browser_context_id = browser_context_id;
+            let _ = new_page.execute(grant).await;
+            let _ = new_page.emulate_geolocation(geolocation.clone()).await;
+        }
+    };
+
+    let cpu_throttling = async {
+        if let Some(rate) = configuration.cpu_throttling_rate {
+            let _ = new_page.emulate_cpu_throttling(rate.max(1.0)).await;
+        }
+    };
+
+    tokio::join!(timezone_id, locale, geolocation, cpu_throttling);
 }
 
 /// attempt to navigate to a page respecting the request timeout. This will attempt to get a response for up to 60 seconds. There is a bug in the browser hanging if the CDP connection or handler errors. [https://github.com/mattsse/chromiumoxide/issues/64]
@@ -433,7 +1341,17 @@ pub(crate) async fn attempt_navigation(
     request_timeout: &Option<Box<core::time::Duration>>,
     browser_context_id: &Option<BrowserContextId>,
     viewport: &Option<crate::features::chrome_common::Viewport>,
+    proxy_rotation: &Option<Box<crate::configuration::ProxyRotation>>,
 ) -> Result<Page, CdpError> {
+    let rotated = match proxy_rotation {
+        Some(proxy_rotation) => rotate_proxy_context(browser, proxy_rotation).await,
+        _ => None,
+    };
+    let browser_context_id = match rotated {
+        Some((ref context_id, _)) => &Some(context_id.clone()),
+        _ => browser_context_id,
+    };
+
     let mut cdp_params = CreateTargetParams::new(url);
     cdp_params.background = Some(browser_context_id.is_some());
     cdp_params.browser_context_id.clone_from(browser_context_id);
@@ -458,9 +1376,177 @@ pub(crate) async fn attempt_navigation(
     )
     .await;
 
-    match page_result {
+    let page_result = match page_result {
         Ok(page) => page,
         Err(_) => Err(CdpError::Timeout),
+    };
+
+    match (rotated, proxy_rotation) {
+        (Some((_, index)), Some(proxy_rotation)) => match page_result {
+            Ok(page) => {
+                let proxy = &proxy_rotation.proxies[index];
+                if let (Some(username), Some(password)) = (&proxy.username, &proxy.password) {
+                    let _ = page
+                        .authenticate(chromiumoxide::auth::Credentials {
+                            username: username.clone(),
+                            password: password.clone(),
+                        })
+                        .await;
+                }
+                Ok(page)
+            }
+            Err(e) => {
+                // the context may belong to a browser that closed, create it again on the next page.
+                if let Ok(mut contexts) = proxy_rotation.state.contexts.lock() {
+                    contexts.remove(&index);
+                }
+                Err(e)
+            }
+        },
+        _ => page_result,
+    }
+}
+
+/// Get the browser context of the next proxy in the rotation. The context is created on first use.
+async fn rotate_proxy_context(
+    browser: &Browser,
+    proxy_rotation: &crate::configuration::ProxyRotation,
+) -> Option<(BrowserContextId, usize)> {
+    use chromiumoxide::cdp::browser_protocol::target::CreateBrowserContextParams;
+
+    let index = proxy_rotation.next_index()?;
+
+    if let Some(context_id) = proxy_rotation
+        .state
+        .contexts
+        .lock()
+        .ok()
+        .and_then(|contexts| contexts.get(&index).cloned())
+    {
+        return Some((context_id, index));
+    }
+
+    let proxy = &proxy_rotation.proxies[index];
+    let create_content = CreateBrowserContextParams {
+        dispose_on_detach: Some(true),
+        proxy_server: Some(chrome_proxy_server(&proxy.addr)),
+        proxy_bypass_list: if proxy.addr.starts_with("http://localhost") {
+            Some("<-loopback>".into())
+        } else {
+            None
+        },
+        ..Default::default()
+    };
+
+    match browser.execute(create_content).await {
+        Ok(response) => {
+            let context_id = response.result.browser_context_id;
+            if let Ok(mut contexts) = proxy_rotation.state.contexts.lock() {
+                contexts.insert(index, context_id.clone());
+            }
+            Some((context_id, index))
+        }
+        Err(e) => {
+            log("CDP Error: ", e.to_string());
+            None
+        }
+    }
+}
+
+/// The crash events of the renderer of a page.
+pub(crate) type TargetCrashedListener = chromiumoxide::listeners::EventStream<
+    chromiumoxide::cdp::browser_protocol::inspector::EventTargetCrashed,
+>;
+
+/// Listen for the crash of the renderer of the page to recover the crawl of the page on a new target.
+pub(crate) async fn listen_target_crashed(page: &Page) -> Option<TargetCrashedListener> {
+    page.event_listener().await.ok()
+}
+
+/// Whether the renderer of the page crashed since the listener was set.
+async fn target_crashed(listener: &mut Option<TargetCrashedListener>) -> bool {
+    match listener {
+        Some(listener) => matches!(
+            tokio::time::timeout(std::time::Duration::ZERO, listener.next()).await,
+            Ok(Some(_))
+        ),
+        _ => false,
+    }
+}
+
+/// Set up a target for the crawl of the url: the events of the page and the interception of the host.
+pub(crate) async fn setup_chrome_page(
+    page: &Page,
+    config: &Configuration,
+    url: &str,
+    host_name: &str,
+) -> Option<JoinHandle<()>> {
+    setup_chrome_events(page, config, url).await;
+
+    setup_chrome_interception_base(
+        page,
+        config.chrome_intercept.enabled,
+        &config.auth_challenge_response,
+        config.chrome_intercept.block_visuals,
+        host_name,
+    )
+    .await
+}
+
+/// A target set up for the crawl of a url with the handle of its interception.
+pub(crate) type ChromeTarget = (Page, Option<JoinHandle<()>>);
+
+/// Open a new target set up for the crawl of the url.
+pub(crate) async fn open_chrome_page(
+    browser: &Browser,
+    browser_context_id: &Option<BrowserContextId>,
+    config: &Configuration,
+    url: &str,
+    host_name: &str,
+) -> Result<ChromeTarget, CdpError> {
+    let page = attempt_navigation(
+        "about:blank",
+        browser,
+        &config.request_timeout,
+        browser_context_id,
+        &config.viewport,
+        &config.proxy_rotation,
+    )
+    .await?;
+
+    let intercept_handle = setup_chrome_page(&page, config, url, host_name).await;
+
+    Ok((page, intercept_handle))
+}
+
+/// Replace the target when its renderer crashed since the listener was set. The new target is set up like the crashed
+/// one and the crashed target is closed. Returns `true` when the target was replaced and the url needs to be rendered
+/// again.
+pub(crate) async fn recover_crashed_target(
+    listener: &mut Option<TargetCrashedListener>,
+    browser: &Browser,
+    browser_context_id: &Option<BrowserContextId>,
+    config: &Configuration,
+    url: &str,
+    host_name: &str,
+    target: &mut ChromeTarget,
+) -> bool {
+    if !target_crashed(listener).await {
+        return false;
+    }
+
+    log("Renderer crashed, replacing the target of ", url);
+
+    match open_chrome_page(browser, browser_context_id, config, url, host_name).await {
+        Ok(replacement) => {
+            let (page, intercept_handle) = std::mem::replace(target, replacement);
+            if let Some(h) = intercept_handle {
+                h.abort();
+            }
+            let _ = page.close().await;
+            true
+        }
+        _ => false,
     }
 }
 
@@ -541,27 +1627,81 @@ pub async fn setup_chrome_interception_base(
     None
 }
 
-/// establish all the page events.
-pub async fn setup_chrome_events(chrome_page: &chromiumoxide::Page, config: &Configuration) {
+/// Get a header value set in the configuration.
+fn get_config_header(config: &Configuration, name: reqwest::header::HeaderName) -> Option<String> {
+    match config.headers {
+        Some(ref headers) => match headers.inner().get(name) {
+            Some(value) => value.to_str().ok().map(|v| v.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The user agent to use for stealth mode. The configured agent is used before the `User-Agent` header.
+fn stealth_user_agent(config: &Configuration) -> Option<String> {
+    match config.user_agent.as_ref() {
+        Some(agent) => Some(agent.to_string()),
+        _ => get_config_header(config, reqwest::header::USER_AGENT),
+    }
+}
+
+/// The user agent to use for the page following the user agent strategy.
+fn page_user_agent(config: &Configuration, url: &str) -> Option<String> {
+    match config.user_agent_strategy.get_user_agent(url) {
+        Some(agent) => Some(agent.to_string()),
+        _ if config.stealth_mode || cfg!(feature = "chrome_stealth") => stealth_user_agent(config),
+        _ => None,
+    }
+}
+
+/// The languages to use for stealth mode. The `Accept-Language` header is used before the locale.
+fn stealth_accept_language(config: &Configuration) -> Option<String> {
+    match get_config_header(config, reqwest::header::ACCEPT_LANGUAGE) {
+        Some(accept_language) => Some(accept_language),
+        _ => config
+            .locale
+            .as_ref()
+            .map(|locale| match locale.split_once('-') {
+                Some((language, _)) => format!("{locale},{language};q=0.9"),
+                _ => locale.to_string(),
+            }),
+    }
+}
+
+/// establish all the page events for the target url.
+pub async fn setup_chrome_events(
+    chrome_page: &chromiumoxide::Page,
+    config: &Configuration,
+    url: &str,
+) {
     let stealth_mode = cfg!(feature = "chrome_stealth") || config.stealth_mode;
     let dismiss_dialogs = config.dismiss_dialogs.unwrap_or(true); // polyfill window.alert.
+    let user_agent = page_user_agent(config, url);
 
     let stealth = async {
         if stealth_mode {
-            match config.user_agent.as_ref() {
-                Some(agent) => {
-                    let _ = if dismiss_dialogs {
-                        chrome_page
-                            .enable_stealth_mode_with_agent_and_dimiss_dialogs(agent)
-                            .await
-                    } else {
-                        chrome_page.enable_stealth_mode_with_agent(agent).await
-                    };
+            match user_agent {
+                Some(ref agent) => {
+                    let _ = chrome_page
+                        .enable_stealth_mode_with_agent_and_languages(
+                            agent,
+                            stealth_accept_language(config).as_deref(),
+                            dismiss_dialogs,
+                        )
+                        .await;
                 }
                 _ => {
                     let _ = chrome_page.enable_stealth_mode().await;
                 }
             }
+        } else if let Some(ref agent) = user_agent {
+            let _ = chrome_page
+                .set_user_agent_with_headers(chromiumoxide::page::stealth_user_agent_params(
+                    agent,
+                    stealth_accept_language(config).as_deref(),
+                ))
+                .await;
         }
     };
 
@@ -610,7 +1750,204 @@ pub async fn setup_chrome_events(chrome_page: &chromiumoxide::Page, config: &Con
         }
     };
 
-    tokio::join!(stealth, eval_docs, configure_browser(&chrome_page, &config));
+    let consent_mode = async {
+        if let Some(consent_mode) = config.consent_mode.as_deref() {
+            let _ = chrome_page
+                .set_third_party_policy(Some(consent_mode.third_party_policy(url)))
+                .await;
+            let _ = chrome_page
+                .execute(
+                    chromiumoxide::cdp::browser_protocol::emulation::SetDocumentCookieDisabledParams::new(
+                        true,
+                    ),
+                )
+                .await;
+            let _ = chrome_page.set_block_response_cookies(true).await;
+        }
+    };
+
+    let mock_router = async {
+        let mut router: Option<chromiumoxide::handler::network::MockRouter> =
+            config.mock_router.as_deref().map(|r| r.clone().into());
+
+        if let Some(network_recording) = config.network_recording.as_deref() {
+            match network_recording.mode {
+                crate::configuration::RecordingMode::Replay => {
+                    // the mocked routes still take precedence to tweak responses of the recording.
+                    let replay = network_recording.replay_router().await;
+                    let router = router.get_or_insert_with(Default::default);
+                    router.exact = replay.exact;
+                    router.offline = true;
+                }
+                crate::configuration::RecordingMode::Record => {
+                    setup_network_recording(chrome_page, network_recording).await;
+                }
+            }
+        }
+
+        if router.is_some() {
+            let _ = chrome_page.set_mock_router(router).await;
+        }
+    };
+
+    let robots_txt = async {
+        if let Some(rules) = config.robots_txt_rules.clone() {
+            if let Some(host) = Url::parse(url)
+                .ok()
+                .and_then(|u| u.host_str().map(String::from))
+            {
+                let user_agent = match config.user_agent {
+                    Some(ref ua) => ua.to_string(),
+                    _ => "*".into(),
+                };
+                // the rules only apply to the host crawled, other hosts have their own robots.txt.
+                let filter = chromiumoxide::handler::network::RequestFilter::new(
+                    move |request_url, _resource_type| match Url::parse(request_url) {
+                        Ok(u) => {
+                            u.host_str() == Some(host.as_str())
+                                && !rules.can_fetch(&user_agent, request_url)
+                        }
+                        _ => false,
+                    },
+                );
+                let _ = chrome_page.set_request_filter(Some(filter)).await;
+            }
+        }
+    };
+
+    let cosmetic_filters = async {
+        // hide the placeholders of the ads blocked so they do not show in the screenshots and the text.
+        #[cfg(feature = "adblock")]
+        let _ = chrome_page.enable_cosmetic_filtering(url).await;
+    };
+
+    let bypass_csp = async {
+        if config.bypass_csp {
+            let _ = chrome_page.set_bypass_csp(true).await;
+        }
+    };
+
+    let storage_quota = async {
+        if let Some(quota) = config
+            .browser_storage_limits
+            .as_deref()
+            .and_then(|limits| limits.storage_quota)
+        {
+            if let Ok(u) = Url::parse(url) {
+                let _ = chrome_page
+                    .execute(
+                        chromiumoxide::cdp::browser_protocol::storage::OverrideQuotaForOriginParams {
+                            origin: u.origin().ascii_serialization(),
+                            quota_size: Some(quota as f64),
+                        },
+                    )
+                    .await;
+            }
+        }
+    };
+
+    let fulfill_cache = async {
+        if let Some(fulfill_cache) = config.fulfill_cache.as_deref() {
+            setup_fulfill_cache(chrome_page, fulfill_cache).await;
+        }
+    };
+
+    let request_sampling = async {
+        if let Some(request_sampling) = config.request_sampling.as_deref() {
+            setup_request_sampling(chrome_page, request_sampling).await;
+        }
+    };
+
+    let stream_tracker = async {
+        let abort_streams = config
+            .wait_for
+            .as_ref()
+            .and_then(|wait_for| wait_for.idle_network.as_ref())
+            .is_some_and(|idle_network| idle_network.abort_streams_after.is_some());
+
+        if abort_streams {
+            let _ = chrome_page
+                .evaluate_on_new_document(STREAM_TRACKER_JS)
+                .await;
+        }
+    };
+
+    let max_response_bytes = async {
+        if let Some(max_response_bytes) = config.max_response_bytes.as_deref() {
+            let _ = chrome_page
+                .set_max_response_bytes(max_response_bytes.into())
+                .await;
+        }
+    };
+
+    let download_capture = async {
+        if let Some(download_capture) = config.download_capture.as_deref() {
+            setup_download_capture(chrome_page, download_capture, url).await;
+        }
+    };
+
+    let crawl_events = async {
+        if let Some(crawl_events) = config.crawl_events.as_deref() {
+            setup_crawl_events(chrome_page, crawl_events, url).await;
+        }
+    };
+
+    let proxy_auth = async {
+        if config.proxy_rotation.is_none() {
+            if let Some(credentials) = proxy_credentials(&config.proxies) {
+                let _ = chrome_page.authenticate(credentials).await;
+            }
+        }
+    };
+
+    let redirect_policy = async {
+        if let Some(policy) = chrome_redirect_policy(config) {
+            let _ = chrome_page.set_redirect_policy(Some(policy)).await;
+        }
+    };
+
+    tokio::join!(
+        stealth,
+        eval_docs,
+        consent_mode,
+        mock_router,
+        fulfill_cache,
+        request_sampling,
+        max_response_bytes,
+        stream_tracker,
+        robots_txt,
+        download_capture,
+        crawl_events,
+        storage_quota,
+        redirect_policy,
+        proxy_auth,
+        bypass_csp,
+        cosmetic_filters,
+        configure_browser(&chrome_page, &config)
+    );
+}
+
+/// The redirect policy of the document requests of the browser, deciding the redirects with
+/// [`RedirectPolicy::allows`](crate::configuration::RedirectPolicy::allows) like the HTTP client. The loose policy
+/// keeps the redirect handling of Chrome and the strict policy stays on the host, or the registrable domain when
+/// subdomains or tlds are crawled.
+pub fn chrome_redirect_policy(
+    config: &Configuration,
+) -> Option<chromiumoxide::handler::network::RedirectPolicy> {
+    use crate::configuration::RedirectPolicy;
+
+    let policy = match config.redirect_policy {
+        RedirectPolicy::Loose => return None,
+        RedirectPolicy::Strict if config.subdomains || config.tld => {
+            RedirectPolicy::SameRegistrableDomain
+        }
+        RedirectPolicy::Strict => RedirectPolicy::SameHost,
+        ref policy => policy.clone(),
+    };
+
+    Some(chromiumoxide::handler::network::RedirectPolicy::new(
+        move |origin, target, hops| policy.allows(origin, target, hops),
+    ))
 }
 
 /// static chrome arguments to start
@@ -822,7 +2159,180 @@ static CHROME_ARGS: [&'static str; 63] = [
     "--disable-features=InterestFeedContentSuggestions,PrivacySandboxSettings4,AutofillServerCommunication,CalculateNativeWinOcclusion,OptimizationHints,AudioServiceOutOfProcess,IsolateOrigins,site-per-process,ImprovedCookieControls,LazyFrameLoading,GlobalMediaControls,DestroyProfileOnBrowserClose,MediaRouter,DialMediaRouteProvider,AcceptCHFrame,AutoExpandDetailsElement,CertificateTransparencyComponentUpdater,AvoidUnnecessaryBeforeUnloadCheckSync,Translate"
 ];
 
+/// Track the event sources, the xhr and the fetch requests of the page so the ones left open can be closed.
+pub(crate) static STREAM_TRACKER_JS: &'static str = r#"(()=>{const open=new Set(),track=close=>{const entry={close};open.add(entry);return entry},hidden=(name,value)=>Object.defineProperty(window,name,{value,enumerable:false,configurable:true});if(window.EventSource){const NativeEventSource=window.EventSource;window.EventSource=new Proxy(NativeEventSource,{construct(target,args,newTarget){const source=Reflect.construct(target,args,newTarget),entry=track(()=>source.close());source.addEventListener("error",()=>{source.readyState===2&&open.delete(entry)});return source}})}const send=XMLHttpRequest.prototype.send;XMLHttpRequest.prototype.send=function(...args){const entry=track(()=>this.abort());this.addEventListener("loadend",()=>open.delete(entry));return send.apply(this,args)};const nativeFetch=window.fetch;window.fetch=function(input,init={}){const controller=new AbortController(),signal=init&&init.signal;signal&&signal.addEventListener("abort",()=>controller.abort(signal.reason));const entry=track(()=>controller.abort());return nativeFetch.call(this,input,{...init,signal:controller.signal}).then(response=>{(response.headers.get("content-type")||"").includes("text/event-stream")||open.delete(entry);return response},error=>{open.delete(entry);throw error})};hidden("__spiderOpenStreams",()=>open.size);hidden("__spiderCloseStreams",()=>{const closed=open.size;open.forEach(entry=>{try{entry.close()}catch(_){}});open.clear();return closed})})();"#;
+
+/// Close the event sources and the requests of the page still open once `after` passed since the network went idle, so live
+/// feeds and long polls do not keep the target busy. Requires the [`STREAM_TRACKER_JS`] added to the documents of the page.
+pub async fn abort_open_streams(page: &chromiumoxide::Page, after: std::time::Duration) {
+    let open_streams = |script: &'static str| async move {
+        match page.evaluate(script).await {
+            Ok(result) => result.into_value::<u64>().unwrap_or_default(),
+            _ => 0,
+        }
+    };
+
+    if open_streams("window.__spiderOpenStreams ? window.__spiderOpenStreams() : 0").await > 0 {
+        tokio::time::sleep(after).await;
+
+        let closed =
+            open_streams("window.__spiderCloseStreams ? window.__spiderCloseStreams() : 0").await;
+
+        if closed > 0 {
+            log("Closed the streams left open after the network idle: ", closed.to_string());
+        }
+    }
+}
+
+/// The anti-bot interstitial shown by the page as `cloudflare`, `turnstile` or `queue_it`, else `null`. A Turnstile widget is a
+/// challenge only when the page shows little else, the widgets of the login forms are kept.
+pub(crate) static CHALLENGE_DETECT_JS: &'static str = r##"(()=>{const has=selector=>!!document.querySelector(selector),text=(document.body&&document.body.innerText||"").trim().length;if(location.hostname.endsWith("queue-it.net")||has("#MainPart_divProgressbar, #queue-it_log")||typeof window.queueViewModel!=="undefined")return"queue_it";if(typeof window._cf_chl_opt!=="undefined"||has("#challenge-form, #challenge-running, #challenge-stage, .cf-browser-verification")||/^(Just a moment|Attention Required|Checking your browser)/i.test(document.title||""))return"cloudflare";if(has(".cf-turnstile, iframe[src*='challenges.cloudflare.com']")&&text<500)return"turnstile";return null})()"##;
+
+/// The interval between the checks of a challenge.
+const CHALLENGE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Wait for the anti-bot challenge of the page to clear, up to the timeout of the config. Returns `None` when the page shows no
+/// challenge. The checks failing while the challenge navigates to the page behind it do not count as cleared.
+pub async fn wait_for_challenge(
+    page: &chromiumoxide::Page,
+    config: &crate::configuration::WaitForChallenge,
+) -> Option<crate::configuration::ChallengeOutcome> {
+    let detect = || async {
+        match page.evaluate(CHALLENGE_DETECT_JS).await {
+            Ok(result) => Some(
+                result
+                    .into_value::<Option<String>>()
+                    .ok()
+                    .flatten()
+                    .and_then(|name| crate::configuration::ChallengeKind::from_name(&name)),
+            ),
+            _ => None,
+        }
+    };
+
+    let kind = detect().await.flatten()?;
+    let started = tokio::time::Instant::now();
+    let mut cleared = false;
+
+    while started.elapsed() < config.timeout {
+        tokio::time::sleep(CHALLENGE_POLL_INTERVAL).await;
+
+        if let Some(None) = detect().await {
+            cleared = true;
+            break;
+        }
+    }
+
+    let outcome = crate::configuration::ChallengeOutcome {
+        kind,
+        cleared,
+        waited: started.elapsed(),
+    };
+
+    log("Challenge found on the page: ", format!("{:?}", outcome));
+
+    Some(outcome)
+}
+
 /// Fingerprint handling
 pub(crate) static FP_JS: &'static str = r#"const toBlob=HTMLCanvasElement.prototype.toBlob,toDataURL=HTMLCanvasElement.prototype.toDataURL,getImageData=CanvasRenderingContext2D.prototype.getImageData,noisify=function(e,t){let o={r:Math.floor(10*Math.random())-5,g:Math.floor(10*Math.random())-5,b:Math.floor(10*Math.random())-5,a:Math.floor(10*Math.random())-5},r=e.width,n=e.height,a=getImageData.apply(t,[0,0,r,n]);for(let i=0;i<n;i++)for(let f=0;f<r;f++){let l=i*(4*r)+4*f;a.data[l+0]=a.data[l+0]+o.r,a.data[l+1]=a.data[l+1]+o.g,a.data[l+2]=a.data[l+2]+o.b,a.data[l+3]=a.data[l+3]+o.a}t.putImageData(a,0,0)};Object.defineProperty(HTMLCanvasElement.prototype,"toBlob",{value:function(){return noisify(this,this.getContext("2d")),toBlob.apply(this,arguments)}}),Object.defineProperty(HTMLCanvasElement.prototype,"toDataURL",{value:function(){return noisify(this,this.getContext("2d")),toDataURL.apply(this,arguments)}}),Object.defineProperty(CanvasRenderingContext2D.prototype,"getImageData",{value:function(){return noisify(this.canvas,this),getImageData.apply(this,arguments)}});const config={random:{value:function(){return Math.random()},item:function(e){let t=e.length*config.random.value();return e[Math.floor(t)]},array:function(e){let t=config.random.item(e);return new Int32Array([t,t])},items:function(e,t){let o=e.length,r=Array(t),n=Array(o);for(t>o&&(t=o);t--;){let a=Math.floor(config.random.value()*o);r[t]=e[a in n?n[a]:a],n[a]=--o in n?n[o]:o}return r}},spoof:{webgl:{buffer:function(e){let t=e.prototype.bufferData;Object.defineProperty(e.prototype,"bufferData",{value:function(){let e=Math.floor(10*config.random.value()),o=.1*config.random.value()*arguments[1][e];return arguments[1][e]=arguments[1][e]+o,t.apply(this,arguments)}})},parameter:function(e){e.prototype.getParameter,Object.defineProperty(e.prototype,"getParameter",{value:function(){let e=new Float32Array([1,8192]);if(3415===arguments[0])return 0;if(3414===arguments[0])return 24;if(35661===arguments[0])return config.random.items([128,192,256]);if(3386===arguments[0])return config.random.array([8192,16384,32768]);if(36349===arguments[0]||36347===arguments[0])return config.random.item([4096,8192]);else if(34047===arguments[0]||34921===arguments[0])return config.random.items([2,4,8,16]);else if(7937===arguments[0]||33901===arguments[0]||33902===arguments[0])return e;else if(34930===arguments[0]||36348===arguments[0]||35660===arguments[0])return config.random.item([16,32,64]);else if(34076===arguments[0]||34024===arguments[0]||3379===arguments[0])return config.random.item([16384,32768]);else if(3413===arguments[0]||3412===arguments[0]||3411===arguments[0]||3410===arguments[0]||34852===arguments[0])return config.random.item([2,4,8,16]);else return config.random.item([0,2,4,8,16,32,64,128,256,512,1024,2048,4096,])}})}}}};config.spoof.webgl.buffer(WebGLRenderingContext),config.spoof.webgl.buffer(WebGL2RenderingContext),config.spoof.webgl.parameter(WebGLRenderingContext),config.spoof.webgl.parameter(WebGL2RenderingContext);const rand={noise:function(){return Math.floor(Math.random()+(Math.random()<Math.random()?-1:1)*Math.random())},sign:function(){let e=[-1,-1,-1,-1,-1,-1,1,-1,-1,-1],t=Math.floor(Math.random()*e.length);return e[t]}};Object.defineProperty(HTMLElement.prototype,"offsetHeight",{get(){let e=Math.floor(this.getBoundingClientRect().height),t=e&&1===rand.sign(),o=t?e+rand.noise():e;return o}}),Object.defineProperty(HTMLElement.prototype,"offsetWidth",{get(){let e=Math.floor(this.getBoundingClientRect().width),t=e&&1===rand.sign(),o=t?e+rand.noise():e;return o}});const context={BUFFER:null,getChannelData:function(e){let t=e.prototype.getChannelData;Object.defineProperty(e.prototype,"getChannelData",{value:function(){let e=t.apply(this,arguments);if(context.BUFFER!==e){context.BUFFER=e;for(let o=0;o<e.length;o+=100){let r=Math.floor(Math.random()*o);e[r]=e[r]+1e-7*Math.random()}}return e}})},createAnalyser:function(e){let t=e.prototype.__proto__.createAnalyser;Object.defineProperty(e.prototype.__proto__,"createAnalyser",{value:function(){let e=t.apply(this,arguments),o=e.__proto__.getFloatFrequencyData;return Object.defineProperty(e.__proto__,"getFloatFrequencyData",{value:function(){let e=o.apply(this,arguments);for(let t=0;t<arguments[0].length;t+=100){let r=Math.floor(Math.random()*t);arguments[0][r]=arguments[0][r]+.1*Math.random()}return e}}),e}})}};context.getChannelData(AudioBuffer),context.createAnalyser(AudioContext),context.getChannelData(OfflineAudioContext),context.createAnalyser(OfflineAudioContext),navigator.mediaDevices.getUserMedia=navigator.webkitGetUserMedia=navigator.mozGetUserMedia=navigator.getUserMedia=webkitRTCPeerConnection=RTCPeerConnection=MediaStreamTrack=void 0;const getParameter=WebGLRenderingContext.prototype.getParameter;WebGLRenderingContext.prototype.getParameter=function(e){return 37445===e?"Intel Open Source Technology Center":37446===e?"Mesa DRI Intel(R) Ivybridge Mobile ":getParameter.call(this,e)};const newProto=navigator.__proto__;delete newProto.webdriver,navigator.__proto__=newProto;"#;
 /// Handle extracting links from anchors that are not found.
 pub(crate) static ANCHOR_EVENTS: &'static str = r###"() => new Promise((resolve) => { const _pageRoutes = new Set(), _originalPushState = window.history.pushState; window.history.pushState = function(_state, _title, _url) { _pageRoutes.add(_url) }; function _onRouteChange() { _pageRoutes.add(window.location.href) } document.querySelectorAll("a:not([href])").forEach(_anchor => { _anchor.click() }); window.addEventListener("popstate", _onRouteChange); return resolve(Array.from(_pageRoutes)); } )"###;
+
+/// Collect the `href` of the anchors inside the open shadow roots and the same-origin iframes of the page. The anchors of the
+/// light DOM of the top document are skipped, they are part of the html of the page. Cross-origin frames have no `contentDocument`.
+pub(crate) static DEEP_LINKS: &'static str = r###"(() => { const links = new Set(); const visit = (root, deep) => { for (const el of root.querySelectorAll("*")) { if (deep && (el.localName === "a" || el.localName === "area") && typeof el.href === "string" && el.href) { links.add(el.href) } if (el.shadowRoot) { visit(el.shadowRoot, true) } if (el.localName === "iframe" || el.localName === "frame") { try { if (el.contentDocument) { visit(el.contentDocument, true) } } catch (_) {} } } }; visit(document, false); return Array.from(links); })()"###;
+
+/// The paint and navigation timings of the page in milliseconds since the start of the navigation as `[fcp, lcp, dcl, load]`. The
+/// largest contentful paint entries are buffered by the browser and read with a `PerformanceObserver`.
+pub(crate) static PAINT_TIMINGS: &'static str = r###"new Promise((resolve) => { const timings = [null, null, null, null]; const paint = performance.getEntriesByName("first-contentful-paint")[0]; if (paint) { timings[0] = paint.startTime } const nav = performance.getEntriesByType("navigation")[0]; if (nav) { timings[2] = nav.domContentLoadedEventEnd || null; timings[3] = nav.loadEventEnd || null } try { const observer = new PerformanceObserver((list) => { const entries = list.getEntries(); if (entries.length) { timings[1] = entries[entries.length - 1].startTime } }); observer.observe({ type: "largest-contentful-paint", buffered: true }); setTimeout(() => { observer.disconnect(); resolve(timings) }, 0) } catch (_) { resolve(timings) } })"###;
+
+/// The waterfall of the requests of the page in the order they started with their phases.
+fn request_waterfall(
+    mut waterfall: Vec<chromiumoxide::handler::waterfall::WaterfallEntry>,
+) -> Vec<crate::configuration::RequestWaterfallEntry> {
+    waterfall.sort_by(|a, b| {
+        a.timestamps
+            .request_will_be_sent
+            .unwrap_or_default()
+            .total_cmp(&b.timestamps.request_will_be_sent.unwrap_or_default())
+    });
+
+    let origin = waterfall
+        .first()
+        .and_then(|entry| entry.timestamps.request_will_be_sent)
+        .unwrap_or_default();
+
+    waterfall
+        .into_iter()
+        .map(|entry| {
+            let phases = entry.phases();
+
+            crate::configuration::RequestWaterfallEntry {
+                start: entry.start(origin),
+                queued: phases.queued,
+                dns: phases.dns,
+                connect: phases.connect,
+                tls: phases.tls,
+                ttfb: phases.ttfb,
+                download: phases.download,
+                total: phases.total,
+                encoded_data_length: entry.encoded_data_length,
+                status: entry.status.and_then(|status| u16::try_from(status).ok()),
+                failure_text: entry.failure_text,
+                resource_type: entry.resource_type,
+                url: entry.url,
+            }
+        })
+        .collect()
+}
+
+/// Collect the `Performance.getMetrics` metrics of the loaded page and the paint timings when configured.
+pub async fn performance_metrics(
+    page: &Page,
+    config: &crate::configuration::PerformanceMetricsConfig,
+) -> Option<crate::configuration::PerformanceMetrics> {
+    use chromiumoxide::cdp::browser_protocol::performance::EnableParams;
+
+    let mut performance_metrics = crate::configuration::PerformanceMetrics::default();
+
+    if page.execute(EnableParams::default()).await.is_ok() {
+        if let Ok(metrics) = page.metrics().await {
+            performance_metrics.metrics = metrics
+                .into_iter()
+                .map(|metric| (metric.name, metric.value))
+                .collect();
+        }
+    }
+
+    if config.paint_timings {
+        let timings = tokio::time::timeout(
+            tokio::time::Duration::from_secs(5),
+            page.evaluate(PAINT_TIMINGS),
+        )
+        .await;
+
+        if let Ok(Ok(timings)) = timings {
+            if let Ok(timings) = timings.into_value::<Vec<Option<f64>>>() {
+                let timing = |i: usize| timings.get(i).copied().flatten();
+
+                performance_metrics.first_contentful_paint = timing(0);
+                performance_metrics.largest_contentful_paint = timing(1);
+                performance_metrics.dom_content_loaded = timing(2);
+                performance_metrics.load = timing(3);
+            }
+        }
+    }
+
+    if config.request_waterfall {
+        if let Ok(waterfall) = page.request_waterfall().await {
+            performance_metrics.waterfall = request_waterfall(waterfall);
+        }
+    }
+
+    if performance_metrics == Default::default() {
+        None
+    } else {
+        Some(performance_metrics)
+    }
+}
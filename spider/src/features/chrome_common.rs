@@ -6,12 +6,25 @@ use crate::utils::trie::Trie;
 pub struct WaitForIdleNetwork {
     /// The max time to wait for the network. It is recommended to set this to a value around 30s. Set the value to None to remove the timeout.
     pub timeout: Option<core::time::Duration>,
+    /// Close the event sources, long polls and other requests still open this long after the network went idle.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub abort_streams_after: Option<core::time::Duration>,
 }
 
 impl WaitForIdleNetwork {
     /// Create new WaitForIdleNetwork with timeout.
     pub fn new(timeout: Option<core::time::Duration>) -> Self {
-        Self { timeout }
+        Self {
+            timeout,
+            abort_streams_after: None,
+        }
+    }
+
+    /// Close the streaming connections like the server sent events and the long polls still open this long after the network
+    /// went idle, so pages with live feeds do not keep the target busy.
+    pub fn with_abort_streams_after(mut self, after: Option<core::time::Duration>) -> Self {
+        self.abort_streams_after = after;
+        self
     }
 }
 
@@ -47,6 +60,83 @@ impl WaitForDelay {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Wait for the anti-bot interstitials like the Cloudflare "checking your browser" page, the Turnstile pages and the queue-it
+/// waiting rooms to clear before capturing the content. This does nothing without the `chrome` flag enabled.
+pub struct WaitForChallenge {
+    /// The max time to wait for the challenge to clear. Defaults to 30s.
+    pub timeout: core::time::Duration,
+    /// Solve the captcha widget of the page before waiting. This does nothing without the `captcha_solver` flag enabled.
+    #[cfg(feature = "captcha_solver")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub solver: Option<crate::features::captcha_solver::CaptchaSolver>,
+}
+
+impl Default for WaitForChallenge {
+    fn default() -> Self {
+        Self::new(core::time::Duration::from_secs(30))
+    }
+}
+
+impl WaitForChallenge {
+    /// Create new WaitForChallenge with timeout.
+    pub fn new(timeout: core::time::Duration) -> Self {
+        Self {
+            timeout,
+            #[cfg(feature = "captcha_solver")]
+            solver: None,
+        }
+    }
+
+    /// Solve the reCAPTCHA, hCaptcha or Turnstile widget of the page with the solver and inject the token before waiting for
+    /// the challenge to clear.
+    #[cfg(feature = "captcha_solver")]
+    pub fn with_solver(
+        mut self,
+        solver: Option<crate::features::captcha_solver::CaptchaSolver>,
+    ) -> Self {
+        self.solver = solver;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// An anti-bot interstitial served in place of the page.
+pub enum ChallengeKind {
+    /// The Cloudflare "checking your browser" and managed challenges.
+    Cloudflare,
+    /// A page showing only a Cloudflare Turnstile widget.
+    Turnstile,
+    /// A queue-it waiting room.
+    QueueIt,
+}
+
+impl ChallengeKind {
+    /// The challenge from the name returned by the detection script.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cloudflare" => Some(Self::Cloudflare),
+            "turnstile" => Some(Self::Turnstile),
+            "queue_it" => Some(Self::QueueIt),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The outcome of the wait for a challenge found on the page.
+pub struct ChallengeOutcome {
+    /// The challenge found.
+    pub kind: ChallengeKind,
+    /// The challenge cleared before the timeout and the content is the page behind it.
+    pub cleared: bool,
+    /// The time waited for the challenge.
+    pub waited: core::time::Duration,
+}
+
 #[derive(Debug, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The wait for options for the page. Multiple options can be set. This does nothing without the `chrome` flag enabled.
@@ -62,6 +152,9 @@ pub struct WaitFor {
     #[cfg_attr(feature = "serde", serde(default))]
     /// Wait for page navigations.
     pub page_navigations: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    /// Wait for the anti-bot challenges to clear.
+    pub challenge: Option<WaitForChallenge>,
 }
 
 impl WaitFor {
@@ -88,8 +181,16 @@ impl WaitFor {
             },
             delay,
             dom,
+            challenge: None,
         }
     }
+
+    /// Wait for the anti-bot challenges found on the page to clear with a bounded timeout before capturing the content. The outcome
+    /// is reported on the page.
+    pub fn with_challenge(mut self, challenge: Option<WaitForChallenge>) -> Self {
+        self.challenge = challenge;
+        self
+    }
 }
 
 #[derive(
@@ -194,6 +295,96 @@ impl Viewport {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Device presets to emulate. A preset sets the viewport size, scale factor, touch support and user agent together.
+pub enum DevicePreset {
+    /// Apple iPhone 15 in portrait.
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "iphone", alias = "iphone-15", alias = "IPhone15")
+    )]
+    IPhone15,
+    /// Apple iPhone SE in portrait.
+    #[cfg_attr(feature = "serde", serde(rename = "iphone-se", alias = "IPhoneSE"))]
+    IPhoneSE,
+    /// Apple iPad Pro 12.9 in portrait.
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "ipad", alias = "ipad-pro", alias = "IPadPro")
+    )]
+    IPadPro,
+    /// Google Pixel 8 in portrait.
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "pixel", alias = "pixel-8", alias = "Pixel8")
+    )]
+    Pixel8,
+    /// Samsung Galaxy S23 in portrait.
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "galaxy", alias = "galaxy-s23", alias = "GalaxyS23")
+    )]
+    GalaxyS23,
+    /// A 1366x768 laptop screen.
+    #[cfg_attr(feature = "serde", serde(rename = "laptop", alias = "Laptop"))]
+    Laptop,
+    #[default]
+    /// A 1920x1080 desktop screen.
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "desktop-1080p", alias = "desktop", alias = "Desktop1080p")
+    )]
+    Desktop1080p,
+    /// A 2560x1440 desktop screen.
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "desktop-1440p", alias = "Desktop1440p")
+    )]
+    Desktop1440p,
+}
+
+impl DevicePreset {
+    /// The viewport of the device.
+    pub fn viewport(&self) -> Viewport {
+        let (width, height, scale, mobile) = match self {
+            DevicePreset::IPhone15 => (393, 852, 3.0, true),
+            DevicePreset::IPhoneSE => (375, 667, 2.0, true),
+            DevicePreset::IPadPro => (1024, 1366, 2.0, true),
+            DevicePreset::Pixel8 => (412, 915, 2.625, true),
+            DevicePreset::GalaxyS23 => (360, 780, 3.0, true),
+            DevicePreset::Laptop => (1366, 768, 1.0, false),
+            DevicePreset::Desktop1080p => (1920, 1080, 1.0, false),
+            DevicePreset::Desktop1440p => (2560, 1440, 1.0, false),
+        };
+
+        Viewport {
+            width,
+            height,
+            device_scale_factor: Some(scale),
+            emulating_mobile: mobile,
+            is_landscape: false,
+            has_touch: mobile,
+        }
+    }
+
+    /// The user agent the device browser sends.
+    pub fn user_agent(&self) -> &'static str {
+        match self {
+            DevicePreset::IPhone15 | DevicePreset::IPhoneSE => "Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1",
+            DevicePreset::IPadPro => "Mozilla/5.0 (iPad; CPU OS 17_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1",
+            DevicePreset::Pixel8 => "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Mobile Safari/537.36",
+            DevicePreset::GalaxyS23 => "Mozilla/5.0 (Linux; Android 14; SM-S911B) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Mobile Safari/537.36",
+            DevicePreset::Laptop | DevicePreset::Desktop1080p | DevicePreset::Desktop1440p => "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36",
+        }
+    }
+
+    /// Is the device a phone or tablet.
+    pub fn is_mobile(&self) -> bool {
+        self.viewport().emulating_mobile
+    }
+}
+
 #[cfg(feature = "chrome")]
 impl From<Viewport> for chromiumoxide::handler::viewport::Viewport {
     fn from(viewport: Viewport) -> Self {
@@ -208,6 +399,842 @@ impl From<Viewport> for chromiumoxide::handler::viewport::Viewport {
     }
 }
 
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The geolocation position to emulate for chrome.
+pub struct Geolocation {
+    /// Latitude in degrees.
+    pub latitude: f64,
+    /// Longitude in degrees.
+    pub longitude: f64,
+    /// Accuracy in meters. Defaults to 1 meter.
+    pub accuracy: Option<f64>,
+}
+
+impl Geolocation {
+    /// Create a new geolocation position.
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        Self {
+            latitude,
+            longitude,
+            accuracy: None,
+        }
+    }
+}
+
+#[cfg(feature = "chrome")]
+impl From<Geolocation>
+    for chromiumoxide::cdp::browser_protocol::emulation::SetGeolocationOverrideParams
+{
+    fn from(geolocation: Geolocation) -> Self {
+        Self {
+            latitude: Some(geolocation.latitude),
+            longitude: Some(geolocation.longitude),
+            accuracy: Some(geolocation.accuracy.unwrap_or(1.0)),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A strict privacy profile for chrome. Every request outside of the registrable domain of the page is blocked unless allowed,
+/// the documents excepted so the redirects of the page still load. The document cookies are disabled, the `Set-Cookie`
+/// headers of the responses are removed and the configured cookies are not sent. The blocked requests are kept on the page.
+pub struct ConsentMode {
+    /// Registrable domains allowed to load besides the page ex: `cdn-partner.com`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub allowlist: Vec<String>,
+}
+
+impl ConsentMode {
+    /// Block every third party request.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow the domains to load.
+    pub fn with_allowlist(mut self, allowlist: Vec<String>) -> Self {
+        self.allowlist = allowlist;
+        self
+    }
+
+    /// Whether the request url is outside of the registrable domain of the page url and the allowlist. Urls without a
+    /// host like `data:` are never third parties.
+    pub fn is_third_party(&self, page_url: &str, url: &str) -> bool {
+        self.third_party(page_url)(url)
+    }
+
+    /// The third party check of the page url.
+    fn third_party(&self, page_url: &str) -> impl Fn(&str) -> bool + Send + Sync + 'static {
+        let domain = |url: &str| {
+            url::Url::parse(url).ok().and_then(|u| {
+                u.host_str()
+                    .map(|host| crate::utils::registrable_domain(host).to_ascii_lowercase())
+            })
+        };
+        let first_party = domain(page_url).unwrap_or_default();
+        let allowlist: Vec<String> = self
+            .allowlist
+            .iter()
+            .map(|allowed| crate::utils::registrable_domain(allowed).to_ascii_lowercase())
+            .collect();
+
+        move |url: &str| match domain(url) {
+            Some(domain) => domain != first_party && !allowlist.contains(&domain),
+            _ => false,
+        }
+    }
+
+    /// The browser policy blocking the third party requests of the page url.
+    #[cfg(feature = "chrome")]
+    pub fn third_party_policy(
+        &self,
+        page_url: &str,
+    ) -> chromiumoxide::handler::network::ThirdPartyPolicy {
+        chromiumoxide::handler::network::ThirdPartyPolicy::new(self.third_party(page_url))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A canned response answered by the browser for a mocked request.
+pub struct MockResponse {
+    /// The status code of the response.
+    pub status: u16,
+    /// The headers of the response.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub headers: Vec<(String, String)>,
+    /// The body of the response.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub body: Vec<u8>,
+}
+
+impl Default for MockResponse {
+    fn default() -> Self {
+        Self::new(200)
+    }
+}
+
+impl MockResponse {
+    /// A new empty response with the status code.
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Default::default(),
+            body: Default::default(),
+        }
+    }
+
+    /// Add a header to the response.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Set the body of the response.
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Answer the browser requests matching url patterns with canned responses instead of the network.
+/// Patterns match the full url where `*` matches any characters ex: `https://example.com/api/*`. The first route registered that matches is used.
+pub struct MockRouter {
+    /// The url patterns and responses in order.
+    pub routes: Vec<(String, MockResponse)>,
+}
+
+impl MockRouter {
+    /// A new router without routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer the requests matching the pattern with the response.
+    pub fn route(mut self, pattern: &str, response: MockResponse) -> Self {
+        self.routes.push((pattern.into(), response));
+        self
+    }
+}
+
+#[cfg(feature = "chrome")]
+impl From<MockRouter> for chromiumoxide::handler::network::MockRouter {
+    fn from(router: MockRouter) -> Self {
+        Self {
+            routes: router
+                .routes
+                .into_iter()
+                .map(
+                    |(pattern, response)| chromiumoxide::handler::network::MockRoute {
+                        pattern,
+                        status: response.status,
+                        headers: response.headers,
+                        body: response.body,
+                    },
+                )
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Override how the browser is found and launched. Unset options use the platform defaults.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BrowserLaunchOptions {
+    /// The chrome, chromium or edge executable. Defaults to the `CHROME_BIN` env variable then the detection of the usual installs of the platform.
+    pub executable: Option<std::path::PathBuf>,
+    /// Extra arguments passed to the browser after the defaults.
+    pub args: Vec<String>,
+    /// Run the browser sandbox. Defaults to off on Linux where crawls often run as root in containers and on for Windows and macOS.
+    pub sandbox: Option<bool>,
+    /// Use the new headless mode. Defaults to the `chrome_headless_new` flag.
+    pub headless_new: Option<bool>,
+    /// Apply the container defaults: the renderers stop using `/dev/shm`. Defaults to the detection of Docker, Podman and Kubernetes.
+    pub container: Option<bool>,
+}
+
+impl BrowserLaunchOptions {
+    /// Launch options using the platform defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the browser executable path.
+    pub fn with_executable(mut self, executable: impl Into<std::path::PathBuf>) -> Self {
+        self.executable = Some(executable.into());
+        self
+    }
+
+    /// Add an argument passed to the browser.
+    pub fn with_arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Enable or disable the browser sandbox.
+    pub fn with_sandbox(mut self, sandbox: bool) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+
+    /// Use the new or old headless mode.
+    pub fn with_headless_new(mut self, headless_new: bool) -> Self {
+        self.headless_new = Some(headless_new);
+        self
+    }
+
+    /// Force the container defaults on or off instead of detecting the environment.
+    pub fn with_container(mut self, container: bool) -> Self {
+        self.container = Some(container);
+        self
+    }
+}
+
+/// Limit the disk space the browser uses so long crawls do not fill the disk with cache data.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BrowserStorageLimits {
+    /// Maximum size in bytes of the browser disk cache.
+    pub disk_cache_size: Option<u64>,
+    /// Quota in bytes of the IndexedDB, localStorage, cache storage and service worker data of each crawled origin.
+    pub storage_quota: Option<u64>,
+    /// Directory the temporary browser profiles are created in. Defaults to the system temp dir.
+    pub profile_dir: Option<std::path::PathBuf>,
+}
+
+impl BrowserStorageLimits {
+    /// Storage limits using the browser defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the browser disk cache to the size in bytes.
+    pub fn with_disk_cache_size(mut self, disk_cache_size: u64) -> Self {
+        self.disk_cache_size = Some(disk_cache_size);
+        self
+    }
+
+    /// Limit the site storage of each crawled origin to the size in bytes.
+    pub fn with_storage_quota(mut self, storage_quota: u64) -> Self {
+        self.storage_quota = Some(storage_quota);
+        self
+    }
+
+    /// Create the temporary browser profiles in the directory.
+    pub fn with_profile_dir(mut self, profile_dir: impl Into<std::path::PathBuf>) -> Self {
+        self.profile_dir = Some(profile_dir.into());
+        self
+    }
+}
+
+/// Spread the pages of the crawl across a pool of browsers that are recycled when they leak memory or stop answering
+/// instead of rendering with one browser for the whole crawl.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BrowserPoolConfig {
+    /// The browsers kept running, recycled browsers are replaced to stay at this size.
+    pub min_size: usize,
+    /// The most browsers running at once.
+    pub max_size: usize,
+    /// The pages open at once in a browser before a new browser is launched.
+    pub max_pages_per_browser: usize,
+    /// Recycle the browsers running longer than the duration.
+    pub max_lifetime: Option<std::time::Duration>,
+    /// Recycle the browsers using more resident memory in bytes across their processes. Only read on Linux.
+    pub memory_ceiling: Option<u64>,
+    /// How often the browsers are checked. A zero duration only checks the browsers when a page fails to open.
+    pub health_check_interval: std::time::Duration,
+}
+
+impl Default for BrowserPoolConfig {
+    fn default() -> Self {
+        Self {
+            min_size: 1,
+            max_size: 4,
+            max_pages_per_browser: 8,
+            max_lifetime: None,
+            memory_ceiling: None,
+            health_check_interval: std::time::Duration::from_secs(30),
+        }
+    }
+}
+
+impl BrowserPoolConfig {
+    /// A pool of one to four browsers with eight pages each.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the browsers kept running.
+    pub fn with_min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Set the most browsers running at once.
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Set the pages open at once in a browser.
+    pub fn with_max_pages_per_browser(mut self, max_pages_per_browser: usize) -> Self {
+        self.max_pages_per_browser = max_pages_per_browser;
+        self
+    }
+
+    /// Recycle the browsers after the duration.
+    pub fn with_max_lifetime(mut self, max_lifetime: std::time::Duration) -> Self {
+        self.max_lifetime = Some(max_lifetime);
+        self
+    }
+
+    /// Recycle the browsers using more resident memory in bytes.
+    pub fn with_memory_ceiling(mut self, memory_ceiling: u64) -> Self {
+        self.memory_ceiling = Some(memory_ceiling);
+        self
+    }
+
+    /// Set how often the browsers are checked.
+    pub fn with_health_check_interval(
+        mut self,
+        health_check_interval: std::time::Duration,
+    ) -> Self {
+        self.health_check_interval = health_check_interval;
+        self
+    }
+}
+
+/// Connect to a remote browser, like a browserless endpoint or a shared chrome of a crawl fleet, set with the chrome
+/// connection url or the `CHROME_URL` env variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChromeConnectionOptions {
+    /// The token sent as a bearer `Authorization` header. Endpoints taking a `?token=` parameter keep it on the url.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub auth_token: Option<String>,
+    /// The attempts to connect again when the first connection fails or the connection drops during the crawl.
+    pub reconnect_attempts: usize,
+    /// The delay before the first attempt to connect again, doubled on each attempt.
+    pub reconnect_delay: std::time::Duration,
+}
+
+impl Default for ChromeConnectionOptions {
+    fn default() -> Self {
+        Self {
+            auth_token: None,
+            reconnect_attempts: 3,
+            reconnect_delay: std::time::Duration::from_secs(1),
+        }
+    }
+}
+
+impl ChromeConnectionOptions {
+    /// Connection options reconnecting three times.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the token of the remote browser.
+    pub fn with_auth_token(mut self, auth_token: &str) -> Self {
+        self.auth_token = Some(auth_token.into());
+        self
+    }
+
+    /// Set the attempts to connect again.
+    pub fn with_reconnect_attempts(mut self, reconnect_attempts: usize) -> Self {
+        self.reconnect_attempts = reconnect_attempts;
+        self
+    }
+
+    /// Set the delay before the first attempt to connect again.
+    pub fn with_reconnect_delay(mut self, reconnect_delay: std::time::Duration) -> Self {
+        self.reconnect_delay = reconnect_delay;
+        self
+    }
+
+    /// The delay before the attempt to connect again, starting at one.
+    pub fn backoff(&self, attempt: usize) -> std::time::Duration {
+        self.reconnect_delay
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16) as u32)
+    }
+}
+
+/// Render the pages with Firefox or another browser speaking WebDriver BiDi instead of Chrome, for a second engine
+/// fingerprint or to compare the rendering across engines. Only the chrome intercept, the consent mode third parties and
+/// the mocked routes apply to the pages, the other Chrome options need the Chrome DevTools Protocol.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WebDriverBiDiConfig {
+    /// The WebDriver BiDi server of a running browser ex: `ws://127.0.0.1:9222/session`.
+    pub connection_url: Option<String>,
+    /// The browser executable launched when there is no connection url. Defaults to `firefox`.
+    pub executable: Option<std::path::PathBuf>,
+    /// The extra arguments of the launched browser.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub args: Vec<String>,
+}
+
+impl WebDriverBiDiConfig {
+    /// Launch a headless Firefox.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connect to the WebDriver BiDi server of a running browser.
+    pub fn with_connection_url(mut self, connection_url: &str) -> Self {
+        self.connection_url = Some(connection_url.into());
+        self
+    }
+
+    /// Set the browser executable to launch.
+    pub fn with_executable(mut self, executable: impl Into<std::path::PathBuf>) -> Self {
+        self.executable = Some(executable.into());
+        self
+    }
+
+    /// Set the extra arguments of the launched browser.
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+}
+
+/// Collect the performance metrics of the rendered pages after load with the CDP `Performance` domain.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PerformanceMetricsConfig {
+    /// Read the paint and navigation timings of the page ex: the first and largest contentful paint.
+    pub paint_timings: bool,
+    /// Record the timing waterfall of the requests of the page.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub request_waterfall: bool,
+}
+
+impl PerformanceMetricsConfig {
+    /// Collect the `Performance.getMetrics` metrics of the pages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether the paint and navigation timings of the page are read.
+    pub fn with_paint_timings(mut self, paint_timings: bool) -> Self {
+        self.paint_timings = paint_timings;
+        self
+    }
+
+    /// Set whether the timing waterfall of the requests of the page is recorded.
+    pub fn with_request_waterfall(mut self, request_waterfall: bool) -> Self {
+        self.request_waterfall = request_waterfall;
+        self
+    }
+}
+
+/// A request of the timing waterfall of a page. The phases are in milliseconds and `None` when the request skipped
+/// them, like the dns lookup and the connection of a reused connection.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestWaterfallEntry {
+    /// The url requested.
+    pub url: String,
+    /// The resource type of the request ex: `Script`.
+    pub resource_type: Option<String>,
+    /// The status code of the response.
+    pub status: Option<u16>,
+    /// The error of the request when it failed.
+    pub failure_text: Option<String>,
+    /// The time between the first request of the page and the start of the request.
+    pub start: Option<f64>,
+    /// The time queued before the request started.
+    pub queued: Option<f64>,
+    /// The dns lookup.
+    pub dns: Option<f64>,
+    /// The tcp connection without the tls handshake.
+    pub connect: Option<f64>,
+    /// The tls handshake.
+    pub tls: Option<f64>,
+    /// The time to the first byte of the response.
+    pub ttfb: Option<f64>,
+    /// The download of the body.
+    pub download: Option<f64>,
+    /// The whole request.
+    pub total: Option<f64>,
+    /// The bytes received for the request.
+    pub encoded_data_length: Option<f64>,
+}
+
+/// The performance metrics of a page rendered in chrome. The timings are in milliseconds since the start of the navigation.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PerformanceMetrics {
+    /// The `Performance.getMetrics` values by name ex: `JSHeapUsedSize`, `Nodes`, `LayoutDuration` or `ScriptDuration`.
+    pub metrics: std::collections::BTreeMap<String, f64>,
+    /// The time of the first contentful paint.
+    pub first_contentful_paint: Option<f64>,
+    /// The time of the largest contentful paint.
+    pub largest_contentful_paint: Option<f64>,
+    /// The time the `DOMContentLoaded` event ended.
+    pub dom_content_loaded: Option<f64>,
+    /// The time the `load` event ended.
+    pub load: Option<f64>,
+    /// The timing waterfall of the requests of the page in the order they started.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub waterfall: Vec<RequestWaterfallEntry>,
+}
+
+impl PerformanceMetrics {
+    /// The slowest requests of the waterfall by their total time, the slowest first.
+    pub fn slowest_requests(&self, limit: usize) -> Vec<&RequestWaterfallEntry> {
+        let mut requests: Vec<&RequestWaterfallEntry> = self
+            .waterfall
+            .iter()
+            .filter(|request| request.total.is_some())
+            .collect();
+
+        requests.sort_by(|a, b| {
+            b.total
+                .unwrap_or_default()
+                .total_cmp(&a.total.unwrap_or_default())
+        });
+        requests.truncate(limit);
+        requests
+    }
+}
+
+/// A response saved by a network recording.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordedResponse {
+    /// The method of the request, `GET` when empty.
+    pub method: String,
+    /// The url requested.
+    pub url: String,
+    /// The status code of the response.
+    pub status: u16,
+    /// The headers of the response.
+    pub headers: Vec<(String, String)>,
+    /// The decoded body of the response.
+    pub body: Vec<u8>,
+}
+
+impl RecordedResponse {
+    /// The method of the request.
+    pub fn method(&self) -> &str {
+        if self.method.is_empty() {
+            "GET"
+        } else {
+            &self.method
+        }
+    }
+
+    /// The key of the request in the recording: the method and the url.
+    pub fn key(&self) -> String {
+        format!("{} {}", self.method(), self.url)
+    }
+
+    /// Encode the response as the method and url line, the status line, the header lines, an empty line and the body.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = format!("{}\n{}\n", self.key(), self.status).into_bytes();
+        for (name, value) in self.headers.iter() {
+            bytes.extend_from_slice(format!("{name}: {value}\n").as_bytes());
+        }
+        bytes.push(b'\n');
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+
+    /// Decode a response encoded with [`RecordedResponse::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let split = bytes.windows(2).position(|w| w == b"\n\n")?;
+        let head = std::str::from_utf8(&bytes[..split]).ok()?;
+        let mut lines = head.lines();
+        let line = lines.next()?;
+        let (method, url) = match line.split_once(' ') {
+            Some((method, url)) => (method.to_string(), url.to_string()),
+            _ => (String::from("GET"), line.to_string()),
+        };
+        let status = lines.next()?.parse().ok()?;
+        let headers = lines
+            .filter_map(|line| line.split_once(": "))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        Some(Self {
+            method,
+            url,
+            status,
+            headers,
+            body: bytes[split + 2..].to_vec(),
+        })
+    }
+}
+
+/// Record or replay the network of the browser.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RecordingMode {
+    #[default]
+    /// Save every response the browser receives.
+    Record,
+    /// Answer every browser request from the saved responses and fail the requests missing from the recording.
+    Replay,
+}
+
+/// Persist the responses the browser receives during a crawl to a directory, or replay them to re-crawl offline with the exact same responses.
+/// Each response is stored in its own file keyed by the method and url, the last response for a request wins.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetworkRecording {
+    /// The directory holding the recording. The directory is created if missing.
+    pub dir: std::path::PathBuf,
+    /// Record or replay.
+    pub mode: RecordingMode,
+    /// The replay routes loaded from the directory.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) replay_router:
+        std::sync::Arc<std::sync::Mutex<Option<chromiumoxide::handler::network::MockRouter>>>,
+}
+
+impl PartialEq for NetworkRecording {
+    fn eq(&self, other: &Self) -> bool {
+        self.dir == other.dir && self.mode == other.mode
+    }
+}
+
+impl NetworkRecording {
+    /// Save the responses of the crawl to the directory.
+    pub fn record(dir: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            mode: RecordingMode::Record,
+            #[cfg(feature = "chrome")]
+            replay_router: Default::default(),
+        }
+    }
+
+    /// Answer the requests of the crawl from the responses saved in the directory.
+    pub fn replay(dir: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            mode: RecordingMode::Replay,
+            #[cfg(feature = "chrome")]
+            replay_router: Default::default(),
+        }
+    }
+
+    /// The file path of the response for the request. The name is stable across builds to replay older recordings.
+    pub fn response_path(&self, response: &RecordedResponse) -> std::path::PathBuf {
+        self.dir.join(format!(
+            "{:016x}.res",
+            crate::utils::fnv1a(response.key().as_bytes())
+        ))
+    }
+
+    /// Save a response to the recording.
+    #[cfg(feature = "chrome")]
+    pub async fn save(&self, response: &RecordedResponse) -> std::io::Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(self.response_path(response), response.to_bytes()).await
+    }
+
+    /// The responses saved in the recording.
+    #[cfg(feature = "chrome")]
+    pub async fn load(&self) -> Vec<RecordedResponse> {
+        let mut responses = Vec::new();
+
+        if let Ok(mut entries) = tokio::fs::read_dir(&self.dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let path = entry.path();
+                if path.extension().is_some_and(|ext| ext == "res") {
+                    if let Ok(bytes) = tokio::fs::read(&path).await {
+                        if let Some(response) = RecordedResponse::from_bytes(&bytes) {
+                            responses.push(response);
+                        }
+                    }
+                }
+            }
+        }
+
+        responses
+    }
+
+    /// The routes answering the requests from the recording, loaded once.
+    #[cfg(feature = "chrome")]
+    pub(crate) async fn replay_router(&self) -> chromiumoxide::handler::network::MockRouter {
+        if let Ok(router) = self.replay_router.lock() {
+            if let Some(router) = router.as_ref() {
+                return router.clone();
+            }
+        }
+
+        let router = chromiumoxide::handler::network::MockRouter {
+            exact: self
+                .load()
+                .await
+                .into_iter()
+                .map(|response| {
+                    (
+                        response.key(),
+                        chromiumoxide::handler::network::MockRoute {
+                            pattern: response.url,
+                            status: response.status,
+                            headers: response.headers,
+                            body: response.body,
+                        },
+                    )
+                })
+                .collect(),
+            offline: true,
+            ..Default::default()
+        };
+
+        if let Ok(mut cached) = self.replay_router.lock() {
+            cached.replace(router.clone());
+        }
+
+        router
+    }
+
+    /// Drop the loaded replay routes so a new crawl reads the recording again.
+    #[cfg(feature = "chrome")]
+    pub(crate) fn reset(&self) {
+        if let Ok(mut router) = self.replay_router.lock() {
+            router.take();
+        }
+    }
+}
+
+/// Share the scripts, stylesheets, fonts and images received by the pages of a crawl to fulfill the repeated requests
+/// from memory, or from disk once the memory is full, instead of the network. Only the responses with an `ETag` or
+/// `Last-Modified` validator are cached, a response received with another validator replaces the cached one.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FulfillCacheConfig {
+    /// The bytes of the bodies kept in memory.
+    pub max_memory_bytes: usize,
+    /// The largest body cached.
+    pub max_entry_bytes: usize,
+    /// The directory the bodies are written to once the memory is full. The directory is created if missing.
+    pub dir: Option<std::path::PathBuf>,
+    /// The bytes of the bodies written to the directory.
+    pub max_disk_bytes: usize,
+    /// The responses shared by the pages.
+    #[cfg(feature = "chrome")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) cache: chromiumoxide::handler::fulfill_cache::FulfillCache,
+}
+
+impl Default for FulfillCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_memory_bytes: 64 * 1024 * 1024,
+            max_entry_bytes: 4 * 1024 * 1024,
+            dir: None,
+            max_disk_bytes: 512 * 1024 * 1024,
+            #[cfg(feature = "chrome")]
+            cache: Default::default(),
+        }
+    }
+}
+
+impl PartialEq for FulfillCacheConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.max_memory_bytes == other.max_memory_bytes
+            && self.max_entry_bytes == other.max_entry_bytes
+            && self.dir == other.dir
+            && self.max_disk_bytes == other.max_disk_bytes
+    }
+}
+
+impl FulfillCacheConfig {
+    /// A cache of 64 MiB in memory for bodies up to 4 MiB, with 512 MiB on disk once a directory is set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the bytes of the bodies kept in memory.
+    pub fn with_max_memory_bytes(mut self, max_memory_bytes: usize) -> Self {
+        self.max_memory_bytes = max_memory_bytes;
+        self
+    }
+
+    /// Set the largest body cached.
+    pub fn with_max_entry_bytes(mut self, max_entry_bytes: usize) -> Self {
+        self.max_entry_bytes = max_entry_bytes;
+        self
+    }
+
+    /// Write the bodies to the directory once the memory is full.
+    pub fn with_dir(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// Set the bytes of the bodies written to the directory.
+    pub fn with_max_disk_bytes(mut self, max_disk_bytes: usize) -> Self {
+        self.max_disk_bytes = max_disk_bytes;
+        self
+    }
+
+    /// The file path of the body for the url.
+    pub fn body_path(&self, url: &str) -> Option<std::path::PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{:016x}.body", crate::utils::fnv1a(url.as_bytes()))))
+    }
+
+    /// The responses shared by the pages.
+    #[cfg(feature = "chrome")]
+    pub fn cache(&self) -> &chromiumoxide::handler::fulfill_cache::FulfillCache {
+        &self.cache
+    }
+}
+
 #[doc = "Capture page screenshot.\n[captureScreenshot](https://chromedevtools.github.io/devtools-protocol/tot/Page/#method-captureScreenshot)"]
 #[derive(Debug, Clone, Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -647,6 +1674,15 @@ pub struct RequestInterceptConfiguration {
     pub block_javascript: bool,
     /// Block ads. Requires the `adblock` feature flag.
     pub block_ads: bool,
+    /// Load pages from the network instead of the cached app shells served by service workers.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bypass_service_worker: bool,
+    /// Block the service worker scripts so they can not register and serve later pages in the same target.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub block_service_workers: bool,
+    /// The actions by resource type, taking precedence over the block flags.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub resource_policy: ResourcePolicy,
 }
 
 impl RequestInterceptConfiguration {
@@ -670,6 +1706,174 @@ impl RequestInterceptConfiguration {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The resource type of a browser request.
+pub enum NetworkResourceType {
+    /// The html documents of the pages and frames.
+    Document,
+    /// The stylesheets.
+    Stylesheet,
+    /// The images.
+    Image,
+    /// The audio and video.
+    Media,
+    /// The fonts.
+    Font,
+    /// The scripts.
+    Script,
+    /// The subtitles of the videos.
+    TextTrack,
+    /// The `XMLHttpRequest` requests.
+    Xhr,
+    /// The `fetch` requests.
+    Fetch,
+    /// The prefetched resources.
+    Prefetch,
+    /// The server sent events.
+    EventSource,
+    /// The websockets.
+    WebSocket,
+    /// The web app manifests.
+    Manifest,
+    /// The signed exchanges.
+    SignedExchange,
+    /// The pings and beacons.
+    Ping,
+    /// The content security policy violation reports.
+    CspViolationReport,
+    /// The cors preflight requests.
+    Preflight,
+    /// The other requests.
+    Other,
+}
+
+#[cfg(feature = "chrome")]
+impl From<NetworkResourceType> for chromiumoxide::cdp::browser_protocol::network::ResourceType {
+    fn from(resource_type: NetworkResourceType) -> Self {
+        use chromiumoxide::cdp::browser_protocol::network::ResourceType;
+
+        match resource_type {
+            NetworkResourceType::Document => ResourceType::Document,
+            NetworkResourceType::Stylesheet => ResourceType::Stylesheet,
+            NetworkResourceType::Image => ResourceType::Image,
+            NetworkResourceType::Media => ResourceType::Media,
+            NetworkResourceType::Font => ResourceType::Font,
+            NetworkResourceType::Script => ResourceType::Script,
+            NetworkResourceType::TextTrack => ResourceType::TextTrack,
+            NetworkResourceType::Xhr => ResourceType::Xhr,
+            NetworkResourceType::Fetch => ResourceType::Fetch,
+            NetworkResourceType::Prefetch => ResourceType::Prefetch,
+            NetworkResourceType::EventSource => ResourceType::EventSource,
+            NetworkResourceType::WebSocket => ResourceType::WebSocket,
+            NetworkResourceType::Manifest => ResourceType::Manifest,
+            NetworkResourceType::SignedExchange => ResourceType::SignedExchange,
+            NetworkResourceType::Ping => ResourceType::Ping,
+            NetworkResourceType::CspViolationReport => ResourceType::CspViolationReport,
+            NetworkResourceType::Preflight => ResourceType::Preflight,
+            NetworkResourceType::Other => ResourceType::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// What happens to the intercepted requests of a resource type.
+pub enum ResourceAction {
+    #[default]
+    /// Send the request to the network.
+    Allow,
+    /// Fail the request as blocked by the client.
+    Block,
+    /// Answer the request with an empty `200` response so the page sees the resource as loaded.
+    FulfillEmpty,
+}
+
+#[cfg(feature = "chrome")]
+impl From<ResourceAction> for chromiumoxide::handler::resource_policy::ResourceAction {
+    fn from(action: ResourceAction) -> Self {
+        match action {
+            ResourceAction::Allow => Self::Allow,
+            ResourceAction::Block => Self::Block,
+            ResourceAction::FulfillEmpty => Self::FulfillEmpty,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The action of each resource type of the intercepted requests. The `block_visuals`, `block_stylesheets` and
+/// `block_javascript` flags of the intercept config are shorthands used for the resource types without an action.
+pub struct ResourcePolicy {
+    /// The actions by resource type, a later action of the same type replaces the earlier one.
+    pub actions: Vec<(NetworkResourceType, ResourceAction)>,
+}
+
+impl ResourcePolicy {
+    /// A new policy without actions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the action of the resource type.
+    pub fn with(mut self, resource_type: NetworkResourceType, action: ResourceAction) -> Self {
+        self.actions.push((resource_type, action));
+        self
+    }
+
+    /// Whether no action is set.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+
+#[cfg(feature = "chrome")]
+impl From<&ResourcePolicy> for chromiumoxide::handler::resource_policy::ResourcePolicy {
+    fn from(policy: &ResourcePolicy) -> Self {
+        policy
+            .actions
+            .iter()
+            .map(|(resource_type, action)| ((*resource_type).into(), (*action).into()))
+            .collect()
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The bytes allowed for the response bodies of the browser by resource type. A response announcing a larger
+/// `Content-Length` is aborted once its headers are received, a streamed body going over the cap stops the page from
+/// loading instead of stalling it until the request timeout.
+pub struct MaxResponseBytes {
+    /// The caps by resource type, a later cap of the same type replaces the earlier one.
+    pub limits: Vec<(NetworkResourceType, u64)>,
+}
+
+impl MaxResponseBytes {
+    /// A new config without caps.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow the response bodies of the resource type up to the bytes.
+    pub fn with(mut self, resource_type: NetworkResourceType, max_bytes: u64) -> Self {
+        self.limits.push((resource_type, max_bytes));
+        self
+    }
+}
+
+#[cfg(feature = "chrome")]
+impl From<&MaxResponseBytes>
+    for std::collections::HashMap<chromiumoxide::cdp::browser_protocol::network::ResourceType, u64>
+{
+    fn from(max_response_bytes: &MaxResponseBytes) -> Self {
+        max_response_bytes
+            .limits
+            .iter()
+            .map(|(resource_type, max_bytes)| ((*resource_type).into(), *max_bytes))
+            .collect()
+    }
+}
+
 /// Convert ExecutionScripts to Trie.
 pub fn convert_to_trie_execution_scripts(
     input: &Option<ExecutionScriptsMap>,
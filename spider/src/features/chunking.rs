@@ -0,0 +1,301 @@
+use crate::page::Page;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Split the text into tokens, the chunks are bounded by the tokens of the tokenizer of the embedding model.
+pub trait Tokenizer: Send + Sync {
+    /// The byte ranges of the tokens of the text, in order.
+    fn tokenize(&self, text: &str) -> Vec<Range<usize>>;
+}
+
+impl<F> Tokenizer for F
+where
+    F: Fn(&str) -> Vec<Range<usize>> + Send + Sync,
+{
+    fn tokenize(&self, text: &str) -> Vec<Range<usize>> {
+        self(text)
+    }
+}
+
+/// The words separated by whitespace as the tokens.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WhitespaceTokenizer;
+
+impl Tokenizer for WhitespaceTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<Range<usize>> {
+        let mut tokens = Vec::new();
+        let mut start = None;
+
+        for (i, c) in text.char_indices() {
+            match (c.is_whitespace(), start) {
+                (true, Some(s)) => {
+                    tokens.push(s..i);
+                    start = None;
+                }
+                (false, None) => start = Some(i),
+                _ => (),
+            }
+        }
+
+        if let Some(s) = start {
+            tokens.push(s..text.len());
+        }
+
+        tokens
+    }
+}
+
+/// The tokenizer of the chunker.
+#[derive(Clone)]
+pub struct ChunkTokenizer(Arc<dyn Tokenizer>);
+
+impl ChunkTokenizer {
+    /// Count the tokens with the tokenizer.
+    pub fn new(tokenizer: impl Tokenizer + 'static) -> Self {
+        Self(Arc::new(tokenizer))
+    }
+
+    /// The byte ranges of the tokens of the text.
+    pub fn tokenize(&self, text: &str) -> Vec<Range<usize>> {
+        self.0.tokenize(text)
+    }
+}
+
+impl Default for ChunkTokenizer {
+    fn default() -> Self {
+        Self::new(WhitespaceTokenizer)
+    }
+}
+
+impl std::fmt::Debug for ChunkTokenizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ChunkTokenizer").finish()
+    }
+}
+
+impl PartialEq for ChunkTokenizer {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// A chunk of the text of a page ready to embed and store in a vector database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chunk {
+    /// The id of the chunk, the url of the page and the position of the chunk ex: `https://example.com/docs#3`.
+    pub id: String,
+    /// The url of the page.
+    pub url: String,
+    /// The headings the chunk is under, the top level heading first.
+    pub heading_path: Vec<String>,
+    /// The position of the chunk in the page starting at 0.
+    pub position: usize,
+    /// The tokens of the text.
+    pub token_count: usize,
+    /// The markdown text of the chunk.
+    pub text: String,
+}
+
+/// Split the pages into chunks of at most `max_tokens` tokens repeating the last `overlap` tokens of the previous
+/// chunk. The pages are converted to markdown and split at their headings first, a chunk never spans two sections so
+/// its heading path describes all of its text.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chunker {
+    /// The tokens of a chunk at most. Defaults to 512.
+    pub max_tokens: usize,
+    /// The tokens of the end of a chunk repeated at the start of the next chunk of the section. Defaults to 64.
+    pub overlap: usize,
+    /// Prepend the heading path to the text of the chunks, ex: `Guide > Install`. The heading tokens are not counted.
+    pub heading_context: bool,
+    /// The tokenizer counting the tokens. Defaults to [`WhitespaceTokenizer`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub tokenizer: ChunkTokenizer,
+}
+
+impl Default for Chunker {
+    fn default() -> Self {
+        Self {
+            max_tokens: 512,
+            overlap: 64,
+            heading_context: false,
+            tokenizer: ChunkTokenizer::default(),
+        }
+    }
+}
+
+/// The level and the text of the markdown heading line.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+
+    if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+        Some((level, line[level..].trim()))
+    } else {
+        None
+    }
+}
+
+/// Whether the line opens or closes a fenced code block.
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+impl Chunker {
+    /// A new chunker with the defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The tokens of a chunk at most.
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// The tokens repeated between the consecutive chunks of a section.
+    pub fn with_overlap(mut self, overlap: usize) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    /// Prepend the heading path to the text of the chunks.
+    pub fn with_heading_context(mut self, heading_context: bool) -> Self {
+        self.heading_context = heading_context;
+        self
+    }
+
+    /// Count the tokens with the tokenizer of the embedding model.
+    pub fn with_tokenizer(mut self, tokenizer: impl Tokenizer + 'static) -> Self {
+        self.tokenizer = ChunkTokenizer::new(tokenizer);
+        self
+    }
+
+    /// The chunks of the markdown of the page.
+    pub fn chunk_page(&self, page: &Page) -> Vec<Chunk> {
+        self.chunk(page.get_url(), &page.markdown())
+    }
+
+    /// The chunks of the markdown of the url.
+    pub fn chunk(&self, url: &str, markdown: &str) -> Vec<Chunk> {
+        let mut chunks = Vec::new();
+        let mut path: Vec<(usize, String)> = Vec::new();
+        let mut section = String::new();
+        let mut in_code = false;
+
+        for line in markdown.lines() {
+            if is_fence(line) {
+                in_code = !in_code;
+            } else if !in_code {
+                if let Some((level, text)) = heading(line) {
+                    self.push_section(url, &path, &section, &mut chunks);
+                    section.clear();
+                    path.retain(|(l, _)| *l < level);
+                    path.push((level, text.to_string()));
+                    continue;
+                }
+            }
+
+            section.push_str(line);
+            section.push('\n');
+        }
+
+        self.push_section(url, &path, &section, &mut chunks);
+        chunks
+    }
+
+    /// Push the token windows of the section.
+    fn push_section(
+        &self,
+        url: &str,
+        path: &[(usize, String)],
+        section: &str,
+        chunks: &mut Vec<Chunk>,
+    ) {
+        let tokens = self.tokenizer.tokenize(section);
+
+        if tokens.is_empty() {
+            return;
+        }
+
+        let max_tokens = self.max_tokens.max(1);
+        let step = max_tokens.saturating_sub(self.overlap).max(1);
+        let heading_path: Vec<String> = path.iter().map(|(_, text)| text.clone()).collect();
+        let mut start = 0;
+
+        loop {
+            let end = (start + max_tokens).min(tokens.len());
+            let body = section[tokens[start].start..tokens[end - 1].end].trim();
+
+            if body.is_empty() {
+                break;
+            }
+
+            let text = if self.heading_context && !heading_path.is_empty() {
+                format!("{}\n\n{body}", heading_path.join(" > "))
+            } else {
+                body.to_string()
+            };
+            let position = chunks.len();
+
+            chunks.push(Chunk {
+                id: format!("{url}#{position}"),
+                url: url.to_string(),
+                heading_path: heading_path.clone(),
+                position,
+                token_count: end - start,
+                text,
+            });
+
+            if end == tokens.len() {
+                break;
+            }
+
+            start += step;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_sections_with_overlap() {
+        let markdown = "Intro text.\n\n# Guide\n\none two three four five six seven\n\n## Install\n\n```sh\n# hi\n```\n\n# Next\n\nlast";
+        let chunker = Chunker::new().with_max_tokens(4).with_overlap(1);
+        let chunks = chunker.chunk("https://example.com/docs", markdown);
+        let summary: Vec<(Vec<&str>, &str, usize)> = chunks
+            .iter()
+            .map(|chunk| {
+                (
+                    chunk.heading_path.iter().map(String::as_str).collect(),
+                    chunk.text.as_str(),
+                    chunk.token_count,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (vec![], "Intro text.", 2),
+                (vec!["Guide"], "one two three four", 4),
+                (vec!["Guide"], "four five six seven", 4),
+                (vec!["Guide", "Install"], "```sh\n# hi\n```", 4),
+                (vec!["Next"], "last", 1),
+            ]
+        );
+        assert_eq!(chunks[4].id, "https://example.com/docs#4");
+        assert_eq!(chunks[4].position, 4);
+
+        let chunker = chunker
+            .with_heading_context(true)
+            .with_tokenizer(|text: &str| vec![0..text.trim_end().len()]);
+        let chunks = chunker.chunk("https://example.com/docs", markdown);
+
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks[1].text, "Guide\n\none two three four five six seven");
+    }
+}
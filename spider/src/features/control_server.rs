@@ -0,0 +1,428 @@
+use crate::page::Page;
+use crate::utils::{CONTROL_DRAINING, CONTROL_PAUSED, CONTROL_SHUTDOWN};
+use std::sync::atomic::{AtomicI8, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+/// The delay between the requests is not set at runtime.
+const NO_DELAY: u64 = u64::MAX;
+
+/// The largest request read by the server.
+const MAX_REQUEST_SIZE: usize = 1024 * 1024;
+
+/// The state shared by the clones of the server.
+#[derive(Debug)]
+struct ControlState {
+    /// The control handle pausing, resuming or shutting down the crawl.
+    control: Arc<AtomicI8>,
+    /// The pages crawled.
+    pages: AtomicU64,
+    /// The pages crawled without a success status.
+    errors: AtomicU64,
+    /// The links waiting in the frontier for the next batch.
+    frontier_size: AtomicU64,
+    /// The start of the running crawl.
+    started: Mutex<Option<Instant>>,
+    /// The seeds added to the next batch of the crawl.
+    seeds: Mutex<Vec<String>>,
+    /// The delay between the requests in milliseconds.
+    delay: AtomicU64,
+}
+
+impl Default for ControlState {
+    fn default() -> Self {
+        Self {
+            control: Default::default(),
+            pages: Default::default(),
+            errors: Default::default(),
+            frontier_size: Default::default(),
+            started: Default::default(),
+            seeds: Default::default(),
+            delay: AtomicU64::new(NO_DELAY),
+        }
+    }
+}
+
+/// The status of the crawl returned by `GET /status`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ControlStatus {
+    /// The state of the crawl: `running`, `paused`, `draining` or `stopped`.
+    pub state: String,
+    /// The pages crawled.
+    pub pages: u64,
+    /// The pages crawled without a success status.
+    pub errors: u64,
+    /// The links waiting in the frontier for the next batch.
+    pub frontier_size: u64,
+    /// The seconds since the crawl started.
+    pub elapsed_secs: f64,
+    /// The pages crawled per second since the crawl started.
+    pub pages_per_second: f64,
+    /// The delay between the requests set at runtime in milliseconds.
+    pub delay_ms: Option<u64>,
+    /// The seeds waiting for the next batch.
+    pub pending_seeds: usize,
+}
+
+/// The body of `POST /seeds`.
+#[derive(Debug, serde::Deserialize)]
+struct SeedsRequest {
+    /// The urls to crawl.
+    urls: Vec<String>,
+}
+
+/// The body of `PUT /rate`. The requests per second are used when set, both unset remove the delay.
+#[derive(Debug, serde::Deserialize)]
+struct RateRequest {
+    /// The delay between the requests in milliseconds.
+    delay_ms: Option<u64>,
+    /// The requests per second of the crawl.
+    requests_per_second: Option<f64>,
+}
+
+/// An embedded HTTP server to operate a running crawl. The endpoints answer with JSON:
+///
+/// - `GET /status` the state, the pages and errors, the size of the frontier and the pages per second.
+/// - `POST /seeds` with `{"urls": [...]}` adds the urls to the next batch of the crawl.
+/// - `POST /pause` and `POST /resume` pause and resume the crawl.
+/// - `POST /shutdown` shuts the crawl down gracefully, `?graceful=false` aborts the pages in flight.
+/// - `PUT /rate` with `{"delay_ms": 500}` or `{"requests_per_second": 2.0}` sets the delay between the requests.
+///
+/// The clones share the state, set the server on the website and spawn a clone before the crawl ex:
+/// `tokio::spawn(server.clone().serve("127.0.0.1:7070"))`. Set a token to require an `Authorization: Bearer` header.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ControlServer {
+    /// The bearer token required by the endpoints.
+    pub token: Option<String>,
+    /// The state of the crawl.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    state: Arc<ControlState>,
+}
+
+impl PartialEq for ControlServer {
+    fn eq(&self, other: &Self) -> bool {
+        self.token == other.token && Arc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl ControlServer {
+    /// A server without a token.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require the token as an `Authorization: Bearer` header.
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// The control handle pausing, resuming and shutting down the crawl of the server.
+    pub(crate) fn control(&self) -> Arc<AtomicI8> {
+        self.state.control.clone()
+    }
+
+    /// The status of the crawl.
+    pub fn status(&self) -> ControlStatus {
+        let pages = self.state.pages.load(Ordering::Relaxed);
+        let elapsed_secs = match *self.state.started.lock().unwrap() {
+            Some(started) => started.elapsed().as_secs_f64(),
+            _ => 0.0,
+        };
+
+        ControlStatus {
+            state: match self.state.control.load(Ordering::Relaxed) {
+                CONTROL_PAUSED => "paused",
+                CONTROL_SHUTDOWN => "stopped",
+                CONTROL_DRAINING => "draining",
+                _ => "running",
+            }
+            .into(),
+            pages,
+            errors: self.state.errors.load(Ordering::Relaxed),
+            frontier_size: self.state.frontier_size.load(Ordering::Relaxed),
+            elapsed_secs,
+            pages_per_second: if elapsed_secs > 0.0 {
+                pages as f64 / elapsed_secs
+            } else {
+                0.0
+            },
+            delay_ms: self.delay().map(|delay| delay.as_millis() as u64),
+            pending_seeds: self.state.seeds.lock().unwrap().len(),
+        }
+    }
+
+    /// Add the urls to the next batch of the crawl. The urls go through the same checks as the links found.
+    pub fn add_seeds<I: IntoIterator<Item = String>>(&self, urls: I) {
+        self.state.seeds.lock().unwrap().extend(urls);
+    }
+
+    /// Set the delay between the requests of the crawl, `None` keeps the delay of the configuration only.
+    pub fn set_delay(&self, delay: Option<Duration>) {
+        self.state.delay.store(
+            delay.map_or(NO_DELAY, |delay| delay.as_millis() as u64),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// The delay between the requests set at runtime.
+    pub fn delay(&self) -> Option<Duration> {
+        match self.state.delay.load(Ordering::Relaxed) {
+            NO_DELAY => None,
+            delay => Some(Duration::from_millis(delay)),
+        }
+    }
+
+    /// Reset the counters for the crawl starting.
+    pub(crate) fn start(&self) {
+        self.state.pages.store(0, Ordering::Relaxed);
+        self.state.errors.store(0, Ordering::Relaxed);
+        self.state.frontier_size.store(0, Ordering::Relaxed);
+        *self.state.started.lock().unwrap() = Some(Instant::now());
+    }
+
+    /// Count the page crawled.
+    pub(crate) fn record_page(&self, page: &Page) {
+        self.state.pages.fetch_add(1, Ordering::Relaxed);
+        if !page.status_code.is_success() {
+            self.state.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Set the links waiting in the frontier.
+    pub(crate) fn set_frontier_size(&self, size: usize) {
+        self.state
+            .frontier_size
+            .store(size as u64, Ordering::Relaxed);
+    }
+
+    /// Take the seeds added since the last batch.
+    pub(crate) fn take_seeds(&self) -> Vec<String> {
+        std::mem::take(&mut *self.state.seeds.lock().unwrap())
+    }
+
+    /// Wait for the delay set at runtime before the next request.
+    pub(crate) async fn throttle(&self) {
+        if let Some(delay) = self.delay() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// The status line and the JSON body answering the request.
+    fn respond(&self, head: &str, body: &[u8]) -> (&'static str, String) {
+        let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+        let method = request_line.next().unwrap_or_default();
+        let target = request_line.next().unwrap_or_default();
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+        if let Some(token) = &self.token {
+            let authorized = header(head, "authorization")
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .map_or(false, |value| value.trim() == token);
+
+            if !authorized {
+                return ("401 Unauthorized", error_json("missing or invalid token"));
+            }
+        }
+
+        let control = &self.state.control;
+
+        match (method, path) {
+            ("GET", "/status") => ("200 OK", self.status_json()),
+            ("POST", "/seeds") => match serde_json::from_slice::<SeedsRequest>(body) {
+                Ok(request) => {
+                    let added = request.urls.len();
+                    self.add_seeds(request.urls);
+                    ("200 OK", serde_json::json!({ "added": added }).to_string())
+                }
+                Err(e) => ("400 Bad Request", error_json(&e.to_string())),
+            },
+            ("POST", "/pause") => {
+                crate::utils::pause_handle(control);
+                ("200 OK", self.status_json())
+            }
+            ("POST", "/resume") => {
+                crate::utils::resume_handle(control);
+                ("200 OK", self.status_json())
+            }
+            ("POST", "/shutdown") => {
+                crate::utils::shutdown_handle(
+                    control,
+                    !query.split('&').any(|param| param == "graceful=false"),
+                );
+                ("200 OK", self.status_json())
+            }
+            ("PUT", "/rate") => match serde_json::from_slice::<RateRequest>(body) {
+                Ok(RateRequest {
+                    requests_per_second: Some(rate),
+                    ..
+                }) if rate <= 0.0 || !rate.is_finite() => (
+                    "400 Bad Request",
+                    error_json("requests_per_second must be positive"),
+                ),
+                Ok(request) => {
+                    self.set_delay(match request.requests_per_second {
+                        Some(rate) => Some(Duration::from_secs_f64(1.0 / rate)),
+                        _ => request.delay_ms.map(Duration::from_millis),
+                    });
+                    ("200 OK", self.status_json())
+                }
+                Err(e) => ("400 Bad Request", error_json(&e.to_string())),
+            },
+            _ => ("404 Not Found", error_json("not found")),
+        }
+    }
+
+    /// The status of the crawl as JSON.
+    fn status_json(&self) -> String {
+        serde_json::to_string(&self.status()).unwrap_or_default()
+    }
+
+    /// Serve the endpoints on the address until the listener fails. Spawn it with a clone of the server before the
+    /// crawl ex: `tokio::spawn(server.clone().serve("127.0.0.1:7070"))`.
+    pub async fn serve<A: ToSocketAddrs>(self, addr: A) -> std::io::Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+
+        loop {
+            let (mut stream, _) = listener.accept().await?;
+            let server = self.clone();
+
+            tokio::spawn(async move {
+                let (status, body) = match read_request(&mut stream).await {
+                    Some((head, body)) => server.respond(&head, &body),
+                    _ => ("400 Bad Request", error_json("invalid request")),
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    }
+}
+
+/// An error as JSON.
+fn error_json(error: &str) -> String {
+    serde_json::json!({ "error": error }).to_string()
+}
+
+/// The value of the header in the head of the request.
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().skip(1).find_map(|line| {
+        line.split_once(':')
+            .filter(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim())
+    })
+}
+
+/// Read the head and the body of the request up to the content length.
+async fn read_request(stream: &mut TcpStream) -> Option<(String, Vec<u8>)> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 4096];
+
+    let end = loop {
+        let read = stream.read(&mut chunk).await.ok()?;
+
+        if read == 0 || buf.len() + read > MAX_REQUEST_SIZE {
+            return None;
+        }
+
+        buf.extend_from_slice(&chunk[..read]);
+
+        if let Some(end) = buf.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..end]).into_owned();
+    let length = header(&head, "content-length")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or_default();
+    let start = end + 4;
+
+    if start + length > MAX_REQUEST_SIZE {
+        return None;
+    }
+
+    while buf.len() < start + length {
+        let read = stream.read(&mut chunk).await.ok()?;
+
+        if read == 0 {
+            return None;
+        }
+
+        buf.extend_from_slice(&chunk[..read]);
+    }
+
+    Some((head, buf[start..start + length].to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_endpoints() {
+        let server = ControlServer::new().with_token("secret");
+        let auth = "Authorization: Bearer secret";
+
+        assert_eq!(
+            server.respond("GET /status HTTP/1.1", b"").0,
+            "401 Unauthorized"
+        );
+
+        let (status, body) = server.respond(&format!("GET /status HTTP/1.1\r\n{auth}"), b"");
+        let status_body: ControlStatus = serde_json::from_str(&body).unwrap();
+        assert_eq!(status, "200 OK");
+        assert_eq!(status_body.state, "running");
+
+        let (status, body) = server.respond(
+            &format!("POST /seeds HTTP/1.1\r\n{auth}"),
+            br#"{"urls": ["https://example.com/a", "https://example.com/b"]}"#,
+        );
+        assert_eq!((status, body.as_str()), ("200 OK", r#"{"added":2}"#));
+        assert_eq!(server.status().pending_seeds, 2);
+        assert_eq!(server.take_seeds().len(), 2);
+
+        server.respond(&format!("POST /pause HTTP/1.1\r\n{auth}"), b"");
+        assert_eq!(server.status().state, "paused");
+        server.respond(&format!("POST /resume HTTP/1.1\r\n{auth}"), b"");
+        assert_eq!(server.status().state, "running");
+
+        let (status, _) = server.respond(
+            &format!("PUT /rate HTTP/1.1\r\n{auth}"),
+            br#"{"requests_per_second": 4.0}"#,
+        );
+        assert_eq!(status, "200 OK");
+        assert_eq!(server.delay(), Some(Duration::from_millis(250)));
+        assert_eq!(
+            server
+                .respond(
+                    &format!("PUT /rate HTTP/1.1\r\n{auth}"),
+                    br#"{"requests_per_second": 0}"#
+                )
+                .0,
+            "400 Bad Request"
+        );
+        server.respond(&format!("PUT /rate HTTP/1.1\r\n{auth}"), b"{}");
+        assert_eq!(server.delay(), None);
+
+        let (_, body) = server.respond(
+            &format!("POST /shutdown?graceful=false HTTP/1.1\r\n{auth}"),
+            b"",
+        );
+        assert!(body.contains(r#""state":"stopped""#));
+        assert_eq!(
+            server
+                .respond(&format!("GET /missing HTTP/1.1\r\n{auth}"), b"")
+                .0,
+            "404 Not Found"
+        );
+    }
+}
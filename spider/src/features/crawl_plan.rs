@@ -0,0 +1,302 @@
+use crate::page::Page;
+use crate::utils::PageResponse;
+use crate::Client;
+use std::sync::Arc;
+
+/// Where a planned url was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlanSource {
+    /// The start url of the crawl.
+    Seed,
+    /// The extra links added to the crawl.
+    Extra,
+    /// The sitemaps of the site.
+    Sitemap,
+    /// The links of a planned page.
+    Link,
+}
+
+/// What the crawl would do with a planned url.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlanVerdict {
+    /// The url would be fetched.
+    Visit,
+    /// The url is excluded by the whitelist or the blacklist.
+    Filtered,
+    /// The url is disallowed by robots.txt.
+    Robots,
+    /// The url is over the crawl budget or the depth.
+    BudgetExceeded,
+    /// The url was planned already.
+    Duplicate,
+}
+
+/// A url of the plan with the answer to its HEAD request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlannedUrl {
+    /// The url.
+    pub url: String,
+    /// Where the url was found.
+    pub source: PlanSource,
+    /// What the crawl would do with the url.
+    pub verdict: PlanVerdict,
+    /// The status code of the visited urls, from the page download or the HEAD request.
+    pub status: Option<u16>,
+    /// The content type of the visited urls, from the page download or the HEAD request.
+    pub content_type: Option<String>,
+    /// The content length of the visited urls, the size of the body downloaded or the length of the HEAD request.
+    pub content_length: Option<u64>,
+}
+
+/// How the crawl plan is built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlanOptions {
+    /// Send a HEAD request to the urls the crawl would visit for their status, content type and size. Defaults to true.
+    pub head_requests: bool,
+    /// The HEAD requests and page downloads running at once at most. Defaults to 8.
+    pub concurrency: usize,
+    /// Read the sitemaps of the site for the urls. Requires the `sitemap` flag. Defaults to true.
+    pub sitemaps: bool,
+    /// Download the visited pages to follow their links until the budget is spent. Defaults to true.
+    pub follow_links: bool,
+}
+
+impl Default for PlanOptions {
+    fn default() -> Self {
+        Self {
+            head_requests: true,
+            concurrency: 8,
+            sitemaps: true,
+            follow_links: true,
+        }
+    }
+}
+
+impl PlanOptions {
+    /// The options with HEAD requests, sitemaps and links followed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether the visited urls get a HEAD request.
+    pub fn with_head_requests(mut self, head_requests: bool) -> Self {
+        self.head_requests = head_requests;
+        self
+    }
+
+    /// Set the HEAD requests and downloads running at once at most.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Set whether the sitemaps are read.
+    pub fn with_sitemaps(mut self, sitemaps: bool) -> Self {
+        self.sitemaps = sitemaps;
+        self
+    }
+
+    /// Set whether the visited pages are downloaded to follow their links.
+    pub fn with_follow_links(mut self, follow_links: bool) -> Self {
+        self.follow_links = follow_links;
+        self
+    }
+}
+
+/// The report of a dry run: the urls a crawl would visit and the urls it would skip with the reason, built from the
+/// start url, the extra links, the sitemaps and the links of the visited pages after evaluating robots.txt, the
+/// whitelist, the blacklist and the budget. Only the pages whose links are followed are downloaded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrawlPlan {
+    /// The sitemaps read.
+    pub sitemaps: Vec<String>,
+    /// The urls evaluated in order.
+    pub urls: Vec<PlannedUrl>,
+}
+
+impl CrawlPlan {
+    /// The urls the crawl would visit.
+    pub fn visits(&self) -> impl Iterator<Item = &PlannedUrl> {
+        self.with_verdict(PlanVerdict::Visit)
+    }
+
+    /// The urls with the verdict.
+    pub fn with_verdict(&self, verdict: PlanVerdict) -> impl Iterator<Item = &PlannedUrl> {
+        self.urls.iter().filter(move |url| url.verdict == verdict)
+    }
+
+    /// The urls with the verdict counted.
+    pub fn count(&self, verdict: PlanVerdict) -> usize {
+        self.with_verdict(verdict).count()
+    }
+
+    /// The sum of the content lengths of the urls visited, the urls without a length left out.
+    pub fn estimated_bytes(&self) -> u64 {
+        self.visits().filter_map(|url| url.content_length).sum()
+    }
+
+    /// The plan as text, the counts by verdict followed by a line per url.
+    pub fn report(&self) -> String {
+        let mut report = format!(
+            "visit: {}, filtered: {}, robots: {}, budget exceeded: {}, duplicate: {}, estimated bytes: {}\n",
+            self.count(PlanVerdict::Visit),
+            self.count(PlanVerdict::Filtered),
+            self.count(PlanVerdict::Robots),
+            self.count(PlanVerdict::BudgetExceeded),
+            self.count(PlanVerdict::Duplicate),
+            self.estimated_bytes()
+        );
+
+        for url in &self.urls {
+            report.push_str(&format!("{:?}\t{:?}\t", url.verdict, url.source));
+            if let Some(status) = url.status {
+                report.push_str(&status.to_string());
+            }
+            report.push('\t');
+            report.push_str(&url.url);
+            report.push('\n');
+        }
+
+        report
+    }
+}
+
+/// Send a HEAD request to the visited urls not downloaded, at most `concurrency` at once.
+pub(crate) async fn head_requests(client: &Client, urls: &mut [PlannedUrl], concurrency: usize) {
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(concurrency.max(1)));
+    let mut set = tokio::task::JoinSet::new();
+
+    for (i, url) in urls.iter().enumerate() {
+        if url.verdict != PlanVerdict::Visit || url.status.is_some() {
+            continue;
+        }
+
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        let url = url.url.clone();
+
+        set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.ok()?;
+            let response = client.head(&url).send().await.ok()?;
+            let headers = response.headers();
+            let content_type = headers
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from);
+            let content_length = headers
+                .get(reqwest::header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok());
+
+            Some((i, response.status().as_u16(), content_type, content_length))
+        });
+    }
+
+    while let Some(result) = set.join_next().await {
+        if let Ok(Some((i, status, content_type, content_length))) = result {
+            urls[i].status = Some(status);
+            urls[i].content_type = content_type;
+            urls[i].content_length = content_length;
+        }
+    }
+}
+
+/// Download the visited urls, at most `concurrency` at once, for their answer and their page.
+pub(crate) async fn fetch_pages(
+    client: &Client,
+    urls: &mut [PlannedUrl],
+    concurrency: usize,
+) -> Vec<Page> {
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(concurrency.max(1)));
+    let mut set = tokio::task::JoinSet::new();
+
+    for (i, url) in urls.iter().enumerate() {
+        if url.verdict != PlanVerdict::Visit {
+            continue;
+        }
+
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        let url = url.url.clone();
+
+        set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.ok()?;
+            let response = client.get(&url).send().await.ok()?;
+            let status_code = response.status();
+            let final_url = response.url().to_string();
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from);
+            let body = response.bytes().await.ok()?;
+            let content_length = body.len() as u64;
+            let page = crate::page::build(
+                &url,
+                PageResponse {
+                    content: Some(Box::new(body)),
+                    status_code,
+                    final_url: Some(final_url),
+                    ..Default::default()
+                },
+            );
+
+            Some((i, status_code.as_u16(), content_type, content_length, page))
+        });
+    }
+
+    let mut pages = Vec::new();
+
+    while let Some(result) = set.join_next().await {
+        if let Ok(Some((i, status, content_type, content_length, page))) = result {
+            urls[i].status = Some(status);
+            urls[i].content_type = content_type;
+            urls[i].content_length = Some(content_length);
+            pages.push(page);
+        }
+    }
+
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_report() {
+        let planned = |url: &str, verdict: PlanVerdict, content_length: Option<u64>| PlannedUrl {
+            url: url.into(),
+            source: PlanSource::Sitemap,
+            verdict,
+            status: content_length.map(|_| 200),
+            content_type: None,
+            content_length,
+        };
+        let plan = CrawlPlan {
+            sitemaps: vec!["https://example.com/sitemap.xml".into()],
+            urls: vec![
+                planned("https://example.com/", PlanVerdict::Visit, Some(100)),
+                planned("https://example.com/a", PlanVerdict::Visit, Some(50)),
+                planned("https://example.com/admin", PlanVerdict::Robots, None),
+                planned("https://example.com/a", PlanVerdict::Duplicate, None),
+            ],
+        };
+
+        assert_eq!(plan.visits().count(), 2);
+        assert_eq!(plan.count(PlanVerdict::Robots), 1);
+        assert_eq!(plan.estimated_bytes(), 150);
+
+        let report = plan.report();
+        assert!(report.starts_with(
+            "visit: 2, filtered: 0, robots: 1, budget exceeded: 0, duplicate: 1, estimated bytes: 150\n"
+        ));
+        assert!(report.contains("Visit\tSitemap\t200\thttps://example.com/a\n"));
+        assert!(report.contains("Robots\tSitemap\t\thttps://example.com/admin\n"));
+    }
+}
@@ -0,0 +1,372 @@
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, RedisResult, Script};
+use reqwest::StatusCode;
+use std::time::Duration;
+
+/// Add the links not seen before by any worker to the end of the queue. Returns the count of links queued.
+const PUSH_SCRIPT: &str = r#"
+local queued = 0
+for i = 1, #ARGV do
+    if redis.call('SADD', KEYS[1], ARGV[i]) == 1 then
+        redis.call('RPUSH', KEYS[2], ARGV[i])
+        queued = queued + 1
+    end
+end
+return queued
+"#;
+
+/// Take up to `ARGV[1]` links from the queue and lease them to the worker `ARGV[3]` for `ARGV[2]` milliseconds.
+const CLAIM_SCRIPT: &str = r#"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local claimed = {}
+for i = 1, tonumber(ARGV[1]) do
+    local link = redis.call('LPOP', KEYS[1])
+    if not link then
+        break
+    end
+    redis.call('ZADD', KEYS[2], now + tonumber(ARGV[2]), link)
+    redis.call('HSET', KEYS[3], link, ARGV[3])
+    claimed[#claimed + 1] = link
+end
+return claimed
+"#;
+
+/// Extend the leases the worker `ARGV[2]` still holds by `ARGV[1]` milliseconds.
+const RENEW_SCRIPT: &str = r#"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local renewed = 0
+for i = 3, #ARGV do
+    if redis.call('HGET', KEYS[2], ARGV[i]) == ARGV[2] then
+        redis.call('ZADD', KEYS[1], 'XX', now + tonumber(ARGV[1]), ARGV[i])
+        renewed = renewed + 1
+    end
+end
+return renewed
+"#;
+
+/// Put the links with an expired lease back at the front of the queue. Returns the count of links re-queued.
+const REQUEUE_SCRIPT: &str = r#"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local expired = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', now)
+for _, link in ipairs(expired) do
+    redis.call('ZREM', KEYS[1], link)
+    redis.call('HDEL', KEYS[2], link)
+    redis.call('LPUSH', KEYS[3], link)
+end
+return #expired
+"#;
+
+/// Crawl one site list with several worker processes or machines sharing the frontier, the visited links and the per
+/// host rate limits in Redis. Each worker leases the links it takes from the shared queue and renews the leases while
+/// the pages are fetched. The links leased by a worker that stopped renewing are re-queued for the other workers once
+/// the lease expires. Start the crawl with [`crate::website::Website::crawl_distributed`] on every worker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Distributed {
+    /// The Redis connection url, e.g. `redis://127.0.0.1/`.
+    pub redis_url: String,
+    /// The prefix of the Redis keys of the crawl. Workers with the same namespace crawl together.
+    pub namespace: String,
+    /// The name of the worker holding the leases. Defaults to the host name and the process id.
+    pub worker_id: String,
+    /// The time a worker holds a link before it is given to another worker.
+    pub lease: Duration,
+    /// The time between the checks of the shared queue when it is empty and other workers still hold leases.
+    pub poll_interval: Duration,
+    /// The minimum time between two requests to the same host across all the workers.
+    pub host_delay: Duration,
+    /// The time a host is paused for every worker after a 429 or 503 response without a `Retry-After` header.
+    pub host_backoff: Duration,
+}
+
+/// The default name of the worker.
+fn default_worker_id() -> String {
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "worker".into());
+    string_concat::string_concat!(host, "-", std::process::id().to_string())
+}
+
+impl Default for Distributed {
+    fn default() -> Self {
+        Self {
+            redis_url: "redis://127.0.0.1/".into(),
+            namespace: "spider".into(),
+            worker_id: default_worker_id(),
+            lease: Duration::from_secs(60),
+            poll_interval: Duration::from_millis(500),
+            host_delay: Duration::ZERO,
+            host_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Distributed {
+    /// Coordinate the crawl in the Redis server under the namespace.
+    pub fn new(redis_url: &str, namespace: &str) -> Self {
+        Self {
+            redis_url: redis_url.into(),
+            namespace: namespace.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the name of the worker.
+    pub fn with_worker_id(mut self, worker_id: &str) -> Self {
+        self.worker_id = worker_id.into();
+        self
+    }
+
+    /// Set the time a worker holds a link before it is given to another worker.
+    pub fn with_lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+
+    /// Set the time between the checks of an empty shared queue.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Set the minimum time between two requests to the same host across all the workers.
+    pub fn with_host_delay(mut self, host_delay: Duration) -> Self {
+        self.host_delay = host_delay;
+        self
+    }
+
+    /// Set the time a host is paused after a 429 or 503 response without a `Retry-After` header.
+    pub fn with_host_backoff(mut self, host_backoff: Duration) -> Self {
+        self.host_backoff = host_backoff;
+        self
+    }
+
+    /// The Redis key of the crawl.
+    pub(crate) fn key(&self, name: &str) -> String {
+        string_concat::string_concat!(self.namespace, ":", name)
+    }
+
+    /// The Redis key of the rate limit of the host of the url.
+    pub(crate) fn host_key(&self, url: &str) -> String {
+        let host = match url::Url::parse(url) {
+            Ok(u) => u.host_str().unwrap_or_default().to_string(),
+            _ => url.to_string(),
+        };
+        self.key(&string_concat::string_concat!("rate:", host))
+    }
+
+    /// Connect to the Redis server.
+    pub async fn connect(&self) -> RedisResult<Coordinator> {
+        let client = redis::Client::open(self.redis_url.as_str())?;
+
+        Ok(Coordinator {
+            con: client.get_connection_manager().await?,
+            config: self.clone(),
+        })
+    }
+}
+
+/// The connection of a worker to the shared crawl state.
+#[derive(Clone)]
+pub struct Coordinator {
+    /// The Redis connection.
+    con: ConnectionManager,
+    /// The crawl config.
+    config: Distributed,
+}
+
+impl Coordinator {
+    /// Queue the links no worker has seen yet. Returns the count of links queued.
+    pub async fn push<'a>(&self, links: impl IntoIterator<Item = &'a str>) -> RedisResult<usize> {
+        let links: Vec<&str> = links.into_iter().collect();
+
+        if links.is_empty() {
+            return Ok(0);
+        }
+
+        Script::new(PUSH_SCRIPT)
+            .key(self.config.key("seen"))
+            .key(self.config.key("queue"))
+            .arg(links)
+            .invoke_async(&mut self.con.clone())
+            .await
+    }
+
+    /// Lease up to `count` links of the shared queue to the worker.
+    pub async fn claim(&self, count: usize) -> RedisResult<Vec<String>> {
+        Script::new(CLAIM_SCRIPT)
+            .key(self.config.key("queue"))
+            .key(self.config.key("leases"))
+            .key(self.config.key("owners"))
+            .arg(count)
+            .arg(self.config.lease.as_millis() as u64)
+            .arg(&self.config.worker_id)
+            .invoke_async(&mut self.con.clone())
+            .await
+    }
+
+    /// Extend the leases of the links the worker is still crawling.
+    pub async fn renew<'a>(&self, links: impl IntoIterator<Item = &'a str>) -> RedisResult<usize> {
+        let links: Vec<&str> = links.into_iter().collect();
+
+        if links.is_empty() {
+            return Ok(0);
+        }
+
+        Script::new(RENEW_SCRIPT)
+            .key(self.config.key("leases"))
+            .key(self.config.key("owners"))
+            .arg(self.config.lease.as_millis() as u64)
+            .arg(&self.config.worker_id)
+            .arg(links)
+            .invoke_async(&mut self.con.clone())
+            .await
+    }
+
+    /// Release the lease of a crawled link.
+    pub async fn complete(&self, link: &str) -> RedisResult<()> {
+        redis::pipe()
+            .atomic()
+            .zrem(self.config.key("leases"), link)
+            .ignore()
+            .hdel(self.config.key("owners"), link)
+            .ignore()
+            .query_async(&mut self.con.clone())
+            .await
+    }
+
+    /// Re-queue the links of the workers that stopped renewing their leases.
+    pub async fn requeue_expired(&self) -> RedisResult<usize> {
+        Script::new(REQUEUE_SCRIPT)
+            .key(self.config.key("leases"))
+            .key(self.config.key("owners"))
+            .key(self.config.key("queue"))
+            .invoke_async(&mut self.con.clone())
+            .await
+    }
+
+    /// The count of links queued or leased by a worker.
+    pub async fn remaining(&self) -> RedisResult<usize> {
+        let (queued, leased): (usize, usize) = redis::pipe()
+            .llen(self.config.key("queue"))
+            .zcard(self.config.key("leases"))
+            .query_async(&mut self.con.clone())
+            .await?;
+
+        Ok(queued + leased)
+    }
+
+    /// Whether the link was seen by a worker.
+    pub async fn seen(&self, link: &str) -> RedisResult<bool> {
+        self.con
+            .clone()
+            .sismember(self.config.key("seen"), link)
+            .await
+    }
+
+    /// Wait for the turn of the worker to request the host of the url. The host delay is shared by all the workers.
+    pub async fn acquire_host(&self, url: &str) -> RedisResult<()> {
+        let delay = self.config.host_delay.as_millis() as u64;
+        let key = self.config.host_key(url);
+        let mut con = self.con.clone();
+
+        loop {
+            let wait: i64 = con.pttl(&key).await?;
+
+            if wait > 0 {
+                tokio::time::sleep(Duration::from_millis(wait as u64)).await;
+                continue;
+            }
+
+            if delay == 0 {
+                return Ok(());
+            }
+
+            let acquired: Option<String> = redis::cmd("SET")
+                .arg(&key)
+                .arg(&self.config.worker_id)
+                .arg("NX")
+                .arg("PX")
+                .arg(delay)
+                .query_async(&mut con)
+                .await?;
+
+            if acquired.is_some() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Pause the host of the url for every worker after a 429 or 503 response.
+    pub async fn record_host(
+        &self,
+        url: &str,
+        status_code: StatusCode,
+        retry_after: Option<Duration>,
+    ) -> RedisResult<()> {
+        if status_code == StatusCode::TOO_MANY_REQUESTS
+            || status_code == StatusCode::SERVICE_UNAVAILABLE
+        {
+            let pause = retry_after.unwrap_or(self.config.host_backoff);
+
+            if !pause.is_zero() {
+                let _: () = self
+                    .con
+                    .clone()
+                    .pset_ex(
+                        self.config.host_key(url),
+                        &self.config.worker_id,
+                        pause.as_millis() as u64,
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove the shared state of the crawl to start it again from the beginning. The host rate limits expire on their own.
+    pub async fn clear(&self) -> RedisResult<()> {
+        let keys: Vec<String> = ["seen", "queue", "leases", "owners"]
+            .iter()
+            .map(|name| self.config.key(name))
+            .collect();
+
+        self.con.clone().del(keys).await
+    }
+
+    /// The crawl config.
+    pub fn config(&self) -> &Distributed {
+        &self.config
+    }
+}
+
+impl std::fmt::Debug for Coordinator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Coordinator")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distributed_keys() {
+        let distributed = Distributed::new("redis://127.0.0.1/", "crawl-1")
+            .with_worker_id("worker-a")
+            .with_lease(Duration::from_secs(10));
+
+        assert_eq!(distributed.key("queue"), "crawl-1:queue");
+        assert_eq!(
+            distributed.host_key("https://example.com/a?b=c"),
+            "crawl-1:rate:example.com"
+        );
+        assert_eq!(distributed.worker_id, "worker-a");
+        assert_eq!(distributed.lease, Duration::from_secs(10));
+        assert!(!Distributed::default().worker_id.is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::page::Page;
+use tokio::sync::broadcast;
+
+/// An event of the crawl lifecycle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CrawlEvent {
+    /// A page was crawled with a success or a not modified status.
+    PageCrawled {
+        /// The url of the page.
+        url: String,
+        /// The status code of the page.
+        status_code: u16,
+        /// The depth of the page from the start url.
+        depth: usize,
+        /// The bytes of the page body.
+        bytes: usize,
+    },
+    /// A page was crawled with an error status or failed to fetch.
+    PageFailed {
+        /// The url of the page.
+        url: String,
+        /// The status code of the page, `0` when the request failed before a response.
+        status_code: u16,
+        /// The error of the request.
+        error: Option<String>,
+    },
+    /// The browser blocked a request of a page.
+    RequestBlocked {
+        /// The url of the page.
+        page_url: String,
+        /// The url of the request.
+        url: String,
+        /// The resource type of the request like `Image`.
+        resource_type: String,
+        /// Why the request was blocked like `third_party` or `ad`.
+        reason: String,
+    },
+    /// The crawl budget was reached, the link is not crawled.
+    BudgetExceeded {
+        /// The link over the budget.
+        url: String,
+    },
+    /// A server or a proxy asked the browser to authenticate.
+    AuthChallenged {
+        /// The url of the page.
+        page_url: String,
+        /// The url of the request challenged.
+        url: String,
+        /// The origin of the challenge, `Server` or `Proxy`.
+        source: String,
+        /// The authentication scheme like `basic`.
+        scheme: String,
+    },
+    /// The renderer of a page crashed.
+    TargetCrashed {
+        /// The url of the page.
+        url: String,
+    },
+}
+
+impl CrawlEvent {
+    /// The event of the page crawled.
+    pub fn from_page(page: &Page) -> Self {
+        let url = page.get_url().to_string();
+        let status_code = page.status_code;
+
+        if status_code.is_success()
+            || status_code.is_redirection()
+            || status_code == reqwest::StatusCode::NOT_MODIFIED
+        {
+            CrawlEvent::PageCrawled {
+                url,
+                status_code: status_code.as_u16(),
+                depth: page.depth,
+                bytes: page.get_bytes().map_or(0, |bytes| bytes.len()),
+            }
+        } else {
+            CrawlEvent::PageFailed {
+                url,
+                status_code: status_code.as_u16(),
+                error: page.error_status.clone(),
+            }
+        }
+    }
+
+    /// The url of the page or the link of the event.
+    pub fn url(&self) -> &str {
+        match self {
+            CrawlEvent::PageCrawled { url, .. }
+            | CrawlEvent::PageFailed { url, .. }
+            | CrawlEvent::BudgetExceeded { url }
+            | CrawlEvent::TargetCrashed { url } => url,
+            CrawlEvent::RequestBlocked { page_url, .. }
+            | CrawlEvent::AuthChallenged { page_url, .. } => page_url,
+        }
+    }
+}
+
+/// The sender of the crawl events shared by the crawl tasks. The events are dropped while nobody is subscribed, a
+/// subscriber lagging behind the capacity misses the oldest events.
+#[derive(Debug, Clone)]
+pub struct CrawlEvents {
+    /// The channel of the events.
+    sender: broadcast::Sender<CrawlEvent>,
+}
+
+impl Default for CrawlEvents {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl PartialEq for CrawlEvents {
+    fn eq(&self, other: &Self) -> bool {
+        self.sender.same_channel(&other.sender)
+    }
+}
+
+impl CrawlEvents {
+    /// A channel buffering up to `capacity` events for each subscriber.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Receive the events sent from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<CrawlEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Send the event to the subscribers.
+    pub fn send(&self, event: CrawlEvent) {
+        if self.sender.receiver_count() > 0 {
+            let _ = self.sender.send(event);
+        }
+    }
+
+    /// Send the event of the page crawled.
+    pub fn page(&self, page: &Page) {
+        if self.sender.receiver_count() > 0 {
+            let _ = self.sender.send(CrawlEvent::from_page(page));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page::build;
+    use crate::utils::PageResponse;
+
+    #[tokio::test]
+    async fn send_crawl_events() {
+        let events = CrawlEvents::new(8);
+
+        events.send(CrawlEvent::BudgetExceeded {
+            url: "https://example.com/dropped".into(),
+        });
+
+        let mut rx = events.subscribe();
+        let page = |status_code| {
+            build(
+                "https://example.com/",
+                PageResponse {
+                    content: Some(Box::new("<p>hello</p>".to_string().into())),
+                    status_code,
+                    ..Default::default()
+                },
+            )
+        };
+
+        events.page(&page(reqwest::StatusCode::OK));
+        events.page(&page(reqwest::StatusCode::INTERNAL_SERVER_ERROR));
+
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            CrawlEvent::PageCrawled {
+                url: "https://example.com/".into(),
+                status_code: 200,
+                depth: 0,
+                bytes: 12,
+            }
+        );
+        assert!(matches!(
+            rx.recv().await.unwrap(),
+            CrawlEvent::PageFailed {
+                status_code: 500,
+                ..
+            }
+        ));
+        assert!(rx.try_recv().is_err());
+        assert_eq!(events, events.clone());
+    }
+}
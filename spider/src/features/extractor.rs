@@ -0,0 +1,442 @@
+use crate::packages::scraper::{ElementRef, Html, Selector};
+use crate::page::Page;
+use crate::utils::element_text;
+use hashbrown::HashMap;
+use url::Url;
+
+/// An element matched by a CSS selector.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Selection {
+    /// The tag name of the element.
+    pub name: String,
+    /// The text of the element and its descendants with the whitespace collapsed.
+    pub text: String,
+    /// The html of the element.
+    pub html: String,
+    /// The html of the children of the element.
+    pub inner_html: String,
+    /// The attributes of the element in document order.
+    pub attributes: Vec<(String, String)>,
+}
+
+impl Selection {
+    /// The value of the attribute.
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl From<ElementRef<'_>> for Selection {
+    fn from(element: ElementRef<'_>) -> Self {
+        Self {
+            name: element.value().name().to_string(),
+            text: element_text(&element),
+            html: element.html(),
+            inner_html: element.inner_html(),
+            attributes: element
+                .value()
+                .attrs()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+}
+
+/// The elements of the html matching the CSS selector. Invalid selectors match nothing.
+pub fn select(html: &str, selector: &str) -> Vec<Selection> {
+    match Selector::parse(selector) {
+        Ok(selector) => Html::parse_document(html)
+            .select(&selector)
+            .map(Selection::from)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The value read from the elements matched by a field.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FieldKind {
+    /// The text with the whitespace collapsed.
+    #[default]
+    Text,
+    /// The html of the element.
+    Html,
+    /// The html of the children of the element.
+    InnerHtml,
+    /// The value of the attribute.
+    Attribute(String),
+    /// The value of the attribute resolved against the page url ex: `href` or `src`.
+    Url(String),
+    /// The text parsed as a number ignoring the characters around it ex: `$1,299.00` is `1299.0`.
+    Number,
+    /// Whether the selector matched.
+    Exists,
+    /// The count of matches.
+    Count,
+}
+
+/// A value extracted from a page.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum ExtractedValue {
+    /// Nothing matched or the value could not be read.
+    Null,
+    /// A text, html or attribute value.
+    Text(String),
+    /// A number.
+    Number(f64),
+    /// Whether the selector matched.
+    Bool(bool),
+    /// The count of matches.
+    Count(usize),
+    /// The values of every match.
+    List(Vec<ExtractedValue>),
+}
+
+/// A named field of an [`Extractor`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtractorField {
+    /// The name of the field in the results.
+    pub name: String,
+    /// The CSS selector of the elements or the XPath expression when `xpath` is set.
+    pub selector: String,
+    /// The value read from the elements.
+    pub kind: FieldKind,
+    /// Read every match into a list instead of the first match.
+    pub multiple: bool,
+    /// The selector is a XPath 1.0 expression ex: `//div[@class="price"]/text()`. Requires the feature flag `xpath`,
+    /// the field is `Null` without it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub xpath: bool,
+}
+
+/// A declarative scraping schema mapping names to CSS selectors or XPath expressions and the kind of value to read. The values of the
+/// crawled pages are set on [`Page::extracted`] so a crawl collects structured records without another html parser.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Extractor {
+    /// The fields extracted in order.
+    pub fields: Vec<ExtractorField>,
+}
+
+/// The number in the text ignoring the characters around it and the thousands separators.
+fn parse_number(text: &str) -> Option<f64> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let sign = if text[..start].ends_with('-') {
+        "-"
+    } else {
+        ""
+    };
+    let digits: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.' || *c == ',')
+        .filter(|c| *c != ',')
+        .collect();
+
+    format!("{sign}{}", digits.trim_end_matches('.'))
+        .parse()
+        .ok()
+}
+
+impl Extractor {
+    /// An empty schema.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a field reading the first match of the selector.
+    pub fn with_field(mut self, name: &str, selector: &str, kind: FieldKind) -> Self {
+        self.fields.push(ExtractorField {
+            name: name.into(),
+            selector: selector.into(),
+            kind,
+            multiple: false,
+            xpath: false,
+        });
+        self
+    }
+
+    /// Add a field reading every match of the selector into a list.
+    pub fn with_list(mut self, name: &str, selector: &str, kind: FieldKind) -> Self {
+        self.fields.push(ExtractorField {
+            name: name.into(),
+            selector: selector.into(),
+            kind,
+            multiple: true,
+            xpath: false,
+        });
+        self
+    }
+
+    /// Add a field reading the first node matched by the XPath expression. The text, attribute and comment nodes are
+    /// read as text, the `Url` kind resolves them against the page url and the `Number` kind parses them. Expressions
+    /// returning a string, number or boolean are read as is. Requires the feature flag `xpath`.
+    pub fn with_xpath_field(mut self, name: &str, expression: &str, kind: FieldKind) -> Self {
+        self.fields.push(ExtractorField {
+            name: name.into(),
+            selector: expression.into(),
+            kind,
+            multiple: false,
+            xpath: true,
+        });
+        self
+    }
+
+    /// Add a field reading every node matched by the XPath expression into a list. Requires the feature flag `xpath`.
+    pub fn with_xpath_list(mut self, name: &str, expression: &str, kind: FieldKind) -> Self {
+        self.fields.push(ExtractorField {
+            name: name.into(),
+            selector: expression.into(),
+            kind,
+            multiple: true,
+            xpath: true,
+        });
+        self
+    }
+
+    /// The value of the kind read from the element.
+    fn read(kind: &FieldKind, element: &ElementRef, base: Option<&Url>) -> ExtractedValue {
+        match kind {
+            FieldKind::Text => ExtractedValue::Text(element_text(element)),
+            FieldKind::Html => ExtractedValue::Text(element.html()),
+            FieldKind::InnerHtml => ExtractedValue::Text(element.inner_html()),
+            FieldKind::Attribute(name) => match element.attr(name) {
+                Some(value) => ExtractedValue::Text(value.to_string()),
+                _ => ExtractedValue::Null,
+            },
+            FieldKind::Url(name) => match element.attr(name) {
+                Some(value) => {
+                    ExtractedValue::Text(match base.map(|base| base.join(value.trim())) {
+                        Some(Ok(url)) => url.into(),
+                        _ => value.trim().to_string(),
+                    })
+                }
+                _ => ExtractedValue::Null,
+            },
+            FieldKind::Number => match parse_number(&element_text(element)) {
+                Some(number) => ExtractedValue::Number(number),
+                _ => ExtractedValue::Null,
+            },
+            FieldKind::Exists => ExtractedValue::Bool(true),
+            FieldKind::Count => ExtractedValue::Count(1),
+        }
+    }
+
+    /// The value of the kind read from a text node matched by a XPath expression.
+    #[cfg(feature = "xpath")]
+    fn read_text(kind: &FieldKind, text: &str, base: Option<&Url>) -> ExtractedValue {
+        match kind {
+            FieldKind::Url(_) => {
+                ExtractedValue::Text(match base.map(|base| base.join(text.trim())) {
+                    Some(Ok(url)) => url.into(),
+                    _ => text.trim().to_string(),
+                })
+            }
+            FieldKind::Number => match parse_number(text) {
+                Some(number) => ExtractedValue::Number(number),
+                _ => ExtractedValue::Null,
+            },
+            _ => ExtractedValue::Text(text.split_whitespace().collect::<Vec<_>>().join(" ")),
+        }
+    }
+
+    /// The value of the XPath field for the parsed html. Invalid expressions are `Null`.
+    #[cfg(feature = "xpath")]
+    fn extract_xpath(
+        field: &ExtractorField,
+        document: &Html,
+        base: Option<&Url>,
+    ) -> ExtractedValue {
+        use super::xpath::{evaluate_html, Evaluated, Matched, XPathValue};
+
+        let read = |node: Matched| match node {
+            Matched::Element(element) => Self::read(&field.kind, &element, base),
+            Matched::Attribute(_, text) | Matched::Text(text) => {
+                Self::read_text(&field.kind, &text, base)
+            }
+        };
+
+        match evaluate_html(document, &field.selector) {
+            Ok(Evaluated::Nodes(nodes)) => match &field.kind {
+                FieldKind::Exists => ExtractedValue::Bool(!nodes.is_empty()),
+                FieldKind::Count => ExtractedValue::Count(nodes.len()),
+                _ if field.multiple => ExtractedValue::List(nodes.into_iter().map(read).collect()),
+                _ => match nodes.into_iter().next() {
+                    Some(node) => read(node),
+                    _ => ExtractedValue::Null,
+                },
+            },
+            Ok(Evaluated::Scalar(XPathValue::String(text))) => {
+                Self::read_text(&field.kind, &text, base)
+            }
+            Ok(Evaluated::Scalar(XPathValue::Number(number))) => ExtractedValue::Number(number),
+            Ok(Evaluated::Scalar(XPathValue::Boolean(value))) => ExtractedValue::Bool(value),
+            _ => ExtractedValue::Null,
+        }
+    }
+
+    /// The values of the fields for the html. Fields with an invalid selector are `Null`.
+    pub fn extract(&self, html: &str, base: Option<&Url>) -> HashMap<String, ExtractedValue> {
+        let document = Html::parse_document(html);
+        let mut values = HashMap::with_capacity(self.fields.len());
+
+        for field in &self.fields {
+            if field.xpath {
+                #[cfg(feature = "xpath")]
+                let value = Self::extract_xpath(field, &document, base);
+                #[cfg(not(feature = "xpath"))]
+                let value = ExtractedValue::Null;
+
+                values.insert(field.name.clone(), value);
+                continue;
+            }
+
+            let selector = match Selector::parse(&field.selector) {
+                Ok(selector) => selector,
+                _ => {
+                    values.insert(field.name.clone(), ExtractedValue::Null);
+                    continue;
+                }
+            };
+            let mut matches = document.select(&selector);
+
+            let value = match &field.kind {
+                FieldKind::Exists => ExtractedValue::Bool(matches.next().is_some()),
+                FieldKind::Count => ExtractedValue::Count(matches.count()),
+                kind if field.multiple => ExtractedValue::List(
+                    matches
+                        .map(|element| Self::read(kind, &element, base))
+                        .collect(),
+                ),
+                kind => match matches.next() {
+                    Some(element) => Self::read(kind, &element, base),
+                    _ => ExtractedValue::Null,
+                },
+            };
+
+            values.insert(field.name.clone(), value);
+        }
+
+        values
+    }
+
+    /// The values of the fields for the page.
+    pub fn extract_page(&self, page: &Page) -> HashMap<String, ExtractedValue> {
+        let base = Url::parse(page.get_url()).ok();
+        self.extract(&page.get_html(), base.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = r#"<html><body>
+        <div class="article"><h2>First <b>post</b></h2><a href="/1" rel="next">Read</a></div>
+        <div class="article"><h2>Second   post</h2><span class="price">$1,299.50</span></div>
+    </body></html>"#;
+
+    #[test]
+    fn select_elements() {
+        let matches = select(HTML, "div.article h2");
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].name, "h2");
+        assert_eq!(matches[0].text, "First post");
+        assert_eq!(matches[0].inner_html, "First <b>post</b>");
+        assert_eq!(matches[1].text, "Second post");
+        assert_eq!(select(HTML, "a[rel]")[0].attr("href"), Some("/1"));
+        assert!(select(HTML, "div[").is_empty());
+    }
+
+    #[test]
+    fn extract_fields() {
+        let base = Url::parse("https://example.com/blog/").unwrap();
+        let extractor = Extractor::new()
+            .with_field("title", "h2", FieldKind::Text)
+            .with_list("titles", "h2", FieldKind::Text)
+            .with_field("next", "a[rel=next]", FieldKind::Url("href".into()))
+            .with_field("price", ".price", FieldKind::Number)
+            .with_field("articles", ".article", FieldKind::Count)
+            .with_field("comments", ".comment", FieldKind::Exists)
+            .with_field("missing", ".missing", FieldKind::Text)
+            .with_field("invalid", "div[", FieldKind::Text);
+
+        let values = extractor.extract(HTML, Some(&base));
+
+        assert_eq!(values["title"], ExtractedValue::Text("First post".into()));
+        assert_eq!(
+            values["titles"],
+            ExtractedValue::List(vec![
+                ExtractedValue::Text("First post".into()),
+                ExtractedValue::Text("Second post".into())
+            ])
+        );
+        assert_eq!(
+            values["next"],
+            ExtractedValue::Text("https://example.com/1".into())
+        );
+        assert_eq!(values["price"], ExtractedValue::Number(1299.5));
+        assert_eq!(values["articles"], ExtractedValue::Count(2));
+        assert_eq!(values["comments"], ExtractedValue::Bool(false));
+        assert_eq!(values["missing"], ExtractedValue::Null);
+        assert_eq!(values["invalid"], ExtractedValue::Null);
+        assert_eq!(parse_number("-3 items"), Some(-3.0));
+    }
+
+    #[test]
+    fn extract_page_fields() {
+        let page = crate::page::build(
+            "https://example.com/blog/",
+            crate::utils::PageResponse {
+                content: Some(Box::new(HTML.to_string().into())),
+                ..Default::default()
+            },
+        );
+        let values = Extractor::new()
+            .with_field("next", "a[rel=next]", FieldKind::Url("href".into()))
+            .extract_page(&page);
+
+        assert_eq!(
+            values["next"],
+            ExtractedValue::Text("https://example.com/1".into())
+        );
+        assert_eq!(page.select("div.article h2")[1].text, "Second post");
+    }
+
+    #[cfg(feature = "xpath")]
+    #[test]
+    fn extract_xpath_fields() {
+        let base = Url::parse("https://example.com/blog/").unwrap();
+        let extractor = Extractor::new()
+            .with_xpath_field("title", "//div[@class='article']/h2", FieldKind::Text)
+            .with_xpath_list("links", "//a/@href", FieldKind::Url("href".into()))
+            .with_xpath_field("price", "//span[@class='price']/text()", FieldKind::Number)
+            .with_xpath_field(
+                "articles",
+                "count(//div[@class='article'])",
+                FieldKind::Text,
+            )
+            .with_xpath_field("comments", "//div[@class='comment']", FieldKind::Exists)
+            .with_xpath_field("invalid", "//div[", FieldKind::Text);
+
+        let values = extractor.extract(HTML, Some(&base));
+
+        assert_eq!(values["title"], ExtractedValue::Text("First post".into()));
+        assert_eq!(
+            values["links"],
+            ExtractedValue::List(vec![ExtractedValue::Text("https://example.com/1".into())])
+        );
+        assert_eq!(values["price"], ExtractedValue::Number(1299.5));
+        assert_eq!(values["articles"], ExtractedValue::Number(2.0));
+        assert_eq!(values["comments"], ExtractedValue::Bool(false));
+        assert_eq!(values["invalid"], ExtractedValue::Null);
+    }
+}
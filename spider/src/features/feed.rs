@@ -0,0 +1,444 @@
+use crate::page::Page;
+use crate::utils::log;
+use crate::CaseInsensitiveString;
+use crate::Client;
+use hashbrown::HashSet;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::NsReader;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// An entry of a RSS or Atom feed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeedItem {
+    /// The title of the entry.
+    pub title: Option<String>,
+    /// The url the entry links to.
+    pub link: Option<String>,
+    /// The publish date as written in the feed, `pubDate` for RSS and `published` or `updated` for Atom.
+    pub published: Option<String>,
+}
+
+/// A RSS or Atom feed found on the crawled pages.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Feed {
+    /// The url of the feed.
+    pub url: String,
+    /// The title of the channel or feed.
+    pub title: Option<String>,
+    /// The entries of the feed.
+    pub items: Vec<FeedItem>,
+}
+
+/// The feed urls of a html document: the `<link rel="alternate">` elements with a RSS or Atom type resolved against the base url.
+pub fn discover_feeds(html: &str, base: Option<&Url>) -> Vec<String> {
+    let mut feeds: Vec<String> = Vec::new();
+
+    let _ = lol_html::rewrite_str(
+        html,
+        lol_html::RewriteStrSettings {
+            element_content_handlers: vec![lol_html::element!("link[href][type]", |el| {
+                let alternate = el.get_attribute("rel").is_some_and(|rel| {
+                    rel.split_ascii_whitespace()
+                        .any(|rel| rel.eq_ignore_ascii_case("alternate"))
+                });
+                let feed_type = el.get_attribute("type").is_some_and(|kind| {
+                    let kind = kind.trim();
+                    kind.eq_ignore_ascii_case("application/rss+xml")
+                        || kind.eq_ignore_ascii_case("application/atom+xml")
+                });
+
+                if alternate && feed_type {
+                    if let Some(href) = el.get_attribute("href") {
+                        let url = match base {
+                            Some(base) => base.join(href.trim()).ok().map(String::from),
+                            _ => Url::parse(href.trim()).ok().map(String::from),
+                        };
+                        if let Some(url) = url {
+                            if !feeds.contains(&url) {
+                                feeds.push(url);
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            })],
+            ..lol_html::RewriteStrSettings::new()
+        },
+    );
+
+    feeds
+}
+
+/// The field of the feed the text being read belongs to.
+#[derive(Clone, Copy, PartialEq)]
+enum FeedField {
+    Title,
+    Link,
+    Published,
+    Updated,
+}
+
+/// The `href` of an Atom `<link>` pointing at the entry itself.
+fn atom_link(e: &BytesStart) -> Option<String> {
+    let alternate = match e.try_get_attribute(b"rel") {
+        Ok(Some(rel)) => rel.value.as_ref() == b"alternate",
+        _ => true,
+    };
+
+    if alternate {
+        match e.try_get_attribute(b"href") {
+            Ok(Some(href)) => href.unescape_value().ok().map(|v| v.trim().to_string()),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+/// Parse a RSS or Atom feed. Relative entry links are resolved against the feed url.
+pub fn parse_feed(url: &str, bytes: &[u8]) -> Feed {
+    let mut feed = Feed {
+        url: url.into(),
+        ..Default::default()
+    };
+    let base = Url::parse(url).ok();
+    let resolve = |link: String| match &base {
+        Some(base) => base.join(&link).map(String::from).unwrap_or(link),
+        _ => link,
+    };
+
+    let mut reader = NsReader::from_reader(bytes);
+    reader.config_mut().trim_text(true);
+
+    let mut buf = Vec::new();
+    let mut item: Option<FeedItem> = None;
+    let mut field: Option<FeedField> = None;
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let (_, local) = reader.resolve_element(e.name());
+                match local.as_ref() {
+                    b"item" | b"entry" => item = Some(FeedItem::default()),
+                    b"title" => field = Some(FeedField::Title),
+                    b"link" => match atom_link(&e) {
+                        Some(link) => {
+                            if let Some(item) = item.as_mut() {
+                                item.link.get_or_insert_with(|| resolve(link));
+                            }
+                        }
+                        _ => field = Some(FeedField::Link),
+                    },
+                    b"pubDate" | b"published" => field = Some(FeedField::Published),
+                    b"updated" | b"date" => field = Some(FeedField::Updated),
+                    _ => (),
+                }
+                text.clear();
+            }
+            Ok(Event::Empty(e)) => {
+                let (_, local) = reader.resolve_element(e.name());
+                if local.as_ref() == b"link" {
+                    if let (Some(item), Some(link)) = (item.as_mut(), atom_link(&e)) {
+                        item.link.get_or_insert_with(|| resolve(link));
+                    }
+                }
+            }
+            Ok(Event::Text(e)) if field.is_some() => {
+                if let Ok(v) = e.unescape() {
+                    text.push_str(&v);
+                }
+            }
+            Ok(Event::CData(e)) if field.is_some() => {
+                text.push_str(&String::from_utf8_lossy(&e.into_inner()));
+            }
+            Ok(Event::End(e)) => {
+                let (_, local) = reader.resolve_element(e.name());
+
+                if let b"item" | b"entry" = local.as_ref() {
+                    if let Some(item) = item.take() {
+                        if item.link.is_some() || item.title.is_some() {
+                            feed.items.push(item);
+                        }
+                    }
+                } else if let Some(current) = field.take() {
+                    let value = text.trim().to_string();
+
+                    if !value.is_empty() {
+                        match (item.as_mut(), current) {
+                            (Some(item), FeedField::Title) => {
+                                item.title.get_or_insert(value);
+                            }
+                            (Some(item), FeedField::Link) => {
+                                item.link.get_or_insert_with(|| resolve(value));
+                            }
+                            (Some(item), FeedField::Published) => item.published = Some(value),
+                            (Some(item), FeedField::Updated) => {
+                                item.published.get_or_insert(value);
+                            }
+                            (None, FeedField::Title) => {
+                                feed.title.get_or_insert(value);
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+                text.clear();
+            }
+            Ok(Event::Eof) => break,
+            Err(err) => {
+                log("feed parse error: ", err.to_string());
+                break;
+            }
+            _ => (),
+        }
+        buf.clear();
+    }
+
+    feed
+}
+
+/// Fetch and parse a feed.
+pub async fn fetch_feed(client: &Client, url: &str) -> Option<Feed> {
+    match client.get(url).send().await {
+        Ok(response) => match response.bytes().await {
+            Ok(bytes) => Some(parse_feed(url, &bytes)),
+            Err(err) => {
+                log("http parse error: ", err.to_string());
+                None
+            }
+        },
+        Err(err) => {
+            log("http network error: ", err.to_string());
+            None
+        }
+    }
+}
+
+/// The feeds fetched during a crawl.
+#[derive(Debug, Default)]
+pub(crate) struct FeedState {
+    /// The feed urls already claimed by a page.
+    seen: HashSet<String>,
+    /// The feeds fetched.
+    feeds: Vec<Feed>,
+}
+
+/// Discover the RSS and Atom feeds linked by the crawled pages, fetch them and collect their entries.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeedDiscovery {
+    /// Add the entry links of the feeds on the host of the page to the crawl.
+    pub seed_frontier: bool,
+    /// The max amount of feeds to fetch for a crawl.
+    pub max_feeds: Option<usize>,
+    /// The feeds of the crawl.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) state: Arc<Mutex<FeedState>>,
+}
+
+impl Default for FeedDiscovery {
+    fn default() -> Self {
+        Self {
+            seed_frontier: false,
+            max_feeds: Some(50),
+            state: Default::default(),
+        }
+    }
+}
+
+impl PartialEq for FeedDiscovery {
+    fn eq(&self, other: &Self) -> bool {
+        self.seed_frontier == other.seed_frontier && self.max_feeds == other.max_feeds
+    }
+}
+
+impl FeedDiscovery {
+    /// Collect the feeds without adding their entries to the crawl, fetching up to 50 feeds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the entry links of the feeds on the host of the page to the crawl.
+    pub fn with_seed_frontier(mut self, seed_frontier: bool) -> Self {
+        self.seed_frontier = seed_frontier;
+        self
+    }
+
+    /// Set the max amount of feeds to fetch for a crawl. Use `None` for no limit.
+    pub fn with_max_feeds(mut self, max_feeds: Option<usize>) -> Self {
+        self.max_feeds = max_feeds;
+        self
+    }
+
+    /// Claim the feed urls not fetched yet within the limit.
+    fn claim(&self, urls: Vec<String>) -> Vec<String> {
+        match self.state.lock() {
+            Ok(mut state) => urls
+                .into_iter()
+                .filter(|url| {
+                    !matches!(self.max_feeds, Some(max) if state.seen.len() >= max)
+                        && state.seen.insert(url.clone())
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Fetch the new feeds linked by the page returning the entry links to crawl.
+    pub(crate) async fn discover(
+        &self,
+        client: &Client,
+        page: &Page,
+    ) -> HashSet<CaseInsensitiveString> {
+        let mut links = HashSet::new();
+        let base = Url::parse(page.get_url()).ok();
+        let urls = self.claim(discover_feeds(&page.get_html(), base.as_ref()));
+
+        for url in urls {
+            if let Some(feed) = fetch_feed(client, &url).await {
+                if self.seed_frontier {
+                    let host = base.as_ref().and_then(|base| base.host_str());
+
+                    for link in feed.items.iter().filter_map(|item| item.link.as_deref()) {
+                        let same_host = Url::parse(link)
+                            .ok()
+                            .is_some_and(|link| link.host_str() == host);
+
+                        if same_host {
+                            links.insert(CaseInsensitiveString::new(link));
+                        }
+                    }
+                }
+                if let Ok(mut state) = self.state.lock() {
+                    state.feeds.push(feed);
+                }
+            }
+        }
+
+        links
+    }
+
+    /// The feeds fetched since the crawl started.
+    pub fn feeds(&self) -> Vec<Feed> {
+        match self.state.lock() {
+            Ok(state) => state.feeds.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Reset the feeds for a new crawl.
+    pub(crate) fn reset(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            *state = FeedState::default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovers_feed_links() {
+        let html = r#"<html><head>
+            <link rel="alternate" type="application/rss+xml" href="/feed.xml">
+            <link rel="alternate" type="application/atom+xml" href="https://example.com/atom">
+            <link rel="alternate" type="application/rss+xml" href="/feed.xml">
+            <link rel="stylesheet" type="text/css" href="/style.css">
+            <link rel="alternate" hreflang="fr" href="/fr">
+        </head></html>"#;
+        let base = Url::parse("https://example.com/blog/").ok();
+
+        assert_eq!(
+            discover_feeds(html, base.as_ref()),
+            vec![
+                "https://example.com/feed.xml".to_string(),
+                "https://example.com/atom".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_rss() {
+        let rss = br#"<?xml version="1.0"?>
+            <rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
+            <channel>
+                <title>Example &amp; Co</title>
+                <atom:link href="https://example.com/feed.xml" rel="self"/>
+                <link>https://example.com/</link>
+                <item>
+                    <title><![CDATA[First post]]></title>
+                    <link>https://example.com/first</link>
+                    <pubDate>Mon, 06 Jan 2025 10:00:00 GMT</pubDate>
+                </item>
+                <item>
+                    <title>Second post</title>
+                    <link>/second</link>
+                </item>
+            </channel>
+            </rss>"#;
+
+        let feed = parse_feed("https://example.com/feed.xml", rss);
+
+        assert_eq!(feed.title.as_deref(), Some("Example & Co"));
+        assert_eq!(
+            feed.items,
+            vec![
+                FeedItem {
+                    title: Some("First post".into()),
+                    link: Some("https://example.com/first".into()),
+                    published: Some("Mon, 06 Jan 2025 10:00:00 GMT".into()),
+                },
+                FeedItem {
+                    title: Some("Second post".into()),
+                    link: Some("https://example.com/second".into()),
+                    published: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_atom() {
+        let atom = br#"<?xml version="1.0" encoding="utf-8"?>
+            <feed xmlns="http://www.w3.org/2005/Atom">
+                <title>Example</title>
+                <link href="https://example.com/" rel="alternate"/>
+                <entry>
+                    <title>Post</title>
+                    <link rel="edit" href="https://example.com/edit/1"/>
+                    <link href="https://example.com/post"/>
+                    <updated>2025-01-07T10:00:00Z</updated>
+                    <published>2025-01-06T10:00:00Z</published>
+                </entry>
+            </feed>"#;
+
+        let feed = parse_feed("https://example.com/atom", atom);
+
+        assert_eq!(feed.title.as_deref(), Some("Example"));
+        assert_eq!(
+            feed.items,
+            vec![FeedItem {
+                title: Some("Post".into()),
+                link: Some("https://example.com/post".into()),
+                published: Some("2025-01-06T10:00:00Z".into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn claims_feeds_within_limit() {
+        let discovery = FeedDiscovery::new().with_max_feeds(Some(2));
+        let urls = |urls: &[&str]| urls.iter().map(|u| u.to_string()).collect();
+
+        assert_eq!(discovery.claim(urls(&["a", "b"])).len(), 2);
+        assert!(discovery.claim(urls(&["a", "c"])).is_empty());
+
+        discovery.reset();
+        assert_eq!(discovery.claim(urls(&["c"])), vec!["c".to_string()]);
+    }
+}
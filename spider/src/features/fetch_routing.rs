@@ -0,0 +1,305 @@
+#[cfg(feature = "chrome")]
+use crate::features::politeness::PolitenessProfiles;
+use crate::features::should_crawl::FetchStrategy;
+#[cfg(feature = "chrome")]
+use crate::page::Page;
+#[cfg(feature = "chrome")]
+use crate::utils::frontier::Frontier;
+use crate::utils::url_matches;
+#[cfg(feature = "chrome")]
+use reqwest::StatusCode;
+use std::sync::Arc;
+use url::Url;
+
+/// A custom route of an url.
+pub type FetchRouteFn = Arc<dyn Fn(&Url) -> Option<FetchStrategy> + Send + Sync>;
+
+/// The markers of the javascript challenges served in place of the page.
+const CHALLENGE_MARKERS: &[&str] = &[
+    "/cdn-cgi/challenge-platform",
+    "cf-browser-verification",
+    "window._cf_chl_opt",
+    "challenges.cloudflare.com/turnstile",
+    "queue-it.net",
+    "Checking your browser before accessing",
+    "Enable JavaScript and cookies to continue",
+];
+
+/// The mount points of the single page apps rendering the whole page with javascript.
+const SHELL_ROOTS: &[&str] = &[
+    "id=\"root\"",
+    "id=\"app\"",
+    "id=\"__next\"",
+    "id=\"__nuxt\"",
+    "id=\"svelte\"",
+    "<app-root",
+];
+
+/// The visible characters under which a page with a mount point is an empty shell.
+const SHELL_MAX_TEXT: usize = 200;
+
+/// Why a page fetched over HTTP was fetched again with Chrome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EscalationReason {
+    /// The html is the empty shell of a single page app.
+    SpaShell,
+    /// The html is a javascript challenge in place of the page.
+    JsChallenge,
+}
+
+/// A pattern routing the urls to a fetcher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FetchRule {
+    /// The pattern where `*` matches any characters. Patterns starting with `/` match the path of the url, ex: `/app/*`.
+    pub pattern: String,
+    /// The fetcher of the urls matching the pattern.
+    pub fetch: FetchStrategy,
+}
+
+/// Route each url to a fetcher: raw HTTP, Chrome or HTTP with Chrome as needed. The route function is asked first,
+/// then the first rule matching the url. The urls without a route use the fetcher of the crawl. The pages fetched over
+/// HTTP that are an empty app shell or a javascript challenge are fetched again with Chrome when escalating.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FetchRouting {
+    /// The rules in order.
+    pub rules: Vec<FetchRule>,
+    /// The custom route asked before the rules.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub route: Option<FetchRouteFn>,
+    /// Fetch the empty app shells and the javascript challenges again with Chrome. Defaults to `true`.
+    pub escalate: bool,
+}
+
+impl Default for FetchRouting {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            route: None,
+            escalate: true,
+        }
+    }
+}
+
+impl PartialEq for FetchRouting {
+    fn eq(&self, other: &Self) -> bool {
+        self.rules == other.rules
+            && self.escalate == other.escalate
+            && match (&self.route, &other.route) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (a, b) => a.is_none() && b.is_none(),
+            }
+    }
+}
+
+impl std::fmt::Debug for FetchRouting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FetchRouting")
+            .field("rules", &self.rules)
+            .field("route", &self.route.is_some())
+            .field("escalate", &self.escalate)
+            .finish()
+    }
+}
+
+impl FetchRouting {
+    /// A routing without rules escalating the pages that need javascript.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetch the urls matching the pattern with the fetcher.
+    pub fn with_rule(mut self, pattern: &str, fetch: FetchStrategy) -> Self {
+        self.rules.push(FetchRule {
+            pattern: pattern.into(),
+            fetch,
+        });
+        self
+    }
+
+    /// Route the urls with a custom function asked before the rules.
+    pub fn with_route<F>(mut self, route: F) -> Self
+    where
+        F: Fn(&Url) -> Option<FetchStrategy> + Send + Sync + 'static,
+    {
+        self.route = Some(Arc::new(route));
+        self
+    }
+
+    /// Fetch the empty app shells and the javascript challenges again with Chrome.
+    pub fn with_escalate(mut self, escalate: bool) -> Self {
+        self.escalate = escalate;
+        self
+    }
+
+    /// The fetcher of the url.
+    pub fn fetch_strategy(&self, url: &str) -> Option<FetchStrategy> {
+        self.route
+            .as_ref()
+            .and_then(|route| Url::parse(url).ok().and_then(|url| route(&url)))
+            .or_else(|| {
+                self.rules
+                    .iter()
+                    .find(|rule| url_matches(&rule.pattern, url))
+                    .map(|rule| rule.fetch)
+            })
+    }
+}
+
+/// The fetcher of the url: the one the crawl predicate set, else the route of the url, else the fetcher of the politeness
+/// profile of its domain.
+#[cfg(feature = "chrome")]
+pub(crate) fn route(
+    frontier: &Frontier,
+    routing: &Option<Box<FetchRouting>>,
+    politeness: &Option<Box<PolitenessProfiles>>,
+    url: &str,
+) -> Option<FetchStrategy> {
+    frontier
+        .fetch_strategy(url)
+        .or_else(|| {
+            routing
+                .as_ref()
+                .and_then(|routing| routing.fetch_strategy(url))
+        })
+        .or_else(|| {
+            politeness
+                .as_ref()
+                .and_then(|politeness| politeness.fetch_strategy(url))
+        })
+}
+
+/// Why the page fetched over HTTP with the fetcher should be fetched again with Chrome. The pages forced to HTTP are
+/// kept, the pages without a fetcher are escalated when the routing escalates.
+#[cfg(feature = "chrome")]
+pub(crate) fn escalation(
+    routing: &Option<Box<FetchRouting>>,
+    fetch: Option<FetchStrategy>,
+    page: &Page,
+) -> Option<EscalationReason> {
+    let escalate = match fetch {
+        Some(FetchStrategy::Smart) => true,
+        Some(_) => false,
+        _ => routing.as_ref().is_some_and(|routing| routing.escalate),
+    };
+
+    let challenged = page.status_code == StatusCode::FORBIDDEN
+        || page.status_code == StatusCode::SERVICE_UNAVAILABLE;
+
+    if escalate && (page.status_code.is_success() || challenged) {
+        escalation_reason(&page.get_html())
+    } else {
+        None
+    }
+}
+
+/// Whether the html is a javascript challenge or the empty shell of a single page app.
+pub fn escalation_reason(html: &str) -> Option<EscalationReason> {
+    if html.is_empty() {
+        None
+    } else if is_js_challenge(html) {
+        Some(EscalationReason::JsChallenge)
+    } else if is_spa_shell(html) {
+        Some(EscalationReason::SpaShell)
+    } else {
+        None
+    }
+}
+
+/// Whether the html is a javascript challenge served in place of the page.
+pub fn is_js_challenge(html: &str) -> bool {
+    CHALLENGE_MARKERS.iter().any(|marker| html.contains(marker))
+}
+
+/// Whether the html is the mount point of a single page app without content.
+pub fn is_spa_shell(html: &str) -> bool {
+    html.contains("<script")
+        && SHELL_ROOTS.iter().any(|root| html.contains(root))
+        && visible_text_len(html) < SHELL_MAX_TEXT
+}
+
+/// The characters of the html outside of the tags, the scripts and the styles, without the whitespace.
+fn visible_text_len(html: &str) -> usize {
+    let mut len = 0;
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        len += rest[..start].chars().filter(|c| !c.is_whitespace()).count();
+        rest = &rest[start..];
+
+        let skip_to = ["<script", "<style", "<noscript"]
+            .iter()
+            .find(|tag| {
+                rest.get(..tag.len())
+                    .is_some_and(|open| open.eq_ignore_ascii_case(tag))
+            })
+            .map(|tag| format!("</{}", &tag[1..]));
+
+        rest = match skip_to {
+            Some(close) => match rest.find(&close) {
+                Some(end) => &rest[end + close.len()..],
+                _ => "",
+            },
+            _ => match rest.find('>') {
+                Some(end) => &rest[end + 1..],
+                _ => "",
+            },
+        };
+    }
+
+    len + rest.chars().filter(|c| !c.is_whitespace()).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_urls() {
+        fn api(url: &Url) -> Option<FetchStrategy> {
+            if url.path().starts_with("/api/") {
+                Some(FetchStrategy::Http)
+            } else {
+                None
+            }
+        }
+
+        let routing = FetchRouting::new()
+            .with_rule("/app/*", FetchStrategy::Chrome)
+            .with_rule("*", FetchStrategy::Smart)
+            .with_route(api);
+
+        assert_eq!(
+            routing.fetch_strategy("https://example.com/api/app/1"),
+            Some(FetchStrategy::Http)
+        );
+        assert_eq!(
+            routing.fetch_strategy("https://example.com/app/home"),
+            Some(FetchStrategy::Chrome)
+        );
+        assert_eq!(
+            routing.fetch_strategy("https://example.com/about"),
+            Some(FetchStrategy::Smart)
+        );
+    }
+
+    #[test]
+    fn detects_escalations() {
+        let shell = r#"<html><head><script src="/main.js"></script></head><body><noscript>You need to enable JavaScript to run this app.</noscript><div id="root"></div></body></html>"#;
+        let article = format!(
+            r#"<html><body><div id="root"><p>{}</p></div><script src="/main.js"></script></body></html>"#,
+            "Rendered on the server. ".repeat(20)
+        );
+        let challenge = r#"<html><head><title>Just a moment...</title></head><body><script>window._cf_chl_opt={cvId: '3'};</script></body></html>"#;
+
+        assert_eq!(escalation_reason(shell), Some(EscalationReason::SpaShell));
+        assert_eq!(escalation_reason(&article), None);
+        assert_eq!(
+            escalation_reason(challenge),
+            Some(EscalationReason::JsChallenge)
+        );
+        assert_eq!(escalation_reason("<html><body>Hello</body></html>"), None);
+    }
+}
@@ -0,0 +1,254 @@
+use hashbrown::HashMap;
+use crate::RequestBuilder;
+use reqwest::header::{HeaderMap, ALT_SVC};
+use reqwest::Response;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// The lifetime of an `Alt-Svc` advertisement without a `ma` parameter.
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The result of sending a request with the client.
+#[cfg(not(feature = "cache_request"))]
+type SendResult = reqwest::Result<Response>;
+
+/// The result of sending a request with the client.
+#[cfg(feature = "cache_request")]
+type SendResult = reqwest_middleware::Result<Response>;
+
+/// The HTTP/3 state of a host.
+#[derive(Debug, Clone, Copy)]
+enum HostState {
+    /// The host advertised `h3` with the `Alt-Svc` header until the instant.
+    Advertised(Instant),
+    /// A HTTP/3 request to the host failed, the host is fetched with HTTP/2 or HTTP/1.1.
+    Failed,
+}
+
+/// The lifetime of the `h3` advertisement of an `Alt-Svc` header value for the port of the request ex:
+/// `h3=":443"; ma=86400, h2=":443"`. The alternatives on other hosts or ports are skipped since the transport connects
+/// to the origin. `Some(Duration::ZERO)` is returned for `clear`.
+pub fn parse_alt_svc(value: &str, port: u16) -> Option<Duration> {
+    if value.trim().eq_ignore_ascii_case("clear") {
+        return Some(Duration::ZERO);
+    }
+
+    value.split(',').find_map(|alternative| {
+        let mut params = alternative.split(';');
+        let (protocol, authority) = params.next()?.split_once('=')?;
+
+        if protocol.trim() != "h3" {
+            return None;
+        }
+
+        let authority = authority.trim().trim_matches('"');
+        let (host, alt_port) = authority.rsplit_once(':')?;
+
+        if !host.is_empty() || alt_port.parse::<u16>().ok()? != port {
+            return None;
+        }
+
+        let max_age = params
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.trim() == "ma")
+            .and_then(|(_, value)| value.trim().trim_matches('"').parse::<u64>().ok())
+            .map_or(DEFAULT_MAX_AGE, Duration::from_secs);
+
+        Some(max_age)
+    })
+}
+
+/// Opt-in HTTP/3 over QUIC for the HTTP crawl. The hosts advertising `h3` with the `Alt-Svc` header are fetched with
+/// HTTP/3 after their first response, a failed HTTP/3 request is retried with HTTP/2 or HTTP/1.1 and the host keeps using
+/// them for the rest of the crawl. HTTP/3 requests need the feature flag `http3` and building with
+/// `RUSTFLAGS="--cfg reqwest_unstable"`, without it the advertisements are tracked and the requests use HTTP/2 or HTTP/1.1.
+/// The protocol used is set on [`crate::page::Page::http_version`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Http3 {
+    /// Use HTTP/3 on the hosts advertising `h3` with the `Alt-Svc` header.
+    pub alt_svc: bool,
+    /// Try HTTP/3 first on every host without waiting for an advertisement.
+    pub prior_knowledge: bool,
+    /// The HTTP/3 state of the hosts.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    hosts: Arc<Mutex<HashMap<String, HostState>>>,
+}
+
+impl Default for Http3 {
+    fn default() -> Self {
+        Self {
+            alt_svc: true,
+            prior_knowledge: false,
+            hosts: Default::default(),
+        }
+    }
+}
+
+impl PartialEq for Http3 {
+    fn eq(&self, other: &Self) -> bool {
+        self.alt_svc == other.alt_svc && self.prior_knowledge == other.prior_knowledge
+    }
+}
+
+impl Http3 {
+    /// Use HTTP/3 on the hosts advertising it with the `Alt-Svc` header.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether the hosts advertising `h3` with the `Alt-Svc` header are fetched with HTTP/3.
+    pub fn with_alt_svc(mut self, alt_svc: bool) -> Self {
+        self.alt_svc = alt_svc;
+        self
+    }
+
+    /// Set whether HTTP/3 is tried first on every host.
+    pub fn with_prior_knowledge(mut self, prior_knowledge: bool) -> Self {
+        self.prior_knowledge = prior_knowledge;
+        self
+    }
+
+    /// Whether the url is fetched with HTTP/3.
+    pub fn use_http3(&self, url: &str) -> bool {
+        let host = match Url::parse(url) {
+            Ok(url) if url.scheme() == "https" => match url.host_str() {
+                Some(host) => host.to_string(),
+                _ => return false,
+            },
+            _ => return false,
+        };
+
+        match self.hosts.lock() {
+            Ok(hosts) => match hosts.get(&host) {
+                Some(HostState::Advertised(until)) => self.alt_svc && Instant::now() < *until,
+                Some(HostState::Failed) => false,
+                _ => self.prior_knowledge,
+            },
+            _ => false,
+        }
+    }
+
+    /// Record the `Alt-Svc` advertisement of the response of the url.
+    pub fn record_response(&self, url: &str, headers: &HeaderMap) {
+        let url = match Url::parse(url) {
+            Ok(url) if url.scheme() == "https" => url,
+            _ => return,
+        };
+        let (host, port) = match (url.host_str(), url.port_or_known_default()) {
+            (Some(host), Some(port)) => (host, port),
+            _ => return,
+        };
+        let max_age = headers
+            .get_all(ALT_SVC)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(|value| parse_alt_svc(value, port));
+
+        if let (Some(max_age), Ok(mut hosts)) = (max_age, self.hosts.lock()) {
+            match hosts.get(host) {
+                Some(HostState::Failed) => (),
+                _ if max_age.is_zero() => {
+                    hosts.remove(host);
+                }
+                _ => {
+                    hosts.insert(
+                        host.to_string(),
+                        HostState::Advertised(Instant::now() + max_age),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Fetch the host of the url with HTTP/2 or HTTP/1.1 for the rest of the crawl.
+    pub fn record_failure(&self, url: &str) {
+        if let Some(host) = Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(String::from))
+        {
+            if let Ok(mut hosts) = self.hosts.lock() {
+                hosts.insert(host, HostState::Failed);
+            }
+        }
+    }
+
+    /// Send the request of the url with HTTP/3 when the host supports it, falling back to HTTP/2 or HTTP/1.1.
+    pub async fn send(&self, url: &str, request: RequestBuilder) -> SendResult {
+        #[cfg(feature = "http3")]
+        if self.use_http3(url) {
+            if let Some(h3_request) = request.try_clone() {
+                match h3_request.version(reqwest::Version::HTTP_3).send().await {
+                    Ok(res) => {
+                        self.record_response(url, res.headers());
+                        return Ok(res);
+                    }
+                    Err(e) => {
+                        log::info!("http3 fallback {url}: {e}");
+                        self.record_failure(url);
+                    }
+                }
+            }
+        }
+
+        let res = request.send().await?;
+        self.record_response(url, res.headers());
+        Ok(res)
+    }
+
+    /// Forget the hosts for a new crawl.
+    pub(crate) fn reset(&self) {
+        if let Ok(mut hosts) = self.hosts.lock() {
+            hosts.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn parse_alt_svc_values() {
+        assert_eq!(
+            parse_alt_svc(r#"h3=":443"; ma=3600, h2=":443""#, 443),
+            Some(Duration::from_secs(3600))
+        );
+        assert_eq!(
+            parse_alt_svc(r#"h2=":443", h3=":443""#, 443),
+            Some(DEFAULT_MAX_AGE)
+        );
+        assert_eq!(parse_alt_svc(r#"h3=":8443"; ma=60"#, 443), None);
+        assert_eq!(parse_alt_svc(r#"h3="cdn.example.com:443""#, 443), None);
+        assert_eq!(parse_alt_svc(r#"h3-29=":443""#, 443), None);
+        assert_eq!(parse_alt_svc("clear", 443), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn track_hosts() {
+        let http3 = Http3::new();
+        let url = "https://example.com/";
+        let mut headers = HeaderMap::new();
+
+        assert!(!http3.use_http3(url));
+
+        headers.insert(ALT_SVC, HeaderValue::from_static(r#"h3=":443"; ma=60"#));
+        http3.record_response(url, &headers);
+        assert!(http3.use_http3("https://example.com/docs"));
+        assert!(!http3.use_http3("http://example.com/docs"));
+
+        headers.insert(ALT_SVC, HeaderValue::from_static("clear"));
+        http3.record_response(url, &headers);
+        assert!(!http3.use_http3(url));
+
+        let http3 = Http3::new().with_prior_knowledge(true);
+        assert!(http3.use_http3(url));
+        http3.record_failure(url);
+        assert!(!http3.use_http3(url));
+
+        http3.reset();
+        assert!(http3.use_http3(url));
+    }
+}
@@ -0,0 +1,201 @@
+use regex::Regex;
+use std::cell::RefCell;
+
+lazy_static! {
+    /// The url assigned to `location` or opened with `window.open` ex: `location.href='/next'`.
+    static ref NAVIGATION: Regex = Regex::new(
+        r#"(?:location(?:\.href)?\s*=|location\.(?:assign|replace)\s*\(|window\.open\s*\()\s*['"`]([^'"`]+)['"`]"#
+    )
+    .expect("valid regex");
+    /// The url values of the props of a `__NEXT_DATA__` script ex: `"href":"/posts/1"`.
+    static ref NEXT_DATA_URL: Regex = Regex::new(
+        r#""(?:href|url|link|path|pathname|as)"\s*:\s*"((?:/|https?://)[^"\s]*)""#
+    )
+    .expect("valid regex");
+    /// The static routes of a `__BUILD_MANIFEST` ex: `"/about":["static/chunks/pages/about.js"]`.
+    static ref BUILD_MANIFEST_ROUTE: Regex =
+        Regex::new(r#""(/[^"\[\]_][^"\[\]]*|/)"\s*:\s*\["#).expect("valid regex");
+}
+
+/// The url attributes read by default.
+const URL_ATTRIBUTES: &[&str] = &["data-href", "data-url", "data-link"];
+
+/// The text of an attribute with the quotes and ampersands escaped as html entities decoded.
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Discover the navigation targets set by javascript that are not `<a href>` links: the urls assigned to `location`
+/// in the `onclick` handlers and `javascript:` links, the urls of attributes like `data-href` and the routes of the
+/// Next.js `__NEXT_DATA__` and `__BUILD_MANIFEST` scripts. The targets are guessed from the html without running the
+/// scripts, expect more noise than from the anchors. The targets go through the link filters of the crawl.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JsLinkDiscovery {
+    /// Read the `onclick` handlers and `javascript:` links assigning `location` or calling `window.open`.
+    pub onclick: bool,
+    /// The attributes holding a url ex: `data-href`.
+    pub attributes: Vec<String>,
+    /// Read the routes of the Next.js `__NEXT_DATA__` and `__BUILD_MANIFEST` scripts. Dynamic routes like
+    /// `/posts/[id]` are skipped.
+    pub route_manifests: bool,
+}
+
+impl Default for JsLinkDiscovery {
+    fn default() -> Self {
+        Self {
+            onclick: true,
+            attributes: URL_ATTRIBUTES.iter().map(|a| a.to_string()).collect(),
+            route_manifests: true,
+        }
+    }
+}
+
+impl JsLinkDiscovery {
+    /// Read the `onclick` handlers, the `data-href`, `data-url` and `data-link` attributes and the route manifests.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether the `onclick` handlers and `javascript:` links are read.
+    pub fn with_onclick(mut self, onclick: bool) -> Self {
+        self.onclick = onclick;
+        self
+    }
+
+    /// Set the attributes holding a url.
+    pub fn with_attributes(mut self, attributes: Vec<String>) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Set whether the routes of the Next.js `__NEXT_DATA__` and `__BUILD_MANIFEST` scripts are read.
+    pub fn with_route_manifests(mut self, route_manifests: bool) -> Self {
+        self.route_manifests = route_manifests;
+        self
+    }
+
+    /// The navigation targets of the html in document order as written, relative urls are not resolved.
+    pub fn discover(&self, html: &str) -> Vec<String> {
+        let targets: RefCell<Vec<String>> = RefCell::new(Vec::new());
+        let push = |target: &str| {
+            let target = target.trim();
+            let mut targets = targets.borrow_mut();
+
+            if !target.is_empty() && !targets.iter().any(|t| t == target) {
+                targets.push(target.to_string());
+            }
+        };
+        let mut next_data = String::new();
+        let mut script = String::new();
+
+        let mut element_content_handlers = vec![lol_html::element!("*", |el| {
+            if self.onclick {
+                let handlers = el.get_attribute("onclick").into_iter().chain(
+                    el.get_attribute("href")
+                        .filter(|href| href.trim_start().starts_with("javascript:")),
+                );
+
+                for handler in handlers {
+                    for capture in NAVIGATION.captures_iter(&decode_entities(&handler)) {
+                        push(&capture[1]);
+                    }
+                }
+            }
+
+            for attribute in &self.attributes {
+                if let Some(url) = el.get_attribute(attribute) {
+                    push(&decode_entities(&url));
+                }
+            }
+            Ok(())
+        })];
+
+        if self.route_manifests {
+            element_content_handlers.push(lol_html::text!("script#__NEXT_DATA__", |text| {
+                next_data.push_str(text.as_str());
+
+                if text.last_in_text_node() {
+                    for capture in NEXT_DATA_URL.captures_iter(&next_data) {
+                        push(&capture[1]);
+                    }
+                    next_data.clear();
+                }
+                Ok(())
+            }));
+            element_content_handlers.push(lol_html::text!("script:not([src])", |text| {
+                script.push_str(text.as_str());
+
+                if text.last_in_text_node() {
+                    if script.contains("__BUILD_MANIFEST") {
+                        for capture in BUILD_MANIFEST_ROUTE.captures_iter(&script) {
+                            push(&capture[1]);
+                        }
+                    }
+                    script.clear();
+                }
+                Ok(())
+            }));
+        }
+
+        let _ = lol_html::rewrite_str(
+            html,
+            lol_html::RewriteStrSettings {
+                element_content_handlers,
+                ..lol_html::RewriteStrSettings::new()
+            },
+        );
+
+        targets.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discover_js_links() {
+        let html = r#"<html><body>
+            <button onclick="window.location.href='/checkout'">Buy</button>
+            <div onclick="location.assign(&quot;/cart&quot;)">Cart</div>
+            <a href="javascript:window.open('https://example.com/help')">Help</a>
+            <tr data-href="/orders/1"><td>Order</td></tr>
+            <span onclick="toggle()" data-url="/orders/1">Duplicate</span>
+            <script id="__NEXT_DATA__" type="application/json">{"props":{"pageProps":{"nav":[{"href":"/blog","label":"Blog"},{"url":"mailto:a@example.com"}]}},"page":"/"}</script>
+            <script>self.__BUILD_MANIFEST = {"/":["a.js"],"/about":["b.js"],"/posts/[id]":["c.js"],"/_error":["d.js"]};</script>
+        </body></html>"#;
+
+        assert_eq!(
+            JsLinkDiscovery::new().discover(html),
+            [
+                "/checkout",
+                "/cart",
+                "https://example.com/help",
+                "/orders/1",
+                "/blog",
+                "/",
+                "/about"
+            ]
+        );
+        assert_eq!(
+            JsLinkDiscovery::new()
+                .with_onclick(false)
+                .with_route_manifests(false)
+                .discover(html),
+            ["/orders/1"]
+        );
+        assert_eq!(
+            JsLinkDiscovery::new()
+                .with_onclick(false)
+                .with_route_manifests(false)
+                .with_attributes(vec!["data-target".into()])
+                .discover(r#"<div data-target="/inbox" data-href="/orders/1">Inbox</div>"#),
+            ["/inbox"]
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use crate::page::Page;
+
+/// The ISO 639-1 codes of the ISO 639-3 codes of the languages detected from the text.
+#[cfg(feature = "language_detection")]
+const ISO_639_1: &[(&str, &str)] = &[
+    ("afr", "af"),
+    ("aka", "ak"),
+    ("amh", "am"),
+    ("ara", "ar"),
+    ("aze", "az"),
+    ("bel", "be"),
+    ("ben", "bn"),
+    ("bul", "bg"),
+    ("cat", "ca"),
+    ("ces", "cs"),
+    ("cmn", "zh"),
+    ("dan", "da"),
+    ("deu", "de"),
+    ("ell", "el"),
+    ("eng", "en"),
+    ("epo", "eo"),
+    ("est", "et"),
+    ("fin", "fi"),
+    ("fra", "fr"),
+    ("guj", "gu"),
+    ("heb", "he"),
+    ("hin", "hi"),
+    ("hrv", "hr"),
+    ("hun", "hu"),
+    ("hye", "hy"),
+    ("ind", "id"),
+    ("ita", "it"),
+    ("jav", "jv"),
+    ("jpn", "ja"),
+    ("kan", "kn"),
+    ("kat", "ka"),
+    ("khm", "km"),
+    ("kor", "ko"),
+    ("lat", "la"),
+    ("lav", "lv"),
+    ("lit", "lt"),
+    ("mal", "ml"),
+    ("mar", "mr"),
+    ("mkd", "mk"),
+    ("mya", "my"),
+    ("nep", "ne"),
+    ("nld", "nl"),
+    ("nob", "nb"),
+    ("ori", "or"),
+    ("pan", "pa"),
+    ("pes", "fa"),
+    ("pol", "pl"),
+    ("por", "pt"),
+    ("ron", "ro"),
+    ("rus", "ru"),
+    ("sin", "si"),
+    ("slk", "sk"),
+    ("slv", "sl"),
+    ("sna", "sn"),
+    ("spa", "es"),
+    ("srp", "sr"),
+    ("swe", "sv"),
+    ("tam", "ta"),
+    ("tel", "te"),
+    ("tgl", "tl"),
+    ("tha", "th"),
+    ("tuk", "tk"),
+    ("tur", "tr"),
+    ("ukr", "uk"),
+    ("urd", "ur"),
+    ("uzb", "uz"),
+    ("vie", "vi"),
+    ("yid", "yi"),
+    ("zul", "zu"),
+];
+
+/// The characters of text read for the text detection.
+#[cfg(feature = "language_detection")]
+const TEXT_SAMPLE: usize = 4096;
+
+/// The primary language subtag of a language tag lowercased ex: `en-US` is `en`.
+pub fn primary_language(tag: &str) -> Option<String> {
+    let primary = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+
+    if primary.is_empty() {
+        None
+    } else {
+        Some(primary)
+    }
+}
+
+/// The language declared by the `lang` attribute of the `<html>` element.
+pub fn html_lang(html: &str) -> Option<String> {
+    let mut lang = None;
+
+    let _ = lol_html::rewrite_str(
+        html,
+        lol_html::RewriteStrSettings {
+            element_content_handlers: vec![lol_html::element!("html[lang]", |el| {
+                if lang.is_none() {
+                    lang = el
+                        .get_attribute("lang")
+                        .and_then(|tag| primary_language(&tag));
+                }
+                Ok(())
+            })],
+            ..lol_html::RewriteStrSettings::new()
+        },
+    );
+
+    lang
+}
+
+/// The visible text of the body up to the size of the sample, the scripts and styles are skipped.
+#[cfg(feature = "language_detection")]
+fn text_sample(html: &str) -> String {
+    use crate::packages::scraper::{Html, Node};
+
+    let document = Html::parse_document(html);
+    let mut text = String::new();
+
+    for node in document.tree.root().descendants() {
+        if let Node::Text(chunk) = node.value() {
+            let hidden = node.parent().is_some_and(|parent| match parent.value() {
+                Node::Element(element) => {
+                    matches!(element.name(), "script" | "style" | "noscript" | "template")
+                }
+                _ => false,
+            });
+
+            if !hidden {
+                for word in chunk.split_whitespace() {
+                    text.push_str(word);
+                    text.push(' ');
+                }
+                if text.len() >= TEXT_SAMPLE {
+                    break;
+                }
+            }
+        }
+    }
+
+    text
+}
+
+/// The ISO 639-1 code of the language of the text and whether the detection is reliable.
+#[cfg(feature = "language_detection")]
+pub fn detect_text(text: &str) -> Option<(String, bool)> {
+    let info = whatlang::detect(text)?;
+    let code = info.lang().code();
+    let code = ISO_639_1
+        .iter()
+        .find(|(iso_639_3, _)| *iso_639_3 == code)
+        .map_or(code, |(_, iso_639_1)| iso_639_1);
+
+    Some((code.to_string(), info.is_reliable()))
+}
+
+/// The language of the html as a lowercase ISO 639-1 code ex: `en`. The text of the page is detected with the
+/// feature flag `language_detection` and wins over the `lang` attribute when the detection is reliable, templates often
+/// keep the default `lang` of the site on translated pages.
+pub fn detect_language(html: &str) -> Option<String> {
+    let lang = html_lang(html);
+
+    #[cfg(feature = "language_detection")]
+    if let Some((code, reliable)) = detect_text(&text_sample(html)) {
+        if reliable || lang.is_none() {
+            return Some(code);
+        }
+    }
+
+    lang
+}
+
+/// Detect the language of the pages and skip storing them or following their links when they are not in the set of
+/// languages. The pages with no language found are kept.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LanguageFilter {
+    /// The languages kept as ISO 639-1 codes ex: `en`. Empty keeps every language and only detects them.
+    pub languages: Vec<String>,
+    /// Skip sending the pages in other languages to the subscribers.
+    pub skip_storing: bool,
+    /// Skip the links of the pages in other languages.
+    pub skip_links: bool,
+}
+
+impl Default for LanguageFilter {
+    fn default() -> Self {
+        Self {
+            languages: Vec::new(),
+            skip_storing: true,
+            skip_links: true,
+        }
+    }
+}
+
+impl LanguageFilter {
+    /// Keep the pages in the languages, the pages in other languages are not stored and their links are not followed.
+    pub fn new(languages: Vec<String>) -> Self {
+        Self {
+            languages: languages
+                .iter()
+                .filter_map(|language| primary_language(language))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Set whether the pages in other languages are sent to the subscribers.
+    pub fn with_skip_storing(mut self, skip_storing: bool) -> Self {
+        self.skip_storing = skip_storing;
+        self
+    }
+
+    /// Set whether the links of the pages in other languages are followed.
+    pub fn with_skip_links(mut self, skip_links: bool) -> Self {
+        self.skip_links = skip_links;
+        self
+    }
+
+    /// Whether the language is kept. Unknown languages are kept.
+    pub fn allows(&self, language: Option<&str>) -> bool {
+        match language {
+            Some(language) if !self.languages.is_empty() => self
+                .languages
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(language)),
+            _ => true,
+        }
+    }
+
+    /// Detect the language of the page and return whether the page is kept.
+    pub(crate) fn apply(&self, page: &mut Page) -> bool {
+        page.language = detect_language(&page.get_html());
+        self.allows(page.language.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_html_lang() {
+        assert_eq!(primary_language("en-US"), Some("en".into()));
+        assert_eq!(primary_language(" pt_BR "), Some("pt".into()));
+        assert_eq!(primary_language(""), None);
+        assert_eq!(
+            html_lang(r#"<html lang="fr-CA"><body>Bonjour</body></html>"#),
+            Some("fr".into())
+        );
+        assert_eq!(html_lang("<html><body>Hello</body></html>"), None);
+    }
+
+    #[test]
+    fn filter_languages() {
+        let filter = LanguageFilter::new(vec!["EN-gb".into(), "de".into()]);
+
+        assert_eq!(filter.languages, ["en", "de"]);
+        assert!(filter.allows(Some("en")));
+        assert!(filter.allows(Some("de")));
+        assert!(!filter.allows(Some("fr")));
+        assert!(filter.allows(None));
+        assert!(LanguageFilter::default().allows(Some("fr")));
+    }
+
+    #[cfg(feature = "language_detection")]
+    #[test]
+    fn detect_page_text() {
+        let html = r#"<html lang="en"><head><style>body { color: red; }</style></head><body>
+            <p>Der schnelle braune Fuchs springt über den faulen Hund, und die Katze schläft ruhig auf dem warmen Sofa im Wohnzimmer.</p>
+            <script>var english = "this script is not text";</script>
+        </body></html>"#;
+
+        assert_eq!(detect_language(html), Some("de".into()));
+    }
+}
@@ -0,0 +1,518 @@
+use crate::page::Page;
+use crate::utils::rate_limit::HostRateLimiter;
+use crate::Client;
+use hashbrown::{HashMap, HashSet};
+use reqwest::StatusCode;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use url::Url;
+
+/// Why a link is broken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LinkFailure {
+    /// The server answered with a 4xx or 5xx status code.
+    Status(u16),
+    /// The server did not answer before the timeout.
+    Timeout,
+    /// The request failed before a response, ex: the host does not resolve or the connection was refused.
+    Error(String),
+}
+
+/// A broken link with the pages linking to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BrokenLink {
+    /// The url of the link.
+    pub url: String,
+    /// Why the link is broken.
+    pub failure: LinkFailure,
+    /// The pages linking to the url in the order they were crawled.
+    pub referrers: Vec<String>,
+}
+
+/// The links checked during a crawl.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinkReport {
+    /// The count of links checked, the crawled pages included.
+    pub checked: usize,
+    /// The broken links sorted by url.
+    pub broken: Vec<BrokenLink>,
+}
+
+/// The outbound links of a html document: the `<a href>` and `<area href>` http urls resolved against the base url
+/// without their fragment.
+pub fn outbound_links(html: &str, base: Option<&Url>) -> Vec<String> {
+    let mut links: Vec<String> = Vec::new();
+
+    let _ = lol_html::rewrite_str(
+        html,
+        lol_html::RewriteStrSettings {
+            element_content_handlers: vec![lol_html::element!("a[href], area[href]", |el| {
+                if let Some(href) = el.get_attribute("href") {
+                    let href = href.trim();
+                    let link = match base {
+                        Some(base) => base.join(href),
+                        _ => Url::parse(href),
+                    };
+
+                    if let Ok(mut link) = link {
+                        if link.scheme() == "http" || link.scheme() == "https" {
+                            link.set_fragment(None);
+                            let link = String::from(link);
+
+                            if !links.contains(&link) {
+                                links.push(link);
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            })],
+            ..lol_html::RewriteStrSettings::new()
+        },
+    );
+
+    links
+}
+
+/// Check the url with a HEAD request retried with a GET request when the HEAD request fails. The body of the GET
+/// request is not downloaded.
+pub async fn check_link(
+    client: &Client,
+    url: &str,
+    timeout: Duration,
+    get_fallback: bool,
+) -> Result<u16, LinkFailure> {
+    let head = match tokio::time::timeout(timeout, client.head(url).send()).await {
+        Ok(Ok(response)) => Ok(response.status().as_u16()),
+        Ok(Err(err)) => Err(LinkFailure::Error(err.to_string())),
+        _ => Err(LinkFailure::Timeout),
+    };
+
+    match head {
+        Ok(status) if status < 400 => Ok(status),
+        Err(LinkFailure::Timeout) => Err(LinkFailure::Timeout),
+        _ if get_fallback => match tokio::time::timeout(timeout, client.get(url).send()).await {
+            Ok(Ok(response)) if response.status().as_u16() < 400 => Ok(response.status().as_u16()),
+            Ok(Ok(response)) => Err(LinkFailure::Status(response.status().as_u16())),
+            Ok(Err(err)) => Err(LinkFailure::Error(err.to_string())),
+            _ => Err(LinkFailure::Timeout),
+        },
+        Ok(status) => Err(LinkFailure::Status(status)),
+        Err(failure) => Err(failure),
+    }
+}
+
+/// The links checked during a crawl.
+#[derive(Debug, Default)]
+pub(crate) struct LinkCheckState {
+    /// The failure of the links checked, `None` when the link works.
+    results: HashMap<String, Option<LinkFailure>>,
+    /// The links claimed by a page and not checked yet.
+    pending: HashSet<String>,
+    /// The links to the hosts of their pages, checked at the end of the crawl when the crawl did not visit them.
+    internal: HashSet<String>,
+    /// The pages linking to each url.
+    referrers: HashMap<String, Vec<String>>,
+}
+
+/// The client of the checks of the links to other hosts, without the headers, the cookies and the proxies of the
+/// crawl.
+fn bare_client() -> Client {
+    let client = reqwest::Client::builder()
+        .user_agent(crate::configuration::get_ua(false))
+        .build()
+        .unwrap_or_default();
+
+    #[cfg(feature = "cache_request")]
+    let client = crate::ClientBuilder::new(client).build();
+
+    client
+}
+
+/// Check every outbound link of the crawled pages, the external links included, and report the links answering with
+/// a 4xx or 5xx status code or timing out with the pages linking to them. The pages visited are checked by the crawl
+/// itself. The links to other hosts get a HEAD request in the background from a client without the headers, the
+/// cookies and the proxies of the crawl. The links to the crawled hosts the crawl did not visit are checked at the
+/// end of the crawl with the client and the host rate limits of the crawl, the distributed crawl skips them.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinkCheck {
+    /// Check the links to other hosts.
+    pub external: bool,
+    /// Retry the links failing a HEAD request with a GET request, some servers do not answer HEAD requests.
+    pub get_fallback: bool,
+    /// The time to wait for the response of a check.
+    pub timeout: Duration,
+    /// The checks in flight at once across the crawl.
+    pub concurrency: usize,
+    /// The links checked.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    state: Arc<Mutex<LinkCheckState>>,
+    /// The permits bounding the checks in flight, created on the first check.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    permits: Arc<tokio::sync::OnceCell<Arc<tokio::sync::Semaphore>>>,
+    /// The client of the links to other hosts, created on the first check.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    client: Arc<tokio::sync::OnceCell<Client>>,
+}
+
+impl Default for LinkCheck {
+    fn default() -> Self {
+        Self {
+            external: true,
+            get_fallback: true,
+            timeout: Duration::from_secs(10),
+            concurrency: 8,
+            state: Default::default(),
+            permits: Default::default(),
+            client: Default::default(),
+        }
+    }
+}
+
+impl PartialEq for LinkCheck {
+    fn eq(&self, other: &Self) -> bool {
+        self.external == other.external
+            && self.get_fallback == other.get_fallback
+            && self.timeout == other.timeout
+            && self.concurrency == other.concurrency
+    }
+}
+
+impl LinkCheck {
+    /// Check the internal and external links with a 10 second timeout, 8 checks at a time.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether the links to other hosts are checked.
+    pub fn with_external(mut self, external: bool) -> Self {
+        self.external = external;
+        self
+    }
+
+    /// Set whether the links failing a HEAD request are retried with a GET request.
+    pub fn with_get_fallback(mut self, get_fallback: bool) -> Self {
+        self.get_fallback = get_fallback;
+        self
+    }
+
+    /// Set the time to wait for the response of a check.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the checks in flight at once across the crawl.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self.permits = Default::default();
+        self
+    }
+
+    /// Record the result of the crawled page.
+    pub(crate) fn record_page(&self, page: &Page) {
+        let status = page.status_code;
+        let failure = if status.is_success() || status.is_redirection() {
+            None
+        } else if status == StatusCode::REQUEST_TIMEOUT {
+            Some(LinkFailure::Timeout)
+        } else if status.as_u16() == 599 {
+            Some(LinkFailure::Error(
+                page.error_status
+                    .clone()
+                    .unwrap_or_else(|| "network error".into()),
+            ))
+        } else {
+            Some(LinkFailure::Status(status.as_u16()))
+        };
+
+        if let Ok(mut state) = self.state.lock() {
+            state.pending.remove(page.get_url());
+            state.results.insert(page.get_url().to_string(), failure);
+        }
+    }
+
+    /// Record the crawled page and its outbound links. The links to other hosts not checked yet are claimed and
+    /// returned for [`LinkCheck::check_external`].
+    pub(crate) fn record_links(&self, page: &Page) -> Vec<String> {
+        self.record_page(page);
+
+        let base = Url::parse(page.get_url()).ok();
+        let host = base.as_ref().and_then(|base| base.host_str());
+        let mut claimed = Vec::new();
+
+        if let Ok(mut state) = self.state.lock() {
+            let state = &mut *state;
+
+            for link in outbound_links(&page.get_html(), base.as_ref()) {
+                let external = Url::parse(&link)
+                    .ok()
+                    .is_some_and(|url| url.host_str() != host);
+
+                if external && !self.external {
+                    continue;
+                }
+
+                state
+                    .referrers
+                    .entry(link.clone())
+                    .or_default()
+                    .push(page.get_url().to_string());
+
+                if !external {
+                    state.internal.insert(link);
+                } else if !state.results.contains_key(&link) && state.pending.insert(link.clone()) {
+                    claimed.push(link);
+                }
+            }
+        }
+
+        claimed
+    }
+
+    /// Check the links to other hosts with the client of the checks.
+    pub(crate) async fn check_external(&self, links: Vec<String>) {
+        let client = self.client.get_or_init(|| async { bare_client() }).await;
+
+        self.check_links(client, None, links).await;
+    }
+
+    /// Check the links to the crawled hosts the crawl did not visit with the client and the host rate limits of the
+    /// crawl.
+    pub(crate) async fn check_unvisited(
+        &self,
+        client: &Client,
+        rate_limiter: &Arc<HostRateLimiter>,
+    ) {
+        let links = match self.state.lock() {
+            Ok(mut state) => {
+                let state = &mut *state;
+                let links: Vec<String> = state
+                    .internal
+                    .iter()
+                    .filter(|link| {
+                        !state.results.contains_key(*link) && !state.pending.contains(*link)
+                    })
+                    .cloned()
+                    .collect();
+
+                state.pending.extend(links.iter().cloned());
+                links
+            }
+            _ => return,
+        };
+
+        self.check_links(client, Some(rate_limiter), links).await;
+    }
+
+    /// Check the links, at most `concurrency` checks in flight across the crawl.
+    async fn check_links(
+        &self,
+        client: &Client,
+        rate_limiter: Option<&Arc<HostRateLimiter>>,
+        links: Vec<String>,
+    ) {
+        let permits = self
+            .permits
+            .get_or_init(|| async {
+                Arc::new(tokio::sync::Semaphore::new(self.concurrency.max(1)))
+            })
+            .await
+            .clone();
+        let mut set = tokio::task::JoinSet::new();
+
+        for url in links {
+            let permit = match permits.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                _ => break,
+            };
+            let client = client.clone();
+            let rate_limiter = rate_limiter.cloned();
+            let (timeout, get_fallback) = (self.timeout, self.get_fallback);
+
+            set.spawn(async move {
+                if let Some(rate_limiter) = &rate_limiter {
+                    rate_limiter.acquire(&url).await;
+                }
+                let result = check_link(&client, &url, timeout, get_fallback).await;
+
+                let status = match &result {
+                    Ok(status) | Err(LinkFailure::Status(status)) => {
+                        StatusCode::from_u16(*status).ok()
+                    }
+                    _ => None,
+                };
+                if let (Some(rate_limiter), Some(status)) = (&rate_limiter, status) {
+                    rate_limiter.record(&url, status, None);
+                }
+                drop(permit);
+
+                (url, result)
+            });
+
+            while let Some(result) = set.try_join_next() {
+                self.record_result(result);
+            }
+        }
+
+        while let Some(result) = set.join_next().await {
+            self.record_result(result);
+        }
+    }
+
+    /// Record the result of a check.
+    fn record_result(
+        &self,
+        result: Result<(String, Result<u16, LinkFailure>), tokio::task::JoinError>,
+    ) {
+        if let Ok((url, result)) = result {
+            if let Ok(mut state) = self.state.lock() {
+                state.pending.remove(&url);
+                state.results.insert(url, result.err());
+            }
+        }
+    }
+
+    /// The report of the links checked since the crawl started.
+    pub fn report(&self) -> LinkReport {
+        let state = match self.state.lock() {
+            Ok(state) => state,
+            _ => return Default::default(),
+        };
+
+        let mut broken: Vec<BrokenLink> = state
+            .results
+            .iter()
+            .filter_map(|(url, failure)| {
+                Some(BrokenLink {
+                    url: url.clone(),
+                    failure: failure.clone()?,
+                    referrers: state.referrers.get(url).cloned().unwrap_or_default(),
+                })
+            })
+            .collect();
+
+        broken.sort_by(|a, b| a.url.cmp(&b.url));
+
+        LinkReport {
+            checked: state.results.len(),
+            broken,
+        }
+    }
+
+    /// Forget the links checked for a new crawl.
+    pub(crate) fn reset(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            *state = Default::default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page::build;
+    use crate::utils::PageResponse;
+
+    #[test]
+    fn parse_outbound_links() {
+        let base = Url::parse("https://example.com/docs/").unwrap();
+        let html = r##"<a href="guide#intro">Guide</a><a href="https://other.com/">Other</a><a href="guide">Again</a><a href="mailto:a@example.com">Mail</a><map><area href="/map"></map>"##;
+
+        assert_eq!(
+            outbound_links(html, Some(&base)),
+            [
+                "https://example.com/docs/guide",
+                "https://other.com/",
+                "https://example.com/map"
+            ]
+        );
+    }
+
+    #[test]
+    fn report_broken_pages() {
+        let link_check = LinkCheck::new();
+        let page = |url: &str, status_code: StatusCode| {
+            build(
+                url,
+                PageResponse {
+                    status_code,
+                    ..Default::default()
+                },
+            )
+        };
+
+        if let Ok(mut state) = link_check.state.lock() {
+            state.referrers.insert(
+                "https://example.com/missing".into(),
+                vec!["https://example.com/".into()],
+            );
+        }
+        link_check.record_page(&page("https://example.com/", StatusCode::OK));
+        link_check.record_page(&page("https://example.com/missing", StatusCode::NOT_FOUND));
+        link_check.record_page(&page(
+            "https://example.com/slow",
+            StatusCode::REQUEST_TIMEOUT,
+        ));
+
+        let report = link_check.report();
+        assert_eq!(report.checked, 3);
+        assert_eq!(
+            report.broken,
+            [
+                BrokenLink {
+                    url: "https://example.com/missing".into(),
+                    failure: LinkFailure::Status(404),
+                    referrers: vec!["https://example.com/".into()],
+                },
+                BrokenLink {
+                    url: "https://example.com/slow".into(),
+                    failure: LinkFailure::Timeout,
+                    referrers: Vec::new(),
+                }
+            ]
+        );
+
+        link_check.reset();
+        assert_eq!(link_check.report(), LinkReport::default());
+    }
+
+    #[test]
+    fn claim_external_links() {
+        let link_check = LinkCheck::new();
+        let page = build(
+            "https://example.com/",
+            PageResponse {
+                content: Some(Box::new(bytes::Bytes::from_static(
+                    br#"<a href="/a">A</a><a href="/b">B</a><a href="https://other.com/">Other</a>"#,
+                ))),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(link_check.record_links(&page), ["https://other.com/"]);
+        assert!(link_check.record_links(&page).is_empty());
+
+        let state = link_check.state.lock().unwrap();
+        assert_eq!(state.internal.len(), 2);
+        assert!(state.pending.contains("https://other.com/"));
+        assert_eq!(
+            state.referrers["https://example.com/a"],
+            ["https://example.com/", "https://example.com/"]
+        );
+        drop(state);
+
+        let link_check = LinkCheck::new().with_external(false);
+        assert!(link_check.record_links(&page).is_empty());
+        assert!(!link_check
+            .state
+            .lock()
+            .unwrap()
+            .referrers
+            .contains_key("https://other.com/"));
+    }
+}
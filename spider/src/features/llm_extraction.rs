@@ -0,0 +1,769 @@
+use crate::features::openai_common::GPTConfigs;
+use crate::page::Page;
+use crate::utils::log;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The system prompt of the extraction requests.
+const SYSTEM_PROMPT: &str = "Extract the data asked by the user from the web page. Answer with JSON only, without explanations or code fences.";
+
+/// The name of the schema sent to the providers naming their structured outputs.
+const SCHEMA_NAME: &str = "extraction";
+
+/// A page to extract data from, sent to the [`LlmExtractor`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LlmRequest<'a> {
+    /// The url of the page.
+    pub url: &'a str,
+    /// The instructions of the extraction.
+    pub prompt: &'a str,
+    /// The JSON Schema the answer follows.
+    pub schema: Option<&'a Value>,
+    /// The content of the page as markdown, text or html.
+    pub content: &'a str,
+}
+
+impl LlmRequest<'_> {
+    /// The user message with the prompt, the url and the content of the page.
+    pub fn message(&self) -> String {
+        format!("{}\n\nURL: {}\n\n{}", self.prompt, self.url, self.content)
+    }
+}
+
+/// The error of an extraction.
+#[derive(Debug)]
+pub enum LlmError {
+    /// The request to the provider failed.
+    Request(reqwest::Error),
+    /// The provider answered with the error status and body.
+    Status(u16, String),
+    /// The answer is not valid JSON.
+    Json(serde_json::Error),
+    /// The answer has no content.
+    Empty,
+}
+
+impl std::fmt::Display for LlmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LlmError::Request(e) => write!(f, "request failed: {e}"),
+            LlmError::Status(status, body) => write!(f, "status {status}: {body}"),
+            LlmError::Json(e) => write!(f, "invalid json: {e}"),
+            LlmError::Empty => f.write_str("empty answer"),
+        }
+    }
+}
+
+impl std::error::Error for LlmError {}
+
+impl From<reqwest::Error> for LlmError {
+    fn from(e: reqwest::Error) -> Self {
+        LlmError::Request(e)
+    }
+}
+
+impl From<serde_json::Error> for LlmError {
+    fn from(e: serde_json::Error) -> Self {
+        LlmError::Json(e)
+    }
+}
+
+/// Extract structured data from the pages with a language model. Implemented by [`OpenAiExtractor`],
+/// [`AnthropicExtractor`] and [`LlamaCppExtractor`], implement it for the other providers.
+#[async_trait::async_trait]
+pub trait LlmExtractor: Send + Sync {
+    /// The JSON extracted from the page.
+    async fn extract(&self, request: &LlmRequest<'_>) -> Result<Value, LlmError>;
+}
+
+/// The JSON of the answer, the code fences of the models ignoring the instructions removed.
+fn parse_answer(answer: &str) -> Result<Value, LlmError> {
+    let answer = answer.trim();
+
+    if answer.is_empty() {
+        return Err(LlmError::Empty);
+    }
+
+    let answer = match answer.strip_prefix("```") {
+        Some(fenced) => fenced
+            .trim_start_matches(|c: char| c.is_ascii_alphanumeric())
+            .trim_end()
+            .trim_end_matches("```"),
+        _ => answer,
+    };
+
+    Ok(serde_json::from_str(answer.trim())?)
+}
+
+/// Post the JSON body and read the JSON answer.
+async fn post_json(request: reqwest::RequestBuilder, body: &Value) -> Result<Value, LlmError> {
+    let response = request
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(body)?)
+        .send()
+        .await?;
+    let status = response.status();
+    let body = response.bytes().await?;
+
+    if !status.is_success() {
+        return Err(LlmError::Status(
+            status.as_u16(),
+            String::from_utf8_lossy(&body).into_owned(),
+        ));
+    }
+
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// Extract with the OpenAI chat completions API or a compatible server, the schema sent as the structured output format.
+#[derive(Debug, Clone)]
+pub struct OpenAiExtractor {
+    /// The url of the API. Defaults to `https://api.openai.com/v1`.
+    pub base_url: String,
+    /// The key of the API. Defaults to the `OPENAI_API_KEY` env variable.
+    pub api_key: Option<String>,
+    /// The model. Defaults to `gpt-4o-mini`.
+    pub model: String,
+    /// The max tokens of the answer.
+    pub max_tokens: Option<u32>,
+    /// The temperature between 0 - 2.
+    pub temperature: Option<f32>,
+    /// The max time to wait for the answer. Defaults to 120s.
+    pub timeout: Duration,
+    /// The client of the requests.
+    client: reqwest::Client,
+}
+
+impl Default for OpenAiExtractor {
+    fn default() -> Self {
+        Self {
+            base_url: "https://api.openai.com/v1".into(),
+            api_key: std::env::var("OPENAI_API_KEY").ok(),
+            model: "gpt-4o-mini".into(),
+            max_tokens: None,
+            temperature: None,
+            timeout: Duration::from_secs(120),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl From<&GPTConfigs> for OpenAiExtractor {
+    fn from(configs: &GPTConfigs) -> Self {
+        let mut extractor = if configs.model.is_empty() {
+            Self::default()
+        } else {
+            Self::new(&configs.model)
+        };
+
+        if configs.api_key.is_some() {
+            extractor.api_key = configs.api_key.clone();
+        }
+        if configs.max_tokens > 0 {
+            extractor.max_tokens = Some(configs.max_tokens.into());
+        }
+        extractor.temperature = configs.temperature;
+        extractor
+    }
+}
+
+impl OpenAiExtractor {
+    /// Extract with the model.
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Send the requests to an OpenAI compatible server ex: `http://localhost:8000/v1`.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').into();
+        self
+    }
+
+    /// Set the key of the API.
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Set the max tokens of the answer.
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Set the temperature.
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Set the max time to wait for the answer.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmExtractor for OpenAiExtractor {
+    async fn extract(&self, request: &LlmRequest<'_>) -> Result<Value, LlmError> {
+        let mut body = json!({
+            "model": self.model,
+            "messages": [
+                { "role": "system", "content": SYSTEM_PROMPT },
+                { "role": "user", "content": request.message() },
+            ],
+            "response_format": match request.schema {
+                Some(schema) => json!({
+                    "type": "json_schema",
+                    "json_schema": { "name": SCHEMA_NAME, "schema": schema },
+                }),
+                _ => json!({ "type": "json_object" }),
+            },
+        });
+
+        if let Some(max_tokens) = self.max_tokens {
+            body["max_tokens"] = max_tokens.into();
+        }
+        if let Some(temperature) = self.temperature {
+            body["temperature"] = temperature.into();
+        }
+
+        let mut http = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .timeout(self.timeout);
+
+        if let Some(api_key) = &self.api_key {
+            http = http.bearer_auth(api_key);
+        }
+
+        let answer = post_json(http, &body).await?;
+
+        parse_answer(
+            answer["choices"][0]["message"]["content"]
+                .as_str()
+                .unwrap_or_default(),
+        )
+    }
+}
+
+/// Extract with the Anthropic messages API, the schema sent as the input schema of a forced tool call.
+#[derive(Debug, Clone)]
+pub struct AnthropicExtractor {
+    /// The url of the API. Defaults to `https://api.anthropic.com/v1`.
+    pub base_url: String,
+    /// The key of the API. Defaults to the `ANTHROPIC_API_KEY` env variable.
+    pub api_key: Option<String>,
+    /// The model. Defaults to `claude-3-5-haiku-latest`.
+    pub model: String,
+    /// The max tokens of the answer. Defaults to 1024.
+    pub max_tokens: u32,
+    /// The temperature between 0 - 1.
+    pub temperature: Option<f32>,
+    /// The max time to wait for the answer. Defaults to 120s.
+    pub timeout: Duration,
+    /// The client of the requests.
+    client: reqwest::Client,
+}
+
+impl Default for AnthropicExtractor {
+    fn default() -> Self {
+        Self {
+            base_url: "https://api.anthropic.com/v1".into(),
+            api_key: std::env::var("ANTHROPIC_API_KEY").ok(),
+            model: "claude-3-5-haiku-latest".into(),
+            max_tokens: 1024,
+            temperature: None,
+            timeout: Duration::from_secs(120),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl AnthropicExtractor {
+    /// Extract with the model.
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the url of the API.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').into();
+        self
+    }
+
+    /// Set the key of the API.
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Set the max tokens of the answer.
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Set the temperature.
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Set the max time to wait for the answer.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmExtractor for AnthropicExtractor {
+    async fn extract(&self, request: &LlmRequest<'_>) -> Result<Value, LlmError> {
+        let mut body = json!({
+            "model": self.model,
+            "max_tokens": self.max_tokens,
+            "system": SYSTEM_PROMPT,
+            "messages": [{ "role": "user", "content": request.message() }],
+        });
+
+        if let Some(schema) = request.schema {
+            body["tools"] = json!([{
+                "name": SCHEMA_NAME,
+                "description": "Record the data extracted from the page.",
+                "input_schema": schema,
+            }]);
+            body["tool_choice"] = json!({ "type": "tool", "name": SCHEMA_NAME });
+        }
+        if let Some(temperature) = self.temperature {
+            body["temperature"] = temperature.into();
+        }
+
+        let mut http = self
+            .client
+            .post(format!("{}/messages", self.base_url))
+            .timeout(self.timeout)
+            .header("anthropic-version", "2023-06-01");
+
+        if let Some(api_key) = &self.api_key {
+            http = http.header("x-api-key", api_key);
+        }
+
+        let answer = post_json(http, &body).await?;
+        let content = answer["content"].as_array().ok_or(LlmError::Empty)?;
+
+        if let Some(tool_use) = content.iter().find(|block| block["type"] == "tool_use") {
+            return Ok(tool_use["input"].clone());
+        }
+
+        parse_answer(
+            &content
+                .iter()
+                .filter(|block| block["type"] == "text")
+                .filter_map(|block| block["text"].as_str())
+                .collect::<String>(),
+        )
+    }
+}
+
+/// Extract with a local llama.cpp server, the schema converted to a grammar by the server.
+#[derive(Debug, Clone)]
+pub struct LlamaCppExtractor {
+    /// The url of the server. Defaults to `http://127.0.0.1:8080`.
+    pub base_url: String,
+    /// The max tokens of the answer. Defaults to 1024.
+    pub n_predict: u32,
+    /// The temperature.
+    pub temperature: Option<f32>,
+    /// The max time to wait for the answer. Defaults to 300s.
+    pub timeout: Duration,
+    /// The client of the requests.
+    client: reqwest::Client,
+}
+
+impl Default for LlamaCppExtractor {
+    fn default() -> Self {
+        Self {
+            base_url: "http://127.0.0.1:8080".into(),
+            n_predict: 1024,
+            temperature: None,
+            timeout: Duration::from_secs(300),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl LlamaCppExtractor {
+    /// Extract with the server at the url.
+    pub fn new(base_url: &str) -> Self {
+        Self::default().with_base_url(base_url)
+    }
+
+    /// Set the url of the server.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').into();
+        self
+    }
+
+    /// Set the max tokens of the answer.
+    pub fn with_n_predict(mut self, n_predict: u32) -> Self {
+        self.n_predict = n_predict;
+        self
+    }
+
+    /// Set the temperature.
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Set the max time to wait for the answer.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmExtractor for LlamaCppExtractor {
+    async fn extract(&self, request: &LlmRequest<'_>) -> Result<Value, LlmError> {
+        let mut body = json!({
+            "prompt": format!("{SYSTEM_PROMPT}\n\n{}\n\nJSON:", request.message()),
+            "n_predict": self.n_predict,
+            "json_schema": request.schema.cloned().unwrap_or_else(|| json!({})),
+        });
+
+        if let Some(temperature) = self.temperature {
+            body["temperature"] = temperature.into();
+        }
+
+        let http = self
+            .client
+            .post(format!("{}/completion", self.base_url))
+            .timeout(self.timeout);
+        let answer = post_json(http, &body).await?;
+
+        parse_answer(answer["content"].as_str().unwrap_or_default())
+    }
+}
+
+/// The extractor of the crawl.
+#[derive(Clone)]
+pub struct LlmProvider(Arc<dyn LlmExtractor>);
+
+impl LlmProvider {
+    /// Extract with the extractor.
+    pub fn new(extractor: impl LlmExtractor + 'static) -> Self {
+        Self(Arc::new(extractor))
+    }
+
+    /// The JSON extracted from the page.
+    pub async fn extract(&self, request: &LlmRequest<'_>) -> Result<Value, LlmError> {
+        self.0.extract(request).await
+    }
+}
+
+impl Default for LlmProvider {
+    fn default() -> Self {
+        Self::new(OpenAiExtractor::default())
+    }
+}
+
+impl std::fmt::Debug for LlmProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("LlmProvider").finish()
+    }
+}
+
+impl PartialEq for LlmProvider {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// The content of the page sent to the model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LlmContent {
+    /// The markdown of the page with its links and images.
+    #[default]
+    Markdown,
+    /// The main text of the page without the navigation, the sidebars and the footers.
+    Text,
+    /// The html of the page.
+    Html,
+}
+
+/// Extract structured JSON from every page with a language model, given the prompt and the JSON Schema of the crawl,
+/// into [`crate::page::Page::llm_extracted`]. The pages of the crawl are sent concurrently, at most `concurrency`
+/// requests run at once across the clones of the extraction. The crawl goes on while the pages wait for the model,
+/// they are sent to the subscribers once extracted and the crawl waits when `max_pending` pages are waiting.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LlmExtraction {
+    /// The instructions of the extraction ex: `Extract the name, price and currency of the product.`
+    pub prompt: String,
+    /// The JSON Schema of the answer.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub schema: Option<Value>,
+    /// The content of the page sent. Defaults to the markdown.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub content: LlmContent,
+    /// The characters of the content sent at most, the rest is cut. Defaults to 32000.
+    pub max_content_length: usize,
+    /// The requests running at once at most. Defaults to 4.
+    pub concurrency: usize,
+    /// The pages waiting for their extraction at most before the crawl waits. Defaults to 64.
+    pub max_pending: usize,
+    /// The model extracting the data. Defaults to the [`OpenAiExtractor`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub extractor: LlmProvider,
+    /// The permits bounding the requests running at once, created on the first page.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    permits: Arc<tokio::sync::OnceCell<tokio::sync::Semaphore>>,
+    /// The slots of the pages waiting for their extraction, created on the first page.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pending: Arc<tokio::sync::OnceCell<Arc<tokio::sync::Semaphore>>>,
+}
+
+impl Default for LlmExtraction {
+    fn default() -> Self {
+        Self {
+            prompt: String::new(),
+            schema: None,
+            content: LlmContent::default(),
+            max_content_length: 32_000,
+            concurrency: 4,
+            max_pending: 64,
+            extractor: LlmProvider::default(),
+            permits: Default::default(),
+            pending: Default::default(),
+        }
+    }
+}
+
+impl PartialEq for LlmExtraction {
+    fn eq(&self, other: &Self) -> bool {
+        self.prompt == other.prompt
+            && self.schema == other.schema
+            && self.content == other.content
+            && self.max_content_length == other.max_content_length
+            && self.concurrency == other.concurrency
+            && self.max_pending == other.max_pending
+            && self.extractor == other.extractor
+    }
+}
+
+impl From<&GPTConfigs> for LlmExtraction {
+    /// The extraction with the OpenAI model, the prompt and the JSON Schema of the GPT configs.
+    fn from(configs: &GPTConfigs) -> Self {
+        Self {
+            prompt: configs.prompt.to_string(),
+            schema: configs
+                .json_schema
+                .as_ref()
+                .and_then(|format| format.schema.as_deref())
+                .and_then(|schema| serde_json::from_str(schema).ok()),
+            extractor: LlmProvider::new(OpenAiExtractor::from(configs)),
+            ..Default::default()
+        }
+    }
+}
+
+impl LlmExtraction {
+    /// Extract with the prompt and the model.
+    pub fn new(prompt: &str, extractor: impl LlmExtractor + 'static) -> Self {
+        Self {
+            prompt: prompt.into(),
+            extractor: LlmProvider::new(extractor),
+            ..Default::default()
+        }
+    }
+
+    /// The JSON Schema of the answer.
+    pub fn with_schema(mut self, schema: Value) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// The content of the page sent.
+    pub fn with_content(mut self, content: LlmContent) -> Self {
+        self.content = content;
+        self
+    }
+
+    /// The characters of the content sent at most.
+    pub fn with_max_content_length(mut self, max_content_length: usize) -> Self {
+        self.max_content_length = max_content_length;
+        self
+    }
+
+    /// The requests running at once at most.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self.permits = Default::default();
+        self
+    }
+
+    /// The pages waiting for their extraction at most before the crawl waits.
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending;
+        self.pending = Default::default();
+        self
+    }
+
+    /// Wait for a slot of the pages waiting for their extraction, released when the slot is dropped.
+    pub(crate) async fn reserve(&self) -> Option<tokio::sync::OwnedSemaphorePermit> {
+        self.pending
+            .get_or_init(|| async {
+                Arc::new(tokio::sync::Semaphore::new(self.max_pending.max(1)))
+            })
+            .await
+            .clone()
+            .acquire_owned()
+            .await
+            .ok()
+    }
+
+    /// The content of the page sent to the model, cut at the max length.
+    pub fn page_content(&self, page: &Page) -> String {
+        let mut content = match self.content {
+            LlmContent::Markdown => page.markdown(),
+            LlmContent::Text => match &page.article {
+                Some(article) => article.text.clone(),
+                _ => crate::features::text_extraction::TextExtraction::default()
+                    .extract_page(page)
+                    .map(|article| article.text)
+                    .unwrap_or_default(),
+            },
+            LlmContent::Html => page.get_html(),
+        };
+
+        if let Some((end, _)) = content.char_indices().nth(self.max_content_length) {
+            content.truncate(end);
+        }
+
+        content
+    }
+
+    /// The JSON extracted from the page, `None` when the page is empty or the extraction failed.
+    pub async fn extract_page(&self, page: &Page) -> Option<Value> {
+        let content = self.page_content(page);
+
+        if content.trim().is_empty() {
+            return None;
+        }
+
+        let permits = self
+            .permits
+            .get_or_init(|| async { tokio::sync::Semaphore::new(self.concurrency.max(1)) })
+            .await;
+        let _permit = permits.acquire().await.ok()?;
+        let request = LlmRequest {
+            url: page.get_url(),
+            prompt: &self.prompt,
+            schema: self.schema.as_ref(),
+            content: &content,
+        };
+
+        match self.extractor.extract(&request).await {
+            Ok(value) => Some(value),
+            Err(e) => {
+                log("LLM extraction failed: ", e.to_string());
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// An extractor answering with the url after a delay, counting the requests running at once.
+    #[derive(Default)]
+    struct Counting {
+        running: AtomicUsize,
+        max: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmExtractor for Arc<Counting> {
+        async fn extract(&self, request: &LlmRequest<'_>) -> Result<Value, LlmError> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(json!({ "url": request.url, "prompt": request.prompt }))
+        }
+    }
+
+    #[test]
+    fn parses_fenced_answers() {
+        assert_eq!(parse_answer("{\"a\": 1}").unwrap(), json!({ "a": 1 }));
+        assert_eq!(
+            parse_answer("```json\n{\"a\": [1, 2]}\n```").unwrap(),
+            json!({ "a": [1, 2] })
+        );
+        assert!(matches!(parse_answer("  "), Err(LlmError::Empty)));
+        assert!(matches!(parse_answer("no"), Err(LlmError::Json(_))));
+    }
+
+    #[tokio::test]
+    async fn extracts_with_bounded_concurrency() {
+        let counting = Arc::new(Counting::default());
+        let extraction = LlmExtraction::new("Extract the url.", counting.clone())
+            .with_concurrency(2)
+            .with_max_content_length(5);
+        let mut set = tokio::task::JoinSet::new();
+
+        for i in 0..6 {
+            let extraction = extraction.clone();
+            set.spawn(async move {
+                let page = crate::page::build(
+                    &format!("https://example.com/{i}"),
+                    crate::utils::PageResponse {
+                        content: Some(Box::new(bytes::Bytes::from_static(b"<p>Hello world</p>"))),
+                        ..Default::default()
+                    },
+                );
+                assert_eq!(extraction.page_content(&page), "Hello");
+                extraction.extract_page(&page).await
+            });
+        }
+
+        let mut results = Vec::new();
+        while let Some(result) = set.join_next().await {
+            results.push(result.unwrap().unwrap());
+        }
+
+        assert_eq!(results.len(), 6);
+        assert!(results
+            .contains(&json!({ "url": "https://example.com/3", "prompt": "Extract the url." })));
+        assert_eq!(counting.max.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn bounds_the_pending_pages() {
+        let extraction = LlmExtraction::new("Extract the url.", Arc::new(Counting::default()))
+            .with_max_pending(1);
+        let slot = extraction.reserve().await.unwrap();
+
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), extraction.clone().reserve())
+                .await
+                .is_err()
+        );
+        drop(slot);
+        assert!(extraction.reserve().await.is_some());
+    }
+}
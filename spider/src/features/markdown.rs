@@ -0,0 +1,475 @@
+use crate::packages::scraper::{ElementRef, Html, Node};
+use ego_tree::NodeRef;
+use url::Url;
+
+/// The elements left out of the markdown.
+const SKIPPED_TAGS: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "iframe", "svg", "canvas", "button",
+    "input", "select", "textarea", "object", "embed",
+];
+
+/// The elements rendering their children as blocks.
+const CONTAINER_TAGS: &[&str] = &[
+    "html", "body", "div", "section", "article", "main", "header", "footer", "aside", "nav",
+    "figure", "form", "fieldset", "details", "dl", "address", "center",
+];
+
+/// The elements rendering their inline content as a paragraph.
+const PARAGRAPH_TAGS: &[&str] = &[
+    "p",
+    "figcaption",
+    "summary",
+    "dt",
+    "dd",
+    "caption",
+    "legend",
+];
+
+/// Whether the element renders as a block.
+fn is_block(name: &str) -> bool {
+    CONTAINER_TAGS.contains(&name)
+        || PARAGRAPH_TAGS.contains(&name)
+        || matches!(
+            name,
+            "h1" | "h2"
+                | "h3"
+                | "h4"
+                | "h5"
+                | "h6"
+                | "hr"
+                | "pre"
+                | "blockquote"
+                | "ul"
+                | "ol"
+                | "li"
+                | "table"
+        )
+}
+
+/// The text of the node as written, used for the code blocks.
+fn raw_text(node: NodeRef<'_, Node>, out: &mut String) {
+    for child in node.children() {
+        match child.value() {
+            Node::Text(text) => out.push_str(text),
+            Node::Element(element) if element.name() == "br" => out.push('\n'),
+            Node::Element(_) => raw_text(child, out),
+            _ => (),
+        }
+    }
+}
+
+/// Wrap the inline markdown with the marker, keeping the spaces around it outside.
+fn wrap(content: &str, open: &str, close: &str) -> String {
+    let trimmed = content.trim();
+
+    if trimmed.is_empty() {
+        return content.into();
+    }
+
+    let leading = if content.starts_with(char::is_whitespace) {
+        " "
+    } else {
+        ""
+    };
+    let trailing = if content.ends_with(char::is_whitespace) {
+        " "
+    } else {
+        ""
+    };
+
+    format!("{leading}{open}{trimmed}{close}{trailing}")
+}
+
+/// The backticks fencing the code, longer than the backticks inside it.
+fn fence(code: &str, min: usize) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+
+    for c in code.chars() {
+        if c == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+
+    "`".repeat(min.max(longest + 1))
+}
+
+/// The language of a code block from the `language-` or `lang-` class of the pre or code element.
+fn code_language(pre: ElementRef<'_>) -> Option<String> {
+    std::iter::once(pre)
+        .chain(
+            pre.children()
+                .filter_map(ElementRef::wrap)
+                .filter(|child| child.value().name() == "code"),
+        )
+        .flat_map(|element| element.value().classes())
+        .find_map(|class| {
+            class
+                .strip_prefix("language-")
+                .or_else(|| class.strip_prefix("lang-"))
+                .map(String::from)
+        })
+}
+
+/// The paragraph of the inline markdown with the hard line breaks kept.
+fn paragraph(inline: &str) -> String {
+    inline
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("  \n")
+}
+
+/// Convert the html to markdown with the headings, paragraphs, emphasis, lists, quotes, tables, links and images. The
+/// code blocks keep their text and language. The links and images are resolved against the base url.
+pub fn html_to_markdown(html: &str, base: Option<&Url>) -> String {
+    let document = Html::parse_document(html);
+    let converter = Converter { base };
+
+    converter.blocks(*document.root_element()).join("\n\n")
+}
+
+/// The html to markdown conversion.
+struct Converter<'a> {
+    /// The url resolving the links and images.
+    base: Option<&'a Url>,
+}
+
+impl Converter<'_> {
+    /// The url resolved against the base.
+    fn resolve(&self, href: &str) -> String {
+        let href = href.trim();
+
+        match self.base.and_then(|base| base.join(href).ok()) {
+            Some(url) => url.to_string(),
+            _ => href.into(),
+        }
+    }
+
+    /// The blocks of the children of the node, the inline runs between the blocks as paragraphs.
+    fn blocks(&self, node: NodeRef<'_, Node>) -> Vec<String> {
+        let mut blocks = Vec::new();
+        let mut run = String::new();
+
+        for child in node.children() {
+            match ElementRef::wrap(child) {
+                Some(element) if is_block(element.value().name()) => {
+                    self.flush(&mut run, &mut blocks);
+                    self.block(element, &mut blocks);
+                }
+                _ => self.inline_node(child, &mut run),
+            }
+        }
+
+        self.flush(&mut run, &mut blocks);
+        blocks
+    }
+
+    /// Push the inline run as a paragraph.
+    fn flush(&self, run: &mut String, blocks: &mut Vec<String>) {
+        let text = paragraph(run);
+
+        if !text.is_empty() {
+            blocks.push(text);
+        }
+
+        run.clear();
+    }
+
+    /// Push the markdown of the block element.
+    fn block(&self, element: ElementRef<'_>, blocks: &mut Vec<String>) {
+        let name = element.value().name();
+
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let text = self
+                    .inline(*element)
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                if !text.is_empty() {
+                    let level = name[1..].parse().unwrap_or(1);
+                    blocks.push(format!("{} {text}", "#".repeat(level)));
+                }
+            }
+            "hr" => blocks.push("---".into()),
+            "pre" => {
+                let mut code = String::new();
+                raw_text(*element, &mut code);
+                let code = code.trim_matches('\n');
+
+                if !code.trim().is_empty() {
+                    let fence = fence(code, 3);
+                    blocks.push(format!(
+                        "{fence}{}\n{code}\n{fence}",
+                        code_language(element).unwrap_or_default()
+                    ));
+                }
+            }
+            "blockquote" => {
+                let quote = self.blocks(*element).join("\n\n");
+
+                if !quote.is_empty() {
+                    blocks.push(
+                        quote
+                            .lines()
+                            .map(|line| {
+                                if line.is_empty() {
+                                    ">".to_string()
+                                } else {
+                                    format!("> {line}")
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    );
+                }
+            }
+            "ul" | "ol" => {
+                let list = self.list(element);
+
+                if !list.is_empty() {
+                    blocks.push(list);
+                }
+            }
+            "table" => {
+                if let Some(table) = self.table(element) {
+                    blocks.push(table);
+                }
+            }
+            _ if PARAGRAPH_TAGS.contains(&name) => {
+                let text = paragraph(&self.inline(*element));
+
+                if !text.is_empty() {
+                    blocks.push(text);
+                }
+            }
+            _ => blocks.extend(self.blocks(*element)),
+        }
+    }
+
+    /// The items of the list with their nested blocks indented under the marker.
+    fn list(&self, list: ElementRef<'_>) -> String {
+        let ordered = list.value().name() == "ol";
+        let mut number: usize = list
+            .value()
+            .attr("start")
+            .and_then(|start| start.trim().parse().ok())
+            .unwrap_or(1);
+        let mut items = Vec::new();
+
+        for item in list.children().filter_map(ElementRef::wrap) {
+            let marker = if ordered {
+                format!("{number}. ")
+            } else {
+                "- ".into()
+            };
+            let indent = " ".repeat(marker.len());
+            let content = if item.value().name() == "li" {
+                self.blocks(*item).join("\n")
+            } else {
+                let mut blocks = Vec::new();
+                self.block(item, &mut blocks);
+                blocks.join("\n")
+            };
+
+            if content.is_empty() {
+                continue;
+            }
+
+            let mut lines = content.lines();
+            let mut rendered = format!("{marker}{}", lines.next().unwrap_or_default());
+
+            for line in lines {
+                rendered.push('\n');
+                if !line.is_empty() {
+                    rendered.push_str(&indent);
+                    rendered.push_str(line);
+                }
+            }
+
+            items.push(rendered);
+            number += 1;
+        }
+
+        items.join("\n")
+    }
+
+    /// The table with its first row as the header, `None` without cells.
+    fn table(&self, table: ElementRef<'_>) -> Option<String> {
+        let rows: Vec<Vec<String>> = table
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .filter(|row| row.value().name() == "tr")
+            .map(|row| {
+                row.children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                    .map(|cell| {
+                        self.inline(*cell)
+                            .split_whitespace()
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                            .replace('|', "\\|")
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|row| !row.is_empty())
+            .collect();
+
+        let columns = rows.iter().map(Vec::len).max()?;
+        let line = |row: &[String]| {
+            let mut cells: Vec<&str> = row.iter().map(String::as_str).collect();
+            cells.resize(columns, "");
+            format!("| {} |", cells.join(" | "))
+        };
+
+        let mut lines = vec![line(&rows[0]), line(&vec!["---".to_string(); columns])];
+        lines.extend(rows[1..].iter().map(|row| line(row)));
+
+        Some(lines.join("\n"))
+    }
+
+    /// The inline markdown of the children of the node.
+    fn inline(&self, node: NodeRef<'_, Node>) -> String {
+        let mut out = String::new();
+
+        for child in node.children() {
+            self.inline_node(child, &mut out);
+        }
+
+        out
+    }
+
+    /// Push the inline markdown of the node.
+    fn inline_node(&self, node: NodeRef<'_, Node>, out: &mut String) {
+        let element = match node.value() {
+            Node::Text(text) => {
+                let mut last_space = out.is_empty() || out.ends_with([' ', '\n']);
+
+                for c in text.chars() {
+                    if c.is_whitespace() {
+                        if !last_space {
+                            out.push(' ');
+                            last_space = true;
+                        }
+                    } else {
+                        out.push(c);
+                        last_space = false;
+                    }
+                }
+                return;
+            }
+            Node::Element(element) => element,
+            _ => return,
+        };
+
+        let name = element.name();
+
+        if SKIPPED_TAGS.contains(&name) {
+            return;
+        }
+
+        match name {
+            "br" => out.push('\n'),
+            "a" => {
+                let text = self.inline(node);
+
+                match element.attr("href").map(str::trim) {
+                    Some(href)
+                        if !href.is_empty()
+                            && !href.starts_with('#')
+                            && !href.starts_with("javascript:")
+                            && !text.trim().is_empty() =>
+                    {
+                        out.push_str(&wrap(&text, "[", &format!("]({})", self.resolve(href))))
+                    }
+                    _ => out.push_str(&text),
+                }
+            }
+            "img" => {
+                if let Some(src) = element
+                    .attr("src")
+                    .or_else(|| element.attr("data-src"))
+                    .filter(|src| !src.trim().is_empty() && !src.starts_with("data:"))
+                {
+                    let alt = element.attr("alt").unwrap_or_default().trim();
+                    out.push_str(&format!("![{alt}]({})", self.resolve(src)));
+                }
+            }
+            "code" | "kbd" | "samp" | "tt" => {
+                let mut code = String::new();
+                raw_text(node, &mut code);
+                let code = code.split_whitespace().collect::<Vec<_>>().join(" ");
+
+                if !code.is_empty() {
+                    let fence = fence(&code, 1);
+                    let padding = if code.starts_with('`') || code.ends_with('`') {
+                        " "
+                    } else {
+                        ""
+                    };
+                    out.push_str(&format!("{fence}{padding}{code}{padding}{fence}"));
+                }
+            }
+            "strong" | "b" => out.push_str(&wrap(&self.inline(node), "**", "**")),
+            "em" | "i" => out.push_str(&wrap(&self.inline(node), "*", "*")),
+            "del" | "s" | "strike" => out.push_str(&wrap(&self.inline(node), "~~", "~~")),
+            _ if is_block(name) => {
+                let text = self.inline(node);
+                if !out.is_empty() && !out.ends_with([' ', '\n']) {
+                    out.push(' ');
+                }
+                out.push_str(text.trim());
+                out.push(' ');
+            }
+            _ => {
+                for child in node.children() {
+                    self.inline_node(child, out);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_html() {
+        let base = Url::parse("https://example.com/blog/post").unwrap();
+        let html = r#"<html><head><title>Post</title><style>p{}</style></head><body>
+            <h1>Hello   <em>world</em></h1>
+            <p>Read the <a href="/docs">docs</a> or <strong> the guide </strong>.<br>New line.</p>
+            <p><img src="img/cat.png" alt="A cat"> <code>let x = `y`;</code></p>
+            <pre><code class="language-rust">fn main() {
+    println!("hi");
+}
+</code></pre>
+            <ul><li>One</li><li>Two<ol start="3"><li>Three</li></ol></li></ul>
+            <blockquote><p>Quoted</p><p>Twice</p></blockquote>
+            <table><tr><th>Name</th><th>Value</th></tr><tr><td>a|b</td><td>1</td></tr></table>
+            <script>alert(1)</script>
+            </body></html>"#;
+
+        assert_eq!(
+            html_to_markdown(html, Some(&base)),
+            [
+                "# Hello *world*",
+                "Read the [docs](https://example.com/docs) or **the guide** .  \nNew line.",
+                "![A cat](https://example.com/blog/img/cat.png) ``let x = `y`;``",
+                "```rust\nfn main() {\n    println!(\"hi\");\n}\n```",
+                "- One\n- Two\n  3. Three",
+                "> Quoted\n>\n> Twice",
+                "| Name | Value |\n| --- | --- |\n| a\\|b | 1 |",
+            ]
+            .join("\n\n")
+        );
+    }
+}
@@ -0,0 +1,407 @@
+use crate::page::Page;
+use hashbrown::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, ToSocketAddrs};
+use url::Url;
+
+/// Upper bounds in seconds of the latency histogram buckets. Slower fetches land in the `+Inf` bucket.
+pub const LATENCY_BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// The content type of the Prometheus text exposition format.
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// A fixed bucket latency histogram safe to update from many tasks.
+#[derive(Debug, Default)]
+struct Histogram {
+    /// The fetches done within each bucket, the last one holding the overflow.
+    buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    /// The fetches done.
+    count: AtomicU64,
+    /// The total latency in microseconds.
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn record(&self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Write the cumulative buckets, the sum and the count of the histogram with the labels.
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut seen = 0;
+        let separator = if labels.is_empty() { "" } else { "," };
+
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            seen += bucket.load(Ordering::Relaxed);
+            let bound = match LATENCY_BUCKETS.get(i) {
+                Some(bound) => bound.to_string(),
+                _ => "+Inf".into(),
+            };
+            let _ = writeln!(
+                out,
+                "{name}_bucket{{{labels}{separator}le=\"{bound}\"}} {seen}"
+            );
+        }
+
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let count = self.count.load(Ordering::Relaxed);
+
+        if labels.is_empty() {
+            let _ = writeln!(out, "{name}_sum {sum}\n{name}_count {count}");
+        } else {
+            let _ = writeln!(
+                out,
+                "{name}_sum{{{labels}}} {sum}\n{name}_count{{{labels}}} {count}"
+            );
+        }
+    }
+}
+
+/// The counters shared by the clones of the metrics.
+#[derive(Debug, Default)]
+struct MetricsState {
+    /// The pages crawled.
+    pages: AtomicU64,
+    /// The pages crawled without a success status.
+    failed_pages: AtomicU64,
+    /// The bytes of the page bodies.
+    bytes: AtomicU64,
+    /// The links waiting in the frontier for the next batch.
+    frontier_size: AtomicU64,
+    /// The fetch latency of every host.
+    latency: Histogram,
+    /// The fetch latency by host.
+    hosts: RwLock<HashMap<String, Arc<Histogram>>>,
+}
+
+/// The label value escaped for the text format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Write the help and the type of a metric.
+fn describe(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+}
+
+/// Counters and histograms of the crawl: the pages crawled, the bytes transferred, the fetch latency by host and the size
+/// of the frontier. With Chrome the requests blocked by the network managers by reason and the open tabs are added. The
+/// metrics are read with [`CrawlMetrics::render`] in the Prometheus text format or served with [`CrawlMetrics::serve`].
+/// The clones share the counters, keep a clone to read them while the crawl runs. The counters are not reset between
+/// crawls.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrawlMetrics {
+    /// The prefix of the metric names ex: `spider`.
+    pub namespace: String,
+    /// Record the fetch latency of each host. Crawls spanning many hosts grow a histogram per host.
+    pub per_host_latency: bool,
+    /// The counters of the crawl.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    state: Arc<MetricsState>,
+}
+
+impl Default for CrawlMetrics {
+    fn default() -> Self {
+        Self {
+            namespace: "spider".into(),
+            per_host_latency: true,
+            state: Default::default(),
+        }
+    }
+}
+
+impl PartialEq for CrawlMetrics {
+    fn eq(&self, other: &Self) -> bool {
+        self.namespace == other.namespace && self.per_host_latency == other.per_host_latency
+    }
+}
+
+impl CrawlMetrics {
+    /// Record the metrics of the crawl under the `spider` prefix with the latency of each host.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the prefix of the metric names.
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Set whether the fetch latency of each host is recorded.
+    pub fn with_per_host_latency(mut self, per_host_latency: bool) -> Self {
+        self.per_host_latency = per_host_latency;
+        self
+    }
+
+    /// Record a page fetched with the time the fetch took including the retries.
+    pub fn record_page(&self, page: &Page, elapsed: Duration) {
+        let state = &self.state;
+
+        state.pages.fetch_add(1, Ordering::Relaxed);
+        if !page.status_code.is_success() {
+            state.failed_pages.fetch_add(1, Ordering::Relaxed);
+        }
+        state.bytes.fetch_add(
+            page.get_bytes().map_or(0, |bytes| bytes.len() as u64),
+            Ordering::Relaxed,
+        );
+        state.latency.record(elapsed);
+
+        if self.per_host_latency {
+            if let Some(histogram) = Url::parse(page.get_url())
+                .ok()
+                .and_then(|url| url.host_str().and_then(|host| self.host(host)))
+            {
+                histogram.record(elapsed);
+            }
+        }
+    }
+
+    /// The latency histogram of the host.
+    fn host(&self, host: &str) -> Option<Arc<Histogram>> {
+        if let Some(histogram) = self.state.hosts.read().ok()?.get(host) {
+            return Some(histogram.clone());
+        }
+
+        Some(
+            self.state
+                .hosts
+                .write()
+                .ok()?
+                .entry(host.into())
+                .or_default()
+                .clone(),
+        )
+    }
+
+    /// Set the links waiting in the frontier.
+    pub fn set_frontier_size(&self, size: usize) {
+        self.state
+            .frontier_size
+            .store(size as u64, Ordering::Relaxed);
+    }
+
+    /// The pages crawled.
+    pub fn pages_crawled(&self) -> u64 {
+        self.state.pages.load(Ordering::Relaxed)
+    }
+
+    /// The pages crawled without a success status.
+    pub fn pages_failed(&self) -> u64 {
+        self.state.failed_pages.load(Ordering::Relaxed)
+    }
+
+    /// The bytes of the page bodies crawled.
+    pub fn bytes_transferred(&self) -> u64 {
+        self.state.bytes.load(Ordering::Relaxed)
+    }
+
+    /// The links waiting in the frontier at the last batch.
+    pub fn frontier_size(&self) -> u64 {
+        self.state.frontier_size.load(Ordering::Relaxed)
+    }
+
+    /// The metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let state = &self.state;
+        let ns = &self.namespace;
+        let mut out = String::new();
+
+        describe(
+            &mut out,
+            &format!("{ns}_pages_crawled_total"),
+            "counter",
+            "The pages crawled.",
+        );
+        let _ = writeln!(out, "{ns}_pages_crawled_total {}", self.pages_crawled());
+        describe(
+            &mut out,
+            &format!("{ns}_pages_failed_total"),
+            "counter",
+            "The pages crawled without a success status.",
+        );
+        let _ = writeln!(out, "{ns}_pages_failed_total {}", self.pages_failed());
+        describe(
+            &mut out,
+            &format!("{ns}_bytes_transferred_total"),
+            "counter",
+            "The bytes of the page bodies crawled.",
+        );
+        let _ = writeln!(
+            out,
+            "{ns}_bytes_transferred_total {}",
+            self.bytes_transferred()
+        );
+        describe(
+            &mut out,
+            &format!("{ns}_frontier_size"),
+            "gauge",
+            "The links waiting in the frontier.",
+        );
+        let _ = writeln!(out, "{ns}_frontier_size {}", self.frontier_size());
+
+        let name = format!("{ns}_fetch_duration_seconds");
+        describe(&mut out, &name, "histogram", "The time to fetch the pages.");
+        state.latency.render(&mut out, &name, "");
+
+        if self.per_host_latency {
+            let name = format!("{ns}_host_fetch_duration_seconds");
+            describe(
+                &mut out,
+                &name,
+                "histogram",
+                "The time to fetch the pages by host.",
+            );
+
+            if let Ok(hosts) = state.hosts.read() {
+                let mut hosts: Vec<_> = hosts.iter().collect();
+                hosts.sort_by(|a, b| a.0.cmp(b.0));
+
+                for (host, histogram) in hosts {
+                    histogram.render(&mut out, &name, &format!("host=\"{}\"", escape_label(host)));
+                }
+            }
+        }
+
+        #[cfg(feature = "chrome")]
+        {
+            let network = chromiumoxide::metrics::network_metrics();
+
+            describe(
+                &mut out,
+                &format!("{ns}_requests_blocked_total"),
+                "counter",
+                "The browser requests blocked by reason.",
+            );
+            for (reason, blocked) in network.blocked_requests() {
+                let _ = writeln!(
+                    out,
+                    "{ns}_requests_blocked_total{{reason=\"{}\"}} {blocked}",
+                    reason.as_str()
+                );
+            }
+            describe(
+                &mut out,
+                &format!("{ns}_open_tabs"),
+                "gauge",
+                "The pages open in the browsers.",
+            );
+            let _ = writeln!(out, "{ns}_open_tabs {}", network.open_pages());
+        }
+
+        out
+    }
+
+    /// Serve the metrics in the Prometheus text format at `GET /metrics` on the address until the listener fails. Spawn
+    /// it with a clone of the metrics before the crawl ex: `tokio::spawn(metrics.clone().serve("0.0.0.0:9090"))`.
+    pub async fn serve<A: ToSocketAddrs>(self, addr: A) -> std::io::Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+
+        loop {
+            let (mut stream, _) = listener.accept().await?;
+            let metrics = self.clone();
+
+            tokio::spawn(async move {
+                let mut request = [0u8; 1024];
+                let read = stream.read(&mut request).await.unwrap_or_default();
+                let request = String::from_utf8_lossy(&request[..read]);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+                let response = if request.starts_with("GET ")
+                    && (path == "/metrics" || path.starts_with("/metrics?"))
+                {
+                    let body = metrics.render();
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: {PROMETHEUS_CONTENT_TYPE}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .into()
+                };
+
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page::build;
+    use crate::utils::PageResponse;
+
+    #[test]
+    fn render_prometheus_text() {
+        let metrics = CrawlMetrics::new();
+        let page = |url: &str, status_code: reqwest::StatusCode| {
+            build(
+                url,
+                PageResponse {
+                    content: Some(Box::new("<p>hello</p>".to_string().into())),
+                    status_code,
+                    ..Default::default()
+                },
+            )
+        };
+
+        metrics.record_page(
+            &page("https://example.com/", reqwest::StatusCode::OK),
+            Duration::from_millis(80),
+        );
+        metrics.record_page(
+            &page(
+                "https://example.com/missing",
+                reqwest::StatusCode::NOT_FOUND,
+            ),
+            Duration::from_secs(2),
+        );
+        metrics.clone().set_frontier_size(7);
+
+        assert_eq!(metrics.pages_crawled(), 2);
+        assert_eq!(metrics.pages_failed(), 1);
+        assert_eq!(metrics.bytes_transferred(), 24);
+
+        let text = metrics.render();
+
+        assert!(text
+            .contains("# TYPE spider_pages_crawled_total counter\nspider_pages_crawled_total 2\n"));
+        assert!(text.contains("spider_frontier_size 7\n"));
+        assert!(text.contains("spider_fetch_duration_seconds_bucket{le=\"0.05\"} 0\n"));
+        assert!(text.contains("spider_fetch_duration_seconds_bucket{le=\"0.1\"} 1\n"));
+        assert!(text.contains("spider_fetch_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.contains("spider_fetch_duration_seconds_sum 2.08\n"));
+        assert!(text.contains(
+            "spider_host_fetch_duration_seconds_bucket{host=\"example.com\",le=\"2.5\"} 2\n"
+        ));
+        assert!(text.contains("spider_host_fetch_duration_seconds_count{host=\"example.com\"} 2\n"));
+        assert!(!CrawlMetrics::new()
+            .with_per_host_latency(false)
+            .render()
+            .contains("host_fetch_duration"));
+
+        let namespaced = CrawlMetrics::new().with_namespace("crawler");
+        namespaced.set_frontier_size(3);
+        assert!(namespaced.render().contains("crawler_frontier_size 3\n"));
+    }
+}
@@ -1,3 +1,24 @@
+/// Crawl invariants checked after a crawl for CI gates
+pub mod assertions;
+/// Captcha solving of the Chrome pages with pluggable solvers
+#[cfg(feature = "captcha_solver")]
+pub mod captcha_solver;
+/// Browser pool with health checks and recycling
+#[cfg(feature = "chrome")]
+pub mod browser_pool;
+/// Page content change detection between crawls
+#[cfg(feature = "change_detection")]
+pub mod change_detection;
+/// Fault injection to test the crawl configuration
+#[cfg(feature = "chaos")]
+pub mod chaos;
+/// Crawl checkpoints to resume after a restart
+pub mod checkpoint;
+/// Token bounded chunks of the text of the pages for embeddings
+pub mod chunking;
+/// HTTP control server of a running crawl
+#[cfg(feature = "control_server")]
+pub mod control_server;
 /// Chrome utils
 #[cfg(feature = "chrome")]
 pub mod chrome;
@@ -9,16 +30,99 @@ pub mod chrome_mouse_movements;
 #[cfg(feature = "real_browser")]
 /// Viewport
 pub mod chrome_viewport;
+/// Dry run reports of the urls a crawl would visit
+pub mod crawl_plan;
+/// Distributed crawl coordination with Redis
+#[cfg(feature = "distributed")]
+pub mod distributed;
 /// Decentralized header handling
 #[cfg(feature = "decentralized_headers")]
 pub mod decentralized_headers;
+/// Crawl lifecycle events
+pub mod events;
+/// CSS selector scraping with declarative extraction schemas
+pub mod extractor;
+/// Per url routing to the HTTP and Chrome fetchers with escalation of the pages needing javascript
+pub mod fetch_routing;
+/// RSS and Atom feed discovery
+pub mod feed;
+/// HTTP/3 transport with Alt-Svc discovery and fallback
+pub mod http3;
+/// Link discovery from javascript handlers, url attributes and route manifests
+pub mod js_links;
+/// Page language detection and filtering
+pub mod language;
 /// URL globbing
 #[cfg(feature = "glob")]
 pub mod glob;
+/// Broken link checks of the outbound links of the pages
+pub mod link_check;
+/// Structured JSON extraction of the pages with OpenAI, Anthropic or llama.cpp models
+#[cfg(feature = "llm_extraction")]
+pub mod llm_extraction;
+/// HTML to markdown conversion of the pages
+pub mod markdown;
+/// Prometheus metrics of the crawl
+#[cfg(feature = "metrics")]
+pub mod metrics;
+/// Near duplicate detection with SimHash fingerprints
+pub mod near_duplicate;
 /// OpenAI
 #[cfg(feature = "openai")]
 pub mod openai;
 /// Common modules for OpenAI
 pub mod openai_common;
+/// Next page following for paginated listings
+pub mod pagination;
+/// Crawl phases with before and after hooks
+#[cfg(feature = "phases")]
+pub mod phases;
+/// Per domain delay, concurrency, User-Agent, proxy and fetcher
+pub mod politeness;
+/// Personal data detection and redaction
+pub mod pii;
+/// NTLM and Negotiate authentication to corporate proxies
+#[cfg(feature = "proxy_auth")]
+pub mod proxy_auth;
+/// Request tagging and sampled dumps for debugging
+pub mod request_sampling;
+/// NDJSON, CSV and Parquet files of the page records
+#[cfg(feature = "result_writer")]
+pub mod result_writer;
+/// Conditional requests with the validators of the last crawl
+pub mod revalidation;
+/// Time and size based output rotation
+#[cfg(feature = "rotation")]
+pub mod rotation;
+/// Async predicate deciding which discovered urls are crawled and how
+pub mod should_crawl;
+/// Recurring crawls on cron expressions with jitter and run diffs
+#[cfg(feature = "scheduler")]
+pub mod scheduler;
+/// Stream the crawled pages to Kafka, NATS or custom sinks
+#[cfg(feature = "sink")]
+pub mod sink;
+/// Sitemap index, gzip and lastmod handling
+#[cfg(feature = "sitemap")]
+pub mod sitemap;
+/// Crawl snapshots
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+/// Page artifact storage on disk and S3
+#[cfg(feature = "storage")]
+pub mod storage;
+/// JSON-LD, microdata, RDFa and OpenGraph extraction
+#[cfg(feature = "structured_data")]
+pub mod structured_data;
 /// Spoof the refereer
 pub mod spoof_referrer;
+/// Readability style extraction of the title, byline, publish date and main text of the pages
+pub mod text_extraction;
+/// URL normalization and canonicalization before the links are deduplicated
+pub mod url_normalization;
+/// Firefox and the other WebDriver BiDi browsers as the rendering backend
+#[cfg(feature = "chrome")]
+pub mod webdriver_bidi;
+/// XPath 1.0 queries against the html
+#[cfg(feature = "xpath")]
+pub mod xpath;
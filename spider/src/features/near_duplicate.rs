@@ -0,0 +1,311 @@
+use crate::page::Page;
+use crate::utils::{fnv1a_extend, FNV_OFFSET};
+use hashbrown::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// The words hashed together as one feature of the fingerprint.
+const SHINGLE_SIZE: usize = 3;
+
+/// The 64 bit FNV-1a hash of the lowercase words.
+fn hash_words(words: &[&str]) -> u64 {
+    words.iter().fold(FNV_OFFSET, |hash, word| {
+        fnv1a_extend(
+            hash,
+            word.bytes()
+                .map(|byte| byte.to_ascii_lowercase())
+                .chain(std::iter::once(b' ')),
+        )
+    })
+}
+
+/// The text of the html without the tags, the scripts and the styles.
+fn visible_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len() / 2);
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        text.push(' ');
+        rest = &rest[start..];
+
+        let closing = if starts_with_ignore_case(rest, "<script") {
+            "</script>"
+        } else if starts_with_ignore_case(rest, "<style") {
+            "</style>"
+        } else {
+            ">"
+        };
+
+        rest = match find_ignore_case(rest, closing) {
+            Some(end) => &rest[end + closing.len()..],
+            _ => "",
+        };
+    }
+    text.push_str(rest);
+
+    text
+}
+
+/// Whether the text starts with the ascii prefix ignoring the case.
+fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+    text.as_bytes()
+        .get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix.as_bytes()))
+}
+
+/// The position of the ascii needle in the haystack ignoring the case.
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// The 64 bit SimHash of the text from its shingles of three words. Texts sharing most of their content have
+/// fingerprints a few bits apart, compare them with [`distance`]. `None` is returned when the text has no words.
+pub fn simhash(text: &str) -> Option<u64> {
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    if words.is_empty() {
+        return None;
+    }
+
+    let mut weights = [0i64; 64];
+
+    for shingle in words.windows(SHINGLE_SIZE.min(words.len())) {
+        let hash = hash_words(shingle);
+
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash & (1 << bit) != 0 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    Some(
+        weights
+            .iter()
+            .enumerate()
+            .filter(|(_, weight)| **weight > 0)
+            .fold(0, |fingerprint, (bit, _)| fingerprint | (1 << bit)),
+    )
+}
+
+/// The SimHash of the visible text of the html, `None` when the html has no text.
+pub fn html_fingerprint(html: &str) -> Option<u64> {
+    simhash(&visible_text(html))
+}
+
+/// The count of bits different between two fingerprints.
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// The fingerprints of the pages crawled, bucketed by bands of bits. Two fingerprints at most `max_distance` bits
+/// apart have one of their `max_distance + 1` bands equal, so only the fingerprints sharing a band are compared.
+#[derive(Debug, Default)]
+struct Fingerprints {
+    /// The fingerprints of the pages crawled with their url.
+    seen: Vec<(u64, String)>,
+    /// The position in `seen` of the fingerprints by band and value of the band.
+    buckets: HashMap<(u32, u64), Vec<usize>>,
+}
+
+/// The bits of the band of the fingerprint split in `bands` bands.
+fn band(fingerprint: u64, band: u32, bands: u32) -> u64 {
+    let start = band * 64 / bands;
+    let end = (band + 1) * 64 / bands;
+    let bits = fingerprint >> start;
+
+    if end - start == 64 {
+        bits
+    } else {
+        bits & ((1 << (end - start)) - 1)
+    }
+}
+
+/// Skip the pages with nearly the same content as a page crawled before, ex: the print views or the urls only
+/// differing by tracking parameters. The pages are compared by the SimHash of their visible text, the pages without
+/// text are never duplicates.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NearDuplicates {
+    /// The max count of bits between two fingerprints of near duplicates. Defaults to 3.
+    pub max_distance: u32,
+    /// Do not send the near duplicates to the subscribers.
+    pub suppress_results: bool,
+    /// Do not crawl the links of the near duplicates.
+    pub suppress_links: bool,
+    /// The fingerprints of the pages crawled.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    seen: Arc<Mutex<Fingerprints>>,
+}
+
+impl Default for NearDuplicates {
+    fn default() -> Self {
+        Self {
+            max_distance: 3,
+            suppress_results: true,
+            suppress_links: true,
+            seen: Default::default(),
+        }
+    }
+}
+
+impl PartialEq for NearDuplicates {
+    fn eq(&self, other: &Self) -> bool {
+        self.max_distance == other.max_distance
+            && self.suppress_results == other.suppress_results
+            && self.suppress_links == other.suppress_links
+    }
+}
+
+impl NearDuplicates {
+    /// Suppress the near duplicates from the results and the link expansion.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the max count of bits between two fingerprints of near duplicates.
+    pub fn with_max_distance(mut self, max_distance: u32) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+
+    /// Set whether the near duplicates are sent to the subscribers.
+    pub fn with_suppress_results(mut self, suppress_results: bool) -> Self {
+        self.suppress_results = suppress_results;
+        self
+    }
+
+    /// Set whether the links of the near duplicates are crawled.
+    pub fn with_suppress_links(mut self, suppress_links: bool) -> Self {
+        self.suppress_links = suppress_links;
+        self
+    }
+
+    /// The url of the page crawled before the fingerprint is nearly the same as, the fingerprint is kept otherwise.
+    pub fn check(&self, url: &str, fingerprint: u64) -> Option<String> {
+        let mut seen = self.seen.lock().ok()?;
+        let seen = &mut *seen;
+        let bands = self.max_distance.min(63) + 1;
+
+        for i in 0..bands {
+            if let Some(bucket) = seen.buckets.get(&(i, band(fingerprint, i, bands))) {
+                if let Some((_, original)) = bucket
+                    .iter()
+                    .map(|position| &seen.seen[*position])
+                    .find(|(seen, _)| distance(*seen, fingerprint) <= self.max_distance)
+                {
+                    return Some(original.clone());
+                }
+            }
+        }
+
+        for i in 0..bands {
+            seen.buckets
+                .entry((i, band(fingerprint, i, bands)))
+                .or_default()
+                .push(seen.seen.len());
+        }
+        seen.seen.push((fingerprint, url.to_string()));
+
+        None
+    }
+
+    /// Whether the page is a near duplicate of a page crawled before. Empty pages, pages without text and errors are
+    /// never duplicates.
+    pub(crate) fn is_duplicate(&self, page: &Page) -> bool {
+        !page.is_empty()
+            && page.status_code.is_success()
+            && page
+                .fingerprint()
+                .is_some_and(|fingerprint| self.check(page.get_url(), fingerprint).is_some())
+    }
+
+    /// The fingerprints of the pages crawled with their url.
+    pub fn fingerprints(&self) -> Vec<(u64, String)> {
+        match self.seen.lock() {
+            Ok(seen) => seen.seen.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Reset the fingerprints for a new crawl.
+    pub(crate) fn reset(&self) {
+        if let Ok(mut seen) = self.seen.lock() {
+            *seen = Default::default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn near_duplicate_fingerprints() {
+        let article = "<html><head><style>p { color: red }</style></head><body><h1>Rust web crawler</h1><p>The crawler fetches the pages of a website concurrently and follows the links it finds on every page until the budget is spent.</p></body></html>";
+        let print = article.replace("</body>", "<script>print()</script><p>Printed</p></body>");
+        let other = "<html><body><h1>Release notes</h1><p>This version adds request coalescing, conditional requests and phase hooks to the scheduler.</p></body></html>";
+
+        assert!(visible_text(article).contains("Rust web crawler"));
+        assert!(!visible_text(article).contains("color"));
+
+        let a = html_fingerprint(article).unwrap();
+        let print = html_fingerprint(&print).unwrap();
+        let other = html_fingerprint(other).unwrap();
+        assert!(distance(a, print) <= 8);
+        assert!(distance(a, other) > 8);
+        assert_eq!(html_fingerprint("<div><img src=\"a.png\"></div>"), None);
+
+        let near_duplicates = NearDuplicates::new().with_max_distance(8);
+        assert_eq!(near_duplicates.check("https://example.com/a", a), None);
+        assert_eq!(
+            near_duplicates.check("https://example.com/a?print=1", print),
+            Some("https://example.com/a".into())
+        );
+        assert_eq!(
+            near_duplicates.check("https://example.com/notes", other),
+            None
+        );
+        assert_eq!(near_duplicates.fingerprints().len(), 2);
+    }
+
+    #[test]
+    fn banded_lookup() {
+        let near_duplicates = NearDuplicates::new();
+        let fingerprint = 0x0123_4567_89ab_cdef;
+
+        assert_eq!(
+            near_duplicates.check("https://example.com/a", fingerprint),
+            None
+        );
+        assert_eq!(
+            near_duplicates.check(
+                "https://example.com/b",
+                fingerprint ^ (1 << 63 | 1 << 31 | 1)
+            ),
+            Some("https://example.com/a".into())
+        );
+        assert_eq!(
+            near_duplicates.check("https://example.com/c", fingerprint ^ 0b1111),
+            None
+        );
+        assert_eq!(band(u64::MAX, 0, 1), u64::MAX);
+        assert_eq!(band(fingerprint, 3, 4), 0x0123);
+
+        let near_duplicates = NearDuplicates::new().with_max_distance(0);
+        assert_eq!(near_duplicates.check("https://example.com/a", 0), None);
+        assert_eq!(
+            near_duplicates.check("https://example.com/b", 0),
+            Some("https://example.com/a".into())
+        );
+        assert_eq!(near_duplicates.check("https://example.com/c", 1), None);
+    }
+}
@@ -0,0 +1,365 @@
+use crate::packages::scraper::{Html, Selector};
+use crate::page::Page;
+use crate::utils::{element_text, fnv1a};
+use hashbrown::HashMap;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+lazy_static! {
+    static ref REL_NEXT_SELECTOR: Selector =
+        Selector::parse("link[rel~=next][href], a[rel~=next][href]").expect("valid selector");
+    static ref ANCHOR_SELECTOR: Selector = Selector::parse("a[href]").expect("valid selector");
+    static ref PAGINATION_SELECTOR: Selector = Selector::parse(
+        "[class*=pagination], [class*=pager], link[rel~=prev], a[rel~=prev]"
+    )
+    .expect("valid selector");
+}
+
+/// The next page selectors tried by default after `rel=next`.
+const NEXT_SELECTORS: &[&str] = &[
+    ".pagination a.next",
+    ".pagination .next a",
+    "a.next",
+    "a.next-page",
+    "li.next a",
+    ".pager__item--next a",
+    r#"a[aria-label="Next"]"#,
+    r#"a[aria-label="Next page"]"#,
+];
+
+/// The text of the next page links matched by default ignoring the case.
+const NEXT_LABELS: &[&str] = &["next", "next page", "next »", "next ›", "»", "›"];
+
+/// The page number query parameters incremented by default.
+const PAGE_PARAMS: &[&str] = &["page", "pg"];
+
+/// The url of the next page of a html document with the url of the page: the `rel=next` link, the first link matching the
+/// selectors or the labels, then the url with the page number parameter incremented. The parameter is only incremented
+/// on pages with pagination markup: a pagination or pager element, a `rel=prev` link or a link to another page number of
+/// the same path. Links to other hosts are skipped.
+pub fn next_page_url(
+    html: &str,
+    url: &str,
+    selectors: &[String],
+    labels: &[String],
+    page_params: &[String],
+) -> Option<String> {
+    let base = Url::parse(url).ok()?;
+    let same_host = |href: &str| {
+        base.join(href.trim())
+            .ok()
+            .filter(|next| next.host_str() == base.host_str() && next.as_str() != base.as_str())
+            .map(|mut next| {
+                next.set_fragment(None);
+                String::from(next)
+            })
+    };
+    let document = Html::parse_document(html);
+
+    let next = document
+        .select(&REL_NEXT_SELECTOR)
+        .find_map(|element| same_host(element.attr("href")?))
+        .or_else(|| {
+            selectors
+                .iter()
+                .filter_map(|selector| Selector::parse(selector).ok())
+                .find_map(|selector| {
+                    document
+                        .select(&selector)
+                        .find_map(|element| same_host(element.attr("href")?))
+                })
+        })
+        .or_else(|| {
+            document.select(&ANCHOR_SELECTOR).find_map(|element| {
+                let text = element_text(&element);
+
+                if labels.iter().any(|label| label.eq_ignore_ascii_case(&text)) {
+                    same_host(element.attr("href")?)
+                } else {
+                    None
+                }
+            })
+        });
+
+    next.or_else(|| {
+        let (name, number) = base.query_pairs().find_map(|(name, value)| {
+            if page_params.iter().any(|param| param.as_str() == name) {
+                Some((name.into_owned(), value.parse::<usize>().ok()?))
+            } else {
+                None
+            }
+        })?;
+        let paginated = document.select(&PAGINATION_SELECTOR).next().is_some()
+            || document.select(&ANCHOR_SELECTOR).any(|element| {
+                element
+                    .attr("href")
+                    .and_then(|href| base.join(href.trim()).ok())
+                    .is_some_and(|link| {
+                        link.path() == base.path()
+                            && link.query_pairs().any(|(key, value)| {
+                                key == name.as_str() && value.parse::<usize>().is_ok()
+                            })
+                    })
+            });
+
+        if !paginated {
+            return None;
+        }
+
+        let mut next = base.clone();
+        let query: Vec<(String, String)> = base
+            .query_pairs()
+            .map(|(key, value)| {
+                if key == name.as_str() {
+                    (key.into_owned(), (number + 1).to_string())
+                } else {
+                    (key.into_owned(), value.into_owned())
+                }
+            })
+            .collect();
+
+        next.query_pairs_mut().clear().extend_pairs(query);
+        Some(next.into())
+    })
+}
+
+/// The position and the content hash of the previous page of a page in a pagination chain.
+#[derive(Debug, Default, Clone, Copy)]
+struct ChainLink {
+    /// The count of pages followed before the page.
+    position: usize,
+    /// The content hash of the page linking to the page.
+    previous_hash: u64,
+}
+
+/// Follow the next page of the paginated listings: the `rel=next` links, the common next page selectors and labels and
+/// the `?page=N` query parameters. In chrome mode the links rendered by javascript are found. A chain stops after
+/// `max_pages` pages, on a page that is not a success or on a page with the same content as the previous page.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pagination {
+    /// The CSS selectors of the next page links tried after `rel=next` ex: `a.next`.
+    pub selectors: Vec<String>,
+    /// The text of the next page links ignoring the case ex: `Next`.
+    pub labels: Vec<String>,
+    /// The page number query parameters incremented when no next page link is found ex: `page`.
+    pub page_params: Vec<String>,
+    /// The most pages followed after the first page of a chain.
+    pub max_pages: usize,
+    /// The chain links of the pages found.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    chains: Arc<Mutex<HashMap<String, ChainLink>>>,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self {
+            selectors: NEXT_SELECTORS.iter().map(|s| s.to_string()).collect(),
+            labels: NEXT_LABELS.iter().map(|s| s.to_string()).collect(),
+            page_params: PAGE_PARAMS.iter().map(|s| s.to_string()).collect(),
+            max_pages: 50,
+            chains: Default::default(),
+        }
+    }
+}
+
+impl PartialEq for Pagination {
+    fn eq(&self, other: &Self) -> bool {
+        self.selectors == other.selectors
+            && self.labels == other.labels
+            && self.page_params == other.page_params
+            && self.max_pages == other.max_pages
+    }
+}
+
+impl Pagination {
+    /// Follow up to 50 pages of each chain with the default selectors, labels and page parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the CSS selectors of the next page links.
+    pub fn with_selectors(mut self, selectors: Vec<String>) -> Self {
+        self.selectors = selectors;
+        self
+    }
+
+    /// Set the text of the next page links.
+    pub fn with_labels(mut self, labels: Vec<String>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Set the page number query parameters.
+    pub fn with_page_params(mut self, page_params: Vec<String>) -> Self {
+        self.page_params = page_params;
+        self
+    }
+
+    /// Set the most pages followed after the first page of a chain.
+    pub fn with_max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    /// The url of the next page of the page to crawl, `None` when the chain ends.
+    pub fn next_page(&self, page: &Page) -> Option<String> {
+        if !page.status_code.is_success() {
+            return None;
+        }
+
+        let html = page.get_html();
+        let hash = fnv1a(html.as_bytes());
+
+        let mut chains = self.chains.lock().ok()?;
+        let link = chains.get(page.get_url()).copied().unwrap_or_default();
+
+        if link.position >= self.max_pages || (link.position > 0 && link.previous_hash == hash) {
+            return None;
+        }
+
+        let next = next_page_url(
+            &html,
+            page.get_url(),
+            &self.selectors,
+            &self.labels,
+            &self.page_params,
+        )?;
+
+        if chains.contains_key(&next) {
+            return None;
+        }
+
+        chains.insert(
+            next.clone(),
+            ChainLink {
+                position: link.position + 1,
+                previous_hash: hash,
+            },
+        );
+
+        Some(next)
+    }
+
+    /// Forget the chains for a new crawl.
+    pub(crate) fn reset(&self) {
+        if let Ok(mut chains) = self.chains.lock() {
+            chains.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page::build;
+    use crate::utils::PageResponse;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn find_next_page() {
+        let url = "https://example.com/blog?page=2&sort=new";
+        let (selectors, labels, params) = (
+            strings(NEXT_SELECTORS),
+            strings(NEXT_LABELS),
+            strings(PAGE_PARAMS),
+        );
+        let next = |html: &str| next_page_url(html, url, &selectors, &labels, &params);
+
+        assert_eq!(
+            next(
+                r#"<head><link rel="prev" href="/blog?page=1"><link rel="next" href="/blog?page=3"></head>"#
+            ),
+            Some("https://example.com/blog?page=3".into())
+        );
+        assert_eq!(
+            next(
+                r#"<ul class="pagination"><li><a class="next" href="/blog/older">Older</a></li></ul>"#
+            ),
+            Some("https://example.com/blog/older".into())
+        );
+        assert_eq!(
+            next(r#"<a href="https://other.com/next">Next</a><a href=" /blog/p3 "> Next  </a>"#),
+            Some("https://example.com/blog/p3".into())
+        );
+        assert_eq!(
+            next(r#"<nav class="pagination"><a href="/blog/archive">Archive</a></nav>"#),
+            Some("https://example.com/blog?page=3&sort=new".into())
+        );
+        assert_eq!(
+            next(r#"<a href="?page=1&sort=new">1</a>"#),
+            Some("https://example.com/blog?page=3&sort=new".into())
+        );
+        assert_eq!(next("<p>no links</p>"), None);
+        assert_eq!(
+            next_page_url(
+                r#"<ul class="pagination"></ul>"#,
+                "https://example.com/blog?p=2",
+                &selectors,
+                &labels,
+                &params
+            ),
+            None
+        );
+        assert_eq!(
+            next_page_url(
+                r#"<ul class="pagination"></ul>"#,
+                "https://example.com/blog?offset=2",
+                &selectors,
+                &labels,
+                &strings(&["offset"])
+            ),
+            Some("https://example.com/blog?offset=3".into())
+        );
+        assert_eq!(
+            next_page_url(
+                "<p></p>",
+                "https://example.com/blog",
+                &selectors,
+                &labels,
+                &params
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn stop_chains() {
+        let pagination = Pagination::new().with_max_pages(2);
+        let page = |url: &str, html: &str| {
+            build(
+                url,
+                PageResponse {
+                    content: Some(Box::new(
+                        format!(r#"<ul class="pagination"></ul>{html}"#).into(),
+                    )),
+                    status_code: reqwest::StatusCode::OK,
+                    ..Default::default()
+                },
+            )
+        };
+
+        assert_eq!(
+            pagination.next_page(&page("https://example.com/?page=1", "<p>1</p>")),
+            Some("https://example.com/?page=2".into())
+        );
+        assert_eq!(
+            pagination.next_page(&page("https://example.com/?page=2", "<p>2</p>")),
+            Some("https://example.com/?page=3".into())
+        );
+        assert_eq!(
+            pagination.next_page(&page("https://example.com/?page=3", "<p>3</p>")),
+            None
+        );
+
+        pagination.reset();
+        pagination.next_page(&page("https://example.com/?page=1", "<p>1</p>"));
+        assert_eq!(
+            pagination.next_page(&page("https://example.com/?page=2", "<p>1</p>")),
+            None
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use crate::page::Page;
+use std::sync::Arc;
+
+/// A named phase of a crawl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CrawlPhase {
+    /// The start page is fetched and the frontier is seeded with the links of the start page and the extra links.
+    Discover,
+    /// The pages of the frontier are fetched concurrently until the frontier is empty or a limit is reached.
+    Fetch,
+    /// A fetched page is processed before it is sent to the subscribers. The hooks run once per page, the loops of the
+    /// crawl share the processing of the pages.
+    Extract,
+    /// The crawl state is saved and the sinks are flushed.
+    Finalize,
+}
+
+/// The state of the crawl given to the hooks of a phase.
+#[derive(Debug)]
+pub struct PhaseContext<'a> {
+    /// The phase running.
+    pub phase: CrawlPhase,
+    /// The start url of the crawl or the url of the page during the extract phase.
+    pub url: &'a str,
+    /// The links visited so far.
+    pub visited: usize,
+    /// The page processed during the extract phase. Changes to the page are sent to the subscribers.
+    pub page: Option<&'a mut Page>,
+}
+
+/// A hook running before and after the phases of a crawl, ex: to warm a cache before the fetch phase or to write the
+/// results to a database at the finalize phase.
+#[async_trait::async_trait]
+pub trait PhaseHook: Send + Sync {
+    /// Run before the phase.
+    async fn before(&self, _context: &mut PhaseContext<'_>) {}
+
+    /// Run after the phase.
+    async fn after(&self, _context: &mut PhaseContext<'_>) {}
+}
+
+/// The hooks of the crawl phases run in the order they are added.
+#[derive(Clone, Default)]
+pub struct PhaseHooks {
+    /// The hooks.
+    hooks: Vec<Arc<dyn PhaseHook>>,
+}
+
+impl PhaseHooks {
+    /// No hook until one is added.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a hook running after the hooks added before.
+    pub fn with_hook(mut self, hook: impl PhaseHook + 'static) -> Self {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+
+    /// The count of hooks.
+    pub fn len(&self) -> usize {
+        self.hooks.len()
+    }
+
+    /// Whether no hook was added.
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Run the hooks before the phase.
+    pub(crate) async fn before(&self, context: &mut PhaseContext<'_>) {
+        for hook in &self.hooks {
+            hook.before(context).await;
+        }
+    }
+
+    /// Run the hooks after the phase.
+    pub(crate) async fn after(&self, context: &mut PhaseContext<'_>) {
+        for hook in &self.hooks {
+            hook.after(context).await;
+        }
+    }
+}
+
+impl std::fmt::Debug for PhaseHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PhaseHooks")
+            .field("hooks", &self.hooks.len())
+            .finish()
+    }
+}
+
+impl PartialEq for PhaseHooks {
+    fn eq(&self, other: &Self) -> bool {
+        self.hooks.len() == other.hooks.len()
+            && self
+                .hooks
+                .iter()
+                .zip(&other.hooks)
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+    }
+}
+
+/// Run the hooks of the extract phase of the page, before or after the page is processed.
+pub(crate) async fn extract_phase(
+    hooks: &Option<PhaseHooks>,
+    url: &str,
+    visited: usize,
+    page: &mut Page,
+    after: bool,
+) {
+    if let Some(hooks) = hooks {
+        let mut context = PhaseContext {
+            phase: CrawlPhase::Extract,
+            url,
+            visited,
+            page: Some(page),
+        };
+
+        if after {
+            hooks.after(&mut context).await;
+        } else {
+            hooks.before(&mut context).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page::build;
+    use crate::utils::PageResponse;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordHook(Mutex<Vec<String>>);
+
+    #[async_trait::async_trait]
+    impl PhaseHook for Arc<RecordHook> {
+        async fn before(&self, context: &mut PhaseContext<'_>) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("before {:?}", context.phase));
+        }
+
+        async fn after(&self, context: &mut PhaseContext<'_>) {
+            if let Some(page) = context.page.as_mut() {
+                page.waf_check = context.visited == 1;
+            }
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("after {:?}", context.phase));
+        }
+    }
+
+    #[tokio::test]
+    async fn phase_hooks() {
+        let record = Arc::new(RecordHook::default());
+        let hooks = Some(PhaseHooks::new().with_hook(record.clone()));
+        let mut page = build("https://example.com", PageResponse::default());
+
+        extract_phase(&hooks, "https://example.com", 1, &mut page, false).await;
+        extract_phase(&hooks, "https://example.com", 1, &mut page, true).await;
+
+        assert!(page.waf_check);
+        assert_eq!(
+            *record.0.lock().unwrap(),
+            ["before Extract", "after Extract"]
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use crate::page::Page;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+lazy_static! {
+    static ref EMAIL: Regex =
+        Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b").unwrap();
+    /// US social security numbers and UK national insurance numbers.
+    static ref NATIONAL_ID: Regex = Regex::new(
+        r"\b(?:\d{3}-\d{2}-\d{4}|[A-CEGHJ-PR-TW-Z]{2} ?\d{2} ?\d{2} ?\d{2} ?[A-D])\b"
+    )
+    .unwrap();
+    /// Numbers with at least two separated groups to avoid matching plain numbers, see [`is_phone_number`] for the
+    /// dotted addresses.
+    static ref PHONE_NUMBER: Regex = Regex::new(
+        r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{2,4}\)|\b\d{2,4})[\s.-]\d{3,4}[\s.-]\d{3,4}\b"
+    )
+    .unwrap();
+}
+
+/// Whether a match of the pattern is a finding.
+type MatchFilter = fn(&str, &regex::Match) -> bool;
+
+/// Whether the phone number match is not a part of a dotted address like an IPv4 address or a version.
+fn is_phone_number(content: &str, found: &regex::Match) -> bool {
+    let before = content[..found.start()].chars().next_back();
+    let mut after = content[found.end()..].chars();
+
+    let dotted_before = matches!(before, Some(c) if c == '.' || c.is_ascii_digit());
+    let dotted_after =
+        after.next() == Some('.') && after.next().is_some_and(|c| c.is_ascii_digit());
+
+    !(dotted_before || dotted_after)
+}
+
+/// Any match is a finding.
+fn is_match(_content: &str, _found: &regex::Match) -> bool {
+    true
+}
+
+/// The amount of personal data found.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PiiFindings {
+    /// Email addresses found.
+    pub emails: usize,
+    /// Phone numbers found.
+    pub phone_numbers: usize,
+    /// National identification numbers found.
+    pub national_ids: usize,
+}
+
+impl PiiFindings {
+    /// The total amount of findings.
+    pub fn total(&self) -> usize {
+        self.emails + self.phone_numbers + self.national_ids
+    }
+}
+
+/// The findings counted across the pages of a crawl.
+#[derive(Debug, Default)]
+pub(crate) struct PiiFindingsCounter {
+    emails: AtomicUsize,
+    phone_numbers: AtomicUsize,
+    national_ids: AtomicUsize,
+}
+
+/// Detect and redact personal data in the page content before it is sent to the subscribers and stored.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PiiRedaction {
+    /// Detect email addresses.
+    pub emails: bool,
+    /// Detect phone numbers.
+    pub phone_numbers: bool,
+    /// Detect national identification numbers like US social security and UK national insurance numbers.
+    pub national_ids: bool,
+    /// The text to replace the findings with. Defaults to a label of the kind found ex: `[EMAIL]`.
+    pub replacement: Option<String>,
+    /// Count the findings without changing the content.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub detect_only: bool,
+    /// The findings of the crawl.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) findings: Arc<PiiFindingsCounter>,
+}
+
+impl Default for PiiRedaction {
+    fn default() -> Self {
+        Self {
+            emails: true,
+            phone_numbers: true,
+            national_ids: true,
+            replacement: None,
+            detect_only: false,
+            findings: Default::default(),
+        }
+    }
+}
+
+impl PartialEq for PiiRedaction {
+    fn eq(&self, other: &Self) -> bool {
+        self.emails == other.emails
+            && self.phone_numbers == other.phone_numbers
+            && self.national_ids == other.national_ids
+            && self.replacement == other.replacement
+            && self.detect_only == other.detect_only
+    }
+}
+
+impl PiiRedaction {
+    /// Detect and redact all the kinds of personal data.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the findings with the text instead of the kind label.
+    pub fn with_replacement(mut self, replacement: &str) -> Self {
+        self.replacement = Some(replacement.into());
+        self
+    }
+
+    /// Count the findings without changing the content.
+    pub fn with_detect_only(mut self, detect_only: bool) -> Self {
+        self.detect_only = detect_only;
+        self
+    }
+
+    /// Redact the content returning the findings.
+    pub fn redact<'a>(&self, content: &'a str) -> (Cow<'a, str>, PiiFindings) {
+        let mut findings = PiiFindings::default();
+        let mut content = Cow::Borrowed(content);
+
+        // national ids run before phone numbers since they share the digit groups.
+        let passes = [
+            (
+                self.emails,
+                &*EMAIL,
+                is_match as MatchFilter,
+                "[EMAIL]",
+                &mut findings.emails,
+            ),
+            (
+                self.national_ids,
+                &*NATIONAL_ID,
+                is_match,
+                "[NATIONAL_ID]",
+                &mut findings.national_ids,
+            ),
+            (
+                self.phone_numbers,
+                &*PHONE_NUMBER,
+                is_phone_number,
+                "[PHONE]",
+                &mut findings.phone_numbers,
+            ),
+        ];
+
+        for (enabled, pattern, accept, label, count) in passes {
+            if !enabled {
+                continue;
+            }
+
+            let found: Vec<(usize, usize)> = pattern
+                .find_iter(&content)
+                .filter(|found| accept(&content, found))
+                .map(|found| (found.start(), found.end()))
+                .collect();
+
+            *count = found.len();
+
+            if *count > 0 && !self.detect_only {
+                let replacement = self.replacement.as_deref().unwrap_or(label);
+                let mut redacted = String::with_capacity(content.len());
+                let mut last = 0;
+
+                for (start, end) in found {
+                    redacted.push_str(&content[last..start]);
+                    redacted.push_str(replacement);
+                    last = end;
+                }
+                redacted.push_str(&content[last..]);
+
+                content = Cow::Owned(redacted);
+            }
+        }
+
+        (content, findings)
+    }
+
+    /// Redact the page body and count the findings for the crawl. The body is only replaced when something was
+    /// redacted.
+    pub(crate) fn redact_page(&self, mut page: Page) -> Page {
+        if let Some(body) = page.get_bytes() {
+            let body = String::from_utf8_lossy(body);
+            let (content, findings) = self.redact(&body);
+
+            self.findings
+                .emails
+                .fetch_add(findings.emails, Ordering::Relaxed);
+            self.findings
+                .phone_numbers
+                .fetch_add(findings.phone_numbers, Ordering::Relaxed);
+            self.findings
+                .national_ids
+                .fetch_add(findings.national_ids, Ordering::Relaxed);
+
+            if !self.detect_only && findings.total() > 0 {
+                page.set_html_bytes(Some(content.into_owned().into()));
+            }
+        }
+        page
+    }
+
+    /// The findings counted since the crawl started.
+    pub fn findings(&self) -> PiiFindings {
+        PiiFindings {
+            emails: self.findings.emails.load(Ordering::Relaxed),
+            phone_numbers: self.findings.phone_numbers.load(Ordering::Relaxed),
+            national_ids: self.findings.national_ids.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Reset the findings for a new crawl.
+    pub(crate) fn reset_findings(&self) {
+        self.findings.emails.store(0, Ordering::Relaxed);
+        self.findings.phone_numbers.store(0, Ordering::Relaxed);
+        self.findings.national_ids.store(0, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::features::should_crawl::FetchStrategy;
+use crate::Client;
+use hashbrown::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How the crawl treats the urls of a domain.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PolitenessProfile {
+    /// The time between the requests to the domain, on top of the adaptive rate limits of its hosts.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub delay: Option<Duration>,
+    /// The requests in flight to the domain at most across its hosts, in place of the per host limit of the crawl.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub concurrency: Option<usize>,
+    /// The User-Agent of the HTTP requests to the domain.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub user_agent: Option<String>,
+    /// The proxy of the HTTP requests to the domain ex: `http://proxy.example.com:8080`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub proxy: Option<String>,
+    /// The fetcher of the urls of the domain, HTTP or Chrome. The fetcher of the crawl predicate and the fetch routing
+    /// rules come first.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fetch: Option<FetchStrategy>,
+}
+
+impl PolitenessProfile {
+    /// A profile keeping the settings of the crawl.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait the delay between the requests to the domain.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Send at most the concurrency of requests at once to the domain.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = Some(concurrency);
+        self
+    }
+
+    /// Send the User-Agent to the domain.
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Send the requests to the domain through the proxy.
+    pub fn with_proxy(mut self, proxy: &str) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Fetch the urls of the domain with the fetcher.
+    pub fn with_fetch(mut self, fetch: FetchStrategy) -> Self {
+        self.fetch = Some(fetch);
+        self
+    }
+
+    /// Whether the requests to the domain need their own client.
+    pub(crate) fn needs_client(&self) -> bool {
+        self.user_agent.is_some() || self.proxy.is_some()
+    }
+}
+
+/// The runtime state shared by the clones of the profiles.
+#[derive(Debug, Default)]
+pub struct PolitenessState {
+    /// The clients of the domains with a User-Agent or a proxy.
+    clients: Mutex<HashMap<String, Client>>,
+}
+
+/// The politeness profiles by registrable domain, ex: `example.com` for `www.example.com` and `shop.example.com`. One
+/// crawl spanning partner and third party sites can slow down, use another User-Agent or proxy, or render with Chrome
+/// the urls of some domains only. The urls of the domains without a profile use the settings of the crawl. The delays
+/// and the concurrency of the domains are applied by the host limits of the crawl before a request takes a crawl
+/// permit.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PolitenessProfiles {
+    /// The profiles by registrable domain.
+    pub profiles: HashMap<String, PolitenessProfile>,
+    /// The clients of the domains.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    state: Arc<PolitenessState>,
+}
+
+impl PartialEq for PolitenessProfiles {
+    fn eq(&self, other: &Self) -> bool {
+        self.profiles == other.profiles
+    }
+}
+
+/// The registrable domain of the url.
+pub(crate) fn domain_key(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    Some(crate::utils::registrable_domain(url.host_str()?).to_ascii_lowercase())
+}
+
+impl PolitenessProfiles {
+    /// No profiles.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Treat the urls of the registrable domain of the host with the profile.
+    pub fn with_profile(mut self, domain: &str, profile: PolitenessProfile) -> Self {
+        self.profiles.insert(
+            crate::utils::registrable_domain(domain).to_ascii_lowercase(),
+            profile,
+        );
+        self
+    }
+
+    /// The registrable domain and the profile of the url.
+    fn entry(&self, url: &str) -> Option<(String, &PolitenessProfile)> {
+        let domain = domain_key(url)?;
+        let profile = self.profiles.get(&domain)?;
+        Some((domain, profile))
+    }
+
+    /// The profile of the url.
+    pub fn profile(&self, url: &str) -> Option<&PolitenessProfile> {
+        self.entry(url).map(|(_, profile)| profile)
+    }
+
+    /// The fetcher of the url.
+    pub fn fetch_strategy(&self, url: &str) -> Option<FetchStrategy> {
+        self.profile(url)?.fetch
+    }
+
+    /// The delay between the requests by registrable domain.
+    pub(crate) fn delays(&self) -> HashMap<String, Duration> {
+        self.profiles
+            .iter()
+            .filter_map(|(domain, profile)| Some((domain.clone(), profile.delay?)))
+            .collect()
+    }
+
+    /// The requests in flight at most by registrable domain.
+    pub(crate) fn concurrency_limits(&self) -> HashMap<String, usize> {
+        self.profiles
+            .iter()
+            .filter_map(|(domain, profile)| Some((domain.clone(), profile.concurrency?)))
+            .collect()
+    }
+
+    /// The client of the domain of the url when its profile sets a User-Agent or a proxy.
+    pub(crate) fn client(&self, url: &str) -> Option<Client> {
+        let domain = domain_key(url)?;
+        self.state.clients.lock().ok()?.get(&domain).cloned()
+    }
+
+    /// Set the clients of the domains with a User-Agent or a proxy.
+    pub(crate) fn set_clients(&self, clients: HashMap<String, Client>) {
+        if let Ok(mut current) = self.state.clients.lock() {
+            *current = clients;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_domain_profiles() {
+        let profiles = PolitenessProfiles::new()
+            .with_profile(
+                "www.partner.com",
+                PolitenessProfile::new()
+                    .with_concurrency(1)
+                    .with_fetch(FetchStrategy::Http),
+            )
+            .with_profile(
+                "slow.org",
+                PolitenessProfile::new().with_delay(Duration::from_millis(50)),
+            );
+
+        assert_eq!(
+            profiles.fetch_strategy("https://shop.partner.com/item"),
+            Some(FetchStrategy::Http)
+        );
+        assert_eq!(profiles.profile("https://example.com/"), None);
+        assert_eq!(profiles.concurrency_limits().get("partner.com"), Some(&1));
+        assert_eq!(profiles.concurrency_limits().get("slow.org"), None);
+        assert_eq!(
+            profiles.delays().get("slow.org"),
+            Some(&Duration::from_millis(50))
+        );
+        assert_eq!(profiles.delays().len(), 1);
+    }
+}
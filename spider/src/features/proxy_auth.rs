@@ -0,0 +1,717 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use md4::{Digest, Md4};
+use std::convert::TryInto;
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// The max size of a request or response head read by the relay.
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// The max size of a request body buffered by the relay to replay it during the handshake.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// The user of the relay, the password is a secret made for each relay.
+const RELAY_USER: &str = "spider";
+
+/// The signature starting every NTLM message.
+const NTLM_SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+
+/// The flags of the NTLM negotiate message: unicode, oem, request target, ntlm, always sign, extended session
+/// security, 128 and 56 bit keys.
+const NTLM_FLAGS: u32 = 0xa008_8207;
+
+/// The target info entry holding the server timestamp.
+const MSV_AV_TIMESTAMP: u16 = 7;
+
+type HmacMd5 = Hmac<md5::Md5>;
+
+/// The authentication scheme of the proxy.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProxyAuthScheme {
+    /// NTLMv2 with the username, password and domain.
+    #[default]
+    Ntlm,
+    /// SPNEGO with Kerberos using the ticket cache of the user. This does nothing without the `proxy_negotiate` flag
+    /// enabled.
+    Negotiate,
+}
+
+/// Authenticate to a corporate HTTP proxy with NTLM or Negotiate. The crawl connects to a relay on the loopback that
+/// runs the handshake on every connection to the proxy, so the HTTP client and Chrome use the relay as a plain proxy
+/// in place of the corporate proxy. The relay only listens on `127.0.0.1` and only serves the clients sending the
+/// secret made for the relay with basic proxy authentication, the other local processes are refused.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProxyAuth {
+    /// The url of the corporate proxy ex: `http://proxy.corp.example.com:8080`.
+    pub proxy: String,
+    /// The authentication scheme.
+    pub scheme: ProxyAuthScheme,
+    /// The username, or the Kerberos principal with Negotiate. The ticket cache principal is used when empty.
+    pub username: String,
+    /// The password of the user for NTLM, never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub password: String,
+    /// The domain of the user for NTLM.
+    pub domain: String,
+    /// The workstation name sent with NTLM.
+    pub workstation: String,
+    /// The service principal of the proxy for Negotiate. Defaults to `HTTP/<proxy host>`.
+    pub service_principal: Option<String>,
+    /// The url of the relay with its credentials once started.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    relay: Arc<Mutex<Option<String>>>,
+    /// The `Proxy-Authorization` expected from the clients of the relay.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    relay_authorization: Arc<Mutex<Option<String>>>,
+}
+
+impl ProxyAuth {
+    /// Authenticate to the proxy with NTLM.
+    pub fn ntlm(
+        proxy: impl Into<String>,
+        domain: impl Into<String>,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        Self {
+            proxy: proxy.into(),
+            scheme: ProxyAuthScheme::Ntlm,
+            domain: domain.into(),
+            username: username.into(),
+            password: password.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Authenticate to the proxy with Negotiate using the Kerberos ticket cache of the user.
+    pub fn negotiate(proxy: impl Into<String>) -> Self {
+        Self {
+            proxy: proxy.into(),
+            scheme: ProxyAuthScheme::Negotiate,
+            ..Default::default()
+        }
+    }
+
+    /// Set the workstation name sent with NTLM.
+    pub fn with_workstation(mut self, workstation: impl Into<String>) -> Self {
+        self.workstation = workstation.into();
+        self
+    }
+
+    /// Set the Kerberos principal of the user for Negotiate.
+    pub fn with_principal(mut self, principal: impl Into<String>) -> Self {
+        self.username = principal.into();
+        self
+    }
+
+    /// Set the service principal of the proxy for Negotiate.
+    pub fn with_service_principal(mut self, service_principal: impl Into<String>) -> Self {
+        self.service_principal = Some(service_principal.into());
+        self
+    }
+
+    /// The host and port of the proxy.
+    fn proxy_addr(&self) -> std::io::Result<(String, u16)> {
+        let url = url::Url::parse(&self.proxy)
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e.to_string()))?;
+
+        match (url.host_str(), url.port_or_known_default()) {
+            (Some(host), Some(port)) => Ok((host.to_string(), port)),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "the proxy url has no host",
+            )),
+        }
+    }
+
+    /// The url of the relay authenticating to the proxy with the credentials of the relay, started on the first call.
+    /// Must be called from a tokio runtime.
+    pub fn relay(&self) -> std::io::Result<String> {
+        let mut relay = self.relay.lock().map_err(|e| Error::other(e.to_string()))?;
+
+        if let Some(url) = relay.as_ref() {
+            return Ok(url.clone());
+        }
+
+        if self.scheme == ProxyAuthScheme::Negotiate && !cfg!(feature = "proxy_negotiate") {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "Negotiate proxies require the `proxy_negotiate` feature flag",
+            ));
+        }
+
+        self.proxy_addr()?;
+
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+        listener.set_nonblocking(true)?;
+        let secret = format!("{:032x}", rand::random::<u128>());
+        let url = format!("http://{RELAY_USER}:{secret}@{}", listener.local_addr()?);
+        let listener = TcpListener::from_std(listener)?;
+
+        if let Ok(mut relay_authorization) = self.relay_authorization.lock() {
+            *relay_authorization = Some(format!(
+                "Basic {}",
+                STANDARD.encode(format!("{RELAY_USER}:{secret}"))
+            ));
+        }
+
+        let auth = self.clone();
+
+        crate::utils::spawn_task("proxy_auth_relay", async move {
+            while let Ok((client, _)) = listener.accept().await {
+                let auth = auth.clone();
+
+                tokio::spawn(async move {
+                    if let Err(e) = auth.relay_connection(client).await {
+                        log::warn!("proxy auth relay: {e}");
+                    }
+                });
+            }
+        });
+
+        *relay = Some(url.clone());
+
+        Ok(url)
+    }
+
+    /// Authenticate a new connection to the proxy with the first request of the client and pipe the connections.
+    async fn relay_connection(&self, mut client: TcpStream) -> std::io::Result<()> {
+        let head = read_head(&mut client).await?;
+        let authorized = match self.relay_authorization.lock() {
+            Ok(relay_authorization) => {
+                relay_authorization.is_some()
+                    && relay_authorization.as_deref() == header(&head, "proxy-authorization")
+            }
+            _ => false,
+        };
+
+        if !authorized {
+            client
+                .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\nProxy-Authenticate: Basic realm=\"spider\"\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .await?;
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "the client did not send the relay credentials",
+            ));
+        }
+
+        let body = read_body(&mut client, &head).await?;
+        let (host, port) = self.proxy_addr()?;
+        let mut upstream = TcpStream::connect((host.as_str(), port)).await?;
+
+        let response = match self.scheme {
+            ProxyAuthScheme::Ntlm => {
+                let negotiate = STANDARD.encode(ntlm_negotiate());
+                let response =
+                    send_request(&mut upstream, &head, &body, &format!("NTLM {negotiate}")).await?;
+
+                match proxy_challenge(&response, "NTLM").and_then(|c| STANDARD.decode(c).ok()) {
+                    Some(challenge) if status_code(&response) == 407 => {
+                        drain_body(&mut upstream, &response).await?;
+                        let authenticate = ntlm_authenticate(
+                            &challenge,
+                            &self.domain,
+                            &self.username,
+                            &self.password,
+                            &self.workstation,
+                            rand::random(),
+                        )
+                        .ok_or_else(|| {
+                            Error::new(ErrorKind::InvalidData, "invalid NTLM challenge")
+                        })?;
+                        let authorization = format!("NTLM {}", STANDARD.encode(authenticate));
+
+                        send_request(&mut upstream, &head, &body, &authorization).await?
+                    }
+                    _ => response,
+                }
+            }
+            ProxyAuthScheme::Negotiate => {
+                // the ticket cache and the KDC are reached with blocking calls.
+                let auth = self.clone();
+                let service_host = host.clone();
+                let token =
+                    tokio::task::spawn_blocking(move || auth.negotiate_token(&service_host))
+                        .await
+                        .map_err(|e| Error::other(e.to_string()))??;
+                let authorization = format!("Negotiate {}", STANDARD.encode(token));
+
+                send_request(&mut upstream, &head, &body, &authorization).await?
+            }
+        };
+
+        client.write_all(&response).await?;
+        tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+
+        Ok(())
+    }
+
+    /// The SPNEGO token of the user for the proxy.
+    #[cfg(feature = "proxy_negotiate")]
+    fn negotiate_token(&self, host: &str) -> std::io::Result<Vec<u8>> {
+        use cross_krb5::{ClientCtx, InitiateFlags};
+
+        let service_principal = match &self.service_principal {
+            Some(service_principal) => service_principal.clone(),
+            _ => format!("HTTP/{host}"),
+        };
+        let principal = if self.username.is_empty() {
+            None
+        } else {
+            Some(self.username.as_str())
+        };
+
+        let (_, token) =
+            ClientCtx::new(InitiateFlags::empty(), principal, &service_principal, None)
+                .map_err(|e| Error::new(ErrorKind::PermissionDenied, e.to_string()))?;
+
+        Ok(token.to_vec())
+    }
+
+    /// Negotiate is not supported without the flag.
+    #[cfg(not(feature = "proxy_negotiate"))]
+    fn negotiate_token(&self, _host: &str) -> std::io::Result<Vec<u8>> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "Negotiate proxies require the `proxy_negotiate` feature flag",
+        ))
+    }
+}
+
+impl std::fmt::Debug for ProxyAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProxyAuth")
+            .field("proxy", &self.proxy)
+            .field("scheme", &self.scheme)
+            .field("domain", &self.domain)
+            .field("username", &self.username)
+            .field("workstation", &self.workstation)
+            .field("service_principal", &self.service_principal)
+            .finish()
+    }
+}
+
+impl PartialEq for ProxyAuth {
+    fn eq(&self, other: &Self) -> bool {
+        self.proxy == other.proxy
+            && self.scheme == other.scheme
+            && self.username == other.username
+            && self.password == other.password
+            && self.domain == other.domain
+            && self.workstation == other.workstation
+            && self.service_principal == other.service_principal
+    }
+}
+
+/// Read an HTTP head up to the empty line. The head is read byte by byte to leave the rest on the connection.
+async fn read_head(stream: &mut (impl AsyncRead + Unpin)) -> std::io::Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(512);
+
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_HEAD_SIZE {
+            return Err(Error::new(ErrorKind::InvalidData, "the head is too large"));
+        }
+        head.push(stream.read_u8().await?);
+    }
+
+    Ok(head)
+}
+
+/// The value of the header of the head.
+fn header<'a>(head: &'a [u8], name: &str) -> Option<&'a str> {
+    std::str::from_utf8(head)
+        .ok()?
+        .split("\r\n")
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+/// The content length of the head.
+fn content_length(head: &[u8]) -> usize {
+    header(head, "content-length")
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_default()
+}
+
+/// The status code of a response head.
+fn status_code(head: &[u8]) -> u16 {
+    std::str::from_utf8(head)
+        .ok()
+        .and_then(|head| head.split(' ').nth(1))
+        .and_then(|code| code.parse().ok())
+        .unwrap_or_default()
+}
+
+/// The challenge of the scheme in the `Proxy-Authenticate` headers of the response.
+fn proxy_challenge<'a>(head: &'a [u8], scheme: &str) -> Option<&'a str> {
+    std::str::from_utf8(head)
+        .ok()?
+        .split("\r\n")
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(key, _)| key.trim().eq_ignore_ascii_case("proxy-authenticate"))
+        .find_map(|(_, value)| {
+            let (name, challenge) = value.trim().split_once(' ')?;
+            if name.eq_ignore_ascii_case(scheme) {
+                Some(challenge.trim())
+            } else {
+                None
+            }
+        })
+}
+
+/// Read the body of the request to replay it during the handshake. Chunked bodies are not supported.
+async fn read_body(stream: &mut (impl AsyncRead + Unpin), head: &[u8]) -> std::io::Result<Vec<u8>> {
+    if header(head, "transfer-encoding").is_some() {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "chunked request bodies are not supported by the proxy auth relay",
+        ));
+    }
+
+    let length = content_length(head);
+
+    if length > MAX_BODY_SIZE {
+        return Err(Error::new(ErrorKind::InvalidData, "the body is too large"));
+    }
+
+    let mut body = vec![0; length];
+    stream.read_exact(&mut body).await?;
+
+    Ok(body)
+}
+
+/// Skip the body of a challenge response to reuse the connection.
+async fn drain_body(stream: &mut (impl AsyncRead + Unpin), head: &[u8]) -> std::io::Result<()> {
+    if header(head, "transfer-encoding").is_some() {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "chunked challenge responses are not supported by the proxy auth relay",
+        ));
+    }
+
+    let length = content_length(head) as u64;
+    tokio::io::copy(&mut stream.take(length), &mut tokio::io::sink()).await?;
+
+    Ok(())
+}
+
+/// Send the request of the client with the authorization and keep alive headers and read the response head.
+async fn send_request(
+    stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+    head: &[u8],
+    body: &[u8],
+    authorization: &str,
+) -> std::io::Result<Vec<u8>> {
+    let head = String::from_utf8_lossy(head);
+    let mut lines = head.split("\r\n").filter(|line| !line.is_empty());
+    let mut request = String::with_capacity(head.len() + authorization.len() + 64);
+
+    if let Some(request_line) = lines.next() {
+        request.push_str(request_line);
+        request.push_str("\r\n");
+    }
+
+    for line in lines {
+        let name = line.split(':').next().unwrap_or_default().trim();
+
+        if !name.eq_ignore_ascii_case("proxy-authorization")
+            && !name.eq_ignore_ascii_case("proxy-connection")
+        {
+            request.push_str(line);
+            request.push_str("\r\n");
+        }
+    }
+
+    request.push_str("Proxy-Connection: keep-alive\r\nProxy-Authorization: ");
+    request.push_str(authorization);
+    request.push_str("\r\n\r\n");
+
+    stream.write_all(request.as_bytes()).await?;
+    stream.write_all(body).await?;
+    stream.flush().await?;
+
+    read_head(stream).await
+}
+
+/// The text encoded as UTF-16 little endian.
+fn utf16(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+/// The HMAC-MD5 of the parts.
+fn hmac_md5(key: &[u8], parts: &[&[u8]]) -> [u8; 16] {
+    let mut mac = HmacMd5::new_from_slice(key).expect("HMAC takes a key of any size");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+/// The NTLMv2 hash of the user.
+fn ntowf_v2(domain: &str, username: &str, password: &str) -> [u8; 16] {
+    let nt_hash = Md4::digest(utf16(password));
+    hmac_md5(&nt_hash, &[&utf16(&(username.to_uppercase() + domain))])
+}
+
+/// The NTLM negotiate message sent first.
+fn ntlm_negotiate() -> Vec<u8> {
+    let mut message = Vec::with_capacity(32);
+    message.extend_from_slice(NTLM_SIGNATURE);
+    message.extend_from_slice(&1u32.to_le_bytes());
+    message.extend_from_slice(&NTLM_FLAGS.to_le_bytes());
+    message.extend_from_slice(&[0; 16]);
+    message
+}
+
+/// The bytes of the security buffer at the offset of the message.
+fn security_buffer(message: &[u8], offset: usize) -> Option<&[u8]> {
+    let field = message.get(offset..offset + 8)?;
+    let len = u16::from_le_bytes([field[0], field[1]]) as usize;
+    let start = u32::from_le_bytes([field[4], field[5], field[6], field[7]]) as usize;
+    message.get(start..start.checked_add(len)?)
+}
+
+/// The server timestamp of the target info.
+fn target_timestamp(mut target_info: &[u8]) -> Option<[u8; 8]> {
+    while target_info.len() >= 4 {
+        let id = u16::from_le_bytes([target_info[0], target_info[1]]);
+        let len = u16::from_le_bytes([target_info[2], target_info[3]]) as usize;
+        let value = target_info.get(4..4 + len)?;
+
+        if id == MSV_AV_TIMESTAMP {
+            return value.try_into().ok();
+        }
+        target_info = &target_info[4 + len..];
+    }
+    None
+}
+
+/// The current time in 100 nanoseconds since 1601.
+fn ntlm_timestamp() -> [u8; 8] {
+    let since_epoch = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    (since_epoch.as_nanos() as u64 / 100 + 116_444_736_000_000_000).to_le_bytes()
+}
+
+/// The NTLMv2 challenge responses of the server challenge, target info and timestamp.
+fn ntlm_v2_responses(
+    key: &[u8; 16],
+    server_challenge: &[u8],
+    client_challenge: [u8; 8],
+    timestamp: [u8; 8],
+    target_info: &[u8],
+) -> (Vec<u8>, Vec<u8>) {
+    let mut blob = Vec::with_capacity(32 + target_info.len());
+    blob.extend_from_slice(&[1, 1, 0, 0, 0, 0, 0, 0]);
+    blob.extend_from_slice(&timestamp);
+    blob.extend_from_slice(&client_challenge);
+    blob.extend_from_slice(&[0; 4]);
+    blob.extend_from_slice(target_info);
+    blob.extend_from_slice(&[0; 4]);
+
+    let mut nt_response = hmac_md5(key, &[server_challenge, &blob]).to_vec();
+    nt_response.extend_from_slice(&blob);
+
+    let mut lm_response = hmac_md5(key, &[server_challenge, &client_challenge]).to_vec();
+    lm_response.extend_from_slice(&client_challenge);
+
+    (lm_response, nt_response)
+}
+
+/// The NTLM authenticate message answering the challenge message of the proxy.
+fn ntlm_authenticate(
+    challenge: &[u8],
+    domain: &str,
+    username: &str,
+    password: &str,
+    workstation: &str,
+    client_challenge: [u8; 8],
+) -> Option<Vec<u8>> {
+    if challenge.get(..8)? != NTLM_SIGNATURE || challenge.get(8..12)? != 2u32.to_le_bytes() {
+        return None;
+    }
+
+    let flags = u32::from_le_bytes(challenge.get(20..24)?.try_into().ok()?);
+    let server_challenge = challenge.get(24..32)?;
+    let target_info = security_buffer(challenge, 40).unwrap_or_default();
+    let timestamp = target_timestamp(target_info).unwrap_or_else(ntlm_timestamp);
+
+    let key = ntowf_v2(domain, username, password);
+    let (lm_response, nt_response) = ntlm_v2_responses(
+        &key,
+        server_challenge,
+        client_challenge,
+        timestamp,
+        target_info,
+    );
+
+    let payloads = [
+        lm_response,
+        nt_response,
+        utf16(domain),
+        utf16(username),
+        utf16(workstation),
+        Vec::new(),
+    ];
+    let mut message = Vec::with_capacity(64 + payloads.iter().map(Vec::len).sum::<usize>());
+    message.extend_from_slice(NTLM_SIGNATURE);
+    message.extend_from_slice(&3u32.to_le_bytes());
+
+    let mut offset = 64u32;
+    for payload in &payloads {
+        let len = payload.len() as u16;
+        message.extend_from_slice(&len.to_le_bytes());
+        message.extend_from_slice(&len.to_le_bytes());
+        message.extend_from_slice(&offset.to_le_bytes());
+        offset += payload.len() as u32;
+    }
+    message.extend_from_slice(&((flags & NTLM_FLAGS) | 1).to_le_bytes());
+
+    for payload in &payloads {
+        message.extend_from_slice(payload);
+    }
+
+    Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ntlm_v2() {
+        let key = ntowf_v2("Domain", "User", "Password");
+        assert_eq!(
+            key,
+            [
+                0x0c, 0x86, 0x8a, 0x40, 0x3b, 0xfd, 0x7a, 0x93, 0xa3, 0x00, 0x1e, 0xf2, 0x2e, 0xf0,
+                0x2e, 0x3f
+            ]
+        );
+
+        let target_info = b"\x02\x00\x0c\x00D\x00o\x00m\x00a\x00i\x00n\x00\x01\x00\x0c\x00S\x00e\x00r\x00v\x00e\x00r\x00\x00\x00\x00\x00";
+        let server_challenge = [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef];
+        let (_, nt_response) =
+            ntlm_v2_responses(&key, &server_challenge, [0xaa; 8], [0; 8], target_info);
+        assert_eq!(
+            nt_response[..16],
+            [
+                0x68, 0xcd, 0x0a, 0xb8, 0x51, 0xe5, 0x1c, 0x96, 0xaa, 0xbc, 0x92, 0x7b, 0xeb, 0xef,
+                0x6a, 0x1c
+            ]
+        );
+
+        let mut challenge = NTLM_SIGNATURE.to_vec();
+        challenge.extend_from_slice(&2u32.to_le_bytes());
+        challenge.extend_from_slice(&[0; 8]);
+        challenge.extend_from_slice(&NTLM_FLAGS.to_le_bytes());
+        challenge.extend_from_slice(&server_challenge);
+        challenge.extend_from_slice(&[0; 8]);
+        challenge.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        challenge.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        challenge.extend_from_slice(&48u32.to_le_bytes());
+        challenge.extend_from_slice(target_info);
+
+        let message =
+            ntlm_authenticate(&challenge, "Domain", "User", "Password", "", [0xaa; 8]).unwrap();
+        assert_eq!(security_buffer(&message, 36), Some(&utf16("User")[..]));
+        assert_eq!(
+            security_buffer(&message, 20).unwrap().len(),
+            48 + target_info.len()
+        );
+        assert_eq!(
+            ntlm_authenticate(&challenge[..20], "", "", "", "", [0; 8]),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_heads() {
+        let head = b"HTTP/1.1 407 Proxy Authentication Required\r\nProxy-Authenticate: Negotiate\r\nproxy-authenticate: NTLM TlRMTVNTUAAC\r\nContent-Length: 12\r\n\r\n";
+
+        assert_eq!(status_code(head), 407);
+        assert_eq!(content_length(head), 12);
+        assert_eq!(proxy_challenge(head, "NTLM"), Some("TlRMTVNTUAAC"));
+        assert_eq!(proxy_challenge(head, "Basic"), None);
+        assert_eq!(ntlm_negotiate().len(), 32);
+    }
+
+    #[tokio::test]
+    async fn relay_ntlm_handshake() {
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_url = format!("http://{}", proxy.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let (mut stream, _) = proxy.accept().await.unwrap();
+            let head = read_head(&mut stream).await.unwrap();
+            assert!(header(&head, "proxy-authorization")
+                .unwrap()
+                .starts_with("NTLM TlRMTVNTUAAB"));
+
+            let mut challenge = NTLM_SIGNATURE.to_vec();
+            challenge.extend_from_slice(&2u32.to_le_bytes());
+            challenge.extend_from_slice(&[0; 8]);
+            challenge.extend_from_slice(&NTLM_FLAGS.to_le_bytes());
+            challenge.extend_from_slice(&[7; 8]);
+            challenge.extend_from_slice(&[0; 16]);
+            let response = format!(
+                "HTTP/1.1 407 Proxy Authentication Required\r\nProxy-Authenticate: NTLM {}\r\nContent-Length: 4\r\n\r\ndeny",
+                STANDARD.encode(challenge)
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+
+            let head = read_head(&mut stream).await.unwrap();
+            assert!(head.starts_with(b"CONNECT example.com:443 HTTP/1.1\r\n"));
+            assert!(header(&head, "proxy-authorization")
+                .unwrap()
+                .starts_with("NTLM TlRMTVNTUAAD"));
+            stream
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\npong")
+                .await
+                .unwrap();
+        });
+
+        let auth = ProxyAuth::ntlm(proxy_url, "Domain", "User", "Password");
+        let relay = auth.relay().unwrap();
+        assert_eq!(auth.relay().unwrap(), relay);
+
+        let relay = url::Url::parse(&relay).unwrap();
+        let addr = format!("{}:{}", relay.host_str().unwrap(), relay.port().unwrap());
+        let credentials = STANDARD.encode(format!(
+            "{}:{}",
+            relay.username(),
+            relay.password().unwrap()
+        ));
+
+        let mut intruder = TcpStream::connect(&addr).await.unwrap();
+        intruder
+            .write_all(b"CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n")
+            .await
+            .unwrap();
+        assert_eq!(status_code(&read_head(&mut intruder).await.unwrap()), 407);
+
+        let mut client = TcpStream::connect(&addr).await.unwrap();
+        client
+            .write_all(
+                format!(
+                    "CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\nProxy-Authorization: Basic {credentials}\r\n\r\n"
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(status_code(&read_head(&mut client).await.unwrap()), 200);
+        let mut tunnel = [0; 4];
+        client.read_exact(&mut tunnel).await.unwrap();
+        assert_eq!(&tunnel, b"pong");
+    }
+}
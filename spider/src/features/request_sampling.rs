@@ -0,0 +1,372 @@
+use crate::features::politeness::domain_key;
+use crate::page::Page;
+use crate::utils::{log, url_matches};
+use hashbrown::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// The headers with credentials written as `[REDACTED]` in the samples.
+const REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// The headers of a request or response.
+pub(crate) type SampleHeaders = Vec<(String, String)>;
+
+/// A name given to the requests matching a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestTag {
+    /// The name of the tag, used as the directory of its samples.
+    pub name: String,
+    /// The pattern where `*` matches any characters. Patterns starting with `/` match the path of the url, ex: `/api/*`.
+    pub pattern: String,
+}
+
+impl RequestTag {
+    /// Tag the requests matching the pattern.
+    pub fn new(name: &str, pattern: &str) -> Self {
+        Self {
+            name: name.into(),
+            pattern: pattern.into(),
+        }
+    }
+
+    /// Whether the url matches the pattern of the tag.
+    pub fn matches(&self, url: &str) -> bool {
+        url_matches(&self.pattern, url)
+    }
+}
+
+/// The samples written during a crawl.
+#[derive(Debug, Default)]
+pub(crate) struct SamplingState {
+    /// The samples written by tag.
+    counts: HashMap<String, usize>,
+    /// The headers of the HTTP client.
+    request_headers: SampleHeaders,
+    /// The headers of the HTTP clients of the politeness profiles, by registrable domain.
+    domain_headers: HashMap<String, SampleHeaders>,
+    /// The headers the browser sent for the pages matching a tag, by url.
+    sent_headers: HashMap<String, SampleHeaders>,
+}
+
+/// Tag the requests matching patterns and write the first full request and response dumps of each tag to a directory.
+/// Each crawl starts the count again and overwrites the samples of the previous crawl. The pages are sampled with the
+/// headers sent by the HTTP client or the browser, and with Chrome the requests made by the pages like the `fetch`
+/// calls are sampled too. The credential headers are redacted.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestSampling {
+    /// The tags of the requests.
+    pub tags: Vec<RequestTag>,
+    /// The dumps written per tag for each crawl.
+    pub samples_per_tag: usize,
+    /// The directory of the dumps, a sub directory per tag.
+    pub dir: PathBuf,
+    /// The samples of the crawl.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) state: Arc<Mutex<SamplingState>>,
+}
+
+impl PartialEq for RequestSampling {
+    fn eq(&self, other: &Self) -> bool {
+        self.tags == other.tags
+            && self.samples_per_tag == other.samples_per_tag
+            && self.dir == other.dir
+    }
+}
+
+impl RequestSampling {
+    /// Write up to 5 samples per tag to the directory.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            tags: Vec::new(),
+            samples_per_tag: 5,
+            dir: dir.into(),
+            state: Default::default(),
+        }
+    }
+
+    /// Tag the requests matching the pattern.
+    pub fn with_tag(mut self, name: &str, pattern: &str) -> Self {
+        self.tags.push(RequestTag::new(name, pattern));
+        self
+    }
+
+    /// Set the dumps written per tag for each crawl.
+    pub fn with_samples_per_tag(mut self, samples_per_tag: usize) -> Self {
+        self.samples_per_tag = samples_per_tag;
+        self
+    }
+
+    /// The names of the tags matching the url.
+    pub fn tags_for(&self, url: &str) -> Vec<&str> {
+        self.tags
+            .iter()
+            .filter(|tag| tag.matches(url))
+            .map(|tag| tag.name.as_str())
+            .collect()
+    }
+
+    /// Whether a tag matching the url has samples left.
+    pub(crate) fn wants(&self, url: &str) -> bool {
+        let tags = self.tags_for(url);
+
+        !tags.is_empty()
+            && match self.state.lock() {
+                Ok(state) => tags.iter().any(|tag| {
+                    state.counts.get(*tag).copied().unwrap_or_default() < self.samples_per_tag
+                }),
+                _ => false,
+            }
+    }
+
+    /// Claim the next sample number of the tag, none when the tag is full.
+    fn claim(&self, tag: &str) -> Option<usize> {
+        let mut state = self.state.lock().ok()?;
+        let count = state.counts.entry(tag.to_string()).or_default();
+
+        if *count < self.samples_per_tag {
+            *count += 1;
+            Some(*count)
+        } else {
+            None
+        }
+    }
+
+    /// The directory of the samples of the tag.
+    pub fn tag_dir(&self, tag: &str) -> PathBuf {
+        let name: String = tag
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(name)
+    }
+
+    /// Write the dump of the page for each matching tag with samples left, with the headers the browser sent for the
+    /// page or the headers of the HTTP client.
+    pub(crate) async fn record(&self, page: &Page) {
+        let url = page.get_url();
+
+        if !self.wants(url) {
+            return;
+        }
+
+        let request_headers = match self.state.lock() {
+            Ok(mut state) => match state.sent_headers.remove(url) {
+                Some(headers) => headers,
+                _ => domain_key(url)
+                    .and_then(|domain| state.domain_headers.get(&domain))
+                    .unwrap_or(&state.request_headers)
+                    .clone(),
+            },
+            _ => Default::default(),
+        };
+        #[cfg(feature = "headers")]
+        let response_headers: SampleHeaders = match page.headers.as_ref() {
+            Some(headers) => headers
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        name.to_string(),
+                        String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                    )
+                })
+                .collect(),
+            _ => Default::default(),
+        };
+        #[cfg(not(feature = "headers"))]
+        let response_headers = SampleHeaders::new();
+
+        self.record_exchange(
+            "GET",
+            url,
+            &request_headers,
+            &page.status_code.to_string(),
+            &response_headers,
+            page.get_bytes().map_or(&[][..], |body| body),
+        )
+        .await;
+    }
+
+    /// Write the dump of the request for each matching tag with samples left.
+    pub(crate) async fn record_exchange(
+        &self,
+        method: &str,
+        url: &str,
+        request_headers: &[(String, String)],
+        status: &str,
+        response_headers: &[(String, String)],
+        body: &[u8],
+    ) {
+        let samples: Vec<(PathBuf, usize)> = self
+            .tags_for(url)
+            .into_iter()
+            .filter_map(|tag| Some((self.tag_dir(tag), self.claim(tag)?)))
+            .collect();
+
+        if samples.is_empty() {
+            return;
+        }
+
+        let mut dump = Vec::new();
+        let _ = writeln!(dump, "{method} {url}");
+        write_headers(&mut dump, request_headers);
+        let _ = writeln!(dump, "\n{status}");
+        write_headers(&mut dump, response_headers);
+        dump.push(b'\n');
+        dump.extend_from_slice(body);
+
+        let written = tokio::task::spawn_blocking(move || {
+            for (dir, n) in samples {
+                if let Err(err) = write_sample(&dir, n, &dump) {
+                    log("request sampling error: ", err.to_string());
+                }
+            }
+        })
+        .await;
+
+        if let Err(err) = written {
+            log("request sampling error: ", err.to_string());
+        }
+    }
+
+    /// Keep the headers the browser sent for the page until the page is recorded.
+    #[cfg(feature = "chrome")]
+    pub(crate) fn sent(&self, url: &str, headers: SampleHeaders) {
+        if self.wants(url) {
+            if let Ok(mut state) = self.state.lock() {
+                state.sent_headers.insert(url.to_string(), headers);
+            }
+        }
+    }
+
+    /// Set the headers of the HTTP client of the crawl.
+    #[cfg(not(feature = "decentralized"))]
+    pub(crate) fn set_request_headers(&self, request_headers: SampleHeaders) {
+        if let Ok(mut state) = self.state.lock() {
+            state.request_headers = request_headers;
+        }
+    }
+
+    /// Set the headers of the HTTP clients of the politeness profiles by registrable domain.
+    pub(crate) fn set_domain_request_headers(
+        &self,
+        domain_headers: HashMap<String, SampleHeaders>,
+    ) {
+        if let Ok(mut state) = self.state.lock() {
+            state.domain_headers = domain_headers;
+        }
+    }
+
+    /// The samples written by tag since the crawl started.
+    pub fn samples(&self) -> HashMap<String, usize> {
+        match self.state.lock() {
+            Ok(state) => state.counts.clone(),
+            _ => Default::default(),
+        }
+    }
+
+    /// Reset the samples for a new crawl.
+    pub(crate) fn reset(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.counts.clear();
+            state.sent_headers.clear();
+        }
+    }
+}
+
+/// Write the headers of the dump, the credentials redacted.
+fn write_headers(dump: &mut Vec<u8>, headers: &[(String, String)]) {
+    for (name, value) in headers {
+        if REDACTED_HEADERS
+            .iter()
+            .any(|redacted| name.eq_ignore_ascii_case(redacted))
+        {
+            let _ = writeln!(dump, "{name}: [REDACTED]");
+        } else {
+            let _ = writeln!(dump, "{name}: {value}");
+        }
+    }
+}
+
+/// Write the sample file of the tag directory.
+fn write_sample(dir: &Path, n: usize, dump: &[u8]) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join(format!("{n}.http")), dump)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_tags() {
+        let sampling = RequestSampling::new("samples")
+            .with_tag("api", "/api/*")
+            .with_tag("search", "*?q=*")
+            .with_tag("example", "https://example.com/*");
+
+        assert_eq!(
+            sampling.tags_for("https://example.com/api/users?q=1"),
+            vec!["api", "search", "example"]
+        );
+        assert_eq!(
+            sampling.tags_for("https://other.com/about"),
+            Vec::<&str>::new()
+        );
+        assert_eq!(sampling.tag_dir("a/b").file_name().unwrap(), "a_b");
+    }
+
+    #[tokio::test]
+    async fn samples_per_tag() {
+        let dir = std::env::temp_dir().join(format!("spider-samples-{}", std::process::id()));
+        let sampling = RequestSampling::new(&dir)
+            .with_tag("api", "/api/*")
+            .with_samples_per_tag(1);
+
+        #[cfg(not(feature = "decentralized"))]
+        sampling.set_request_headers(vec![("user-agent".into(), "spider".into())]);
+        assert!(sampling.wants("https://example.com/api/users"));
+
+        sampling
+            .record_exchange(
+                "POST",
+                "https://example.com/api/users",
+                &[
+                    ("Authorization".into(), "Bearer secret".into()),
+                    ("Accept".into(), "application/json".into()),
+                ],
+                "200",
+                &[("set-cookie".into(), "session=secret".into())],
+                b"[]",
+            )
+            .await;
+        sampling
+            .record_exchange("GET", "https://example.com/api/other", &[], "200", &[], b"")
+            .await;
+
+        let dump = std::fs::read_to_string(dir.join("api").join("1.http")).unwrap();
+        assert_eq!(
+            dump,
+            "POST https://example.com/api/users\nAuthorization: [REDACTED]\nAccept: application/json\n\n200\nset-cookie: [REDACTED]\n\n[]"
+        );
+        assert!(!dir.join("api").join("2.http").exists());
+        assert!(!sampling.wants("https://example.com/api/users"));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        sampling.reset();
+        assert!(sampling.samples().is_empty());
+    }
+}
@@ -0,0 +1,540 @@
+use crate::features::extractor::ExtractedValue;
+use crate::features::rotation::{RotatingWriter, Rotation};
+use crate::features::sink::{write_blocking, PageRecord, Sink, SinkError};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "result_writer_parquet")]
+use std::time::Instant;
+
+/// The file format of the result writer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ResultFormat {
+    /// One JSON record per line.
+    Ndjson,
+    /// Comma separated values with a header row in each file.
+    Csv,
+    /// Apache Parquet files. Requires the `result_writer_parquet` flag.
+    #[cfg(feature = "result_writer_parquet")]
+    Parquet,
+}
+
+impl ResultFormat {
+    /// The extension of the files of the format without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ResultFormat::Ndjson => "jsonl",
+            ResultFormat::Csv => "csv",
+            #[cfg(feature = "result_writer_parquet")]
+            ResultFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// A column of the CSV and Parquet files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Column {
+    /// The url of the page.
+    Url,
+    /// The status code of the response.
+    StatusCode,
+    /// The title of the page.
+    Title,
+    /// The links followed from the start page.
+    Depth,
+    /// The milliseconds since the page was fetched.
+    DurationMs,
+    /// The time of the record in milliseconds since the unix epoch.
+    CrawledAt,
+    /// The error of the request.
+    ErrorStatus,
+    /// The final destination of the redirects.
+    FinalRedirectDestination,
+    /// The extracted fields as a JSON object.
+    Extracted,
+    /// An extracted field.
+    Field(String),
+}
+
+/// A value of a column.
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    /// A text column.
+    Text(Option<String>),
+    /// An integer column.
+    Int(Option<i64>),
+}
+
+impl Column {
+    /// The name of the column.
+    fn name(&self) -> &str {
+        match self {
+            Column::Url => "url",
+            Column::StatusCode => "status_code",
+            Column::Title => "title",
+            Column::Depth => "depth",
+            Column::DurationMs => "duration_ms",
+            Column::CrawledAt => "crawled_at",
+            Column::ErrorStatus => "error_status",
+            Column::FinalRedirectDestination => "final_redirect_destination",
+            Column::Extracted => "extracted",
+            Column::Field(name) => name,
+        }
+    }
+
+    /// The value of the column for the record.
+    fn cell(&self, record: &PageRecord) -> Cell {
+        match self {
+            Column::Url => Cell::Text(Some(record.url.clone())),
+            Column::StatusCode => Cell::Int(Some(record.status_code.into())),
+            Column::Title => Cell::Text(record.title.clone()),
+            Column::Depth => Cell::Int(Some(record.depth as i64)),
+            Column::DurationMs => Cell::Int(record.duration_ms.map(|ms| ms as i64)),
+            Column::CrawledAt => Cell::Int(Some(record.crawled_at as i64)),
+            Column::ErrorStatus => Cell::Text(record.error_status.clone()),
+            Column::FinalRedirectDestination => {
+                Cell::Text(record.final_redirect_destination.clone())
+            }
+            Column::Extracted => Cell::Text(
+                record
+                    .extracted
+                    .as_ref()
+                    .and_then(|extracted| serde_json::to_string(extracted).ok()),
+            ),
+            Column::Field(name) => Cell::Text(
+                record
+                    .extracted
+                    .as_ref()
+                    .and_then(|extracted| extracted.get(name))
+                    .and_then(field_text),
+            ),
+        }
+    }
+}
+
+/// The extracted value as the text of a column, the lists as JSON.
+fn field_text(value: &ExtractedValue) -> Option<String> {
+    match serde_json::to_value(value).ok()? {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(text) => Some(text),
+        value => Some(value.to_string()),
+    }
+}
+
+/// The value quoted for CSV when it holds a separator, a quote or a line break.
+fn csv_escape(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+/// A CSV line of the values.
+fn csv_line<'a>(values: impl Iterator<Item = std::borrow::Cow<'a, str>>) -> String {
+    let mut line = values.collect::<Vec<_>>().join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// The output of the result writer.
+#[derive(Debug)]
+enum Output {
+    /// The lines of the NDJSON and CSV files.
+    Lines(RotatingWriter),
+    /// The rows of the next Parquet file.
+    #[cfg(feature = "result_writer_parquet")]
+    Parquet {
+        /// The writer of the files.
+        writer: RotatingWriter,
+        /// The rows waiting for the file.
+        rows: Vec<Vec<Cell>>,
+        /// The time the first row waiting was added.
+        opened: Instant,
+    },
+}
+
+impl Output {
+    /// The output of the format.
+    #[cfg_attr(not(feature = "result_writer_parquet"), allow(unused_variables))]
+    fn new(format: ResultFormat, writer: RotatingWriter) -> Self {
+        #[cfg(feature = "result_writer_parquet")]
+        if format == ResultFormat::Parquet {
+            return Output::Parquet {
+                writer,
+                rows: Vec::new(),
+                opened: Instant::now(),
+            };
+        }
+
+        Output::Lines(writer)
+    }
+}
+
+/// Stream the page records of a crawl to NDJSON, CSV or Parquet files rotating with the [`Rotation`]. The CSV and
+/// Parquet files have the columns `url`, `status_code`, `title`, `depth`, `duration_ms`, `crawled_at`,
+/// `error_status`, `final_redirect_destination` and the extracted fields, as one `extracted` JSON column or as a
+/// column per field set with [`ResultWriter::with_fields`]. The Parquet rows are kept in memory until the file is
+/// written, once it has the max rows, once the interval of the rotation passed or when the crawl ends. Use it as the
+/// sink of the crawl with [`crate::features::sink::PageSink::new`], without the content unless the html should be in
+/// the NDJSON records.
+#[derive(Debug)]
+pub struct ResultWriter {
+    /// The format of the files.
+    format: ResultFormat,
+    /// The columns of the CSV and Parquet files.
+    columns: Arc<[Column]>,
+    /// The rows of a Parquet file.
+    max_rows: usize,
+    /// The files written, off the async runtime.
+    output: Arc<Mutex<Output>>,
+}
+
+impl ResultWriter {
+    /// Write the records in the format to the files of the rotation. The extension of the rotation is kept, use
+    /// [`ResultFormat::extension`] to name the files after the format.
+    pub fn new(format: ResultFormat, rotation: Rotation) -> Self {
+        let writer = RotatingWriter::new(rotation);
+
+        Self {
+            format,
+            columns: Arc::new([
+                Column::Url,
+                Column::StatusCode,
+                Column::Title,
+                Column::Depth,
+                Column::DurationMs,
+                Column::CrawledAt,
+                Column::ErrorStatus,
+                Column::FinalRedirectDestination,
+                Column::Extracted,
+            ]),
+            max_rows: 10_000,
+            output: Arc::new(Mutex::new(Output::new(format, writer))),
+        }
+    }
+
+    /// Write the extracted fields as their own columns in place of the `extracted` JSON column of the CSV and Parquet
+    /// files.
+    pub fn with_fields(mut self, fields: &[&str]) -> Self {
+        self.columns = self
+            .columns
+            .iter()
+            .filter(|column| column != &&Column::Extracted)
+            .cloned()
+            .chain(fields.iter().map(|field| Column::Field(field.to_string())))
+            .collect();
+        self
+    }
+
+    /// Set the rows of a Parquet file. Defaults to 10000.
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows.max(1);
+        self
+    }
+
+    /// The header line of the CSV files.
+    fn csv_header(&self) -> String {
+        csv_line(self.columns.iter().map(|column| csv_escape(column.name())))
+    }
+
+    /// The CSV line of the record.
+    fn csv_row(&self, record: &PageRecord) -> String {
+        csv_line(self.columns.iter().map(|column| match column.cell(record) {
+            Cell::Text(text) => csv_escape(&text.unwrap_or_default()).into_owned().into(),
+            Cell::Int(int) => int.map(|int| int.to_string()).unwrap_or_default().into(),
+        }))
+    }
+}
+
+/// Write the Parquet file of the rows waiting.
+#[cfg(feature = "result_writer_parquet")]
+fn write_parquet(
+    columns: &[Column],
+    writer: &mut RotatingWriter,
+    rows: &mut Vec<Vec<Cell>>,
+) -> Result<(), SinkError> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let file = parquet_file(columns, rows).map_err(|err| SinkError::Delivery(err.to_string()))?;
+
+    rows.clear();
+    writer
+        .rotate()
+        .and_then(|_| writer.write_all(&file))
+        .and_then(|_| writer.close())
+        .map_err(|err| SinkError::Delivery(err.to_string()))
+}
+
+/// The Parquet file of the rows with a column per column of the writer.
+#[cfg(feature = "result_writer_parquet")]
+fn parquet_file(columns: &[Column], rows: &[Vec<Cell>]) -> parquet::errors::Result<Vec<u8>> {
+    use parquet::basic::{ConvertedType, Repetition, Type as PhysicalType};
+    use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+    use parquet::schema::types::Type;
+
+    let fields = columns
+        .iter()
+        .map(|column| {
+            let builder = match column.cell(&PageRecord::default()) {
+                Cell::Int(_) => Type::primitive_type_builder(column.name(), PhysicalType::INT64),
+                Cell::Text(_) => {
+                    Type::primitive_type_builder(column.name(), PhysicalType::BYTE_ARRAY)
+                        .with_converted_type(ConvertedType::UTF8)
+                }
+            };
+
+            builder
+                .with_repetition(Repetition::OPTIONAL)
+                .build()
+                .map(Arc::new)
+        })
+        .collect::<parquet::errors::Result<Vec<_>>>()?;
+
+    let schema = Arc::new(
+        Type::group_type_builder("page_record")
+            .with_fields(fields)
+            .build()?,
+    );
+    let properties = Arc::new(parquet::file::properties::WriterProperties::builder().build());
+    let mut writer =
+        parquet::file::writer::SerializedFileWriter::new(Vec::new(), schema, properties)?;
+    let mut row_group = writer.next_row_group()?;
+    let mut index = 0;
+
+    while let Some(mut column) = row_group.next_column()? {
+        let levels: Vec<i16> = rows
+            .iter()
+            .map(|row| match &row[index] {
+                Cell::Text(Some(_)) | Cell::Int(Some(_)) => 1,
+                _ => 0,
+            })
+            .collect();
+
+        match rows.first().map(|row| &row[index]) {
+            Some(Cell::Int(_)) => {
+                let values: Vec<i64> = rows
+                    .iter()
+                    .filter_map(|row| match &row[index] {
+                        Cell::Int(int) => *int,
+                        _ => None,
+                    })
+                    .collect();
+                column
+                    .typed::<Int64Type>()
+                    .write_batch(&values, Some(&levels), None)?;
+            }
+            _ => {
+                let values: Vec<ByteArray> = rows
+                    .iter()
+                    .filter_map(|row| match &row[index] {
+                        Cell::Text(Some(text)) => Some(ByteArray::from(text.as_str())),
+                        _ => None,
+                    })
+                    .collect();
+                column
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, Some(&levels), None)?;
+            }
+        }
+
+        column.close()?;
+        index += 1;
+    }
+
+    row_group.close()?;
+    writer.into_inner()
+}
+
+#[async_trait::async_trait]
+impl Sink for ResultWriter {
+    async fn send(&self, record: &PageRecord) -> Result<(), SinkError> {
+        let line = match self.format {
+            ResultFormat::Csv => self.csv_row(record).into_bytes(),
+            ResultFormat::Ndjson => {
+                let mut line = record.to_json()?;
+                line.push(b'\n');
+                line
+            }
+            #[cfg(feature = "result_writer_parquet")]
+            ResultFormat::Parquet => Vec::new(),
+        };
+        let header = (self.format == ResultFormat::Csv).then(|| self.csv_header());
+        #[cfg(feature = "result_writer_parquet")]
+        let (columns, max_rows) = (self.columns.clone(), self.max_rows);
+        #[cfg(feature = "result_writer_parquet")]
+        let row: Vec<Cell> = if self.format == ResultFormat::Parquet {
+            columns.iter().map(|column| column.cell(record)).collect()
+        } else {
+            Vec::new()
+        };
+
+        write_blocking(&self.output, move |output| match output {
+            Output::Lines(writer) => {
+                let result = match header {
+                    // the header and the first row are written at once so the file does not rotate in between.
+                    Some(header) if writer.due(line.len()) => {
+                        let mut first = header.into_bytes();
+                        first.extend(line);
+                        writer.rotate().and_then(|_| writer.write_all(&first))
+                    }
+                    _ => writer.write_all(&line),
+                };
+
+                result.map_err(|err| SinkError::Delivery(err.to_string()))
+            }
+            #[cfg(feature = "result_writer_parquet")]
+            Output::Parquet {
+                writer,
+                rows,
+                opened,
+            } => {
+                if rows.is_empty() {
+                    *opened = Instant::now();
+                }
+
+                rows.push(row);
+
+                if rows.len() >= max_rows
+                    || writer
+                        .rotation()
+                        .interval
+                        .is_some_and(|interval| opened.elapsed() >= interval)
+                {
+                    write_parquet(&columns, writer, rows)
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await
+    }
+
+    async fn flush(&self) -> Result<(), SinkError> {
+        #[cfg(feature = "result_writer_parquet")]
+        let columns = self.columns.clone();
+
+        write_blocking(&self.output, move |output| match output {
+            Output::Lines(writer) => writer
+                .flush()
+                .map_err(|err| SinkError::Delivery(err.to_string())),
+            #[cfg(feature = "result_writer_parquet")]
+            Output::Parquet { writer, rows, .. } => write_parquet(&columns, writer, rows),
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "spider-result-writer-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn record(url: &str, title: &str) -> PageRecord {
+        PageRecord {
+            url: url.into(),
+            status_code: 200,
+            title: Some(title.into()),
+            depth: 1,
+            crawled_at: 1_700_000_000_000,
+            extracted: Some(BTreeMap::from([(
+                "price".to_string(),
+                ExtractedValue::Number(9.5),
+            )])),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn writes_csv_with_header_per_file() {
+        let dir = test_dir("csv");
+        let rotation = Rotation::new(&dir, "pages", ResultFormat::Csv.extension())
+            .with_interval(None)
+            .with_max_bytes(Some(100));
+        let writer = ResultWriter::new(ResultFormat::Csv, rotation.clone()).with_fields(&["price"]);
+
+        writer
+            .send(&record("https://example.com/a", "Tom, \"Jerry\""))
+            .await
+            .unwrap();
+        writer
+            .send(&record("https://example.com/b", "B"))
+            .await
+            .unwrap();
+        writer.flush().await.unwrap();
+        drop(writer);
+
+        let files = rotation.files().unwrap();
+        assert_eq!(files.len(), 2);
+
+        let header = "url,status_code,title,depth,duration_ms,crawled_at,error_status,final_redirect_destination,price\r\n";
+        assert_eq!(
+            std::fs::read_to_string(&files[0]).unwrap(),
+            format!("{header}https://example.com/a,200,\"Tom, \"\"Jerry\"\"\",1,,1700000000000,,,9.5\r\n")
+        );
+        assert!(std::fs::read_to_string(&files[1])
+            .unwrap()
+            .starts_with(header));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn writes_ndjson() {
+        let dir = test_dir("ndjson");
+        let rotation = Rotation::new(&dir, "pages", ResultFormat::Ndjson.extension());
+        let writer = ResultWriter::new(ResultFormat::Ndjson, rotation.clone());
+
+        writer
+            .send(&record("https://example.com/a", "A"))
+            .await
+            .unwrap();
+        writer.flush().await.unwrap();
+
+        let files = rotation.files().unwrap();
+        let contents = std::fs::read_to_string(&files[0]).unwrap();
+        let line: PageRecord = serde_json::from_str(contents.trim_end()).unwrap();
+        assert_eq!(line, record("https://example.com/a", "A"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[cfg(feature = "result_writer_parquet")]
+    #[tokio::test]
+    async fn writes_parquet() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let dir = test_dir("parquet");
+        let rotation = Rotation::new(&dir, "pages", ResultFormat::Parquet.extension());
+        let writer = ResultWriter::new(ResultFormat::Parquet, rotation.clone()).with_max_rows(2);
+
+        for page in ["a", "b", "c"].iter() {
+            writer
+                .send(&record(&format!("https://example.com/{page}"), page))
+                .await
+                .unwrap();
+        }
+        writer.flush().await.unwrap();
+
+        let files = rotation.files().unwrap();
+        assert_eq!(files.len(), 2);
+
+        let reader = SerializedFileReader::new(std::fs::File::open(&files[0]).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 2);
+        assert_eq!(metadata.schema_descr().num_columns(), 9);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,326 @@
+use crate::features::checkpoint::{read_list, read_str, read_u32, write_str};
+use crate::CaseInsensitiveString;
+use hashbrown::{HashMap, HashSet};
+use reqwest::header::{
+    HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use std::io::{Error, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// The magic bytes starting a revalidation file.
+const REVALIDATION_MAGIC: &[u8; 4] = b"SPRV";
+
+/// The version of the revalidation format written by this crate.
+pub const REVALIDATION_VERSION: u32 = 1;
+
+/// The validators of a url kept from the last crawl.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Validators {
+    /// The `ETag` header of the last response.
+    pub etag: Option<String>,
+    /// The `Last-Modified` header of the last response.
+    pub last_modified: Option<String>,
+    /// The links found on the page, crawled again when the page is not modified.
+    pub links: Vec<String>,
+}
+
+impl Validators {
+    /// The conditional request headers of the validators.
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        if let Some(value) = self
+            .etag
+            .as_deref()
+            .and_then(|etag| HeaderValue::from_str(etag).ok())
+        {
+            headers.insert(IF_NONE_MATCH, value);
+        }
+        if let Some(value) = self
+            .last_modified
+            .as_deref()
+            .and_then(|last_modified| HeaderValue::from_str(last_modified).ok())
+        {
+            headers.insert(IF_MODIFIED_SINCE, value);
+        }
+
+        headers
+    }
+}
+
+/// Keep the `ETag` and `Last-Modified` headers of every url crawled and send them back as `If-None-Match` and
+/// `If-Modified-Since` on the next crawl. A page answering with a 304 is marked unchanged without downloading the body
+/// and the links it had on the last crawl are followed again. Open the cache with a file to keep it across runs.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Revalidation {
+    /// The file the validators are read from and saved to at the end of the crawl.
+    pub path: Option<PathBuf>,
+    /// The validators by url.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    validators: Arc<Mutex<HashMap<String, Validators>>>,
+}
+
+impl Revalidation {
+    /// Keep the validators in memory for the crawls of the website.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the validators in the file. The validators of the file are read when it exists.
+    pub fn open(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let validators = if path.exists() {
+            Self::load(&path)?
+        } else {
+            Default::default()
+        };
+
+        Ok(Self {
+            path: Some(path),
+            validators: Arc::new(Mutex::new(validators)),
+        })
+    }
+
+    /// The validators of the url.
+    pub fn get(&self, url: &str) -> Option<Validators> {
+        self.validators
+            .lock()
+            .ok()
+            .and_then(|validators| validators.get(url).cloned())
+    }
+
+    /// The count of urls with validators.
+    pub fn len(&self) -> usize {
+        self.validators.lock().map(|v| v.len()).unwrap_or_default()
+    }
+
+    /// Whether no url has validators.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove every validator to download all the pages on the next crawl.
+    pub fn clear(&self) {
+        if let Ok(mut validators) = self.validators.lock() {
+            validators.clear();
+        }
+    }
+
+    /// The conditional request headers of the url, empty when the url was not crawled before.
+    pub(crate) fn headers(&self, url: &str) -> HeaderMap {
+        match self.get(url) {
+            Some(validators) => validators.headers(),
+            _ => HeaderMap::new(),
+        }
+    }
+
+    /// Keep the validators of a successful response. Urls without validators are dropped from the cache.
+    pub(crate) fn record_response(&self, url: &str, headers: &HeaderMap) {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value: &HeaderValue| value.to_str().ok())
+                .map(String::from)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+
+        if let Ok(mut validators) = self.validators.lock() {
+            if etag.is_none() && last_modified.is_none() {
+                validators.remove(url);
+            } else {
+                let entry = validators.entry(url.to_string()).or_default();
+                entry.etag = etag;
+                entry.last_modified = last_modified;
+            }
+        }
+    }
+
+    /// Keep the links of a changed page or add the links kept for an unchanged page.
+    pub(crate) fn update_links(
+        &self,
+        url: &str,
+        unchanged: bool,
+        links: &mut HashSet<CaseInsensitiveString>,
+    ) {
+        if let Ok(mut validators) = self.validators.lock() {
+            if let Some(entry) = validators.get_mut(url) {
+                if unchanged {
+                    links.extend(entry.links.iter().map(CaseInsensitiveString::new));
+                } else {
+                    entry.links = links.iter().map(|link| link.inner().to_string()).collect();
+                }
+            }
+        }
+    }
+
+    /// Encode the validators: the magic, the version, the count and each url with its validators and links as little
+    /// endian length prefixed values. A missing validator is an empty string.
+    pub fn encode(&self, w: &mut impl Write) -> std::io::Result<()> {
+        let validators = self
+            .validators
+            .lock()
+            .map_err(|e| Error::other(e.to_string()))?;
+
+        w.write_all(REVALIDATION_MAGIC)?;
+        w.write_all(&REVALIDATION_VERSION.to_le_bytes())?;
+        w.write_all(&(validators.len() as u32).to_le_bytes())?;
+
+        for (url, entry) in validators.iter() {
+            write_str(w, url)?;
+            write_str(w, entry.etag.as_deref().unwrap_or_default())?;
+            write_str(w, entry.last_modified.as_deref().unwrap_or_default())?;
+            w.write_all(&(entry.links.len() as u32).to_le_bytes())?;
+            for link in &entry.links {
+                write_str(w, link)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decode the validators written by [`Revalidation::encode`].
+    pub fn decode(r: &mut impl Read) -> std::io::Result<HashMap<String, Validators>> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+
+        if &magic != REVALIDATION_MAGIC {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "not a revalidation file",
+            ));
+        }
+
+        let version = read_u32(r)?;
+
+        if version > REVALIDATION_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("revalidation version {version} is not supported, the latest version is {REVALIDATION_VERSION}"),
+            ));
+        }
+
+        let count = read_u32(r)? as usize;
+        let mut validators = HashMap::with_capacity(count.min(1 << 16));
+        let non_empty = |value: String| if value.is_empty() { None } else { Some(value) };
+
+        for _ in 0..count {
+            let url = read_str(r)?;
+            let etag = non_empty(read_str(r)?);
+            let last_modified = non_empty(read_str(r)?);
+            let links = read_list(r)?;
+
+            validators.insert(
+                url,
+                Validators {
+                    etag,
+                    last_modified,
+                    links,
+                },
+            );
+        }
+
+        Ok(validators)
+    }
+
+    /// Read the validators of the file.
+    fn load(path: &Path) -> std::io::Result<HashMap<String, Validators>> {
+        Self::decode(&mut std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+
+    /// Write the validators to the file when set. The file is replaced at once so a crash while writing keeps the
+    /// previous validators.
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            _ => return Ok(()),
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let tmp = path.with_extension("tmp");
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&tmp)?);
+
+        self.encode(&mut file)?;
+        file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+        std::fs::rename(tmp, path)
+    }
+}
+
+impl std::fmt::Debug for Revalidation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Revalidation")
+            .field("path", &self.path)
+            .field("urls", &self.len())
+            .finish()
+    }
+}
+
+impl PartialEq for Revalidation {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path && Arc::ptr_eq(&self.validators, &other.validators)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditional_headers() {
+        let revalidation = Revalidation::new();
+        let url = "https://example.com/a";
+        assert!(revalidation.headers(url).is_empty());
+
+        let mut response = HeaderMap::new();
+        response.insert(ETAG, HeaderValue::from_static("\"abc\""));
+        response.insert(
+            LAST_MODIFIED,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        revalidation.record_response(url, &response);
+
+        let headers = revalidation.headers(url);
+        assert_eq!(headers[IF_NONE_MATCH], "\"abc\"");
+        assert_eq!(headers[IF_MODIFIED_SINCE], "Wed, 21 Oct 2015 07:28:00 GMT");
+
+        let mut links: HashSet<CaseInsensitiveString> = HashSet::new();
+        links.insert("https://example.com/b".into());
+        revalidation.update_links(url, false, &mut links);
+
+        let mut unchanged_links = HashSet::new();
+        revalidation.update_links(url, true, &mut unchanged_links);
+        assert_eq!(unchanged_links, links);
+
+        revalidation.record_response(url, &HeaderMap::new());
+        assert!(revalidation.is_empty());
+    }
+
+    #[test]
+    fn revalidation_roundtrip() {
+        let path = std::env::temp_dir()
+            .join(format!("spider-revalidation-{}", std::process::id()))
+            .join("validators.bin");
+        let revalidation = Revalidation::open(&path).unwrap();
+        let mut response = HeaderMap::new();
+        response.insert(ETAG, HeaderValue::from_static("W/\"1\""));
+        revalidation.record_response("https://example.com", &response);
+        revalidation.save().unwrap();
+
+        let reopened = Revalidation::open(&path).unwrap();
+        assert_eq!(
+            reopened.get("https://example.com"),
+            Some(Validators {
+                etag: Some("W/\"1\"".into()),
+                ..Default::default()
+            })
+        );
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
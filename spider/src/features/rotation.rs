@@ -0,0 +1,689 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// When the output files of a long running crawl rotate, how long they are kept and whether they are compressed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rotation {
+    /// The directory of the files.
+    pub dir: PathBuf,
+    /// The start of the file names ex: `crawl` for `crawl-1718000000000-0000.jsonl`.
+    pub prefix: String,
+    /// The extension of the files without the dot ex: `jsonl`, `warc` or `log`.
+    pub extension: String,
+    /// Start a new file once the current one is open for the duration.
+    pub interval: Option<Duration>,
+    /// Start a new file once the current one reaches the size in bytes.
+    pub max_bytes: Option<u64>,
+    /// Keep at most the files, the oldest are removed first.
+    pub max_files: Option<usize>,
+    /// Remove the files older than the duration.
+    pub max_age: Option<Duration>,
+    /// Gzip the files once they are rotated.
+    pub compress: bool,
+}
+
+impl Rotation {
+    /// Write the files to the directory rotating every hour.
+    pub fn new(dir: impl Into<PathBuf>, prefix: &str, extension: &str) -> Self {
+        Self {
+            dir: dir.into(),
+            prefix: prefix.into(),
+            extension: extension.into(),
+            interval: Some(Duration::from_secs(3600)),
+            max_bytes: None,
+            max_files: None,
+            max_age: None,
+            compress: false,
+        }
+    }
+
+    /// Rotate the file once it is open for the duration.
+    pub fn with_interval(mut self, interval: Option<Duration>) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Rotate the file once it reaches the size in bytes.
+    pub fn with_max_bytes(mut self, max_bytes: Option<u64>) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Keep at most the files.
+    pub fn with_max_files(mut self, max_files: Option<usize>) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Remove the files older than the duration.
+    pub fn with_max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Gzip the files once they are rotated.
+    pub fn with_compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Whether the file name is an output of the rotation, compressed or not.
+    fn is_output(&self, file_name: &str) -> bool {
+        let file_name = file_name.strip_suffix(".gz").unwrap_or(file_name);
+
+        file_name
+            .strip_prefix(&self.prefix)
+            .and_then(|rest| rest.strip_prefix('-'))
+            .and_then(|rest| rest.strip_suffix(&self.extension))
+            .is_some_and(|rest| rest.ends_with('.'))
+    }
+
+    /// The output files of the rotation sorted from the oldest.
+    pub fn files(&self) -> std::io::Result<Vec<PathBuf>> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| self.is_output(name))
+            })
+            .map(|entry| entry.path())
+            .collect();
+
+        files.sort();
+
+        Ok(files)
+    }
+}
+
+/// Gzip the file next to it and remove the original.
+fn compress_file(path: &Path) -> std::io::Result<()> {
+    let mut gz_path = path.as_os_str().to_owned();
+    gz_path.push(".gz");
+
+    let mut input = File::open(path)?;
+    let mut encoder = flate2::write::GzEncoder::new(
+        BufWriter::new(File::create(&gz_path)?),
+        flate2::Compression::default(),
+    );
+
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.flush()?;
+
+    std::fs::remove_file(path)
+}
+
+/// A writer splitting its output in files by time or size. The rotated files are compressed and the files past the
+/// retention are removed, so unattended crawls do not need an external logrotate.
+#[derive(Debug)]
+pub struct RotatingWriter {
+    /// The rotation config.
+    rotation: Rotation,
+    /// The file written.
+    file: Option<BufWriter<File>>,
+    /// The path of the file written.
+    path: PathBuf,
+    /// The time the file was opened.
+    opened: SystemTime,
+    /// The bytes written to the file.
+    written: u64,
+    /// The files opened by the writer, used to order files opened in the same millisecond.
+    sequence: u32,
+}
+
+impl RotatingWriter {
+    /// A writer for the rotation. The first file is opened on the first write.
+    pub fn new(rotation: Rotation) -> Self {
+        Self {
+            rotation,
+            file: None,
+            path: PathBuf::new(),
+            opened: SystemTime::now(),
+            written: 0,
+            sequence: 0,
+        }
+    }
+
+    /// The rotation config.
+    pub fn rotation(&self) -> &Rotation {
+        &self.rotation
+    }
+
+    /// The path of the file written, empty before the first write.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the file is due to rotate before the bytes are written.
+    pub(crate) fn due(&self, len: usize) -> bool {
+        match self.file {
+            Some(_) => {
+                self.written > 0
+                    && (self
+                        .rotation
+                        .max_bytes
+                        .is_some_and(|max| self.written + len as u64 > max)
+                        || self.rotation.interval.is_some_and(|interval| {
+                            self.opened.elapsed().unwrap_or_default() >= interval
+                        }))
+            }
+            _ => true,
+        }
+    }
+
+    /// Close the file written, compress it and open the next one.
+    pub fn rotate(&mut self) -> std::io::Result<()> {
+        self.close()?;
+        std::fs::create_dir_all(&self.rotation.dir)?;
+
+        let now = SystemTime::now();
+        let millis = now
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+
+        self.path = self.rotation.dir.join(format!(
+            "{}-{millis:013}-{:04}.{}",
+            self.rotation.prefix, self.sequence, self.rotation.extension
+        ));
+        self.sequence = self.sequence.wrapping_add(1);
+        self.file = Some(BufWriter::new(
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?,
+        ));
+        self.opened = now;
+        self.written = 0;
+
+        self.apply_retention()
+    }
+
+    /// Flush and close the file written, compressing it when set.
+    pub fn close(&mut self) -> std::io::Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+            drop(file);
+
+            if self.rotation.compress {
+                compress_file(&self.path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove the files past the retention, never the file written.
+    fn apply_retention(&self) -> std::io::Result<()> {
+        let mut files = self.rotation.files()?;
+        files.retain(|path| path != &self.path);
+
+        if let Some(max_age) = self.rotation.max_age {
+            files.retain(|path| {
+                let expired = std::fs::metadata(path)
+                    .and_then(|m| m.modified())
+                    .map(|modified| modified.elapsed().unwrap_or_default() > max_age)
+                    .unwrap_or(false);
+
+                !(expired && std::fs::remove_file(path).is_ok())
+            });
+        }
+
+        if let Some(max_files) = self.rotation.max_files {
+            // the file written counts toward the files kept.
+            let keep = max_files.saturating_sub(1);
+
+            if files.len() > keep {
+                for path in &files[..files.len() - keep] {
+                    std::fs::remove_file(path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for RotatingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.due(buf.len()) {
+            self.rotate()?;
+        }
+
+        match self.file.as_mut() {
+            Some(file) => {
+                let written = file.write(buf)?;
+                self.written += written as u64;
+                Ok(written)
+            }
+            _ => Ok(0),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Drop for RotatingWriter {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+/// Stream the page records to rotating JSONL files, one record per line. The files are written off the async runtime.
+#[cfg(feature = "sink")]
+#[derive(Debug)]
+pub struct RotatingJsonlSink(std::sync::Arc<std::sync::Mutex<RotatingWriter>>);
+
+#[cfg(feature = "sink")]
+impl RotatingJsonlSink {
+    /// Write the records to the files of the rotation.
+    pub fn new(rotation: Rotation) -> Self {
+        Self(std::sync::Arc::new(std::sync::Mutex::new(
+            RotatingWriter::new(rotation),
+        )))
+    }
+}
+
+/// Write the buffer to the writer, a delivery error of the sink on failure.
+#[cfg(feature = "sink")]
+fn sink_write(
+    writer: &mut RotatingWriter,
+    buf: &[u8],
+) -> Result<(), crate::features::sink::SinkError> {
+    writer
+        .write_all(buf)
+        .map_err(|err| crate::features::sink::SinkError::Delivery(err.to_string()))
+}
+
+/// Flush the writer, a delivery error of the sink on failure.
+#[cfg(feature = "sink")]
+fn sink_flush(writer: &mut RotatingWriter) -> Result<(), crate::features::sink::SinkError> {
+    writer
+        .flush()
+        .map_err(|err| crate::features::sink::SinkError::Delivery(err.to_string()))
+}
+
+#[cfg(feature = "sink")]
+#[async_trait::async_trait]
+impl crate::features::sink::Sink for RotatingJsonlSink {
+    async fn send(
+        &self,
+        record: &crate::features::sink::PageRecord,
+    ) -> Result<(), crate::features::sink::SinkError> {
+        let mut line = record.to_json()?;
+        line.push(b'\n');
+
+        crate::features::sink::write_blocking(&self.0, move |writer| sink_write(writer, &line))
+            .await
+    }
+
+    async fn flush(&self) -> Result<(), crate::features::sink::SinkError> {
+        crate::features::sink::write_blocking(&self.0, sink_flush).await
+    }
+}
+
+/// The ISO 8601 UTC time of the milliseconds since the unix epoch ex: `2024-06-10T08:00:00Z`.
+#[cfg(feature = "sink")]
+fn iso_date(millis: u64) -> String {
+    let secs = millis / 1000;
+    let days = (secs / 86_400) as i64;
+    let secs = secs % 86_400;
+
+    // the civil date of the days since the epoch, Howard Hinnant's algorithm.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// A random `urn:uuid` for the WARC record ids.
+#[cfg(feature = "sink")]
+fn warc_record_id() -> String {
+    use std::hash::{BuildHasher, Hasher};
+
+    let random = || {
+        std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish()
+    };
+    let (high, low) = (random(), random());
+
+    format!(
+        "<urn:uuid:{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}>",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0x0fff,
+        (low >> 48) & 0x3fff | 0x8000,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+/// The WARC `response` record of the page: the status line, the headers and the html of the record as the HTTP
+/// response. The headers of the transfer are left out since the html is decoded.
+#[cfg(feature = "sink")]
+pub fn warc_record(record: &crate::features::sink::PageRecord) -> Vec<u8> {
+    let html = record.html.as_deref().unwrap_or_default();
+    let reason = reqwest::StatusCode::from_u16(record.status_code)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or_default();
+    let mut block = format!("HTTP/1.1 {} {reason}\r\n", record.status_code);
+
+    for (name, value) in record.headers.iter().filter(|(name, _)| {
+        !["content-length", "content-encoding", "transfer-encoding"]
+            .iter()
+            .any(|skipped| name.eq_ignore_ascii_case(skipped))
+    }) {
+        block.push_str(&format!("{name}: {value}\r\n"));
+    }
+    block.push_str(&format!("content-length: {}\r\n\r\n", html.len()));
+    block.push_str(html);
+
+    let mut warc = format!(
+        "WARC/1.1\r\nWARC-Type: response\r\nWARC-Record-ID: {}\r\nWARC-Date: {}\r\nWARC-Target-URI: {}\r\nContent-Type: application/http;msgtype=response\r\nContent-Length: {}\r\n\r\n",
+        warc_record_id(),
+        iso_date(record.crawled_at),
+        record.url,
+        block.len()
+    )
+    .into_bytes();
+
+    warc.extend(block.into_bytes());
+    warc.extend(b"\r\n\r\n");
+    warc
+}
+
+/// Stream the pages to rotating WARC files, one `response` record per page, for the archive tools. Keep the content
+/// of the [`crate::features::sink::PageSink`] so the records have the html. The files are written off the async
+/// runtime.
+#[cfg(feature = "sink")]
+#[derive(Debug)]
+pub struct RotatingWarcSink(std::sync::Arc<std::sync::Mutex<RotatingWriter>>);
+
+#[cfg(feature = "sink")]
+impl RotatingWarcSink {
+    /// Write the records to the files of the rotation, use the `warc` extension for the archive tools.
+    pub fn new(rotation: Rotation) -> Self {
+        Self(std::sync::Arc::new(std::sync::Mutex::new(
+            RotatingWriter::new(rotation),
+        )))
+    }
+}
+
+#[cfg(feature = "sink")]
+#[async_trait::async_trait]
+impl crate::features::sink::Sink for RotatingWarcSink {
+    async fn send(
+        &self,
+        record: &crate::features::sink::PageRecord,
+    ) -> Result<(), crate::features::sink::SinkError> {
+        let warc = warc_record(record);
+
+        crate::features::sink::write_blocking(&self.0, move |writer| sink_write(writer, &warc))
+            .await
+    }
+
+    async fn flush(&self) -> Result<(), crate::features::sink::SinkError> {
+        crate::features::sink::write_blocking(&self.0, sink_flush).await
+    }
+}
+
+/// A message of the log writer thread.
+enum LogMessage {
+    /// A line to write.
+    Line(Vec<u8>),
+    /// Flush the file written.
+    Flush,
+}
+
+/// A [`log::Log`] writing the records of the crawl to rotating log files, one line per record. The lines are written
+/// by a thread of the logger so logging never blocks on the files. Install it with [`RotatingLogger::init`].
+#[derive(Debug)]
+pub struct RotatingLogger {
+    /// The records kept.
+    level: log::LevelFilter,
+    /// The lines sent to the writer thread.
+    sender: std::sync::Mutex<std::sync::mpsc::Sender<LogMessage>>,
+}
+
+impl RotatingLogger {
+    /// A logger writing the records up to the level to the files of the rotation.
+    pub fn new(rotation: Rotation, level: log::LevelFilter) -> std::io::Result<Self> {
+        let (sender, receiver) = std::sync::mpsc::channel::<LogMessage>();
+        let mut writer = RotatingWriter::new(rotation);
+
+        std::thread::Builder::new()
+            .name("spider-log-rotation".into())
+            .spawn(move || {
+                while let Ok(message) = receiver.recv() {
+                    let _ = match message {
+                        LogMessage::Line(line) => writer.write_all(&line),
+                        LogMessage::Flush => writer.flush(),
+                    };
+                }
+            })?;
+
+        Ok(Self {
+            level,
+            sender: std::sync::Mutex::new(sender),
+        })
+    }
+
+    /// Install the logger as the logger of the process.
+    pub fn init(self) -> Result<(), log::SetLoggerError> {
+        let level = self.level;
+
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(level);
+
+        Ok(())
+    }
+
+    /// Send the message to the writer thread.
+    fn send(&self, message: LogMessage) {
+        if let Ok(sender) = self.sender.lock() {
+            let _ = sender.send(message);
+        }
+    }
+}
+
+impl log::Log for RotatingLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            let millis = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis();
+
+            self.send(LogMessage::Line(
+                format!(
+                    "{millis} {} {} {}\n",
+                    record.level(),
+                    record.target(),
+                    record.args()
+                )
+                .into_bytes(),
+            ));
+        }
+    }
+
+    fn flush(&self) {
+        self.send(LogMessage::Flush);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("spider-rotation-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn rotates_by_size_with_retention() {
+        let dir = test_dir("size");
+        let rotation = Rotation::new(&dir, "crawl", "jsonl")
+            .with_interval(None)
+            .with_max_bytes(Some(10))
+            .with_max_files(Some(2));
+        let mut writer = RotatingWriter::new(rotation.clone());
+
+        for _ in 0..4 {
+            writer.write_all(b"{\"a\":1}\n").unwrap();
+        }
+        writer.flush().unwrap();
+
+        let files = rotation.files().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files.last().unwrap(), writer.path());
+        assert_eq!(std::fs::read(writer.path()).unwrap(), b"{\"a\":1}\n");
+        assert!(!rotation.is_output("other-1.jsonl"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn compresses_rotated_files() {
+        let dir = test_dir("gzip");
+        let rotation = Rotation::new(&dir, "crawl", "log")
+            .with_interval(Some(Duration::ZERO))
+            .with_compress(true);
+        let mut writer = RotatingWriter::new(rotation.clone());
+
+        writer.write_all(b"first\n").unwrap();
+        let first = writer.path().to_path_buf();
+        writer.write_all(b"second\n").unwrap();
+        drop(writer);
+
+        let files = rotation.files().unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().all(|f| f.extension().unwrap() == "gz"));
+
+        let mut gz_path = first.into_os_string();
+        gz_path.push(".gz");
+        let mut contents = String::new();
+        flate2::read::GzDecoder::new(File::open(gz_path).unwrap())
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "first\n");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[cfg(feature = "sink")]
+    #[tokio::test]
+    async fn writes_warc_records() {
+        use crate::features::sink::{PageRecord, Sink};
+
+        assert_eq!(iso_date(0), "1970-01-01T00:00:00Z");
+        assert_eq!(iso_date(951_782_400_000), "2000-02-29T00:00:00Z");
+        assert_eq!(iso_date(1_718_006_400_000), "2024-06-10T08:00:00Z");
+
+        let dir = test_dir("warc");
+        let rotation = Rotation::new(&dir, "crawl", "warc");
+        let sink = RotatingWarcSink::new(rotation.clone());
+        let record = PageRecord {
+            url: "https://example.com/".into(),
+            status_code: 200,
+            html: Some("<html></html>".into()),
+            headers: vec![
+                ("content-type".into(), "text/html".into()),
+                ("content-encoding".into(), "gzip".into()),
+            ],
+            crawled_at: 1_718_006_400_000,
+            ..Default::default()
+        };
+
+        sink.send(&record).await.unwrap();
+        sink.flush().await.unwrap();
+
+        let files = rotation.files().unwrap();
+        let warc = std::fs::read_to_string(&files[0]).unwrap();
+        let block =
+            "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\ncontent-length: 13\r\n\r\n<html></html>";
+
+        assert!(warc.starts_with("WARC/1.1\r\nWARC-Type: response\r\nWARC-Record-ID: <urn:uuid:"));
+        assert!(warc.contains("WARC-Date: 2024-06-10T08:00:00Z\r\n"));
+        assert!(warc.contains("WARC-Target-URI: https://example.com/\r\n"));
+        assert!(warc.contains(&format!(
+            "Content-Length: {}\r\n\r\n{block}\r\n\r\n",
+            block.len()
+        )));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn writes_log_records() {
+        use log::Log;
+
+        let dir = test_dir("log");
+        let rotation = Rotation::new(&dir, "crawl", "log");
+        let logger = RotatingLogger::new(rotation.clone(), log::LevelFilter::Info).unwrap();
+
+        logger.log(
+            &log::Record::builder()
+                .level(log::Level::Warn)
+                .target("spider")
+                .args(format_args!("robots error"))
+                .build(),
+        );
+        logger.log(
+            &log::Record::builder()
+                .level(log::Level::Debug)
+                .args(format_args!("skipped"))
+                .build(),
+        );
+        logger.flush();
+
+        let mut contents = String::new();
+
+        for _ in 0..100 {
+            contents = rotation
+                .files()
+                .ok()
+                .and_then(|files| files.first().cloned())
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .unwrap_or_default();
+            if !contents.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        assert!(contents.ends_with(" WARN spider robots error\n"));
+        assert!(!contents.contains("skipped"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,403 @@
+use crate::features::snapshot::{CrawlSnapshot, SnapshotDiff};
+use crate::utils::log;
+use crate::website::Website;
+use async_job::Job;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+/// What happens when a run is due while the previous run is still crawling.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverlapPolicy {
+    /// Skip the run due, the previous run keeps crawling.
+    #[default]
+    Skip,
+    /// Wait for the previous run to finish and start the run due after it.
+    Queue,
+    /// Cancel the previous run and start the run due.
+    CancelPrevious,
+}
+
+/// The report of a scheduled run given to the hooks.
+#[derive(Debug, Clone)]
+pub struct ScheduledRun {
+    /// The run number starting at 1.
+    pub run: u64,
+    /// The time the run started.
+    pub started_at: SystemTime,
+    /// The time the run took.
+    pub duration: Duration,
+    /// The run was cancelled by the next run, the snapshot is empty.
+    pub cancelled: bool,
+    /// The pages the recorder lagged behind on. The pages missed are left out of the changed links of the diff and keep
+    /// the records of the previous run for the next diff.
+    pub missed_pages: u64,
+    /// The links visited and the content hashes of the pages of the run.
+    pub snapshot: CrawlSnapshot,
+    /// The changes since the previous completed run, `None` for the first run.
+    pub diff: Option<SnapshotDiff>,
+}
+
+/// A hook receiving the report of each scheduled run, ex: to alert on the pages removed or changed since the last run.
+#[async_trait::async_trait]
+pub trait ScheduleHook: Send + Sync {
+    /// Run after each scheduled run.
+    async fn on_run(&self, run: &ScheduledRun);
+}
+
+/// A run crawling in the background.
+struct RunningCrawl {
+    /// The run number.
+    run: u64,
+    /// The time the run started.
+    started_at: SystemTime,
+    /// The instant the run started.
+    started: Instant,
+    /// The crawl returning the website for the next run and the snapshot of the run.
+    task: JoinHandle<FinishedCrawl>,
+}
+
+/// A run that finished crawling.
+struct FinishedCrawl {
+    /// The website for the next run.
+    website: Website,
+    /// The snapshot of the run.
+    snapshot: CrawlSnapshot,
+    /// The pages the recorder lagged behind on.
+    missed: u64,
+}
+
+/// What woke the scheduler up.
+enum SchedulerEvent {
+    /// The next run is due.
+    Due,
+    /// The running crawl finished.
+    Finished(Option<FinishedCrawl>),
+    /// The scheduler was stopped.
+    Stop,
+}
+
+/// Run the cron job of a website, ex: `0 */15 * * * *` every 15 minutes, like [`crate::website::run_cron`] with a random
+/// jitter, an overlap policy and reports. Each run is the [`Job`] of the website so the
+/// [`crate::configuration::Configuration::cron_type`] picks a crawl or a scrape, and the hooks receive the snapshot of the
+/// run with the diff against the previous run. The website keeps its state between the runs so the
+/// [`crate::configuration::RecrawlConfig`] applies to the runs after the first.
+pub struct CrawlScheduler {
+    /// The website crawled.
+    website: Website,
+    /// The cron schedule with seconds.
+    schedule: cron::Schedule,
+    /// The max random delay added to each run.
+    jitter: Duration,
+    /// What happens when a run is due while the previous run is crawling.
+    overlap: OverlapPolicy,
+    /// The hooks receiving the reports.
+    hooks: Vec<Arc<dyn ScheduleHook>>,
+}
+
+/// Stop a running scheduler.
+pub struct SchedulerHandle {
+    /// Wake the scheduler up to stop.
+    stop: Arc<Notify>,
+    /// The scheduler loop.
+    task: JoinHandle<()>,
+}
+
+impl SchedulerHandle {
+    /// Stop the scheduler and cancel the run crawling.
+    pub async fn stop(self) {
+        self.stop.notify_one();
+        let _ = self.task.await;
+    }
+}
+
+impl CrawlScheduler {
+    /// Crawl the website on the cron expression with the seconds field, ex: `0 0 * * * *` every hour.
+    pub fn new(website: Website, expression: &str) -> Result<Self, cron::error::Error> {
+        Ok(Self {
+            website,
+            schedule: cron::Schedule::from_str(expression)?,
+            jitter: Duration::ZERO,
+            overlap: OverlapPolicy::default(),
+            hooks: Vec::new(),
+        })
+    }
+
+    /// Crawl the website on the expression of its cron job set with [`Website::with_cron`].
+    pub fn from_website(website: Website) -> Result<Self, cron::error::Error> {
+        let expression = website.configuration.cron_str.clone();
+
+        Self::new(website, &expression)
+    }
+
+    /// Delay each run by a random time up to the jitter, so the runs of several schedulers do not hit the hosts at once.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set what happens when a run is due while the previous run is crawling.
+    pub fn with_overlap(mut self, overlap: OverlapPolicy) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    /// Add a hook receiving the report of each run.
+    pub fn with_hook(mut self, hook: impl ScheduleHook + 'static) -> Self {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+
+    /// Start the scheduler in the background.
+    pub fn start(self) -> SchedulerHandle {
+        let stop = Arc::new(Notify::new());
+
+        SchedulerHandle {
+            stop: stop.clone(),
+            task: tokio::spawn(self.run(stop)),
+        }
+    }
+
+    /// The time until the next run with the jitter.
+    fn delay_until(&self, next: chrono::DateTime<chrono::Utc>) -> Duration {
+        let jitter = match self.jitter.as_millis() as u64 {
+            0 => Duration::ZERO,
+            max => Duration::from_millis(fastrand::u64(0..=max)),
+        };
+
+        (next - chrono::Utc::now()).to_std().unwrap_or_default() + jitter
+    }
+
+    /// Run the crawls until the schedule ends or the scheduler is stopped.
+    async fn run(self, stop: Arc<Notify>) {
+        let mut website = Some(self.website.clone());
+        let mut previous: Option<CrawlSnapshot> = None;
+        let mut running: Option<RunningCrawl> = None;
+        let mut run = 0;
+
+        'schedule: for next in self.schedule.upcoming(chrono::Utc) {
+            let due = tokio::time::sleep(self.delay_until(next));
+            tokio::pin!(due);
+
+            loop {
+                let event = tokio::select! {
+                    _ = &mut due => SchedulerEvent::Due,
+                    result = finished(&mut running) => SchedulerEvent::Finished(result),
+                    _ = stop.notified() => SchedulerEvent::Stop,
+                };
+
+                match event {
+                    SchedulerEvent::Due => break,
+                    SchedulerEvent::Finished(result) => {
+                        if let Some(current) = running.take() {
+                            website = self.complete(current, result, &mut previous).await;
+                        }
+                    }
+                    SchedulerEvent::Stop => break 'schedule,
+                }
+            }
+
+            if let Some(mut current) = running.take() {
+                match self.overlap {
+                    OverlapPolicy::Skip => {
+                        log(
+                            "Scheduled run skipped, the previous run is crawling: ",
+                            run.to_string(),
+                        );
+                        running = Some(current);
+                        continue;
+                    }
+                    OverlapPolicy::Queue => {
+                        let result = (&mut current.task).await.ok();
+                        website = self.complete(current, result, &mut previous).await;
+                    }
+                    OverlapPolicy::CancelPrevious => {
+                        current.task.abort();
+                        website = self.complete(current, None, &mut previous).await;
+                    }
+                }
+            }
+
+            run += 1;
+            running = Some(start_crawl(
+                run,
+                website.take().unwrap_or_else(|| self.website.clone()),
+            ));
+        }
+
+        if let Some(current) = running.take() {
+            current.task.abort();
+        }
+    }
+
+    /// Report the run to the hooks. Returns the website of the run, `None` when the run was cancelled.
+    async fn complete(
+        &self,
+        current: RunningCrawl,
+        result: Option<FinishedCrawl>,
+        previous: &mut Option<CrawlSnapshot>,
+    ) -> Option<Website> {
+        let (website, snapshot, diff, missed) = match result {
+            Some(FinishedCrawl {
+                website,
+                snapshot,
+                missed,
+            }) => {
+                let diff = previous.as_ref().map(|previous| previous.diff(&snapshot));
+
+                if missed > 0 {
+                    log::warn!(
+                        "scheduled run {} missed {missed} pages, the diff is incomplete",
+                        current.run
+                    );
+                }
+
+                let mut next = snapshot.clone();
+
+                if let Some(previous) = previous.as_ref() {
+                    for (url, page) in previous.pages.iter() {
+                        if next.visited.contains(url) && !next.pages.contains_key(url) {
+                            next.pages.insert(url.clone(), page.clone());
+                        }
+                    }
+                }
+
+                *previous = Some(next);
+                (Some(website), snapshot, diff, missed)
+            }
+            _ => (
+                None,
+                CrawlSnapshot::new(self.website.get_url().inner()),
+                None,
+                0,
+            ),
+        };
+
+        let report = ScheduledRun {
+            run: current.run,
+            started_at: current.started_at,
+            duration: current.started.elapsed(),
+            cancelled: website.is_none(),
+            missed_pages: missed,
+            snapshot,
+            diff,
+        };
+
+        for hook in &self.hooks {
+            hook.on_run(&report).await;
+        }
+
+        website
+    }
+}
+
+/// The result of the running crawl, never ready without one.
+async fn finished(running: &mut Option<RunningCrawl>) -> Option<FinishedCrawl> {
+    match running {
+        Some(current) => (&mut current.task).await.ok(),
+        _ => std::future::pending().await,
+    }
+}
+
+/// Run the job of the website in the background recording the pages sent to the subscribers. The pages the recorder
+/// lags behind on are counted.
+fn start_crawl(run: u64, mut website: Website) -> RunningCrawl {
+    RunningCrawl {
+        run,
+        started_at: SystemTime::now(),
+        started: Instant::now(),
+        task: tokio::spawn(async move {
+            let recorder = website.subscribe(0).map(|mut rx| {
+                let url = website.get_url().inner().to_string();
+
+                tokio::spawn(async move {
+                    let mut snapshot = CrawlSnapshot::new(&url);
+                    let mut missed = 0;
+
+                    loop {
+                        match rx.recv().await {
+                            Ok(page) => snapshot.record(&page),
+                            Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                                missed += skipped;
+                            }
+                            _ => break,
+                        }
+                    }
+
+                    (snapshot, missed)
+                })
+            });
+
+            website.handle().await;
+            website.unsubscribe();
+
+            let mut snapshot = website.snapshot();
+            let mut missed = 0;
+
+            if let Some(recorder) = recorder {
+                if let Ok((recorded, skipped)) = recorder.await {
+                    snapshot.pages.extend(recorded.pages);
+                    missed = skipped;
+                }
+            }
+
+            FinishedCrawl {
+                website,
+                snapshot,
+                missed,
+            }
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordRuns(Mutex<Vec<(u64, bool, bool)>>);
+
+    #[async_trait::async_trait]
+    impl ScheduleHook for Arc<RecordRuns> {
+        async fn on_run(&self, run: &ScheduledRun) {
+            self.0
+                .lock()
+                .unwrap()
+                .push((run.run, run.cancelled, run.diff.is_some()));
+            assert_eq!(run.missed_pages, 0);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_expression() {
+        assert!(CrawlScheduler::new(Website::new("https://example.com"), "every day").is_err());
+
+        let mut website = Website::new("https://example.com");
+        website.with_cron("0 0 * * * *", crate::website::CronType::Crawl);
+        assert!(CrawlScheduler::from_website(website).is_ok());
+    }
+
+    #[tokio::test]
+    async fn reports_runs_with_diff() {
+        let runs = Arc::new(RecordRuns::default());
+        let mut website = Website::new("https://choosealicense.com");
+        website.with_limit(1);
+
+        let handle = CrawlScheduler::new(website, "* * * * * *")
+            .unwrap()
+            .with_overlap(OverlapPolicy::Queue)
+            .with_hook(runs.clone())
+            .start();
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        handle.stop().await;
+
+        let runs = runs.0.lock().unwrap();
+        assert!(runs.len() >= 2, "{:?}", runs);
+        assert_eq!(runs[0], (1, false, false));
+        assert_eq!(runs[1], (2, false, true));
+    }
+}
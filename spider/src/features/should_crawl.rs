@@ -0,0 +1,334 @@
+use crate::utils::frontier::Frontier;
+use case_insensitive_string::CaseInsensitiveString;
+use hashbrown::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use url::Url;
+
+/// The fetcher of a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FetchStrategy {
+    /// Fetch the html over HTTP without rendering it.
+    Http,
+    /// Render the page in Chrome. Requires the `chrome` flag, the HTTP crawls without a browser fetch the page over HTTP.
+    Chrome,
+    /// Fetch the html over HTTP and render the page in Chrome when it is an empty app shell or a javascript challenge.
+    Smart,
+}
+
+/// How the crawl handles a discovered url.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrawlDecision {
+    /// Add the url to the frontier.
+    pub crawl: bool,
+    /// The score of the url in the frontier in place of the score of the ordering, the highest first.
+    pub priority: Option<f64>,
+    /// The fetcher of the url in place of the fetcher of the crawl.
+    pub fetch: Option<FetchStrategy>,
+}
+
+impl Default for CrawlDecision {
+    fn default() -> Self {
+        Self::crawl()
+    }
+}
+
+impl CrawlDecision {
+    /// Crawl the url as configured.
+    pub fn crawl() -> Self {
+        Self {
+            crawl: true,
+            priority: None,
+            fetch: None,
+        }
+    }
+
+    /// Do not crawl the url.
+    pub fn skip() -> Self {
+        Self {
+            crawl: false,
+            ..Self::crawl()
+        }
+    }
+
+    /// Order the url by the priority, the highest first.
+    pub fn with_priority(mut self, priority: f64) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Fetch the url with the fetcher.
+    pub fn with_fetch(mut self, fetch: FetchStrategy) -> Self {
+        self.fetch = Some(fetch);
+        self
+    }
+}
+
+/// The future of a [`CrawlDecision`].
+pub type CrawlDecisionFuture = Pin<Box<dyn Future<Output = CrawlDecision> + Send>>;
+
+/// The predicate calls running at once by default.
+const DEFAULT_CONCURRENCY: usize = 16;
+
+/// An async predicate deciding if a discovered url enters the frontier, given the url, its depth and the page it was
+/// found on. The predicate runs once for each url the whitelist and blacklist allow that was not found before, the
+/// decisions are kept for the crawl.
+#[derive(Clone)]
+pub struct OnShouldCrawl {
+    /// The predicate.
+    predicate: Arc<dyn Fn(Url, usize, Option<Url>) -> CrawlDecisionFuture + Send + Sync>,
+    /// The predicate calls running at once.
+    concurrency: usize,
+}
+
+impl OnShouldCrawl {
+    /// A predicate from the async function.
+    pub fn new<F, Fut>(predicate: F) -> Self
+    where
+        F: Fn(Url, usize, Option<Url>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = CrawlDecision> + Send + 'static,
+    {
+        Self {
+            predicate: Arc::new(move |url, depth, referrer| {
+                Box::pin(predicate(url, depth, referrer))
+            }),
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Cap the predicate calls running at once, defaults to 16.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// The decision for the url.
+    pub async fn decide(&self, url: Url, depth: usize, referrer: Option<Url>) -> CrawlDecision {
+        (self.predicate)(url, depth, referrer).await
+    }
+
+    /// Ask the predicate about the links found on the referrer page, up to the concurrency limit at once. The skipped
+    /// links are removed and the decisions of the links asked are returned. The links that are not valid urls are kept.
+    pub(crate) async fn filter(
+        &self,
+        referrer: &str,
+        depth: usize,
+        links: &mut HashSet<CaseInsensitiveString>,
+    ) -> Vec<(CaseInsensitiveString, CrawlDecision)> {
+        let referrer = Url::parse(referrer).ok();
+        let mut set = tokio::task::JoinSet::new();
+        let mut decisions = Vec::with_capacity(links.len());
+
+        for link in links.iter() {
+            if let Ok(url) = Url::parse(link.inner()) {
+                if set.len() >= self.concurrency {
+                    if let Some(Ok(decision)) = set.join_next().await {
+                        decisions.push(decision);
+                    }
+                }
+                let decision = (self.predicate)(url, depth, referrer.clone());
+                let link = link.clone();
+                set.spawn(async move { (link, decision.await) });
+            }
+        }
+
+        while let Some(result) = set.join_next().await {
+            if let Ok(decision) = result {
+                decisions.push(decision);
+            }
+        }
+
+        for (link, decision) in decisions.iter() {
+            if !decision.crawl {
+                links.remove(link);
+            }
+        }
+
+        decisions
+    }
+}
+
+impl std::fmt::Debug for OnShouldCrawl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("OnShouldCrawl").finish()
+    }
+}
+
+impl PartialEq for OnShouldCrawl {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.predicate, &other.predicate) && self.concurrency == other.concurrency
+    }
+}
+
+/// Remove the links the predicate skipped.
+pub(crate) fn remove_skipped(
+    links: &mut HashSet<CaseInsensitiveString>,
+    decisions: &[(CaseInsensitiveString, CrawlDecision)],
+) {
+    let skipped: HashSet<&CaseInsensitiveString> = decisions
+        .iter()
+        .filter(|(_, decision)| !decision.crawl)
+        .map(|(link, _)| link)
+        .collect();
+
+    if !skipped.is_empty() {
+        links.retain(|link| !skipped.contains(link));
+    }
+}
+
+/// Record the links found on the referrer page in the frontier, after removing the links the predicate skips. Only the
+/// allowed links unknown to the frontier are asked, the links skipped before are removed without asking again.
+pub(crate) async fn discover(
+    frontier: &Frontier,
+    on_should_crawl: &Option<OnShouldCrawl>,
+    referrer: &str,
+    links: &mut HashSet<CaseInsensitiveString>,
+    allowed: impl Fn(&CaseInsensitiveString) -> bool,
+) {
+    match on_should_crawl {
+        Some(on_should_crawl) if !links.is_empty() => {
+            links.retain(|link| !frontier.is_rejected(link));
+
+            let mut unknown: HashSet<CaseInsensitiveString> = links
+                .iter()
+                .filter(|link| !frontier.contains(link) && allowed(link))
+                .cloned()
+                .collect();
+            let decisions = on_should_crawl
+                .filter(referrer, frontier.depth(referrer) + 1, &mut unknown)
+                .await;
+
+            remove_skipped(links, &decisions);
+            frontier.discovered(referrer, links);
+            frontier.decided(decisions);
+        }
+        _ => frontier.discovered(referrer, links),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn filters_discovered_links() {
+        let on_should_crawl = OnShouldCrawl::new(|url, depth, referrer| async move {
+            assert_eq!(depth, 1);
+            assert!(referrer.is_some());
+
+            match url.path() {
+                "/private" => CrawlDecision::skip(),
+                "/app" => CrawlDecision::crawl().with_fetch(FetchStrategy::Chrome),
+                "/news" => CrawlDecision::crawl().with_priority(10.0),
+                _ => CrawlDecision::crawl(),
+            }
+        });
+
+        let frontier = Frontier::new(None, 4).with_discovered(true);
+        let mut links: HashSet<CaseInsensitiveString> = [
+            "https://example.com/private",
+            "https://example.com/app",
+            "https://example.com/news",
+            "https://example.com/about",
+        ]
+        .iter()
+        .map(|link| CaseInsensitiveString::new(link))
+        .collect();
+
+        discover(
+            &frontier,
+            &Some(on_should_crawl),
+            "https://example.com/",
+            &mut links,
+            |_| true,
+        )
+        .await;
+
+        assert_eq!(links.len(), 3);
+        assert!(!links.contains(&CaseInsensitiveString::new("https://example.com/private")));
+        assert_eq!(
+            frontier.fetch_strategy("https://example.com/app"),
+            Some(FetchStrategy::Chrome)
+        );
+        assert_eq!(frontier.fetch_strategy("https://example.com/about"), None);
+
+        let batch = frontier.next_batch(&mut links);
+        assert_eq!(batch[0].inner(), "https://example.com/news");
+    }
+
+    #[tokio::test]
+    async fn asks_allowed_links_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let asked = Arc::new(AtomicUsize::new(0));
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let on_should_crawl = {
+            let asked = asked.clone();
+            let running = running.clone();
+            let max_running = max_running.clone();
+
+            OnShouldCrawl::new(move |url, _depth, _referrer| {
+                let running = running.clone();
+                let max_running = max_running.clone();
+                asked.fetch_add(1, Ordering::SeqCst);
+
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now, Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+
+                    if url.path().starts_with("/private") {
+                        CrawlDecision::skip()
+                    } else {
+                        CrawlDecision::crawl()
+                    }
+                }
+            })
+            .with_concurrency(2)
+        };
+        let on_should_crawl = Some(on_should_crawl);
+        let allowed = |link: &CaseInsensitiveString| !link.inner().contains("/blocked");
+
+        let frontier = Frontier::new(None, 4).with_discovered(true);
+        let page_links = || -> HashSet<CaseInsensitiveString> {
+            (0..8)
+                .map(|i| format!("https://example.com/private/{}", i))
+                .chain((0..8).map(|i| format!("https://example.com/docs/{}", i)))
+                .chain(std::iter::once("https://example.com/blocked".to_string()))
+                .map(CaseInsensitiveString::from)
+                .collect()
+        };
+
+        let mut links = page_links();
+        discover(
+            &frontier,
+            &on_should_crawl,
+            "https://example.com/",
+            &mut links,
+            allowed,
+        )
+        .await;
+
+        assert_eq!(asked.load(Ordering::SeqCst), 16);
+        assert!(max_running.load(Ordering::SeqCst) <= 2);
+        assert_eq!(links.len(), 9);
+        assert!(frontier.is_rejected(&CaseInsensitiveString::new("https://example.com/private/0")));
+
+        let mut links = page_links();
+        discover(
+            &frontier,
+            &on_should_crawl,
+            "https://example.com/docs/0",
+            &mut links,
+            allowed,
+        )
+        .await;
+
+        assert_eq!(asked.load(Ordering::SeqCst), 16);
+        assert_eq!(links.len(), 9);
+    }
+}
@@ -0,0 +1,413 @@
+use crate::features::extractor::ExtractedValue;
+use crate::page::Page;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+#[cfg(feature = "sink_kafka")]
+use std::time::Duration;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The error of a sink.
+#[derive(Debug)]
+pub enum SinkError {
+    /// The record could not be serialized.
+    Serialize(serde_json::Error),
+    /// The record could not be delivered.
+    Delivery(String),
+}
+
+impl std::fmt::Display for SinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SinkError::Serialize(err) => write!(f, "sink serialize error: {err}"),
+            SinkError::Delivery(err) => write!(f, "sink delivery error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SinkError {}
+
+impl From<serde_json::Error> for SinkError {
+    fn from(err: serde_json::Error) -> Self {
+        SinkError::Serialize(err)
+    }
+}
+
+/// The page streamed to a sink.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PageRecord {
+    /// The url of the page.
+    pub url: String,
+    /// The status code of the response.
+    pub status_code: u16,
+    /// The error of the request if any.
+    pub error_status: Option<String>,
+    /// The final destination of the page if redirects were performed.
+    pub final_redirect_destination: Option<String>,
+    /// The html of the page. Unset when the sink skips the content.
+    pub html: Option<String>,
+    /// The response headers. This is empty without the `headers` flag.
+    pub headers: Vec<(String, String)>,
+    /// The links found on the page when the crawl returns the page links.
+    pub links: Vec<String>,
+    /// The third party requests blocked by the consent mode of the browser.
+    pub blocked_requests: Vec<String>,
+    /// The text of the title element of the page.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// The links followed from the start page to the page.
+    #[serde(default)]
+    pub depth: usize,
+    /// The values of the fields of the extractor of the crawl.
+    #[serde(default)]
+    pub extracted: Option<BTreeMap<String, ExtractedValue>>,
+    /// The milliseconds since the page was fetched. This is unset without the `time` flag.
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    /// The time the record was made in milliseconds since the unix epoch.
+    #[serde(default)]
+    pub crawled_at: u64,
+}
+
+impl PageRecord {
+    /// The record of the page. The html is left out when `content` is false.
+    pub fn new(page: &Page, content: bool) -> Self {
+        Self {
+            url: page.get_url().to_string(),
+            status_code: page.status_code.as_u16(),
+            error_status: page.error_status.clone(),
+            final_redirect_destination: page.final_redirect_destination.clone(),
+            html: if content { Some(page.get_html()) } else { None },
+            #[cfg(feature = "headers")]
+            headers: page
+                .headers
+                .iter()
+                .flatten()
+                .map(|(name, value)| {
+                    (
+                        name.to_string(),
+                        String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                    )
+                })
+                .collect(),
+            #[cfg(not(feature = "headers"))]
+            headers: Vec::new(),
+            links: page
+                .page_links
+                .iter()
+                .flat_map(|links| links.iter())
+                .map(|link| link.inner().to_string())
+                .collect(),
+            #[cfg(feature = "chrome")]
+            blocked_requests: page
+                .blocked_third_party_requests
+                .clone()
+                .unwrap_or_default(),
+            #[cfg(not(feature = "chrome"))]
+            blocked_requests: Vec::new(),
+            title: std::str::from_utf8(page.get_html_bytes_u8())
+                .ok()
+                .and_then(page_title),
+            depth: page.depth(),
+            extracted: page.extracted.as_ref().map(|extracted| {
+                extracted
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect()
+            }),
+            #[cfg(all(feature = "time", not(feature = "decentralized")))]
+            duration_ms: Some(page.get_duration_elasped().as_millis() as u64),
+            #[cfg(not(all(feature = "time", not(feature = "decentralized"))))]
+            duration_ms: None,
+            crawled_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+        }
+    }
+
+    /// The record as json.
+    pub fn to_json(&self) -> Result<Vec<u8>, SinkError> {
+        Ok(serde_json::to_vec(self)?)
+    }
+}
+
+/// The position of the ascii tag in the html ignoring the case.
+fn find_tag(html: &str, tag: &str) -> Option<usize> {
+    html.as_bytes()
+        .windows(tag.len())
+        .position(|window| window.eq_ignore_ascii_case(tag.as_bytes()))
+}
+
+/// The text of the title element of the html with the whitespace collapsed and the common entities decoded.
+pub fn page_title(html: &str) -> Option<String> {
+    let start = find_tag(html, "<title")?;
+    let start = start + html[start..].find('>')? + 1;
+    let end = start + find_tag(&html[start..], "</title")?;
+
+    let title = html[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+
+    if title.is_empty() {
+        None
+    } else {
+        Some(title)
+    }
+}
+
+/// Run the blocking write of a file sink on the blocking thread pool, with the output locked.
+#[cfg(feature = "rotation")]
+pub(crate) async fn write_blocking<T: Send + 'static>(
+    output: &Arc<std::sync::Mutex<T>>,
+    write: impl FnOnce(&mut T) -> Result<(), SinkError> + Send + 'static,
+) -> Result<(), SinkError> {
+    let output = output.clone();
+
+    tokio::task::spawn_blocking(move || match output.lock() {
+        Ok(mut output) => write(&mut output),
+        Err(err) => Err(SinkError::Delivery(err.to_string())),
+    })
+    .await
+    .map_err(|err| SinkError::Delivery(err.to_string()))?
+}
+
+/// A destination the crawled pages are streamed to as they complete instead of being kept in memory. The page task
+/// waits for the record to be accepted before it releases its crawl permit, so a slow sink slows the crawl down.
+#[async_trait::async_trait]
+pub trait Sink: Send + Sync {
+    /// Deliver the record of a page.
+    async fn send(&self, record: &PageRecord) -> Result<(), SinkError>;
+
+    /// Deliver the records buffered. Called once the crawl ends.
+    async fn flush(&self) -> Result<(), SinkError> {
+        Ok(())
+    }
+}
+
+/// The sink of a crawl.
+#[derive(Clone)]
+pub struct PageSink {
+    /// The destination of the records.
+    pub sink: Arc<dyn Sink>,
+    /// Send the html of the pages with the records.
+    pub content: bool,
+}
+
+impl PageSink {
+    /// Stream the pages with their html to the sink.
+    pub fn new(sink: impl Sink + 'static) -> Self {
+        Self {
+            sink: Arc::new(sink),
+            content: true,
+        }
+    }
+
+    /// Set whether the html of the pages is sent with the records.
+    pub fn with_content(mut self, content: bool) -> Self {
+        self.content = content;
+        self
+    }
+
+    /// Send the page to the sink. The errors are logged and the crawl goes on.
+    pub(crate) async fn send(&self, page: &Page) {
+        if let Err(err) = self.sink.send(&PageRecord::new(page, self.content)).await {
+            crate::utils::log("sink error: ", err.to_string());
+        }
+    }
+
+    /// Flush the sink at the end of the crawl.
+    pub(crate) async fn flush(&self) {
+        if let Err(err) = self.sink.flush().await {
+            crate::utils::log("sink error: ", err.to_string());
+        }
+    }
+}
+
+impl std::fmt::Debug for PageSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PageSink")
+            .field("content", &self.content)
+            .finish()
+    }
+}
+
+impl PartialEq for PageSink {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.sink, &other.sink) && self.content == other.content
+    }
+}
+
+/// Stream the records to a Kafka topic keyed by the page url.
+#[cfg(feature = "sink_kafka")]
+pub struct KafkaSink {
+    /// The producer.
+    producer: rdkafka::producer::FutureProducer,
+    /// The topic of the records.
+    topic: String,
+    /// The time a record waits for room in the producer queue.
+    queue_timeout: Duration,
+}
+
+#[cfg(feature = "sink_kafka")]
+impl KafkaSink {
+    /// Produce to the topic of the brokers, a comma separated list of `host:port`.
+    pub fn new(brokers: &str, topic: &str) -> Result<Self, SinkError> {
+        let mut config = rdkafka::ClientConfig::new();
+        config.set("bootstrap.servers", brokers);
+        Self::from_config(&config, topic)
+    }
+
+    /// Produce to the topic with the client config, ex: to set the credentials or the compression.
+    pub fn from_config(config: &rdkafka::ClientConfig, topic: &str) -> Result<Self, SinkError> {
+        Ok(Self {
+            producer: config
+                .create()
+                .map_err(|err| SinkError::Delivery(err.to_string()))?,
+            topic: topic.into(),
+            queue_timeout: Duration::from_secs(30),
+        })
+    }
+
+    /// Set the time a record waits for room in the producer queue before it fails.
+    pub fn with_queue_timeout(mut self, queue_timeout: Duration) -> Self {
+        self.queue_timeout = queue_timeout;
+        self
+    }
+}
+
+#[cfg(feature = "sink_kafka")]
+#[async_trait::async_trait]
+impl Sink for KafkaSink {
+    async fn send(&self, record: &PageRecord) -> Result<(), SinkError> {
+        let payload = record.to_json()?;
+
+        self.producer
+            .send(
+                rdkafka::producer::FutureRecord::to(&self.topic)
+                    .key(record.url.as_str())
+                    .payload(payload.as_slice()),
+                rdkafka::util::Timeout::After(self.queue_timeout),
+            )
+            .await
+            .map(|_| ())
+            .map_err(|(err, _)| SinkError::Delivery(err.to_string()))
+    }
+
+    async fn flush(&self) -> Result<(), SinkError> {
+        use rdkafka::producer::Producer;
+        let producer = self.producer.clone();
+        let timeout = self.queue_timeout;
+
+        tokio::task::spawn_blocking(move || producer.flush(rdkafka::util::Timeout::After(timeout)))
+            .await
+            .map_err(|err| SinkError::Delivery(err.to_string()))?
+            .map_err(|err| SinkError::Delivery(err.to_string()))
+    }
+}
+
+/// Publish the records to a NATS subject.
+#[cfg(feature = "sink_nats")]
+pub struct NatsSink {
+    /// The client.
+    client: async_nats::Client,
+    /// The subject of the records.
+    subject: String,
+}
+
+#[cfg(feature = "sink_nats")]
+impl NatsSink {
+    /// Connect to the NATS server ex: `nats://127.0.0.1:4222` and publish to the subject.
+    pub async fn connect(url: &str, subject: &str) -> Result<Self, SinkError> {
+        Ok(Self::new(
+            async_nats::connect(url)
+                .await
+                .map_err(|err| SinkError::Delivery(err.to_string()))?,
+            subject,
+        ))
+    }
+
+    /// Publish to the subject with a connected client.
+    pub fn new(client: async_nats::Client, subject: &str) -> Self {
+        Self {
+            client,
+            subject: subject.into(),
+        }
+    }
+}
+
+#[cfg(feature = "sink_nats")]
+#[async_trait::async_trait]
+impl Sink for NatsSink {
+    async fn send(&self, record: &PageRecord) -> Result<(), SinkError> {
+        self.client
+            .publish(self.subject.clone(), record.to_json()?.into())
+            .await
+            .map_err(|err| SinkError::Delivery(err.to_string()))
+    }
+
+    async fn flush(&self) -> Result<(), SinkError> {
+        self.client
+            .flush()
+            .await
+            .map_err(|err| SinkError::Delivery(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page::build;
+    use crate::utils::PageResponse;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemorySink(Mutex<Vec<PageRecord>>);
+
+    #[async_trait::async_trait]
+    impl Sink for MemorySink {
+        async fn send(&self, record: &PageRecord) -> Result<(), SinkError> {
+            self.0.lock().unwrap().push(record.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn sink_records() {
+        let memory = Arc::new(MemorySink::default());
+        let sink = PageSink {
+            sink: memory.clone(),
+            content: false,
+        };
+        let page = build(
+            "https://example.com",
+            PageResponse {
+                content: Some(Box::new("<html></html>".into())),
+                status_code: reqwest::StatusCode::NOT_FOUND,
+                ..Default::default()
+            },
+        );
+
+        sink.send(&page).await;
+        sink.flush().await;
+
+        let records = memory.0.lock().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].url, "https://example.com");
+        assert_eq!(records[0].status_code, 404);
+        assert_eq!(records[0].html, None);
+        assert_eq!(records[0].title, None);
+        assert_eq!(
+            page_title("<head><TITLE data-x>\n  Tom &amp; Jerry\n</TITLE></head>").as_deref(),
+            Some("Tom & Jerry")
+        );
+
+        let record: PageRecord = serde_json::from_slice(&records[0].to_json().unwrap()).unwrap();
+        assert_eq!(record, records[0]);
+    }
+}
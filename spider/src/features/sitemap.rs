@@ -0,0 +1,209 @@
+use crate::configuration::SitemapConfig;
+use crate::utils::log;
+use crate::Client;
+use chrono::{DateTime, FixedOffset, Utc};
+use sitemap::reader::{SiteMapEntity, SiteMapReader};
+use sitemap::structs::{Location, Priority};
+use std::io::Read;
+
+/// A page url listed in a sitemap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapLink {
+    /// The url of the page.
+    pub url: String,
+    /// The `lastmod` date of the page.
+    pub lastmod: Option<DateTime<FixedOffset>>,
+    /// The `priority` of the page from 0.0 to 1.0.
+    pub priority: Option<f32>,
+}
+
+impl SitemapLink {
+    /// The fetch priority: the sitemap priority or 0.5 when unset, plus the boost of the config when the page was modified within the fresh window.
+    pub fn score(&self, config: &SitemapConfig, now: DateTime<Utc>) -> f32 {
+        let fresh = self.lastmod.is_some_and(|lastmod| {
+            now.signed_duration_since(lastmod)
+                .to_std()
+                .map_or(true, |age| age <= config.fresh_within)
+        });
+
+        self.priority.unwrap_or(0.5) + if fresh { config.priority_boost } else { 0.0 }
+    }
+}
+
+/// The entries of a sitemap or sitemap index.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SitemapDocument {
+    /// The page urls of a sitemap.
+    pub links: Vec<SitemapLink>,
+    /// The nested sitemaps of a sitemap index.
+    pub sitemaps: Vec<String>,
+}
+
+/// Decompress a gzipped sitemap like `sitemap.xml.gz`. Plain sitemaps are returned as is.
+pub fn decode_sitemap(bytes: &[u8]) -> std::borrow::Cow<'_, [u8]> {
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut decoded = Vec::new();
+
+        match flate2::read::MultiGzDecoder::new(bytes).read_to_end(&mut decoded) {
+            Ok(_) => decoded.into(),
+            Err(err) => {
+                log("sitemap gzip error: ", err.to_string());
+                bytes.into()
+            }
+        }
+    } else {
+        bytes.into()
+    }
+}
+
+/// Parse the urls and nested sitemaps of a plain or gzipped sitemap.
+pub fn parse_sitemap(bytes: &[u8]) -> SitemapDocument {
+    let mut document = SitemapDocument::default();
+
+    for entity in SiteMapReader::new(decode_sitemap(bytes).as_ref()) {
+        match entity {
+            SiteMapEntity::Url(url_entry) => {
+                if let Location::Url(url) = url_entry.loc {
+                    document.links.push(SitemapLink {
+                        url: url.into(),
+                        lastmod: url_entry.lastmod.get_time(),
+                        priority: match url_entry.priority {
+                            Priority::Value(priority) => Some(priority),
+                            _ => None,
+                        },
+                    });
+                }
+            }
+            SiteMapEntity::SiteMap(sitemap_entry) => {
+                if let Location::Url(url) = sitemap_entry.loc {
+                    document.sitemaps.push(url.into());
+                }
+            }
+            SiteMapEntity::Err(err) => log("incorrect sitemap error: ", err.msg()),
+        }
+    }
+
+    document
+}
+
+/// Order the sitemap urls by descending score then freshest `lastmod` when the config prioritizes them.
+pub fn order_sitemap_links(links: &mut [SitemapLink], config: &SitemapConfig) {
+    if config.prioritize_lastmod {
+        let now = Utc::now();
+
+        links.sort_by(|a, b| {
+            b.score(config, now)
+                .total_cmp(&a.score(config, now))
+                .then_with(|| b.lastmod.cmp(&a.lastmod))
+        });
+    }
+}
+
+/// The frontier priority of the sitemap urls by their score, none when the config does not prioritize them.
+pub fn sitemap_priorities(links: &[SitemapLink], config: &SitemapConfig) -> Vec<(String, f64)> {
+    if config.prioritize_lastmod {
+        let now = Utc::now();
+
+        links
+            .iter()
+            .map(|link| (link.url.clone(), f64::from(link.score(config, now))))
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Fetch and parse a sitemap, logging the errors.
+pub async fn fetch_sitemap(client: &Client, url: &str) -> Option<SitemapDocument> {
+    match client.get(url).send().await {
+        Ok(response) => match response.bytes().await {
+            Ok(bytes) => Some(parse_sitemap(&bytes)),
+            Err(err) => {
+                log("http parse error: ", err.to_string());
+                None
+            }
+        },
+        Err(err) => {
+            log("http network error: ", err.to_string());
+            None
+        }
+    }
+}
+
+/// Fetch a sitemap and the nested sitemaps of its indexes up to the max depth of the config, returning the ordered page urls.
+pub async fn collect_sitemap_links(
+    client: &Client,
+    url: &str,
+    config: &SitemapConfig,
+) -> Vec<SitemapLink> {
+    let mut links = Vec::new();
+    let mut visited = hashbrown::HashSet::new();
+    let mut sitemaps = vec![url.to_string()];
+    let mut depth = 0;
+
+    while !sitemaps.is_empty() && depth <= config.max_depth {
+        for sitemap_url in std::mem::take(&mut sitemaps) {
+            if visited.insert(sitemap_url.clone()) {
+                if let Some(document) = fetch_sitemap(client, &sitemap_url).await {
+                    links.extend(document.links);
+                    sitemaps.extend(document.sitemaps);
+                }
+            }
+        }
+        depth += 1;
+    }
+
+    order_sitemap_links(&mut links, config);
+
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const SITEMAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>https://choosealicense.com/old/</loc><lastmod>2015-01-01</lastmod><priority>0.6</priority></url>
+  <url><loc>https://choosealicense.com/new/</loc><lastmod>2099-01-01</lastmod><priority>0.5</priority></url>
+  <url><loc>https://choosealicense.com/about/</loc><priority>0.8</priority></url>
+</urlset>"#;
+
+    #[test]
+    fn parses_gzipped_sitemaps() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(SITEMAP.as_bytes()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let document = parse_sitemap(&gzipped);
+        assert_eq!(document, parse_sitemap(SITEMAP.as_bytes()));
+        assert_eq!(document.links.len(), 3);
+
+        let index = parse_sitemap(
+            br#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <sitemap><loc>https://choosealicense.com/sitemap-1.xml.gz</loc></sitemap>
+</sitemapindex>"#,
+        );
+        assert_eq!(
+            index.sitemaps,
+            vec!["https://choosealicense.com/sitemap-1.xml.gz".to_string()]
+        );
+    }
+
+    #[test]
+    fn orders_fresh_links_first() {
+        let mut links = parse_sitemap(SITEMAP.as_bytes()).links;
+        order_sitemap_links(&mut links, &SitemapConfig::default());
+
+        let urls: Vec<&str> = links.iter().map(|link| link.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://choosealicense.com/new/",
+                "https://choosealicense.com/about/",
+                "https://choosealicense.com/old/"
+            ]
+        );
+    }
+}
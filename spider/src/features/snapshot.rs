@@ -0,0 +1,161 @@
+use crate::page::Page;
+use crate::utils::fnv1a;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The version of the snapshot format written by this crate.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// A snapshot of a crawl. The snapshot holds the visited set, the crawl metadata and the content hashes of the pages. Page bodies are never stored.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CrawlSnapshot {
+    /// The snapshot format version.
+    pub version: u32,
+    /// The start url of the crawl.
+    pub url: String,
+    /// The crawl id of the website.
+    #[serde(default)]
+    pub crawl_id: String,
+    /// The unix time in seconds the snapshot was taken.
+    #[serde(default)]
+    pub created_at: u64,
+    /// The links visited sorted.
+    #[serde(default)]
+    pub visited: BTreeSet<String>,
+    /// The metadata of the pages recorded keyed by url.
+    #[serde(default)]
+    pub pages: BTreeMap<String, SnapshotPage>,
+}
+
+/// The metadata of a page in a snapshot.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SnapshotPage {
+    /// The status code of the page.
+    pub status_code: u16,
+    /// The FNV-1a hash of the page content.
+    pub content_hash: u64,
+    /// The size of the page content in bytes.
+    pub content_length: usize,
+}
+
+/// The difference between two snapshots.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    /// The links only found in the newer snapshot.
+    pub added: Vec<String>,
+    /// The links only found in the older snapshot.
+    pub removed: Vec<String>,
+    /// The links recorded in both snapshots with a different status or content hash.
+    pub changed: Vec<String>,
+}
+
+/// Errors reading a snapshot.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The snapshot was written with a newer format version.
+    UnsupportedVersion(u32),
+    /// The snapshot could not be serialized or deserialized.
+    Json(serde_json::Error),
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::UnsupportedVersion(version) => write!(
+                f,
+                "snapshot version {version} is not supported, the latest version is {SNAPSHOT_VERSION}"
+            ),
+            SnapshotError::Json(e) => write!(f, "snapshot json error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(e: serde_json::Error) -> Self {
+        SnapshotError::Json(e)
+    }
+}
+
+impl CrawlSnapshot {
+    /// A new empty snapshot for the url.
+    pub fn new(url: &str) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            url: url.into(),
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    /// Record the metadata and content hash of a page. The page is added to the visited set.
+    pub fn record(&mut self, page: &Page) {
+        let content = page.get_html_bytes_u8();
+        let url = page.get_url().to_string();
+
+        self.pages.insert(
+            url.clone(),
+            SnapshotPage {
+                status_code: page.status_code.as_u16(),
+                content_hash: fnv1a(content),
+                content_length: content.len(),
+            },
+        );
+        self.visited.insert(url);
+    }
+
+    /// Has the page changed since the snapshot. Pages not recorded are treated as changed.
+    pub fn is_changed(&self, page: &Page) -> bool {
+        match self.pages.get(page.get_url()) {
+            Some(recorded) => {
+                recorded.status_code != page.status_code.as_u16()
+                    || recorded.content_hash != fnv1a(page.get_html_bytes_u8())
+            }
+            _ => true,
+        }
+    }
+
+    /// The changes from this snapshot to a newer one.
+    pub fn diff(&self, newer: &CrawlSnapshot) -> SnapshotDiff {
+        let added = newer.visited.difference(&self.visited).cloned().collect();
+        let removed = self.visited.difference(&newer.visited).cloned().collect();
+        let changed = self
+            .pages
+            .iter()
+            .filter_map(|(url, page)| match newer.pages.get(url) {
+                Some(newer_page) if newer_page != page => Some(url.clone()),
+                _ => None,
+            })
+            .collect();
+
+        SnapshotDiff {
+            added,
+            removed,
+            changed,
+        }
+    }
+
+    /// Serialize the snapshot to json.
+    pub fn to_json(&self) -> Result<String, SnapshotError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Deserialize a snapshot from json. Snapshots written with a newer format version are rejected.
+    pub fn from_json(json: &str) -> Result<Self, SnapshotError> {
+        let snapshot: CrawlSnapshot = serde_json::from_str(json)?;
+        snapshot.check_version()?;
+        Ok(snapshot)
+    }
+
+    /// Check the snapshot can be read by this version of the crate.
+    pub fn check_version(&self) -> Result<(), SnapshotError> {
+        if self.version > SNAPSHOT_VERSION {
+            Err(SnapshotError::UnsupportedVersion(self.version))
+        } else {
+            Ok(())
+        }
+    }
+}
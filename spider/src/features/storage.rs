@@ -0,0 +1,479 @@
+use crate::page::Page;
+use crate::utils::fnv1a;
+use bytes::Bytes;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// The file holding the url of the stored page.
+const URL_FILE: &str = "url.txt";
+
+/// An artifact of a crawled page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Artifact {
+    /// The raw html of the page.
+    Html,
+    /// The screenshot of the page.
+    Screenshot,
+    /// The HAR of the network requests of the page.
+    Har,
+    /// The console messages of the page, one per line.
+    Console,
+    /// The performance metrics of the page as JSON.
+    Metrics,
+    /// The extracted fields, article, chunks and LLM output of the page as JSON.
+    Extraction,
+}
+
+impl Artifact {
+    /// All the artifacts in the storage order.
+    pub const ALL: [Artifact; 6] = [
+        Artifact::Html,
+        Artifact::Screenshot,
+        Artifact::Har,
+        Artifact::Console,
+        Artifact::Metrics,
+        Artifact::Extraction,
+    ];
+
+    /// The file name of the artifact in the directory of the page.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Artifact::Html => "page.html",
+            Artifact::Screenshot => "screenshot.png",
+            Artifact::Har => "network.har",
+            Artifact::Console => "console.log",
+            Artifact::Metrics => "metrics.json",
+            Artifact::Extraction => "extraction.json",
+        }
+    }
+}
+
+/// The artifacts of a crawled page persisted together with a single [`PageArtifacts::store`]. Each artifact is a file
+/// named by [`Artifact::file_name`] in the directory of the page, the layout is stable across releases.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageArtifacts {
+    /// The raw html of the page.
+    pub html: Option<Bytes>,
+    /// The screenshot of the page.
+    pub screenshot: Option<Bytes>,
+    /// The HAR of the network requests of the page.
+    pub har: Option<Bytes>,
+    /// The console messages of the page, one per line.
+    pub console: Option<Bytes>,
+    /// The performance metrics of the page as JSON.
+    pub metrics: Option<Bytes>,
+    /// The extraction output of the page as a JSON object with the `extracted`, `article`, `chunks` and `llm_extracted` keys set.
+    pub extraction: Option<Bytes>,
+}
+
+/// The extraction output of the page as JSON, none without output.
+fn extraction_json(page: &Page) -> Option<Bytes> {
+    let mut output = serde_json::Map::new();
+
+    if let Some(extracted) = &page.extracted {
+        output.insert("extracted".into(), serde_json::to_value(extracted).ok()?);
+    }
+    if let Some(article) = &page.article {
+        output.insert("article".into(), serde_json::to_value(article).ok()?);
+    }
+    if let Some(chunks) = &page.chunks {
+        output.insert("chunks".into(), serde_json::to_value(chunks).ok()?);
+    }
+    #[cfg(feature = "llm_extraction")]
+    if let Some(llm_extracted) = &page.llm_extracted {
+        output.insert("llm_extracted".into(), (**llm_extracted).clone());
+    }
+
+    if output.is_empty() {
+        None
+    } else {
+        serde_json::to_vec_pretty(&output).ok().map(Bytes::from)
+    }
+}
+
+impl PageArtifacts {
+    /// The artifacts of the page with its extraction output. The screenshot and the performance metrics are kept with the
+    /// `chrome` flag.
+    pub fn from_page(page: &Page) -> Self {
+        Self {
+            html: page.get_bytes().cloned(),
+            #[cfg(feature = "chrome")]
+            screenshot: page.screenshot_bytes.clone().map(Bytes::from),
+            #[cfg(not(feature = "chrome"))]
+            screenshot: None,
+            har: None,
+            console: None,
+            #[cfg(feature = "chrome")]
+            metrics: page
+                .performance_metrics
+                .as_ref()
+                .and_then(|metrics| serde_json::to_vec_pretty(metrics).ok())
+                .map(Bytes::from),
+            #[cfg(not(feature = "chrome"))]
+            metrics: None,
+            extraction: extraction_json(page),
+        }
+    }
+
+    /// Set the HAR of the network requests of the page.
+    pub fn with_har(mut self, har: impl Into<Bytes>) -> Self {
+        self.har = Some(har.into());
+        self
+    }
+
+    /// Set the console messages of the page.
+    pub fn with_console<I, S>(mut self, messages: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut console = String::new();
+
+        for message in messages {
+            console.push_str(message.as_ref());
+            console.push('\n');
+        }
+
+        self.console = Some(console.into());
+        self
+    }
+
+    /// Set the metrics of the page as JSON.
+    pub fn with_metrics(mut self, metrics: impl Into<Bytes>) -> Self {
+        self.metrics = Some(metrics.into());
+        self
+    }
+
+    /// Set the extraction output of the page as JSON.
+    pub fn with_extraction(mut self, extraction: impl Into<Bytes>) -> Self {
+        self.extraction = Some(extraction.into());
+        self
+    }
+
+    /// Store all the artifacts of the url in the store, replacing the artifacts stored before.
+    pub async fn store(&self, url: &str, store: &dyn PageStore) -> std::io::Result<()> {
+        store.put(url, self).await
+    }
+
+    /// The bytes of the artifact.
+    pub fn artifact(&self, artifact: Artifact) -> Option<&Bytes> {
+        match artifact {
+            Artifact::Html => self.html.as_ref(),
+            Artifact::Screenshot => self.screenshot.as_ref(),
+            Artifact::Har => self.har.as_ref(),
+            Artifact::Console => self.console.as_ref(),
+            Artifact::Metrics => self.metrics.as_ref(),
+            Artifact::Extraction => self.extraction.as_ref(),
+        }
+    }
+
+    /// Set the bytes of the artifact.
+    pub fn set_artifact(&mut self, artifact: Artifact, bytes: Option<Bytes>) {
+        match artifact {
+            Artifact::Html => self.html = bytes,
+            Artifact::Screenshot => self.screenshot = bytes,
+            Artifact::Har => self.har = bytes,
+            Artifact::Console => self.console = bytes,
+            Artifact::Metrics => self.metrics = bytes,
+            Artifact::Extraction => self.extraction = bytes,
+        }
+    }
+}
+
+/// The storage key of the url: the host followed by the hash of the url ex: `example.com/5b3e5c0a7d5b1c2f`.
+pub fn store_key(url: &str) -> String {
+    let host = match url::Url::parse(url) {
+        Ok(u) => u.host_str().unwrap_or("_").replace(':', "_"),
+        _ => "_".into(),
+    };
+
+    format!("{host}/{:016x}", fnv1a(url.as_bytes()))
+}
+
+/// A storage backend persisting the artifacts of the crawled pages by url.
+#[async_trait::async_trait]
+pub trait PageStore: Send + Sync {
+    /// Store the artifacts of the url, replacing the artifacts stored before.
+    async fn put(&self, url: &str, artifacts: &PageArtifacts) -> std::io::Result<()>;
+
+    /// Whether artifacts are stored for the url.
+    async fn exists(&self, url: &str) -> std::io::Result<bool>;
+
+    /// The artifacts stored for the url.
+    async fn get(&self, url: &str) -> std::io::Result<Option<PageArtifacts>>;
+
+    /// Store the artifacts of the crawled page.
+    async fn put_page(&self, page: &Page) -> std::io::Result<()> {
+        self.put(page.get_url(), &PageArtifacts::from_page(page))
+            .await
+    }
+}
+
+/// Store the artifacts in a directory per url under the root directory: `<root>/<host>/<url hash>/` holding `url.txt`
+/// and the files of the artifacts set.
+#[derive(Debug, Clone)]
+pub struct FsStore {
+    /// The root directory.
+    pub root: PathBuf,
+}
+
+impl FsStore {
+    /// Store the artifacts under the root directory.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The directory of the url.
+    pub fn dir(&self, url: &str) -> PathBuf {
+        self.root.join(store_key(url))
+    }
+}
+
+#[async_trait::async_trait]
+impl PageStore for FsStore {
+    async fn put(&self, url: &str, artifacts: &PageArtifacts) -> std::io::Result<()> {
+        let dir = self.dir(url);
+        tokio::fs::create_dir_all(&dir).await?;
+
+        for artifact in Artifact::ALL {
+            let path = dir.join(artifact.file_name());
+
+            match artifacts.artifact(artifact) {
+                Some(bytes) => tokio::fs::write(path, bytes).await?,
+                _ => match tokio::fs::remove_file(path).await {
+                    Err(err) if err.kind() != ErrorKind::NotFound => return Err(err),
+                    _ => (),
+                },
+            }
+        }
+
+        tokio::fs::write(dir.join(URL_FILE), url).await
+    }
+
+    async fn exists(&self, url: &str) -> std::io::Result<bool> {
+        tokio::fs::try_exists(self.dir(url).join(URL_FILE)).await
+    }
+
+    async fn get(&self, url: &str) -> std::io::Result<Option<PageArtifacts>> {
+        let dir = self.dir(url);
+
+        if !self.exists(url).await? {
+            return Ok(None);
+        }
+
+        let mut artifacts = PageArtifacts::default();
+
+        for artifact in Artifact::ALL {
+            match tokio::fs::read(dir.join(artifact.file_name())).await {
+                Ok(bytes) => artifacts.set_artifact(artifact, Some(bytes.into())),
+                Err(err) if err.kind() == ErrorKind::NotFound => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Some(artifacts))
+    }
+}
+
+/// Store the artifacts in an S3 bucket under a key prefix, one object per artifact.
+#[cfg(feature = "storage_s3")]
+#[derive(Debug, Clone)]
+pub struct S3Store {
+    /// The S3 client.
+    client: aws_sdk_s3::Client,
+    /// The bucket.
+    bucket: String,
+    /// The prefix of the object keys.
+    prefix: String,
+}
+
+/// Map an S3 error to an io error.
+#[cfg(feature = "storage_s3")]
+fn s3_error(err: impl std::error::Error + Send + Sync + 'static) -> std::io::Error {
+    std::io::Error::other(err)
+}
+
+#[cfg(feature = "storage_s3")]
+impl S3Store {
+    /// Store the artifacts in the bucket with the client.
+    pub fn new(client: aws_sdk_s3::Client, bucket: &str) -> Self {
+        Self {
+            client,
+            bucket: bucket.into(),
+            prefix: String::new(),
+        }
+    }
+
+    /// Store the artifacts in the bucket with the credentials and region of the environment.
+    pub async fn from_env(bucket: &str) -> Self {
+        let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        Self::new(aws_sdk_s3::Client::new(&config), bucket)
+    }
+
+    /// Set the prefix of the object keys ex: `crawls/2024-06-01/`.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// The object key of the file of the url.
+    fn key(&self, url: &str, file_name: &str) -> String {
+        format!("{}{}/{file_name}", self.prefix, store_key(url))
+    }
+
+    /// The object of the key, none when it does not exist.
+    async fn get_object(&self, key: String) -> std::io::Result<Option<Bytes>> {
+        match self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(output) => Ok(Some(
+                output.body.collect().await.map_err(s3_error)?.into_bytes(),
+            )),
+            Err(err) => {
+                let err = err.into_service_error();
+                if err.is_no_such_key() {
+                    Ok(None)
+                } else {
+                    Err(s3_error(err))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "storage_s3")]
+#[async_trait::async_trait]
+impl PageStore for S3Store {
+    async fn put(&self, url: &str, artifacts: &PageArtifacts) -> std::io::Result<()> {
+        for artifact in Artifact::ALL {
+            let key = self.key(url, artifact.file_name());
+
+            match artifacts.artifact(artifact) {
+                Some(bytes) => {
+                    self.client
+                        .put_object()
+                        .bucket(&self.bucket)
+                        .key(key)
+                        .body(bytes.clone().into())
+                        .send()
+                        .await
+                        .map_err(|err| s3_error(err.into_service_error()))?;
+                }
+                _ => {
+                    self.client
+                        .delete_object()
+                        .bucket(&self.bucket)
+                        .key(key)
+                        .send()
+                        .await
+                        .map_err(|err| s3_error(err.into_service_error()))?;
+                }
+            }
+        }
+
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(self.key(url, URL_FILE))
+            .body(Bytes::copy_from_slice(url.as_bytes()).into())
+            .send()
+            .await
+            .map_err(|err| s3_error(err.into_service_error()))?;
+
+        Ok(())
+    }
+
+    async fn exists(&self, url: &str) -> std::io::Result<bool> {
+        match self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(self.key(url, URL_FILE))
+            .send()
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(err) => {
+                let err = err.into_service_error();
+                if err.is_not_found() {
+                    Ok(false)
+                } else {
+                    Err(s3_error(err))
+                }
+            }
+        }
+    }
+
+    async fn get(&self, url: &str) -> std::io::Result<Option<PageArtifacts>> {
+        if !self.exists(url).await? {
+            return Ok(None);
+        }
+
+        let mut artifacts = PageArtifacts::default();
+
+        for artifact in Artifact::ALL {
+            let bytes = self.get_object(self.key(url, artifact.file_name())).await?;
+            artifacts.set_artifact(artifact, bytes);
+        }
+
+        Ok(Some(artifacts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_keys() {
+        assert_eq!(
+            store_key("https://example.com/a"),
+            store_key("https://example.com/a")
+        );
+        assert_ne!(
+            store_key("https://example.com/a"),
+            store_key("https://example.com/b")
+        );
+        assert!(store_key("https://example.com:8080/a").starts_with("example.com/"));
+    }
+
+    #[tokio::test]
+    async fn fs_store_roundtrip() {
+        let root = std::env::temp_dir().join(format!("spider-store-{}", std::process::id()));
+        let store = FsStore::new(&root);
+        let url = "https://example.com/a";
+        let artifacts = PageArtifacts {
+            html: Some("<html></html>".into()),
+            screenshot: Some(vec![137u8, 80, 78, 71].into()),
+            ..Default::default()
+        };
+
+        assert!(!store.exists(url).await.unwrap());
+        assert_eq!(store.get(url).await.unwrap(), None);
+
+        store.put(url, &artifacts).await.unwrap();
+        assert!(store.exists(url).await.unwrap());
+        assert_eq!(store.get(url).await.unwrap(), Some(artifacts.clone()));
+
+        let artifacts = PageArtifacts {
+            screenshot: None,
+            ..artifacts
+        }
+        .with_har("{}")
+        .with_console(["log: ready", "error: failed"])
+        .with_extraction(r#"{"article":{}}"#);
+        artifacts.store(url, &store).await.unwrap();
+        assert_eq!(store.get(url).await.unwrap(), Some(artifacts.clone()));
+        assert_eq!(
+            std::fs::read_to_string(store.dir(url).join("console.log")).unwrap(),
+            "log: ready\nerror: failed\n"
+        );
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
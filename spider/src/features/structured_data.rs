@@ -0,0 +1,313 @@
+use crate::packages::scraper::{ElementRef, Html, Selector};
+use crate::utils::element_text;
+use serde_json::{Map, Value};
+use url::Url;
+
+lazy_static! {
+    static ref JSON_LD_SELECTOR: Selector =
+        Selector::parse(r#"script[type="application/ld+json"]"#).expect("valid selector");
+    static ref MICRODATA_SELECTOR: Selector =
+        Selector::parse("[itemscope]:not([itemprop])").expect("valid selector");
+    static ref RDFA_SELECTOR: Selector =
+        Selector::parse("[typeof]:not([property])").expect("valid selector");
+    static ref META_SELECTOR: Selector = Selector::parse("meta[content]").expect("valid selector");
+}
+
+/// The OpenGraph namespaces of the `<meta property>` tags.
+const OPEN_GRAPH_PREFIXES: &[&str] = &["og:", "article:", "book:", "profile:", "music:", "video:"];
+
+/// The structured data of a html document.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StructuredData {
+    /// The JSON-LD objects of the `<script type="application/ld+json">` blocks. Arrays are flattened.
+    pub json_ld: Vec<Value>,
+    /// The top level microdata items with their `@type`, `@id` and properties.
+    pub microdata: Vec<Value>,
+    /// The top level RDFa items with their `@context`, `@type`, `@id` and properties.
+    pub rdfa: Vec<Value>,
+    /// The OpenGraph properties keyed by their full name ex: `og:title`. Repeated properties are arrays.
+    pub open_graph: Map<String, Value>,
+    /// The Twitter card properties keyed by their full name ex: `twitter:card`. Repeated properties are arrays.
+    pub twitter: Map<String, Value>,
+}
+
+impl StructuredData {
+    /// Whether the document has no structured data.
+    pub fn is_empty(&self) -> bool {
+        self.json_ld.is_empty()
+            && self.microdata.is_empty()
+            && self.rdfa.is_empty()
+            && self.open_graph.is_empty()
+            && self.twitter.is_empty()
+    }
+}
+
+/// Add the value to the property, the values of a repeated property are collected in an array.
+fn insert_property(object: &mut Map<String, Value>, name: &str, value: Value) {
+    match object.get_mut(name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(current) => {
+            let first = current.take();
+            *current = Value::Array(vec![first, value]);
+        }
+        _ => {
+            object.insert(name.to_string(), value);
+        }
+    }
+}
+
+/// The value of a single or space separated attribute, an array when it has several tokens.
+fn tokens_value(value: &str) -> Value {
+    let tokens: Vec<Value> = value
+        .split_ascii_whitespace()
+        .map(|token| Value::String(token.to_string()))
+        .collect();
+
+    match tokens.len() {
+        1 => tokens.into_iter().next().unwrap_or_default(),
+        _ => Value::Array(tokens),
+    }
+}
+
+/// The url resolved against the base url.
+fn resolve(base: Option<&Url>, href: &str) -> String {
+    match base.map(|base| base.join(href.trim())) {
+        Some(Ok(url)) => url.into(),
+        _ => href.trim().to_string(),
+    }
+}
+
+/// The value of a microdata property from its element.
+fn microdata_value(element: &ElementRef, base: Option<&Url>) -> Value {
+    let url = |attr: &str| element.attr(attr).map(|href| resolve(base, href));
+    let value = match element.value().name() {
+        "meta" => element.attr("content").map(str::to_string),
+        "audio" | "embed" | "iframe" | "img" | "source" | "track" | "video" => url("src"),
+        "a" | "area" | "link" => url("href"),
+        "object" => url("data"),
+        "data" | "meter" => element.attr("value").map(str::to_string),
+        "time" => element.attr("datetime").map(str::to_string),
+        _ => None,
+    };
+
+    Value::String(value.unwrap_or_else(|| element_text(element)))
+}
+
+/// The microdata item of the `itemscope` element.
+fn microdata_item(element: &ElementRef, base: Option<&Url>) -> Value {
+    let mut item = Map::new();
+
+    if let Some(itemtype) = element.attr("itemtype") {
+        item.insert("@type".into(), tokens_value(itemtype));
+    }
+    if let Some(itemid) = element.attr("itemid") {
+        item.insert("@id".into(), Value::String(resolve(base, itemid)));
+    }
+
+    microdata_properties(element, base, &mut item);
+
+    Value::Object(item)
+}
+
+/// Collect the properties of the descendants of the element, the nested items keep their own properties.
+fn microdata_properties(element: &ElementRef, base: Option<&Url>, item: &mut Map<String, Value>) {
+    for child in element.children().filter_map(ElementRef::wrap) {
+        let nested = child.attr("itemscope").is_some();
+
+        if let Some(itemprop) = child.attr("itemprop") {
+            let value = if nested {
+                microdata_item(&child, base)
+            } else {
+                microdata_value(&child, base)
+            };
+
+            for name in itemprop.split_ascii_whitespace() {
+                insert_property(item, name, value.clone());
+            }
+        }
+
+        if !nested {
+            microdata_properties(&child, base, item);
+        }
+    }
+}
+
+/// The value of a RDFa property from its element.
+fn rdfa_value(element: &ElementRef, base: Option<&Url>) -> Value {
+    let value = match element.attr("content") {
+        Some(content) => content.to_string(),
+        _ => match ["resource", "href", "src"]
+            .iter()
+            .find_map(|attr| element.attr(attr))
+        {
+            Some(href) => resolve(base, href),
+            _ => element_text(element),
+        },
+    };
+
+    Value::String(value)
+}
+
+/// The RDFa item of the `typeof` element.
+fn rdfa_item(element: &ElementRef, base: Option<&Url>, vocab: Option<&str>) -> Value {
+    let mut item = Map::new();
+    let vocab = element.attr("vocab").or(vocab);
+
+    if let Some(vocab) = vocab {
+        item.insert("@context".into(), Value::String(vocab.to_string()));
+    }
+    if let Some(kind) = element.attr("typeof") {
+        item.insert("@type".into(), tokens_value(kind));
+    }
+    if let Some(resource) = element.attr("resource").or_else(|| element.attr("about")) {
+        item.insert("@id".into(), Value::String(resolve(base, resource)));
+    }
+
+    rdfa_properties(element, base, vocab, &mut item);
+
+    Value::Object(item)
+}
+
+/// Collect the RDFa properties of the descendants of the element, the nested items keep their own properties.
+fn rdfa_properties(
+    element: &ElementRef,
+    base: Option<&Url>,
+    vocab: Option<&str>,
+    item: &mut Map<String, Value>,
+) {
+    for child in element.children().filter_map(ElementRef::wrap) {
+        let nested = child.attr("typeof").is_some();
+        let vocab = child.attr("vocab").or(vocab);
+
+        if let Some(property) = child.attr("property") {
+            let value = if nested {
+                rdfa_item(&child, base, vocab)
+            } else {
+                rdfa_value(&child, base)
+            };
+
+            for name in property.split_ascii_whitespace() {
+                insert_property(item, name, value.clone());
+            }
+        }
+
+        if !nested {
+            rdfa_properties(&child, base, vocab, item);
+        }
+    }
+}
+
+/// The nearest `vocab` of the ancestors of the element.
+fn inherited_vocab<'a>(element: &ElementRef<'a>) -> Option<&'a str> {
+    element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .find_map(|ancestor| ancestor.value().attr("vocab"))
+}
+
+/// Extract the JSON-LD, microdata, RDFa, OpenGraph and Twitter card data of the html. The urls are resolved against
+/// the base url. Invalid JSON-LD blocks are skipped.
+pub fn extract(html: &str, base: Option<&Url>) -> StructuredData {
+    let document = Html::parse_document(html);
+    let mut data = StructuredData::default();
+
+    for script in document.select(&JSON_LD_SELECTOR) {
+        let text = script.text().collect::<String>();
+
+        match serde_json::from_str::<Value>(text.trim()) {
+            Ok(Value::Array(values)) => data.json_ld.extend(values),
+            Ok(value) => data.json_ld.push(value),
+            _ => (),
+        }
+    }
+
+    for element in document.select(&MICRODATA_SELECTOR) {
+        data.microdata.push(microdata_item(&element, base));
+    }
+
+    for element in document.select(&RDFA_SELECTOR) {
+        let vocab = inherited_vocab(&element);
+        data.rdfa.push(rdfa_item(&element, base, vocab));
+    }
+
+    for meta in document.select(&META_SELECTOR) {
+        let content = Value::String(meta.attr("content").unwrap_or_default().to_string());
+
+        match meta.attr("property").or_else(|| meta.attr("name")) {
+            Some(name) if OPEN_GRAPH_PREFIXES.iter().any(|p| name.starts_with(p)) => {
+                insert_property(&mut data.open_graph, name, content);
+            }
+            Some(name) if name.starts_with("twitter:") => {
+                insert_property(&mut data.twitter, name, content);
+            }
+            _ => (),
+        }
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn extract_structured_data() {
+        let base = Url::parse("https://example.com/posts/1").unwrap();
+        let html = r#"<html><head>
+            <meta property="og:title" content="Hello">
+            <meta property="og:image" content="https://example.com/a.png">
+            <meta property="og:image" content="https://example.com/b.png">
+            <meta name="twitter:card" content="summary">
+            <script type="application/ld+json">[{"@type": "Article"}, {"@type": "Person"}]</script>
+            <script type="application/ld+json">{ invalid</script>
+        </head><body>
+            <div itemscope itemtype="https://schema.org/Product">
+                <span itemprop="name">Lamp</span>
+                <img itemprop="image" src="/lamp.png">
+                <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+                    <meta itemprop="price" content="20">
+                </div>
+            </div>
+            <div vocab="https://schema.org/" typeof="Person">
+                <p><span property="name">Ada   Lovelace</span></p>
+                <a property="url" href="/ada">Ada</a>
+            </div>
+        </body></html>"#;
+
+        let data = extract(html, Some(&base));
+
+        assert_eq!(
+            data.json_ld,
+            [json!({"@type": "Article"}), json!({"@type": "Person"})]
+        );
+        assert_eq!(
+            data.microdata,
+            [json!({
+                "@type": "https://schema.org/Product",
+                "name": "Lamp",
+                "image": "https://example.com/lamp.png",
+                "offers": {"@type": "https://schema.org/Offer", "price": "20"}
+            })]
+        );
+        assert_eq!(
+            data.rdfa,
+            [json!({
+                "@context": "https://schema.org/",
+                "@type": "Person",
+                "name": "Ada Lovelace",
+                "url": "https://example.com/ada"
+            })]
+        );
+        assert_eq!(data.open_graph["og:title"], json!("Hello"));
+        assert_eq!(
+            data.open_graph["og:image"],
+            json!(["https://example.com/a.png", "https://example.com/b.png"])
+        );
+        assert_eq!(data.twitter["twitter:card"], json!("summary"));
+        assert!(!data.is_empty());
+        assert!(extract("<p>none</p>", None).is_empty());
+    }
+}
@@ -0,0 +1,468 @@
+use crate::packages::scraper::{ElementRef, Html, Node, Selector};
+use crate::page::Page;
+use ego_tree::{NodeId, NodeRef};
+use hashbrown::HashMap;
+
+/// The elements never holding the main content.
+const SKIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "iframe", "svg", "nav", "aside", "footer", "header",
+    "form", "button", "select",
+];
+
+/// The class and id words of the elements unlikely to hold the main content.
+const UNLIKELY_WORDS: &[&str] = &[
+    "comment",
+    "sidebar",
+    "footer",
+    "nav",
+    "menu",
+    "share",
+    "social",
+    "related",
+    "sponsor",
+    "advert",
+    "promo",
+    "popup",
+    "cookie",
+    "banner",
+    "breadcrumb",
+    "newsletter",
+    "subscribe",
+];
+
+/// The class and id words of the elements likely to hold the main content.
+const LIKELY_WORDS: &[&str] = &[
+    "article", "body", "content", "entry", "main", "page", "post", "text", "blog", "story",
+];
+
+/// The elements of the paragraphs scored.
+const PARAGRAPH_TAGS: &[&str] = &["p", "pre", "td", "blockquote"];
+
+/// The elements of the blocks of the main text.
+const BLOCK_TAGS: &[&str] = &[
+    "p",
+    "pre",
+    "blockquote",
+    "li",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "figcaption",
+    "dd",
+    "dt",
+];
+
+/// The separators between the title of the article and the name of the site.
+const TITLE_SEPARATORS: &[&str] = &[" | ", " - ", " – ", " — ", " :: ", " » "];
+
+/// The main content of a page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Article {
+    /// The title of the article without the name of the site.
+    pub title: Option<String>,
+    /// The author of the article.
+    pub byline: Option<String>,
+    /// The publish date as written in the page, usually ISO 8601.
+    pub published: Option<String>,
+    /// The description of the article.
+    pub excerpt: Option<String>,
+    /// The text of the article without the navigation, the sidebars and the footers, one paragraph per line.
+    pub text: String,
+}
+
+/// Extract the title, the byline, the publish date and the main text of the pages with Readability style heuristics:
+/// the paragraphs score their parents by their length and commas, the class and id of the parents raise or lower the
+/// score and the link density lowers it. The paragraphs of the best parent and of its siblings scoring close to it are
+/// the main text. The html is read as fetched over HTTP or rendered by Chrome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextExtraction {
+    /// The characters of a paragraph to score its parents. Defaults to 25.
+    pub min_paragraph_length: usize,
+    /// The characters of the main text to return an article. Defaults to 200.
+    pub min_text_length: usize,
+}
+
+impl Default for TextExtraction {
+    fn default() -> Self {
+        Self {
+            min_paragraph_length: 25,
+            min_text_length: 200,
+        }
+    }
+}
+
+/// The text of the node without the skipped elements.
+fn push_text(node: NodeRef<'_, Node>, out: &mut String) {
+    for child in node.children() {
+        match child.value() {
+            Node::Text(text) => out.push_str(text),
+            Node::Element(element) if !SKIPPED_TAGS.contains(&element.name()) => {
+                if element.name() == "br" {
+                    out.push(' ');
+                }
+                push_text(child, out);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// The text of the element without the skipped elements with the whitespace collapsed.
+fn visible_text(element: ElementRef<'_>) -> String {
+    let mut text = String::new();
+    push_text(*element, &mut text);
+    collapse_whitespace(&text)
+}
+
+/// The words of the text separated by one space.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The share of the text of the element inside links.
+fn link_density(element: ElementRef<'_>, text_len: usize) -> f64 {
+    if text_len == 0 {
+        return 0.0;
+    }
+
+    let links: usize = element
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|element| element.value().name() == "a")
+        .map(|link| visible_text(link).len())
+        .sum();
+
+    (links as f64 / text_len as f64).min(1.0)
+}
+
+/// Whether the class or id of the element has one of the words.
+fn has_word(element: ElementRef<'_>, words: &[&str]) -> bool {
+    let element = element.value();
+
+    [element.attr("class"), element.id()]
+        .iter()
+        .flatten()
+        .any(|value| {
+            let value = value.to_ascii_lowercase();
+            words.iter().any(|word| value.contains(word))
+        })
+}
+
+/// Whether the element or one of its ancestors is unlikely to hold the main content.
+fn is_unlikely(element: ElementRef<'_>) -> bool {
+    std::iter::once(*element)
+        .chain(element.ancestors())
+        .filter_map(ElementRef::wrap)
+        .any(|element| {
+            let name = element.value().name();
+
+            SKIPPED_TAGS.contains(&name)
+                || (!matches!(name, "body" | "html" | "article" | "main")
+                    && has_word(element, UNLIKELY_WORDS)
+                    && !has_word(element, LIKELY_WORDS))
+        })
+}
+
+/// The score of an element before its paragraphs, from its tag and its class and id.
+fn initial_score(element: ElementRef<'_>) -> f64 {
+    let tag = match element.value().name() {
+        "article" | "main" => 10.0,
+        "div" => 5.0,
+        "section" | "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+    let mut class = 0.0;
+
+    if has_word(element, UNLIKELY_WORDS) {
+        class -= 25.0;
+    }
+    if has_word(element, LIKELY_WORDS) {
+        class += 25.0;
+    }
+
+    tag + class
+}
+
+/// The content of the first meta tag with one of the names or properties.
+fn meta(document: &Html, names: &[&str]) -> Option<String> {
+    let selector = Selector::parse("meta[content]").ok()?;
+
+    names.iter().find_map(|name| {
+        document.select(&selector).find_map(|meta| {
+            let element = meta.value();
+            let key = element.attr("property").or_else(|| element.attr("name"))?;
+
+            if key.eq_ignore_ascii_case(name) {
+                Some(collapse_whitespace(element.attr("content")?)).filter(|c| !c.is_empty())
+            } else {
+                None
+            }
+        })
+    })
+}
+
+/// The first element matching the selector with text.
+fn first_text(document: &Html, selector: &str) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+
+    document
+        .select(&selector)
+        .map(visible_text)
+        .find(|text| !text.is_empty())
+}
+
+/// The title without the name of the site, kept whole when the part left would be too short.
+fn clean_title(title: &str) -> String {
+    for separator in TITLE_SEPARATORS {
+        if let Some(index) = title.rfind(separator) {
+            let head = title[..index].trim();
+
+            if head.split_whitespace().count() >= 3 {
+                return head.into();
+            }
+        }
+    }
+
+    title.trim().into()
+}
+
+impl TextExtraction {
+    /// Extract the articles with the default thresholds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the characters of a paragraph to score its parents.
+    pub fn with_min_paragraph_length(mut self, min_paragraph_length: usize) -> Self {
+        self.min_paragraph_length = min_paragraph_length;
+        self
+    }
+
+    /// Set the characters of the main text to return an article.
+    pub fn with_min_text_length(mut self, min_text_length: usize) -> Self {
+        self.min_text_length = min_text_length;
+        self
+    }
+
+    /// The article of the page.
+    pub fn extract_page(&self, page: &Page) -> Option<Article> {
+        self.extract(&page.get_html())
+    }
+
+    /// The article of the html, `None` when the main text is shorter than the min length.
+    pub fn extract(&self, html: &str) -> Option<Article> {
+        if html.is_empty() {
+            return None;
+        }
+
+        let document = Html::parse_document(html);
+        let text = self.main_text(&document);
+
+        if text.chars().count() < self.min_text_length {
+            return None;
+        }
+
+        Some(Article {
+            title: meta(&document, &["og:title", "twitter:title"])
+                .or_else(|| first_text(&document, "title").map(|title| clean_title(&title)))
+                .or_else(|| first_text(&document, "h1")),
+            byline: meta(&document, &["author", "article:author", "dc.creator"])
+                .filter(|author| !author.starts_with("http"))
+                .or_else(|| {
+                    first_text(
+                        &document,
+                        "[rel=author], [itemprop=author], .byline, .author, .article-author",
+                    )
+                })
+                .filter(|byline| byline.len() < 100),
+            published: meta(
+                &document,
+                &[
+                    "article:published_time",
+                    "og:published_time",
+                    "datePublished",
+                    "pubdate",
+                    "publish-date",
+                    "date",
+                    "dc.date",
+                ],
+            )
+            .or_else(|| {
+                let selector = Selector::parse("[itemprop=datePublished], time[datetime]").ok()?;
+                document.select(&selector).find_map(|element| {
+                    let element = element.value();
+                    element
+                        .attr("content")
+                        .or_else(|| element.attr("datetime"))
+                        .map(|date| date.trim().to_string())
+                })
+            }),
+            excerpt: meta(&document, &["description", "og:description"]),
+            text,
+        })
+    }
+
+    /// The paragraphs of the best scoring element and of its siblings scoring close to it.
+    fn main_text(&self, document: &Html) -> String {
+        let mut scores: HashMap<NodeId, f64> = HashMap::new();
+
+        for node in document.tree.nodes() {
+            let paragraph = match ElementRef::wrap(node) {
+                Some(paragraph) if PARAGRAPH_TAGS.contains(&paragraph.value().name()) => paragraph,
+                _ => continue,
+            };
+            let text = visible_text(paragraph);
+
+            if text.len() < self.min_paragraph_length || is_unlikely(paragraph) {
+                continue;
+            }
+
+            let score = 1.0 + text.matches(',').count() as f64 + (text.len() / 100).min(3) as f64;
+
+            for (level, ancestor) in paragraph
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .take(2)
+                .enumerate()
+            {
+                *scores
+                    .entry(ancestor.id())
+                    .or_insert_with(|| initial_score(ancestor)) +=
+                    if level == 0 { score } else { score / 2.0 };
+            }
+        }
+
+        let final_score = |element: ElementRef<'_>, score: f64| {
+            let text_len = visible_text(element).len();
+            score * (1.0 - link_density(element, text_len))
+        };
+
+        let top = scores
+            .iter()
+            .filter_map(|(id, score)| {
+                let element = document.tree.get(*id).and_then(ElementRef::wrap)?;
+                Some((element, final_score(element, *score)))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        let (top, top_score) = match top {
+            Some(top) => top,
+            _ => return String::new(),
+        };
+
+        let threshold = (top_score * 0.2).max(10.0);
+        let mut blocks: Vec<String> = Vec::new();
+
+        let siblings: Vec<ElementRef<'_>> = match top.parent() {
+            Some(parent) => parent.children().filter_map(ElementRef::wrap).collect(),
+            _ => vec![top],
+        };
+
+        for sibling in siblings {
+            let append = sibling == top
+                || scores
+                    .get(&sibling.id())
+                    .is_some_and(|score| final_score(sibling, *score) >= threshold)
+                || (sibling.value().name() == "p" && {
+                    let text = visible_text(sibling);
+                    text.len() > 80 && link_density(sibling, text.len()) < 0.25
+                });
+
+            if append && !is_unlikely(sibling) {
+                self.push_blocks(sibling, &mut blocks);
+            }
+        }
+
+        blocks.join("\n")
+    }
+
+    /// Push the text of the blocks of the element, the element itself when it has no blocks.
+    fn push_blocks(&self, element: ElementRef<'_>, blocks: &mut Vec<String>) {
+        if BLOCK_TAGS.contains(&element.value().name()) {
+            let text = visible_text(element);
+            if !text.is_empty() {
+                blocks.push(text);
+            }
+            return;
+        }
+
+        let start = blocks.len();
+
+        for child in element.children().filter_map(ElementRef::wrap) {
+            let name = child.value().name();
+
+            if !SKIPPED_TAGS.contains(&name) && !is_unlikely(child) {
+                self.push_blocks(child, blocks);
+            }
+        }
+
+        if blocks.len() == start {
+            let text = visible_text(element);
+            if text.len() >= self.min_paragraph_length {
+                blocks.push(text);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_article() {
+        let paragraph =
+            "The river rose through the night, and by morning the lower town was under water, \
+            the bridges closed and the schools shut for the week.";
+        let html = format!(
+            r#"<html><head>
+            <title>Floods close the lower town | Valley News</title>
+            <meta name="author" content="Ada Lovelace">
+            <meta property="article:published_time" content="2024-05-01T08:00:00Z">
+            <meta name="description" content="The river rose overnight.">
+            </head><body>
+            <nav><a href="/">Home</a> <a href="/news">News</a></nav>
+            <div class="sidebar"><p>{paragraph}</p><a href="/a">Related story one, and more</a></div>
+            <article class="post-content">
+              <h2>A long night</h2>
+              <p>{paragraph}</p>
+              <p>{paragraph} <script>track()</script></p>
+              <ul><li>Shelters are open at the town hall.</li></ul>
+            </article>
+            <div id="comments"><p>{paragraph}</p></div>
+            <footer><p>Copyright Valley News, all rights reserved, since 1901.</p></footer>
+            </body></html>"#
+        );
+
+        let article = TextExtraction::new().extract(&html).unwrap();
+
+        assert_eq!(
+            article.title.as_deref(),
+            Some("Floods close the lower town")
+        );
+        assert_eq!(article.byline.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(article.published.as_deref(), Some("2024-05-01T08:00:00Z"));
+        assert_eq!(
+            article.excerpt.as_deref(),
+            Some("The river rose overnight.")
+        );
+        assert_eq!(
+            article.text,
+            format!(
+                "A long night\n{paragraph}\n{paragraph}\nShelters are open at the town hall.",
+                paragraph = collapse_whitespace(paragraph)
+            )
+        );
+
+        assert_eq!(
+            TextExtraction::new().extract("<html><body><p>Too short.</p></body></html>"),
+            None
+        );
+    }
+}
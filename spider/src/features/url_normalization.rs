@@ -0,0 +1,315 @@
+use crate::page::Page;
+use crate::CaseInsensitiveString;
+use hashbrown::HashSet;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// The tracking parameters stripped by default.
+const TRACKING_PARAMS: &[&str] = &[
+    "utm_*", "fbclid", "gclid", "dclid", "msclkid", "yclid", "mc_cid", "mc_eid", "_ga", "_gl",
+];
+
+/// The session id parameters collapsed by default.
+const SESSION_PARAMS: &[&str] = &[
+    "jsessionid",
+    "phpsessid",
+    "sessionid",
+    "aspsessionid*",
+    "cfid",
+    "cftoken",
+];
+
+/// Whether the name matches the pattern ignoring the case. A pattern ending with `*` matches the names starting with
+/// the rest of the pattern.
+fn matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name
+            .get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix)),
+        _ => pattern.eq_ignore_ascii_case(name),
+    }
+}
+
+/// Rewrite the links found before they are deduplicated and added to the frontier so trivially different urls of the
+/// same page are crawled once: tracking parameters and session ids are removed and the query parameters are sorted.
+/// Pages declaring a `<link rel="canonical">` to another url are marked as variants of it, the canonical url is followed
+/// and the links to the variants are dropped.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UrlNormalization {
+    /// The query parameters removed ex: `utm_*` or `fbclid`. A trailing `*` matches the parameters starting with the
+    /// rest of the name.
+    pub strip_params: Vec<String>,
+    /// The session id parameters removed from the query and from the path parameters ex: `;jsessionid=...`.
+    pub session_params: Vec<String>,
+    /// Sort the query parameters by name.
+    pub sort_query: bool,
+    /// Resolve the `<link rel="canonical">` of the pages.
+    pub canonical: bool,
+    /// The urls of the pages crawled declaring another canonical url.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    variants: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Default for UrlNormalization {
+    fn default() -> Self {
+        Self {
+            strip_params: TRACKING_PARAMS.iter().map(|p| p.to_string()).collect(),
+            session_params: SESSION_PARAMS.iter().map(|p| p.to_string()).collect(),
+            sort_query: true,
+            canonical: true,
+            variants: Default::default(),
+        }
+    }
+}
+
+impl PartialEq for UrlNormalization {
+    fn eq(&self, other: &Self) -> bool {
+        self.strip_params == other.strip_params
+            && self.session_params == other.session_params
+            && self.sort_query == other.sort_query
+            && self.canonical == other.canonical
+    }
+}
+
+impl UrlNormalization {
+    /// Strip the common tracking parameters and session ids, sort the query and resolve the canonical urls.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the query parameters removed.
+    pub fn with_strip_params(mut self, strip_params: Vec<String>) -> Self {
+        self.strip_params = strip_params;
+        self
+    }
+
+    /// Set the session id parameters removed.
+    pub fn with_session_params(mut self, session_params: Vec<String>) -> Self {
+        self.session_params = session_params;
+        self
+    }
+
+    /// Set whether the query parameters are sorted by name.
+    pub fn with_sort_query(mut self, sort_query: bool) -> Self {
+        self.sort_query = sort_query;
+        self
+    }
+
+    /// Set whether the `<link rel="canonical">` of the pages is resolved.
+    pub fn with_canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// The normalized url. Urls that fail to parse are returned as is, the host is lowercased by the url parser.
+    pub fn normalize(&self, link: &str) -> String {
+        let mut url = match Url::parse(link) {
+            Ok(url) => url,
+            _ => return link.to_string(),
+        };
+
+        if !self.session_params.is_empty() && url.path().contains(';') {
+            let path = url
+                .path()
+                .split('/')
+                .map(|segment| {
+                    let mut parts = segment.split(';');
+                    let mut kept = parts.next().unwrap_or_default().to_string();
+
+                    for param in parts {
+                        let name = param.split('=').next().unwrap_or_default();
+
+                        if !self.session_params.iter().any(|p| matches(p, name)) {
+                            kept.push(';');
+                            kept.push_str(param);
+                        }
+                    }
+
+                    kept
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+
+            url.set_path(&path);
+        }
+
+        if let Some(query) = url.query() {
+            let mut params: Vec<&str> = query
+                .split('&')
+                .filter(|param| {
+                    let name = param.split('=').next().unwrap_or_default();
+
+                    !name.is_empty()
+                        && !self.strip_params.iter().any(|p| matches(p, name))
+                        && !self.session_params.iter().any(|p| matches(p, name))
+                })
+                .collect();
+
+            if self.sort_query {
+                params.sort_by_key(|&param| param.split('=').next().unwrap_or_default());
+            }
+
+            let query = params.join("&");
+            url.set_query(if query.is_empty() { None } else { Some(&query) });
+        }
+
+        url.into()
+    }
+
+    /// The normalized url of the `<link rel="canonical">` of the page.
+    pub fn canonical_url(&self, page: &Page) -> Option<String> {
+        let html = page.get_html();
+        let head = match html.find("</head>") {
+            Some(end) => &html[..end],
+            _ => &html,
+        };
+        let mut href = None;
+
+        let _ = lol_html::rewrite_str(
+            head,
+            lol_html::RewriteStrSettings {
+                element_content_handlers: vec![lol_html::element!("link[rel][href]", |el| {
+                    let canonical = el.get_attribute("rel").is_some_and(|rel| {
+                        rel.split_ascii_whitespace()
+                            .any(|rel| rel.eq_ignore_ascii_case("canonical"))
+                    });
+
+                    if canonical && href.is_none() {
+                        href = el.get_attribute("href");
+                    }
+                    Ok(())
+                })],
+                ..lol_html::RewriteStrSettings::new()
+            },
+        );
+
+        let base = Url::parse(page.get_url()).ok()?;
+        let canonical = base.join(href?.trim()).ok()?;
+
+        match canonical.scheme() {
+            "http" | "https" => Some(self.normalize(canonical.as_str())),
+            _ => None,
+        }
+    }
+
+    /// Normalize the links and drop the variants of a canonical url.
+    pub(crate) fn normalize_links(&self, links: &mut HashSet<CaseInsensitiveString>) {
+        let variants = match self.variants.lock() {
+            Ok(variants) => variants,
+            _ => return,
+        };
+
+        *links = links
+            .drain()
+            .map(|link| self.normalize(link.inner()))
+            .filter(|link| !variants.contains(link))
+            .map(CaseInsensitiveString::from)
+            .collect();
+    }
+
+    /// Mark the page as a variant when it declares another canonical url, follow the canonical url and normalize the
+    /// links found on the page.
+    pub(crate) fn normalize_page_links(
+        &self,
+        page: &Page,
+        links: &mut HashSet<CaseInsensitiveString>,
+    ) {
+        if self.canonical && page.status_code.is_success() {
+            if let Some(canonical) = self.canonical_url(page) {
+                let url = self.normalize(page.get_url());
+
+                if canonical != url {
+                    if let Ok(mut variants) = self.variants.lock() {
+                        variants.insert(url);
+                    }
+                    links.insert(canonical.into());
+                }
+            }
+        }
+
+        self.normalize_links(links);
+    }
+
+    /// Forget the variants for a new crawl.
+    pub(crate) fn reset(&self) {
+        if let Ok(mut variants) = self.variants.lock() {
+            variants.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page::build;
+    use crate::utils::PageResponse;
+
+    #[test]
+    fn normalize_urls() {
+        let normalization = UrlNormalization::new();
+
+        assert_eq!(
+            normalization.normalize(
+                "https://Example.com/a;jsessionid=ABC/b?utm_source=x&z=1&fbclid=2&a=2&PHPSESSID=3"
+            ),
+            "https://example.com/a/b?a=2&z=1"
+        );
+        assert_eq!(
+            normalization.normalize("https://example.com/?utm_medium=mail"),
+            "https://example.com/"
+        );
+        assert_eq!(
+            normalization
+                .clone()
+                .with_sort_query(false)
+                .normalize("https://example.com/?b=1&a=2"),
+            "https://example.com/?b=1&a=2"
+        );
+        assert_eq!(
+            normalization.normalize("https://example.com/watch?sid=7"),
+            "https://example.com/watch?sid=7"
+        );
+        assert_eq!(normalization.normalize("not a url"), "not a url");
+    }
+
+    #[test]
+    fn resolve_canonical() {
+        let normalization = UrlNormalization::new();
+        let page = build(
+            "https://example.com/article?print=1",
+            PageResponse {
+                content: Some(Box::new(
+                    r#"<html><head><link rel="Canonical" href="/article?utm_source=feed"></head><body></body></html>"#
+                        .to_string()
+                        .into(),
+                )),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            normalization.canonical_url(&page),
+            Some("https://example.com/article".into())
+        );
+
+        let mut links: HashSet<CaseInsensitiveString> = HashSet::new();
+        links.insert("https://example.com/other?b=1&a=2".into());
+        normalization.normalize_page_links(&page, &mut links);
+
+        assert_eq!(links.len(), 2);
+        assert!(links.contains(&CaseInsensitiveString::from("https://example.com/article")));
+        assert!(links.contains(&CaseInsensitiveString::from(
+            "https://example.com/other?a=2&b=1"
+        )));
+
+        let mut links: HashSet<CaseInsensitiveString> = HashSet::new();
+        links.insert("https://example.com/article?utm_campaign=a&print=1".into());
+        links.insert("https://example.com/article?utm_campaign=a".into());
+        normalization.normalize_links(&mut links);
+
+        assert_eq!(
+            links.into_iter().collect::<Vec<_>>(),
+            vec![CaseInsensitiveString::from("https://example.com/article")]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use crate::configuration::{Configuration, WebDriverBiDiConfig};
+use crate::page::Page;
+use crate::utils::{log, PageResponse};
+use crate::{CaseInsensitiveString, RelativeSelectors};
+use chromiumoxide::backend::BrowserBackend;
+use chromiumoxide::bidi::{BidiBrowser, BidiInterception};
+use hashbrown::HashSet;
+use reqwest::StatusCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The browser executable launched when the config has none.
+const DEFAULT_EXECUTABLE: &str = "firefox";
+
+/// The browser the pages are rendered with instead of Chrome.
+#[derive(Clone)]
+pub struct BrowserBackendHandle(pub Arc<dyn BrowserBackend>);
+
+impl std::fmt::Debug for BrowserBackendHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("BrowserBackendHandle")
+            .field(&self.0.engine())
+            .finish()
+    }
+}
+
+/// The interception of the requests of the pages from the chrome intercept, the consent mode and the mocked routes.
+pub fn interception(configuration: &Configuration, url: &str) -> BidiInterception {
+    let intercept = &configuration.chrome_intercept;
+
+    BidiInterception {
+        ignore_visuals: intercept.enabled && intercept.block_visuals,
+        block_stylesheets: intercept.enabled && intercept.block_stylesheets,
+        block_javascript: intercept.enabled && intercept.block_javascript,
+        resource_policy: if intercept.enabled {
+            (&intercept.resource_policy).into()
+        } else {
+            Default::default()
+        },
+        third_party: configuration
+            .consent_mode
+            .as_deref()
+            .map(|consent_mode| consent_mode.third_party_policy(url)),
+        request_filter: None,
+        mock_router: configuration
+            .mock_router
+            .as_deref()
+            .map(|router| router.clone().into()),
+    }
+}
+
+/// Connect to the WebDriver BiDi browser of the config or launch one.
+pub async fn launch_backend(
+    config: &WebDriverBiDiConfig,
+    configuration: &Configuration,
+    url: &str,
+) -> Option<BrowserBackendHandle> {
+    let interception = interception(configuration, url);
+
+    let browser = match &config.connection_url {
+        Some(connection_url) => BidiBrowser::connect(connection_url, interception).await,
+        _ => {
+            let executable = config
+                .executable
+                .clone()
+                .unwrap_or_else(|| DEFAULT_EXECUTABLE.into());
+            BidiBrowser::launch(executable, &config.args, interception).await
+        }
+    };
+
+    match browser {
+        Ok(browser) => Some(BrowserBackendHandle(Arc::new(browser))),
+        Err(e) => {
+            log("WebDriver BiDi failed to start: ", e.to_string());
+            None
+        }
+    }
+}
+
+/// Render the page in a new tab of the backend and collect its links. The page is not retried over HTTP.
+pub async fn fetch_page(
+    backend: &BrowserBackendHandle,
+    url: &str,
+    selectors: &RelativeSelectors,
+    request_timeout: &Option<Box<Duration>>,
+) -> (Page, HashSet<CaseInsensitiveString>) {
+    let render = async {
+        let tab = backend.0.new_page().await?;
+        let result = match tab.goto(url).await {
+            Ok(status_code) => tab.content().await.map(|content| (status_code, content)),
+            Err(e) => Err(e),
+        };
+        let _ = tab.close().await;
+        result
+    };
+
+    let result = match request_timeout {
+        Some(timeout) => match tokio::time::timeout(**timeout, render).await {
+            Ok(result) => result,
+            _ => Err(chromiumoxide::error::CdpError::Timeout),
+        },
+        _ => render.await,
+    };
+
+    let res = match result {
+        Ok((status_code, content)) => PageResponse {
+            content: Some(Box::new(content.into())),
+            status_code: status_code
+                .and_then(|status_code| StatusCode::from_u16(status_code).ok())
+                .unwrap_or(StatusCode::OK),
+            ..Default::default()
+        },
+        Err(e) => {
+            log("WebDriver BiDi failed to render: ", url);
+            PageResponse {
+                status_code: match e {
+                    chromiumoxide::error::CdpError::Timeout => StatusCode::REQUEST_TIMEOUT,
+                    _ => StatusCode::BAD_GATEWAY,
+                },
+                ..Default::default()
+            }
+        }
+    };
+
+    let mut page = crate::page::build(url, res);
+    page.should_retry = false;
+
+    let links = page.links(selectors).await;
+
+    (page, links)
+}
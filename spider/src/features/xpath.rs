@@ -0,0 +1,246 @@
+use super::extractor::Selection;
+use crate::packages::scraper::{ElementRef, Html, Node};
+use ego_tree::{NodeId, NodeRef};
+use hashbrown::HashMap;
+use sxd_document::dom::{Document, Element};
+use sxd_xpath::{Context, Factory, Value};
+
+/// The expression could not be parsed or evaluated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XPathError(pub String);
+
+impl std::fmt::Display for XPathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "xpath error: {}", self.0)
+    }
+}
+
+impl std::error::Error for XPathError {}
+
+/// A node matched by a XPath expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum XPathNode {
+    /// An element ex: `//h2`.
+    Element(Selection),
+    /// An attribute with its name and value ex: `//a/@href`.
+    Attribute(String, String),
+    /// A text or comment node ex: `//h2/text()`.
+    Text(String),
+}
+
+impl XPathNode {
+    /// The string value of the node: the text of an element, the value of an attribute or the text of a text node.
+    pub fn value(&self) -> &str {
+        match self {
+            XPathNode::Element(selection) => &selection.text,
+            XPathNode::Attribute(_, value) => value,
+            XPathNode::Text(text) => text,
+        }
+    }
+}
+
+/// The result of a XPath expression.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum XPathValue {
+    /// The nodes matched in document order.
+    Nodes(Vec<XPathNode>),
+    /// A string ex: `string(//title)`.
+    String(String),
+    /// A number ex: `count(//a)`.
+    Number(f64),
+    /// A boolean ex: `boolean(//form)`.
+    Boolean(bool),
+}
+
+/// A node matched in the parsed html.
+pub(crate) enum Matched<'a> {
+    /// An element of the html.
+    Element(ElementRef<'a>),
+    /// An attribute with its name and value.
+    Attribute(String, String),
+    /// The text of a text or comment node.
+    Text(String),
+}
+
+/// The result of a XPath expression evaluated against the parsed html.
+pub(crate) enum Evaluated<'a> {
+    /// The nodes matched in document order.
+    Nodes(Vec<Matched<'a>>),
+    /// A scalar value.
+    Scalar(XPathValue),
+}
+
+/// Copy the children of the html node into the XML element and map the elements created to the html elements.
+fn copy_children<'d>(
+    document: Document<'d>,
+    parent: Element<'d>,
+    node: NodeRef<Node>,
+    elements: &mut HashMap<Element<'d>, NodeId>,
+) {
+    for child in node.children() {
+        match child.value() {
+            Node::Element(element) => {
+                let copy = document.create_element(element.name());
+                for (name, value) in element.attrs() {
+                    copy.set_attribute_value(name, value);
+                }
+                parent.append_child(copy);
+                elements.insert(copy, child.id());
+                copy_children(document, copy, child, elements);
+            }
+            Node::Text(text) => parent.append_child(document.create_text(text)),
+            Node::Comment(comment) => parent.append_child(document.create_comment(comment)),
+            _ => (),
+        }
+    }
+}
+
+/// Evaluate the XPath 1.0 expression against the parsed html. The html tree is copied into a XML document keeping the
+/// element names, attributes, text and comments so the expressions written for the browser DOM match the same nodes.
+pub(crate) fn evaluate_html<'a>(
+    html: &'a Html,
+    expression: &str,
+) -> Result<Evaluated<'a>, XPathError> {
+    let xpath = match Factory::new().build(expression) {
+        Ok(Some(xpath)) => xpath,
+        Ok(None) => return Err(XPathError("the expression is empty".into())),
+        Err(e) => return Err(XPathError(e.to_string())),
+    };
+
+    let package = sxd_document::Package::new();
+    let document = package.as_document();
+    let mut elements = HashMap::new();
+
+    for child in html.tree.root().children() {
+        if let Node::Element(element) = child.value() {
+            let copy = document.create_element(element.name());
+            for (name, value) in element.attrs() {
+                copy.set_attribute_value(name, value);
+            }
+            document.root().append_child(copy);
+            elements.insert(copy, child.id());
+            copy_children(document, copy, child, &mut elements);
+        }
+    }
+
+    let value = xpath
+        .evaluate(&Context::new(), document.root())
+        .map_err(|e| XPathError(e.to_string()))?;
+
+    Ok(match value {
+        Value::Nodeset(nodes) => Evaluated::Nodes(
+            nodes
+                .document_order()
+                .into_iter()
+                .filter_map(|node| match node {
+                    sxd_xpath::nodeset::Node::Element(element) => elements
+                        .get(&element)
+                        .and_then(|id| html.tree.get(*id))
+                        .and_then(ElementRef::wrap)
+                        .map(Matched::Element),
+                    sxd_xpath::nodeset::Node::Attribute(attribute) => Some(Matched::Attribute(
+                        attribute.name().local_part().to_string(),
+                        attribute.value().to_string(),
+                    )),
+                    sxd_xpath::nodeset::Node::Text(text) => {
+                        Some(Matched::Text(text.text().to_string()))
+                    }
+                    sxd_xpath::nodeset::Node::Comment(comment) => {
+                        Some(Matched::Text(comment.text().to_string()))
+                    }
+                    sxd_xpath::nodeset::Node::Root(_) => html
+                        .tree
+                        .root()
+                        .children()
+                        .find_map(ElementRef::wrap)
+                        .map(Matched::Element),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        Value::String(value) => Evaluated::Scalar(XPathValue::String(value)),
+        Value::Number(value) => Evaluated::Scalar(XPathValue::Number(value)),
+        Value::Boolean(value) => Evaluated::Scalar(XPathValue::Boolean(value)),
+    })
+}
+
+/// Evaluate the XPath 1.0 expression against the html ex: `//div[@class="article"]/h2`, `//a/@href` or `count(//a)`.
+pub fn evaluate(html: &str, expression: &str) -> Result<XPathValue, XPathError> {
+    let document = Html::parse_document(html);
+
+    Ok(match evaluate_html(&document, expression)? {
+        Evaluated::Nodes(nodes) => XPathValue::Nodes(
+            nodes
+                .into_iter()
+                .map(|node| match node {
+                    Matched::Element(element) => XPathNode::Element(Selection::from(element)),
+                    Matched::Attribute(name, value) => XPathNode::Attribute(name, value),
+                    Matched::Text(text) => XPathNode::Text(text),
+                })
+                .collect(),
+        ),
+        Evaluated::Scalar(value) => value,
+    })
+}
+
+/// The nodes of the html matching the XPath 1.0 expression. Invalid expressions and expressions returning a string,
+/// number or boolean match nothing.
+pub fn select_xpath(html: &str, expression: &str) -> Vec<XPathNode> {
+    match evaluate(html, expression) {
+        Ok(XPathValue::Nodes(nodes)) => nodes,
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = r#"<html><head><title>Blog</title></head><body>
+        <div class="article"><h2>First <b>post</b></h2><a href="/1">Read</a></div>
+        <div class="article"><h2>Second post</h2><a href="/2">Read</a></div>
+        <!-- footer -->
+    </body></html>"#;
+
+    #[test]
+    fn evaluate_expressions() {
+        let titles = select_xpath(HTML, r#"//div[@class="article"]/h2"#);
+        assert_eq!(titles.len(), 2);
+        assert_eq!(titles[0].value(), "First post");
+        match &titles[0] {
+            XPathNode::Element(selection) => {
+                assert_eq!(selection.inner_html, "First <b>post</b>")
+            }
+            node => panic!("expected an element, got {node:?}"),
+        }
+
+        assert_eq!(
+            select_xpath(HTML, "//a/@href"),
+            [
+                XPathNode::Attribute("href".into(), "/1".into()),
+                XPathNode::Attribute("href".into(), "/2".into())
+            ]
+        );
+        assert_eq!(
+            select_xpath(HTML, "//div[2]/h2/text()"),
+            [XPathNode::Text("Second post".into())]
+        );
+        assert_eq!(
+            select_xpath(HTML, "//comment()"),
+            [XPathNode::Text(" footer ".into())]
+        );
+        assert_eq!(evaluate(HTML, "count(//a)"), Ok(XPathValue::Number(2.0)));
+        assert_eq!(
+            evaluate(HTML, "string(/html/head/title)"),
+            Ok(XPathValue::String("Blog".into()))
+        );
+        assert_eq!(
+            evaluate(HTML, "boolean(//form)"),
+            Ok(XPathValue::Boolean(false))
+        );
+        assert!(evaluate(HTML, "//a[").is_err());
+        assert!(select_xpath(HTML, "//a[").is_empty());
+    }
+}
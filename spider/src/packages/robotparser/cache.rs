@@ -0,0 +1,245 @@
+//! robots.txt rules cached per host for the crawls of a website.
+
+use super::parser::RobotFileParser;
+use crate::Client;
+use hashbrown::HashMap;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The hosts cached by default before the oldest is evicted.
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// How long a robots.txt that could not be fetched is used before it is fetched again.
+const FAILED_RETRY: Duration = Duration::from_secs(60);
+
+/// The robots.txt rules of a host.
+#[derive(Debug, Clone)]
+struct CachedRobots {
+    /// The parsed robots.txt.
+    parser: Box<RobotFileParser>,
+    /// The last fetch failed and the rules are retried shortly.
+    failed: bool,
+}
+
+impl CachedRobots {
+    /// Should the robots.txt be fetched again? The rules are kept for the crawl without a ttl.
+    fn is_stale(&self, ttl: Option<Duration>) -> bool {
+        let ttl = if self.failed {
+            Some(ttl.map_or(FAILED_RETRY, |ttl| ttl.min(FAILED_RETRY)))
+        } else {
+            ttl
+        };
+
+        ttl.is_some_and(|ttl| self.parser.is_stale(ttl))
+    }
+}
+
+/// The cached hosts in the order they were added.
+#[derive(Debug, Default)]
+struct CachedHosts {
+    /// The robots.txt of each host.
+    rules: HashMap<String, CachedRobots>,
+    /// The hosts from the oldest to the newest.
+    order: VecDeque<String>,
+}
+
+/// robots.txt rules keyed by the base url of the host, evicting the oldest host once full.
+#[derive(Debug)]
+pub struct RobotsCache {
+    /// The hosts cached at most.
+    capacity: usize,
+    /// The parsed robots.txt of each host.
+    hosts: Mutex<CachedHosts>,
+}
+
+impl Default for RobotsCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl RobotsCache {
+    /// A cache holding the robots.txt of up to `capacity` hosts.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            hosts: Default::default(),
+        }
+    }
+
+    /// The cached robots.txt rules of the host.
+    pub fn get(&self, url: &str) -> Option<Box<RobotFileParser>> {
+        self.hosts
+            .lock()
+            .ok()?
+            .rules
+            .get(url)
+            .map(|cached| cached.parser.clone())
+    }
+
+    /// Apply the cached robots.txt rules of the host.
+    pub fn with_rules<R>(&self, url: &str, f: impl FnOnce(&RobotFileParser) -> R) -> Option<R> {
+        let hosts = self.hosts.lock().ok()?;
+
+        hosts.rules.get(url).map(|cached| f(&cached.parser))
+    }
+
+    /// Cache the robots.txt rules of the host.
+    pub fn insert(&self, url: &str, parser: Box<RobotFileParser>) {
+        self.insert_cached(
+            url,
+            CachedRobots {
+                parser,
+                failed: false,
+            },
+        )
+    }
+
+    /// Cache the robots.txt of the host, evicting the oldest hosts over the capacity.
+    fn insert_cached(&self, url: &str, cached: CachedRobots) {
+        if let Ok(mut hosts) = self.hosts.lock() {
+            if hosts.rules.insert(url.into(), cached).is_none() {
+                hosts.order.push_back(url.into());
+            }
+
+            while hosts.order.len() > self.capacity {
+                if let Some(oldest) = hosts.order.pop_front() {
+                    hosts.rules.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    /// The count of hosts cached.
+    pub fn len(&self) -> usize {
+        self.hosts
+            .lock()
+            .map(|hosts| hosts.rules.len())
+            .unwrap_or(0)
+    }
+
+    /// Is the cache empty?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Does the host need its robots.txt fetched?
+    pub fn needs_read(&self, url: &str, ttl: Option<Duration>) -> bool {
+        match self.hosts.lock() {
+            Ok(hosts) => hosts
+                .rules
+                .get(url)
+                .is_none_or(|cached| cached.is_stale(ttl)),
+            _ => true,
+        }
+    }
+
+    /// Forget every cached robots.txt.
+    pub fn clear(&self) {
+        if let Ok(mut hosts) = self.hosts.lock() {
+            hosts.rules.clear();
+            hosts.order.clear();
+        }
+    }
+
+    /// The robots.txt rules of the host at `url`, ending with a slash. The cached rules are used
+    /// for the crawl or until the ttl expires and are revalidated with a conditional request
+    /// afterwards. A robots.txt that could not be fetched is fetched again after a minute.
+    pub async fn read(
+        &self,
+        client: &Client,
+        url: &str,
+        ttl: Option<Duration>,
+    ) -> Box<RobotFileParser> {
+        let cached = match self.hosts.lock() {
+            Ok(hosts) => hosts.rules.get(url).cloned(),
+            _ => None,
+        };
+
+        let mut parser = match cached {
+            Some(cached) if !cached.is_stale(ttl) => return cached.parser,
+            Some(cached) => cached.parser,
+            _ => RobotFileParser::new(),
+        };
+
+        let failed = !parser.read(client, url).await;
+
+        self.insert_cached(
+            url,
+            CachedRobots {
+                parser: parser.clone(),
+                failed,
+            },
+        );
+
+        parser
+    }
+}
+
+/// The base url of the host the robots.txt of the url is read from, ending with a slash.
+pub fn robots_base(url: &url::Url) -> Option<String> {
+    let origin = url.origin();
+
+    if origin.is_tuple() {
+        Some(string_concat!(origin.ascii_serialization(), "/"))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_the_oldest_host() {
+        let cache = RobotsCache::new(2);
+
+        cache.insert("https://a.com/", RobotFileParser::new());
+        cache.insert("https://b.com/", RobotFileParser::new());
+        cache.insert("https://a.com/", RobotFileParser::new());
+        cache.insert("https://c.com/", RobotFileParser::new());
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("https://a.com/").is_none());
+        assert!(cache.get("https://b.com/").is_some());
+        assert!(cache.get("https://c.com/").is_some());
+    }
+
+    #[test]
+    fn retries_failed_hosts_before_the_ttl() {
+        let mut fetched = RobotFileParser::new();
+        fetched.modified();
+
+        let mut cached = CachedRobots {
+            parser: fetched,
+            failed: true,
+        };
+        assert!(!cached.is_stale(None));
+        assert!(!cached.is_stale(Some(Duration::from_secs(3600))));
+
+        cached.parser = RobotFileParser::new();
+        assert!(cached.is_stale(None));
+        assert!(cached.is_stale(Some(Duration::from_secs(3600))));
+
+        cached.failed = false;
+        assert!(!cached.is_stale(None));
+        assert!(cached.is_stale(Some(Duration::from_secs(3600))));
+    }
+
+    #[test]
+    fn keys_rules_by_host() {
+        let base = |url: &str| robots_base(&url::Url::parse(url).unwrap());
+
+        assert_eq!(
+            base("https://Docs.Example.com/a/b?c").as_deref(),
+            Some("https://docs.example.com/")
+        );
+        assert_eq!(
+            base("http://example.com:8080/").as_deref(),
+            Some("http://example.com:8080/")
+        );
+        assert_eq!(base("data:text/plain,robots"), None);
+    }
+}
@@ -1 +1,3 @@
+/// Cache of the robots.txt rules per host for a crawl.
+pub mod cache;
 pub mod parser;
@@ -57,6 +57,39 @@ struct RuleLine {
     allowance: bool,
 }
 
+/// Why a url is allowed or disallowed by the robots.txt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RobotsVerdict {
+    /// The robots.txt was not fetched yet so nothing is allowed.
+    NotFetched,
+    /// The robots.txt is missing so everything is allowed.
+    AllowAll,
+    /// The robots.txt is unauthorized or forbidden so everything is disallowed.
+    DisallowAll,
+    /// No rule of the group applying to the agent matches the url.
+    NoMatch,
+    /// A rule of the group applying to the agent matches the url.
+    Rule {
+        /// The user agents of the group, `*` for the default group.
+        group: Vec<String>,
+        /// The path of the rule.
+        path: String,
+        /// Is the rule an `Allow` line?
+        allowed: bool,
+    },
+}
+
+impl RobotsVerdict {
+    /// Is the url allowed?
+    pub fn allowed(&self) -> bool {
+        match self {
+            RobotsVerdict::NotFetched | RobotsVerdict::DisallowAll => false,
+            RobotsVerdict::AllowAll | RobotsVerdict::NoMatch => true,
+            RobotsVerdict::Rule { allowed, .. } => *allowed,
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 /// Determine the amount of request allowed between navigation or crawls.
 pub struct RequestRate {
@@ -109,6 +142,10 @@ pub struct RobotFileParser {
     /// Disallow list of agents to ignore.
     #[cfg(feature = "regex")]
     disallow_agents: HashSet<String>,
+    /// The etag of the robots.txt to revalidate with.
+    etag: Option<String>,
+    /// The last modified date of the robots.txt to revalidate with.
+    last_modified: Option<String>,
 }
 
 impl RuleLine {
@@ -156,6 +193,21 @@ impl RuleLine {
             _ => false,
         }
     }
+
+    /// The path of the rule as written in the robots.txt.
+    #[cfg(not(feature = "regex"))]
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The path of the rule as written in the robots.txt.
+    #[cfg(feature = "regex")]
+    fn path(&self) -> &str {
+        match self.path {
+            Some(ref regex) => regex.as_str(),
+            _ => "",
+        }
+    }
 }
 
 impl Entry {
@@ -190,12 +242,15 @@ impl Entry {
     /// - our agent applies to this entry
     /// - filename is URL decoded
     fn allowance(&self, filename: &str) -> bool {
-        for line in &self.rulelines {
-            if line.applies_to(filename) {
-                return line.allowance;
-            }
+        match self.matching_rule(filename) {
+            Some(line) => line.allowance,
+            _ => true,
         }
-        true
+    }
+
+    /// The first rule matching the filename.
+    fn matching_rule(&self, filename: &str) -> Option<&RuleLine> {
+        self.rulelines.iter().find(|line| line.applies_to(filename))
     }
 
     /// Add to user agent list
@@ -284,6 +339,8 @@ impl RobotFileParser {
             disallow_all: false,
             allow_all: false,
             last_checked: 0i64,
+            etag: None,
+            last_modified: None,
         }
         .into()
     }
@@ -302,10 +359,27 @@ impl RobotFileParser {
             wild_card_agent: false,
             allow_all: false,
             last_checked: 0i64,
+            etag: None,
+            last_modified: None,
         }
         .into()
     }
 
+    /// Forget the parsed rules before parsing a new robots.txt.
+    fn reset(&mut self) {
+        let last_checked = self.last_checked;
+        *self = *RobotFileParser::new();
+        self.last_checked = last_checked;
+    }
+
+    /// Was the robots.txt fetched longer than the ttl ago?
+    pub fn is_stale(&self, ttl: Duration) -> bool {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(time) => time.as_secs() as i64 - self.last_checked >= ttl.as_secs() as i64,
+            _ => true,
+        }
+    }
+
     /// Returns the time the robots.txt file was last fetched.
     ///
     /// This is useful for long-running web spiders that need to
@@ -325,32 +399,60 @@ impl RobotFileParser {
         }
     }
 
-    /// Reads the robots.txt URL and feeds it to the parser.
-    pub async fn read(&mut self, client: &Client, url: &str) {
+    /// Reads the robots.txt URL and feeds it to the parser. A robots.txt read before is revalidated
+    /// with its etag and last modified date and the rules are kept when it did not change. Returns
+    /// `false` when the request failed or the server errored and the rules were left as they were.
+    pub async fn read(&mut self, client: &Client, url: &str) -> bool {
+        use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+
         self.modified();
 
-        let request = client.get(string_concat!(url, "robots.txt"));
+        let mut request = client.get(string_concat!(url, "robots.txt"));
+
+        if let Some(ref etag) = self.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(ref last_modified) = self.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
 
         let res = match request.send().await {
             Ok(res) => res,
             Err(_) => {
-                return;
+                return false;
             }
         };
         let status = res.status();
 
         match status {
+            StatusCode::NOT_MODIFIED => true,
             StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                self.reset();
                 self.disallow_all = true;
+                true
             }
             status
                 if status >= StatusCode::BAD_REQUEST
                     && status < StatusCode::INTERNAL_SERVER_ERROR =>
             {
+                self.reset();
                 self.allow_all = true;
+                true
             }
-            StatusCode::OK => self.from_response(res).await,
-            _ => (),
+            StatusCode::OK => {
+                self.reset();
+                let header = |name| {
+                    res.headers()
+                        .get(name)
+                        .and_then(|v| v.to_str().ok())
+                        .map(String::from)
+                };
+                self.etag = header(ETAG);
+                self.last_modified = header(LAST_MODIFIED);
+                self.from_response(res).await;
+                true
+            }
+            _ => false,
         }
     }
 
@@ -542,33 +644,65 @@ impl RobotFileParser {
 
     /// Using the parsed robots.txt decide if useragent can fetch url
     pub fn can_fetch<T: AsRef<str>>(&self, useragent: T, url: &str) -> bool {
+        self.explain(useragent, url).allowed()
+    }
+
+    /// Using the parsed robots.txt decide why useragent can or can not fetch url. Only the group
+    /// naming the agent applies, the `*` group is used when no group names it.
+    pub fn explain<T: AsRef<str>>(&self, useragent: T, url: &str) -> RobotsVerdict {
         // Until the robots.txt file has been read or found not
         // to exist, we must assume that no url is allowable.
         // This prevents false positives when a user erronenously
         // calls can_fetch() before calling read().
         if self.allow_all {
-            true
-        } else if self.last_checked == 0 || self.disallow_all {
-            false
+            RobotsVerdict::AllowAll
+        } else if self.disallow_all {
+            RobotsVerdict::DisallowAll
+        } else if self.last_checked == 0 {
+            RobotsVerdict::NotFetched
         } else {
-            // search for given user agent matches
-            // the first match counts
-            let url_str = extract_path(&url);
-
-            if self.entry_allowed(&useragent, url_str) {
-                true
-            } else {
-                // try the default entry last
-                let default_entry = &self.default_entry;
+            let url_str = extract_path(url);
+
+            match self.group_for(useragent.as_ref()) {
+                Some(entry) => match entry.matching_rule(url_str) {
+                    Some(line) => RobotsVerdict::Rule {
+                        group: entry.useragents.clone(),
+                        path: line.path().into(),
+                        allowed: line.allowance,
+                    },
+                    _ => RobotsVerdict::NoMatch,
+                },
+                // agent not found ==> access granted
+                _ => RobotsVerdict::NoMatch,
+            }
+        }
+    }
 
-                if !default_entry.is_empty() {
-                    default_entry.allowance(url_str)
+    /// The rule group applying to the agent: the first group naming it or else the `*` group.
+    fn group_for(&self, useragent: &str) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.applies_to(useragent))
+            .or_else(|| {
+                if self.default_entry.is_empty() {
+                    None
                 } else {
-                    // agent not found ==> access granted
-                    true
+                    Some(&self.default_entry)
                 }
-            }
-        }
+            })
+    }
+
+    /// The `Allow` and `Disallow` rules of the group applying to the agent in order of precedence.
+    pub fn rules<T: AsRef<str>>(&self, useragent: T) -> Vec<(String, bool)> {
+        self.group_for(useragent.as_ref())
+            .map(|entry| {
+                entry
+                    .rulelines
+                    .iter()
+                    .map(|line| (line.path().to_string(), line.allowance))
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
     /// Is the entry apply to the robots.txt?
@@ -597,30 +731,13 @@ impl RobotFileParser {
         if self.last_checked == 0 {
             None
         } else {
-            let useragent = useragent.as_ref();
-            let crawl_delay: Option<Duration> = match useragent {
-                Some(ua) => {
-                    for entry in &self.entries {
-                        if entry.applies_to(ua) {
-                            return entry.get_crawl_delay();
-                        }
-                    }
-                    None
-                }
-                _ => None,
+            let useragent = match useragent {
+                Some(ua) => ua.as_str(),
+                _ => "*",
             };
 
-            if crawl_delay.is_some() {
-                crawl_delay
-            } else {
-                let default_entry = &self.default_entry;
-
-                if !default_entry.is_empty() {
-                    return default_entry.get_crawl_delay();
-                }
-
-                None
-            }
+            self.group_for(useragent)
+                .and_then(|entry| entry.get_crawl_delay())
         }
     }
 
@@ -638,3 +755,64 @@ impl RobotFileParser {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTS_TXT: [&str; 11] = [
+        "User-agent: spider",
+        "Disallow: /private/",
+        "Crawl-delay: 2",
+        "",
+        "User-agent: *",
+        "Disallow: /search",
+        "Disallow: /private/",
+        "Allow: /",
+        "Crawl-delay: 5",
+        "",
+        "Sitemap: https://choosealicense.com/sitemap.xml",
+    ];
+
+    #[test]
+    fn applies_agent_groups() {
+        let mut parser = RobotFileParser::new();
+        assert_eq!(parser.explain("spider", "/"), RobotsVerdict::NotFetched);
+
+        parser.modified();
+        parser.parse(&ROBOTS_TXT);
+
+        // the group naming the agent replaces the default group.
+        assert!(parser.can_fetch("spider", "https://choosealicense.com/search"));
+        assert!(!parser.can_fetch("spider", "https://choosealicense.com/private/a"));
+        assert!(!parser.can_fetch("other", "https://choosealicense.com/search"));
+        assert!(parser.can_fetch("other", "https://choosealicense.com/licenses/"));
+
+        assert_eq!(
+            parser.explain("spider/2.0", "https://choosealicense.com/private/a"),
+            RobotsVerdict::Rule {
+                group: vec!["spider".into()],
+                path: "/private/".into(),
+                allowed: false,
+            }
+        );
+        assert_eq!(parser.rules("spider").len(), 1);
+        assert_eq!(parser.rules("other").len(), 3);
+
+        assert_eq!(
+            parser.get_crawl_delay(&Some(Box::new("spider".into()))),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(parser.get_crawl_delay(&None), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn expires_with_ttl() {
+        let mut parser = RobotFileParser::new();
+        assert!(parser.is_stale(Duration::from_secs(60)));
+
+        parser.modified();
+        assert!(!parser.is_stale(Duration::from_secs(60)));
+        assert!(parser.is_stale(Duration::ZERO));
+    }
+}
@@ -151,6 +151,22 @@ pub struct Page {
     #[cfg(feature = "chrome")]
     /// The screenshot bytes of the page.
     pub screenshot_bytes: Option<Vec<u8>>,
+    #[cfg(feature = "chrome")]
+    /// The third party requests blocked by the consent mode of the browser.
+    pub blocked_third_party_requests: Option<Vec<String>>,
+    #[cfg(feature = "chrome")]
+    /// The links inside the open shadow roots and the same-origin iframes of the page.
+    pub deep_links: Option<Vec<String>>,
+    #[cfg(feature = "chrome")]
+    /// The `Performance.getMetrics` metrics and the paint timings of the page collected after load.
+    pub performance_metrics: Option<Box<crate::configuration::PerformanceMetrics>>,
+    #[cfg(feature = "chrome")]
+    /// The anti-bot challenge found on the page and whether it cleared before the content was captured.
+    pub challenge: Option<crate::configuration::ChallengeOutcome>,
+    /// The language of the page as a ISO 639-1 code ex: `en`, detected by the language filter of the crawl.
+    pub language: Option<String>,
+    /// The HTTP version of the response ex: `HTTP/3` when the page was fetched over QUIC.
+    pub http_version: Option<reqwest::Version>,
     #[cfg(feature = "openai")]
     /// The credits used from OpenAI in order.
     pub openai_credits_used: Option<Vec<crate::features::openai_common::OpenAIUsage>>,
@@ -163,6 +179,25 @@ pub struct Page {
     pub should_retry: bool,
     /// A WAF was found on the page.
     pub waf_check: bool,
+    /// The page was not modified since the last crawl. The server answered the conditional request with a 304 and the body was not downloaded.
+    pub unchanged: bool,
+    /// The page was fetched over HTTP first and fetched again with Chrome for the reason.
+    pub escalation: Option<crate::features::fetch_routing::EscalationReason>,
+    /// The links followed from the start page to the page.
+    pub(crate) depth: usize,
+    /// The values of the fields of the [`crate::features::extractor::Extractor`] of the crawl.
+    pub extracted:
+        Option<Box<hashbrown::HashMap<String, crate::features::extractor::ExtractedValue>>>,
+    /// The title, byline, publish date and main text of the page from the [`crate::features::text_extraction::TextExtraction`] of the crawl.
+    pub article: Option<Box<crate::features::text_extraction::Article>>,
+    /// The chunks of the text of the page from the [`crate::features::chunking::Chunker`] of the crawl.
+    pub chunks: Option<Vec<crate::features::chunking::Chunk>>,
+    #[cfg(feature = "llm_extraction")]
+    /// The JSON extracted by the [`crate::features::llm_extraction::LlmExtraction`] of the crawl.
+    pub llm_extracted: Option<Box<serde_json::Value>>,
+    #[cfg(feature = "encoding")]
+    /// The charset declared by the `Content-Type` header of the response, the html is decoded with it.
+    pub(crate) charset: Option<&'static encoding_rs::Encoding>,
 }
 
 /// Represent a page visited.
@@ -190,6 +225,22 @@ pub struct Page {
     #[cfg(feature = "chrome")]
     /// The screenshot bytes of the page.
     pub screenshot_bytes: Option<Vec<u8>>,
+    #[cfg(feature = "chrome")]
+    /// The third party requests blocked by the consent mode of the browser.
+    pub blocked_third_party_requests: Option<Vec<String>>,
+    #[cfg(feature = "chrome")]
+    /// The links inside the open shadow roots and the same-origin iframes of the page.
+    pub deep_links: Option<Vec<String>>,
+    #[cfg(feature = "chrome")]
+    /// The `Performance.getMetrics` metrics and the paint timings of the page collected after load.
+    pub performance_metrics: Option<Box<crate::configuration::PerformanceMetrics>>,
+    #[cfg(feature = "chrome")]
+    /// The anti-bot challenge found on the page and whether it cleared before the content was captured.
+    pub challenge: Option<crate::configuration::ChallengeOutcome>,
+    /// The language of the page as a ISO 639-1 code ex: `en`, detected by the language filter of the crawl.
+    pub language: Option<String>,
+    /// The HTTP version of the response ex: `HTTP/3` when the page was fetched over QUIC.
+    pub http_version: Option<reqwest::Version>,
     #[cfg(feature = "openai")]
     /// The credits used from OpenAI in order.
     pub openai_credits_used: Option<Vec<crate::features::openai_common::OpenAIUsage>>,
@@ -202,6 +253,25 @@ pub struct Page {
     pub should_retry: bool,
     /// A WAF was found on the page.
     pub waf_check: bool,
+    /// The page was not modified since the last crawl. The server answered the conditional request with a 304 and the body was not downloaded.
+    pub unchanged: bool,
+    /// The page was fetched over HTTP first and fetched again with Chrome for the reason.
+    pub escalation: Option<crate::features::fetch_routing::EscalationReason>,
+    /// The links followed from the start page to the page.
+    pub(crate) depth: usize,
+    /// The values of the fields of the [`crate::features::extractor::Extractor`] of the crawl.
+    pub extracted:
+        Option<Box<hashbrown::HashMap<String, crate::features::extractor::ExtractedValue>>>,
+    /// The title, byline, publish date and main text of the page from the [`crate::features::text_extraction::TextExtraction`] of the crawl.
+    pub article: Option<Box<crate::features::text_extraction::Article>>,
+    /// The chunks of the text of the page from the [`crate::features::chunking::Chunker`] of the crawl.
+    pub chunks: Option<Vec<crate::features::chunking::Chunk>>,
+    #[cfg(feature = "llm_extraction")]
+    /// The JSON extracted by the [`crate::features::llm_extraction::LlmExtraction`] of the crawl.
+    pub llm_extracted: Option<Box<serde_json::Value>>,
+    #[cfg(feature = "encoding")]
+    /// The charset declared by the `Content-Type` header of the response, the html is decoded with it.
+    pub(crate) charset: Option<&'static encoding_rs::Encoding>,
 }
 
 /// Validate link and push into the map
@@ -292,29 +362,15 @@ pub fn convert_abs_path(base: &Url, href: &str) -> Url {
     }
 }
 
-/// extract the valid domains from a url.
-fn extract_root_domain(domain: &str) -> &str {
-    let parts: Vec<&str> = domain.split('.').collect();
-
-    if parts.len() >= 3 {
-        let start_index = parts.len() - 2;
-        if let Some(start_pos) = domain.find(parts[start_index]) {
-            &domain[start_pos..]
-        } else {
-            domain
-        }
-    } else if parts.len() == 2 {
-        &parts[0]
+/// check for subdomain matches. A matcher without a dot is the brand of the domain matching every public suffix.
+fn is_subdomain(subdomain: &str, domain: &str) -> bool {
+    if domain.contains('.') {
+        crate::utils::registrable_domain(subdomain) == crate::utils::registrable_domain(domain)
     } else {
-        domain
+        crate::utils::domain_brand(subdomain) == domain
     }
 }
 
-/// check for subdomain matches
-fn is_subdomain(subdomain: &str, domain: &str) -> bool {
-    extract_root_domain(subdomain) == extract_root_domain(domain)
-}
-
 /// validation to match a domain to parent host and the top level redirect for the crawl 'parent_host' and 'base_host' being the input start domain.
 pub fn parent_host_match(
     host_name: Option<&str>,
@@ -354,9 +410,9 @@ pub fn get_page_selectors_base(u: &Url, subdomains: bool, tld: bool) -> Option<R
         let dname = domain_name(&u);
 
         let dname = if tld {
-            extract_root_domain(dname)
+            crate::utils::domain_brand(dname)
         } else {
-            dname
+            crate::utils::registrable_domain(dname)
         };
 
         (
@@ -449,6 +505,16 @@ pub fn build(url: &str, res: PageResponse) -> Page {
         chrome_page: None,
         #[cfg(feature = "chrome")]
         screenshot_bytes: res.screenshot_bytes,
+        #[cfg(feature = "chrome")]
+        blocked_third_party_requests: res.blocked_third_party_requests,
+        #[cfg(feature = "chrome")]
+        deep_links: res.deep_links,
+        #[cfg(feature = "chrome")]
+        performance_metrics: res.performance_metrics,
+        #[cfg(feature = "chrome")]
+        challenge: res.challenge,
+        language: None,
+        http_version: res.http_version,
         #[cfg(feature = "openai")]
         openai_credits_used: res.openai_credits_used,
         #[cfg(feature = "openai")]
@@ -456,6 +522,16 @@ pub fn build(url: &str, res: PageResponse) -> Page {
         page_links: None,
         should_retry,
         waf_check: res.waf_check,
+        unchanged: res.status_code == StatusCode::NOT_MODIFIED,
+        escalation: None,
+        depth: 0,
+        extracted: None,
+        article: None,
+        chunks: None,
+        #[cfg(feature = "llm_extraction")]
+        llm_extracted: None,
+        #[cfg(feature = "encoding")]
+        charset: res.charset,
     }
 }
 
@@ -481,6 +557,8 @@ pub fn build(_: &str, res: PageResponse) -> Page {
             },
             _ => None,
         },
+        #[cfg(feature = "encoding")]
+        charset: res.charset,
         ..Default::default()
     }
 }
@@ -498,6 +576,15 @@ pub struct PageLinkBuildSettings {
     pub subdomains: bool,
 }
 
+/// The options of the request of a streamed page.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PageFetchOptions<'a> {
+    /// Send the validators cached for the url and record the validators of the response.
+    pub revalidation: Option<&'a crate::features::revalidation::Revalidation>,
+    /// Send the request over HTTP/3 to the hosts advertising it.
+    pub http3: Option<&'a crate::features::http3::Http3>,
+}
+
 /// Default byte capacity for response stream collecting.
 const DEFAULT_BYTE_CAPACITY: u64 = 8 * 1024;
 
@@ -530,8 +617,47 @@ impl Page {
     }
 
     /// New page with rewriter
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(url = url))
+    )]
     pub async fn new_page_streaming<
         A: PartialEq + Eq + Sync + Send + Clone + Default + std::hash::Hash + From<String>,
+    >(
+        url: &str,
+        client: &Client,
+        only_html: bool,
+        selectors: &mut RelativeSelectors,
+        external_domains_caseless: &Box<HashSet<CaseInsensitiveString>>,
+        r_settings: &PageLinkBuildSettings,
+        map: &mut hashbrown::HashSet<A>,
+        ssg_map: Option<&mut hashbrown::HashSet<A>>,
+        prior_domain: &Option<Box<Url>>,
+        domain_parsed: &mut Option<Box<Url>>,
+    ) -> Self {
+        Self::new_page_streaming_with_options(
+            url,
+            client,
+            only_html,
+            selectors,
+            external_domains_caseless,
+            r_settings,
+            map,
+            ssg_map,
+            prior_domain,
+            domain_parsed,
+            &PageFetchOptions::default(),
+        )
+        .await
+    }
+
+    /// New page with rewriter, sending the request with the options.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(url = url))
+    )]
+    pub async fn new_page_streaming_with_options<
+        A: PartialEq + Eq + Sync + Send + Clone + Default + std::hash::Hash + From<String>,
     >(
         url: &str,
         client: &Client,
@@ -543,14 +669,28 @@ impl Page {
         ssg_map: Option<&mut hashbrown::HashSet<A>>,
         prior_domain: &Option<Box<Url>>,
         mut domain_parsed: &mut Option<Box<Url>>,
+        options: &PageFetchOptions<'_>,
     ) -> Self {
         use crate::utils::{
-            handle_response_bytes_writer, modify_selectors, setup_default_response,
-            AllowedDomainTypes,
+            get_content_type, handle_response_bytes_writer, modify_selectors, rewriter_encoding,
+            setup_default_response, AllowedDomainTypes,
         };
-        let page_response = match client.get(url).send().await {
+        let (revalidation, http3) = (options.revalidation, options.http3);
+        let request = match revalidation {
+            Some(revalidation) => client.get(url).headers(revalidation.headers(url)),
+            _ => client.get(url),
+        };
+        let response = match http3 {
+            Some(http3) => http3.send(url, request).await,
+            _ => request.send().await,
+        };
+        let page_response = match response {
             Ok(res) if res.status().is_success() => {
+                if let Some(revalidation) = revalidation {
+                    revalidation.record_response(url, res.headers());
+                }
                 let cell = tokio::sync::OnceCell::new();
+                let content_type = get_content_type(&res);
 
                 let mut collected_bytes = bytes::BytesMut::with_capacity(
                     res.content_length().unwrap_or(DEFAULT_BYTE_CAPACITY) as usize,
@@ -644,6 +784,7 @@ impl Page {
                 let settings = lol_html::send::Settings {
                     element_content_handlers,
                     adjust_charset_on_meta_tag: true,
+                    encoding: rewriter_encoding(content_type.as_deref()),
                     ..lol_html::send::Settings::new_for_handler_types()
                 };
 
@@ -669,6 +810,12 @@ impl Page {
                     .0
                     .content
                     .replace(Box::new(collected_bytes.freeze().into()));
+                #[cfg(feature = "encoding")]
+                {
+                    response.0.charset = content_type
+                        .as_deref()
+                        .and_then(crate::utils::charset::header_charset);
+                }
 
                 if r_settings.ssg_build {
                     if let Some(mut ssg_map) = ssg_map {
@@ -753,6 +900,8 @@ impl Page {
         automation_scripts: &Option<AutomationScripts>,
         viewport: &Option<crate::configuration::Viewport>,
         request_timeout: &Option<Box<Duration>>,
+        deep_links: bool,
+        performance_metrics: &Option<Box<crate::configuration::PerformanceMetricsConfig>>,
     ) -> Self {
         let page_resource = crate::utils::fetch_page_html(
             &url,
@@ -766,6 +915,8 @@ impl Page {
             automation_scripts,
             viewport,
             request_timeout,
+            deep_links,
+            performance_metrics,
         )
         .await;
         let mut p = build(url, page_resource);
@@ -993,6 +1144,90 @@ impl Page {
         self.html.is_none()
     }
 
+    /// The links followed from the start page to the page. The start page is at depth 0 and its links at depth 1. The depth
+    /// is tracked when the crawl sets a priority frontier, a depth budget or a crawl predicate, it is 0 otherwise.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The SimHash fingerprint of the visible text of the page. Pages with nearly the same content have fingerprints a
+    /// few bits apart, compare them with [`crate::features::near_duplicate::distance`]. `None` is returned when the page
+    /// has no text.
+    pub fn fingerprint(&self) -> Option<u64> {
+        crate::features::near_duplicate::html_fingerprint(&self.get_html())
+    }
+
+    /// The page as markdown, the links and images resolved against the page url.
+    pub fn markdown(&self) -> String {
+        let url = Url::parse(self.get_url()).ok();
+        crate::features::markdown::html_to_markdown(&self.get_html(), url.as_ref())
+    }
+
+    /// The navigation targets set by javascript on the page, the `onclick` handlers, url attributes and route manifests, resolved
+    /// against the page url and passing the link filters of the selectors.
+    #[cfg(not(feature = "decentralized"))]
+    pub fn js_links(
+        &self,
+        discovery: &crate::features::js_links::JsLinkDiscovery,
+        selectors: &RelativeSelectors,
+    ) -> HashSet<CaseInsensitiveString> {
+        let mut map = HashSet::new();
+
+        for href in discovery.discover(&self.get_html()) {
+            push_link(
+                &self.base,
+                &href,
+                &mut map,
+                &selectors.0,
+                &selectors.1[0],
+                &selectors.1[1],
+                &selectors.2,
+                &selectors.0,
+                &self.external_domains_caseless,
+                false,
+            );
+        }
+
+        map
+    }
+
+    /// The language of the page as a ISO 639-1 code ex: `en` from the `lang` attribute of the html or the text of the page with the
+    /// feature flag `language_detection`.
+    pub fn detect_language(&self) -> Option<String> {
+        crate::features::language::detect_language(&self.get_html())
+    }
+
+    /// The elements of the page matching the CSS selector ex: `div.article h2` with their text, html and attributes.
+    /// Invalid selectors match nothing.
+    pub fn select(&self, selector: &str) -> Vec<crate::features::extractor::Selection> {
+        crate::features::extractor::select(&self.get_html(), selector)
+    }
+
+    /// Evaluate the XPath 1.0 expression against the html of the page ex: `//div[@class="article"]/h2` or `//a/@href`.
+    /// Requires the feature flag `xpath`.
+    #[cfg(feature = "xpath")]
+    pub fn xpath(
+        &self,
+        expression: &str,
+    ) -> Result<crate::features::xpath::XPathValue, crate::features::xpath::XPathError> {
+        crate::features::xpath::evaluate(&self.get_html(), expression)
+    }
+
+    /// The nodes of the page matching the XPath 1.0 expression. Invalid expressions match nothing. Requires the
+    /// feature flag `xpath`.
+    #[cfg(feature = "xpath")]
+    pub fn select_xpath(&self, expression: &str) -> Vec<crate::features::xpath::XPathNode> {
+        crate::features::xpath::select_xpath(&self.get_html(), expression)
+    }
+
+    /// The JSON-LD, microdata, RDFa, OpenGraph and Twitter card data of the page with the urls resolved against the
+    /// page url. Requires the feature flag `structured_data`.
+    #[cfg(feature = "structured_data")]
+    pub fn structured_data(&self) -> crate::features::structured_data::StructuredData {
+        let base = Url::parse(self.get_url()).ok();
+        crate::features::structured_data::extract(&self.get_html(), base.as_ref())
+    }
+
     /// Url getter for page.
     #[cfg(not(feature = "decentralized"))]
     pub fn get_url(&self) -> &str {
@@ -1010,25 +1245,39 @@ impl Page {
     pub fn get_timeout(&self) -> Option<Duration> {
         if self.status_code == 429 {
             const MAX_TIMEOUT: Duration = Duration::from_secs(30);
-            if let Some(ref headers) = self.headers {
-                if let Some(retry_after) = headers.get(reqwest::header::RETRY_AFTER) {
-                    if let Ok(retry_after_str) = retry_after.to_str() {
-                        if let Ok(seconds) = retry_after_str.parse::<u64>() {
-                            return Some(Duration::from_secs(seconds).min(MAX_TIMEOUT));
-                        }
-                        if let Ok(date) = httpdate::parse_http_date(retry_after_str) {
-                            if let Ok(duration) = date.duration_since(std::time::SystemTime::now())
-                            {
-                                return Some(duration.min(MAX_TIMEOUT));
-                            }
-                        }
-                    }
-                }
-            };
+            return self
+                .get_retry_after()
+                .map(|timeout| timeout.min(MAX_TIMEOUT));
         }
         None
     }
 
+    #[cfg(not(feature = "headers"))]
+    /// Get the delay asked by the `Retry-After` header in seconds or as a date. Requires the feature flag `headers`.
+    pub fn get_retry_after(&self) -> Option<Duration> {
+        None
+    }
+
+    #[cfg(feature = "headers")]
+    /// Get the delay asked by the `Retry-After` header in seconds or as a date. Requires the feature flag `headers`.
+    pub fn get_retry_after(&self) -> Option<Duration> {
+        let retry_after = self
+            .headers
+            .as_ref()?
+            .get(reqwest::header::RETRY_AFTER)?
+            .to_str()
+            .ok()?;
+
+        if let Ok(seconds) = retry_after.parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+
+        httpdate::parse_http_date(retry_after)
+            .ok()?
+            .duration_since(std::time::SystemTime::now())
+            .ok()
+    }
+
     /// Url getter for page after redirects.
     #[cfg(not(feature = "decentralized"))]
     pub fn get_url_final(&self) -> &str {
@@ -1108,7 +1357,14 @@ impl Page {
                 if html.is_empty() {
                     Default::default()
                 } else {
-                    auto_encoder::auto_encode_bytes(html)
+                    #[cfg(feature = "encoding")]
+                    {
+                        crate::utils::charset::decode(self.charset, html)
+                    }
+                    #[cfg(not(feature = "encoding"))]
+                    {
+                        auto_encoder::auto_encode_bytes(html)
+                    }
                 }
             }
             _ => Default::default(),
@@ -1220,6 +1476,32 @@ impl Page {
         }
     }
 
+    /// Add the links found in the open shadow roots and the same-origin iframes of the page.
+    #[cfg(all(not(feature = "decentralized"), feature = "chrome"))]
+    fn push_deep_links<A: PartialEq + Eq + std::hash::Hash + From<String>>(
+        &self,
+        selectors: &RelativeSelectors,
+        deep_links: &Option<Vec<String>>,
+        map: &mut HashSet<A>,
+    ) {
+        if let Some(deep_links) = deep_links {
+            for href in deep_links {
+                push_link(
+                    &self.base,
+                    href,
+                    map,
+                    &selectors.0,
+                    &selectors.1[0],
+                    &selectors.1[1],
+                    &selectors.2,
+                    &selectors.0,
+                    &self.external_domains_caseless,
+                    false,
+                );
+            }
+        }
+    }
+
     /// Find the links as a stream using string resource validation
     #[inline(always)]
     #[cfg(all(not(feature = "decentralized")))]
@@ -1291,6 +1573,9 @@ impl Page {
             }
         }
 
+        #[cfg(feature = "chrome")]
+        self.push_deep_links(selectors, &self.deep_links, &mut map);
+
         map
     }
 
@@ -1680,6 +1965,7 @@ impl Page {
                             &configuration.request_timeout,
                             &context_id,
                             &configuration.viewport,
+                            &configuration.proxy_rotation,
                         )
                         .await
                         {
@@ -1693,8 +1979,12 @@ impl Page {
                                 )
                                 .await;
 
-                            crate::features::chrome::setup_chrome_events(&new_page, &configuration)
-                                .await;
+                            crate::features::chrome::setup_chrome_events(
+                                &new_page,
+                                &configuration,
+                                &html_resource,
+                            )
+                            .await;
 
                             let page_resource = crate::utils::fetch_page_html_chrome_base(
                                 &html_resource,
@@ -1722,6 +2012,8 @@ impl Page {
                                 &configuration.automation_scripts,
                                 &configuration.viewport,
                                 &configuration.request_timeout,
+                                configuration.chrome_deep_links,
+                                &configuration.performance_metrics,
                             )
                             .await;
 
@@ -1739,6 +2031,8 @@ impl Page {
 
                     match rx.await {
                         Ok(v) => {
+                            self.push_deep_links(selectors, &v.deep_links, &mut map);
+
                             let extended_map = self
                                 .links_stream_base::<A>(
                                     selectors,
@@ -1847,6 +2141,10 @@ impl Page {
                 }
             }
         }
+
+        #[cfg(feature = "chrome")]
+        self.push_deep_links(selectors, &self.deep_links, &mut map);
+
         map
     }
 
@@ -2137,6 +2435,91 @@ async fn test_abs_path() {
     );
 }
 
+#[cfg(all(not(feature = "decentralized"), feature = "chrome"))]
+#[tokio::test]
+async fn test_deep_links() {
+    let link_result = "https://choosealicense.com/";
+    let mut page = build(
+        link_result,
+        PageResponse {
+            content: Some(Box::new(
+                r#"<html><body><a href="/about/">About</a><my-nav></my-nav></body></html>"#
+                    .to_string()
+                    .into(),
+            )),
+            deep_links: Some(vec![
+                "https://choosealicense.com/licenses/".into(),
+                "https://example.com/".into(),
+            ]),
+            ..Default::default()
+        },
+    );
+    let selectors = get_page_selectors(link_result, false, false).expect("valid selectors");
+    let links = page.links(&selectors).await;
+
+    assert!(links.contains(&CaseInsensitiveString::from("https://choosealicense.com/about/")));
+    assert!(links.contains(&CaseInsensitiveString::from(
+        "https://choosealicense.com/licenses/"
+    )));
+    assert!(!links.contains(&CaseInsensitiveString::from("https://example.com/")));
+
+    page.deep_links = None;
+    assert!(!page.links(&selectors).await.contains(&CaseInsensitiveString::from(
+        "https://choosealicense.com/licenses/"
+    )));
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn test_declared_charset() {
+    let (html, _, _) = encoding_rs::WINDOWS_1251
+        .encode("<html><body><p>Привет, как у тебя дела сегодня вечером?</p></body></html>");
+    let page = build(
+        "https://example.com/",
+        PageResponse {
+            content: Some(Box::new(html.to_vec().into())),
+            charset: Some(encoding_rs::WINDOWS_1251),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        page.get_html(),
+        "<html><body><p>Привет, как у тебя дела сегодня вечером?</p></body></html>"
+    );
+    assert_eq!(page.get_html_bytes_u8(), &html[..]);
+    assert_eq!(page.get_html_encoded("windows-1251"), page.get_html());
+}
+
+#[test]
+fn test_parent_host_match() {
+    let matches = |url: &str, subdomains: bool, tld: bool, host: &str| {
+        let selectors = get_page_selectors(url, subdomains, tld).expect("valid selectors");
+        parent_host_match(
+            Some(host),
+            &selectors.0,
+            &selectors.1[0],
+            &selectors.2,
+            &selectors.0,
+        )
+    };
+    let url = "https://www.example.co.uk/";
+
+    assert_eq!(crate::utils::registrable_domain("www.example.com"), "example.com");
+    assert_eq!(crate::utils::registrable_domain("a.b.example.co.uk"), "example.co.uk");
+    assert_eq!(crate::utils::registrable_domain("127.0.0.1"), "127.0.0.1");
+    assert_eq!(crate::utils::domain_brand("shop.example.co.uk"), "example");
+
+    assert!(matches(url, false, false, "www.example.co.uk"));
+    assert!(!matches(url, false, false, "blog.example.co.uk"));
+    assert!(matches(url, true, false, "blog.example.co.uk"));
+    assert!(!matches(url, true, false, "other.co.uk"));
+    assert!(!matches(url, true, false, "example.de"));
+    assert!(matches(url, false, true, "example.de"));
+    assert!(matches(url, false, true, "shop.example.com"));
+    assert!(!matches(url, false, true, "example-shop.com"));
+}
+
 #[cfg(all(feature = "time", not(feature = "decentralized")))]
 #[tokio::test]
 async fn test_duration() {
@@ -0,0 +1,251 @@
+use crate::features::checkpoint::{read_list, read_u32, read_u64, write_str};
+use crate::utils::{fnv1a_extend, FNV_OFFSET};
+use hashbrown::HashSet;
+use std::io::{Error, ErrorKind, Read, Write};
+
+/// The least links removed kept by a filter.
+const MIN_REMOVED_LINKS: usize = 1024;
+
+/// A bloom filter of links. The filter answers if a link was inserted with a chance of false positives and never a
+/// false negative. The links removed are kept exactly until they are inserted again, up to a hundredth of the expected
+/// links. Past that the links removed stay in the filter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BloomFilter {
+    /// The bits of the filter.
+    bits: Vec<u64>,
+    /// The amount of bits.
+    bit_count: u64,
+    /// The hashes per link.
+    hashes: u32,
+    /// The links inserted that were not in the filter yet.
+    len: usize,
+    /// The links expected, used to size the filter.
+    expected_links: usize,
+    /// The rate of false positives at the expected links.
+    false_positive_rate: f64,
+    /// The links removed from the filter.
+    removed: HashSet<String>,
+}
+
+/// Hash the link with a seed. The hash is stable across builds so the bits can be saved and loaded.
+fn hash_link(link: &str, seed: u64) -> u64 {
+    fnv1a_extend(fnv1a_extend(FNV_OFFSET, seed.to_le_bytes()), link.bytes())
+}
+
+/// The bit positions of the link with double hashing.
+fn positions(link: &str, hashes: u32, bit_count: u64) -> impl Iterator<Item = u64> {
+    let h1 = hash_link(link, 0);
+    let h2 = hash_link(link, 0x9e37_79b9_7f4a_7c15) | 1;
+
+    (0..hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % bit_count)
+}
+
+impl BloomFilter {
+    /// A filter sized for the expected links at the false positive rate.
+    pub fn new(expected_links: usize, false_positive_rate: f64) -> Self {
+        let n = expected_links.max(1) as f64;
+        let p = false_positive_rate.clamp(f64::MIN_POSITIVE, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+        let bit_count = (-(n * p.ln()) / (ln2 * ln2)).ceil().max(64.0) as u64;
+        let hashes = ((bit_count as f64 / n) * ln2).round().clamp(1.0, 32.0) as u32;
+
+        Self {
+            bits: vec![0; bit_count.div_ceil(64) as usize],
+            bit_count,
+            hashes,
+            len: 0,
+            expected_links,
+            false_positive_rate,
+            removed: HashSet::new(),
+        }
+    }
+
+    /// Insert the link.
+    pub fn insert(&mut self, link: &str) {
+        let mut added = false;
+
+        for position in positions(link, self.hashes, self.bit_count) {
+            let (word, bit) = ((position / 64) as usize, 1u64 << (position % 64));
+            if self.bits[word] & bit == 0 {
+                self.bits[word] |= bit;
+                added = true;
+            }
+        }
+
+        if self.removed.remove(link) || added {
+            self.len += 1;
+        }
+    }
+
+    /// Whether the link was inserted, with the chance of a false positive.
+    pub fn contains(&self, link: &str) -> bool {
+        !self.removed.contains(link)
+            && positions(link, self.hashes, self.bit_count).all(|position| {
+                self.bits[(position / 64) as usize] & (1u64 << (position % 64)) != 0
+            })
+    }
+
+    /// Remove the link so it can be inserted again. Returns `false` when the link was not in the filter or the links
+    /// removed are full.
+    pub fn remove(&mut self, link: &str) -> bool {
+        if self.contains(link) && self.removed.len() < self.max_removed() {
+            self.removed.insert(link.to_string());
+            self.len = self.len.saturating_sub(1);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The most links removed kept.
+    fn max_removed(&self) -> usize {
+        (self.expected_links / 100).max(MIN_REMOVED_LINKS)
+    }
+
+    /// The links inserted, links colliding with the links before are not counted.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no link was inserted.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove every link.
+    pub fn clear(&mut self) {
+        self.bits.iter_mut().for_each(|word| *word = 0);
+        self.removed.clear();
+        self.len = 0;
+    }
+
+    /// The links expected.
+    pub fn expected_links(&self) -> usize {
+        self.expected_links
+    }
+
+    /// The rate of false positives at the expected links.
+    pub fn false_positive_rate(&self) -> f64 {
+        self.false_positive_rate
+    }
+
+    /// The memory used by the bits in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        self.bits.len() * 8
+    }
+
+    /// Encode the filter: the expected links, the false positive rate, the hashes, the links inserted, the bits and the
+    /// links removed as little endian values.
+    pub fn encode(&self, w: &mut impl Write) -> std::io::Result<()> {
+        w.write_all(&(self.expected_links as u64).to_le_bytes())?;
+        w.write_all(&self.false_positive_rate.to_le_bytes())?;
+        w.write_all(&self.hashes.to_le_bytes())?;
+        w.write_all(&(self.len as u64).to_le_bytes())?;
+        w.write_all(&self.bit_count.to_le_bytes())?;
+
+        for word in &self.bits {
+            w.write_all(&word.to_le_bytes())?;
+        }
+
+        w.write_all(&(self.removed.len() as u32).to_le_bytes())?;
+        for link in &self.removed {
+            write_str(w, link)?;
+        }
+
+        Ok(())
+    }
+
+    /// Decode a filter written by [`BloomFilter::encode`].
+    pub fn decode(r: &mut impl Read) -> std::io::Result<Self> {
+        let expected_links = read_u64(r)? as usize;
+        let false_positive_rate = f64::from_bits(read_u64(r)?);
+        let hashes = read_u32(r)?;
+        let len = read_u64(r)? as usize;
+        let bit_count = read_u64(r)?;
+
+        if bit_count == 0 || hashes == 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "empty bloom filter"));
+        }
+
+        let words = bit_count.div_ceil(64) as usize;
+        let mut bits = Vec::with_capacity(words.min(1 << 20));
+
+        for _ in 0..words {
+            bits.push(read_u64(r)?);
+        }
+
+        Ok(Self {
+            bits,
+            bit_count,
+            hashes,
+            len,
+            expected_links,
+            false_positive_rate,
+            removed: read_list(r)?.into_iter().collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bloom_false_positive_rate() {
+        let mut bloom = BloomFilter::new(10_000, 0.01);
+
+        for i in 0..10_000 {
+            bloom.insert(&format!("https://example.com/{i}"));
+        }
+
+        assert!((0..10_000).all(|i| bloom.contains(&format!("https://example.com/{i}"))));
+
+        let false_positives = (0..10_000)
+            .filter(|i| bloom.contains(&format!("https://example.org/{i}")))
+            .count();
+
+        assert!(false_positives < 200, "{} false positives", false_positives);
+        assert!(bloom.size_in_bytes() < 16 * 1024);
+    }
+
+    #[test]
+    fn bloom_remove() {
+        let mut bloom = BloomFilter::new(100, 0.01);
+
+        bloom.insert("https://example.com/a");
+        bloom.remove("https://example.com/a");
+        assert!(!bloom.contains("https://example.com/a"));
+        assert!(bloom.is_empty());
+
+        bloom.insert("https://example.com/a");
+        assert!(bloom.contains("https://example.com/a"));
+        assert_eq!(bloom.len(), 1);
+
+        bloom.clear();
+        assert!(!bloom.contains("https://example.com/a"));
+
+        for i in 0..MIN_REMOVED_LINKS + 1 {
+            bloom.insert(&format!("https://example.com/{i}"));
+        }
+        assert!((0..MIN_REMOVED_LINKS).all(|i| bloom.remove(&format!("https://example.com/{i}"))));
+        assert!(!bloom.remove(&format!("https://example.com/{MIN_REMOVED_LINKS}")));
+        assert!(bloom.contains(&format!("https://example.com/{MIN_REMOVED_LINKS}")));
+    }
+
+    #[test]
+    fn bloom_roundtrip() {
+        let mut bloom = BloomFilter::new(1000, 0.001);
+
+        bloom.insert("https://example.com/a");
+        bloom.insert("https://example.com/b");
+        bloom.remove("https://example.com/b");
+
+        let mut bytes = Vec::new();
+        bloom.encode(&mut bytes).unwrap();
+        let decoded = BloomFilter::decode(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(decoded, bloom);
+        assert!(decoded.contains("https://example.com/a"));
+        assert!(!decoded.contains("https://example.com/b"));
+    }
+}
@@ -0,0 +1,70 @@
+use encoding_rs::Encoding;
+
+/// The encoding of the `charset` parameter of a `Content-Type` header value ex: `text/html; charset=Shift_JIS`.
+pub fn header_charset(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+
+        if name.trim().eq_ignore_ascii_case("charset") {
+            Encoding::for_label_no_replacement(value.trim().trim_matches(['"', '\'']).as_bytes())
+        } else {
+            None
+        }
+    })
+}
+
+/// The html decoded to UTF-8 with the byte order mark, the charset declared by the `Content-Type` header, then the
+/// meta declaration or the encoding guessed from the bytes.
+pub fn decode(declared: Option<&'static Encoding>, html: &[u8]) -> String {
+    match declared {
+        Some(encoding) if Encoding::for_bom(html).is_none() => {
+            encoding.decode_without_bom_handling(html).0.into_owned()
+        }
+        _ => auto_encoder::auto_encode_bytes(html),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_header_charsets() {
+        assert_eq!(
+            header_charset("text/html; charset=\"Shift_JIS\""),
+            Some(encoding_rs::SHIFT_JIS)
+        );
+        assert_eq!(
+            header_charset("application/xhtml+xml; Charset=euc-kr"),
+            Some(encoding_rs::EUC_KR)
+        );
+        assert_eq!(header_charset("text/html"), None);
+    }
+
+    #[test]
+    fn decode_pages() {
+        let (html, _, _) = encoding_rs::WINDOWS_1251
+            .encode("<html><body><p>Привет, как у тебя дела сегодня вечером?</p></body></html>");
+
+        assert_eq!(
+            decode(Some(encoding_rs::WINDOWS_1251), &html),
+            "<html><body><p>Привет, как у тебя дела сегодня вечером?</p></body></html>"
+        );
+
+        let (html, _, _) = encoding_rs::SHIFT_JIS.encode(
+            r#"<html><head><meta charset="Shift_JIS"></head><body><p>こんにちは、世界</p></body></html>"#,
+        );
+
+        assert_eq!(
+            decode(None, &html),
+            r#"<html><head><meta charset="Shift_JIS"></head><body><p>こんにちは、世界</p></body></html>"#
+        );
+        assert_eq!(
+            decode(
+                Some(encoding_rs::EUC_JP),
+                b"\xEF\xBB\xBF<p>d\xC3\xA9j\xC3\xA0 vu</p>"
+            ),
+            "<p>déjà vu</p>"
+        );
+    }
+}
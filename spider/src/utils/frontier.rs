@@ -0,0 +1,519 @@
+use crate::configuration::{DepthBudget, PriorityFrontier};
+use crate::features::should_crawl::{CrawlDecision, FetchStrategy};
+use crate::utils::ProcessLinkStatus;
+use crate::CaseInsensitiveString;
+use hashbrown::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// Score the shallowest links first.
+pub fn breadth_first(_url: &Url, depth: usize, _referrer: Option<&Url>) -> f64 {
+    -(depth as f64)
+}
+
+/// Score the deepest links first.
+pub fn depth_first(_url: &Url, depth: usize, _referrer: Option<&Url>) -> f64 {
+    depth as f64
+}
+
+/// Score the links by the path segments they share with the page linking to them, the shallowest first on a tie.
+pub fn same_path_first(url: &Url, depth: usize, referrer: Option<&Url>) -> f64 {
+    let shared = match (
+        url.path_segments(),
+        referrer.and_then(|r| r.path_segments()),
+    ) {
+        (Some(segments), Some(referrer_segments))
+            if Some(url.host()) == referrer.map(Url::host) =>
+        {
+            segments
+                .zip(referrer_segments)
+                .take_while(|(a, b)| !a.is_empty() && a == b)
+                .count()
+        }
+        _ => 0,
+    };
+
+    shared as f64 - depth as f64 / 1000.0
+}
+
+/// The date of a `/yyyy/mm/dd/` or `/yyyy/mm/` or `/yyyy/` path as a day count.
+fn path_date(url: &Url) -> Option<u32> {
+    let segments: Vec<&str> = url.path_segments()?.collect();
+
+    segments.iter().enumerate().find_map(|(i, segment)| {
+        let year = segment
+            .parse::<u32>()
+            .ok()
+            .filter(|y| segment.len() == 4 && (1990..=2100).contains(y))?;
+        let part = |offset: usize, max: u32| {
+            segments
+                .get(i + offset)
+                .filter(|s| s.len() <= 2)
+                .and_then(|s| s.parse::<u32>().ok())
+                .filter(|v| (1..=max).contains(v))
+        };
+        let month = part(1, 12);
+        let day = month.and_then(|_| part(2, 31));
+
+        Some((year * 12 + month.unwrap_or(0)) * 31 + day.unwrap_or(0))
+    })
+}
+
+/// Score the links with the most recent date in the path first. The links without a date follow, the shallowest first.
+pub fn freshness_first(url: &Url, depth: usize, _referrer: Option<&Url>) -> f64 {
+    match path_date(url) {
+        Some(date) => date as f64,
+        _ => -(depth as f64),
+    }
+}
+
+/// The depth and referrer of a discovered url.
+#[derive(Debug, Clone)]
+struct FrontierEntry {
+    /// The links followed from the start of the crawl.
+    depth: usize,
+    /// The page that linked the url first, kept for the ordering.
+    referrer: Option<Arc<Url>>,
+    /// The score set by the crawl predicate in place of the score of the ordering.
+    priority: Option<f64>,
+    /// The fetcher set by the crawl predicate.
+    fetch: Option<FetchStrategy>,
+}
+
+/// Order the urls of the crawl by the score of the [`PriorityFrontier`]. The crawl tasks record where each link was found
+/// and the crawl loop takes the best scored batch of the pending urls. The depth of the urls is tracked for the ordering,
+/// the [`DepthBudget`] and the crawl predicate.
+#[derive(Debug, Default)]
+pub struct Frontier {
+    /// The ordering, the urls keep the discovery order without it.
+    config: Option<PriorityFrontier>,
+    /// The urls taken at once when the ordering does not set a batch size.
+    concurrency: usize,
+    /// The discovered urls.
+    entries: Mutex<HashMap<CaseInsensitiveString, FrontierEntry>>,
+    /// Track the urls taken from the frontier until their page is handled.
+    track_pending: bool,
+    /// The urls taken and not handled yet.
+    pending: Mutex<HashSet<CaseInsensitiveString>>,
+    /// The max pages crawled from a depth.
+    depth_budget: Option<DepthBudget>,
+    /// The pages crawled for each limit of the depth budget.
+    depth_spent: Mutex<HashMap<usize, usize>>,
+    /// The crawl predicate set the priority of an url, the urls are ordered without an ordering.
+    prioritized: AtomicBool,
+    /// Record the discovered urls for the crawl predicate.
+    track_discovered: bool,
+    /// The urls the crawl predicate skipped, not asked again.
+    rejected: Mutex<HashSet<CaseInsensitiveString>>,
+}
+
+impl Frontier {
+    /// A new frontier for the ordering taking batches of the concurrency limit by default.
+    pub fn new(config: Option<PriorityFrontier>, concurrency: usize) -> Self {
+        Self {
+            config,
+            concurrency,
+            ..Default::default()
+        }
+    }
+
+    /// Track the urls taken from the frontier until their page is handled, used by the crawl checkpoints.
+    pub fn with_pending(mut self, track_pending: bool) -> Self {
+        self.track_pending = track_pending;
+        self
+    }
+
+    /// Cap the pages crawled from a depth. The shallowest urls are taken first when set.
+    pub fn with_depth_budget(mut self, depth_budget: Option<DepthBudget>) -> Self {
+        self.depth_budget = depth_budget.filter(|budget| !budget.limits.is_empty());
+        self
+    }
+
+    /// Record the depth of the discovered urls for the crawl predicate without an ordering or depth budget.
+    pub fn with_discovered(mut self, track_discovered: bool) -> Self {
+        self.track_discovered = track_discovered;
+        self
+    }
+
+    /// Whether the urls are ordered.
+    pub fn enabled(&self) -> bool {
+        self.config.is_some()
+    }
+
+    /// Whether the discovered urls are recorded.
+    fn tracks_discovered(&self) -> bool {
+        self.enabled() || self.depth_budget.is_some() || self.track_discovered
+    }
+
+    /// The links followed from the start page to the url. Unknown urls are at depth 0.
+    pub fn depth(&self, link: &str) -> usize {
+        match self.entries.lock() {
+            Ok(entries) => entries
+                .get(&CaseInsensitiveString::new(link))
+                .map_or(0, |entry| entry.depth),
+            _ => 0,
+        }
+    }
+
+    /// Whether the url was discovered.
+    pub fn contains(&self, link: &CaseInsensitiveString) -> bool {
+        match self.entries.lock() {
+            Ok(entries) => entries.contains_key(link),
+            _ => false,
+        }
+    }
+
+    /// Whether the crawl predicate skipped the url.
+    pub fn is_rejected(&self, link: &CaseInsensitiveString) -> bool {
+        match self.rejected.lock() {
+            Ok(rejected) => rejected.contains(link),
+            _ => false,
+        }
+    }
+
+    /// The fetcher the crawl predicate set for the url.
+    pub fn fetch_strategy(&self, link: &str) -> Option<FetchStrategy> {
+        match self.entries.lock() {
+            Ok(entries) => entries
+                .get(&CaseInsensitiveString::new(link))
+                .and_then(|entry| entry.fetch),
+            _ => None,
+        }
+    }
+
+    /// Block the allowed url when the depth budget of its depth is spent, the budget is spent by the url otherwise.
+    pub fn admit(
+        &self,
+        link: &CaseInsensitiveString,
+        status: ProcessLinkStatus,
+    ) -> ProcessLinkStatus {
+        let budget = match &self.depth_budget {
+            Some(budget) if status == ProcessLinkStatus::Allowed => budget,
+            _ => return status,
+        };
+        let depth = self.depth(link.inner());
+
+        match self.depth_spent.lock() {
+            Ok(mut spent) => {
+                let limits = budget.limits.range(..=depth);
+
+                if limits
+                    .clone()
+                    .any(|(min_depth, max)| spent.get(min_depth).copied().unwrap_or(0) >= *max)
+                {
+                    return ProcessLinkStatus::Blocked;
+                }
+
+                for (min_depth, _) in limits {
+                    *spent.entry(*min_depth).or_default() += 1;
+                }
+
+                status
+            }
+            _ => status,
+        }
+    }
+
+    /// The urls taken from the frontier that were not handled yet.
+    pub fn pending(&self) -> Vec<CaseInsensitiveString> {
+        match self.pending.lock() {
+            Ok(pending) => pending.iter().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Mark an url taken from the frontier as handled without crawling it.
+    pub fn skipped(&self, link: &CaseInsensitiveString) {
+        if self.track_pending {
+            if let Ok(mut pending) = self.pending.lock() {
+                pending.remove(link);
+            }
+        }
+    }
+
+    /// Record the links found on the page. A link keeps the depth and referrer it was found with first.
+    pub fn discovered(&self, referrer: &str, links: &HashSet<CaseInsensitiveString>) {
+        if self.track_pending {
+            self.skipped(&CaseInsensitiveString::new(referrer));
+        }
+
+        if !self.tracks_discovered() || links.is_empty() {
+            return;
+        }
+
+        if let Ok(mut entries) = self.entries.lock() {
+            let depth = entries
+                .get(&CaseInsensitiveString::new(referrer))
+                .map_or(1, |entry| entry.depth + 1);
+            let referrer = if self.enabled() {
+                Url::parse(referrer).ok().map(Arc::new)
+            } else {
+                None
+            };
+
+            for link in links {
+                entries
+                    .entry(link.clone())
+                    .or_insert_with(|| FrontierEntry {
+                        depth,
+                        referrer: referrer.clone(),
+                        priority: None,
+                        fetch: None,
+                    });
+            }
+        }
+    }
+
+    /// Record the priority and fetcher the crawl predicate set for the discovered urls and the urls it skipped.
+    pub fn decided(&self, decisions: Vec<(CaseInsensitiveString, CrawlDecision)>) {
+        if let (Ok(mut entries), Ok(mut rejected)) = (self.entries.lock(), self.rejected.lock()) {
+            for (link, decision) in decisions {
+                if !decision.crawl {
+                    rejected.insert(link);
+                } else if let Some(entry) = entries.get_mut(&link) {
+                    if decision.priority.is_some() {
+                        entry.priority = decision.priority;
+                        self.prioritized.store(true, Ordering::Relaxed);
+                    }
+                    entry.fetch = decision.fetch;
+                }
+            }
+        }
+    }
+
+    /// Take the next urls to crawl from the pending links: every link without an ordering, else the best scored batch.
+    pub fn next_batch(
+        &self,
+        links: &mut HashSet<CaseInsensitiveString>,
+    ) -> Vec<CaseInsensitiveString> {
+        let batch = self.take_batch(links);
+
+        if self.track_pending {
+            if let Ok(mut pending) = self.pending.lock() {
+                pending.extend(batch.iter().cloned());
+            }
+        }
+
+        batch
+    }
+
+    /// Take the next batch of urls.
+    fn take_batch(&self, links: &mut HashSet<CaseInsensitiveString>) -> Vec<CaseInsensitiveString> {
+        let config = match &self.config {
+            Some(config) => Some(config),
+            _ if self.depth_budget.is_some() => return self.take_shallowest(links),
+            _ if self.prioritized.load(Ordering::Relaxed) => None,
+            _ => return links.drain().collect(),
+        };
+        let batch_size = config
+            .and_then(|config| config.batch_size)
+            .unwrap_or(self.concurrency)
+            .max(1);
+
+        if links.len() <= 1 {
+            return links.drain().collect();
+        }
+
+        let mut scored: Vec<(f64, CaseInsensitiveString)> = match self.entries.lock() {
+            Ok(entries) => links
+                .drain()
+                .map(|link| {
+                    let entry = entries.get(&link);
+                    let score = match (entry.and_then(|entry| entry.priority), config) {
+                        (Some(priority), _) => priority,
+                        (_, Some(config)) => match Url::parse(link.inner()) {
+                            Ok(url) => config.scorer.score(
+                                &url,
+                                entry.map_or(0, |entry| entry.depth),
+                                entry.and_then(|entry| entry.referrer.as_deref()),
+                            ),
+                            _ => f64::MIN,
+                        },
+                        _ => 0.0,
+                    };
+                    (score, link)
+                })
+                .collect(),
+            _ => return links.drain().collect(),
+        };
+
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let rest = scored.split_off(batch_size.min(scored.len()));
+        links.extend(rest.into_iter().map(|(_, link)| link));
+
+        scored.into_iter().map(|(_, link)| link).collect()
+    }
+
+    /// Take the shallowest urls in the url order, a batch of the concurrency limit at a time.
+    fn take_shallowest(
+        &self,
+        links: &mut HashSet<CaseInsensitiveString>,
+    ) -> Vec<CaseInsensitiveString> {
+        let mut sorted: Vec<(usize, CaseInsensitiveString)> = match self.entries.lock() {
+            Ok(entries) => links
+                .drain()
+                .map(|link| (entries.get(&link).map_or(0, |entry| entry.depth), link))
+                .collect(),
+            _ => return links.drain().collect(),
+        };
+
+        sorted.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.inner().cmp(b.1.inner())));
+
+        let rest = sorted.split_off(self.concurrency.max(1).min(sorted.len()));
+        links.extend(rest.into_iter().map(|(_, link)| link));
+
+        sorted.into_iter().map(|(_, link)| link).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::FrontierScorer;
+
+    fn links(urls: &[&str]) -> HashSet<CaseInsensitiveString> {
+        urls.iter().map(|u| CaseInsensitiveString::new(u)).collect()
+    }
+
+    fn inner(batch: Vec<CaseInsensitiveString>) -> Vec<String> {
+        batch.iter().map(|l| l.inner().to_string()).collect()
+    }
+
+    #[test]
+    fn scores_builtin_orders() {
+        let url = Url::parse("https://example.com/blog/2025/01/06/post").unwrap();
+        let referrer = Url::parse("https://example.com/blog/2025/").unwrap();
+        let other = Url::parse("https://example.com/about").unwrap();
+
+        assert!(breadth_first(&url, 1, None) > breadth_first(&url, 2, None));
+        assert!(depth_first(&url, 2, None) > depth_first(&url, 1, None));
+        assert!(
+            same_path_first(&url, 3, Some(&referrer)) > same_path_first(&other, 1, Some(&referrer))
+        );
+        assert!(freshness_first(&url, 5, None) > freshness_first(&other, 1, None));
+        assert!(
+            freshness_first(&url, 1, None)
+                > freshness_first(
+                    &Url::parse("https://example.com/2024/12/31/").unwrap(),
+                    1,
+                    None
+                )
+        );
+    }
+
+    #[test]
+    fn takes_best_scored_batch() {
+        let frontier = Frontier::new(
+            Some(PriorityFrontier::new(FrontierScorer::BreadthFirst).with_batch_size(2)),
+            10,
+        );
+
+        frontier.discovered("https://example.com/", &links(&["https://example.com/a"]));
+        frontier.discovered(
+            "https://example.com/a",
+            &links(&["https://example.com/a/b"]),
+        );
+        frontier.discovered(
+            "https://example.com/a/b",
+            &links(&["https://example.com/a/b/c"]),
+        );
+
+        let mut pending = links(&[
+            "https://example.com/a/b/c",
+            "https://example.com/a/b",
+            "https://example.com/a",
+        ]);
+
+        assert_eq!(
+            inner(frontier.next_batch(&mut pending)),
+            vec!["https://example.com/a", "https://example.com/a/b"]
+        );
+        assert_eq!(
+            inner(frontier.next_batch(&mut pending)),
+            vec!["https://example.com/a/b/c"]
+        );
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn custom_score_and_disabled() {
+        fn shortest(url: &Url, _depth: usize, _referrer: Option<&Url>) -> f64 {
+            -(url.as_str().len() as f64)
+        }
+
+        let frontier = Frontier::new(Some(PriorityFrontier::with_score(shortest)), 1);
+        let mut pending = links(&["https://example.com/long/path", "https://example.com/x"]);
+
+        assert_eq!(
+            inner(frontier.next_batch(&mut pending)),
+            vec!["https://example.com/x"]
+        );
+
+        let frontier = Frontier::new(None, 1);
+        let mut pending = links(&["https://example.com/a", "https://example.com/b"]);
+        assert_eq!(frontier.next_batch(&mut pending).len(), 2);
+        assert!(frontier.pending().is_empty());
+    }
+
+    #[test]
+    fn depth_budget() {
+        let frontier =
+            Frontier::new(None, 2).with_depth_budget(Some(DepthBudget::new().with_limit(2, 1)));
+
+        frontier.discovered(
+            "https://example.com/",
+            &links(&["https://example.com/a", "https://example.com/b"]),
+        );
+        frontier.discovered(
+            "https://example.com/a",
+            &links(&["https://example.com/a/1", "https://example.com/a/2"]),
+        );
+
+        assert_eq!(frontier.depth("https://example.com/"), 0);
+        assert_eq!(frontier.depth("https://example.com/b"), 1);
+        assert_eq!(frontier.depth("https://example.com/a/2"), 2);
+
+        let mut pending = links(&[
+            "https://example.com/a/2",
+            "https://example.com/b",
+            "https://example.com/a/1",
+            "https://example.com/a",
+        ]);
+        let batch = frontier.next_batch(&mut pending);
+        assert_eq!(
+            inner(batch.clone()),
+            vec!["https://example.com/a", "https://example.com/b"]
+        );
+
+        let admitted: Vec<ProcessLinkStatus> = batch
+            .into_iter()
+            .chain(frontier.next_batch(&mut pending))
+            .map(|link| frontier.admit(&link, ProcessLinkStatus::Allowed))
+            .collect();
+        assert_eq!(
+            admitted,
+            vec![
+                ProcessLinkStatus::Allowed,
+                ProcessLinkStatus::Allowed,
+                ProcessLinkStatus::Allowed,
+                ProcessLinkStatus::Blocked
+            ]
+        );
+    }
+
+    #[test]
+    fn tracks_pending() {
+        let frontier = Frontier::new(None, 1).with_pending(true);
+        let mut pending = links(&["https://example.com/a", "https://example.com/b"]);
+
+        frontier.next_batch(&mut pending);
+        assert_eq!(frontier.pending().len(), 2);
+
+        frontier.discovered("https://example.com/a", &links(&["https://example.com/c"]));
+        frontier.skipped(&CaseInsensitiveString::new("https://example.com/b"));
+        assert!(frontier.pending().is_empty());
+        assert!(!frontier.contains(&CaseInsensitiveString::new("https://example.com/c")));
+    }
+}
@@ -5,6 +5,11 @@ use reqwest::{
     ClientBuilder,
 };
 
+/// Case-insensitive, multi-value headers shared with the browser, keeping the casing of the names. The HTTP path keeps
+/// the [`HeaderMap`] the type wraps, convert it with `Headers::from`.
+#[cfg(feature = "chrome")]
+pub use chromiumoxide::headers::Headers;
+
 /// Setup the default headers for the request.
 pub fn setup_default_headers(
     client_builder: ClientBuilder,
@@ -12,6 +17,16 @@ pub fn setup_default_headers(
     header_map: HeaderMap,
     url: &Option<Box<url::Url>>,
 ) -> ClientBuilder {
+    client_builder.default_headers(default_headers(configuration, header_map, url))
+}
+
+/// The default headers of the requests: the configured headers, a spoofed referrer, the host when preserved and the
+/// header map.
+pub fn default_headers(
+    configuration: &Configuration,
+    header_map: HeaderMap,
+    url: &Option<Box<url::Url>>,
+) -> HeaderMap {
     let mut headers = match configuration.headers {
         Some(ref h) => *h.clone(),
         None => crate::configuration::SerializableHeaderMap::default(),
@@ -44,7 +59,7 @@ pub fn setup_default_headers(
 
     headers.extend(header_map);
 
-    client_builder.default_headers(headers.0)
+    headers.0
 }
 
 /// Build the headers to use to act like a browser
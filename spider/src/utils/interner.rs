@@ -1,3 +1,5 @@
+use crate::configuration::VisitedSet;
+use crate::utils::bloom::BloomFilter;
 use crate::CaseInsensitiveString;
 use hashbrown::HashSet;
 use std::hash::Hash;
@@ -50,6 +52,8 @@ where
     pub(crate) links_visited: HashSet<SymbolUsize>,
     /// The string interner.
     pub(crate) interner: StringInterner<Backend>,
+    /// The bloom filter used in place of the links when set.
+    pub(crate) bloom: Option<Box<BloomFilter>>,
     /// Phantom data to link the generic type.
     _marker: PhantomData<K>,
 }
@@ -62,6 +66,7 @@ where
         Self {
             links_visited: HashSet::new(),
             interner: StringInterner::new(),
+            bloom: None,
             _marker: PhantomData,
         }
     }
@@ -76,17 +81,68 @@ where
         Self::default()
     }
 
+    /// New list bucket storing the links in a bloom filter. The bucket uses a fixed amount of memory with a chance of
+    /// reporting links as visited that were not and can not list the links.
+    pub fn with_bloom(expected_links: usize, false_positive_rate: f64) -> Self {
+        Self {
+            bloom: Some(Box::new(BloomFilter::new(
+                expected_links,
+                false_positive_rate,
+            ))),
+            ..Self::default()
+        }
+    }
+
+    /// New list bucket for the visited set.
+    pub fn from_visited_set(visited_set: &VisitedSet) -> Self {
+        match visited_set {
+            VisitedSet::Exact => Self::new(),
+            VisitedSet::Bloom {
+                expected_links,
+                false_positive_rate,
+            } => Self::with_bloom(*expected_links, *false_positive_rate),
+        }
+    }
+
+    /// The visited set of the bucket.
+    pub fn visited_set(&self) -> VisitedSet {
+        match &self.bloom {
+            Some(bloom) => VisitedSet::Bloom {
+                expected_links: bloom.expected_links(),
+                false_positive_rate: bloom.false_positive_rate(),
+            },
+            _ => VisitedSet::Exact,
+        }
+    }
+
     /// Add a new link to the bucket.
     #[inline(always)]
     pub fn insert(&mut self, link: K) {
-        let symbol = self.interner.get_or_intern(link.as_ref());
-        self.links_visited.insert(symbol);
+        match self.bloom.as_mut() {
+            Some(bloom) => bloom.insert(link.as_ref()),
+            _ => {
+                let symbol = self.interner.get_or_intern(link.as_ref());
+                self.links_visited.insert(symbol);
+            }
+        }
+    }
+
+    /// Remove a link from the bucket so it can be visited again.
+    #[inline(always)]
+    pub fn remove(&mut self, link: &K) {
+        if let Some(bloom) = self.bloom.as_mut() {
+            bloom.remove(link.as_ref());
+        } else if let Some(symbol) = self.interner.get(link.as_ref()) {
+            self.links_visited.remove(&symbol);
+        }
     }
 
     /// Does the bucket contain the link.
     #[inline(always)]
     pub fn contains(&self, link: &K) -> bool {
-        if let Some(symbol) = self.interner.get(link.as_ref()) {
+        if let Some(bloom) = self.bloom.as_ref() {
+            bloom.contains(link.as_ref())
+        } else if let Some(symbol) = self.interner.get(link.as_ref()) {
             self.links_visited.contains(&symbol)
         } else {
             false
@@ -95,20 +151,29 @@ where
 
     /// The bucket length.
     pub fn len(&self) -> usize {
-        self.links_visited.len()
+        match self.bloom.as_ref() {
+            Some(bloom) => bloom.len(),
+            _ => self.links_visited.len(),
+        }
     }
 
-    /// Drain the bucket.
+    /// Drain the bucket. The bloom filter is cleared without yielding the links.
     pub fn drain(&mut self) -> hashbrown::hash_set::Drain<'_, SymbolUsize> {
+        if let Some(bloom) = self.bloom.as_mut() {
+            bloom.clear();
+        }
         self.links_visited.drain()
     }
 
     /// Clear the bucket.
     pub fn clear(&mut self) {
+        if let Some(bloom) = self.bloom.as_mut() {
+            bloom.clear();
+        }
         self.links_visited.clear()
     }
 
-    /// Get a vector of all the inner values of the links in the bucket.
+    /// Get a vector of all the inner values of the links in the bucket. This is empty with a bloom filter.
     pub fn get_links(&self) -> HashSet<K>
     where
         K: Hash + Clone + From<String>,
@@ -120,12 +185,28 @@ where
             .collect()
     }
 
+    /// The links in the bucket. This is empty with a bloom filter.
+    pub fn links(&self) -> impl Iterator<Item = &str> + '_ {
+        self.links_visited
+            .iter()
+            .filter_map(move |symbol| self.interner.resolve(*symbol))
+    }
+
     /// Extend with current links.
     #[inline(always)]
     pub fn extend_links(&mut self, links: &mut HashSet<K>, msg: HashSet<K>)
     where
         K: Clone,
     {
+        if self.bloom.is_some() {
+            for link in msg {
+                if !self.contains(&link) {
+                    links.insert(link);
+                }
+            }
+            return;
+        }
+
         for link in msg {
             let symbol = self.interner.get_or_intern(link.as_ref());
             if !self.links_visited.contains(&symbol) {
@@ -140,11 +221,7 @@ where
     where
         K: Clone,
     {
-        if let Some(symbol) = self.interner.get(s.as_ref()) {
-            if !self.links_visited.contains(&symbol) {
-                links.insert(s);
-            }
-        } else {
+        if !self.contains(&s) {
             links.insert(s);
         }
     }
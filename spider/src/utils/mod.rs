@@ -1,7 +1,18 @@
+/// Bloom filter of the links visited.
+pub mod bloom;
+/// Decode the pages with the charset declared by their response.
+#[cfg(feature = "encoding")]
+pub mod charset;
+/// Scored ordering of the crawl frontier.
+pub mod frontier;
 /// Utils to modify the HTTP header.
 pub mod header_utils;
 /// String interner.
 pub mod interner;
+/// Per host adaptive rate limiting.
+pub mod rate_limit;
+/// Re-queue failed urls in the crawl frontier.
+pub mod retry;
 /// A trie struct.
 pub mod trie;
 
@@ -196,11 +207,26 @@ pub struct PageResponse {
     pub status_code: StatusCode,
     /// The final url destination after any redirects.
     pub final_url: Option<String>,
+    #[cfg(feature = "encoding")]
+    /// The charset declared by the `Content-Type` header of the response.
+    pub charset: Option<&'static encoding_rs::Encoding>,
     /// The message of the response error if any.
     pub error_for_status: Option<Result<Response, Error>>,
     #[cfg(feature = "chrome")]
     /// The screenshot bytes of the page. The ScreenShotConfig bytes boolean needs to be set to true.
     pub screenshot_bytes: Option<Vec<u8>>,
+    #[cfg(feature = "chrome")]
+    /// The third party requests blocked by the consent mode of the browser.
+    pub blocked_third_party_requests: Option<Vec<String>>,
+    #[cfg(feature = "chrome")]
+    /// The links inside the open shadow roots and the same-origin iframes of the page.
+    pub deep_links: Option<Vec<String>>,
+    #[cfg(feature = "chrome")]
+    /// The performance metrics of the page collected after load.
+    pub performance_metrics: Option<Box<crate::configuration::PerformanceMetrics>>,
+    #[cfg(feature = "chrome")]
+    /// The outcome of the wait for the anti-bot challenge found on the page.
+    pub challenge: Option<crate::configuration::ChallengeOutcome>,
     #[cfg(feature = "openai")]
     /// The credits used from OpenAI in order.
     pub openai_credits_used: Option<Vec<crate::features::openai_common::OpenAIUsage>>,
@@ -209,6 +235,8 @@ pub struct PageResponse {
     pub extra_ai_data: Option<Vec<crate::page::AIResults>>,
     /// A WAF was found on the page.
     pub waf_check: bool,
+    /// The HTTP version of the response.
+    pub http_version: Option<reqwest::Version>,
 }
 
 /// wait for event with timeout
@@ -352,6 +380,10 @@ pub async fn page_wait(
                         chromiumoxide::cdp::browser_protocol::network::EventLoadingFinished,
                     >(page, network_idle.timeout)
                     .await;
+
+                    if let Some(after) = network_idle.abort_streams_after {
+                        crate::features::chrome::abort_open_streams(page, after).await;
+                    }
                 }
                 _ => (),
             }
@@ -473,9 +505,9 @@ pub struct ChromeHTTPReqRes {
     /// The HTTP method of the request.
     pub method: String,
     /// The HTTP response headers for the request.
-    pub response_headers: std::collections::HashMap<String, String>,
+    pub response_headers: crate::utils::header_utils::Headers,
     /// The HTTP request headers for the request.
-    pub request_headers: std::collections::HashMap<String, String>,
+    pub request_headers: crate::utils::header_utils::Headers,
     /// The HTTP protocol of the request.
     pub protocol: String,
 }
@@ -489,8 +521,8 @@ pub async fn perform_chrome_http_request(
     let mut waf_check = false;
     let mut status_code = StatusCode::OK;
     let mut method = String::from("GET");
-    let mut response_headers = std::collections::HashMap::default();
-    let mut request_headers = std::collections::HashMap::default();
+    let mut response_headers = crate::utils::header_utils::Headers::default();
+    let mut request_headers = crate::utils::header_utils::Headers::default();
     let mut protocol = String::from("http/1.1");
 
     let page_base =
@@ -525,14 +557,8 @@ pub async fn perform_chrome_http_request(
                                 _ => (),
                             }
 
-                            match response.headers.inner().as_object() {
-                                Some(res_headers) => {
-                                    for (k, v) in res_headers {
-                                        response_headers.insert(k.to_string(), v.to_string());
-                                    }
-                                }
-                                _ => (),
-                            }
+                            response_headers =
+                                crate::utils::header_utils::Headers::from_cdp(&response.headers);
 
                             if !response.url.starts_with(source) {
                                 waf_check = match response.security_details {
@@ -822,7 +848,7 @@ impl ResponseLike for HttpResponseLike {
     }
 }
 
-/// Convert headers to header map
+/// Convert headers to header map. The `\n` separated values like the `Set-Cookie` of the browser are split.
 #[cfg(any(
     feature = "cache_chrome_hybrid",
     feature = "headers",
@@ -834,14 +860,16 @@ pub fn convert_headers(
     let mut header_map = reqwest::header::HeaderMap::new();
 
     for (index, items) in headers.iter().enumerate() {
-        match reqwest::header::HeaderValue::from_str(&items.1) {
-            Ok(head) => {
-                use std::str::FromStr;
-                match reqwest::header::HeaderName::from_str(&items.0) {
-                    Ok(key) => {
-                        header_map.insert(key, head);
+        use std::str::FromStr;
+        match reqwest::header::HeaderName::from_str(&items.0) {
+            Ok(key) => {
+                for value in items.1.split('\n') {
+                    match reqwest::header::HeaderValue::from_str(value) {
+                        Ok(head) => {
+                            header_map.append(key.clone(), head);
+                        }
+                        _ => (),
                     }
-                    _ => (),
                 }
             }
             _ => (),
@@ -975,13 +1003,13 @@ pub async fn cache_chrome_response(
                 "http/3.0" | "http/3" => HttpVersion::H3,
                 _ => HttpVersion::Http11,
             },
-            headers: chrome_http_req_res.response_headers,
+            headers: chrome_http_req_res.response_headers.to_hash_map(),
         };
         put_hybrid_cache(
             &string_concat!("GET", ":", target_url),
             http_response,
             &"GET",
-            chrome_http_req_res.request_headers,
+            chrome_http_req_res.request_headers.to_hash_map(),
         )
         .await;
     }
@@ -998,6 +1026,10 @@ pub async fn cache_chrome_response(
 
 #[cfg(feature = "chrome")]
 /// Perform a network request to a resource extracting all content as text streaming via chrome.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(url = source))
+)]
 pub async fn fetch_page_html_chrome_base(
     source: &str,
     page: &chromiumoxide::Page,
@@ -1012,6 +1044,8 @@ pub async fn fetch_page_html_chrome_base(
     automation_scripts: &Option<AutomationScripts>,
     viewport: &Option<crate::configuration::Viewport>,
     request_timeout: &Option<Box<std::time::Duration>>,
+    deep_links: bool,
+    performance_metrics: &Option<Box<crate::configuration::PerformanceMetricsConfig>>,
 ) -> Result<PageResponse, chromiumoxide::error::CdpError> {
     let mut chrome_http_req_res = ChromeHTTPReqRes::default();
 
@@ -1063,6 +1097,30 @@ pub async fn fetch_page_html_chrome_base(
         None
     };
 
+    let challenge = match wait_for.as_ref().and_then(|wait_for| wait_for.challenge.as_ref()) {
+        Some(challenge) if timeout_error.is_none() && !page_set => {
+            #[cfg(feature = "captcha_solver")]
+            if let Some(solver) = &challenge.solver {
+                crate::features::captcha_solver::solve_page_captcha(
+                    page,
+                    solver,
+                    url_target.unwrap_or(source),
+                )
+                .await;
+            }
+            crate::features::chrome::wait_for_challenge(page, challenge).await
+        }
+        _ => None,
+    };
+
+    if let Some(challenge) = &challenge {
+        chrome_http_req_res.status_code = if challenge.cleared {
+            StatusCode::OK
+        } else {
+            StatusCode::FORBIDDEN
+        };
+    }
+
     let page_response = if timeout_error.is_none() && chrome_http_req_res.status_code.is_success() {
         // we do not need to wait for navigation if content is assigned. The method set_content already handles this.
         let final_url = if wait_for_navigation {
@@ -1140,6 +1198,35 @@ pub async fn fetch_page_html_chrome_base(
         set_page_response_headers(&mut chrome_http_req_res, &mut page_response);
         set_page_response_cookies(&mut page_response, &page).await;
 
+        if let Ok(blocked) = page.blocked_third_party_requests().await {
+            if !blocked.is_empty() {
+                page_response.blocked_third_party_requests = Some(blocked);
+            }
+        }
+
+        if deep_links && ok {
+            let links = tokio::time::timeout(
+                tokio::time::Duration::from_secs(10),
+                page.evaluate(crate::features::chrome::DEEP_LINKS),
+            )
+            .await;
+
+            if let Ok(Ok(links)) = links {
+                if let Ok(links) = links.into_value::<Vec<String>>() {
+                    if !links.is_empty() {
+                        page_response.deep_links = Some(links);
+                    }
+                }
+            }
+        }
+
+        if let Some(performance_metrics) = performance_metrics {
+            page_response.performance_metrics =
+                crate::features::chrome::performance_metrics(page, performance_metrics)
+                    .await
+                    .map(Box::new);
+        }
+
         if openai_config.is_some() {
             run_openai_request(
                 match url_target {
@@ -1164,6 +1251,7 @@ pub async fn fetch_page_html_chrome_base(
         }
         page_response.status_code = chrome_http_req_res.status_code;
         page_response.waf_check = chrome_http_req_res.waf_check;
+        page_response.challenge = challenge;
         if !page_set {
             cache_chrome_response(&source, &page_response, chrome_http_req_res).await;
         }
@@ -1174,6 +1262,7 @@ pub async fn fetch_page_html_chrome_base(
         set_page_response_headers(&mut chrome_http_req_res, &mut page_response);
         page_response.status_code = chrome_http_req_res.status_code;
         page_response.waf_check = chrome_http_req_res.waf_check;
+        page_response.challenge = challenge;
 
         if let Some(_elasped) = timeout_error {
             page_response.status_code = StatusCode::REQUEST_TIMEOUT;
@@ -1219,10 +1308,8 @@ fn set_page_response_headers(
     chrome_http_req_res: &mut ChromeHTTPReqRes,
     page_response: &mut PageResponse,
 ) {
-    let response_headers = convert_headers(&chrome_http_req_res.response_headers);
-
-    if !response_headers.is_empty() {
-        page_response.headers = Some(response_headers);
+    if !chrome_http_req_res.response_headers.is_empty() {
+        page_response.headers = Some(chrome_http_req_res.response_headers.inner().clone());
     }
 }
 
@@ -1431,6 +1518,29 @@ fn block_streaming(res: &Response, only_html: bool) -> bool {
     block_streaming
 }
 
+/// The `Content-Type` header value of the response.
+pub(crate) fn get_content_type(res: &Response) -> Option<String> {
+    res.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(String::from)
+}
+
+/// The encoding of the html rewriter from the charset of the `Content-Type` header.
+#[cfg(feature = "encoding")]
+pub(crate) fn rewriter_encoding(content_type: Option<&str>) -> lol_html::AsciiCompatibleEncoding {
+    content_type
+        .and_then(charset::header_charset)
+        .and_then(lol_html::AsciiCompatibleEncoding::new)
+        .unwrap_or_else(lol_html::AsciiCompatibleEncoding::utf_8)
+}
+
+/// The encoding of the html rewriter. This is always UTF-8 without the `encoding` feature flag enabled.
+#[cfg(not(feature = "encoding"))]
+pub(crate) fn rewriter_encoding(_content_type: Option<&str>) -> lol_html::AsciiCompatibleEncoding {
+    lol_html::AsciiCompatibleEncoding::utf_8()
+}
+
 /// Handle the response bytes
 pub async fn handle_response_bytes(
     res: Response,
@@ -1446,9 +1556,14 @@ pub async fn handle_response_bytes(
     };
 
     let status_code: StatusCode = res.status();
+    let http_version = res.version();
     #[cfg(feature = "headers")]
     let headers = res.headers().clone();
     let cookies = get_cookies(&res);
+    #[cfg(feature = "encoding")]
+    let declared_charset = get_content_type(&res)
+        .as_deref()
+        .and_then(charset::header_charset);
 
     let mut content: Option<Box<bytes::Bytes>> = None;
 
@@ -1489,9 +1604,12 @@ pub async fn handle_response_bytes(
         headers: Some(headers),
         #[cfg(feature = "cookies")]
         cookies,
+        #[cfg(feature = "encoding")]
+        charset: declared_charset,
         content,
         final_url: rd,
         status_code,
+        http_version: Some(http_version),
         ..Default::default()
     }
 }
@@ -1516,6 +1634,7 @@ where
     };
 
     let status_code: StatusCode = res.status();
+    let http_version = res.version();
     #[cfg(feature = "headers")]
     let headers = res.headers().clone();
     let cookies = get_cookies(&res);
@@ -1572,6 +1691,7 @@ where
             // content,
             final_url,
             status_code,
+            http_version: Some(http_version),
             ..Default::default()
         },
         rewrite_error,
@@ -1586,6 +1706,7 @@ pub(crate) fn setup_default_response(res: &Response) -> PageResponse {
         #[cfg(feature = "cookies")]
         cookies: get_cookies(&res),
         status_code: res.status(),
+        http_version: Some(res.version()),
         ..Default::default()
     }
 }
@@ -1818,6 +1939,8 @@ pub async fn fetch_page_html(
     automation_scripts: &Option<AutomationScripts>,
     viewport: &Option<crate::configuration::Viewport>,
     request_timeout: &Option<tokio::time::Duration>,
+    deep_links: bool,
+    performance_metrics: &Option<Box<crate::configuration::PerformanceMetricsConfig>>,
 ) -> PageResponse {
     use crate::tokio::io::{AsyncReadExt, AsyncWriteExt};
     use percent_encoding::utf8_percent_encode;
@@ -1840,6 +1963,8 @@ pub async fn fetch_page_html(
                 automation_scripts,
                 &viewport,
                 request_timeout,
+                deep_links,
+                performance_metrics,
             )
             .await
             {
@@ -1980,6 +2105,8 @@ pub async fn fetch_page_html(
     automation_scripts: &Option<AutomationScripts>,
     viewport: &Option<crate::configuration::Viewport>,
     request_timeout: &Option<Box<std::time::Duration>>,
+    deep_links: bool,
+    performance_metrics: &Option<Box<crate::configuration::PerformanceMetricsConfig>>,
 ) -> PageResponse {
     match fetch_page_html_chrome_base(
         &target_url,
@@ -1995,6 +2122,8 @@ pub async fn fetch_page_html(
         automation_scripts,
         viewport,
         request_timeout,
+        deep_links,
+        performance_metrics,
     )
     .await
     {
@@ -2020,6 +2149,8 @@ pub async fn fetch_page_html_chrome(
     automation_scripts: &Option<AutomationScripts>,
     viewport: &Option<crate::configuration::Viewport>,
     request_timeout: &Option<Box<tokio::time::Duration>>,
+    deep_links: bool,
+    performance_metrics: &Option<Box<crate::configuration::PerformanceMetricsConfig>>,
 ) -> PageResponse {
     match &page {
         page => {
@@ -2037,6 +2168,8 @@ pub async fn fetch_page_html_chrome(
                 automation_scripts,
                 viewport,
                 request_timeout,
+                deep_links,
+                performance_metrics,
             )
             .await
             {
@@ -2717,6 +2850,53 @@ pub fn log(message: &'static str, data: impl AsRef<str>) {
     }
 }
 
+/// The control handle state of a crawl scheduling pages.
+pub(crate) const CONTROL_RUNNING: i8 = 0;
+/// The control handle state of a paused crawl.
+pub(crate) const CONTROL_PAUSED: i8 = 1;
+/// The control handle state of a crawl aborting the pages in flight.
+pub(crate) const CONTROL_SHUTDOWN: i8 = 2;
+/// The control handle state of a crawl waiting for the pages in flight before it stops.
+pub(crate) const CONTROL_DRAINING: i8 = 3;
+
+/// Pause the crawl of the control handle if it runs.
+pub(crate) fn pause_handle(handle: &std::sync::atomic::AtomicI8) {
+    use std::sync::atomic::Ordering;
+    let _ = handle.compare_exchange(
+        CONTROL_RUNNING,
+        CONTROL_PAUSED,
+        Ordering::Relaxed,
+        Ordering::Relaxed,
+    );
+}
+
+/// Resume the crawl of the control handle if it is paused.
+pub(crate) fn resume_handle(handle: &std::sync::atomic::AtomicI8) {
+    use std::sync::atomic::Ordering;
+    let _ = handle.compare_exchange(
+        CONTROL_PAUSED,
+        CONTROL_RUNNING,
+        Ordering::Relaxed,
+        Ordering::Relaxed,
+    );
+}
+
+/// Shut down the crawl of the control handle. A graceful shutdown does not undo an abort.
+pub(crate) fn shutdown_handle(handle: &std::sync::atomic::AtomicI8, graceful: bool) {
+    use std::sync::atomic::Ordering;
+    if graceful {
+        let _ = handle.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |state| {
+            if state == CONTROL_SHUTDOWN {
+                None
+            } else {
+                Some(CONTROL_DRAINING)
+            }
+        });
+    } else {
+        handle.store(CONTROL_SHUTDOWN, Ordering::Relaxed);
+    }
+}
+
 #[cfg(feature = "control")]
 /// determine action
 #[derive(PartialEq, Debug)]
@@ -2807,6 +2987,18 @@ pub(crate) fn setup_website_selectors(
     }
 }
 
+/// The link activity for the crawl.
+#[derive(Debug, Clone, Default, PartialEq, Eq, strum::EnumString, strum::Display)]
+pub enum ProcessLinkStatus {
+    /// The link can process.
+    #[default]
+    Allowed,
+    /// The link is blocked.
+    Blocked,
+    /// The budget is exceeded for the crawl.
+    BudgetExceeded,
+}
+
 /// Allow subdomains or tlds.
 #[derive(Debug, Default, Clone, Copy)]
 pub struct AllowedDomainTypes {
@@ -2862,6 +3054,280 @@ pub fn get_last_segment(path: &str) -> &str {
     }
 }
 
+#[cfg(all(feature = "reqwest_rustls_tls", not(target_arch = "wasm32")))]
+/// Use rustls for the client even if native-tls is enabled by another crate. This keeps the TLS, proxy, and HTTP/2 ALPN handling without openssl.
+pub(crate) fn setup_tls_backend(client: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+    client.use_rustls_tls()
+}
+
+#[cfg(not(all(feature = "reqwest_rustls_tls", not(target_arch = "wasm32"))))]
+/// Use the default TLS backend for the client.
+pub(crate) fn setup_tls_backend(client: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+    client
+}
+
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "decentralized")))]
+/// Send the first client certificate matching the host for mutual TLS.
+pub(crate) fn setup_client_certificate(
+    client: reqwest::ClientBuilder,
+    client_certificates: Option<&[crate::configuration::ClientCertificate]>,
+    host: Option<&str>,
+) -> reqwest::ClientBuilder {
+    use crate::configuration::ClientCertificateSource;
+
+    let certificate = match (client_certificates, host) {
+        (Some(certificates), Some(host)) => certificates.iter().find(|c| c.matches_host(host)),
+        _ => None,
+    };
+
+    match certificate.map(|c| &c.source) {
+        Some(ClientCertificateSource::Pem(pem)) => match reqwest::Identity::from_pem(pem) {
+            Ok(identity) => client.identity(identity),
+            Err(e) => {
+                log::warn!("invalid client certificate: {e}");
+                client
+            }
+        },
+        #[cfg(any(
+            feature = "reqwest_native_tls",
+            feature = "reqwest_native_tls_alpn",
+            feature = "reqwest_native_tls_vendored"
+        ))]
+        Some(ClientCertificateSource::Pkcs12 { der, password }) => {
+            match reqwest::Identity::from_pkcs12_der(der, password) {
+                // PKCS#12 identities are only read by the native TLS backend.
+                Ok(identity) => client.use_native_tls().identity(identity),
+                Err(e) => {
+                    log::warn!("invalid client certificate: {e}");
+                    client
+                }
+            }
+        }
+        #[cfg(not(any(
+            feature = "reqwest_native_tls",
+            feature = "reqwest_native_tls_alpn",
+            feature = "reqwest_native_tls_vendored"
+        )))]
+        Some(ClientCertificateSource::Pkcs12 { .. }) => {
+            log::warn!(
+                "PKCS#12 client certificates require the `reqwest_native_tls` feature flag."
+            );
+            client
+        }
+        _ => client,
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", not(feature = "decentralized")))]
+/// Client certificates are not supported on wasm.
+pub(crate) fn setup_client_certificate(
+    client: reqwest::ClientBuilder,
+    _client_certificates: Option<&[crate::configuration::ClientCertificate]>,
+    _host: Option<&str>,
+) -> reqwest::ClientBuilder {
+    client
+}
+
+/// The offset basis of the 64 bit FNV-1a hash.
+pub(crate) const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+
+/// Continue the 64 bit FNV-1a hash with the bytes.
+pub(crate) fn fnv1a_extend(hash: u64, bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes
+        .into_iter()
+        .fold(hash, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// The 64 bit FNV-1a hash of the bytes. The hash is stable across runs, platforms and builds.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    fnv1a_extend(FNV_OFFSET, bytes.iter().copied())
+}
+
+/// The text of the element and its descendants with the whitespace collapsed.
+pub(crate) fn element_text(element: &crate::packages::scraper::ElementRef) -> String {
+    element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Match the text with a pattern where `*` matches any characters.
+pub(crate) fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+
+    let mut rest = match text.strip_prefix(first) {
+        Some(rest) => rest,
+        _ => return false,
+    };
+
+    let parts: Vec<&str> = parts.collect();
+
+    match parts.split_last() {
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(i) => rest = &rest[i + part.len()..],
+                    _ => return false,
+                }
+            }
+            rest.ends_with(last)
+        }
+        _ => rest.is_empty(),
+    }
+}
+
+/// Match the url with a pattern where `*` matches any characters. Patterns starting with `/` match the path and query
+/// of the url.
+pub(crate) fn url_matches(pattern: &str, url: &str) -> bool {
+    if pattern.starts_with('/') {
+        match Url::parse(url) {
+            Ok(url) => match url.query() {
+                Some(query) => {
+                    wildcard_match(pattern, &format!("{}?{}", url.path(), query))
+                        || wildcard_match(pattern, url.path())
+                }
+                _ => wildcard_match(pattern, url.path()),
+            },
+            _ => false,
+        }
+    } else {
+        wildcard_match(pattern, url)
+    }
+}
+
+/// The registrable domain of the host from the public suffix list ex: `www.example.co.uk` is `example.co.uk`.
+/// Ip addresses and hosts without a registrable domain are returned as is.
+#[cfg(feature = "public_suffix")]
+pub fn registrable_domain(host: &str) -> &str {
+    let host = host.trim_end_matches('.');
+
+    if host.parse::<std::net::IpAddr>().is_ok() {
+        host
+    } else {
+        psl::domain_str(host).unwrap_or(host)
+    }
+}
+
+/// The registrable domain of the host ex: `www.example.co.uk` is `example.co.uk`.
+/// Short second level labels under a country code like `co.uk` or `com.au` keep the third label.
+#[cfg(not(feature = "public_suffix"))]
+pub fn registrable_domain(host: &str) -> &str {
+    let host = host.trim_end_matches('.');
+    let labels: Vec<&str> = host.rsplitn(4, '.').collect();
+
+    let keep = match labels.as_slice() {
+        [tld, sld, _, ..]
+            if tld.len() == 2
+                && (sld.len() <= 2
+                    || matches!(*sld, "com" | "net" | "org" | "gov" | "edu" | "mil")) =>
+        {
+            3
+        }
+        _ => 2,
+    };
+
+    if labels.len() <= keep || host.parse::<std::net::IpAddr>().is_ok() {
+        host
+    } else {
+        let skip: usize = labels[..keep].iter().map(|l| l.len() + 1).sum();
+        &host[host.len() + 1 - skip..]
+    }
+}
+
+/// The registrable domain of the host without its public suffix ex: `shop.example.co.uk` is `example`.
+#[cfg(feature = "public_suffix")]
+pub fn domain_brand(host: &str) -> &str {
+    let domain = registrable_domain(host);
+
+    match psl::suffix_str(domain) {
+        Some(suffix) if suffix.len() < domain.len() => {
+            domain[..domain.len() - suffix.len()].trim_end_matches('.')
+        }
+        _ => domain,
+    }
+}
+
+/// The registrable domain of the host without its suffix ex: `shop.example.co.uk` is `example`.
+#[cfg(not(feature = "public_suffix"))]
+pub fn domain_brand(host: &str) -> &str {
+    let domain = registrable_domain(host);
+
+    if domain.parse::<std::net::IpAddr>().is_ok() {
+        domain
+    } else {
+        domain.split('.').next().unwrap_or(domain)
+    }
+}
+
+#[cfg(not(feature = "decentralized"))]
+/// The proxy url for the HTTP client. Hosts behind `socks5://` proxies are resolved by the proxy the same way Chrome does.
+pub(crate) fn http_proxy_url(proxy: &str) -> std::borrow::Cow<'_, str> {
+    match proxy.strip_prefix("socks5://") {
+        Some(addr) => std::borrow::Cow::Owned(string_concat!("socks5h://", addr)),
+        _ => std::borrow::Cow::Borrowed(proxy),
+    }
+}
+
+#[cfg(not(feature = "decentralized"))]
+/// Route the client through the proxies. SOCKS proxies require the `socks` flag.
+pub(crate) fn setup_proxies(
+    mut client: reqwest::ClientBuilder,
+    proxies: Option<&[String]>,
+) -> reqwest::ClientBuilder {
+    if let Some(proxies) = proxies {
+        for proxy in proxies {
+            let proxy = http_proxy_url(proxy);
+
+            if !cfg!(feature = "socks") && proxy.starts_with("socks") {
+                log::warn!("SOCKS proxies require the `socks` feature flag, skipping the proxy.");
+                continue;
+            }
+
+            match reqwest::Proxy::all(proxy.as_ref()) {
+                Ok(proxy) => client = client.proxy(proxy),
+                Err(e) => log::warn!("invalid proxy skipped: {e}"),
+            }
+        }
+    }
+    client
+}
+
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "decentralized")))]
+/// Connect to the addresses set for the hosts instead of resolving them with DNS. The client keeps the port of the url,
+/// the overrides on the other ports than the one the crawl connects to are skipped.
+pub(crate) fn setup_resolve_overrides(
+    mut client: reqwest::ClientBuilder,
+    resolve: Option<&[crate::configuration::ResolveOverride]>,
+    url: Option<&url::Url>,
+) -> reqwest::ClientBuilder {
+    if let Some(resolve) = resolve {
+        for entry in resolve.iter() {
+            if entry.applies_to(url) {
+                client = client.resolve(&entry.host, entry.socket_addr());
+            } else {
+                log::warn!(
+                    "resolve override {}:{} skipped, the client connects to the host on another port",
+                    entry.host,
+                    entry.port
+                );
+            }
+        }
+    }
+    client
+}
+
+#[cfg(all(target_arch = "wasm32", not(feature = "decentralized")))]
+/// Resolve overrides are not supported on wasm.
+pub(crate) fn setup_resolve_overrides(
+    client: reqwest::ClientBuilder,
+    _resolve: Option<&[crate::configuration::ResolveOverride]>,
+    _url: Option<&url::Url>,
+) -> reqwest::ClientBuilder {
+    client
+}
+
 #[cfg(feature = "tracing")]
 /// Spawns a new asynchronous task.
 pub(crate) fn spawn_task<F>(task_name: &str, future: F) -> tokio::task::JoinHandle<F::Output>
@@ -2886,7 +3352,7 @@ where
 }
 
 #[cfg(feature = "tracing")]
-/// Spawn a joinset.
+/// Spawn a joinset. The task runs in a span with the fields of the page recorded by [`trace_page`].
 pub(crate) fn spawn_set<F, T>(
     task_name: &str,
     set: &mut tokio::task::JoinSet<T>,
@@ -2898,12 +3364,35 @@ where
     F: Send + 'static,
     T: Send + 'static,
 {
+    use tracing::Instrument;
+
+    let span = tracing::info_span!(
+        "crawl_task",
+        task = task_name,
+        url = tracing::field::Empty,
+        status = tracing::field::Empty,
+        depth = tracing::field::Empty,
+        bytes = tracing::field::Empty,
+    );
+
     set.build_task()
         .name(task_name)
-        .spawn_on(future, &handle)
+        .spawn_on(future.instrument(span), &handle)
         .expect("set should spawn")
 }
 
+/// Record the url, the status code, the depth and the size of the page fetched on the span of the crawl task.
+#[cfg(feature = "tracing")]
+pub(crate) fn trace_page(page: &crate::page::Page) {
+    let span = tracing::Span::current();
+
+    span.record("url", page.get_url());
+    span.record("status", page.status_code.as_u16());
+    span.record("depth", page.depth);
+    span.record("bytes", page.get_bytes().map_or(0, |bytes| bytes.len()));
+    tracing::debug!("page fetched");
+}
+
 #[cfg(not(feature = "tracing"))]
 /// Spawn a joinset.
 pub(crate) fn spawn_set<F, T>(
@@ -0,0 +1,373 @@
+use crate::configuration::AdaptiveRateLimit;
+use reqwest::StatusCode;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// The token bucket of a host.
+#[derive(Debug)]
+struct HostBucket {
+    /// The requests allowed per second.
+    rate: f64,
+    /// The tokens available. Negative when requests are waiting on a reservation.
+    tokens: f64,
+    /// The last time the tokens were refilled.
+    refilled: Instant,
+    /// The successful responses since the last rate change.
+    successes: u32,
+    /// No request is sent to the host before this time.
+    paused_until: Option<Instant>,
+}
+
+/// Limit the requests sent to each host with a token bucket. The rate slows down on 429 and 503 responses,
+/// pauses the host for the `Retry-After` delay and speeds back up after a run of successful responses. The domains of
+/// the politeness profiles with a delay wait the delay between their requests.
+#[derive(Debug, Default)]
+pub struct HostRateLimiter {
+    /// The limits, the limiter lets every request through without them.
+    config: Option<AdaptiveRateLimit>,
+    /// The buckets by host.
+    hosts: Mutex<hashbrown::HashMap<String, HostBucket>>,
+    /// The delay between the requests by registrable domain.
+    delays: hashbrown::HashMap<String, Duration>,
+    /// The time the next request to each delayed domain can be sent.
+    next_request: Mutex<hashbrown::HashMap<String, Instant>>,
+}
+
+/// The host of the url used as the bucket key.
+fn host_key(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(u) => u.host_str().unwrap_or_default().to_string(),
+        _ => url.to_string(),
+    }
+}
+
+/// The registrable domain of the url used as the key of the politeness profiles.
+fn domain_key(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    Some(crate::utils::registrable_domain(url.host_str()?).to_ascii_lowercase())
+}
+
+impl HostRateLimiter {
+    /// A new limiter for the limits.
+    pub fn new(config: Option<AdaptiveRateLimit>) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Wait the delay between the requests to each registrable domain.
+    pub fn with_delays(mut self, delays: hashbrown::HashMap<String, Duration>) -> Self {
+        self.delays = delays;
+        self
+    }
+
+    /// The time to wait before the request to the url can be sent. The request is counted right away.
+    pub fn reserve(&self, url: &str) -> Option<Duration> {
+        self.wait(url, true)
+    }
+
+    /// The time to wait before the request to the url can be sent, without counting the request.
+    pub fn ready_in(&self, url: &str) -> Option<Duration> {
+        self.wait(url, false)
+    }
+
+    /// The time to wait for the host and the domain of the url, the request is counted when `reserve` is set.
+    fn wait(&self, url: &str, reserve: bool) -> Option<Duration> {
+        let now = Instant::now();
+        let wait = self
+            .host_wait(url, now, reserve)
+            .max(self.domain_wait(url, now, reserve))?;
+
+        if wait.is_zero() {
+            None
+        } else {
+            Some(wait)
+        }
+    }
+
+    /// The time to wait for the token bucket of the host.
+    fn host_wait(&self, url: &str, now: Instant, reserve: bool) -> Option<Duration> {
+        let config = self.config.as_ref()?;
+        let mut hosts = self.hosts.lock().ok()?;
+        let bucket = hosts.entry(host_key(url)).or_insert_with(|| HostBucket {
+            rate: config.initial_rate,
+            tokens: config.burst,
+            refilled: now,
+            successes: 0,
+            paused_until: None,
+        });
+
+        let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * bucket.rate).min(config.burst);
+        bucket.refilled = now;
+
+        let mut wait = if bucket.tokens < 1.0 {
+            Duration::from_secs_f64((1.0 - bucket.tokens) / bucket.rate)
+        } else {
+            Duration::ZERO
+        };
+
+        if reserve {
+            bucket.tokens -= 1.0;
+        }
+
+        if let Some(paused_until) = bucket.paused_until {
+            wait = wait.max(paused_until.saturating_duration_since(now));
+        }
+
+        Some(wait)
+    }
+
+    /// The time to wait for the delay of the registrable domain.
+    fn domain_wait(&self, url: &str, now: Instant, reserve: bool) -> Option<Duration> {
+        if self.delays.is_empty() {
+            return None;
+        }
+
+        let domain = domain_key(url)?;
+        let delay = *self.delays.get(&domain)?;
+        let mut next_request = self.next_request.lock().ok()?;
+        let slot = next_request
+            .get(&domain)
+            .map_or(now, |next| (*next).max(now));
+
+        if reserve {
+            next_request.insert(domain, slot + delay);
+        }
+
+        Some(slot.saturating_duration_since(now))
+    }
+
+    /// Wait until the request to the url can be sent.
+    pub async fn acquire(&self, url: &str) {
+        if let Some(wait) = self.reserve(url) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Adapt the rate of the host to the response.
+    pub fn record(&self, url: &str, status_code: StatusCode, retry_after: Option<Duration>) {
+        let config = match self.config.as_ref() {
+            Some(config) => config,
+            _ => return,
+        };
+
+        if let Ok(mut hosts) = self.hosts.lock() {
+            if let Some(bucket) = hosts.get_mut(&host_key(url)) {
+                if status_code == StatusCode::TOO_MANY_REQUESTS
+                    || status_code == StatusCode::SERVICE_UNAVAILABLE
+                {
+                    bucket.rate = (bucket.rate * config.slowdown).max(config.min_rate);
+                    bucket.successes = 0;
+
+                    if let Some(retry_after) = retry_after {
+                        let paused_until = Instant::now() + retry_after.min(config.max_retry_after);
+                        bucket.paused_until = Some(match bucket.paused_until {
+                            Some(current) => current.max(paused_until),
+                            _ => paused_until,
+                        });
+                    }
+                } else if status_code.is_success() {
+                    bucket.successes += 1;
+
+                    if bucket.successes >= config.speedup_after {
+                        bucket.rate = (bucket.rate * config.speedup).min(config.max_rate);
+                        bucket.successes = 0;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The requests allowed per second for the host of the url.
+    pub fn rate(&self, url: &str) -> Option<f64> {
+        let hosts = self.hosts.lock().ok()?;
+        hosts.get(&host_key(url)).map(|bucket| bucket.rate)
+    }
+
+    /// Has limits.
+    pub fn enabled(&self) -> bool {
+        self.config.is_some() || !self.delays.is_empty()
+    }
+}
+
+/// The permit of a request counting against its host until dropped. Hosts without a limit hand out empty permits.
+#[derive(Debug, Default)]
+pub struct HostPermit(Option<OwnedSemaphorePermit>);
+
+impl HostPermit {
+    /// Whether the request counts against a limit.
+    pub fn is_limited(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+/// Limit the requests in flight to each host independently of the global concurrency limit. The domains of the
+/// politeness profiles with a concurrency share their limit across their hosts in place of the host limit.
+#[derive(Debug, Default)]
+pub struct HostConcurrencyLimiter {
+    /// The requests allowed in flight to each host, the limiter lets every request through without it.
+    max_per_host: Option<usize>,
+    /// The requests allowed in flight by registrable domain.
+    domain_limits: hashbrown::HashMap<String, usize>,
+    /// The permits by host or domain.
+    hosts: Mutex<hashbrown::HashMap<String, Arc<Semaphore>>>,
+}
+
+impl HostConcurrencyLimiter {
+    /// A new limiter allowing `max_per_host` requests in flight to each host.
+    pub fn new(max_per_host: Option<usize>) -> Self {
+        Self {
+            max_per_host: max_per_host.map(|max| max.max(1)),
+            ..Default::default()
+        }
+    }
+
+    /// Allow the requests in flight to each registrable domain across its hosts.
+    pub fn with_domain_limits(mut self, domain_limits: hashbrown::HashMap<String, usize>) -> Self {
+        self.domain_limits = domain_limits;
+        self
+    }
+
+    /// The permits of the host or the domain of the url.
+    fn semaphore(&self, url: &str) -> Option<Arc<Semaphore>> {
+        let domain_limit = if self.domain_limits.is_empty() {
+            None
+        } else {
+            domain_key(url).and_then(|domain| {
+                let limit = *self.domain_limits.get(&domain)?;
+                Some((domain, limit))
+            })
+        };
+        let (key, limit) = match domain_limit {
+            Some(domain_limit) => domain_limit,
+            _ => (host_key(url), self.max_per_host?),
+        };
+        let mut hosts = self.hosts.lock().ok()?;
+
+        Some(
+            hosts
+                .entry(key)
+                .or_insert_with(|| Arc::new(Semaphore::new(limit.max(1))))
+                .clone(),
+        )
+    }
+
+    /// Wait until a request to the url can be sent. The request counts against its host until the permit is dropped.
+    pub async fn acquire(&self, url: &str) -> HostPermit {
+        match self.semaphore(url) {
+            Some(semaphore) => HostPermit(semaphore.acquire_owned().await.ok()),
+            _ => Default::default(),
+        }
+    }
+
+    /// The permit of a request to the url, `None` when the host is at its limit.
+    pub fn try_acquire(&self, url: &str) -> Option<HostPermit> {
+        match self.semaphore(url) {
+            Some(semaphore) => semaphore.try_acquire_owned().ok().map(Some).map(HostPermit),
+            _ => Some(Default::default()),
+        }
+    }
+
+    /// Has a limit.
+    pub fn enabled(&self) -> bool {
+        self.max_per_host.is_some() || !self.domain_limits.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adapts_to_responses() {
+        let limiter = HostRateLimiter::new(Some(AdaptiveRateLimit {
+            initial_rate: 4.0,
+            min_rate: 1.0,
+            max_rate: 8.0,
+            burst: 1.0,
+            speedup_after: 2,
+            ..Default::default()
+        }));
+        let url = "https://choosealicense.com/licenses/";
+
+        assert_eq!(limiter.reserve(url), None);
+        assert!(limiter.reserve(url).is_some());
+        // other hosts have their own bucket.
+        assert_eq!(limiter.reserve("https://example.com"), None);
+
+        limiter.record(url, StatusCode::TOO_MANY_REQUESTS, None);
+        assert_eq!(limiter.rate(url), Some(2.0));
+
+        limiter.record(url, StatusCode::SERVICE_UNAVAILABLE, None);
+        limiter.record(url, StatusCode::SERVICE_UNAVAILABLE, None);
+        assert_eq!(limiter.rate(url), Some(1.0));
+
+        limiter.record(url, StatusCode::OK, None);
+        limiter.record(url, StatusCode::OK, None);
+        assert_eq!(limiter.rate(url), Some(1.25));
+
+        limiter.record(
+            url,
+            StatusCode::TOO_MANY_REQUESTS,
+            Some(Duration::from_secs(5)),
+        );
+        assert!(limiter.reserve(url) >= Some(Duration::from_secs(4)));
+    }
+
+    #[test]
+    fn waits_the_domain_delays() {
+        let limiter = HostRateLimiter::new(None).with_delays(
+            std::iter::once(("slow.org".to_string(), Duration::from_secs(1))).collect(),
+        );
+
+        assert!(limiter.enabled());
+        assert_eq!(limiter.ready_in("https://slow.org/1"), None);
+        assert_eq!(limiter.reserve("https://slow.org/1"), None);
+        assert!(limiter.ready_in("https://www.slow.org/2") > Some(Duration::from_millis(900)));
+        assert!(limiter.reserve("https://slow.org/2") > Some(Duration::from_millis(900)));
+        assert!(limiter.reserve("https://slow.org/3") > Some(Duration::from_millis(1900)));
+        assert_eq!(limiter.reserve("https://example.com/"), None);
+    }
+
+    #[tokio::test]
+    async fn limits_hosts_in_flight() {
+        let limiter = HostConcurrencyLimiter::new(Some(2));
+        let url = "https://choosealicense.com/licenses/";
+
+        let first = limiter.acquire(url).await;
+        let second = limiter.acquire("https://choosealicense.com/about/").await;
+
+        assert!(first.is_limited() && second.is_limited());
+        assert!(limiter.try_acquire(url).is_none());
+        // other hosts have their own permits.
+        assert!(limiter.try_acquire("https://example.com").is_some());
+
+        drop(first);
+        assert!(limiter.try_acquire(url).is_some());
+
+        let unlimited = HostConcurrencyLimiter::new(None);
+        assert!(!unlimited.enabled());
+        assert!(!unlimited.acquire(url).await.is_limited());
+        assert!(unlimited.try_acquire(url).is_some());
+    }
+
+    #[test]
+    fn limits_domains_in_flight() {
+        let limiter = HostConcurrencyLimiter::new(None)
+            .with_domain_limits(std::iter::once(("partner.com".to_string(), 1)).collect());
+
+        assert!(limiter.enabled());
+        let permit = limiter.try_acquire("https://partner.com/a").unwrap();
+        assert!(permit.is_limited());
+        assert!(limiter.try_acquire("https://www.partner.com/b").is_none());
+        assert!(!limiter
+            .try_acquire("https://example.com/")
+            .unwrap()
+            .is_limited());
+        drop(permit);
+        assert!(limiter.try_acquire("https://shop.partner.com/c").is_some());
+    }
+}
@@ -0,0 +1,71 @@
+use crate::configuration::RetryPolicy;
+use case_insensitive_string::CaseInsensitiveString;
+use reqwest::StatusCode;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The urls re-queued by the retry policy of a crawl.
+#[derive(Debug, Default)]
+pub struct RetryQueue {
+    /// The policy deciding the retries.
+    policy: Option<RetryPolicy>,
+    /// The attempts made for the urls retried.
+    attempts: Mutex<hashbrown::HashMap<CaseInsensitiveString, u32>>,
+    /// The urls ready to go back to the frontier.
+    ready: Mutex<Vec<CaseInsensitiveString>>,
+}
+
+impl RetryQueue {
+    /// A new queue for the policy. The queue never retries without a policy.
+    pub fn new(policy: Option<RetryPolicy>) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// The delay to wait before re-queuing the url if the policy retries the response status.
+    pub fn schedule(
+        &self,
+        link: &CaseInsensitiveString,
+        status_code: StatusCode,
+    ) -> Option<Duration> {
+        let policy = self.policy.as_ref()?;
+
+        if !policy.retries(status_code) {
+            return None;
+        }
+
+        let mut attempts = self.attempts.lock().ok()?;
+        let attempt = attempts.entry(link.clone()).or_insert(1);
+
+        if *attempt >= policy.max_attempts {
+            return None;
+        }
+
+        let delay = policy.delay(*attempt);
+        *attempt += 1;
+
+        Some(delay)
+    }
+
+    /// Put the url back in the frontier once the delay passed.
+    pub fn push(&self, link: CaseInsensitiveString) {
+        if let Ok(mut ready) = self.ready.lock() {
+            ready.push(link);
+        }
+    }
+
+    /// Take the urls ready to crawl again.
+    pub fn drain(&self) -> Vec<CaseInsensitiveString> {
+        match self.ready.lock() {
+            Ok(mut ready) => std::mem::take(&mut *ready),
+            _ => Default::default(),
+        }
+    }
+
+    /// Has a retry policy.
+    pub fn enabled(&self) -> bool {
+        self.policy.is_some()
+    }
+}
@@ -20,6 +20,10 @@ impl Command {
         // this case where the user didn't explicitely kill the child
         // process before dropping the handle.
         inner.kill_on_drop(true);
+        // Lead a new process group so the browser and all of its helper
+        // processes can be terminated together.
+        #[cfg(unix)]
+        inner.process_group(0);
         Self { inner }
     }
 
@@ -83,6 +87,11 @@ impl Child {
         self.inner.kill().await
     }
 
+    /// The OS-assigned process identifier, `None` once the child has been reaped
+    pub fn id(&self) -> Option<u32> {
+        self.inner.id()
+    }
+
     /// Asynchronously wait for the child process to exit
     pub async fn wait(&mut self) -> std::io::Result<ExitStatus> {
         self.inner.wait().await
@@ -0,0 +1,92 @@
+//! The engine-neutral subset of a browser: open a page, navigate, read the html, evaluate a script and close.
+//!
+//! [`BrowserBackend`] opens pages and [`BackendPage`] drives one page. Firefox and the other WebDriver BiDi browsers
+//! implement it with [`crate::bidi::BidiBrowser`]. [`Browser`] and [`Page`] implement it too so code written against the
+//! trait runs on Chromium, but the handler layer is not behind the trait: the Chrome DevTools Protocol features like
+//! the network interception, the emulation, the screenshots and the event listeners stay on [`Page`].
+
+use futures::future::BoxFuture;
+
+use chromiumoxide_cdp::cdp::browser_protocol::browser::CloseParams;
+use chromiumoxide_cdp::cdp::browser_protocol::target::CreateTargetParams;
+
+use crate::error::Result;
+use crate::{Browser, Page};
+
+/// A page opened by a [`BrowserBackend`].
+pub trait BackendPage: Send + Sync {
+    /// Navigate to the url and wait for the page to load. Resolves to the status code of the document response when
+    /// the engine reports it.
+    fn goto<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Option<u16>>>;
+
+    /// The html of the page.
+    fn content(&self) -> BoxFuture<'_, Result<String>>;
+
+    /// Evaluate the javascript expression in the page and return its value as json. Promises are awaited.
+    fn evaluate<'a>(&'a self, expression: &'a str) -> BoxFuture<'a, Result<serde_json::Value>>;
+
+    /// Close the page.
+    fn close(&self) -> BoxFuture<'_, Result<()>>;
+}
+
+/// A browser engine the pages are rendered with.
+pub trait BrowserBackend: Send + Sync {
+    /// The name of the engine like `chromium` or `firefox`.
+    fn engine(&self) -> &str;
+
+    /// Open a blank page.
+    fn new_page(&self) -> BoxFuture<'_, Result<Box<dyn BackendPage>>>;
+
+    /// Close the browser.
+    fn close(&self) -> BoxFuture<'_, Result<()>>;
+}
+
+impl BackendPage for Page {
+    fn goto<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Option<u16>>> {
+        Box::pin(async move {
+            Page::goto(self, url).await?;
+
+            let request = self.wait_for_navigation_response().await?;
+
+            Ok(request
+                .as_ref()
+                .and_then(|request| request.response.as_ref())
+                .map(|response| response.status as u16))
+        })
+    }
+
+    fn content(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(Page::content(self))
+    }
+
+    fn evaluate<'a>(&'a self, expression: &'a str) -> BoxFuture<'a, Result<serde_json::Value>> {
+        Box::pin(async move {
+            let result = Page::evaluate(self, expression).await?;
+            Ok(result.value().cloned().unwrap_or_default())
+        })
+    }
+
+    fn close(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(self.clone().close())
+    }
+}
+
+impl BrowserBackend for Browser {
+    fn engine(&self) -> &str {
+        "chromium"
+    }
+
+    fn new_page(&self) -> BoxFuture<'_, Result<Box<dyn BackendPage>>> {
+        Box::pin(async move {
+            let page = Browser::new_page(self, CreateTargetParams::new("about:blank")).await?;
+            Ok(Box::new(page) as Box<dyn BackendPage>)
+        })
+    }
+
+    fn close(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            self.execute(CloseParams::default()).await?;
+            Ok(())
+        })
+    }
+}
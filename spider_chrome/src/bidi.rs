@@ -0,0 +1,757 @@
+//! A [WebDriver BiDi](https://w3c.github.io/webdriver-bidi/) client to render pages with Firefox and the other
+//! browsers speaking the protocol.
+//!
+//! The requests of the pages are intercepted with the policies of the Chromium network manager: the
+//! [`ResourcePolicy`], the [`ThirdPartyPolicy`], the [`RequestFilter`] and the [`MockRouter`].
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_tungstenite::tokio::ConnectStream;
+use async_tungstenite::tungstenite::Message as WsMessage;
+use async_tungstenite::{tungstenite::protocol::WebSocketConfig, WebSocketStream};
+use futures::channel::oneshot;
+use futures::future::BoxFuture;
+use futures::lock::Mutex as AsyncMutex;
+use futures::stream::{SplitSink, SplitStream};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+
+use chromiumoxide_cdp::cdp::browser_protocol::network::ResourceType;
+
+use crate::async_process::{Child, Command, Stdio};
+use crate::backend::{BackendPage, BrowserBackend};
+use crate::error::{CdpError, Result};
+use crate::handler::network::{
+    MockRoute, MockRouter, RequestFilter, ThirdPartyPolicy, IGNORE_VISUAL_RESOURCE_MAP,
+};
+use crate::handler::resource_policy::{ResourceAction, ResourcePolicy};
+
+/// The time the browser has to print the address of its WebDriver BiDi server.
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// The navigations kept waiting for their status code before the oldest are dropped.
+const MAX_PENDING_NAVIGATIONS: usize = 1024;
+
+/// What to do with an intercepted request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterceptAction {
+    /// Send the request to the network.
+    Continue,
+    /// Fail the request.
+    Fail,
+    /// Answer the request with the canned response.
+    Provide(MockRoute),
+}
+
+/// The interception of the requests of the pages, using the same policies as the Chromium network manager.
+#[derive(Debug, Clone, Default)]
+pub struct BidiInterception {
+    /// Block the images, media and fonts.
+    pub ignore_visuals: bool,
+    /// Block the stylesheets.
+    pub block_stylesheets: bool,
+    /// Block the scripts.
+    pub block_javascript: bool,
+    /// The actions by resource type, taking precedence over the ignore flags.
+    pub resource_policy: ResourcePolicy,
+    /// Block the third party requests of the page.
+    pub third_party: Option<ThirdPartyPolicy>,
+    /// Block the requests the filter returns `true` for.
+    pub request_filter: Option<RequestFilter>,
+    /// Answer the requests matching the routes with canned responses.
+    pub mock_router: Option<MockRouter>,
+}
+
+impl BidiInterception {
+    /// Whether any request can be blocked or answered.
+    pub fn enabled(&self) -> bool {
+        self.ignore_visuals
+            || self.block_stylesheets
+            || self.block_javascript
+            || !self.resource_policy.is_empty()
+            || self.third_party.is_some()
+            || self.request_filter.is_some()
+            || self.mock_router.is_some()
+    }
+
+    /// The action for the request. The documents are only blocked by the request filter, the mock router and the
+    /// resource policy.
+    pub fn intercept(
+        &self,
+        method: &str,
+        url: &str,
+        resource_type: &ResourceType,
+    ) -> InterceptAction {
+        if let Some(router) = &self.mock_router {
+            match router.find(method, url) {
+                Some(route) => return InterceptAction::Provide(route.clone()),
+                _ if router.offline => return InterceptAction::Fail,
+                _ => (),
+            }
+        }
+
+        if let Some(filter) = &self.request_filter {
+            if (filter.0)(url, resource_type) {
+                return InterceptAction::Fail;
+            }
+        }
+
+        match self.resource_policy.get(resource_type) {
+            Some(ResourceAction::Block) => return InterceptAction::Fail,
+            Some(ResourceAction::FulfillEmpty) => {
+                return InterceptAction::Provide(MockRoute {
+                    pattern: url.into(),
+                    status: 200,
+                    ..Default::default()
+                })
+            }
+            Some(ResourceAction::Allow) | None if *resource_type == ResourceType::Document => {
+                return InterceptAction::Continue
+            }
+            Some(ResourceAction::Allow) => (),
+            None => {
+                if self.ignore_visuals
+                    && IGNORE_VISUAL_RESOURCE_MAP.contains(resource_type.as_ref())
+                    || self.block_stylesheets && *resource_type == ResourceType::Stylesheet
+                    || self.block_javascript && *resource_type == ResourceType::Script
+                {
+                    return InterceptAction::Fail;
+                }
+            }
+        }
+
+        let blocked = self
+            .third_party
+            .as_ref()
+            .is_some_and(|policy| policy.is_blocked(url));
+
+        if blocked {
+            InterceptAction::Fail
+        } else {
+            InterceptAction::Continue
+        }
+    }
+}
+
+/// The resource type of the request from its fetch `destination` and `initiatorType`.
+pub fn resource_type(request: &Value) -> ResourceType {
+    match request["destination"].as_str().unwrap_or_default() {
+        "document" | "iframe" | "frame" => ResourceType::Document,
+        "style" => ResourceType::Stylesheet,
+        "script" | "worker" | "sharedworker" | "serviceworker" => ResourceType::Script,
+        "image" => ResourceType::Image,
+        "font" => ResourceType::Font,
+        "audio" | "video" | "track" => ResourceType::Media,
+        "manifest" => ResourceType::Manifest,
+        _ => match request["initiatorType"].as_str().unwrap_or_default() {
+            "fetch" => ResourceType::Fetch,
+            "xmlhttprequest" => ResourceType::Xhr,
+            "beacon" | "ping" => ResourceType::Ping,
+            "img" | "image" => ResourceType::Image,
+            "css" | "link" => ResourceType::Stylesheet,
+            "script" => ResourceType::Script,
+            _ => ResourceType::Other,
+        },
+    }
+}
+
+/// The websocket connection of the session.
+struct BidiConnection {
+    /// The writing half of the websocket.
+    sink: AsyncMutex<SplitSink<WebSocketStream<ConnectStream>, WsMessage>>,
+    /// The identifier of the next command.
+    next_id: AtomicU64,
+    /// The commands waiting for their response.
+    pending: Mutex<HashMap<u64, oneshot::Sender<Result<Value>>>>,
+    /// The status code of the document response of the navigations.
+    navigations: Mutex<HashMap<String, u16>>,
+    /// The interception of the requests.
+    interception: BidiInterception,
+}
+
+impl BidiConnection {
+    /// Send the command and wait for its result.
+    async fn command(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(id, tx);
+        }
+
+        if let Err(err) = self.write(id, method, params).await {
+            if let Ok(mut pending) = self.pending.lock() {
+                pending.remove(&id);
+            }
+            return Err(err);
+        }
+
+        rx.await.map_err(|_| CdpError::NoResponse)?
+    }
+
+    /// Send the command without waiting for its result.
+    async fn notify(&self, method: &str, params: Value) -> Result<()> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.write(id, method, params).await
+    }
+
+    /// Write the command to the websocket.
+    async fn write(&self, id: u64, method: &str, params: Value) -> Result<()> {
+        let message = json!({ "id": id, "method": method, "params": params }).to_string();
+        self.sink
+            .lock()
+            .await
+            .send(WsMessage::Text(message))
+            .await?;
+        Ok(())
+    }
+
+    /// Dispatch a message of the browser.
+    async fn on_message(&self, text: &str) {
+        let message: Value = match serde_json::from_str(text) {
+            Ok(message) => message,
+            _ => return,
+        };
+
+        match message["type"].as_str() {
+            Some(kind @ ("success" | "error")) => {
+                let tx = match (message["id"].as_u64(), self.pending.lock()) {
+                    (Some(id), Ok(mut pending)) => pending.remove(&id),
+                    _ => None,
+                };
+
+                if let Some(tx) = tx {
+                    let _ = tx.send(if kind == "success" {
+                        Ok(message["result"].clone())
+                    } else {
+                        Err(CdpError::msg(format!(
+                            "{}: {}",
+                            message["error"].as_str().unwrap_or_default(),
+                            message["message"].as_str().unwrap_or_default()
+                        )))
+                    });
+                }
+            }
+            Some("event") => {
+                self.on_event(
+                    message["method"].as_str().unwrap_or_default(),
+                    &message["params"],
+                )
+                .await
+            }
+            _ => (),
+        }
+    }
+
+    /// Track the document responses and answer the intercepted requests.
+    async fn on_event(&self, method: &str, params: &Value) {
+        match method {
+            "network.responseCompleted" => {
+                if let (Some(navigation), Some(status)) = (
+                    params["navigation"].as_str(),
+                    params["response"]["status"].as_u64(),
+                ) {
+                    if let Ok(mut navigations) = self.navigations.lock() {
+                        if navigations.len() >= MAX_PENDING_NAVIGATIONS {
+                            navigations.clear();
+                        }
+                        navigations.insert(navigation.to_string(), status as u16);
+                    }
+                }
+            }
+            "network.beforeRequestSent" if params["isBlocked"].as_bool() == Some(true) => {
+                let request = &params["request"];
+                let request_id = request["request"].clone();
+                let url = request["url"].as_str().unwrap_or_default();
+                let method = request["method"].as_str().unwrap_or("GET");
+
+                let _ = match self
+                    .interception
+                    .intercept(method, url, &resource_type(request))
+                {
+                    InterceptAction::Continue => {
+                        self.notify("network.continueRequest", json!({ "request": request_id }))
+                            .await
+                    }
+                    InterceptAction::Fail => {
+                        self.notify("network.failRequest", json!({ "request": request_id }))
+                            .await
+                    }
+                    InterceptAction::Provide(route) => {
+                        self.notify(
+                            "network.provideResponse",
+                            provide_response(request_id, &route),
+                        )
+                        .await
+                    }
+                };
+            }
+            _ => (),
+        }
+    }
+
+    /// Fail the commands waiting for a response once the connection is gone.
+    fn close_pending(&self) {
+        if let Ok(mut pending) = self.pending.lock() {
+            for (_, tx) in pending.drain() {
+                let _ = tx.send(Err(CdpError::NoResponse));
+            }
+        }
+    }
+}
+
+/// The `network.provideResponse` parameters answering the request with the route.
+fn provide_response(request_id: Value, route: &MockRoute) -> Value {
+    use base64::Engine;
+
+    json!({
+        "request": request_id,
+        "statusCode": route.status,
+        "headers": route
+            .headers
+            .iter()
+            .map(|(name, value)| json!({ "name": name, "value": { "type": "string", "value": value } }))
+            .collect::<Vec<_>>(),
+        "body": {
+            "type": "base64",
+            "value": base64::engine::general_purpose::STANDARD.encode(&route.body),
+        },
+    })
+}
+
+/// Read the messages of the browser until the websocket closes.
+async fn read_messages(
+    connection: Arc<BidiConnection>,
+    mut stream: SplitStream<WebSocketStream<ConnectStream>>,
+) {
+    while let Some(message) = stream.next().await {
+        match message {
+            Ok(WsMessage::Text(text)) => connection.on_message(&text).await,
+            Ok(WsMessage::Close(_)) | Err(_) => break,
+            _ => (),
+        }
+    }
+
+    connection.close_pending();
+}
+
+/// The session url of the WebDriver BiDi server address.
+fn session_url(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(mut parsed) => {
+            match parsed.scheme() {
+                "http" => {
+                    let _ = parsed.set_scheme("ws");
+                }
+                "https" => {
+                    let _ = parsed.set_scheme("wss");
+                }
+                _ => (),
+            }
+            if parsed.path().trim_matches('/').is_empty() {
+                parsed.set_path("/session");
+            }
+            parsed.to_string()
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// A browser driven over WebDriver BiDi like Firefox.
+pub struct BidiBrowser {
+    /// The connection of the session.
+    connection: Arc<BidiConnection>,
+    /// The task reading the messages of the browser.
+    reader: tokio::task::JoinHandle<()>,
+    /// The name of the browser reported by the session.
+    engine: String,
+    /// The process of the launched browser.
+    process: AsyncMutex<Option<Child>>,
+    /// The temporary profile of the launched browser.
+    profile: Option<PathBuf>,
+}
+
+impl std::fmt::Debug for BidiBrowser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BidiBrowser")
+            .field("engine", &self.engine)
+            .finish()
+    }
+}
+
+impl BidiBrowser {
+    /// Connect to the WebDriver BiDi server of a running browser like `ws://127.0.0.1:9222/session` and start a session.
+    pub async fn connect(url: &str, interception: BidiInterception) -> Result<Self> {
+        let config = WebSocketConfig {
+            max_message_size: None,
+            max_frame_size: None,
+            ..Default::default()
+        };
+
+        let (ws, _) =
+            async_tungstenite::tokio::connect_async_with_config(session_url(url), Some(config))
+                .await?;
+        let (sink, stream) = ws.split();
+
+        let connection = Arc::new(BidiConnection {
+            sink: AsyncMutex::new(sink),
+            next_id: AtomicU64::new(0),
+            pending: Default::default(),
+            navigations: Default::default(),
+            interception,
+        });
+
+        let reader = tokio::spawn(read_messages(connection.clone(), stream));
+
+        let mut browser = Self {
+            connection,
+            reader,
+            engine: String::new(),
+            process: AsyncMutex::new(None),
+            profile: None,
+        };
+
+        let session = browser
+            .connection
+            .command(
+                "session.new",
+                json!({ "capabilities": { "alwaysMatch": { "acceptInsecureCerts": true } } }),
+            )
+            .await?;
+
+        browser.engine = session["capabilities"]["browserName"]
+            .as_str()
+            .unwrap_or("firefox")
+            .to_string();
+
+        let mut events = vec!["network.responseCompleted"];
+
+        if browser.connection.interception.enabled() {
+            events.push("network.beforeRequestSent");
+            browser
+                .connection
+                .command(
+                    "network.addIntercept",
+                    json!({ "phases": ["beforeRequestSent"] }),
+                )
+                .await?;
+        }
+
+        browser
+            .connection
+            .command("session.subscribe", json!({ "events": events }))
+            .await?;
+
+        Ok(browser)
+    }
+
+    /// Launch a headless Firefox with a temporary profile and connect to its WebDriver BiDi server.
+    pub async fn launch(
+        executable: impl AsRef<Path>,
+        args: &[String],
+        interception: BidiInterception,
+    ) -> Result<Self> {
+        let profile = crate::reaper::temp_profile_dir();
+        std::fs::create_dir_all(&profile)?;
+
+        let mut child = Command::new(executable.as_ref())
+            .args([
+                "--headless",
+                "--no-remote",
+                "--remote-debugging-port=0",
+                "--profile",
+            ])
+            .arg(&profile)
+            .args(args)
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let url = match tokio::time::timeout(LAUNCH_TIMEOUT, bidi_url_from_output(&mut child)).await
+        {
+            Ok(Ok(url)) => url,
+            Ok(Err(err)) => {
+                let _ = child.kill().await;
+                crate::reaper::remove_profile(&profile);
+                return Err(err);
+            }
+            Err(_) => {
+                let _ = child.kill().await;
+                crate::reaper::remove_profile(&profile);
+                return Err(CdpError::Timeout);
+            }
+        };
+
+        match Self::connect(&url, interception).await {
+            Ok(mut browser) => {
+                browser.process = AsyncMutex::new(Some(child));
+                browser.profile = Some(profile);
+                Ok(browser)
+            }
+            Err(err) => {
+                let _ = child.kill().await;
+                crate::reaper::remove_profile(&profile);
+                Err(err)
+            }
+        }
+    }
+}
+
+/// The address of the WebDriver BiDi server printed by the browser like `WebDriver BiDi listening on ws://127.0.0.1:9222`.
+async fn bidi_url_from_output(child: &mut Child) -> Result<String> {
+    use futures::AsyncBufReadExt;
+
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| CdpError::msg("The browser has no stderr."))?;
+    let mut reader = futures::io::BufReader::new(stderr);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+
+        if reader.read_line(&mut line).await? == 0 {
+            return Err(CdpError::msg(
+                "The browser exited before starting WebDriver BiDi.",
+            ));
+        }
+
+        if let Some((_, url)) = line.rsplit_once("WebDriver BiDi listening on ") {
+            return Ok(url.trim().to_string());
+        }
+    }
+}
+
+impl Drop for BidiBrowser {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+impl BrowserBackend for BidiBrowser {
+    fn engine(&self) -> &str {
+        &self.engine
+    }
+
+    fn new_page(&self) -> BoxFuture<'_, Result<Box<dyn BackendPage>>> {
+        Box::pin(async move {
+            let result = self
+                .connection
+                .command("browsingContext.create", json!({ "type": "tab" }))
+                .await?;
+
+            let context = result["context"]
+                .as_str()
+                .ok_or_else(|| CdpError::msg("The browsing context was not created."))?;
+
+            Ok(Box::new(BidiPage {
+                connection: self.connection.clone(),
+                context: context.to_string(),
+            }) as Box<dyn BackendPage>)
+        })
+    }
+
+    fn close(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let _ = self.connection.command("browser.close", json!({})).await;
+
+            if let Some(mut child) = self.process.lock().await.take() {
+                let _ = child.kill().await;
+            }
+            if let Some(profile) = &self.profile {
+                crate::reaper::remove_profile(profile);
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// A browsing context of a [`BidiBrowser`].
+#[derive(Clone)]
+pub struct BidiPage {
+    /// The connection of the session.
+    connection: Arc<BidiConnection>,
+    /// The identifier of the browsing context.
+    context: String,
+}
+
+impl std::fmt::Debug for BidiPage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BidiPage")
+            .field("context", &self.context)
+            .finish()
+    }
+}
+
+impl BidiPage {
+    /// Evaluate the expression in the page and return the remote value of the result.
+    async fn evaluate_remote(&self, expression: &str) -> Result<Value> {
+        let result = self
+            .connection
+            .command(
+                "script.evaluate",
+                json!({
+                    "expression": expression,
+                    "target": { "context": self.context },
+                    "awaitPromise": true,
+                    "resultOwnership": "none",
+                }),
+            )
+            .await?;
+
+        match result["type"].as_str() {
+            Some("success") => Ok(result["result"].clone()),
+            _ => Err(CdpError::msg(
+                result["exceptionDetails"]["text"]
+                    .as_str()
+                    .unwrap_or("The script threw an exception."),
+            )),
+        }
+    }
+}
+
+impl BackendPage for BidiPage {
+    fn goto<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Option<u16>>> {
+        Box::pin(async move {
+            let result = self
+                .connection
+                .command(
+                    "browsingContext.navigate",
+                    json!({ "context": self.context, "url": url, "wait": "complete" }),
+                )
+                .await?;
+
+            Ok(
+                match (
+                    result["navigation"].as_str(),
+                    self.connection.navigations.lock(),
+                ) {
+                    (Some(navigation), Ok(mut navigations)) => navigations.remove(navigation),
+                    _ => None,
+                },
+            )
+        })
+    }
+
+    fn content(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(async move {
+            let value = self
+                .evaluate_remote("document.documentElement.outerHTML")
+                .await?;
+
+            Ok(value["value"].as_str().unwrap_or_default().to_string())
+        })
+    }
+
+    fn evaluate<'a>(&'a self, expression: &'a str) -> BoxFuture<'a, Result<serde_json::Value>> {
+        Box::pin(async move {
+            let value = self
+                .evaluate_remote(&format!(
+                    "(async () => JSON.stringify(await ({expression})))()"
+                ))
+                .await?;
+
+            match value["value"].as_str() {
+                Some(json) => Ok(serde_json::from_str(json)?),
+                _ => Ok(Value::Null),
+            }
+        })
+    }
+
+    fn close(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            self.connection
+                .command("browsingContext.close", json!({ "context": self.context }))
+                .await?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intercept_requests() {
+        let interception = BidiInterception {
+            ignore_visuals: true,
+            third_party: Some(ThirdPartyPolicy::new(|url| {
+                !url::Url::parse(url)
+                    .ok()
+                    .and_then(|u| u.host_str().map(|host| host.ends_with("example.com")))
+                    .unwrap_or(true)
+            })),
+            mock_router: Some(MockRouter {
+                routes: vec![MockRoute {
+                    pattern: "https://example.com/api/*".into(),
+                    status: 200,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert!(interception.enabled());
+        assert!(!BidiInterception::default().enabled());
+        assert!(matches!(
+            interception.intercept("GET", "https://example.com/api/items", &ResourceType::Fetch),
+            InterceptAction::Provide(_)
+        ));
+        assert_eq!(
+            interception.intercept("GET", "https://example.com/logo.png", &ResourceType::Image),
+            InterceptAction::Fail
+        );
+        assert_eq!(
+            interception.intercept("GET", "https://cdn.tracker.io/t.js", &ResourceType::Script),
+            InterceptAction::Fail
+        );
+        assert_eq!(
+            interception.intercept("GET", "https://other.org/", &ResourceType::Document),
+            InterceptAction::Continue
+        );
+        assert_eq!(
+            interception.intercept(
+                "GET",
+                "https://www.example.com/app.js",
+                &ResourceType::Script
+            ),
+            InterceptAction::Continue
+        );
+    }
+
+    #[test]
+    fn request_resource_type() {
+        assert_eq!(
+            resource_type(&json!({ "destination": "image" })),
+            ResourceType::Image
+        );
+        assert_eq!(
+            resource_type(&json!({ "destination": "", "initiatorType": "fetch" })),
+            ResourceType::Fetch
+        );
+        assert_eq!(resource_type(&json!({})), ResourceType::Other);
+    }
+
+    #[test]
+    fn bidi_session_url() {
+        assert_eq!(
+            session_url("ws://127.0.0.1:9222"),
+            "ws://127.0.0.1:9222/session"
+        );
+        assert_eq!(
+            session_url("http://127.0.0.1:9222/"),
+            "ws://127.0.0.1:9222/session"
+        );
+        assert_eq!(
+            session_url("ws://127.0.0.1:9222/session/abc"),
+            "ws://127.0.0.1:9222/session/abc"
+        );
+    }
+}
@@ -28,10 +28,13 @@ use crate::conn::Connection;
 use crate::detection::{self, DetectionOptions};
 use crate::error::{BrowserStderr, CdpError, Result};
 use crate::handler::browser::BrowserContext;
+use crate::handler::resource_policy::ResourcePolicy;
 use crate::handler::viewport::Viewport;
 use crate::handler::{Handler, HandlerConfig, HandlerMessage, REQUEST_TIMEOUT};
+use crate::headers::Headers;
 use crate::listeners::{EventListenerRequest, EventStream};
 use crate::page::Page;
+use crate::reaper;
 use crate::utils;
 use chromiumoxide_cdp::cdp::browser_protocol::browser::{
     BrowserContextId, CloseReturns, GetVersionParams, GetVersionReturns,
@@ -50,6 +53,8 @@ pub struct Browser {
     config: Option<BrowserConfig>,
     /// The spawned chromium instance
     child: Option<Child>,
+    /// The temporary profile directory created for the spawned instance, removed on drop
+    temp_profile: Option<PathBuf>,
     /// The debug web socket url of the chromium instance
     debug_ws_url: String,
     /// The context of the browser
@@ -79,6 +84,32 @@ pub struct BrowserConnection {
     pub web_socket_debugger_url: String,
 }
 
+/// The `json/version` endpoint of the http URL keeping its query.
+fn json_version_url(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(mut parsed) => {
+            let path = parsed.path().trim_end_matches('/');
+            if !path.ends_with("/json/version") {
+                let path = format!("{path}/json/version");
+                parsed.set_path(&path);
+            }
+            parsed.to_string()
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// The Websocket URL with the query of the URL it was retrieved from, when it has none.
+fn with_query_of(ws_url: String, url: &str) -> String {
+    match (url::Url::parse(&ws_url), url::Url::parse(url)) {
+        (Ok(mut ws), Ok(parsed)) if ws.query().is_none() && parsed.query().is_some() => {
+            ws.set_query(parsed.query());
+            ws.to_string()
+        }
+        _ => ws_url,
+    }
+}
+
 impl Browser {
     /// Connect to an already running chromium instance via the given URL.
     ///
@@ -93,51 +124,58 @@ impl Browser {
     pub async fn connect_with_config(
         url: impl Into<String>,
         config: HandlerConfig,
+    ) -> Result<(Self, Handler)> {
+        Self::connect_with_auth(url, None, config).await
+    }
+
+    /// Connect to a remote chromium instance, like a browserless endpoint, with a given `HandlerConfig`.
+    ///
+    /// The token is sent as a bearer `Authorization` header of the `json/version` request and the websocket handshake.
+    /// The query of the URL, like a `?token=` parameter, is kept on the Websocket URL retrieved from `json/version`.
+    pub async fn connect_with_auth(
+        url: impl Into<String>,
+        auth_token: Option<&str>,
+        config: HandlerConfig,
     ) -> Result<(Self, Handler)> {
         let mut debug_ws_url = url.into();
 
         if debug_ws_url.starts_with("http") {
-            match reqwest::Client::new()
-                .get(
-                    if debug_ws_url.ends_with("/json/version")
-                        || debug_ws_url.ends_with("/json/version/")
-                    {
-                        debug_ws_url.clone()
-                    } else {
-                        format!(
-                            "{}{}json/version",
-                            &debug_ws_url,
-                            if debug_ws_url.ends_with('/') { "" } else { "/" }
-                        )
-                    },
-                )
-                .header("content-type", "application/json")
-                .send()
-                .await
-            {
+            let mut request = reqwest::Client::new()
+                .get(json_version_url(&debug_ws_url))
+                .header("content-type", "application/json");
+
+            if let Some(auth_token) = auth_token {
+                request = request.bearer_auth(auth_token);
+            }
+
+            match request.send().await {
                 Ok(req) => {
                     let connection: BrowserConnection =
                         serde_json::from_slice(&req.bytes().await.unwrap_or_default())
                             .unwrap_or_default();
                     if !connection.web_socket_debugger_url.is_empty() {
-                        debug_ws_url = connection.web_socket_debugger_url;
+                        debug_ws_url =
+                            with_query_of(connection.web_socket_debugger_url, &debug_ws_url);
                     }
                 }
                 Err(_) => return Err(CdpError::NoResponse),
             }
         }
 
-        let conn = Connection::<CdpEventMessage>::connect(&debug_ws_url).await?;
+        let conn =
+            Connection::<CdpEventMessage>::connect_with_auth(&debug_ws_url, auth_token).await?;
 
         let (tx, rx) = channel(1000);
 
-        let fut = Handler::new(conn, rx, config);
+        let mut fut = Handler::new(conn, rx, config);
+        fut.set_reconnect_url(debug_ws_url.clone(), auth_token.map(String::from));
         let browser_context = fut.default_browser_context().clone();
 
         let browser = Self {
             sender: tx,
             config: None,
             child: None,
+            temp_profile: None,
             debug_ws_url,
             browser_context,
         };
@@ -157,8 +195,33 @@ impl Browser {
         // Canonalize paths to reduce issues with sandboxing
         config.executable = utils::canonicalize_except_snap(config.executable).await?;
 
+        // Give every instance its own throwaway profile so concurrent browsers do not fight over
+        // the profile lock and the directory can be removed once the browser is gone.
+        let temp_profile = if config.user_data_dir.is_none() {
+            let dir = match config.profile_root {
+                Some(ref root) => reaper::temp_profile_dir_in(root),
+                _ => reaper::temp_profile_dir(),
+            };
+            config.user_data_dir = Some(dir.clone());
+            Some(dir)
+        } else {
+            None
+        };
+
         // Launch a new chromium instance
-        let mut child = config.launch()?;
+        let mut child = match config.launch() {
+            Ok(child) => child,
+            Err(e) => {
+                if let Some(ref profile) = temp_profile {
+                    reaper::remove_profile(profile);
+                }
+                return Err(e.into());
+            }
+        };
+
+        if let Some(pid) = child.id() {
+            reaper::register(pid, temp_profile.clone());
+        }
 
         /// Faillible initialization to run once the child process is created.
         ///
@@ -188,6 +251,12 @@ impl Browser {
                     child.kill().await.expect("`Browser::launch` failed but could not clean-up the child process (`kill`)");
                     child.wait().await.expect("`Browser::launch` failed but could not clean-up the child process (`wait`)");
                 }
+                if let Some(pid) = child.id() {
+                    reaper::unregister(pid);
+                }
+                if let Some(ref profile) = temp_profile {
+                    reaper::remove_profile(profile);
+                }
                 return Err(e);
             }
         };
@@ -208,9 +277,14 @@ impl Browser {
             ignore_stylesheets: config.ignore_stylesheets,
             ignore_javascript: config.ignore_javascript,
             ignore_ads: config.ignore_ads,
+            bypass_service_worker: config.bypass_service_worker,
+            block_service_workers: config.block_service_workers,
+            resource_policy: config.resource_policy.clone(),
             extra_headers: config.extra_headers.clone(),
             only_html: config.only_html,
             created_first_target: false,
+            event_drop_rate: config.event_drop_rate,
+            ..Default::default()
         };
 
         let fut = Handler::new(conn, rx, handler_config);
@@ -220,6 +294,7 @@ impl Browser {
             sender: tx,
             config: Some(config),
             child: Some(child),
+            temp_profile,
             debug_ws_url,
             browser_context,
         };
@@ -327,7 +402,12 @@ impl Browser {
     /// connected to an existing browser through [`Browser::connect`])
     pub async fn kill(&mut self) -> Option<io::Result<()>> {
         match self.child.as_mut() {
-            Some(child) => Some(child.kill().await),
+            Some(child) => {
+                if let Some(pid) = child.id() {
+                    reaper::kill_process_group(pid);
+                }
+                Some(child.kill().await)
+            }
             None => None,
         }
     }
@@ -537,9 +617,17 @@ impl Browser {
 impl Drop for Browser {
     fn drop(&mut self) {
         if let Some(child) = self.child.as_mut() {
+            let pid = child.id();
+
             if let Ok(Some(_)) = child.try_wait() {
                 // Already exited, do nothing. Usually occurs after using the method close or kill.
             } else {
+                // The helper processes share the browser's process group and are not covered by
+                // `kill_on_drop`, take the whole group down before it can be orphaned.
+                if let Some(pid) = pid {
+                    reaper::kill_process_group(pid);
+                    let _ = child.try_wait();
+                }
                 // We set the `kill_on_drop` property for the child process, so no need to explicitely
                 // kill it here. It can't really be done anyway since the method is async.
                 //
@@ -549,6 +637,14 @@ impl Drop for Browser {
                 // warn him here.
                 tracing::warn!("Browser was not closed manually, it will be killed automatically in the background");
             }
+
+            if let Some(pid) = pid {
+                reaper::unregister(pid);
+            }
+        }
+
+        if let Some(ref profile) = self.temp_profile {
+            reaper::remove_profile(profile);
         }
     }
 }
@@ -655,6 +751,13 @@ pub struct BrowserConfig {
     /// Data dir for user data
     pub user_data_dir: Option<PathBuf>,
 
+    /// Directory the throwaway profiles are created in when no `user_data_dir` is set. Defaults
+    /// to the system temp dir.
+    pub profile_root: Option<PathBuf>,
+
+    /// Maximum size in bytes of the disk cache of the browser.
+    pub disk_cache_size: Option<u64>,
+
     /// Whether to launch the `Browser` in incognito mode
     incognito: bool,
 
@@ -687,10 +790,18 @@ pub struct BrowserConfig {
     pub ignore_javascript: bool,
     /// Whether to ignore ads when request interception is enabled.
     pub ignore_ads: bool,
+    /// Whether to load pages from the network instead of the service workers.
+    pub bypass_service_worker: bool,
+    /// Whether to block the service worker scripts when request interception is enabled.
+    pub block_service_workers: bool,
+    /// The actions by resource type when request interception is enabled, taking precedence over the ignore flags.
+    pub resource_policy: ResourcePolicy,
     /// Extra headers.
-    pub extra_headers: Option<HashMap<String, String>>,
+    pub extra_headers: Option<Headers>,
     /// Only html
     pub only_html: bool,
+    /// The share of the CDP events dropped before they reach the event listeners between 0.0 and 1.0.
+    pub event_drop_rate: f64,
 }
 
 #[derive(Debug, Clone)]
@@ -704,6 +815,8 @@ pub struct BrowserConfigBuilder {
     extensions: Vec<String>,
     process_envs: Option<HashMap<String, String>>,
     user_data_dir: Option<PathBuf>,
+    profile_root: Option<PathBuf>,
+    disk_cache_size: Option<u64>,
     incognito: bool,
     launch_timeout: Duration,
     ignore_https_errors: bool,
@@ -717,8 +830,12 @@ pub struct BrowserConfigBuilder {
     ignore_ads: bool,
     ignore_javascript: bool,
     ignore_stylesheets: bool,
+    bypass_service_worker: bool,
+    block_service_workers: bool,
+    resource_policy: ResourcePolicy,
     only_html: bool,
-    extra_headers: Option<HashMap<String, String>>,
+    extra_headers: Option<Headers>,
+    event_drop_rate: f64,
 }
 
 impl BrowserConfig {
@@ -743,6 +860,8 @@ impl Default for BrowserConfigBuilder {
             extensions: Vec::new(),
             process_envs: None,
             user_data_dir: None,
+            profile_root: None,
+            disk_cache_size: None,
             incognito: false,
             launch_timeout: Duration::from_millis(LAUNCH_TIMEOUT),
             ignore_https_errors: true,
@@ -756,8 +875,12 @@ impl Default for BrowserConfigBuilder {
             ignore_ads: false,
             ignore_javascript: false,
             ignore_stylesheets: false,
+            bypass_service_worker: false,
+            block_service_workers: false,
+            resource_policy: Default::default(),
             only_html: false,
             extra_headers: Default::default(),
+            event_drop_rate: 0.0,
         }
     }
 }
@@ -828,6 +951,18 @@ impl BrowserConfigBuilder {
         self
     }
 
+    /// Create the throwaway profiles in the directory instead of the system temp dir.
+    pub fn profile_root(mut self, root: impl AsRef<Path>) -> Self {
+        self.profile_root = Some(root.as_ref().to_path_buf());
+        self
+    }
+
+    /// Limit the disk cache of the browser to the size in bytes.
+    pub fn disk_cache_size(mut self, size: u64) -> Self {
+        self.disk_cache_size = Some(size);
+        self
+    }
+
     pub fn chrome_executable(mut self, path: impl AsRef<Path>) -> Self {
         self.executable = Some(path.as_ref().to_path_buf());
         self
@@ -913,10 +1048,22 @@ impl BrowserConfigBuilder {
         self.cache_enabled = false;
         self
     }
-    pub fn set_extra_headers(mut self, headers: Option<HashMap<String, String>>) -> Self {
+    pub fn set_extra_headers(mut self, headers: Option<Headers>) -> Self {
         self.extra_headers = headers;
         self
     }
+
+    /// Set the actions by resource type of the intercepted requests, taking precedence over the ignore flags.
+    pub fn resource_policy(mut self, policy: ResourcePolicy) -> Self {
+        self.resource_policy = policy;
+        self
+    }
+
+    /// Drop the share of the CDP events between 0.0 and 1.0 before they reach the event listeners of this browser.
+    pub fn event_drop_rate(mut self, rate: f64) -> Self {
+        self.event_drop_rate = rate.clamp(0.0, 1.0);
+        self
+    }
     pub fn build(self) -> std::result::Result<BrowserConfig, String> {
         let executable = if let Some(e) = self.executable {
             e
@@ -933,6 +1080,8 @@ impl BrowserConfigBuilder {
             extensions: self.extensions,
             process_envs: self.process_envs,
             user_data_dir: self.user_data_dir,
+            profile_root: self.profile_root,
+            disk_cache_size: self.disk_cache_size,
             incognito: self.incognito,
             launch_timeout: self.launch_timeout,
             ignore_https_errors: self.ignore_https_errors,
@@ -946,8 +1095,12 @@ impl BrowserConfigBuilder {
             ignore_ads: self.ignore_ads,
             ignore_javascript: self.ignore_javascript,
             ignore_stylesheets: self.ignore_stylesheets,
+            bypass_service_worker: self.bypass_service_worker,
+            block_service_workers: self.block_service_workers,
+            resource_policy: self.resource_policy,
             extra_headers: self.extra_headers,
             only_html: self.only_html,
+            event_drop_rate: self.event_drop_rate,
         })
     }
 }
@@ -988,6 +1141,10 @@ impl BrowserConfig {
             ));
         }
 
+        if let Some(size) = self.disk_cache_size {
+            cmd.arg(format!("--disk-cache-size={size}"));
+        }
+
         if let Some((width, height)) = self.window_size {
             cmd.arg(format!("--window-size={width},{height}"));
         }
@@ -0,0 +1,27 @@
+//! Fault injection for the events of the browser.
+//!
+//! Dropping a share of the CDP events before they reach the event listeners reproduces a slow or
+//! flaky browser: the handler still tracks the frames and the network, only the listeners of the
+//! pages miss the events. This lets the callers check their timeouts and retries before relying
+//! on them in production.
+//!
+//! Each browser drops the events at the `event_drop_rate` of its handler config, nothing is
+//! dropped until the rate is above zero.
+
+/// Whether the next event is dropped at the rate between 0.0 and 1.0.
+pub(crate) fn drop_event(rate: f64) -> bool {
+    rate > 0.0 && rand::random::<f64>() < rate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_events_at_rate() {
+        assert!(drop_event(1.0));
+        assert!(drop_event(2.0));
+        assert!(!drop_event(0.0));
+        assert!(!drop_event(-1.0));
+    }
+}
@@ -126,6 +126,8 @@ impl CommandChain {
     /// issued command
     pub fn received_response(&mut self, identifier: &str) -> bool {
         if self.waiting.as_ref().map(|(c, _)| c.as_ref()) == Some(identifier) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(method = identifier, "command chain response");
             self.waiting.take();
             true
         } else {
@@ -148,6 +150,12 @@ impl CommandChain {
                 Poll::Pending
             }
         } else if let Some((method, val)) = self.cmds.pop_front() {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = method.as_ref(),
+                remaining = self.cmds.len(),
+                "command chain next"
+            );
             self.waiting = Some((method.clone(), now + self.timeout));
             Poll::Ready(Some(Ok((method, val))))
         } else {
@@ -3,6 +3,8 @@ use std::marker::PhantomData;
 use std::pin::Pin;
 use std::task::ready;
 
+use async_tungstenite::tungstenite::client::IntoClientRequest;
+use async_tungstenite::tungstenite::http::header::{HeaderValue, AUTHORIZATION};
 use async_tungstenite::tungstenite::Message as WsMessage;
 use async_tungstenite::{tungstenite::protocol::WebSocketConfig, WebSocketStream};
 use futures::stream::Stream;
@@ -34,17 +36,30 @@ pub struct Connection<T: EventMessage> {
 
 impl<T: EventMessage + Unpin> Connection<T> {
     pub async fn connect(debug_ws_url: impl AsRef<str>) -> Result<Self> {
+        Self::connect_with_auth(debug_ws_url, None).await
+    }
+
+    /// Connect to the websocket sending the token as a bearer `Authorization` header of the handshake.
+    pub async fn connect_with_auth(
+        debug_ws_url: impl AsRef<str>,
+        auth_token: Option<&str>,
+    ) -> Result<Self> {
         let config = WebSocketConfig {
             max_message_size: None,
             max_frame_size: None,
             ..Default::default()
         };
 
-        let (ws, _) = async_tungstenite::tokio::connect_async_with_config(
-            debug_ws_url.as_ref(),
-            Some(config),
-        )
-        .await?;
+        let mut request = debug_ws_url.as_ref().into_client_request()?;
+
+        if let Some(auth_token) = auth_token {
+            let value = HeaderValue::from_str(&format!("Bearer {auth_token}"))
+                .map_err(|_| CdpError::msg("Invalid auth token."))?;
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
+
+        let (ws, _) =
+            async_tungstenite::tokio::connect_async_with_config(request, Some(config)).await?;
 
         Ok(Self {
             pending_commands: Default::default(),
@@ -19,14 +19,17 @@ impl Default for DetectionOptions {
     }
 }
 
+/// The environment variables checked for an executable path, in order.
+const ENV_VARS: [&str; 3] = ["CHROME", "CHROME_PATH", "CHROME_BIN"];
+
 /// Returns the path to Chrome's executable.
 ///
 /// The following elements will be checked:
-///   - `CHROME` environment variable
+///   - `CHROME`, `CHROME_PATH` and `CHROME_BIN` environment variables
 ///   - Usual filenames in the user path
 ///   - (Windows) Registry
-///   - (Windows & MacOS) Usual installations paths
-///     If all of the above fail, an error is returned.
+///   - Usual installations paths (per user installs included on Windows & MacOS)
+///     If all of the above fail, an error listing the locations checked is returned.
 pub fn default_executable(options: DetectionOptions) -> Result<std::path::PathBuf, String> {
     if let Some(path) = get_by_env_var() {
         return Ok(path);
@@ -37,7 +40,7 @@ pub fn default_executable(options: DetectionOptions) -> Result<std::path::PathBu
     }
 
     #[cfg(windows)]
-    if let Some(path) = get_by_registry() {
+    if let Some(path) = get_by_registry(&options) {
         return Ok(path);
     }
 
@@ -45,20 +48,29 @@ pub fn default_executable(options: DetectionOptions) -> Result<std::path::PathBu
         return Ok(path);
     }
 
-    Err("Could not auto detect a chrome executable".to_string())
+    Err(format!(
+        "Could not auto detect a chrome executable. Install Chrome, Chromium or Edge or set the path with the `CHROME` environment variable. Checked the environment variables {}, the executables {} in the PATH{} and the paths {}",
+        ENV_VARS.join(", "),
+        app_names(&options).join(", "),
+        if cfg!(windows) { ", the registry" } else { "" },
+        default_paths(&options)
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
 }
 
 fn get_by_env_var() -> Option<PathBuf> {
-    if let Ok(path) = env::var("CHROME") {
-        if Path::new(&path).exists() {
-            return Some(path.into());
-        }
-    }
-
-    None
+    ENV_VARS
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|path| Path::new(path).exists())
+        .map(PathBuf::from)
 }
 
-fn get_by_name(options: &DetectionOptions) -> Option<PathBuf> {
+/// The executable names searched in the PATH.
+fn app_names(options: &DetectionOptions) -> Vec<&'static str> {
     let default_apps = [
         ("chrome", true),
         ("chrome-browser", true),
@@ -74,90 +86,182 @@ fn get_by_name(options: &DetectionOptions) -> Option<PathBuf> {
         ("microsoft-edge-beta", options.msedge && options.unstable),
         ("microsoft-edge-dev", options.msedge && options.unstable),
     ];
-    for (app, allowed) in default_apps {
-        if !allowed {
-            continue;
-        }
-        if let Ok(path) = which::which(app) {
-            return Some(path);
-        }
-    }
 
-    None
+    default_apps
+        .into_iter()
+        .filter(|(_, allowed)| *allowed)
+        .map(|(app, _)| app)
+        .collect()
 }
 
+fn get_by_name(options: &DetectionOptions) -> Option<PathBuf> {
+    app_names(options)
+        .into_iter()
+        .find_map(|app| which::which(app).ok())
+}
+
+/// The usual installation paths of the platform.
 #[allow(unused_variables)]
-fn get_by_path(options: &DetectionOptions) -> Option<PathBuf> {
+fn default_paths(options: &DetectionOptions) -> Vec<PathBuf> {
     #[cfg(all(unix, not(target_os = "macos")))]
-    let default_paths: [(&str, bool); 3] = [
-        ("/opt/chromium.org/chromium", true),
-        ("/opt/google/chrome", true),
+    let default_paths: Vec<(PathBuf, bool)> = vec![
+        ("/opt/chromium.org/chromium".into(), true),
+        ("/opt/google/chrome".into(), true),
+        ("/opt/google/chrome/chrome".into(), true),
+        ("/opt/microsoft/msedge/msedge".into(), options.msedge),
+        ("/snap/bin/chromium".into(), true),
         // test for lambda
-        ("/tmp/aws/lib", true),
+        ("/tmp/aws/lib".into(), true),
     ];
+
     #[cfg(windows)]
-    let default_paths = [(
-        r"C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe",
-        options.msedge,
-    )];
-    #[cfg(target_os = "macos")]
-    let default_paths = [
-        (
-            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
-            true,
-        ),
-        (
-            "/Applications/Google Chrome Beta.app/Contents/MacOS/Google Chrome Beta",
-            options.unstable,
-        ),
-        (
-            "/Applications/Google Chrome Dev.app/Contents/MacOS/Google Chrome Dev",
-            options.unstable,
-        ),
-        (
-            "/Applications/Google Chrome Canary.app/Contents/MacOS/Google Chrome Canary",
-            options.unstable,
-        ),
-        ("/Applications/Chromium.app/Contents/MacOS/Chromium", true),
-        (
-            "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
-            options.msedge,
-        ),
-        (
-            "/Applications/Microsoft Edge Beta.app/Contents/MacOS/Microsoft Edge Beta",
-            options.msedge && options.unstable,
-        ),
-        (
-            "/Applications/Microsoft Edge Dev.app/Contents/MacOS/Microsoft Edge Dev",
-            options.msedge && options.unstable,
-        ),
-        (
-            "/Applications/Microsoft Edge Canary.app/Contents/MacOS/Microsoft Edge Canary",
-            options.msedge && options.unstable,
-        ),
-    ];
+    let default_paths: Vec<(PathBuf, bool)> = {
+        let installs = [
+            (r"Google\Chrome\Application\chrome.exe", true),
+            (
+                r"Google\Chrome Beta\Application\chrome.exe",
+                options.unstable,
+            ),
+            (
+                r"Google\Chrome Dev\Application\chrome.exe",
+                options.unstable,
+            ),
+            (
+                r"Google\Chrome SxS\Application\chrome.exe",
+                options.unstable,
+            ),
+            (r"Chromium\Application\chrome.exe", true),
+            (r"Microsoft\Edge\Application\msedge.exe", options.msedge),
+            (
+                r"Microsoft\Edge Beta\Application\msedge.exe",
+                options.msedge && options.unstable,
+            ),
+            (
+                r"Microsoft\Edge Dev\Application\msedge.exe",
+                options.msedge && options.unstable,
+            ),
+        ];
+        // machine wide installs first then per user installs.
+        let roots: Vec<PathBuf> = ["ProgramFiles", "ProgramFiles(x86)", "LOCALAPPDATA"]
+            .iter()
+            .filter_map(|var| env::var_os(var))
+            .map(PathBuf::from)
+            .collect();
 
-    for (path, allowed) in default_paths {
-        if !allowed {
-            continue;
-        }
-        if Path::new(path).exists() {
-            return Some(path.into());
+        roots
+            .iter()
+            .flat_map(|root| {
+                installs
+                    .iter()
+                    .map(move |(install, allowed)| (root.join(install), *allowed))
+            })
+            .collect()
+    };
+
+    #[cfg(target_os = "macos")]
+    let default_paths: Vec<(PathBuf, bool)> = {
+        let apps = [
+            ("Google Chrome.app/Contents/MacOS/Google Chrome", true),
+            (
+                "Google Chrome Beta.app/Contents/MacOS/Google Chrome Beta",
+                options.unstable,
+            ),
+            (
+                "Google Chrome Dev.app/Contents/MacOS/Google Chrome Dev",
+                options.unstable,
+            ),
+            (
+                "Google Chrome Canary.app/Contents/MacOS/Google Chrome Canary",
+                options.unstable,
+            ),
+            ("Chromium.app/Contents/MacOS/Chromium", true),
+            (
+                "Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+                options.msedge,
+            ),
+            (
+                "Microsoft Edge Beta.app/Contents/MacOS/Microsoft Edge Beta",
+                options.msedge && options.unstable,
+            ),
+            (
+                "Microsoft Edge Dev.app/Contents/MacOS/Microsoft Edge Dev",
+                options.msedge && options.unstable,
+            ),
+            (
+                "Microsoft Edge Canary.app/Contents/MacOS/Microsoft Edge Canary",
+                options.msedge && options.unstable,
+            ),
+        ];
+        // system applications first then the applications of the user.
+        let mut roots = vec![PathBuf::from("/Applications")];
+        if let Some(home) = env::var_os("HOME") {
+            roots.push(PathBuf::from(home).join("Applications"));
         }
-    }
 
-    None
+        roots
+            .iter()
+            .flat_map(|root| {
+                apps.iter()
+                    .map(move |(app, allowed)| (root.join(app), *allowed))
+            })
+            .collect()
+    };
+
+    #[cfg(not(any(unix, windows)))]
+    let default_paths: Vec<(PathBuf, bool)> = Vec::new();
+
+    default_paths
+        .into_iter()
+        .filter(|(_, allowed)| *allowed)
+        .map(|(path, _)| path)
+        .collect()
+}
+
+fn get_by_path(options: &DetectionOptions) -> Option<PathBuf> {
+    default_paths(options)
+        .into_iter()
+        .find(|path| path.exists())
 }
 
 #[cfg(windows)]
-fn get_by_registry() -> Option<PathBuf> {
-    winreg::RegKey::predef(winreg::enums::HKEY_LOCAL_MACHINE)
-        .open_subkey("SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\App Paths\\chrome.exe")
-        .or_else(|_| {
-            winreg::RegKey::predef(winreg::enums::HKEY_CURRENT_USER)
-                .open_subkey("Software\\Microsoft\\Windows\\CurrentVersion\\App Paths\\chrome.exe")
-        })
-        .and_then(|key| key.get_value::<String, _>(""))
-        .map(PathBuf::from)
-        .ok()
+fn get_by_registry(options: &DetectionOptions) -> Option<PathBuf> {
+    let exes: &[&str] = if options.msedge {
+        &["chrome.exe", "msedge.exe"]
+    } else {
+        &["chrome.exe"]
+    };
+
+    exes.iter().find_map(|exe| {
+        let key = format!("Software\\Microsoft\\Windows\\CurrentVersion\\App Paths\\{exe}");
+
+        winreg::RegKey::predef(winreg::enums::HKEY_LOCAL_MACHINE)
+            .open_subkey(&key)
+            .or_else(|_| winreg::RegKey::predef(winreg::enums::HKEY_CURRENT_USER).open_subkey(&key))
+            .and_then(|key| key.get_value::<String, _>(""))
+            .map(PathBuf::from)
+            .ok()
+            .filter(|path| path.exists())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detection_error_lists_locations() {
+        let options = DetectionOptions {
+            msedge: false,
+            unstable: false,
+        };
+
+        assert!(!app_names(&options).contains(&"msedge"));
+        assert!(!default_paths(&options).is_empty());
+
+        if default_executable(options.clone()).is_err() {
+            let err = default_executable(options).unwrap_err();
+            assert!(err.contains("CHROME_BIN"));
+            assert!(err.contains("chromium"));
+        }
+    }
 }
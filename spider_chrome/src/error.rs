@@ -61,6 +61,12 @@ pub enum CdpError {
     JavascriptException(Box<ExceptionDetails>),
     #[error("{0}")]
     Url(#[from] url::ParseError),
+    /// The renderer of the target crashed while the request was in flight.
+    #[error("Target crashed.")]
+    TargetCrashed,
+    /// The session of the target detached while the request was in flight.
+    #[error("Target detached.")]
+    TargetDetached,
 }
 impl CdpError {
     pub fn msg(msg: impl Into<String>) -> Self {
@@ -93,6 +99,8 @@ impl From<NavigationError> for CdpError {
         match err {
             NavigationError::Timeout { .. } => CdpError::Timeout,
             NavigationError::FrameNotFound { frame, .. } => CdpError::FrameNotFound(frame),
+            NavigationError::TargetCrashed { .. } => CdpError::TargetCrashed,
+            NavigationError::TargetDetached { .. } => CdpError::TargetDetached,
         }
     }
 }
@@ -42,7 +42,11 @@ impl EmulationManager {
             .build()
             .unwrap();
 
-        let set_touch = SetTouchEmulationEnabledParams::new(true);
+        let mut set_touch = SetTouchEmulationEnabledParams::new(viewport.has_touch);
+
+        if viewport.has_touch {
+            set_touch.max_touch_points = Some(5);
+        }
 
         let mut chains = Vec::with_capacity(2);
 
@@ -58,6 +62,8 @@ impl EmulationManager {
 
         self.needs_reload = self.emulating_mobile != viewport.emulating_mobile
             || self.has_touch != viewport.has_touch;
+        self.emulating_mobile = viewport.emulating_mobile;
+        self.has_touch = viewport.has_touch;
         chain
     }
 }
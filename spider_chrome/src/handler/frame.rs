@@ -196,6 +196,8 @@ pub struct FrameManager {
     pending_navigations: VecDeque<(FrameNavigationRequest, NavigationWatcher)>,
     /// The currently ongoing navigation
     navigation: Option<(NavigationWatcher, Instant)>,
+    /// The navigations failed by a crash or a detach of the target, reported on the next poll
+    aborted_navigations: VecDeque<NavigationError>,
 }
 
 impl FrameManager {
@@ -208,6 +210,7 @@ impl FrameManager {
             request_timeout,
             pending_navigations: Default::default(),
             navigation: None,
+            aborted_navigations: Default::default(),
         }
     }
 
@@ -303,7 +306,21 @@ impl FrameManager {
         }
     }
 
+    /// Fail the ongoing and the queued navigations, their lifecycle events never arrive once the target crashed or
+    /// detached.
+    pub fn abort_navigations(&mut self, err: fn(NavigationId) -> NavigationError) {
+        if let Some((watcher, _)) = self.navigation.take() {
+            self.aborted_navigations.push_back(err(watcher.id));
+        }
+        while let Some((req, _)) = self.pending_navigations.pop_front() {
+            self.aborted_navigations.push_back(err(req.id));
+        }
+    }
+
     pub fn poll(&mut self, now: Instant) -> Option<FrameEvent> {
+        if let Some(err) = self.aborted_navigations.pop_front() {
+            return Some(FrameEvent::NavigationResult(Err(err)));
+        }
         // check if the navigation completed
         if let Some((watcher, deadline)) = self.navigation.take() {
             if now > deadline {
@@ -598,6 +615,12 @@ pub enum NavigationError {
         id: NavigationId,
         frame: FrameId,
     },
+    TargetCrashed {
+        id: NavigationId,
+    },
+    TargetDetached {
+        id: NavigationId,
+    },
 }
 
 impl NavigationError {
@@ -605,6 +628,8 @@ impl NavigationError {
         match self {
             NavigationError::Timeout { id, .. } => id,
             NavigationError::FrameNotFound { id, .. } => id,
+            NavigationError::TargetCrashed { id } => id,
+            NavigationError::TargetDetached { id } => id,
         }
     }
 }
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use chromiumoxide_cdp::cdp::browser_protocol::network::ResourceType;
+
+/// The body of a cached response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CachedBody {
+    /// The body is kept in memory.
+    Memory(Arc<[u8]>),
+    /// The body of the length is kept in a file read when the request is fulfilled.
+    Disk(PathBuf, usize),
+}
+
+impl CachedBody {
+    /// The bytes of the body, the file is read off the runtime.
+    pub async fn read(&self) -> Option<Vec<u8>> {
+        match self {
+            CachedBody::Memory(body) => Some(body.to_vec()),
+            CachedBody::Disk(path, _) => tokio::fs::read(path).await.ok(),
+        }
+    }
+
+    /// The bytes of the body held in memory.
+    fn memory_len(&self) -> usize {
+        match self {
+            CachedBody::Memory(body) => body.len(),
+            CachedBody::Disk(..) => 0,
+        }
+    }
+
+    /// The bytes of the body written to disk.
+    fn disk_len(&self) -> usize {
+        match self {
+            CachedBody::Memory(_) => 0,
+            CachedBody::Disk(_, len) => *len,
+        }
+    }
+}
+
+/// A response cached to fulfill the requests of its url.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedResponse {
+    /// The status code of the response.
+    pub status: u16,
+    /// The headers of the response, without the `Set-Cookie` headers.
+    pub headers: Vec<(String, String)>,
+    /// The `ETag` or `Last-Modified` validator of the response.
+    pub validator: String,
+    /// The body of the response.
+    pub body: CachedBody,
+}
+
+/// The responses of the subresources shared by the pages of a crawl. The requests for a cached url are fulfilled from
+/// the cache instead of the network. A response is replaced when the same url is received with another validator.
+/// The cookies of a response are never replayed.
+#[derive(Debug, Clone, Default)]
+pub struct FulfillCache {
+    /// The responses by url.
+    responses: Arc<RwLock<HashMap<String, Arc<CachedResponse>>>>,
+    /// The bytes of the bodies held in memory.
+    memory_bytes: Arc<AtomicUsize>,
+    /// The bytes of the bodies written to disk.
+    disk_bytes: Arc<AtomicUsize>,
+}
+
+impl PartialEq for FulfillCache {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.responses, &other.responses)
+    }
+}
+
+impl FulfillCache {
+    /// An empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the requests of the resource type and method are fulfilled from the cache.
+    pub fn is_cacheable(resource_type: &ResourceType, method: &str) -> bool {
+        method.eq_ignore_ascii_case("GET")
+            && matches!(
+                resource_type,
+                ResourceType::Script
+                    | ResourceType::Stylesheet
+                    | ResourceType::Font
+                    | ResourceType::Image
+            )
+    }
+
+    /// The response cached for the url.
+    pub fn get(&self, url: &str) -> Option<Arc<CachedResponse>> {
+        self.responses.read().ok()?.get(url).cloned()
+    }
+
+    /// Whether the response of the url with the validator is cached.
+    pub fn contains(&self, url: &str, validator: &str) -> bool {
+        self.get(url)
+            .is_some_and(|response| response.validator == validator)
+    }
+
+    /// Cache the response of the url, replacing the previous response. The `Set-Cookie` headers are dropped.
+    pub fn insert(&self, url: String, mut response: CachedResponse) {
+        response
+            .headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case("set-cookie"));
+
+        let memory = response.body.memory_len();
+        let disk = response.body.disk_len();
+
+        if let Ok(mut responses) = self.responses.write() {
+            if let Some(previous) = responses.insert(url, Arc::new(response)) {
+                self.memory_bytes
+                    .fetch_sub(previous.body.memory_len(), Ordering::Relaxed);
+                self.disk_bytes
+                    .fetch_sub(previous.body.disk_len(), Ordering::Relaxed);
+            }
+            self.memory_bytes.fetch_add(memory, Ordering::Relaxed);
+            self.disk_bytes.fetch_add(disk, Ordering::Relaxed);
+        }
+    }
+
+    /// The responses cached.
+    pub fn len(&self) -> usize {
+        self.responses.read().map_or(0, |responses| responses.len())
+    }
+
+    /// Whether no response is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The bytes of the bodies held in memory.
+    pub fn memory_bytes(&self) -> usize {
+        self.memory_bytes.load(Ordering::Relaxed)
+    }
+
+    /// The bytes of the bodies written to disk.
+    pub fn disk_bytes(&self) -> usize {
+        self.disk_bytes.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fulfill_cache() {
+        let cache = FulfillCache::new();
+        let shared = cache.clone();
+        let response = |validator: &str, body: &[u8]| CachedResponse {
+            status: 200,
+            headers: vec![
+                ("Content-Type".into(), "text/javascript".into()),
+                ("Set-Cookie".into(), "session=1".into()),
+            ],
+            validator: validator.into(),
+            body: CachedBody::Memory(body.into()),
+        };
+
+        assert!(FulfillCache::is_cacheable(&ResourceType::Font, "GET"));
+        assert!(!FulfillCache::is_cacheable(&ResourceType::Script, "POST"));
+        assert!(!FulfillCache::is_cacheable(&ResourceType::Document, "GET"));
+
+        shared.insert(
+            "https://example.com/app.js".into(),
+            response("\"v1\"", b"let a;"),
+        );
+        assert!(cache.contains("https://example.com/app.js", "\"v1\""));
+        assert!(!cache.contains("https://example.com/app.js", "\"v2\""));
+        assert_eq!(cache.memory_bytes(), 6);
+        assert_eq!(
+            cache.get("https://example.com/app.js").unwrap().headers,
+            vec![("Content-Type".to_string(), "text/javascript".to_string())]
+        );
+
+        shared.insert(
+            "https://example.com/app.js".into(),
+            response("\"v2\"", b"let ab;"),
+        );
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.memory_bytes(), 7);
+        assert_eq!(
+            cache
+                .get("https://example.com/app.js")
+                .unwrap()
+                .body
+                .read()
+                .await,
+            Some(b"let ab;".to_vec())
+        );
+        assert_eq!(cache, shared);
+
+        let path = std::env::temp_dir().join("fulfill_cache_test.body");
+        std::fs::write(&path, b"body {}").unwrap();
+        shared.insert(
+            "https://example.com/app.css".into(),
+            CachedResponse {
+                body: CachedBody::Disk(path.clone(), 7),
+                ..response("\"v1\"", b"")
+            },
+        );
+        assert_eq!(cache.disk_bytes(), 7);
+        assert_eq!(
+            cache
+                .get("https://example.com/app.css")
+                .unwrap()
+                .body
+                .read()
+                .await,
+            Some(b"body {}".to_vec())
+        );
+        let _ = std::fs::remove_file(path);
+    }
+}
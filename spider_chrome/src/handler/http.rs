@@ -1,6 +1,5 @@
 use chromiumoxide_cdp::cdp::browser_protocol::network::{InterceptionId, RequestId, Response};
 use chromiumoxide_cdp::cdp::browser_protocol::page::FrameId;
-use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub struct HttpRequest {
@@ -9,7 +8,7 @@ pub struct HttpRequest {
     pub failure_text: Option<String>,
     pub interception_id: Option<InterceptionId>,
     pub response: Option<Response>,
-    pub headers: HashMap<String, String>,
+    pub headers: crate::headers::Headers,
     pub frame: Option<FrameId>,
     pub is_navigation_request: bool,
     pub allow_interception: bool,
@@ -19,6 +18,23 @@ pub struct HttpRequest {
     pub resource_type: Option<String>,
     pub post_data: Option<String>,
     pub redirect_chain: Vec<HttpRequest>,
+    /// The decision of the redirect policy for the redirect response of the request, `None` when it did not redirect.
+    pub redirect_allowed: Option<bool>,
+    /// The timestamps of the network events of the request.
+    pub timestamps: RequestTimestamps,
+}
+
+/// The timestamps of the network events of a request, in seconds of the monotonic clock of the browser. The timestamps
+/// share the clock of the `requestTime` of the CDP resource timing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestTimestamps {
+    /// The `Network.requestWillBeSent` timestamp.
+    pub request_will_be_sent: Option<f64>,
+    /// The `Network.responseReceived` timestamp.
+    pub response_received: Option<f64>,
+    /// The `Network.loadingFinished` or `Network.loadingFailed` timestamp.
+    pub loading_finished: Option<f64>,
 }
 
 impl HttpRequest {
@@ -45,6 +61,8 @@ impl HttpRequest {
             resource_type: None,
             post_data: None,
             redirect_chain,
+            redirect_allowed: None,
+            timestamps: Default::default(),
         }
     }
 
@@ -5,6 +5,7 @@ use std::time::{Duration, Instant};
 use fnv::FnvHashMap;
 use futures::channel::mpsc::Receiver;
 use futures::channel::oneshot::Sender as OneshotSender;
+use futures::future::{BoxFuture, FutureExt};
 use futures::stream::{Fuse, Stream, StreamExt};
 use futures::task::{Context, Poll};
 
@@ -24,10 +25,12 @@ use crate::handler::browser::BrowserContext;
 use crate::handler::frame::FrameNavigationRequest;
 use crate::handler::frame::{NavigationError, NavigationId, NavigationOk};
 use crate::handler::job::PeriodicJob;
+use crate::handler::resource_policy::ResourcePolicy;
 use crate::handler::session::Session;
 use crate::handler::target::TargetEvent;
 use crate::handler::target::{Target, TargetConfig};
 use crate::handler::viewport::Viewport;
+use crate::metrics;
 use crate::page::Page;
 
 /// Standard timeout in MS
@@ -38,15 +41,18 @@ pub mod commandfuture;
 pub mod domworld;
 pub mod emulation;
 pub mod frame;
+pub mod fulfill_cache;
 pub mod http;
 pub mod httpfuture;
 mod job;
 pub mod network;
 mod page;
+pub mod resource_policy;
 mod session;
 pub mod target;
 pub mod target_message_future;
 pub mod viewport;
+pub mod waterfall;
 
 /// The handler that monitors the state of the chromium browser and drives all
 /// the requests and events.
@@ -83,6 +89,19 @@ pub struct Handler {
     event_listeners: EventListeners,
     /// Keeps track is the browser is closing
     closing: bool,
+    /// The websocket url and auth token connected again when the connection drops.
+    reconnect_url: Option<(String, Option<String>)>,
+    /// The connection to the browser being established again.
+    reconnecting: Option<Reconnecting>,
+}
+
+/// The attempts to connect again to the browser after the websocket dropped.
+struct Reconnecting(BoxFuture<'static, Result<Connection<CdpEventMessage>>>);
+
+impl std::fmt::Debug for Reconnecting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Reconnecting").finish()
+    }
 }
 
 impl Handler {
@@ -121,9 +140,74 @@ impl Handler {
             config,
             event_listeners: Default::default(),
             closing: false,
+            reconnect_url: None,
+            reconnecting: None,
+        }
+    }
+
+    /// Connect again to the websocket url with the auth token when the connection drops, up to the reconnect attempts
+    /// of the config.
+    pub(crate) fn set_reconnect_url(&mut self, url: String, auth_token: Option<String>) {
+        if self.config.reconnect_attempts > 0 {
+            self.reconnect_url = Some((url, auth_token));
         }
     }
 
+    /// Start connecting again to the browser with a backoff. Returns `false` without a url to connect to.
+    fn start_reconnect(&mut self) -> bool {
+        let (url, auth_token) = match self.reconnect_url.clone() {
+            Some(reconnect_url) => reconnect_url,
+            _ => return false,
+        };
+        let attempts = self.config.reconnect_attempts;
+        let delay = self.config.reconnect_delay;
+
+        tracing::warn!("WS connection closed, connecting again to the browser");
+
+        self.reconnecting = Some(Reconnecting(Box::pin(async move {
+            let mut attempt = 0;
+
+            loop {
+                futures_timer::Delay::new(delay.saturating_mul(1 << attempt.min(16) as u32)).await;
+                attempt += 1;
+
+                match Connection::connect_with_auth(&url, auth_token.as_deref()).await {
+                    Ok(conn) => return Ok(conn),
+                    Err(err) if attempt >= attempts => return Err(err),
+                    Err(err) => {
+                        tracing::warn!("WS reconnection attempt {attempt} failed: {:?}", err)
+                    }
+                }
+            }
+        })));
+
+        true
+    }
+
+    /// Continue on the new connection. The sessions of the dropped connection are gone: the commands in flight fail
+    /// and the targets are attached again once the browser reports them.
+    fn on_reconnected(&mut self, mut conn: Connection<CdpEventMessage>) {
+        let pending = std::mem::take(&mut self.pending_commands);
+
+        for (_, (req, _, _)) in pending {
+            self.fail_pending_request(req, || CdpError::NoResponse);
+        }
+
+        self.navigations.clear();
+        self.sessions.clear();
+        self.targets.clear();
+        self.target_ids.clear();
+
+        let discover = SetDiscoverTargetsParams::new(true);
+        let discover_id = discover.identifier();
+
+        if let Ok(params) = serde_json::to_value(discover) {
+            let _ = conn.submit_command(discover_id, None, params);
+        }
+
+        self.conn = conn;
+    }
+
     /// Return the target with the matching `target_id`
     pub fn get_target(&self, target_id: &TargetId) -> Option<&Target> {
         self.targets.get(target_id)
@@ -204,7 +288,18 @@ impl Handler {
 
     /// Received a response to a request.
     fn on_response(&mut self, resp: Response) {
-        if let Some((req, method, _)) = self.pending_commands.remove(&resp.id) {
+        if let Some((req, method, started)) = self.pending_commands.remove(&resp.id) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                call_id = ?resp.id,
+                method = method.as_ref(),
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                error = resp.error.is_some(),
+                "command response"
+            );
+            if metrics::enabled() {
+                metrics::command_metrics().record(method.as_ref(), started.elapsed());
+            }
             match req {
                 PendingRequest::CreateTarget(tx) => {
                     match to_command_response::<CreateTargetParams>(resp, method) {
@@ -250,7 +345,7 @@ impl Handler {
                         self.config.created_first_target = true;
                     }
                 }
-                PendingRequest::ExternalCommand(tx) => {
+                PendingRequest::ExternalCommand(tx, _) => {
                     let _ = tx.send(Ok(resp)).ok();
                 }
                 PendingRequest::InternalCommand(target_id) => {
@@ -267,21 +362,37 @@ impl Handler {
     }
 
     /// Submit a command initiated via channel
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(method = msg.method.as_ref()))
+    )]
     pub(crate) fn submit_external_command(
         &mut self,
         msg: CommandMessage,
         now: Instant,
     ) -> Result<()> {
-        let call_id = self
-            .conn
-            .submit_command(msg.method.clone(), msg.session_id, msg.params)?;
+        let call_id =
+            self.conn
+                .submit_command(msg.method.clone(), msg.session_id.clone(), msg.params)?;
         self.pending_commands.insert(
             call_id,
-            (PendingRequest::ExternalCommand(msg.sender), msg.method, now),
+            (
+                PendingRequest::ExternalCommand(msg.sender, msg.session_id),
+                msg.method,
+                now,
+            ),
         );
         Ok(())
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip_all,
+            fields(target_id = target_id.as_ref(), method = req.method.as_ref())
+        )
+    )]
     pub(crate) fn submit_internal_command(
         &mut self,
         target_id: TargetId,
@@ -407,8 +518,12 @@ impl Handler {
             CdpEvent::TargetAttachedToTarget(ev) => self.on_attached_to_target(ev),
             CdpEvent::TargetTargetDestroyed(ev) => self.on_target_destroyed(ev),
             CdpEvent::TargetDetachedFromTarget(ev) => self.on_detached_from_target(ev),
+            CdpEvent::TargetTargetCrashed(ev) => self.on_target_crashed(ev),
             _ => {}
         }
+        if crate::chaos::drop_event(self.config.event_drop_rate) {
+            return;
+        }
         chromiumoxide_cdp::consume_event!(match params {
             |ev| self.event_listeners.start_send(ev),
             |json| { let _ = self.event_listeners.try_send_custom(&method, json);}
@@ -451,12 +566,20 @@ impl Handler {
                 ignore_visuals: self.config.ignore_visuals,
                 ignore_stylesheets: self.config.ignore_stylesheets,
                 ignore_javascript: self.config.ignore_javascript,
+                bypass_service_worker: self.config.bypass_service_worker,
+                block_service_workers: self.config.block_service_workers,
+                resource_policy: self.config.resource_policy.clone(),
                 extra_headers: self.config.extra_headers.clone(),
                 only_html: self.config.only_html && self.config.created_first_target,
+                event_drop_rate: self.config.event_drop_rate,
             },
             browser_ctx,
         );
 
+        if target.is_page() {
+            metrics::network_metrics().page_opened();
+        }
+
         self.target_ids.push(target.target_id().clone());
         self.targets.insert(target.target_id().clone(), target);
     }
@@ -473,18 +596,54 @@ impl Handler {
     /// The session was detached from target.
     /// Can be issued multiple times per target if multiple session have been
     /// attached to it.
+    ///
+    /// The requests in flight on the session never get a response, they fail
+    /// right away instead of waiting on the timeout.
     fn on_detached_from_target(&mut self, event: EventDetachedFromTarget) {
         // remove the session
         if let Some(session) = self.sessions.remove(&event.session_id) {
             if let Some(target) = self.targets.get_mut(session.target_id()) {
-                target.session_id().take();
+                if target.session_id() == Some(&event.session_id) {
+                    target.session_id_mut().take();
+                    target.on_detached();
+                }
+            }
+        }
+
+        let detached = self
+            .pending_commands
+            .iter()
+            .filter(|(_, (req, _, _))| match req {
+                PendingRequest::ExternalCommand(_, Some(session_id)) => {
+                    *session_id == event.session_id
+                }
+                _ => false,
+            })
+            .map(|(k, _)| *k)
+            .collect::<Vec<_>>();
+
+        for call in detached {
+            if let Some((PendingRequest::ExternalCommand(tx, _), _, _)) =
+                self.pending_commands.remove(&call)
+            {
+                let _ = tx.send(Err(CdpError::TargetDetached));
             }
         }
     }
 
+    /// The renderer of a target crashed.
+    fn on_target_crashed(&mut self, event: EventTargetCrashed) {
+        if let Some(target) = self.targets.get_mut(&event.target_id) {
+            target.on_crashed();
+        }
+    }
+
     /// Fired when the target was destroyed in the browser
     fn on_target_destroyed(&mut self, event: EventTargetDestroyed) {
         if let Some(target) = self.targets.remove(&event.target_id) {
+            if target.is_page() {
+                metrics::network_metrics().page_closed();
+            }
             // TODO shutdown?
             if let Some(session) = target.session_id() {
                 self.sessions.remove(session);
@@ -505,32 +664,40 @@ impl Handler {
             .collect::<Vec<_>>();
 
         for call in timed_out {
-            if let Some((req, _, _)) = self.pending_commands.remove(&call) {
-                match req {
-                    PendingRequest::CreateTarget(tx) => {
-                        let _ = tx.send(Err(CdpError::Timeout));
-                    }
-                    PendingRequest::GetTargets(tx) => {
-                        let _ = tx.send(Err(CdpError::Timeout));
-                    }
-                    PendingRequest::Navigate(nav) => {
-                        if let Some(nav) = self.navigations.remove(&nav) {
-                            match nav {
-                                NavigationRequest::Navigate(nav) => {
-                                    let _ = nav.tx.send(Err(CdpError::Timeout));
-                                }
-                            }
+            if let Some((req, method, _)) = self.pending_commands.remove(&call) {
+                if metrics::enabled() {
+                    metrics::command_metrics().record_timeout(method.as_ref());
+                }
+                self.fail_pending_request(req, || CdpError::Timeout);
+            }
+        }
+    }
+
+    /// Answer the request waiting for a response with the error.
+    fn fail_pending_request(&mut self, req: PendingRequest, err: impl Fn() -> CdpError) {
+        match req {
+            PendingRequest::CreateTarget(tx) => {
+                let _ = tx.send(Err(err()));
+            }
+            PendingRequest::GetTargets(tx) => {
+                let _ = tx.send(Err(err()));
+            }
+            PendingRequest::Navigate(nav) => {
+                if let Some(nav) = self.navigations.remove(&nav) {
+                    match nav {
+                        NavigationRequest::Navigate(nav) => {
+                            let _ = nav.tx.send(Err(err()));
                         }
                     }
-                    PendingRequest::ExternalCommand(tx) => {
-                        let _ = tx.send(Err(CdpError::Timeout));
-                    }
-                    PendingRequest::InternalCommand(_) => {}
-                    PendingRequest::CloseBrowser(tx) => {
-                        let _ = tx.send(Err(CdpError::Timeout));
-                    }
                 }
             }
+            PendingRequest::ExternalCommand(tx, _) => {
+                let _ = tx.send(Err(err()));
+            }
+            PendingRequest::InternalCommand(_) => {}
+            PendingRequest::CloseBrowser(tx) => {
+                let _ = tx.send(Err(err()));
+            }
         }
     }
 
@@ -539,6 +706,15 @@ impl Handler {
     }
 }
 
+impl Drop for Handler {
+    /// The pages of a dropped handler are no longer open.
+    fn drop(&mut self) {
+        for _ in self.targets.values().filter(|target| target.is_page()) {
+            metrics::network_metrics().page_closed();
+        }
+    }
+}
+
 impl Stream for Handler {
     type Item = Result<()>;
 
@@ -546,6 +722,22 @@ impl Stream for Handler {
         let pin = self.get_mut();
 
         loop {
+            if let Some(reconnecting) = pin.reconnecting.as_mut() {
+                match reconnecting.0.poll_unpin(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Ok(conn)) => {
+                        pin.reconnecting = None;
+                        pin.on_reconnected(conn);
+                    }
+                    Poll::Ready(Err(err)) => {
+                        tracing::error!("WS reconnection failed: {:?}", err);
+                        pin.reconnecting = None;
+                        pin.reconnect_url = None;
+                        return Poll::Ready(None);
+                    }
+                }
+            }
+
             let now = Instant::now();
             // temporary pinning of the browser receiver should be safe as we are pinning
             // through the already pinned self. with the receivers we can also
@@ -634,22 +826,30 @@ impl Stream for Handler {
 
             let mut done = true;
 
-            while let Poll::Ready(Some(ev)) = Pin::new(&mut pin.conn).poll_next(cx) {
+            while let Poll::Ready(ev) = Pin::new(&mut pin.conn).poll_next(cx) {
                 match ev {
-                    Ok(Message::Response(resp)) => {
+                    Some(Ok(Message::Response(resp))) => {
                         pin.on_response(resp);
                         if pin.closing {
                             // handler should stop processing
                             return Poll::Ready(None);
                         }
                     }
-                    Ok(Message::Event(ev)) => {
+                    Some(Ok(Message::Event(ev))) => {
                         pin.on_event(ev);
                     }
-                    Err(err) => {
+                    Some(Err(err)) => {
                         tracing::error!("WS Connection error: {:?}", err);
                         return Poll::Ready(Some(Err(err)));
                     }
+                    None => {
+                        // the connection dropped, poll the reconnection on the next turn.
+                        if pin.closing || !pin.start_reconnect() {
+                            return Poll::Ready(None);
+                        }
+                        done = false;
+                        break;
+                    }
                 }
                 done = false;
             }
@@ -690,12 +890,24 @@ pub struct HandlerConfig {
     pub ignore_javascript: bool,
     /// Whether to ignore ads.
     pub ignore_ads: bool,
+    /// Whether to load pages from the network instead of the service workers.
+    pub bypass_service_worker: bool,
+    /// Whether to block the service worker scripts.
+    pub block_service_workers: bool,
+    /// The actions by resource type, taking precedence over the ignore flags.
+    pub resource_policy: ResourcePolicy,
     /// Extra headers.
-    pub extra_headers: Option<HashMap<String, String>>,
+    pub extra_headers: Option<crate::headers::Headers>,
     /// Only Html.
     pub only_html: bool,
     /// Created the first target.
     pub created_first_target: bool,
+    /// The attempts to connect again to a remote browser when the connection drops. Defaults to none.
+    pub reconnect_attempts: usize,
+    /// The delay before the first attempt to connect again, doubled on each attempt.
+    pub reconnect_delay: Duration,
+    /// The share of the CDP events dropped before they reach the event listeners between 0.0 and 1.0.
+    pub event_drop_rate: f64,
 }
 
 impl Default for HandlerConfig {
@@ -711,9 +923,15 @@ impl Default for HandlerConfig {
             ignore_stylesheets: false,
             ignore_ads: false,
             ignore_javascript: false,
+            bypass_service_worker: false,
+            block_service_workers: false,
+            resource_policy: Default::default(),
             only_html: false,
             extra_headers: Default::default(),
             created_first_target: false,
+            reconnect_attempts: 0,
+            reconnect_delay: Duration::from_secs(1),
+            event_drop_rate: 0.0,
         }
     }
 }
@@ -773,8 +991,9 @@ enum PendingRequest {
     /// after the `Target` notifies the `Handler` that the `Page` has finished
     /// loading, which comes after the response.
     Navigate(NavigationId),
-    /// A common request received via a channel (`Page`).
-    ExternalCommand(OneshotSender<Result<Response>>),
+    /// A common request received via a channel (`Page`) with the session of
+    /// the target it was sent to.
+    ExternalCommand(OneshotSender<Result<Response>>, Option<SessionId>),
     /// Requests that are initiated directly from a `Target` (all the
     /// initialization commands).
     InternalCommand(TargetId),
@@ -797,3 +1016,50 @@ pub(crate) enum HandlerMessage {
     AddEventListener(EventListenerRequest),
     CloseBrowser(OneshotSender<Result<CloseReturns>>),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reconnect() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (_tx, rx) = futures::channel::mpsc::channel(1);
+        let config = HandlerConfig {
+            reconnect_attempts: 2,
+            reconnect_delay: Duration::from_millis(10),
+            ..Default::default()
+        };
+
+        let server = tokio::spawn(async move {
+            let mut connections = 0;
+
+            while connections < 2 {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = async_tungstenite::tokio::accept_async(stream)
+                    .await
+                    .unwrap();
+                connections += 1;
+                // the first connection drops right away.
+                if connections == 1 {
+                    let _ = ws.close(None).await;
+                } else {
+                    // the discovery of the targets is sent again on the new connection.
+                    assert!(ws.next().await.is_some());
+                }
+            }
+        });
+
+        let mut handler = Handler::new(Connection::connect(&url).await.unwrap(), rx, config);
+        handler.set_reconnect_url(url, None);
+
+        let driver = tokio::spawn(async move { while handler.next().await.is_some() {} });
+
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .unwrap()
+            .unwrap();
+        driver.abort();
+    }
+}
@@ -1,22 +1,34 @@
 use chromiumoxide_cdp::cdp::browser_protocol::fetch::{
     self, AuthChallengeResponse, AuthChallengeResponseResponse, ContinueRequestParams,
-    ContinueWithAuthParams, DisableParams, EventAuthRequired, EventRequestPaused, RequestPattern,
+    ContinueResponseParams, ContinueWithAuthParams, DisableParams, EventAuthRequired,
+    EventRequestPaused, FailRequestParams, FulfillRequestParams, HeaderEntry, RequestPattern,
+    RequestStage,
 };
-use chromiumoxide_cdp::cdp::browser_protocol::network::ResourceType;
 use chromiumoxide_cdp::cdp::browser_protocol::network::{
-    EmulateNetworkConditionsParams, EventLoadingFailed, EventLoadingFinished,
-    EventRequestServedFromCache, EventRequestWillBeSent, EventResponseReceived, Headers,
-    InterceptionId, RequestId, Response, SetCacheDisabledParams, SetExtraHttpHeadersParams,
+    EmulateNetworkConditionsParams, EventDataReceived, EventLoadingFailed, EventLoadingFinished,
+    EventRequestServedFromCache, EventRequestWillBeSent, EventResponseReceived, InterceptionId,
+    RequestId, Response, SetBypassServiceWorkerParams, SetCacheDisabledParams,
+    SetExtraHttpHeadersParams,
 };
+use chromiumoxide_cdp::cdp::browser_protocol::network::{ErrorReason, ResourceType};
 use chromiumoxide_cdp::cdp::browser_protocol::{
-    network::EnableParams, security::SetIgnoreCertificateErrorsParams,
+    network::EnableParams, page::StopLoadingParams, security::SetIgnoreCertificateErrorsParams,
 };
-use chromiumoxide_types::{Command, Method, MethodId};
+use chromiumoxide_cdp::cdp::CustomEvent;
+use chromiumoxide_types::{Command, Method, MethodId, MethodType};
 
 use crate::auth::Credentials;
 use crate::cmd::CommandChain;
+use crate::handler::fulfill_cache::{CachedBody, CachedResponse, FulfillCache};
 use crate::handler::http::HttpRequest;
+use crate::handler::resource_policy::{ResourceAction, ResourcePolicy};
+use crate::handler::waterfall::{WaterfallEntry, MAX_WATERFALL_ENTRIES};
+use crate::metrics::{self, BlockReason};
+use crate::utils::wildcard_match;
+use futures::stream::{FuturesUnordered, StreamExt};
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::Duration;
 
 lazy_static::lazy_static! {
@@ -79,6 +91,208 @@ lazy_static::lazy_static! {
     };
 }
 
+#[cfg(feature = "adblock")]
+lazy_static::lazy_static! {
+    /// The network and cosmetic filters blocking the ads.
+    static ref AD_ENGINE: adblock::Engine = {
+        let mut filter_set = adblock::lists::FilterSet::new(false);
+        filter_set.add_filters(
+            &vec![
+                String::from("-advertisement."),
+                String::from("-ads."),
+                String::from("-ad."),
+                String::from("-advertisement-icon."),
+                String::from("-advertisement-management/"),
+                String::from("-advertisement/script."),
+                String::from("-ads/script."),
+                // the placeholders of the ad slots left in the page once the ads are blocked.
+                String::from("##ins.adsbygoogle"),
+                String::from("##amp-ad"),
+                String::from("##amp-embed[type=\"ad\"]"),
+                String::from("##div[id^=\"div-gpt-ad\"]"),
+                String::from("##iframe[id^=\"google_ads_iframe\"]"),
+                String::from("##div[data-google-query-id]"),
+                String::from("##[data-ad-slot]"),
+                String::from("##[data-ad-unit]"),
+                String::from("##div[class^=\"ad-placeholder\"]"),
+                String::from("##div[id^=\"taboola-\"]"),
+                String::from("##div[class^=\"OUTBRAIN\"]"),
+                String::from("##a[href^=\"https://ad.doubleclick.net/\"]"),
+            ],
+            adblock::lists::ParseOptions::default(),
+        );
+        adblock::Engine::from_filter_set(filter_set, true)
+    };
+}
+
+/// The css hiding the ad placeholders of the page from the cosmetic filters for the hostname of the url.
+#[cfg(feature = "adblock")]
+pub fn cosmetic_filter_css(url: &str) -> Option<String> {
+    let resources = AD_ENGINE.url_cosmetic_resources(url);
+    let mut css = String::new();
+
+    let hidden: Vec<&str> = resources
+        .hide_selectors
+        .iter()
+        .filter(|selector| !resources.exceptions.contains(*selector))
+        .map(|selector| selector.as_str())
+        .collect();
+
+    if !hidden.is_empty() {
+        css.push_str(&hidden.join(","));
+        css.push_str("{display:none !important}");
+    }
+
+    for (selector, styles) in resources.style_selectors.iter() {
+        if !resources.exceptions.contains(selector) {
+            css.push_str(&format!("{selector}{{{}}}", styles.join(";")));
+        }
+    }
+
+    if css.is_empty() {
+        None
+    } else {
+        Some(css)
+    }
+}
+
+/// The script adding the cosmetic filter css of the url to the documents of the page.
+#[cfg(feature = "adblock")]
+pub fn cosmetic_filter_script(url: &str) -> Option<String> {
+    let css = serde_json::to_string(&cosmetic_filter_css(url)?).ok()?;
+
+    Some(format!(
+        r#"(() => {{ const add = () => {{ const style = document.createElement("style"); style.textContent = {css}; (document.head || document.documentElement).appendChild(style) }}; if (document.documentElement) {{ add() }} else {{ document.addEventListener("readystatechange", add, {{ once: true }}) }} }})()"#
+    ))
+}
+
+/// Block the requests of third parties. The policy receives the url of the request and returns `true` to block it.
+/// The crawler decides the first party of the page, ex: the registrable domain of the public suffix list.
+#[derive(Clone)]
+pub struct ThirdPartyPolicy(pub std::sync::Arc<dyn Fn(&str) -> bool + Send + Sync>);
+
+impl ThirdPartyPolicy {
+    /// A new policy blocking the requests the function returns `true` for.
+    pub fn new(is_third_party: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        Self(std::sync::Arc::new(is_third_party))
+    }
+
+    /// Is the request url from a blocked third party.
+    pub fn is_blocked(&self, url: &str) -> bool {
+        (self.0)(url)
+    }
+}
+
+impl std::fmt::Debug for ThirdPartyPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ThirdPartyPolicy").finish()
+    }
+}
+
+/// Decide the redirects followed by the document requests of the page. The policy receives the url of the first
+/// request, the url of the redirect and the count of redirects including this one, and returns `true` to follow it.
+#[derive(Clone)]
+pub struct RedirectPolicy(pub std::sync::Arc<dyn Fn(&str, &str, usize) -> bool + Send + Sync>);
+
+impl RedirectPolicy {
+    /// A new policy following the redirects the function returns `true` for.
+    pub fn new(allows: impl Fn(&str, &str, usize) -> bool + Send + Sync + 'static) -> Self {
+        Self(std::sync::Arc::new(allows))
+    }
+
+    /// Whether the redirect to the target url is followed.
+    pub fn allows(&self, origin: &str, target: &str, hops: usize) -> bool {
+        (self.0)(origin, target, hops)
+    }
+}
+
+impl std::fmt::Debug for RedirectPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("RedirectPolicy").finish()
+    }
+}
+
+/// The redirects of a document request followed so far.
+#[derive(Debug, Clone)]
+struct RedirectHops {
+    /// The url of the first request.
+    origin: String,
+    /// The url of the last hop.
+    url: String,
+    /// The count of redirects.
+    hops: usize,
+    /// The decision of the policy for the last hop.
+    allowed: bool,
+}
+
+/// A canned response for the requests matching the url pattern.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockRoute {
+    /// The url pattern. `*` matches any characters.
+    pub pattern: String,
+    /// The status code of the response.
+    pub status: u16,
+    /// The headers of the response.
+    pub headers: Vec<(String, String)>,
+    /// The body of the response.
+    pub body: Vec<u8>,
+}
+
+/// Answer the requests matching the routes with canned responses. The first route matching the url is used.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockRouter {
+    /// The routes in order.
+    pub routes: Vec<MockRoute>,
+    /// The routes answering a single request keyed by the url, or by the method and url ex: `POST https://example.com/api`, looked up after the patterns.
+    pub exact: HashMap<String, MockRoute>,
+    /// Fail the requests without a route instead of letting them reach the network.
+    pub offline: bool,
+}
+
+impl MockRouter {
+    /// The route for the request. The exact routes of the method and url are preferred to the routes of the url.
+    pub fn find(&self, method: &str, url: &str) -> Option<&MockRoute> {
+        self.routes
+            .iter()
+            .find(|route| wildcard_match(&route.pattern, url))
+            .or_else(|| self.exact.get(&format!("{method} {url}")))
+            .or_else(|| self.exact.get(url))
+    }
+}
+
+/// Decide if a paused request should fail. The filter receives the url and resource type of the request and returns `true` to block it.
+#[derive(Clone)]
+pub struct RequestFilter(pub std::sync::Arc<dyn Fn(&str, &ResourceType) -> bool + Send + Sync>);
+
+impl RequestFilter {
+    /// A new filter blocking the requests the function returns `true` for.
+    pub fn new(filter: impl Fn(&str, &ResourceType) -> bool + Send + Sync + 'static) -> Self {
+        Self(std::sync::Arc::new(filter))
+    }
+}
+
+/// The paused request, the cached response and the body read from disk.
+type FulfillRead = (fetch::RequestId, Arc<CachedResponse>, Option<Vec<u8>>);
+
+impl std::fmt::Debug for RequestFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("RequestFilter").finish()
+    }
+}
+
+/// The body bytes received for a response of a resource type with a size cap.
+#[derive(Debug)]
+struct ResponseBudget {
+    /// The url of the response.
+    url: String,
+    /// The resource type of the response.
+    resource_type: ResourceType,
+    /// The bytes allowed for the body.
+    max_bytes: u64,
+    /// The bytes of the body received.
+    received: u64,
+}
+
 #[derive(Debug)]
 pub struct NetworkManager {
     queued_events: VecDeque<NetworkEvent>,
@@ -86,7 +300,7 @@ pub struct NetworkManager {
     requests: HashMap<RequestId, HttpRequest>,
     // TODO put event in an Arc?
     requests_will_be_sent: HashMap<RequestId, EventRequestWillBeSent>,
-    extra_headers: HashMap<String, String>,
+    extra_headers: crate::headers::Headers,
     request_id_to_interception_id: HashMap<RequestId, InterceptionId>,
     user_cache_disabled: bool,
     attempted_authentications: HashSet<RequestId>,
@@ -103,6 +317,36 @@ pub struct NetworkManager {
     pub block_javascript: bool,
     /// Only html from loading.
     pub only_html: bool,
+    /// Block the service worker scripts from loading.
+    pub block_service_workers: bool,
+    /// The actions by resource type, taking precedence over the shorthands.
+    resource_policy: ResourcePolicy,
+    /// Block the third party requests of the page.
+    third_party_policy: Option<ThirdPartyPolicy>,
+    /// The third party requests blocked.
+    blocked_third_party: Vec<String>,
+    /// Remove the `Set-Cookie` headers of the responses so the pages can not store cookies.
+    block_response_cookies: bool,
+    /// Block the requests matching the filter.
+    request_filter: Option<RequestFilter>,
+    /// Answer the requests matching the routes.
+    mock_router: Option<MockRouter>,
+    /// Answer the repeated subresource requests with the responses shared by the pages.
+    fulfill_cache: Option<FulfillCache>,
+    /// The cached bodies read from disk for the paused requests.
+    fulfill_reads: FuturesUnordered<tokio::task::JoinHandle<FulfillRead>>,
+    /// The bytes allowed for the response bodies by resource type.
+    max_response_bytes: HashMap<ResourceType, u64>,
+    /// The body bytes received for the responses with a size cap by request id.
+    response_budgets: HashMap<RequestId, ResponseBudget>,
+    /// The redirects followed by the document requests, every redirect is followed without a policy.
+    redirect_policy: Option<RedirectPolicy>,
+    /// The redirects of the document requests in flight by request id.
+    redirects: HashMap<String, RedirectHops>,
+    /// The timing waterfall of the requests of the page.
+    waterfall: Vec<WaterfallEntry>,
+    /// The waterfall entries of the responses still loading their body by request id.
+    waterfall_loading: HashMap<RequestId, usize>,
 }
 
 impl NetworkManager {
@@ -125,6 +369,21 @@ impl NetworkManager {
             block_javascript: false,
             block_stylesheets: false,
             only_html: false,
+            block_service_workers: false,
+            resource_policy: Default::default(),
+            third_party_policy: None,
+            blocked_third_party: Default::default(),
+            block_response_cookies: false,
+            request_filter: None,
+            mock_router: None,
+            fulfill_cache: None,
+            fulfill_reads: Default::default(),
+            max_response_bytes: Default::default(),
+            response_budgets: Default::default(),
+            redirect_policy: None,
+            redirects: Default::default(),
+            waterfall: Default::default(),
+            waterfall_loading: Default::default(),
         }
     }
 
@@ -164,15 +423,22 @@ impl NetworkManager {
         self.queued_events.pop_front()
     }
 
-    pub fn extra_headers(&self) -> &HashMap<String, String> {
+    pub fn extra_headers(&self) -> &crate::headers::Headers {
         &self.extra_headers
     }
 
-    pub fn set_extra_headers(&mut self, headers: HashMap<String, String>) {
+    pub fn set_extra_headers(&mut self, headers: crate::headers::Headers) {
         self.extra_headers = headers;
         self.extra_headers.remove("proxy-authorization");
-        if let Ok(headers) = serde_json::to_value(&self.extra_headers) {
-            self.push_cdp_request(SetExtraHttpHeadersParams::new(Headers::new(headers)));
+        self.push_cdp_request(SetExtraHttpHeadersParams::new(self.extra_headers.to_cdp()));
+    }
+
+    /// Replace the `User-Agent` in the extra headers so it does not override the agent set for the target.
+    pub fn set_extra_headers_user_agent(&mut self, user_agent: &str) {
+        if self.extra_headers.contains_key("user-agent") {
+            let mut headers = std::mem::take(&mut self.extra_headers);
+            headers.insert("user-agent", user_agent);
+            self.set_extra_headers(headers);
         }
     }
 
@@ -186,6 +452,13 @@ impl NetworkManager {
         self.update_protocol_cache_disabled();
     }
 
+    /// Load the requests from the network instead of the service workers of the page.
+    pub fn set_bypass_service_worker(&mut self, bypass: bool) {
+        if bypass {
+            self.push_cdp_request(SetBypassServiceWorkerParams::new(true));
+        }
+    }
+
     pub fn update_protocol_cache_disabled(&mut self) {
         self.push_cdp_request(SetCacheDisabledParams::new(
             self.user_cache_disabled || self.protocol_request_interception_enabled,
@@ -197,8 +470,441 @@ impl NetworkManager {
         self.update_protocol_request_interception()
     }
 
+    /// Block the third party requests of the page.
+    pub fn set_third_party_policy(&mut self, policy: Option<ThirdPartyPolicy>) {
+        self.third_party_policy = policy;
+        self.update_protocol_request_interception()
+    }
+
+    /// Fail the service worker scripts of the page. The requests are intercepted to find the scripts.
+    pub fn set_block_service_workers(&mut self, block_service_workers: bool) {
+        self.block_service_workers = block_service_workers;
+        self.update_protocol_request_interception()
+    }
+
+    /// Remove the `Set-Cookie` headers of the responses of the page. The responses pause once their headers are
+    /// received.
+    pub fn set_block_response_cookies(&mut self, block_response_cookies: bool) {
+        self.block_response_cookies = block_response_cookies;
+        self.update_protocol_request_interception()
+    }
+
+    /// Block the requests the filter returns `true` for.
+    pub fn set_request_filter(&mut self, filter: Option<RequestFilter>) {
+        self.request_filter = filter;
+        self.update_protocol_request_interception()
+    }
+
+    /// Answer the requests matching the routes with canned responses.
+    pub fn set_mock_router(&mut self, router: Option<MockRouter>) {
+        self.mock_router = router;
+        self.update_protocol_request_interception()
+    }
+
+    /// Answer the repeated subresource requests with the responses of the cache shared by the pages.
+    pub fn set_fulfill_cache(&mut self, cache: Option<FulfillCache>) {
+        self.fulfill_cache = cache;
+        self.update_protocol_request_interception()
+    }
+
+    /// Set the bytes allowed for the response bodies by resource type. The responses announcing a larger
+    /// `Content-Length` are failed once their headers are received and the page stops loading when a streamed body
+    /// goes over the cap.
+    pub fn set_max_response_bytes(&mut self, max_response_bytes: HashMap<ResourceType, u64>) {
+        self.max_response_bytes = max_response_bytes;
+        self.update_protocol_request_interception()
+    }
+
+    /// Set the actions by resource type of the intercepted requests. The actions take precedence over the
+    /// `ignore_visuals`, `block_stylesheets` and `block_javascript` shorthands.
+    pub fn set_resource_policy(&mut self, policy: ResourcePolicy) {
+        self.resource_policy = policy;
+    }
+
+    /// Set the redirects followed by the document requests. Blocked redirects fail the request.
+    pub fn set_redirect_policy(&mut self, policy: Option<RedirectPolicy>) {
+        self.redirect_policy = policy;
+        self.update_protocol_request_interception()
+    }
+
+    /// Track the hop of the document request and return whether the policy follows it. The request will be sent and
+    /// request paused events both track the hop, the second one gets the decision of the first.
+    fn track_redirect(&mut self, request_id: &str, url: &str) -> bool {
+        let policy = match self.redirect_policy.as_ref() {
+            Some(policy) => policy,
+            _ => return true,
+        };
+
+        match self.redirects.get_mut(request_id) {
+            Some(redirect) if redirect.url == url => redirect.allowed,
+            Some(redirect) => {
+                redirect.hops += 1;
+                redirect.url = url.to_string();
+                redirect.allowed = policy.allows(&redirect.origin, url, redirect.hops);
+                redirect.allowed
+            }
+            _ => {
+                self.redirects.insert(
+                    request_id.to_string(),
+                    RedirectHops {
+                        origin: url.to_string(),
+                        url: url.to_string(),
+                        hops: 0,
+                        allowed: true,
+                    },
+                );
+                true
+            }
+        }
+    }
+
+    /// Fail the document request if the redirect policy does not follow it.
+    fn block_redirect(&mut self, event: &EventRequestPaused) -> bool {
+        if event.resource_type != ResourceType::Document {
+            return false;
+        }
+
+        let blocked = match event.network_id.as_ref() {
+            Some(network_id) => !self.track_redirect(network_id.as_ref(), &event.request.url),
+            _ => false,
+        };
+
+        if blocked {
+            self.push_cdp_request(FailRequestParams::new(
+                event.request_id.clone(),
+                ErrorReason::BlockedByClient,
+            ));
+            self.record_blocked(event, BlockReason::Redirect);
+        }
+
+        blocked
+    }
+
+    /// Fulfill the request with the mocked response if a route matches. Requests without a route fail when the router is offline.
+    fn fulfill_mocked(&mut self, event: &EventRequestPaused) -> bool {
+        use base64::Engine;
+
+        let router = match self.mock_router.as_ref() {
+            Some(router) => router,
+            _ => return false,
+        };
+
+        let params = match router.find(&event.request.method, &event.request.url) {
+            Some(route) => {
+                let mut params =
+                    FulfillRequestParams::new(event.request_id.clone(), route.status as i64);
+                params.response_headers = Some(
+                    route
+                        .headers
+                        .iter()
+                        .map(|(name, value)| HeaderEntry::new(name, value))
+                        .collect(),
+                );
+                params.body = Some(
+                    base64::engine::general_purpose::STANDARD
+                        .encode(&route.body)
+                        .into(),
+                );
+                params
+            }
+            _ if router.offline => {
+                self.push_cdp_request(FailRequestParams::new(
+                    event.request_id.clone(),
+                    ErrorReason::InternetDisconnected,
+                ));
+                return true;
+            }
+            _ => return false,
+        };
+
+        self.push_cdp_request(params);
+
+        true
+    }
+
+    /// Fulfill the subresource request with the response of the shared cache. The bodies on disk are read off the
+    /// handler and the request is answered once the read completes.
+    fn fulfill_cached(&mut self, event: &EventRequestPaused) -> bool {
+        if !FulfillCache::is_cacheable(&event.resource_type, &event.request.method) {
+            return false;
+        }
+
+        let response = match self
+            .fulfill_cache
+            .as_ref()
+            .and_then(|cache| cache.get(&event.request.url))
+        {
+            Some(response) => response,
+            _ => return false,
+        };
+
+        match response.body {
+            CachedBody::Memory(ref body) => {
+                let body = body.clone();
+                self.push_fulfill_cached(event.request_id.clone(), &response, &body);
+            }
+            CachedBody::Disk(..) => {
+                let request_id = event.request_id.clone();
+
+                self.fulfill_reads.push(tokio::spawn(async move {
+                    let body = response.body.read().await;
+                    (request_id, response, body)
+                }));
+            }
+        }
+
+        true
+    }
+
+    /// Answer the paused request with the cached response and body.
+    fn push_fulfill_cached(
+        &mut self,
+        request_id: fetch::RequestId,
+        response: &CachedResponse,
+        body: &[u8],
+    ) {
+        use base64::Engine;
+
+        let mut params = FulfillRequestParams::new(request_id, response.status as i64);
+        params.response_headers = Some(
+            response
+                .headers
+                .iter()
+                .map(|(name, value)| HeaderEntry::new(name, value))
+                .collect(),
+        );
+        params.body = Some(
+            base64::engine::general_purpose::STANDARD
+                .encode(body)
+                .into(),
+        );
+
+        self.push_cdp_request(params);
+    }
+
+    /// Answer the requests whose cached body was read from disk, the requests whose file is gone go to the network.
+    pub fn poll_fulfill_reads(&mut self, cx: &mut Context<'_>) {
+        while let Poll::Ready(Some(read)) = self.fulfill_reads.poll_next_unpin(cx) {
+            match read {
+                Ok((request_id, response, Some(body))) => {
+                    self.push_fulfill_cached(request_id, &response, &body)
+                }
+                Ok((request_id, _, None)) => {
+                    self.push_cdp_request(ContinueRequestParams::new(request_id))
+                }
+                Err(_) => (),
+            }
+        }
+    }
+
+    /// Fail the request if the filter blocks it.
+    fn block_filtered(&mut self, event: &EventRequestPaused) -> bool {
+        let blocked = match self.request_filter {
+            Some(ref filter) => (filter.0)(&event.request.url, &event.resource_type),
+            _ => false,
+        };
+
+        if blocked {
+            self.push_cdp_request(FailRequestParams::new(
+                event.request_id.clone(),
+                ErrorReason::BlockedByClient,
+            ));
+            self.record_blocked(event, BlockReason::Filter);
+        }
+
+        blocked
+    }
+
+    /// The third party requests blocked by the policy.
+    pub fn blocked_third_party(&self) -> &[String] {
+        &self.blocked_third_party
+    }
+
+    /// The timing waterfall of the requests of the page in the order of their responses.
+    pub fn waterfall(&self) -> &[WaterfallEntry] {
+        &self.waterfall
+    }
+
+    /// Add the request to the waterfall, returning the index of its entry.
+    fn record_waterfall(&mut self, request: &HttpRequest) -> Option<usize> {
+        if self.waterfall.len() < MAX_WATERFALL_ENTRIES {
+            self.waterfall.push(WaterfallEntry::new(request));
+            Some(self.waterfall.len() - 1)
+        } else {
+            None
+        }
+    }
+
+    /// Count the request blocked for the reason, trace it with the feature flag `tracing` and queue the
+    /// [`EventRequestBlocked`] for the listeners of the page.
+    fn record_blocked(&mut self, event: &EventRequestPaused, reason: BlockReason) {
+        self.record_blocked_request(
+            event.request_id.as_ref(),
+            &event.request.url,
+            &event.resource_type,
+            reason,
+        )
+    }
+
+    /// Count the request blocked for the reason like [`NetworkManager::record_blocked`] from its parts.
+    fn record_blocked_request(
+        &mut self,
+        request_id: &str,
+        url: &str,
+        resource_type: &ResourceType,
+        reason: BlockReason,
+    ) {
+        metrics::network_metrics().record_blocked(reason);
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            request_id,
+            url,
+            resource_type = ?resource_type,
+            block_reason = reason.as_str(),
+            "request blocked"
+        );
+
+        self.queued_events
+            .push_back(NetworkEvent::RequestBlocked(EventRequestBlocked {
+                request_id: request_id.to_string(),
+                url: url.to_string(),
+                resource_type: resource_type.as_ref().to_string(),
+                reason: reason.as_str().to_string(),
+            }));
+    }
+
+    /// Fail the paused response when its `Content-Length` is over the cap of its resource type, continue it otherwise.
+    fn on_fetch_response_paused(&mut self, event: &EventRequestPaused) {
+        let oversized = match self.max_response_bytes.get(&event.resource_type) {
+            Some(max_bytes) => event
+                .response_headers
+                .iter()
+                .flatten()
+                .find(|header| header.name.eq_ignore_ascii_case("content-length"))
+                .and_then(|header| header.value.trim().parse::<u64>().ok())
+                .is_some_and(|length| length > *max_bytes),
+            _ => false,
+        };
+
+        if oversized {
+            self.push_cdp_request(FailRequestParams::new(
+                event.request_id.clone(),
+                ErrorReason::Aborted,
+            ));
+            self.record_blocked(event, BlockReason::Oversized);
+        } else if self.block_response_cookies {
+            self.continue_without_cookies(event)
+        } else {
+            self.push_cdp_request(ContinueRequestParams::new(event.request_id.clone()))
+        }
+    }
+
+    /// Continue the paused response without its `Set-Cookie` headers.
+    fn continue_without_cookies(&mut self, event: &EventRequestPaused) {
+        let headers = event.response_headers.as_deref().unwrap_or_default();
+
+        if headers
+            .iter()
+            .any(|header| header.name.eq_ignore_ascii_case("set-cookie"))
+        {
+            let mut params = ContinueResponseParams::new(event.request_id.clone());
+            params.response_headers = Some(
+                headers
+                    .iter()
+                    .filter(|header| !header.name.eq_ignore_ascii_case("set-cookie"))
+                    .cloned()
+                    .collect(),
+            );
+            self.push_cdp_request(params);
+        } else {
+            self.push_cdp_request(ContinueRequestParams::new(event.request_id.clone()))
+        }
+    }
+
+    /// Count the body bytes of the responses with a size cap and stop loading the page once a body goes over it,
+    /// the streamed bodies without a `Content-Length` can not be failed from the fetch domain.
+    pub fn on_data_received(&mut self, event: &EventDataReceived) {
+        let oversized = match self.response_budgets.get_mut(event.request_id.as_ref()) {
+            Some(budget) => {
+                budget.received += event.data_length.max(event.encoded_data_length).max(0) as u64;
+                budget.received > budget.max_bytes
+            }
+            _ => false,
+        };
+
+        if oversized {
+            if let Some(budget) = self.response_budgets.remove(event.request_id.as_ref()) {
+                self.push_cdp_request(StopLoadingParams::default());
+                self.record_blocked_request(
+                    event.request_id.as_ref(),
+                    &budget.url,
+                    &budget.resource_type,
+                    BlockReason::Oversized,
+                );
+            }
+        }
+    }
+
+    /// Fail the service worker script requests. Chrome sends the `Service-Worker: script` header when fetching them.
+    fn block_service_worker(&mut self, event: &EventRequestPaused) -> bool {
+        let blocked = self.block_service_workers
+            && event
+                .request
+                .headers
+                .inner()
+                .as_object()
+                .map_or(false, |headers| {
+                    headers
+                        .keys()
+                        .any(|k| k.eq_ignore_ascii_case("service-worker"))
+                });
+
+        if blocked {
+            self.push_cdp_request(FailRequestParams::new(
+                event.request_id.clone(),
+                ErrorReason::BlockedByClient,
+            ));
+            self.record_blocked(event, BlockReason::ServiceWorker);
+        }
+
+        blocked
+    }
+
+    /// Fail the request if it is from a blocked third party. The documents are never blocked so the redirects of the
+    /// page to another domain still load.
+    fn block_third_party(&mut self, event: &EventRequestPaused) -> bool {
+        let blocked = match self.third_party_policy {
+            Some(ref policy) => {
+                event.resource_type != ResourceType::Document
+                    && policy.is_blocked(&event.request.url)
+            }
+            _ => false,
+        };
+
+        if blocked {
+            self.blocked_third_party.push(event.request.url.clone());
+            self.push_cdp_request(FailRequestParams::new(
+                event.request_id.clone(),
+                ErrorReason::BlockedByClient,
+            ));
+            self.record_blocked(event, BlockReason::ThirdParty);
+        }
+
+        blocked
+    }
+
     fn update_protocol_request_interception(&mut self) {
-        let enabled = self.user_request_interception_enabled || self.credentials.is_some();
+        let enabled = self.user_request_interception_enabled
+            || self.credentials.is_some()
+            || self.third_party_policy.is_some()
+            || self.request_filter.is_some()
+            || self.mock_router.is_some()
+            || self.fulfill_cache.is_some()
+            || !self.max_response_bytes.is_empty()
+            || self.block_response_cookies
+            || self.block_service_workers
+            || self.redirect_policy.is_some();
 
         if enabled == self.protocol_request_interception_enabled {
             return;
@@ -206,10 +912,30 @@ impl NetworkManager {
         self.update_protocol_cache_disabled();
 
         if enabled {
+            let mut patterns = vec![RequestPattern::builder().url_pattern("*").build()];
+            // the responses of the capped resource types, or every response when the cookies are removed, pause
+            // again once their headers are received.
+            if self.block_response_cookies {
+                patterns.push(
+                    RequestPattern::builder()
+                        .url_pattern("*")
+                        .request_stage(RequestStage::Response)
+                        .build(),
+                );
+            } else {
+                patterns.extend(self.max_response_bytes.keys().map(|resource_type| {
+                    RequestPattern::builder()
+                        .url_pattern("*")
+                        .resource_type(resource_type.clone())
+                        .request_stage(RequestStage::Response)
+                        .build()
+                }));
+            }
+
             self.push_cdp_request(
                 fetch::EnableParams::builder()
                     .handle_auth_requests(true)
-                    .pattern(RequestPattern::builder().url_pattern("*").build())
+                    .patterns(patterns)
                     .build(),
             )
         } else {
@@ -217,8 +943,31 @@ impl NetworkManager {
         }
     }
 
-    #[cfg(not(feature = "adblock"))]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip_all,
+            fields(
+                request_id = event.request_id.as_ref(),
+                url = %event.request.url,
+                resource_type = ?event.resource_type
+            )
+        )
+    )]
     pub fn on_fetch_request_paused(&mut self, event: &EventRequestPaused) {
+        if event.response_status_code.is_some() || event.response_error_reason.is_some() {
+            return self.on_fetch_response_paused(event);
+        }
+        if self.fulfill_mocked(event)
+            || self.block_service_worker(event)
+            || self.block_third_party(event)
+            || self.block_filtered(event)
+            || self.block_redirect(event)
+            || self.fulfill_cached(event)
+        {
+            return;
+        }
         if !self.user_request_interception_enabled && self.protocol_request_interception_enabled {
             self.push_cdp_request(ContinueRequestParams::new(event.request_id.clone()))
         } else {
@@ -228,35 +977,30 @@ impl NetworkManager {
                 {
                     self.on_request(&request_will_be_sent, Some(event.request_id.clone().into()));
                 } else {
-                    let skip_networking = IGNORE_NETWORKING_RESOURCE_MAP
-                        .contains(&event.resource_type.as_ref())
-                        || self.ignore_visuals
-                            && (IGNORE_VISUAL_RESOURCE_MAP.contains(&event.resource_type.as_ref()))
-                        || self.block_stylesheets
-                            && ResourceType::Stylesheet == event.resource_type
-                        || self.block_javascript
-                            && ResourceType::Script == event.resource_type
-                            && !JS_FRAMEWORK_ALLOW.contains(&event.request.url.as_str())
-                        || (!self.block_javascript
-                            && event
-                                .request
-                                .url
-                                .starts_with("https://www.google-analytics.com")
-                            || event
-                                .request
-                                .url
-                                .starts_with("https://www.googletagmanager.com")
-                            || event.request.url.starts_with("https://px.ads.linkedin.com"));
-
-                    if skip_networking {
-                        let fullfill_params =
-                            crate::handler::network::fetch::FulfillRequestParams::new(
+                    let (action, reason) = match self.resource_action(event) {
+                        ResourceAction::Allow if self.detect_ad(event) => {
+                            (ResourceAction::FulfillEmpty, BlockReason::Ad)
+                        }
+                        action => (action, BlockReason::Resource),
+                    };
+
+                    match action {
+                        ResourceAction::Allow => self
+                            .push_cdp_request(ContinueRequestParams::new(event.request_id.clone())),
+                        ResourceAction::Block => {
+                            self.push_cdp_request(FailRequestParams::new(
+                                event.request_id.clone(),
+                                ErrorReason::BlockedByClient,
+                            ));
+                            self.record_blocked(event, reason);
+                        }
+                        ResourceAction::FulfillEmpty => {
+                            self.push_cdp_request(FulfillRequestParams::new(
                                 event.request_id.clone(),
                                 200,
-                            );
-                        self.push_cdp_request(fullfill_params);
-                    } else {
-                        self.push_cdp_request(ContinueRequestParams::new(event.request_id.clone()))
+                            ));
+                            self.record_blocked(event, reason);
+                        }
                     }
                 }
             } else {
@@ -265,72 +1009,48 @@ impl NetworkManager {
         }
     }
 
-    #[cfg(feature = "adblock")]
-    pub fn on_fetch_request_paused(&mut self, event: &EventRequestPaused) {
-        if !self.user_request_interception_enabled && self.protocol_request_interception_enabled {
-            self.push_cdp_request(ContinueRequestParams::new(event.request_id.clone()))
-        } else {
-            if let Some(network_id) = event.network_id.as_ref() {
-                if let Some(request_will_be_sent) =
-                    self.requests_will_be_sent.remove(network_id.as_ref())
-                {
-                    self.on_request(&request_will_be_sent, Some(event.request_id.clone().into()));
-                } else {
-                    let skip_networking = IGNORE_NETWORKING_RESOURCE_MAP
-                        .contains(&event.resource_type.as_ref())
-                        || self.ignore_visuals
-                            && (IGNORE_VISUAL_RESOURCE_MAP.contains(&event.resource_type.as_ref())
-                                || self.block_stylesheets
-                                    && ResourceType::Stylesheet == event.resource_type)
-                        || self.block_javascript
-                            && ResourceType::Script == event.resource_type
-                            && !JS_FRAMEWORK_ALLOW.contains(&event.request.url.as_str());
-
-                    if self.detect_ad(event) || skip_networking {
-                        let fullfill_params =
-                            crate::handler::network::fetch::FulfillRequestParams::new(
-                                event.request_id.clone(),
-                                200,
-                            );
-                        self.push_cdp_request(fullfill_params);
-                    } else {
-                        self.push_cdp_request(ContinueRequestParams::new(event.request_id.clone()))
-                    }
-                }
+    /// The action of the resource policy for the request. The resource types without an action in the policy use the
+    /// `ignore_visuals`, `block_stylesheets` and `block_javascript` shorthands, the prefetches and pings are always
+    /// fulfilled empty.
+    pub fn resource_action(&self, event: &EventRequestPaused) -> ResourceAction {
+        let resource_type = &event.resource_type;
+
+        match self.resource_policy.get(resource_type) {
+            Some(action) => action,
+            _ if IGNORE_NETWORKING_RESOURCE_MAP.contains(&resource_type.as_ref()) => {
+                ResourceAction::FulfillEmpty
+            }
+            _ if self.ignore_visuals
+                && IGNORE_VISUAL_RESOURCE_MAP.contains(&resource_type.as_ref()) =>
+            {
+                ResourceAction::FulfillEmpty
+            }
+            _ if self.block_stylesheets && *resource_type == ResourceType::Stylesheet => {
+                ResourceAction::FulfillEmpty
             }
+            _ if self.block_javascript
+                && *resource_type == ResourceType::Script
+                && !JS_FRAMEWORK_ALLOW.contains(&event.request.url.as_str()) =>
+            {
+                ResourceAction::FulfillEmpty
+            }
+            _ => ResourceAction::Allow,
         }
+    }
 
-        if self.only_html {
-            self.made_request = true;
-        }
+    /// Detect the analytics and tracking requests.
+    #[cfg(not(feature = "adblock"))]
+    pub fn detect_ad(&self, event: &EventRequestPaused) -> bool {
+        let u = &event.request.url;
+
+        u.starts_with("https://www.google-analytics.com")
+            || u.starts_with("https://www.googletagmanager.com")
+            || u.starts_with("https://px.ads.linkedin.com")
     }
 
     /// Perform a page intercept for chrome
     #[cfg(feature = "adblock")]
     pub fn detect_ad(&self, event: &EventRequestPaused) -> bool {
-        use adblock::{
-            lists::{FilterSet, ParseOptions},
-            Engine,
-        };
-        lazy_static::lazy_static! {
-            static ref AD_ENGINE: Engine = {
-                let mut filter_set = FilterSet::new(false);
-                filter_set.add_filters(
-                    &vec![
-                        String::from("-advertisement."),
-                        String::from("-ads."),
-                        String::from("-ad."),
-                        String::from("-advertisement-icon."),
-                        String::from("-advertisement-management/"),
-                        String::from("-advertisement/script."),
-                        String::from("-ads/script."),
-                    ],
-                    ParseOptions::default(),
-                );
-                Engine::from_filter_set(filter_set, true)
-            };
-        };
-
         let asset = ResourceType::Image == event.resource_type
             || ResourceType::Media == event.resource_type
             || ResourceType::Stylesheet == event.resource_type;
@@ -386,6 +1106,18 @@ impl NetworkManager {
     }
 
     /// Request interception doesn't happen for data URLs with Network Service.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip_all,
+            fields(
+                request_id = event.request_id.as_ref(),
+                url = %event.request.url,
+                resource_type = ?event.r#type
+            )
+        )
+    )]
     pub fn on_request_will_be_sent(&mut self, event: &EventRequestWillBeSent) {
         if self.protocol_request_interception_enabled && !event.request.url.starts_with("data:") {
             if let Some(interception_id) = self
@@ -412,13 +1144,42 @@ impl NetworkManager {
     pub fn on_response_received(&mut self, event: &EventResponseReceived) {
         if let Some(mut request) = self.requests.remove(event.request_id.as_ref()) {
             request.set_response(event.response.clone());
+            request.timestamps.response_received = Some(*event.timestamp.inner());
+            if let Some(max_bytes) = self.max_response_bytes.get(&event.r#type) {
+                self.response_budgets.insert(
+                    event.request_id.clone(),
+                    ResponseBudget {
+                        url: event.response.url.clone(),
+                        resource_type: event.r#type.clone(),
+                        max_bytes: *max_bytes,
+                        received: 0,
+                    },
+                );
+            }
+            if let Some(index) = self.record_waterfall(&request) {
+                self.waterfall_loading
+                    .insert(event.request_id.clone(), index);
+            }
             self.queued_events
                 .push_back(NetworkEvent::RequestFinished(request))
         }
     }
 
     pub fn on_network_loading_finished(&mut self, event: &EventLoadingFinished) {
-        if let Some(request) = self.requests.remove(event.request_id.as_ref()) {
+        self.redirects.remove(event.request_id.as_ref());
+        self.response_budgets.remove(event.request_id.as_ref());
+        let timestamp = Some(*event.timestamp.inner());
+        if let Some(index) = self.waterfall_loading.remove(event.request_id.as_ref()) {
+            if let Some(entry) = self.waterfall.get_mut(index) {
+                entry.timestamps.loading_finished = timestamp;
+                entry.encoded_data_length = Some(event.encoded_data_length);
+            }
+        }
+        if let Some(mut request) = self.requests.remove(event.request_id.as_ref()) {
+            request.timestamps.loading_finished = timestamp;
+            if let Some(index) = self.record_waterfall(&request) {
+                self.waterfall[index].encoded_data_length = Some(event.encoded_data_length);
+            }
             if let Some(interception_id) = request.interception_id.as_ref() {
                 self.attempted_authentications
                     .remove(interception_id.as_ref());
@@ -429,8 +1190,26 @@ impl NetworkManager {
     }
 
     pub fn on_network_loading_failed(&mut self, event: &EventLoadingFailed) {
+        self.redirects.remove(event.request_id.as_ref());
+        self.response_budgets.remove(event.request_id.as_ref());
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            request_id = event.request_id.as_ref(),
+            resource_type = ?event.r#type,
+            error = %event.error_text,
+            "request failed"
+        );
+        let timestamp = Some(*event.timestamp.inner());
+        if let Some(index) = self.waterfall_loading.remove(event.request_id.as_ref()) {
+            if let Some(entry) = self.waterfall.get_mut(index) {
+                entry.timestamps.loading_finished = timestamp;
+                entry.failure_text = Some(event.error_text.clone());
+            }
+        }
         if let Some(mut request) = self.requests.remove(event.request_id.as_ref()) {
             request.failure_text = Some(event.error_text.clone());
+            request.timestamps.loading_finished = timestamp;
+            self.record_waterfall(&request);
             if let Some(interception_id) = request.interception_id.as_ref() {
                 self.attempted_authentications
                     .remove(interception_id.as_ref());
@@ -440,34 +1219,71 @@ impl NetworkManager {
         }
     }
 
+    /// Fail the requests in flight with the error text, their loading events never arrive once the renderer
+    /// crashed or the target detached.
+    pub fn abort_requests(&mut self, error_text: &str) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            requests = self.requests.len(),
+            error = error_text,
+            "requests aborted"
+        );
+        self.redirects.clear();
+        self.requests_will_be_sent.clear();
+        self.request_id_to_interception_id.clear();
+        self.attempted_authentications.clear();
+        self.waterfall_loading.clear();
+        for (_, mut request) in self.requests.drain() {
+            request.failure_text = Some(error_text.to_string());
+            self.queued_events
+                .push_back(NetworkEvent::RequestFailed(request));
+        }
+    }
+
     fn on_request(
         &mut self,
         event: &EventRequestWillBeSent,
         interception_id: Option<InterceptionId>,
     ) {
         let mut redirect_chain = Vec::new();
+        let allowed = if event.r#type == Some(ResourceType::Document) {
+            self.track_redirect(event.request_id.as_ref(), &event.request.url)
+        } else {
+            true
+        };
         if let Some(redirect_resp) = event.redirect_response.as_ref() {
             if let Some(mut request) = self.requests.remove(event.request_id.as_ref()) {
-                self.handle_request_redirect(&mut request, redirect_resp.clone());
+                self.handle_request_redirect(&mut request, redirect_resp.clone(), allowed);
                 redirect_chain = std::mem::take(&mut request.redirect_chain);
                 redirect_chain.push(request);
             }
         }
-        let request = HttpRequest::new(
+        let mut request = HttpRequest::new(
             event.request_id.clone(),
             event.frame_id.clone(),
             interception_id,
             self.user_request_interception_enabled,
             redirect_chain,
         );
+        request.headers = crate::headers::Headers::from_cdp(&event.request.headers);
+        request.url = Some(event.request.url.clone());
+        request.method = Some(event.request.method.clone());
+        request.resource_type = event.r#type.as_ref().map(|r| r.as_ref().to_string());
+        request.timestamps.request_will_be_sent = Some(*event.timestamp.inner());
 
         self.requests.insert(event.request_id.clone(), request);
         self.queued_events
             .push_back(NetworkEvent::Request(event.request_id.clone()));
     }
 
-    fn handle_request_redirect(&mut self, request: &mut HttpRequest, response: Response) {
+    fn handle_request_redirect(
+        &mut self,
+        request: &mut HttpRequest,
+        response: Response,
+        allowed: bool,
+    ) {
         request.set_response(response);
+        request.redirect_allowed = Some(allowed);
         if let Some(interception_id) = request.interception_id.as_ref() {
             self.attempted_authentications
                 .remove(interception_id.as_ref());
@@ -482,4 +1298,537 @@ pub enum NetworkEvent {
     Response(RequestId),
     RequestFailed(HttpRequest),
     RequestFinished(HttpRequest),
+    RequestBlocked(EventRequestBlocked),
+}
+
+/// A request of the page the network manager did not send to the network, failed or fulfilled empty. Listen to it with
+/// `page.event_listener::<EventRequestBlocked>()`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventRequestBlocked {
+    /// The id of the paused request.
+    pub request_id: String,
+    /// The url of the request.
+    pub url: String,
+    /// The resource type of the request like `Image`.
+    pub resource_type: String,
+    /// The [`BlockReason`] of the request like `third_party`.
+    pub reason: String,
+}
+
+impl EventRequestBlocked {
+    pub const IDENTIFIER: &'static str = "Spider.requestBlocked";
+}
+
+impl MethodType for EventRequestBlocked {
+    fn method_id() -> MethodId {
+        Self::IDENTIFIER.into()
+    }
+}
+
+impl CustomEvent for EventRequestBlocked {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "adblock")]
+    #[test]
+    fn cosmetic_filters() {
+        let css = cosmetic_filter_css("https://www.example.com/").unwrap_or_default();
+
+        assert!(css.contains("ins.adsbygoogle"));
+        assert!(css.ends_with("{display:none !important}"));
+        assert!(cosmetic_filter_script("https://www.example.com/")
+            .is_some_and(|script| script.contains("createElement(\"style\")")));
+    }
+
+    #[test]
+    fn third_party_policy() {
+        let policy = ThirdPartyPolicy::new(|url| !url.starts_with("https://www.example.com/"));
+        let paused: EventRequestPaused = serde_json::from_value(serde_json::json!({
+            "requestId": "interception-1",
+            "frameId": "frame-1",
+            "resourceType": "Script",
+            "request": {
+                "url": "https://www.google-analytics.com/analytics.js",
+                "method": "GET",
+                "headers": {},
+                "initialPriority": "Low",
+                "referrerPolicy": "no-referrer"
+            }
+        }))
+        .unwrap();
+
+        assert!(!policy.is_blocked("https://www.example.com/app.js"));
+
+        let mut network_manager = NetworkManager::new(true, Duration::from_secs(30));
+        network_manager.set_third_party_policy(Some(policy));
+        while network_manager.poll().is_some() {}
+
+        assert!(network_manager.block_third_party(&paused));
+        assert_eq!(
+            network_manager.blocked_third_party(),
+            ["https://www.google-analytics.com/analytics.js"]
+        );
+
+        let redirect: EventRequestPaused = serde_json::from_value(serde_json::json!({
+            "requestId": "interception-2",
+            "frameId": "frame-1",
+            "resourceType": "Document",
+            "request": {
+                "url": "https://login.example.org/",
+                "method": "GET",
+                "headers": {},
+                "initialPriority": "VeryHigh",
+                "referrerPolicy": "no-referrer"
+            }
+        }))
+        .unwrap();
+
+        assert!(!network_manager.block_third_party(&redirect));
+    }
+
+    #[test]
+    fn block_response_cookies() {
+        let paused: EventRequestPaused = serde_json::from_value(serde_json::json!({
+            "requestId": "interception-1",
+            "frameId": "frame-1",
+            "resourceType": "Document",
+            "request": {
+                "url": "https://www.example.com/",
+                "method": "GET",
+                "headers": {},
+                "initialPriority": "VeryHigh",
+                "referrerPolicy": "no-referrer"
+            },
+            "responseStatusCode": 200,
+            "responseHeaders": [
+                { "name": "Content-Type", "value": "text/html" },
+                { "name": "Set-Cookie", "value": "session=1" }
+            ]
+        }))
+        .unwrap();
+
+        let mut network_manager = NetworkManager::new(true, Duration::from_secs(30));
+        network_manager.set_block_response_cookies(true);
+        while network_manager.poll().is_some() {}
+
+        network_manager.on_fetch_request_paused(&paused);
+
+        match network_manager.poll() {
+            Some(NetworkEvent::SendCdpRequest((method, params))) => {
+                assert_eq!(method, ContinueResponseParams::IDENTIFIER);
+                assert_eq!(
+                    params["responseHeaders"],
+                    serde_json::json!([{ "name": "Content-Type", "value": "text/html" }])
+                );
+            }
+            _ => panic!("expected the response to continue"),
+        }
+    }
+
+    #[test]
+    fn resource_policy() {
+        let paused = |resource_type: &str| -> EventRequestPaused {
+            serde_json::from_value(serde_json::json!({
+                "requestId": "interception-1",
+                "frameId": "frame-1",
+                "resourceType": resource_type,
+                "request": {
+                    "url": "https://example.com/asset",
+                    "method": "GET",
+                    "headers": {},
+                    "initialPriority": "Low",
+                    "referrerPolicy": "no-referrer"
+                }
+            }))
+            .unwrap()
+        };
+
+        let mut network_manager = NetworkManager::new(true, Duration::from_secs(30));
+        network_manager.block_stylesheets = true;
+
+        assert_eq!(
+            network_manager.resource_action(&paused("Stylesheet")),
+            ResourceAction::FulfillEmpty
+        );
+        assert_eq!(
+            network_manager.resource_action(&paused("Ping")),
+            ResourceAction::FulfillEmpty
+        );
+        assert_eq!(
+            network_manager.resource_action(&paused("Font")),
+            ResourceAction::Allow
+        );
+
+        network_manager.set_resource_policy(
+            ResourcePolicy::new()
+                .with(ResourceType::Stylesheet, ResourceAction::Allow)
+                .with(ResourceType::Font, ResourceAction::Block),
+        );
+
+        assert_eq!(
+            network_manager.resource_action(&paused("Stylesheet")),
+            ResourceAction::Allow
+        );
+        assert_eq!(
+            network_manager.resource_action(&paused("Font")),
+            ResourceAction::Block
+        );
+    }
+
+    #[test]
+    fn max_response_bytes() {
+        let paused = |content_length: &str| -> EventRequestPaused {
+            serde_json::from_value(serde_json::json!({
+                "requestId": "interception-1",
+                "frameId": "frame-1",
+                "resourceType": "Fetch",
+                "request": {
+                    "url": "https://example.com/feed.json",
+                    "method": "GET",
+                    "headers": {},
+                    "initialPriority": "High",
+                    "referrerPolicy": "no-referrer"
+                },
+                "responseStatusCode": 200,
+                "responseHeaders": [{ "name": "Content-Length", "value": content_length }]
+            }))
+            .unwrap()
+        };
+
+        let mut network_manager = NetworkManager::new(true, Duration::from_secs(30));
+        network_manager.set_max_response_bytes(HashMap::from([(ResourceType::Fetch, 1024)]));
+        while network_manager.poll().is_some() {}
+
+        network_manager.on_fetch_request_paused(&paused("512"));
+        assert!(matches!(
+            network_manager.poll(),
+            Some(NetworkEvent::SendCdpRequest((method, _))) if method == ContinueRequestParams::IDENTIFIER
+        ));
+
+        network_manager.on_fetch_request_paused(&paused("2147483648"));
+        assert!(matches!(
+            network_manager.poll(),
+            Some(NetworkEvent::SendCdpRequest((method, _))) if method == FailRequestParams::IDENTIFIER
+        ));
+        assert!(matches!(
+            network_manager.poll(),
+            Some(NetworkEvent::RequestBlocked(blocked)) if blocked.reason == "oversized"
+        ));
+    }
+
+    #[test]
+    fn block_service_workers() {
+        let paused = |headers: serde_json::Value| -> EventRequestPaused {
+            serde_json::from_value(serde_json::json!({
+                "requestId": "interception-1",
+                "frameId": "frame-1",
+                "resourceType": "Other",
+                "request": {
+                    "url": "https://example.com/sw.js",
+                    "method": "GET",
+                    "headers": headers,
+                    "initialPriority": "Low",
+                    "referrerPolicy": "no-referrer"
+                }
+            }))
+            .unwrap()
+        };
+
+        let mut network_manager = NetworkManager::new(true, Duration::from_secs(30));
+        network_manager.set_block_service_workers(true);
+
+        let mut intercepting = false;
+        while let Some(event) = network_manager.poll() {
+            intercepting |= matches!(
+                event,
+                NetworkEvent::SendCdpRequest((method, _)) if method == fetch::EnableParams::IDENTIFIER
+            );
+        }
+        assert!(intercepting);
+
+        assert!(network_manager
+            .block_service_worker(&paused(serde_json::json!({ "Service-Worker": "script" }))));
+        assert!(!network_manager.block_service_worker(&paused(serde_json::json!({}))));
+        assert!(matches!(
+            network_manager.poll(),
+            Some(NetworkEvent::SendCdpRequest((method, _))) if method == FailRequestParams::IDENTIFIER
+        ));
+        match network_manager.poll() {
+            Some(NetworkEvent::RequestBlocked(blocked)) => {
+                assert_eq!(blocked.url, "https://example.com/sw.js");
+                assert_eq!(blocked.resource_type, "Other");
+                assert_eq!(blocked.reason, "service_worker");
+            }
+            _ => panic!("expected a blocked request event"),
+        }
+    }
+
+    #[test]
+    fn abort_requests() {
+        let will_be_sent: EventRequestWillBeSent = serde_json::from_value(serde_json::json!({
+            "requestId": "request-1",
+            "loaderId": "loader-1",
+            "documentURL": "https://example.com/",
+            "request": {
+                "url": "https://example.com/",
+                "method": "GET",
+                "headers": {},
+                "initialPriority": "VeryHigh",
+                "referrerPolicy": "no-referrer"
+            },
+            "timestamp": 1.0,
+            "wallTime": 1.0,
+            "initiator": { "type": "other" },
+            "redirectHasExtraInfo": false,
+            "type": "Document",
+            "frameId": "frame-1"
+        }))
+        .unwrap();
+
+        let mut network_manager = NetworkManager::new(true, Duration::from_secs(30));
+        network_manager.on_request_will_be_sent(&will_be_sent);
+        assert!(matches!(
+            network_manager.poll(),
+            Some(NetworkEvent::Request(_))
+        ));
+
+        network_manager.abort_requests("net::ERR_FAILED");
+        match network_manager.poll() {
+            Some(NetworkEvent::RequestFailed(request)) => {
+                assert_eq!(request.url.as_deref(), Some("https://example.com/"));
+                assert_eq!(request.failure_text.as_deref(), Some("net::ERR_FAILED"));
+            }
+            _ => panic!("expected a failed request event"),
+        }
+        assert!(network_manager.poll().is_none());
+    }
+
+    #[test]
+    fn request_filter() {
+        let mut network_manager = NetworkManager::new(true, Duration::from_secs(30));
+        network_manager
+            .set_request_filter(Some(RequestFilter::new(|url, _| url.contains("/private/"))));
+
+        let paused = |url: &str| -> EventRequestPaused {
+            serde_json::from_value(serde_json::json!({
+                "requestId": "interception-1",
+                "frameId": "frame-1",
+                "resourceType": "XHR",
+                "request": {
+                    "url": url,
+                    "method": "GET",
+                    "headers": {},
+                    "initialPriority": "High",
+                    "referrerPolicy": "no-referrer"
+                }
+            }))
+            .unwrap()
+        };
+
+        assert!(network_manager.block_filtered(&paused("https://example.com/private/data.json")));
+        assert!(!network_manager.block_filtered(&paused("https://example.com/public/data.json")));
+    }
+
+    #[test]
+    fn mock_router() {
+        assert!(wildcard_match(
+            "https://example.com/*",
+            "https://example.com/a/b"
+        ));
+        assert!(wildcard_match("*.png", "https://example.com/logo.png"));
+        assert!(wildcard_match(
+            "https://*/api/*/items",
+            "https://example.com/api/v1/items"
+        ));
+        assert!(!wildcard_match(
+            "https://example.com/api",
+            "https://example.com/api/v1"
+        ));
+
+        let mut network_manager = NetworkManager::new(true, Duration::from_secs(30));
+        network_manager.set_mock_router(Some(MockRouter {
+            routes: vec![MockRoute {
+                pattern: "https://example.com/api/*".into(),
+                status: 201,
+                headers: vec![("Content-Type".into(), "application/json".into())],
+                body: b"{}".to_vec(),
+            }],
+            ..Default::default()
+        }));
+
+        let paused: EventRequestPaused = serde_json::from_value(serde_json::json!({
+            "requestId": "interception-1",
+            "frameId": "frame-1",
+            "resourceType": "Fetch",
+            "request": {
+                "url": "https://example.com/api/items",
+                "method": "GET",
+                "headers": {},
+                "initialPriority": "High",
+                "referrerPolicy": "no-referrer"
+            }
+        }))
+        .unwrap();
+
+        while network_manager.poll().is_some() {}
+
+        assert!(network_manager.fulfill_mocked(&paused));
+        match network_manager.poll() {
+            Some(NetworkEvent::SendCdpRequest((method, params))) => {
+                assert_eq!(method, FulfillRequestParams::IDENTIFIER);
+                assert_eq!(params["responseCode"], 201);
+                assert_eq!(params["body"], "e30=");
+            }
+            _ => panic!("expected a fulfill request"),
+        }
+
+        let mut exact = HashMap::new();
+        exact.insert(
+            "https://example.com/".to_string(),
+            MockRoute {
+                pattern: "https://example.com/".into(),
+                status: 200,
+                ..Default::default()
+            },
+        );
+        exact.insert(
+            "POST https://example.com/api/items".to_string(),
+            MockRoute {
+                pattern: "https://example.com/api/items".into(),
+                status: 202,
+                ..Default::default()
+            },
+        );
+        let router = MockRouter {
+            exact,
+            offline: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            router
+                .find("POST", "https://example.com/api/items")
+                .map(|route| route.status),
+            Some(202)
+        );
+        assert!(router
+            .find("GET", "https://example.com/api/items")
+            .is_none());
+        network_manager.set_mock_router(Some(router));
+        while network_manager.poll().is_some() {}
+
+        assert!(network_manager.fulfill_mocked(&paused));
+        match network_manager.poll() {
+            Some(NetworkEvent::SendCdpRequest((method, params))) => {
+                assert_eq!(method, FailRequestParams::IDENTIFIER);
+                assert_eq!(params["errorReason"], "InternetDisconnected");
+            }
+            _ => panic!("expected a fail request"),
+        }
+    }
+
+    #[tokio::test]
+    async fn fulfill_cached() {
+        let paused = |resource_type: &str| -> EventRequestPaused {
+            serde_json::from_value(serde_json::json!({
+                "requestId": "interception-1",
+                "frameId": "frame-1",
+                "resourceType": resource_type,
+                "request": {
+                    "url": "https://example.com/font.woff2",
+                    "method": "GET",
+                    "headers": {},
+                    "initialPriority": "High",
+                    "referrerPolicy": "no-referrer"
+                }
+            }))
+            .unwrap()
+        };
+
+        let cache = FulfillCache::new();
+        let mut network_manager = NetworkManager::new(true, Duration::from_secs(30));
+        network_manager.set_fulfill_cache(Some(cache.clone()));
+        while network_manager.poll().is_some() {}
+
+        assert!(!network_manager.fulfill_cached(&paused("Font")));
+
+        cache.insert(
+            "https://example.com/font.woff2".into(),
+            CachedResponse {
+                status: 200,
+                headers: vec![("Content-Type".into(), "font/woff2".into())],
+                validator: "\"v1\"".into(),
+                body: CachedBody::Memory(b"{}".as_slice().into()),
+            },
+        );
+
+        assert!(!network_manager.fulfill_cached(&paused("Document")));
+        assert!(network_manager.fulfill_cached(&paused("Font")));
+        match network_manager.poll() {
+            Some(NetworkEvent::SendCdpRequest((method, params))) => {
+                assert_eq!(method, FulfillRequestParams::IDENTIFIER);
+                assert_eq!(params["responseCode"], 200);
+                assert_eq!(params["body"], "e30=");
+            }
+            _ => panic!("expected a fulfill request"),
+        }
+
+        let path = std::env::temp_dir().join("fulfill_cached_test.body");
+        std::fs::write(&path, b"[]").unwrap();
+        cache.insert(
+            "https://example.com/font.woff2".into(),
+            CachedResponse {
+                status: 200,
+                headers: vec![],
+                validator: "\"v2\"".into(),
+                body: CachedBody::Disk(path.clone(), 2),
+            },
+        );
+
+        assert!(network_manager.fulfill_cached(&paused("Font")));
+        assert!(network_manager.poll().is_none());
+        let event = futures::future::poll_fn(|cx| {
+            network_manager.poll_fulfill_reads(cx);
+            match network_manager.poll() {
+                Some(event) => Poll::Ready(event),
+                _ => Poll::Pending,
+            }
+        })
+        .await;
+        match event {
+            NetworkEvent::SendCdpRequest((method, params)) => {
+                assert_eq!(method, FulfillRequestParams::IDENTIFIER);
+                assert_eq!(params["body"], "W10=");
+            }
+            _ => panic!("expected a fulfill request"),
+        }
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn redirect_policy() {
+        let origin = "https://www.example.com/a";
+        let mut manager = NetworkManager::new(false, Duration::from_secs(1));
+
+        assert!(manager.track_redirect("1", origin));
+        assert!(manager.track_redirect("1", "https://login.example.com"));
+        assert!(manager.redirects.is_empty());
+
+        manager.set_redirect_policy(Some(RedirectPolicy::new(|origin, target, hops| {
+            let host = |url: &str| {
+                url::Url::parse(url)
+                    .ok()
+                    .map(|u| u.host_str().map(String::from))
+            };
+            hops <= 2 && host(origin) == host(target)
+        })));
+
+        assert!(manager.track_redirect("1", origin));
+        assert!(manager.track_redirect("1", "https://www.example.com/b"));
+        assert!(!manager.track_redirect("1", "https://login.example.com"));
+        assert!(!manager.track_redirect("1", "https://login.example.com"));
+        assert_eq!(manager.redirects["1"].hops, 2);
+    }
 }
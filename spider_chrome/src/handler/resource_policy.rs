@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use chromiumoxide_cdp::cdp::browser_protocol::network::ResourceType;
+
+/// What happens to the intercepted requests of a resource type.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum ResourceAction {
+    /// Send the request to the network.
+    #[default]
+    Allow,
+    /// Fail the request as blocked by the client.
+    Block,
+    /// Answer the request with an empty `200` response, the page sees the resource as loaded.
+    FulfillEmpty,
+}
+
+/// The action of each resource type of the intercepted requests. The types without an action fall back to the
+/// `ignore_visuals`, `block_stylesheets` and `block_javascript` shorthands and are allowed when none applies.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ResourcePolicy {
+    /// The actions by resource type.
+    actions: HashMap<ResourceType, ResourceAction>,
+}
+
+impl ResourcePolicy {
+    /// A policy without actions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the action of the resource type.
+    pub fn with(mut self, resource_type: ResourceType, action: ResourceAction) -> Self {
+        self.set(resource_type, action);
+        self
+    }
+
+    /// Set the action of the resource type.
+    pub fn set(&mut self, resource_type: ResourceType, action: ResourceAction) -> &mut Self {
+        self.actions.insert(resource_type, action);
+        self
+    }
+
+    /// The action set for the resource type.
+    pub fn get(&self, resource_type: &ResourceType) -> Option<ResourceAction> {
+        self.actions.get(resource_type).copied()
+    }
+
+    /// Whether no action is set.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// The resource types with an action.
+    pub fn iter(&self) -> impl Iterator<Item = (&ResourceType, &ResourceAction)> {
+        self.actions.iter()
+    }
+}
+
+impl FromIterator<(ResourceType, ResourceAction)> for ResourcePolicy {
+    fn from_iter<I: IntoIterator<Item = (ResourceType, ResourceAction)>>(iter: I) -> Self {
+        Self {
+            actions: iter.into_iter().collect(),
+        }
+    }
+}
@@ -1,8 +1,9 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Instant;
 
+use chromiumoxide_cdp::cdp::browser_protocol::network::ResourceType;
 use chromiumoxide_cdp::cdp::browser_protocol::target::DetachFromTargetParams;
 use futures::channel::oneshot::Sender;
 use futures::stream::Stream;
@@ -11,7 +12,7 @@ use futures::task::{Context, Poll};
 use chromiumoxide_cdp::cdp::browser_protocol::page::{FrameId, GetFrameTreeParams};
 use chromiumoxide_cdp::cdp::browser_protocol::{
     browser::BrowserContextId,
-    log as cdplog, performance,
+    inspector, log as cdplog, performance,
     target::{AttachToTargetParams, SessionId, SetAutoAttachParams, TargetId, TargetInfo},
 };
 use chromiumoxide_cdp::cdp::events::CdpEvent;
@@ -30,9 +31,15 @@ use crate::handler::frame::{
     FrameEvent, FrameManager, NavigationError, NavigationId, NavigationOk,
 };
 use crate::handler::frame::{FrameNavigationRequest, UTILITY_WORLD_NAME};
-use crate::handler::network::{NetworkEvent, NetworkManager};
+use crate::handler::fulfill_cache::FulfillCache;
+use crate::handler::network::{
+    EventRequestBlocked, MockRouter, NetworkEvent, NetworkManager, RedirectPolicy, RequestFilter,
+    ThirdPartyPolicy,
+};
 use crate::handler::page::PageHandle;
+use crate::handler::resource_policy::ResourcePolicy;
 use crate::handler::viewport::Viewport;
+use crate::handler::waterfall::WaterfallEntry;
 use crate::handler::{PageInner, REQUEST_TIMEOUT};
 use crate::listeners::{EventListenerRequest, EventListeners};
 use crate::{page::Page, ArcHttpRequest};
@@ -112,6 +119,9 @@ impl Target {
         network_manager.ignore_visuals = config.ignore_visuals;
         network_manager.block_javascript = config.ignore_javascript;
         network_manager.block_stylesheets = config.ignore_stylesheets;
+        network_manager.set_block_service_workers(config.block_service_workers);
+        network_manager.set_resource_policy(config.resource_policy.clone());
+        network_manager.set_bypass_service_worker(config.bypass_service_worker);
         network_manager.only_html = config.only_html;
 
         Self {
@@ -290,6 +300,8 @@ impl Target {
                 }
             }
 
+            CdpEvent::InspectorTargetCrashed(_) => self.on_crashed(),
+
             // `NetworkManager` events
             CdpEvent::FetchRequestPaused(ev) => self.network_manager.on_fetch_request_paused(ev),
             CdpEvent::FetchAuthRequired(ev) => self.network_manager.on_fetch_auth_required(ev),
@@ -300,6 +312,7 @@ impl Target {
                 self.network_manager.on_request_served_from_cache(ev)
             }
             CdpEvent::NetworkResponseReceived(ev) => self.network_manager.on_response_received(ev),
+            CdpEvent::NetworkDataReceived(ev) => self.network_manager.on_data_received(ev),
             CdpEvent::NetworkLoadingFinished(ev) => {
                 self.network_manager.on_network_loading_finished(ev)
             }
@@ -308,12 +321,51 @@ impl Target {
             }
             _ => {}
         }
+        if crate::chaos::drop_event(self.config.event_drop_rate) {
+            return;
+        }
         chromiumoxide_cdp::consume_event!(match params {
            |ev| self.event_listeners.start_send(ev),
            |json| { let _ = self.event_listeners.try_send_custom(&method, json);}
         });
     }
 
+    /// The renderer of the target crashed. The requests in flight and the navigations fail right away instead of
+    /// waiting on the timeout, the target can be navigated again to restart the renderer.
+    pub fn on_crashed(&mut self) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(target_id = self.target_id().as_ref(), "target crashed");
+        self.network_manager.abort_requests("net::ERR_FAILED");
+        self.frame_manager
+            .abort_navigations(|id| NavigationError::TargetCrashed { id });
+        self.abort_initialization(CdpError::TargetCrashed);
+    }
+
+    /// The session of the target detached, the responses of the target never arrive on the session.
+    pub fn on_detached(&mut self) {
+        self.network_manager.abort_requests("net::ERR_ABORTED");
+        self.frame_manager
+            .abort_navigations(|id| NavigationError::TargetDetached { id });
+        self.abort_initialization(CdpError::TargetDetached);
+    }
+
+    /// Fail the initiator of a target that is not initialized yet and close the target.
+    fn abort_initialization(&mut self, err: CdpError) {
+        if self.is_initialized() || matches!(self.init_state, TargetInit::Closing) {
+            return;
+        }
+        if let Some(initiator) = self.initiator.take() {
+            let _ = initiator.send(Err(err));
+        }
+        self.init_state = TargetInit::Closing;
+        let close_target = CloseTargetParams::new(self.info.target_id.clone());
+        self.queued_events.push_back(TargetEvent::Request(Request {
+            method: close_target.identifier(),
+            session_id: None,
+            params: serde_json::to_value(close_target).unwrap(),
+        }));
+    }
+
     /// Called when a init command timed out
     fn on_initialization_failed(&mut self) -> TargetEvent {
         if let Some(initiator) = self.initiator.take() {
@@ -424,7 +476,7 @@ impl Target {
                     }
                 }
             }
-            TargetInit::Closing => return None,
+            TargetInit::Closing => return self.queued_events.pop_front(),
         };
         loop {
             if let Some(frame) = self.frame_manager.main_frame() {
@@ -528,10 +580,45 @@ impl Target {
                         TargetMessage::Authenticate(credentials) => {
                             self.network_manager.authenticate(credentials);
                         }
+                        TargetMessage::UserAgent(user_agent) => {
+                            self.network_manager
+                                .set_extra_headers_user_agent(&user_agent);
+                        }
+                        TargetMessage::ThirdPartyPolicy(policy) => {
+                            self.network_manager.set_third_party_policy(policy);
+                        }
+                        TargetMessage::BlockResponseCookies(block_response_cookies) => {
+                            self.network_manager
+                                .set_block_response_cookies(block_response_cookies);
+                        }
+                        TargetMessage::MockRouter(router) => {
+                            self.network_manager.set_mock_router(router);
+                        }
+                        TargetMessage::FulfillCache(cache) => {
+                            self.network_manager.set_fulfill_cache(cache);
+                        }
+                        TargetMessage::MaxResponseBytes(max_response_bytes) => {
+                            self.network_manager
+                                .set_max_response_bytes(max_response_bytes);
+                        }
+                        TargetMessage::RequestFilter(filter) => {
+                            self.network_manager.set_request_filter(filter);
+                        }
+                        TargetMessage::RedirectPolicy(policy) => {
+                            self.network_manager.set_redirect_policy(policy);
+                        }
+                        TargetMessage::BlockedThirdParty(tx) => {
+                            let _ = tx.send(self.network_manager.blocked_third_party().to_vec());
+                        }
+                        TargetMessage::Waterfall(tx) => {
+                            let _ = tx.send(self.network_manager.waterfall().to_vec());
+                        }
                     }
                 }
             }
 
+            self.network_manager.poll_fulfill_reads(cx);
+
             while let Some(event) = self.network_manager.poll() {
                 match event {
                     NetworkEvent::SendCdpRequest((method, params)) => {
@@ -550,6 +637,13 @@ impl Target {
                     NetworkEvent::RequestFinished(request) => {
                         self.frame_manager.on_http_request_finished(request);
                     }
+                    NetworkEvent::RequestBlocked(blocked) => {
+                        if let Ok(json) = serde_json::to_value(blocked) {
+                            let _ = self
+                                .event_listeners
+                                .try_send_custom(EventRequestBlocked::IDENTIFIER, json);
+                        }
+                    }
                 }
             }
 
@@ -587,6 +681,7 @@ impl Target {
             .unwrap();
         let enable_performance = performance::EnableParams::default();
         let enable_log = cdplog::EnableParams::default();
+        let enable_inspector = inspector::EnableParams::default();
         CommandChain::new(
             vec![
                 (attach.identifier(), serde_json::to_value(attach).unwrap()),
@@ -598,6 +693,10 @@ impl Target {
                     enable_log.identifier(),
                     serde_json::to_value(enable_log).unwrap(),
                 ),
+                (
+                    enable_inspector.identifier(),
+                    serde_json::to_value(enable_inspector).unwrap(),
+                ),
             ],
             timeout,
         )
@@ -615,8 +714,13 @@ pub struct TargetConfig {
     pub ignore_visuals: bool,
     pub ignore_javascript: bool,
     pub ignore_stylesheets: bool,
+    pub bypass_service_worker: bool,
+    pub block_service_workers: bool,
+    pub resource_policy: ResourcePolicy,
     pub only_html: bool,
-    pub extra_headers: Option<std::collections::HashMap<String, String>>,
+    pub extra_headers: Option<crate::headers::Headers>,
+    /// The share of the CDP events dropped before they reach the event listeners.
+    pub event_drop_rate: f64,
 }
 
 impl Default for TargetConfig {
@@ -630,8 +734,12 @@ impl Default for TargetConfig {
             ignore_javascript: false,
             ignore_visuals: false,
             ignore_stylesheets: false,
+            bypass_service_worker: false,
+            block_service_workers: false,
+            resource_policy: Default::default(),
             only_html: false,
             extra_headers: Default::default(),
+            event_drop_rate: 0.0,
         }
     }
 }
@@ -801,4 +909,24 @@ pub enum TargetMessage {
     /// Get the `ExecutionContext` if available
     GetExecutionContext(GetExecutionContext),
     Authenticate(Credentials),
+    /// Set the user agent used in the extra headers of this target
+    UserAgent(String),
+    /// Block the third party requests of the page
+    ThirdPartyPolicy(Option<ThirdPartyPolicy>),
+    /// Remove the `Set-Cookie` headers of the responses of the page
+    BlockResponseCookies(bool),
+    /// Return the third party requests blocked by the policy
+    BlockedThirdParty(Sender<Vec<String>>),
+    /// Return the timing waterfall of the requests of the page
+    Waterfall(Sender<Vec<WaterfallEntry>>),
+    /// Block the requests matching the filter
+    RequestFilter(Option<RequestFilter>),
+    /// Answer the requests matching the routes with canned responses
+    MockRouter(Option<MockRouter>),
+    /// Answer the repeated subresource requests with the responses shared by the pages
+    FulfillCache(Option<FulfillCache>),
+    /// Set the bytes allowed for the response bodies by resource type
+    MaxResponseBytes(HashMap<ResourceType, u64>),
+    /// Set the redirects followed by the document requests
+    RedirectPolicy(Option<RedirectPolicy>),
 }
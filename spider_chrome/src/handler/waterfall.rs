@@ -0,0 +1,196 @@
+use chromiumoxide_cdp::cdp::browser_protocol::network::ResourceTiming;
+
+use crate::handler::http::{HttpRequest, RequestTimestamps};
+
+/// The requests kept in the waterfall of a page, the later requests are not recorded.
+pub const MAX_WATERFALL_ENTRIES: usize = 2048;
+
+/// A request of the timing waterfall of a page.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaterfallEntry {
+    /// The url requested.
+    pub url: String,
+    /// The resource type of the request like `Script`.
+    pub resource_type: Option<String>,
+    /// The status code of the response.
+    pub status: Option<i64>,
+    /// The response was served from the memory cache.
+    pub from_memory_cache: bool,
+    /// The error of the request when it failed.
+    pub failure_text: Option<String>,
+    /// The timestamps of the network events of the request.
+    pub timestamps: RequestTimestamps,
+    /// The bytes received for the request.
+    pub encoded_data_length: Option<f64>,
+    /// The CDP resource timing of the response.
+    pub timing: Option<ResourceTiming>,
+}
+
+/// The phases of a request in milliseconds. A phase is `None` when the request skipped it, like the dns lookup and
+/// the connection of a reused connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaterfallPhases {
+    /// The time queued before the request started.
+    pub queued: Option<f64>,
+    /// The dns lookup.
+    pub dns: Option<f64>,
+    /// The tcp connection without the tls handshake.
+    pub connect: Option<f64>,
+    /// The tls handshake.
+    pub tls: Option<f64>,
+    /// The time to the first byte, from sending the request to receiving the headers of the response.
+    pub ttfb: Option<f64>,
+    /// The download of the body, from the response to the end of the loading.
+    pub download: Option<f64>,
+    /// The whole request, from the request to the end of the loading.
+    pub total: Option<f64>,
+}
+
+/// The milliseconds between the two offsets of the resource timing. The offsets are `-1` when the phase did not happen.
+fn span(start: f64, end: f64) -> Option<f64> {
+    if start >= 0.0 && end >= start {
+        Some(end - start)
+    } else {
+        None
+    }
+}
+
+/// The milliseconds between the two timestamps in seconds.
+fn elapsed(start: Option<f64>, end: Option<f64>) -> Option<f64> {
+    match (start, end) {
+        (Some(start), Some(end)) if end >= start => Some((end - start) * 1000.0),
+        _ => None,
+    }
+}
+
+impl WaterfallEntry {
+    /// The entry of the request.
+    pub fn new(request: &HttpRequest) -> Self {
+        let response = request.response.as_ref();
+
+        Self {
+            url: request.url.clone().unwrap_or_default(),
+            resource_type: request.resource_type.clone(),
+            status: response.map(|response| response.status),
+            from_memory_cache: request.from_memory_cache,
+            failure_text: request.failure_text.clone(),
+            timestamps: request.timestamps,
+            encoded_data_length: None,
+            timing: response.and_then(|response| response.timing.clone()),
+        }
+    }
+
+    /// The phases of the request.
+    pub fn phases(&self) -> WaterfallPhases {
+        let timestamps = &self.timestamps;
+        let end = timestamps.loading_finished.or(timestamps.response_received);
+
+        let mut phases = WaterfallPhases {
+            download: elapsed(timestamps.response_received, timestamps.loading_finished),
+            total: elapsed(timestamps.request_will_be_sent, end),
+            ..Default::default()
+        };
+
+        if let Some(timing) = &self.timing {
+            let started = [timing.dns_start, timing.connect_start, timing.send_start]
+                .into_iter()
+                .find(|offset| *offset >= 0.0);
+
+            phases.queued = started.and_then(|started| {
+                elapsed(timestamps.request_will_be_sent, Some(timing.request_time))
+                    .map(|queued| queued + started)
+            });
+            phases.dns = span(timing.dns_start, timing.dns_end);
+            phases.tls = span(timing.ssl_start, timing.ssl_end);
+            phases.connect = span(timing.connect_start, timing.connect_end)
+                .map(|connect| connect - phases.tls.unwrap_or_default());
+            phases.ttfb = if timing.receive_headers_start > 0.0 {
+                span(timing.send_start, timing.receive_headers_start)
+            } else {
+                span(timing.send_start, timing.receive_headers_end)
+            };
+        }
+
+        phases
+    }
+
+    /// The milliseconds between the first request of the page and the start of the request.
+    pub fn start(&self, origin: f64) -> Option<f64> {
+        elapsed(Some(origin), self.timestamps.request_will_be_sent)
+    }
+}
+
+/// The slowest requests of the waterfall by their total time, the slowest first.
+pub fn slowest(waterfall: &[WaterfallEntry], limit: usize) -> Vec<&WaterfallEntry> {
+    let mut entries: Vec<(f64, &WaterfallEntry)> = waterfall
+        .iter()
+        .filter_map(|entry| entry.phases().total.map(|total| (total, entry)))
+        .collect();
+
+    entries.sort_by(|a, b| b.0.total_cmp(&a.0));
+    entries.truncate(limit);
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(request_time: f64) -> ResourceTiming {
+        serde_json::from_value(serde_json::json!({
+            "requestTime": request_time,
+            "proxyStart": -1, "proxyEnd": -1,
+            "dnsStart": 2, "dnsEnd": 12,
+            "connectStart": 12, "connectEnd": 52,
+            "sslStart": 22, "sslEnd": 52,
+            "workerStart": -1, "workerReady": -1, "workerFetchStart": -1, "workerRespondWithSettled": -1,
+            "sendStart": 53, "sendEnd": 54,
+            "pushStart": 0, "pushEnd": 0,
+            "receiveHeadersStart": 153, "receiveHeadersEnd": 154
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn waterfall_phases() {
+        let entry = WaterfallEntry {
+            url: "https://example.com/app.js".into(),
+            timestamps: RequestTimestamps {
+                request_will_be_sent: Some(10.0),
+                response_received: Some(10.2),
+                loading_finished: Some(10.5),
+            },
+            timing: Some(timing(10.001)),
+            ..Default::default()
+        };
+
+        let phases = entry.phases();
+        let close = |value: Option<f64>, expected: f64| (value.unwrap() - expected).abs() < 1e-6;
+
+        assert!(close(phases.queued, 3.0));
+        assert!(close(phases.dns, 10.0));
+        assert!(close(phases.connect, 10.0));
+        assert!(close(phases.tls, 30.0));
+        assert!(close(phases.ttfb, 100.0));
+        assert!(close(phases.download, 300.0));
+        assert!(close(phases.total, 500.0));
+        assert!(close(entry.start(9.5), 500.0));
+
+        let cached = WaterfallEntry {
+            timestamps: RequestTimestamps {
+                request_will_be_sent: Some(10.0),
+                response_received: Some(10.01),
+                loading_finished: None,
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(cached.phases().dns, None);
+        assert!(close(cached.phases().total, 10.0));
+
+        let waterfall = vec![cached, entry.clone()];
+        assert_eq!(slowest(&waterfall, 1), vec![&entry]);
+    }
+}
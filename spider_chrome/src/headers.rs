@@ -0,0 +1,303 @@
+//! Case-insensitive, multi-value HTTP headers shared by the browser handler and its callers.
+//!
+//! CDP passes headers as a plain JSON object, which loses the repeated values and lets the same
+//! name appear twice with a different case. [`Headers`] keeps the values in a
+//! [`reqwest::header::HeaderMap`] along with the casing the names were set with, and only
+//! flattens them when talking to the browser: repeated request headers are joined with `, `,
+//! `Set-Cookie` values with `\n` like Chrome reports them, and the `\n` separated values of the
+//! responses are split back apart.
+
+use chromiumoxide_cdp::cdp::browser_protocol::network;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, SET_COOKIE};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// HTTP headers with case-insensitive names and repeated values. The names keep the casing they were first set with.
+#[derive(Debug, Default, Clone)]
+pub struct Headers {
+    /// The values by lowercased name.
+    map: HeaderMap,
+    /// The names set with another casing than the lowercased name.
+    names: HashMap<HeaderName, String>,
+}
+
+impl PartialEq for Headers {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl Eq for Headers {}
+
+impl Headers {
+    /// Empty headers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The first value of the header.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.map.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    /// Every value of the header.
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.map
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect()
+    }
+
+    /// Whether the header is set.
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.map.contains_key(name)
+    }
+
+    /// Keep the casing of the name when it is not lowercase.
+    fn set_casing(&mut self, header: &HeaderName, name: &str) {
+        if header.as_str() != name {
+            self.names.insert(header.clone(), name.to_string());
+        } else {
+            self.names.remove(header);
+        }
+    }
+
+    /// Set the header replacing its previous values and casing. Returns false when the name or value is not a valid
+    /// header.
+    pub fn insert(&mut self, name: &str, value: &str) -> bool {
+        match (HeaderName::from_str(name), HeaderValue::from_str(value)) {
+            (Ok(header), Ok(value)) => {
+                self.set_casing(&header, name);
+                self.map.insert(header, value);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Add a value to the header keeping its previous values and casing. Returns false when the name or value is not a
+    /// valid header.
+    pub fn append(&mut self, name: &str, value: &str) -> bool {
+        match (HeaderName::from_str(name), HeaderValue::from_str(value)) {
+            (Ok(header), Ok(value)) => {
+                if !self.map.contains_key(&header) {
+                    self.set_casing(&header, name);
+                }
+                self.map.append(header, value);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Remove every value of the header. Returns true when the header was set.
+    pub fn remove(&mut self, name: &str) -> bool {
+        match HeaderName::from_str(name) {
+            Ok(header) => {
+                self.names.remove(&header);
+                self.map.remove(header).is_some()
+            }
+            _ => false,
+        }
+    }
+
+    /// The amount of values.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether no header is set.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// The name of the header with the casing it was set with.
+    fn name<'a>(&'a self, header: &'a HeaderName) -> &'a str {
+        self.names
+            .get(header)
+            .map_or(header.as_str(), String::as_str)
+    }
+
+    /// The headers as name and value pairs with the casing the names were set with, a repeated header yielding a pair
+    /// per value.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.map
+            .iter()
+            .filter_map(|(name, value)| Some((self.name(name), value.to_str().ok()?)))
+    }
+
+    /// The inner header map, the names are lowercased.
+    pub fn inner(&self) -> &HeaderMap {
+        &self.map
+    }
+
+    /// Take the inner header map, the names are lowercased.
+    pub fn into_inner(self) -> HeaderMap {
+        self.map
+    }
+
+    /// The headers with one entry per name with the casing it was set with. The repeated values are joined with `, `
+    /// and the `Set-Cookie` values with `\n` since a cookie can hold a comma.
+    pub fn to_hash_map(&self) -> HashMap<String, String> {
+        let mut map: HashMap<String, String> = HashMap::with_capacity(self.map.keys_len());
+
+        for header in self.map.keys() {
+            let separator = if *header == SET_COOKIE { "\n" } else { ", " };
+            let values: Vec<&str> = self
+                .map
+                .get_all(header)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .collect();
+
+            if !values.is_empty() {
+                map.insert(self.name(header).to_string(), values.join(separator));
+            }
+        }
+
+        map
+    }
+
+    /// The headers as the CDP object sent with the requests of the browser.
+    pub fn to_cdp(&self) -> network::Headers {
+        network::Headers::new(serde_json::to_value(self.to_hash_map()).unwrap_or_default())
+    }
+
+    /// Read the headers reported by the browser. The `\n` separated values are split.
+    pub fn from_cdp(headers: &network::Headers) -> Self {
+        let mut result = Self::new();
+
+        if let Some(object) = headers.inner().as_object() {
+            for (name, value) in object {
+                if let Some(value) = value.as_str() {
+                    for value in value.split('\n') {
+                        result.append(name, value.trim());
+                    }
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl From<HeaderMap> for Headers {
+    fn from(map: HeaderMap) -> Self {
+        Self {
+            map,
+            names: HashMap::new(),
+        }
+    }
+}
+
+impl From<&HeaderMap> for Headers {
+    fn from(map: &HeaderMap) -> Self {
+        map.clone().into()
+    }
+}
+
+impl From<Headers> for HeaderMap {
+    fn from(headers: Headers) -> Self {
+        headers.map
+    }
+}
+
+impl From<HashMap<String, String>> for Headers {
+    fn from(headers: HashMap<String, String>) -> Self {
+        headers.iter().collect()
+    }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>> FromIterator<(K, V)> for Headers {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut headers = Self::new();
+        for (name, value) in iter {
+            headers.append(name.as_ref(), value.as_ref());
+        }
+        headers
+    }
+}
+
+impl serde::Serialize for Headers {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let map: std::collections::BTreeMap<String, String> =
+            self.to_hash_map().into_iter().collect();
+        map.serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Headers {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let map: HashMap<String, String> = HashMap::deserialize(deserializer)?;
+        Ok(map.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn case_insensitive_multi_value() {
+        let mut headers = Headers::new();
+        headers.insert("Content-Type", "text/html");
+        headers.append("Set-Cookie", "a=1");
+        headers.append("set-cookie", "b=2");
+
+        assert_eq!(headers.get("content-type"), Some("text/html"));
+        assert_eq!(headers.get_all("SET-COOKIE"), vec!["a=1", "b=2"]);
+        assert_eq!(headers.len(), 3);
+
+        headers.insert("content-type", "application/json");
+        assert_eq!(headers.get_all("Content-Type"), vec!["application/json"]);
+
+        assert!(headers.remove("CONTENT-TYPE"));
+        assert!(!headers.contains_key("content-type"));
+        assert!(!headers.insert("bad name", "value"));
+    }
+
+    #[test]
+    fn cdp_roundtrip() {
+        let cdp = network::Headers::new(serde_json::json!({
+            "Content-Type": "text/html",
+            "Set-Cookie": "a=1\nb=2",
+        }));
+        let headers = Headers::from_cdp(&cdp);
+
+        assert_eq!(headers.get("content-type"), Some("text/html"));
+        assert_eq!(headers.get_all("set-cookie"), vec!["a=1", "b=2"]);
+
+        let map = headers.to_hash_map();
+        assert_eq!(map.get("Set-Cookie").map(String::as_str), Some("a=1\nb=2"));
+        assert_eq!(
+            headers.to_cdp().inner()["Content-Type"],
+            serde_json::json!("text/html")
+        );
+        assert_eq!(
+            Headers::from_cdp(&headers.to_cdp()).get_all("set-cookie"),
+            vec!["a=1", "b=2"]
+        );
+
+        let mut headers = Headers::new();
+        headers.append("Accept", "text/html");
+        headers.append("accept", "application/json");
+        headers.insert("User-Agent", "spider");
+        assert_eq!(
+            headers.to_hash_map().get("Accept").map(String::as_str),
+            Some("text/html, application/json")
+        );
+        assert_eq!(
+            headers.iter().find(|(_, value)| *value == "spider"),
+            Some(("User-Agent", "spider"))
+        );
+        assert!(headers.remove("user-agent"));
+        assert!(headers.iter().all(|(name, _)| name == "Accept"));
+    }
+}
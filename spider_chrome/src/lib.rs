@@ -78,7 +78,10 @@ pub use crate::handler::Handler;
 pub use crate::page::Page;
 
 pub mod auth;
+pub mod backend;
+pub mod bidi;
 pub mod browser;
+pub mod chaos;
 pub(crate) mod cmd;
 pub mod conn;
 pub mod detection;
@@ -90,11 +93,14 @@ pub mod fetcher {
 }
 pub mod async_process;
 pub mod handler;
+pub mod headers;
 pub mod js;
 pub mod keys;
 pub mod layout;
 pub mod listeners;
+pub mod metrics;
 pub mod page;
+pub mod reaper;
 pub(crate) mod utils;
 
 pub type ArcHttpRequest = Option<Arc<HttpRequest>>;
@@ -0,0 +1,348 @@
+//! Latency histograms of the CDP commands sent by the [`crate::Handler`].
+//!
+//! The handler times every command from the moment it is written to the connection until the
+//! browser answers, keyed by the CDP method (`Page.navigate`, `Runtime.evaluate`,
+//! `Page.captureScreenshot`, ...). The time only covers the CDP round trip: a slow render with
+//! fast commands points at the network, slow commands point at the browser itself.
+//!
+//! Recording is off until [`set_enabled`] is called. The requests blocked by the network managers
+//! and the open pages are always counted, see [`network_metrics`].
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Upper bounds in milliseconds of the histogram buckets. Slower commands land in an overflow bucket.
+pub const BUCKETS_MS: [u64; 13] = [1, 2, 5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+/// Whether the handlers record the command latencies.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    /// The command latencies recorded by every handler of this process.
+    static ref COMMAND_METRICS: CommandMetrics = CommandMetrics::default();
+    /// The blocked requests and the open pages of every handler of this process.
+    static ref NETWORK_METRICS: NetworkMetrics = NetworkMetrics::default();
+}
+
+/// Start or stop recording the command latencies.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether the command latencies are recorded.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// The command latencies recorded by every handler of this process.
+pub fn command_metrics() -> &'static CommandMetrics {
+    &COMMAND_METRICS
+}
+
+/// The blocked requests and the open pages of every handler of this process.
+pub fn network_metrics() -> &'static NetworkMetrics {
+    &NETWORK_METRICS
+}
+
+/// A fixed bucket latency histogram safe to update from many handlers.
+#[derive(Debug, Default)]
+struct LatencyHistogram {
+    /// The commands answered within each bucket, the last one holding the overflow.
+    buckets: [AtomicU64; BUCKETS_MS.len() + 1],
+    /// The commands answered.
+    count: AtomicU64,
+    /// The total latency in microseconds.
+    sum_micros: AtomicU64,
+    /// The slowest latency in microseconds.
+    max_micros: AtomicU64,
+    /// The commands that were never answered.
+    timeouts: AtomicU64,
+}
+
+impl LatencyHistogram {
+    fn record(&self, elapsed: Duration) {
+        let millis = elapsed.as_millis() as u64;
+        let micros = elapsed.as_micros() as u64;
+        let bucket = BUCKETS_MS
+            .iter()
+            .position(|bound| millis <= *bound)
+            .unwrap_or(BUCKETS_MS.len());
+
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(micros, Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self, method: &str) -> CommandLatency {
+        CommandLatency {
+            method: method.into(),
+            count: self.count.load(Ordering::Relaxed),
+            sum: Duration::from_micros(self.sum_micros.load(Ordering::Relaxed)),
+            max: Duration::from_micros(self.max_micros.load(Ordering::Relaxed)),
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+            buckets: self
+                .buckets
+                .iter()
+                .map(|bucket| bucket.load(Ordering::Relaxed))
+                .collect(),
+        }
+    }
+}
+
+/// The latency histograms of the commands keyed by CDP method.
+#[derive(Debug, Default)]
+pub struct CommandMetrics {
+    histograms: RwLock<HashMap<String, Arc<LatencyHistogram>>>,
+}
+
+impl CommandMetrics {
+    fn histogram(&self, method: &str) -> Option<Arc<LatencyHistogram>> {
+        if let Some(histogram) = self.histograms.read().ok()?.get(method) {
+            return Some(histogram.clone());
+        }
+
+        Some(
+            self.histograms
+                .write()
+                .ok()?
+                .entry(method.into())
+                .or_default()
+                .clone(),
+        )
+    }
+
+    /// Record the time the browser took to answer a command.
+    pub fn record(&self, method: &str, elapsed: Duration) {
+        if let Some(histogram) = self.histogram(method) {
+            histogram.record(elapsed);
+        }
+    }
+
+    /// Record a command that was never answered.
+    pub fn record_timeout(&self, method: &str) {
+        if let Some(histogram) = self.histogram(method) {
+            histogram.timeouts.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// The latencies of every command recorded, sorted by method.
+    pub fn snapshot(&self) -> Vec<CommandLatency> {
+        let mut latencies: Vec<CommandLatency> = match self.histograms.read() {
+            Ok(histograms) => histograms
+                .iter()
+                .map(|(method, histogram)| histogram.snapshot(method))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        latencies.sort_by(|a, b| a.method.cmp(&b.method));
+        latencies
+    }
+
+    /// Forget every recorded latency.
+    pub fn reset(&self) {
+        if let Ok(mut histograms) = self.histograms.write() {
+            histograms.clear();
+        }
+    }
+}
+
+/// A snapshot of the latency histogram of a CDP method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandLatency {
+    /// The CDP method, like `Page.navigate`.
+    pub method: String,
+    /// The commands answered.
+    pub count: u64,
+    /// The total latency of the answered commands.
+    pub sum: Duration,
+    /// The slowest latency.
+    pub max: Duration,
+    /// The commands that were never answered.
+    pub timeouts: u64,
+    /// The commands answered within each bound of [`BUCKETS_MS`], followed by the overflow.
+    pub buckets: Vec<u64>,
+}
+
+impl CommandLatency {
+    /// The mean latency of the answered commands.
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            self.sum / self.count as u32
+        }
+    }
+
+    /// The upper bound of the bucket holding the quantile, `q` going from 0.0 to 1.0. The
+    /// overflow bucket reports the slowest latency.
+    pub fn quantile(&self, q: f64) -> Duration {
+        let rank = (q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64;
+        let mut seen = 0;
+
+        for (i, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank.max(1) {
+                return match BUCKETS_MS.get(i) {
+                    Some(bound) => Duration::from_millis(*bound).min(self.max),
+                    _ => self.max,
+                };
+            }
+        }
+
+        self.max
+    }
+}
+
+/// Why the network manager did not send a request to the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockReason {
+    /// A service worker script.
+    ServiceWorker,
+    /// A request to a third party blocked by the policy.
+    ThirdParty,
+    /// A request blocked by the request filter.
+    Filter,
+    /// A document redirect not followed by the redirect policy.
+    Redirect,
+    /// A resource skipped like the visuals, stylesheets, scripts or trackers, fulfilled empty.
+    Resource,
+    /// A request matching the ad filters.
+    Ad,
+    /// A response over the size cap of its resource type.
+    Oversized,
+}
+
+impl BlockReason {
+    /// Every reason in the order of the counters.
+    pub const ALL: [BlockReason; 7] = [
+        BlockReason::ServiceWorker,
+        BlockReason::ThirdParty,
+        BlockReason::Filter,
+        BlockReason::Redirect,
+        BlockReason::Resource,
+        BlockReason::Ad,
+        BlockReason::Oversized,
+    ];
+
+    /// The name of the reason like `third_party`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlockReason::ServiceWorker => "service_worker",
+            BlockReason::ThirdParty => "third_party",
+            BlockReason::Filter => "filter",
+            BlockReason::Redirect => "redirect",
+            BlockReason::Resource => "resource",
+            BlockReason::Ad => "ad",
+            BlockReason::Oversized => "oversized",
+        }
+    }
+}
+
+/// The requests blocked by reason and the pages open in the browsers.
+#[derive(Debug, Default)]
+pub struct NetworkMetrics {
+    /// The requests blocked for each reason of [`BlockReason::ALL`].
+    blocked: [AtomicU64; BlockReason::ALL.len()],
+    /// The page targets open.
+    open_pages: AtomicI64,
+}
+
+impl NetworkMetrics {
+    /// Count a request blocked for the reason.
+    pub fn record_blocked(&self, reason: BlockReason) {
+        self.blocked[reason as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The requests blocked for the reason.
+    pub fn blocked(&self, reason: BlockReason) -> u64 {
+        self.blocked[reason as usize].load(Ordering::Relaxed)
+    }
+
+    /// The requests blocked for every reason.
+    pub fn blocked_requests(&self) -> Vec<(BlockReason, u64)> {
+        BlockReason::ALL
+            .iter()
+            .map(|reason| (*reason, self.blocked(*reason)))
+            .collect()
+    }
+
+    /// Count a page target opened.
+    pub fn page_opened(&self) {
+        self.open_pages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a page target closed.
+    pub fn page_closed(&self) {
+        self.open_pages.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// The page targets open in the browsers.
+    pub fn open_pages(&self) -> u64 {
+        self.open_pages.load(Ordering::Relaxed).max(0) as u64
+    }
+
+    /// Forget the blocked requests, the open pages are kept.
+    pub fn reset(&self) {
+        for blocked in &self.blocked {
+            blocked.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_latency_histograms() {
+        let metrics = CommandMetrics::default();
+
+        for millis in [3, 4, 40, 4000, 20000] {
+            metrics.record("Page.navigate", Duration::from_millis(millis));
+        }
+        metrics.record("Runtime.evaluate", Duration::from_millis(1));
+        metrics.record_timeout("Page.navigate");
+
+        let latencies = metrics.snapshot();
+        assert_eq!(latencies.len(), 2);
+
+        let navigate = &latencies[0];
+        assert_eq!(navigate.method, "Page.navigate");
+        assert_eq!(navigate.count, 5);
+        assert_eq!(navigate.timeouts, 1);
+        assert_eq!(navigate.max, Duration::from_secs(20));
+        assert_eq!(navigate.buckets[2], 2);
+        assert_eq!(navigate.buckets[BUCKETS_MS.len()], 1);
+        assert_eq!(navigate.quantile(0.5), Duration::from_millis(50));
+        assert_eq!(navigate.quantile(1.0), Duration::from_secs(20));
+        assert_eq!(navigate.mean(), Duration::from_micros(4_809_400));
+
+        metrics.reset();
+        assert!(metrics.snapshot().is_empty());
+    }
+
+    #[test]
+    fn counts_blocked_requests() {
+        let metrics = NetworkMetrics::default();
+
+        metrics.record_blocked(BlockReason::ThirdParty);
+        metrics.record_blocked(BlockReason::ThirdParty);
+        metrics.record_blocked(BlockReason::Ad);
+        metrics.page_opened();
+        metrics.page_opened();
+        metrics.page_closed();
+
+        assert_eq!(metrics.blocked(BlockReason::ThirdParty), 2);
+        assert_eq!(metrics.blocked(BlockReason::Filter), 0);
+        assert_eq!(metrics.blocked_requests()[5], (BlockReason::Ad, 1));
+        assert_eq!(metrics.open_pages(), 1);
+
+        metrics.reset();
+        assert_eq!(metrics.blocked(BlockReason::ThirdParty), 0);
+        assert_eq!(metrics.open_pages(), 1);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 
@@ -10,11 +11,11 @@ use rand::{thread_rng, Rng};
 
 use chromiumoxide_cdp::cdp::browser_protocol::dom::*;
 use chromiumoxide_cdp::cdp::browser_protocol::emulation::{
-    MediaFeature, SetEmulatedMediaParams, SetGeolocationOverrideParams, SetLocaleOverrideParams,
-    SetTimezoneOverrideParams,
+    MediaFeature, SetCpuThrottlingRateParams, SetEmulatedMediaParams, SetGeolocationOverrideParams,
+    SetLocaleOverrideParams, SetTimezoneOverrideParams, UserAgentBrandVersion, UserAgentMetadata,
 };
 use chromiumoxide_cdp::cdp::browser_protocol::network::{
-    Cookie, CookieParam, DeleteCookiesParams, GetCookiesParams, SetCookiesParams,
+    Cookie, CookieParam, DeleteCookiesParams, GetCookiesParams, ResourceType, SetCookiesParams,
     SetUserAgentOverrideParams,
 };
 use chromiumoxide_cdp::cdp::browser_protocol::page::*;
@@ -34,8 +35,11 @@ use crate::element::Element;
 use crate::error::{CdpError, Result};
 use crate::handler::commandfuture::CommandFuture;
 use crate::handler::domworld::DOMWorldKind;
+use crate::handler::fulfill_cache::FulfillCache;
 use crate::handler::httpfuture::HttpFuture;
+use crate::handler::network::{MockRouter, RedirectPolicy, RequestFilter, ThirdPartyPolicy};
 use crate::handler::target::{GetName, GetParent, GetUrl, TargetMessage};
+use crate::handler::waterfall::WaterfallEntry;
 use crate::handler::PageInner;
 use crate::js::{Evaluation, EvaluationResult};
 use crate::layout::Point;
@@ -94,7 +98,10 @@ static PLUGINS_SET: phf::Set<&'static str> = phf_set! {
 };
 
 pub const HIDE_CHROME: &str = "window.chrome={runtime:{}};['log','warn','error','info','debug','table'].forEach((method)=>{console[method]=()=>{};});";
-pub const HIDE_WEBGL: &str = "const getParameter=WebGLRenderingContext.getParameter;WebGLRenderingContext.prototype.getParameter=function(parameter){ if (parameter === 37445) { return 'Google Inc. (NVIDIA)';} if (parameter === 37446) { return 'ANGLE (NVIDIA, NVIDIA GeForce GTX 1050 Direct3D11 vs_5_0 ps_5_0, D3D11-27.21.14.5671)'; } return getParameter(parameter);};";
+pub const HIDE_WEBGL: &str = "[WebGLRenderingContext,WebGL2RenderingContext].forEach((ctx)=>{const getParameter=ctx.prototype.getParameter;ctx.prototype.getParameter=function(parameter){ if (parameter === 37445) { return 'Google Inc. (NVIDIA)';} if (parameter === 37446) { return 'ANGLE (NVIDIA, NVIDIA GeForce GTX 1050 Direct3D11 vs_5_0 ps_5_0, D3D11-27.21.14.5671)'; } return getParameter.call(this, parameter);};});";
+pub const HIDE_WEBGL_MAC: &str = "[WebGLRenderingContext,WebGL2RenderingContext].forEach((ctx)=>{const getParameter=ctx.prototype.getParameter;ctx.prototype.getParameter=function(parameter){ if (parameter === 37445) { return 'Google Inc. (Apple)';} if (parameter === 37446) { return 'ANGLE (Apple, ANGLE Metal Renderer: Apple M1, Unspecified Version)'; } return getParameter.call(this, parameter);};});";
+pub const HIDE_USER_AGENT_DATA: &str =
+    "Object.defineProperty(Navigator.prototype,'userAgentData',{get:()=>undefined});";
 pub const HIDE_PERMISSIONS: &str = "const originalQuery=window.navigator.permissions.query;window.navigator.permissions.__proto__.query=parameters=>{ return parameters.name === 'notifications' ? Promise.resolve({ state: Notification.permission }) : originalQuery(parameters);}";
 pub const HIDE_WEBDRIVER: &str =
     "Object.defineProperty(navigator,'webdriver',{get:()=>undefined});";
@@ -141,12 +148,187 @@ fn generate_hide_plugins() -> String {
     format!("{}{}", NAVIGATOR_SCRIPT, plugin_script)
 }
 
+/// Generate the languages script from an `Accept-Language` value ex: "en-US,en;q=0.9".
+fn generate_hide_languages(accept_language: &str) -> String {
+    let languages: Vec<String> = accept_language
+        .split(',')
+        .filter_map(|l| {
+            let l = l.split(';').next().unwrap_or_default().trim();
+            if l.is_empty() || l == "*" {
+                None
+            } else {
+                Some(format!("'{}'", l.replace('\'', "")))
+            }
+        })
+        .collect();
+
+    if languages.is_empty() {
+        Default::default()
+    } else {
+        format!(
+            "Object.defineProperty(Navigator.prototype,'languages',{{get:()=>[{}]}});Object.defineProperty(Navigator.prototype,'language',{{get:()=>{}}});",
+            languages.join(","),
+            languages[0]
+        )
+    }
+}
+
+/// The platform details of a user agent used for the client hints.
+struct AgentPlatform {
+    /// The `Sec-CH-UA-Platform` value.
+    platform: &'static str,
+    /// The `navigator.platform` value.
+    navigator_platform: &'static str,
+    /// The `Sec-CH-UA-Platform-Version` value.
+    platform_version: &'static str,
+    /// The `Sec-CH-UA-Arch` value.
+    architecture: &'static str,
+    /// The `Sec-CH-UA-Mobile` value.
+    mobile: bool,
+}
+
+/// Get the platform details of the user agent.
+fn agent_platform(ua: &str) -> AgentPlatform {
+    if ua.contains("Android") {
+        AgentPlatform {
+            platform: "Android",
+            navigator_platform: "Linux armv81",
+            platform_version: "10.0.0",
+            architecture: "",
+            mobile: true,
+        }
+    } else if ua.contains("iPhone") || ua.contains("iPad") {
+        AgentPlatform {
+            platform: "iOS",
+            navigator_platform: if ua.contains("iPad") {
+                "iPad"
+            } else {
+                "iPhone"
+            },
+            platform_version: "17.0.0",
+            architecture: "",
+            mobile: true,
+        }
+    } else if ua.contains("Windows") {
+        AgentPlatform {
+            platform: "Windows",
+            navigator_platform: "Win32",
+            platform_version: "10.0.0",
+            architecture: "x86",
+            mobile: false,
+        }
+    } else if ua.contains("Mac OS X") {
+        AgentPlatform {
+            platform: "macOS",
+            navigator_platform: "MacIntel",
+            platform_version: "10.15.7",
+            architecture: "arm",
+            mobile: false,
+        }
+    } else if ua.contains("CrOS") {
+        AgentPlatform {
+            platform: "Chrome OS",
+            navigator_platform: "Linux x86_64",
+            platform_version: "14541.0.0",
+            architecture: "x86",
+            mobile: false,
+        }
+    } else {
+        AgentPlatform {
+            platform: "Linux",
+            navigator_platform: "Linux x86_64",
+            platform_version: "6.5.0",
+            architecture: "x86",
+            mobile: false,
+        }
+    }
+}
+
+/// Get the chromium version of the user agent if the agent is a chromium based browser.
+fn chromium_version(ua: &str) -> Option<&str> {
+    if ua.contains("Firefox/") || ua.contains("FxiOS/") {
+        return None;
+    }
+    let (_, version) = ua.split_once("Chrome/")?;
+    let version = version.split_whitespace().next().unwrap_or_default();
+    if version.is_empty() {
+        None
+    } else {
+        Some(version)
+    }
+}
+
+/// Build the client hints metadata for the user agent so `navigator.userAgentData` and the `Sec-CH-UA` headers match. Returns `None` for agents that are not chromium based.
+pub fn user_agent_metadata(ua: &str) -> Option<UserAgentMetadata> {
+    let full_version = chromium_version(ua)?;
+    let major = full_version.split('.').next().unwrap_or(full_version);
+    let platform = agent_platform(ua);
+    let brand = if ua.contains("Edg/") {
+        "Microsoft Edge"
+    } else {
+        "Google Chrome"
+    };
+
+    Some(UserAgentMetadata {
+        brands: Some(vec![
+            UserAgentBrandVersion::new("Not?A_Brand", "99"),
+            UserAgentBrandVersion::new("Chromium", major),
+            UserAgentBrandVersion::new(brand, major),
+        ]),
+        full_version_list: Some(vec![
+            UserAgentBrandVersion::new("Not?A_Brand", "99.0.0.0"),
+            UserAgentBrandVersion::new("Chromium", full_version),
+            UserAgentBrandVersion::new(brand, full_version),
+        ]),
+        platform: platform.platform.into(),
+        platform_version: platform.platform_version.into(),
+        architecture: platform.architecture.into(),
+        model: Default::default(),
+        mobile: platform.mobile,
+        bitness: Some(if platform.mobile { "" } else { "64" }.into()),
+        wow64: Some(false),
+    })
+}
+
+/// Build the user agent override with the platform and client hints matching the agent.
+pub fn stealth_user_agent_params(
+    ua: &str,
+    accept_language: Option<&str>,
+) -> SetUserAgentOverrideParams {
+    SetUserAgentOverrideParams {
+        user_agent: ua.into(),
+        accept_language: accept_language.map(|l| l.into()),
+        platform: Some(agent_platform(ua).navigator_platform.into()),
+        user_agent_metadata: user_agent_metadata(ua),
+    }
+}
+
 /// Generate the initial stealth script to send in one command.
 fn build_stealth_script() -> String {
     let plugins = generate_hide_plugins();
     format!("{HIDE_CHROME}{HIDE_WEBGL}{HIDE_PERMISSIONS}{HIDE_WEBDRIVER}{plugins}")
 }
 
+/// Generate the stealth script matching the user agent and languages to send in one command.
+fn build_stealth_script_with_agent(ua: &str, accept_language: Option<&str>) -> String {
+    let plugins = generate_hide_plugins();
+    let webgl = if ua.contains("Mac OS X") {
+        HIDE_WEBGL_MAC
+    } else {
+        HIDE_WEBGL
+    };
+    let user_agent_data = if chromium_version(ua).is_some() {
+        ""
+    } else {
+        HIDE_USER_AGENT_DATA
+    };
+    let languages = match accept_language {
+        Some(accept_language) => generate_hide_languages(accept_language),
+        _ => Default::default(),
+    };
+    format!("{HIDE_CHROME}{webgl}{HIDE_PERMISSIONS}{HIDE_WEBDRIVER}{user_agent_data}{languages}{plugins}")
+}
+
 impl Page {
     /// Removes the `navigator.webdriver` property
     /// changes permissions, pluggins rendering contexts and the `window.chrome`
@@ -186,6 +368,33 @@ impl Page {
         Ok(())
     }
 
+    /// Changes your user_agent with a custom agent along with the matching client hints and platform,
+    /// spoofs the `navigator.languages` to match the `Accept-Language`, removes the `navigator.webdriver` property
+    /// changes permissions, pluggins and WebGL vendor for the agent platform and the `window.chrome`
+    /// property to make it harder to detect the scraper as a bot. Optionally add the dialog polyfill to prevent blocking the page.
+    pub async fn enable_stealth_mode_with_agent_and_languages(
+        &self,
+        ua: &str,
+        accept_language: Option<&str>,
+        dismiss_dialogs: bool,
+    ) -> Result<()> {
+        let script = build_stealth_script_with_agent(ua, accept_language);
+        let _ = tokio::join!(
+            self.execute(AddScriptToEvaluateOnNewDocumentParams {
+                source: if dismiss_dialogs {
+                    format!("{script}{DISABLE_DIALOGS}")
+                } else {
+                    script
+                },
+                world_name: None,
+                include_command_line_api: None,
+                run_immediately: None,
+            }),
+            self.set_user_agent_with_headers(stealth_user_agent_params(ua, accept_language))
+        );
+        Ok(())
+    }
+
     /// Changes your user_agent with a custom agent, removes the `navigator.webdriver` property
     /// changes permissions, pluggins rendering contexts and the `window.chrome`
     /// property to make it harder to detect the scraper as a bot. Also add dialog polyfill to prevent blocking the page.
@@ -437,6 +646,112 @@ impl Page {
         Ok(())
     }
 
+    /// Block the third party requests of the policy.
+    pub async fn set_third_party_policy(&self, policy: Option<ThirdPartyPolicy>) -> Result<()> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::ThirdPartyPolicy(policy))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Remove the `Set-Cookie` headers of the responses of the page so the page can not store cookies from the network.
+    pub async fn set_block_response_cookies(&self, block_response_cookies: bool) -> Result<()> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::BlockResponseCookies(block_response_cookies))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Answer the requests of the page matching the routes with canned responses.
+    pub async fn set_mock_router(&self, router: Option<MockRouter>) -> Result<()> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::MockRouter(router))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Answer the repeated script, stylesheet, font and image requests of the page with the responses of the cache
+    /// shared by the pages of a crawl instead of the network.
+    pub async fn set_fulfill_cache(&self, cache: Option<FulfillCache>) -> Result<()> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::FulfillCache(cache))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Set the bytes allowed for the response bodies of the page by resource type. The oversized responses are
+    /// aborted instead of stalling the page until the request timeout.
+    pub async fn set_max_response_bytes(
+        &self,
+        max_response_bytes: HashMap<ResourceType, u64>,
+    ) -> Result<()> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::MaxResponseBytes(max_response_bytes))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Fail the requests of the page the filter returns `true` for.
+    pub async fn set_request_filter(&self, filter: Option<RequestFilter>) -> Result<()> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::RequestFilter(filter))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Set the redirects followed by the document requests of the page, every redirect is followed without a policy.
+    /// Blocked redirects fail the navigation.
+    pub async fn set_redirect_policy(&self, policy: Option<RedirectPolicy>) -> Result<()> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::RedirectPolicy(policy))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns the third party requests blocked by the policy of the page
+    pub async fn blocked_third_party_requests(&self) -> Result<Vec<String>> {
+        let (tx, rx) = oneshot_channel();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::BlockedThirdParty(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
+    /// Returns the timing waterfall of the requests of the page with their dns, connect, tls, time to first byte and
+    /// download phases. Use [`crate::handler::waterfall::slowest`] to find the slow subresources.
+    pub async fn request_waterfall(&self) -> Result<Vec<WaterfallEntry>> {
+        let (tx, rx) = oneshot_channel();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::Waterfall(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
     /// Returns the current url of the page
     pub async fn url(&self) -> Result<Option<String>> {
         let (tx, rx) = oneshot_channel();
@@ -504,6 +819,22 @@ impl Page {
         Ok(self)
     }
 
+    /// Allows overriding user agent with the given string and keeps the extra headers of the page using the same agent.
+    pub async fn set_user_agent_with_headers(
+        &self,
+        params: impl Into<SetUserAgentOverrideParams>,
+    ) -> Result<&Self> {
+        let params = params.into();
+        let user_agent = params.user_agent.clone();
+        self.execute(params).await?;
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::UserAgent(user_agent))
+            .await?;
+        Ok(self)
+    }
+
     /// Returns the user agent of the browser
     pub async fn user_agent(&self) -> Result<String> {
         Ok(self.inner.version().await?.user_agent)
@@ -763,6 +1094,18 @@ impl Page {
         Ok(self)
     }
 
+    /// Slows down the CPU of the page by the rate, 1 is no throttle and 2 is a 2x slowdown.
+    pub async fn emulate_cpu_throttling(&self, rate: f64) -> Result<&Self> {
+        self.execute(SetCpuThrottlingRateParams::new(rate)).await?;
+        Ok(self)
+    }
+
+    /// Enables or disables the bypass of the Content Security Policy of the page.
+    pub async fn set_bypass_csp(&self, enabled: bool) -> Result<&Self> {
+        self.execute(SetBypassCspParams::new(enabled)).await?;
+        Ok(self)
+    }
+
     /// Reloads given page
     ///
     /// To reload ignoring cache run:
@@ -1232,6 +1575,17 @@ impl Page {
         Ok(self.execute(script.into()).await?.result.identifier)
     }
 
+    /// Hide the ad placeholders of the documents of the page with the css of the cosmetic filters for the hostname of
+    /// the url, so the screenshots and the text of the page are free of empty ad slots. Resolves to `None` when no
+    /// filter applies to the url.
+    #[cfg(feature = "adblock")]
+    pub async fn enable_cosmetic_filtering(&self, url: &str) -> Result<Option<ScriptIdentifier>> {
+        match crate::handler::network::cosmetic_filter_script(url) {
+            Some(script) => Ok(Some(self.evaluate_on_new_document(script).await?)),
+            _ => Ok(None),
+        }
+    }
+
     /// Set the content of the frame.
     ///
     /// # Example
@@ -1460,3 +1814,32 @@ impl From<MediaTypeParams> for String {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_agent_metadata_matches_agent() {
+        let metadata = user_agent_metadata(DEFAULT_AGENT).unwrap();
+        assert_eq!(metadata.platform, "macOS");
+        assert!(!metadata.mobile);
+        assert!(metadata
+            .brands
+            .unwrap_or_default()
+            .iter()
+            .any(|b| b.brand == "Google Chrome" && b.version == "130"));
+
+        let firefox = "Mozilla/5.0 (X11; Linux x86_64; rv:131.0) Gecko/20100101 Firefox/131.0";
+        assert!(user_agent_metadata(firefox).is_none());
+    }
+
+    #[test]
+    fn hide_languages_from_accept_language() {
+        assert_eq!(
+            generate_hide_languages("en-US,en;q=0.9"),
+            "Object.defineProperty(Navigator.prototype,'languages',{get:()=>['en-US','en']});Object.defineProperty(Navigator.prototype,'language',{get:()=>'en-US'});"
+        );
+        assert!(generate_hide_languages("*").is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+//! Tracking and cleanup of the chromium processes spawned by [`crate::Browser::launch`].
+//!
+//! Every launched browser gets its own temporary profile directory named
+//! `chromiumoxide-runner-{owner pid}-{n}` and is registered here until it is dropped. This lets
+//! a signal handler tear down every live browser on `SIGTERM`, and lets a later run find
+//! browsers left behind by a crashed process: their profile names point at an owner pid that
+//! no longer exists.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Prefix of the temporary profile directories created for launched browsers.
+pub const PROFILE_PREFIX: &str = "chromiumoxide-runner-";
+
+lazy_static::lazy_static! {
+    /// Live browsers spawned by this process: pid to temporary profile directory.
+    static ref LIVE_BROWSERS: Mutex<HashMap<u32, Option<PathBuf>>> = Mutex::new(HashMap::new());
+    /// The last shutdown signal received by the signal handler.
+    static ref SHUTDOWN_SIGNAL: tokio::sync::watch::Sender<Option<i32>> =
+        tokio::sync::watch::channel(None).0;
+}
+
+/// Counter for unique profile directory names within this process.
+static PROFILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A fresh temporary profile directory path for a new browser launch.
+pub fn temp_profile_dir() -> PathBuf {
+    temp_profile_dir_in(&std::env::temp_dir())
+}
+
+/// A fresh temporary profile directory path for a new browser launch inside `root`.
+pub fn temp_profile_dir_in(root: &Path) -> PathBuf {
+    let n = PROFILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    root.join(format!("{PROFILE_PREFIX}{}-{n}", std::process::id()))
+}
+
+/// The owner pid encoded in a profile directory name created by [`temp_profile_dir`].
+pub fn profile_owner_pid(path: &Path) -> Option<u32> {
+    let name = path.file_name()?.to_str()?;
+    let rest = name.strip_prefix(PROFILE_PREFIX)?;
+    let (pid, n) = rest.split_once('-')?;
+    n.parse::<usize>().ok()?;
+    pid.parse().ok()
+}
+
+/// Track a launched browser until [`unregister`] is called.
+pub(crate) fn register(pid: u32, profile: Option<PathBuf>) {
+    if let Ok(mut live) = LIVE_BROWSERS.lock() {
+        live.insert(pid, profile);
+    }
+}
+
+/// Stop tracking a browser.
+pub(crate) fn unregister(pid: u32) {
+    if let Ok(mut live) = LIVE_BROWSERS.lock() {
+        live.remove(&pid);
+    }
+}
+
+/// Kill the process group of a browser. The browser is spawned as a group leader so this also
+/// takes down its renderer, gpu and zygote helpers.
+#[cfg(unix)]
+pub fn kill_process_group(pid: u32) {
+    if pid == 0 {
+        return;
+    }
+    // SAFETY: killpg has no memory safety requirements; a stale pid only yields ESRCH.
+    unsafe {
+        libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+    }
+}
+
+/// Kill the process group of a browser. Process groups are not used on this platform.
+#[cfg(not(unix))]
+pub fn kill_process_group(_pid: u32) {}
+
+/// Remove a temporary profile directory, ignoring errors.
+pub(crate) fn remove_profile(profile: &Path) {
+    let _ = std::fs::remove_dir_all(profile);
+}
+
+/// Kill every live browser spawned by this process and remove their temporary profiles.
+/// Returns the number of browsers killed.
+pub fn kill_all_browsers() -> usize {
+    let live = match LIVE_BROWSERS.lock() {
+        Ok(mut live) => std::mem::take(&mut *live),
+        Err(_) => return 0,
+    };
+
+    for (pid, profile) in live.iter() {
+        kill_process_group(*pid);
+        if let Some(profile) = profile {
+            remove_profile(profile);
+        }
+    }
+
+    live.len()
+}
+
+/// Kill every live browser and publish the signal to the [`shutdown_signal`] watchers.
+fn on_shutdown_signal(signal: i32) -> usize {
+    let killed = kill_all_browsers();
+    SHUTDOWN_SIGNAL.send_replace(Some(signal));
+    killed
+}
+
+/// Spawn a task that kills every live browser each time the process receives `SIGTERM` or
+/// `SIGINT`. The process is not exited: the handler replaces the default action of the signals,
+/// so the caller watches [`shutdown_signal`] to stop its work and exit. Must be called within a
+/// tokio runtime.
+pub fn spawn_signal_handler() {
+    tokio::spawn(async {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            if let (Ok(mut term), Ok(mut int)) = (
+                signal(SignalKind::terminate()),
+                signal(SignalKind::interrupt()),
+            ) {
+                loop {
+                    let signal = tokio::select! {
+                        _ = term.recv() => libc::SIGTERM,
+                        _ = int.recv() => libc::SIGINT,
+                    };
+                    let killed = on_shutdown_signal(signal);
+                    tracing::info!("Received shutdown signal {signal}, killed {killed} browser(s)");
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        while tokio::signal::ctrl_c().await.is_ok() {
+            let killed = on_shutdown_signal(2);
+            tracing::info!("Received shutdown signal, killed {killed} browser(s)");
+        }
+    });
+}
+
+/// Watch the shutdown signal received by the handler of [`spawn_signal_handler`]: `None` until
+/// `SIGTERM` or `SIGINT` is received, then the number of the last signal.
+pub fn shutdown_signal() -> tokio::sync::watch::Receiver<Option<i32>> {
+    SHUTDOWN_SIGNAL.subscribe()
+}
+
+/// Whether the handler of [`spawn_signal_handler`] received a shutdown signal.
+pub fn shutdown_requested() -> bool {
+    SHUTDOWN_SIGNAL.borrow().is_some()
+}
+
+/// Whether a process with the pid is currently running.
+#[cfg(target_os = "linux")]
+fn pid_alive(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// Scan for chromium processes launched by a previous run of this library whose owning process
+/// is gone, returning their pids. When `kill` is set the orphans are killed and their profiles and
+/// the stale temporary profile directories are removed.
+///
+/// Detection reads `/proc` and is only available on Linux; other platforms return no orphans.
+#[cfg(target_os = "linux")]
+pub fn reap_orphaned_browsers(kill: bool) -> Vec<u32> {
+    let own_pid = std::process::id();
+    let mut orphans = Vec::new();
+    // profiles may live outside the temp dir when a profile root is configured.
+    let mut profiles = Vec::new();
+
+    if let Ok(entries) = std::fs::read_dir("/proc") {
+        for entry in entries.flatten() {
+            let pid = match entry
+                .file_name()
+                .to_str()
+                .and_then(|s| s.parse::<u32>().ok())
+            {
+                Some(pid) => pid,
+                None => continue,
+            };
+            let cmdline = match std::fs::read(entry.path().join("cmdline")) {
+                Ok(cmdline) => cmdline,
+                Err(_) => continue,
+            };
+            let profile = cmdline
+                .split(|b| *b == 0)
+                .filter_map(|arg| std::str::from_utf8(arg).ok())
+                .filter_map(|arg| arg.strip_prefix("--user-data-dir="))
+                .map(PathBuf::from)
+                .find(|dir| profile_owner_pid(dir).is_some());
+
+            if let Some(profile) = profile {
+                if profile_owner_pid(&profile)
+                    .is_some_and(|owner| owner != own_pid && !pid_alive(owner))
+                {
+                    orphans.push(pid);
+                    profiles.push(profile);
+                }
+            }
+        }
+    }
+
+    if kill {
+        for pid in orphans.iter() {
+            // SAFETY: kill has no memory safety requirements; a stale pid only yields ESRCH.
+            unsafe {
+                libc::kill(*pid as libc::pid_t, libc::SIGKILL);
+            }
+        }
+        for profile in profiles.iter() {
+            remove_profile(profile);
+        }
+        if let Ok(entries) = std::fs::read_dir(std::env::temp_dir()) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if let Some(owner) = profile_owner_pid(&path) {
+                    if owner != own_pid && !pid_alive(owner) {
+                        remove_profile(&path);
+                    }
+                }
+            }
+        }
+    }
+
+    orphans
+}
+
+/// Scan for orphaned chromium processes. Detection is only available on Linux.
+#[cfg(not(target_os = "linux"))]
+pub fn reap_orphaned_browsers(_kill: bool) -> Vec<u32> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_owner_pid_roundtrip() {
+        let dir = temp_profile_dir();
+        assert_eq!(profile_owner_pid(&dir), Some(std::process::id()));
+        assert_ne!(temp_profile_dir(), dir);
+
+        let dir = temp_profile_dir_in(Path::new("/var/cache/crawler"));
+        assert!(dir.starts_with("/var/cache/crawler"));
+        assert_eq!(profile_owner_pid(&dir), Some(std::process::id()));
+
+        assert_eq!(
+            profile_owner_pid(Path::new("/tmp/chromiumoxide-runner")),
+            None
+        );
+        assert_eq!(
+            profile_owner_pid(Path::new("/tmp/chromiumoxide-runner-x-1")),
+            None
+        );
+        assert_eq!(profile_owner_pid(Path::new("/tmp/other-12-1")), None);
+    }
+
+    #[test]
+    fn kill_all_browsers_removes_profiles() {
+        let dir = temp_profile_dir();
+        std::fs::create_dir_all(&dir).unwrap();
+        // a pid that can not belong to a running process
+        register(u32::MAX / 2, Some(dir.clone()));
+
+        assert!(kill_all_browsers() >= 1);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn shutdown_signal_is_published() {
+        let signal = shutdown_signal();
+        assert!(!shutdown_requested());
+
+        on_shutdown_signal(15);
+
+        assert!(shutdown_requested());
+        assert_eq!(*signal.borrow(), Some(15));
+    }
+}
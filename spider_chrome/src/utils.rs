@@ -43,6 +43,32 @@ pub(crate) async fn canonicalize_except_snap(path: PathBuf) -> std::io::Result<P
     })
 }
 
+/// Match the text with a pattern where `*` matches any characters.
+pub(crate) fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+
+    let mut rest = match text.strip_prefix(first) {
+        Some(rest) => rest,
+        _ => return false,
+    };
+
+    let parts: Vec<&str> = parts.collect();
+
+    match parts.split_last() {
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(i) => rest = &rest[i + part.len()..],
+                    _ => return false,
+                }
+            }
+            rest.ends_with(last)
+        }
+        _ => rest.is_empty(),
+    }
+}
+
 pub(crate) mod base64 {
     use base64::engine::general_purpose::STANDARD;
     use base64::{DecodeError, Engine};
@@ -8,6 +8,7 @@ use crate::spider::tokio::io::AsyncWriteExt;
 use clap::Parser;
 use options::{Cli, Commands};
 use serde_json::json;
+use spider::features::assertions::{CrawlAssertions, CrawlStats};
 use spider::features::chrome_common::RequestInterceptConfiguration;
 use spider::hashbrown::HashMap;
 use spider::page::get_page_selectors;
@@ -76,6 +77,19 @@ async fn main() {
         }
         _ => (),
     }
+    match cli.resolve {
+        Some(resolve) => {
+            let mut overrides = Vec::with_capacity(resolve.len());
+            for entry in resolve {
+                match entry.parse() {
+                    Ok(resolve_override) => overrides.push(resolve_override),
+                    Err(e) => log("resolve: ", e),
+                }
+            }
+            website.with_resolve(Some(overrides));
+        }
+        _ => (),
+    }
     match cli.depth {
         Some(depth) => {
             website.with_depth(depth);
@@ -234,6 +248,70 @@ async fn main() {
                         }
                     }
                 }
+                Some(Commands::CHECK {
+                    min_pages,
+                    max_pages,
+                    max_error_rate,
+                    max_errors,
+                    baseline,
+                    update_baseline,
+                }) => {
+                    let assertions = CrawlAssertions {
+                        min_pages,
+                        max_pages,
+                        max_error_rate,
+                        max_errors,
+                        baseline: baseline.map(PathBuf::from),
+                    };
+
+                    tokio::spawn(async move {
+                        website.crawl().await;
+                    });
+
+                    let mut stats = CrawlStats::default();
+
+                    while let Ok(res) = rx2.recv().await {
+                        stats.record(&res);
+                    }
+
+                    match assertions.check(&stats) {
+                        Ok(report) => {
+                            let report_json = json!({
+                                "passed": report.passed(),
+                                "pages": report.pages,
+                                "errors": report.errors,
+                                "error_rate": report.error_rate,
+                                "failures": report.failures.iter().map(|failure| json!({
+                                    "assertion": failure.assertion,
+                                    "expected": failure.expected,
+                                    "actual": failure.actual,
+                                    "urls": failure.urls,
+                                })).collect::<serde_json::Value>(),
+                            });
+
+                            match serde_json::to_string_pretty(&report_json) {
+                                Ok(j) => println!("{j}"),
+                                Err(e) => eprintln!("{:?}", e)
+                            }
+
+                            if update_baseline {
+                                if let Some(path) = &assertions.baseline {
+                                    if let Err(e) = stats.save_baseline(path) {
+                                        eprintln!("{:?}", e)
+                                    }
+                                }
+                            }
+
+                            if !report.passed() {
+                                std::process::exit(1);
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("{:?}", e);
+                            std::process::exit(2);
+                        }
+                    }
+                }
                 None => ()
             }
         }
@@ -50,4 +50,7 @@ pub struct Cli {
     /// Dangerously accept invalid certficates
     #[clap(long)]
     pub accept_invalid_certs: bool,
+    /// Connect to an address instead of resolving the host with DNS ex: "example.com:443:127.0.0.1". Can be set multiple times.
+    #[clap(long)]
+    pub resolve: Option<Vec<String>>,
 }
@@ -26,4 +26,25 @@ pub enum Commands {
         #[clap(short, long)]
         target_destination: Option<String>,
     },
+    /// Crawl the website and check invariants of the crawl, printing a json report and exiting with an error code when one fails.
+    CHECK {
+        /// The minimum pages crawled.
+        #[clap(long)]
+        min_pages: Option<usize>,
+        /// The maximum pages crawled.
+        #[clap(long)]
+        max_pages: Option<usize>,
+        /// The maximum share of pages failing between 0.0 and 1.0 ex: 0.01 for 1%.
+        #[clap(long)]
+        max_error_rate: Option<f64>,
+        /// The maximum pages failing.
+        #[clap(long)]
+        max_errors: Option<usize>,
+        /// The baseline file of the broken links allowed. Any other broken link fails the check.
+        #[clap(long)]
+        baseline: Option<String>,
+        /// Write the broken links of the crawl to the baseline file after the check.
+        #[clap(long)]
+        update_baseline: bool,
+    },
 }